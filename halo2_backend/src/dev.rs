@@ -0,0 +1,310 @@
+//! Developer tools for inspecting proofs.
+
+use std::fmt;
+use std::marker::PhantomData;
+use std::ops::Range;
+
+use halo2_middleware::ff::PrimeField;
+
+use crate::arithmetic::CurveAffine;
+use crate::plonk::VerifyingKey;
+use crate::poly::commitment::{CommitmentScheme, Verifier};
+
+/// A labeled range of bytes of a proof.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProofSection {
+    /// The name of the section.
+    pub label: String,
+    /// The bytes of the proof in the section.
+    pub range: Range<usize>,
+    /// The subsections, which cover `range` in order, or nothing if this is a leaf.
+    pub children: Vec<ProofSection>,
+}
+
+impl ProofSection {
+    /// Returns the number of bytes in the section.
+    pub fn len(&self) -> usize {
+        self.range.len()
+    }
+
+    /// Returns `true` if the section has no bytes.
+    pub fn is_empty(&self) -> bool {
+        self.range.is_empty()
+    }
+}
+
+/// The layout of a proof, as returned by [`proof_structure`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProofStructure {
+    /// The top-level sections, which cover the whole proof in order.
+    pub sections: Vec<ProofSection>,
+    /// The length of the proof.
+    pub len: usize,
+    /// Why the proof could not be parsed, if it couldn't. The bytes from the point where
+    /// parsing stopped are in a final "unparsed" section.
+    pub error: Option<String>,
+}
+
+impl fmt::Display for ProofStructure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn write_section(
+            f: &mut fmt::Formatter<'_>,
+            section: &ProofSection,
+            depth: usize,
+            total: usize,
+        ) -> fmt::Result {
+            writeln!(
+                f,
+                "{:<40} {:>8} {:>8} {:>7.2}",
+                format!("{:indent$}{}", "", section.label, indent = 2 * depth),
+                section.range.start,
+                section.len(),
+                100.0 * section.len() as f64 / total.max(1) as f64,
+            )?;
+            for child in &section.children {
+                write_section(f, child, depth + 1, total)?;
+            }
+            Ok(())
+        }
+
+        writeln!(
+            f,
+            "{:<40} {:>8} {:>8} {:>7}",
+            "section", "offset", "bytes", "%"
+        )?;
+        for section in &self.sections {
+            write_section(f, section, 0, self.len)?;
+        }
+        writeln!(f, "{:<40} {:>8} {:>8}", "total", "", self.len)?;
+        if let Some(error) = &self.error {
+            writeln!(f, "parsing stopped: {error}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns where the bytes of `proof` go, for a proof of a single circuit created with
+/// the verifying key `vk` and the multi-open verifier `V`.
+///
+/// The proof is parsed in the same order as [`verify_proof`](crate::plonk::verifier::verify_proof),
+/// and every point and scalar is checked to be a valid encoding, but none of the checks of
+/// the verifier are done. The multi-open argument, whose layout depends on `V`, is left
+/// as a single section. If the proof can't be parsed, for example because it is
+/// truncated, [`ProofStructure::error`] tells where and why.
+pub fn proof_structure<'params, Scheme: CommitmentScheme, V: Verifier<'params, Scheme>>(
+    vk: &VerifyingKey<Scheme::Curve>,
+    proof: &[u8],
+) -> ProofStructure {
+    let mut parser = Parser::<Scheme::Curve>::new(proof);
+    let error = parse(&mut parser, vk, V::QUERY_INSTANCE || vk.commit_instances()).err();
+    if error.is_some() {
+        parser.rest("unparsed");
+    }
+
+    ProofStructure {
+        sections: parser.sections.pop().unwrap(),
+        len: proof.len(),
+        error,
+    }
+}
+
+/// Returns the advice commitments of a proof of `num_circuits` circuits created with the
+/// verifying key `vk`, indexed by circuit and by advice column.
+///
+/// Only the advice commitments near the start of the proof are read, and none of the checks
+/// of the verifier are done. This allows to compare the commitments of different proofs,
+/// for example to check that they share the commitment of an unblinded advice column, see
+/// [`VerifyingKey::unblinded_advice_columns`].
+pub fn extract_advice_commitments<C: CurveAffine>(
+    vk: &VerifyingKey<C>,
+    proof: &[u8],
+    num_circuits: usize,
+) -> Result<Vec<Vec<C>>, String> {
+    let cs = vk.cs();
+    let mut commitments = vec![vec![C::default(); cs.num_advice_columns]; num_circuits];
+    // The commitments to the instance columns and their marker come first, if any.
+    let mut offset = if vk.commit_instances() {
+        (1 + num_circuits * cs.num_instance_columns) * C::Repr::default().as_ref().len()
+    } else {
+        0
+    };
+    // The commitments are ordered by phase, then by circuit, then by column.
+    for phase in cs.phases() {
+        for (circuit, commitments) in commitments.iter_mut().enumerate() {
+            for (column, column_phase) in cs.advice_column_phase.iter().enumerate() {
+                if *column_phase != phase {
+                    continue;
+                }
+                let mut repr = C::Repr::default();
+                let len = repr.as_ref().len();
+                let bytes = proof.get(offset..offset + len).ok_or_else(|| {
+                    format!(
+                        "the proof ends at byte {}, before the commitment of advice column \
+                         {column} of circuit {circuit}",
+                        proof.len()
+                    )
+                })?;
+                repr.as_mut().copy_from_slice(bytes);
+                commitments[column] = Option::from(C::from_bytes(&repr))
+                    .ok_or_else(|| format!("invalid point encoding at byte {offset}"))?;
+                offset += len;
+            }
+        }
+    }
+    Ok(commitments)
+}
+
+fn parse<C: CurveAffine>(
+    parser: &mut Parser<C>,
+    vk: &VerifyingKey<C>,
+    query_instance: bool,
+) -> Result<(), String> {
+    let cs = vk.cs();
+    let permutation_sets = cs.permutation_chunks().len();
+
+    if vk.commit_instances() {
+        parser.section("instance commitments", |parser| {
+            parser.points("mode marker", 1)?;
+            parser.points("columns", cs.num_instance_columns)
+        })?;
+    }
+    parser.section("advice commitments", |parser| {
+        for phase in cs.phases() {
+            let columns = cs
+                .advice_column_phase
+                .iter()
+                .filter(|column_phase| **column_phase == phase)
+                .count();
+            parser.points(&format!("phase {}", phase.0), columns)?;
+        }
+        Ok(())
+    })?;
+    parser.points("lookup permuted commitments", 2 * cs.lookups.len())?;
+    parser.points("permutation product commitments", permutation_sets)?;
+    parser.points("lookup product commitments", cs.lookups.len())?;
+    parser.points("shuffle product commitments", cs.shuffles.len())?;
+    parser.section("vanishing commitments", |parser| {
+        parser.points("random polynomial", 1)?;
+        parser.points("h(X) pieces", vk.get_domain().get_quotient_poly_degree())
+    })?;
+    parser.section("evaluations", |parser| {
+        if query_instance {
+            parser.scalars("instance", cs.instance_queries.len())?;
+        }
+        parser.scalars("advice", cs.advice_queries.len())?;
+        parser.scalars("fixed", cs.fixed_queries.len())?;
+        parser.scalars("vanishing random polynomial", 1)?;
+        parser.scalars("permutation sigmas", vk.permutation().commitments().len())?;
+        // Every set but the last one is also evaluated at the last row.
+        parser.scalars(
+            "permutation products",
+            (3 * permutation_sets).saturating_sub(1),
+        )?;
+        parser.scalars("lookups", 5 * cs.lookups.len())?;
+        parser.scalars("shuffles", 2 * cs.shuffles.len())
+    })?;
+    parser.rest("multiopen argument");
+    Ok(())
+}
+
+/// Splits a proof into sections, reading it like a transcript.
+struct Parser<'a, C: CurveAffine> {
+    proof: &'a [u8],
+    offset: usize,
+    /// The sections of each group being parsed, from the outermost.
+    sections: Vec<Vec<ProofSection>>,
+    _marker: PhantomData<C>,
+}
+
+impl<'a, C: CurveAffine> Parser<'a, C> {
+    fn new(proof: &'a [u8]) -> Self {
+        Parser {
+            proof,
+            offset: 0,
+            sections: vec![vec![]],
+            _marker: PhantomData,
+        }
+    }
+
+    /// Adds a section with the bytes read by `f`, if there are any, including when `f`
+    /// fails.
+    fn section(
+        &mut self,
+        label: &str,
+        f: impl FnOnce(&mut Self) -> Result<(), String>,
+    ) -> Result<(), String> {
+        let start = self.offset;
+        self.sections.push(vec![]);
+        let result = f(self);
+        let children = self.sections.pop().unwrap();
+        if self.offset > start {
+            self.sections.last_mut().unwrap().push(ProofSection {
+                label: label.to_string(),
+                range: start..self.offset,
+                children,
+            });
+        }
+        result
+    }
+
+    /// Adds a section with the rest of the proof.
+    fn rest(&mut self, label: &str) {
+        let end = self.proof.len();
+        self.section(label, |parser| {
+            parser.offset = end;
+            Ok(())
+        })
+        .unwrap()
+    }
+
+    /// Adds a section with `n` points.
+    fn points(&mut self, label: &str, n: usize) -> Result<(), String> {
+        self.section(label, |parser| {
+            for _ in 0..n {
+                let mut repr = C::Repr::default();
+                parser.read(repr.as_mut())?;
+                if Option::<C>::from(C::from_bytes(&repr)).is_none() {
+                    return Err(format!("invalid point encoding at byte {}", parser.offset));
+                }
+                parser.offset += repr.as_ref().len();
+            }
+            Ok(())
+        })
+        .map_err(|e| format!("{label}: {e}"))
+    }
+
+    /// Adds a section with `n` scalars.
+    fn scalars(&mut self, label: &str, n: usize) -> Result<(), String> {
+        self.section(label, |parser| {
+            for _ in 0..n {
+                let mut repr = <C::Scalar as PrimeField>::Repr::default();
+                parser.read(repr.as_mut())?;
+                if Option::<C::Scalar>::from(C::Scalar::from_repr(repr)).is_none() {
+                    return Err(format!(
+                        "invalid field element encoding at byte {}",
+                        parser.offset
+                    ));
+                }
+                parser.offset += repr.as_ref().len();
+            }
+            Ok(())
+        })
+        .map_err(|e| format!("{label}: {e}"))
+    }
+
+    /// Copies the bytes at the current offset into `buf`, without consuming them.
+    fn read(&self, buf: &mut [u8]) -> Result<(), String> {
+        let bytes = self
+            .proof
+            .get(self.offset..self.offset + buf.len())
+            .ok_or_else(|| {
+                format!(
+                    "the proof ends at byte {}, before the end of the value at byte {}",
+                    self.proof.len(),
+                    self.offset
+                )
+            })?;
+        buf.copy_from_slice(bytes);
+        Ok(())
+    }
+}
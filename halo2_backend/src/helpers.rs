@@ -3,7 +3,7 @@ pub(crate) use halo2_common::helpers::{SerdeFormat, SerdePrimeField};
 use halo2_middleware::ff::PrimeField;
 use std::io;
 
-pub(crate) use halo2_common::helpers::{pack, unpack, CurveRead, SerdeCurveAffine};
+pub(crate) use halo2_common::helpers::{pack, split_slice, unpack, CurveRead, SerdeCurveAffine};
 
 /// Reads a vector of polynomials from buffer
 pub(crate) fn read_polynomial_vec<R: io::Read, F: SerdePrimeField, B>(
@@ -19,6 +19,20 @@ pub(crate) fn read_polynomial_vec<R: io::Read, F: SerdePrimeField, B>(
         .collect::<io::Result<Vec<_>>>()
 }
 
+/// Parses a vector of polynomials from the front of `bytes`, in the format of
+/// [`read_polynomial_vec`], and advances `bytes` past it.
+pub(crate) fn polynomial_vec_from_slice<F: SerdePrimeField, B>(
+    bytes: &mut &[u8],
+    format: SerdeFormat,
+) -> io::Result<Vec<Polynomial<F, B>>> {
+    let len = split_slice(bytes, 4)?;
+    let len = u32::from_be_bytes(len.try_into().expect("4 bytes were split"));
+
+    (0..len)
+        .map(|_| Polynomial::<F, B>::from_slice(bytes, format))
+        .collect::<io::Result<Vec<_>>>()
+}
+
 /// Writes a slice of polynomials to buffer
 pub(crate) fn write_polynomial_slice<W: io::Write, F: SerdePrimeField, B>(
     slice: &[Polynomial<F, B>],
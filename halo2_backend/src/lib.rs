@@ -1,3 +1,4 @@
+pub mod dev;
 mod helpers;
 pub mod plonk;
 pub mod poly;
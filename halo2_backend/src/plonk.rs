@@ -1,29 +1,41 @@
 use blake2b_simd::Params as Blake2bParams;
 use group::ff::{Field, FromUniformBytes, PrimeField};
+use group::prime::PrimeCurveAffine;
 
 use crate::helpers::{
-    self, polynomial_slice_byte_length, read_polynomial_vec, write_polynomial_slice,
-    SerdeCurveAffine, SerdePrimeField,
+    self, polynomial_slice_byte_length, polynomial_vec_from_slice, read_polynomial_vec,
+    write_polynomial_slice, SerdeCurveAffine, SerdePrimeField,
 };
 use crate::poly::{
     Coeff, EvaluationDomain, ExtendedLagrangeCoeff, LagrangeCoeff, PinnedEvaluationDomain,
     Polynomial,
 };
+pub use cache::ProverCache;
 use evaluation::Evaluator;
+pub use evaluation::{evaluate_constraints, ArgumentValues, LookupValues};
 use halo2_common::arithmetic::CurveAffine;
 use halo2_common::plonk::{Circuit, ConstraintSystem, PinnedConstraintSystem};
 use halo2_common::transcript::{EncodedChallenge, Transcript};
 use halo2_common::SerdeFormat;
+pub use keygen::CircuitOptions;
+pub use layout::{VkLayout, VK_LAYOUT_VERSION};
+pub use permutation::keygen::Assembly as PermutationAssembly;
 
 use std::io;
+use std::ops::Range;
 
 pub(crate) use halo2_common::plonk::Error;
 
+mod blinder;
+mod cache;
 mod evaluation;
 pub mod keygen;
+mod layout;
 mod lookup;
 mod permutation;
+pub mod proof;
 pub mod prover;
+pub mod self_describing;
 mod shuffle;
 mod vanishing;
 pub mod verifier;
@@ -44,8 +56,14 @@ pub struct VerifyingKey<C: CurveAffine> {
     // TODO: Use setter/getter https://github.com/privacy-scaling-explorations/halo2/issues/259
     /// Whether selector compression is turned on or not.
     pub compress_selectors: bool,
+    /// Whether the instance columns are committed to, see [`CircuitOptions`].
+    commit_instances: bool,
 }
 
+/// Bits of the flags byte of a serialized verifying key.
+const FLAG_COMPRESS_SELECTORS: u8 = 1;
+const FLAG_COMMIT_INSTANCES: u8 = 1 << 1;
+
 // Current version of the VK
 const VERSION: u8 = 0x03;
 
@@ -69,7 +87,7 @@ where
         assert!(*k <= C::Scalar::S);
         // k value fits in 1 byte
         writer.write_all(&[*k as u8])?;
-        writer.write_all(&[self.compress_selectors as u8])?;
+        writer.write_all(&[self.flags()])?;
         writer.write_all(&(self.fixed_commitments.len() as u32).to_le_bytes())?;
         for commitment in &self.fixed_commitments {
             commitment.write(writer, format)?;
@@ -126,15 +144,9 @@ where
                 ),
             ));
         }
-        let mut compress_selectors = [0u8; 1];
-        reader.read_exact(&mut compress_selectors)?;
-        if compress_selectors[0] != 0 && compress_selectors[0] != 1 {
-            return Err(io::Error::new(
-                io::ErrorKind::InvalidData,
-                "unexpected compress_selectors not boolean",
-            ));
-        }
-        let compress_selectors = compress_selectors[0] == 1;
+        let mut flags = [0u8; 1];
+        reader.read_exact(&mut flags)?;
+        let (compress_selectors, commit_instances) = Self::parse_flags(flags[0])?;
         let (domain, cs, _) = keygen::create_domain::<C, ConcreteCircuit>(
             k as u32,
             #[cfg(feature = "circuit-params")]
@@ -179,6 +191,7 @@ where
             cs,
             selectors,
             compress_selectors,
+            commit_instances,
         ))
     }
 
@@ -226,6 +239,7 @@ impl<C: CurveAffine> VerifyingKey<C> {
         cs: ConstraintSystem<C::Scalar>,
         selectors: Vec<Vec<bool>>,
         compress_selectors: bool,
+        commit_instances: bool,
     ) -> Self
     where
         C::ScalarExt: FromUniformBytes<64>,
@@ -243,22 +257,62 @@ impl<C: CurveAffine> VerifyingKey<C> {
             transcript_repr: C::Scalar::ZERO,
             selectors,
             compress_selectors,
+            commit_instances,
         };
+        vk.transcript_repr = vk.fingerprint(commit_instances);
 
+        vk
+    }
+
+    /// Computes the transcript representative of this `VerifyingKey`, as if it was
+    /// generated with the given `commit_instances` option.
+    pub(crate) fn fingerprint(&self, commit_instances: bool) -> C::Scalar
+    where
+        C::ScalarExt: FromUniformBytes<64>,
+    {
         let mut hasher = Blake2bParams::new()
             .hash_length(64)
             .personal(b"Halo2-Verify-Key")
             .to_state();
 
-        let s = format!("{:?}", vk.pinned());
+        let s = format!("{:?}", self.pinned());
 
         hasher.update(&(s.len() as u64).to_le_bytes());
         hasher.update(s.as_bytes());
+        // Only hashed when set, so that the fingerprints of keys which evaluate the
+        // instance columns are unchanged.
+        if commit_instances {
+            hasher.update(b"commit_instances");
+        }
 
         // Hash in final Blake2bState
-        vk.transcript_repr = C::Scalar::from_uniform_bytes(hasher.finalize().as_array());
+        C::Scalar::from_uniform_bytes(hasher.finalize().as_array())
+    }
 
-        vk
+    /// Returns the flags byte of the serialized key.
+    pub(crate) fn flags(&self) -> u8 {
+        let mut flags = 0;
+        if self.compress_selectors {
+            flags |= FLAG_COMPRESS_SELECTORS;
+        }
+        if self.commit_instances {
+            flags |= FLAG_COMMIT_INSTANCES;
+        }
+        flags
+    }
+
+    /// Parses the flags byte of a serialized key into `(compress_selectors, commit_instances)`.
+    pub(crate) fn parse_flags(flags: u8) -> io::Result<(bool, bool)> {
+        if flags & !(FLAG_COMPRESS_SELECTORS | FLAG_COMMIT_INSTANCES) != 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("unexpected verifying key flags: {flags:#04x}"),
+            ));
+        }
+        Ok((
+            flags & FLAG_COMPRESS_SELECTORS != 0,
+            flags & FLAG_COMMIT_INSTANCES != 0,
+        ))
     }
 
     /// Hashes a verification key into a transcript.
@@ -299,10 +353,44 @@ impl<C: CurveAffine> VerifyingKey<C> {
         &self.cs
     }
 
+    /// Returns the indices of the advice columns that are not blinded.
+    ///
+    /// The rows of these columns that are reserved for blinding factors are left at zero,
+    /// and their commitments use a zero blinding factor. The commitment of such a column
+    /// thus only depends on its values: proofs, even of different circuits, that assign
+    /// the same values to such a column have the same commitment to it, which can be
+    /// checked with [`extract_advice_commitments`](crate::dev::extract_advice_commitments)
+    /// to join the proofs. As a consequence, these commitments don't hide the values.
+    pub fn unblinded_advice_columns(&self) -> &[usize] {
+        &self.cs.unblinded_advice_columns
+    }
+
+    /// Returns `true` if the instance columns are committed to and opened in proofs for
+    /// this key, see [`CircuitOptions`].
+    pub fn commit_instances(&self) -> bool {
+        self.commit_instances
+    }
+
+    /// Returns the point written at the start of the proofs for a key which commits to the
+    /// instance columns, so that the verifier detects a proof created in the other mode.
+    ///
+    /// A proof created without committing to the instance columns starts with the
+    /// commitment to an advice column, which is the generator only with negligible
+    /// probability.
+    pub(crate) fn instance_mode_marker() -> C {
+        C::generator()
+    }
+
     /// Returns representative of this `VerifyingKey` in transcripts
     pub fn transcript_repr(&self) -> C::Scalar {
         self.transcript_repr
     }
+
+    /// Returns the hex encoding of [`VerifyingKey::transcript_repr`], which serves as
+    /// a fingerprint of this `VerifyingKey`.
+    pub fn transcript_repr_hex(&self) -> String {
+        proof::to_hex(self.transcript_repr.to_repr().as_ref())
+    }
 }
 
 /// Minimal representation of a verification key that can be used to identify
@@ -318,6 +406,45 @@ pub struct PinnedVerificationKey<'a, C: CurveAffine> {
     permutation: &'a permutation::VerifyingKey<C>,
 }
 
+/// An estimate of the memory that creating a proof takes, in bytes, returned by
+/// [`ProvingKey::estimated_prover_memory`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ProverMemoryEstimate {
+    /// The polynomials of the proving key.
+    pub proving_key: usize,
+    /// The polynomials of the witness of each circuit.
+    pub witness: usize,
+    /// The number of advice and instance columns of each circuit, which are evaluated over
+    /// the extended domain to compute the quotient polynomial.
+    pub extended_columns: usize,
+    /// The size of a column over the extended domain.
+    pub extended_column: usize,
+    /// The number of parts of the extended domain.
+    pub parts: usize,
+    /// The polynomials used to compute the quotient polynomial, besides the columns.
+    pub quotient: usize,
+}
+
+impl ProverMemoryEstimate {
+    /// Returns the peak memory of a proof of `num_circuits` circuits, with at most
+    /// `max_extended_columns` columns held over the extended domain at once, if any. See
+    /// [`ProverV2::with_max_extended_columns`](crate::plonk::prover::ProverV2::with_max_extended_columns).
+    pub fn total(&self, num_circuits: usize, max_extended_columns: Option<usize>) -> usize {
+        let columns = evaluation::extended_windows(
+            num_circuits,
+            self.extended_columns,
+            self.parts,
+            max_extended_columns,
+        )
+        .iter()
+        .map(|window| window.live_parts(self.extended_columns, self.parts))
+        .max()
+        .unwrap_or(0)
+            * (self.extended_column / self.parts);
+        self.proving_key + num_circuits * self.witness + self.quotient + columns
+    }
+}
+
 /// This is a proving key which allows for the creation of proofs for a
 /// particular circuit.
 #[derive(Clone, Debug)]
@@ -331,6 +458,8 @@ pub struct ProvingKey<C: CurveAffine> {
     fixed_cosets: Vec<Polynomial<C::Scalar, ExtendedLagrangeCoeff>>,
     permutation: permutation::ProvingKey<C>,
     ev: Evaluator<C>,
+    /// Not serialized, see [`ProvingKey::warm_cache`].
+    cache: Option<ProverCache<C>>,
 }
 
 impl<C: CurveAffine> ProvingKey<C>
@@ -342,6 +471,86 @@ where
         &self.vk
     }
 
+    /// Returns the rows that circuits with this key can assign, as computed by
+    /// [`ConstraintSystem::usable_rows`].
+    pub fn usable_rows(&self) -> Range<usize> {
+        self.vk.usable_rows()
+    }
+
+    /// Computes and stores the [`ProverCache`], so that the proofs created with this key
+    /// reuse it instead of recomputing it. This is worth it when creating many proofs
+    /// with the same key, at the cost of memory, and doesn't change the proofs.
+    ///
+    /// The cache is not serialized with the key, and is dropped by
+    /// [`ProvingKey::clear_cache`].
+    pub fn warm_cache(&mut self) {
+        if self.cache.is_none() {
+            self.cache = Some(ProverCache::new(self));
+        }
+    }
+
+    /// Drops the [`ProverCache`], if any, to free its memory.
+    pub fn clear_cache(&mut self) {
+        self.cache = None;
+    }
+
+    /// Returns the [`ProverCache`], if it is warm.
+    pub fn cache(&self) -> Option<&ProverCache<C>> {
+        self.cache.as_ref()
+    }
+
+    /// Returns the values over the rows of the fixed columns, including the selectors.
+    pub fn fixed_values(&self) -> &[Polynomial<C::Scalar, LagrangeCoeff>] {
+        &self.fixed_values
+    }
+
+    /// Returns the values over the rows of the permutation `sigma_j` of each column of the
+    /// permutation argument.
+    pub fn permutation_values(&self) -> &[Polynomial<C::Scalar, LagrangeCoeff>] {
+        &self.permutation.permutations
+    }
+
+    /// Estimates the memory that creating a proof of this circuit with `2^k` rows takes,
+    /// which may differ from the rows of this key, to plan the memory of a larger circuit
+    /// from the key of a smaller one.
+    ///
+    /// The estimate only counts the polynomials, which dominate the memory for large `k`,
+    /// and neither the commitment parameters nor the [`ProverCache`].
+    pub fn estimated_prover_memory(&self, k: u32) -> ProverMemoryEstimate {
+        let cs = &self.vk.cs;
+        let n = 1usize << k;
+        let parts = 1usize << (self.vk.domain.extended_k() - self.vk.domain.k());
+        let extended_n = n * parts;
+        let scalar_len = std::mem::size_of::<C::Scalar>();
+
+        let permutation_columns = cs.permutation().columns.len();
+        let permutation_sets = if permutation_columns == 0 {
+            0
+        } else {
+            (permutation_columns + cs.degree() - 3) / (cs.degree() - 2)
+        };
+        let extended_columns = cs.num_advice_columns() + cs.num_instance_columns();
+
+        ProverMemoryEstimate {
+            // The fixed values, polynomials and cosets, the permutation values, polynomials
+            // and cosets, and l_0, l_last and l_active_row.
+            proving_key: scalar_len
+                * ((cs.num_fixed_columns() + permutation_columns) * (2 * n + extended_n)
+                    + 3 * extended_n),
+            // The advice and instance values and polynomials, the lookup, shuffle and
+            // permutation polynomials, and the permutation product cosets.
+            witness: scalar_len
+                * (2 * extended_columns * n
+                    + (3 * cs.lookups().len() + cs.shuffles().len() + permutation_sets) * n
+                    + permutation_sets * extended_n),
+            extended_columns,
+            extended_column: scalar_len * extended_n,
+            parts,
+            // The quotient polynomial, and the cosets of a lookup.
+            quotient: scalar_len * 4 * extended_n,
+        }
+    }
+
     /// Gets the total number of bytes in the serialization of `self`
     fn bytes_length(&self, format: SerdeFormat) -> usize
     where
@@ -424,6 +633,7 @@ where
             fixed_cosets,
             permutation,
             ev,
+            cache: None,
         })
     }
 
@@ -434,18 +644,44 @@ where
         bytes
     }
 
-    /// Reads a proving key from a slice of bytes using [`Self::read`].
+    /// Reads a proving key from a slice of bytes in the format of [`Self::read`], such as a
+    /// memory-mapped file.
+    ///
+    /// The polynomials are parsed from `bytes` in parallel, straight into their vectors,
+    /// without the intermediate copies of [`Self::read`]. They are still copied once: see
+    /// [`scalars_from_slice`](halo2_common::helpers::scalars_from_slice). The trailing bytes
+    /// after the proving key, if any, are ignored.
     pub fn from_bytes<ConcreteCircuit: Circuit<C::Scalar>>(
         mut bytes: &[u8],
         format: SerdeFormat,
         #[cfg(feature = "circuit-params")] params: ConcreteCircuit::Params,
     ) -> io::Result<Self> {
-        Self::read::<_, ConcreteCircuit>(
+        let vk = VerifyingKey::<C>::read::<_, ConcreteCircuit>(
             &mut bytes,
             format,
             #[cfg(feature = "circuit-params")]
             params,
-        )
+        )?;
+        let l0 = Polynomial::from_slice(&mut bytes, format)?;
+        let l_last = Polynomial::from_slice(&mut bytes, format)?;
+        let l_active_row = Polynomial::from_slice(&mut bytes, format)?;
+        let fixed_values = polynomial_vec_from_slice(&mut bytes, format)?;
+        let fixed_polys = polynomial_vec_from_slice(&mut bytes, format)?;
+        let fixed_cosets = polynomial_vec_from_slice(&mut bytes, format)?;
+        let permutation = permutation::ProvingKey::from_slice(&mut bytes, format)?;
+        let ev = Evaluator::new(vk.cs());
+        Ok(Self {
+            vk,
+            l0,
+            l_last,
+            l_active_row,
+            fixed_values,
+            fixed_polys,
+            fixed_cosets,
+            permutation,
+            ev,
+            cache: None,
+        })
     }
 }
 
@@ -454,4 +690,10 @@ impl<C: CurveAffine> VerifyingKey<C> {
     pub fn get_domain(&self) -> &EvaluationDomain<C::Scalar> {
         &self.domain
     }
+
+    /// Returns the rows that circuits with this key can assign, as computed by
+    /// [`ConstraintSystem::usable_rows`].
+    pub fn usable_rows(&self) -> Range<usize> {
+        self.cs.usable_rows(self.domain.k())
+    }
 }
@@ -0,0 +1,97 @@
+use halo2_middleware::ff::Field;
+use rand_chacha::ChaCha20Rng;
+use rand_core::{RngCore, SeedableRng};
+
+use crate::multicore::{
+    IndexedParallelIterator, IntoParallelIterator, ParallelIterator, ParallelSliceMut,
+};
+use crate::poly::commitment::Blind;
+
+/// Number of values of [`Blinder::fill`] that are sampled from the same seed.
+///
+/// This is fixed so that the sampled values don't depend on the number of threads.
+const FILL_CHUNK_SIZE: usize = 1 << 10;
+
+/// The source of the blinding factors of a proof.
+///
+/// Blinding factors are sampled from `rng`, in the order documented on
+/// [`ProverV2`](super::prover::ProverV2). With unit blinds, every blinding factor is one
+/// and `rng` is only passed on to the multi-open prover.
+#[derive(Debug)]
+pub(crate) struct Blinder<R> {
+    rng: R,
+    unit_blinds: bool,
+}
+
+impl<R: RngCore> Blinder<R> {
+    pub(crate) fn new(rng: R) -> Self {
+        Blinder {
+            rng,
+            unit_blinds: false,
+        }
+    }
+
+    #[cfg(feature = "unit-blinds")]
+    pub(crate) fn set_unit_blinds(&mut self, unit_blinds: bool) {
+        self.unit_blinds = unit_blinds;
+    }
+
+    /// Samples a blinding factor.
+    pub(crate) fn blind<F: Field>(&mut self) -> F {
+        if self.unit_blinds {
+            F::ONE
+        } else {
+            F::random(&mut self.rng)
+        }
+    }
+
+    /// Fills `values` with blinding factors, in parallel.
+    ///
+    /// A 32-byte seed is sampled for each chunk of [`FILL_CHUNK_SIZE`] values, in order,
+    /// and the values of the chunk are sampled from a ChaCha20 RNG with that seed.
+    pub(crate) fn fill<F: Field>(&mut self, values: &mut [F]) {
+        if self.unit_blinds {
+            values.fill(F::ONE);
+            return;
+        }
+
+        let seeds: Vec<_> = (0..(values.len() + FILL_CHUNK_SIZE - 1) / FILL_CHUNK_SIZE)
+            .map(|_| {
+                let mut seed = [0u8; 32];
+                self.rng.fill_bytes(&mut seed);
+                seed
+            })
+            .collect();
+        values
+            .par_chunks_mut(FILL_CHUNK_SIZE)
+            .zip(seeds.into_par_iter())
+            .for_each(|(chunk, seed)| {
+                let mut rng = ChaCha20Rng::from_seed(seed);
+                chunk.iter_mut().for_each(|v| *v = F::random(&mut rng));
+            });
+    }
+
+    /// Samples the blinding factors of a grand product column, see [`ProductBlinds`].
+    pub(crate) fn product_blinds<F: Field>(&mut self, blinding_factors: usize) -> ProductBlinds<F> {
+        let rows = (0..blinding_factors).map(|_| self.blind()).collect();
+        let blind = Blind(self.blind());
+        ProductBlinds { rows, blind }
+    }
+
+    /// Returns the underlying RNG, which is used by the multi-open prover.
+    pub(crate) fn into_rng(self) -> R {
+        self.rng
+    }
+}
+
+/// The blinding factors of a grand product column: its blinding rows, then the blind of its
+/// commitment.
+///
+/// They are sampled before the column is computed, so that the columns of several
+/// arguments can be computed in parallel while the blinding factors are sampled in the
+/// documented order.
+#[derive(Debug)]
+pub(crate) struct ProductBlinds<F> {
+    pub(crate) rows: Vec<F>,
+    pub(crate) blind: Blind<F>,
+}
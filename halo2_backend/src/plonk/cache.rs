@@ -0,0 +1,148 @@
+use halo2_middleware::ff::{Field, PrimeField};
+
+use super::evaluation::evaluate;
+use super::ProvingKey;
+use crate::arithmetic::{parallelize, CurveAffine};
+use crate::poly::{LagrangeCoeff, Polynomial};
+use halo2_common::plonk::Expression;
+
+/// The values of the expressions of an argument, or `None` for the expressions which
+/// depend on the witness.
+pub(crate) type ExpressionValues<F> = Vec<Option<Polynomial<F, LagrangeCoeff>>>;
+
+/// Data which the prover computes from the proving key alone, cached to amortize it over
+/// many proofs. See [`ProvingKey::warm_cache`].
+///
+/// The proving key already stores the extended-domain cosets of the fixed and permutation
+/// polynomials, and `l_0`, `l_last` and `l_active_row`; this caches the rest.
+#[derive(Clone, Debug)]
+pub struct ProverCache<C: CurveAffine> {
+    /// `δ^j ω^i` for each column `j` of the permutation argument and each row `i`.
+    pub(crate) permutation_identities: Vec<Polynomial<C::Scalar, LagrangeCoeff>>,
+    /// The values of the input and table expressions of each lookup.
+    pub(crate) lookups: Vec<(ExpressionValues<C::Scalar>, ExpressionValues<C::Scalar>)>,
+    /// The values of the input and shuffle expressions of each shuffle.
+    pub(crate) shuffles: Vec<(ExpressionValues<C::Scalar>, ExpressionValues<C::Scalar>)>,
+}
+
+impl<C: CurveAffine> ProverCache<C> {
+    pub(crate) fn new(pk: &ProvingKey<C>) -> Self {
+        let domain = &pk.vk.domain;
+        let n = 1 << domain.k();
+        let omega = domain.get_omega();
+
+        let mut delta = C::Scalar::ONE;
+        let permutation_identities = pk
+            .vk
+            .cs
+            .permutation
+            .columns
+            .iter()
+            .map(|_| {
+                let delta_j = delta;
+                let mut values = domain.empty_lagrange();
+                parallelize(&mut values, |values, start| {
+                    let mut deltaomega = delta_j * omega.pow_vartime([start as u64, 0, 0, 0]);
+                    for value in values.iter_mut() {
+                        *value = deltaomega;
+                        deltaomega *= &omega;
+                    }
+                });
+                delta *= &<C::Scalar as PrimeField>::DELTA;
+                values
+            })
+            .collect();
+
+        let fixed_values = |expressions: &[Expression<C::Scalar>]| -> ExpressionValues<_> {
+            expressions
+                .iter()
+                .map(|expression| {
+                    is_fixed(expression).then(|| {
+                        domain.lagrange_from_vec(evaluate(
+                            expression,
+                            n,
+                            1,
+                            &pk.fixed_values,
+                            &[],
+                            &[],
+                            &[],
+                        ))
+                    })
+                })
+                .collect()
+        };
+        let lookups = pk
+            .vk
+            .cs
+            .lookups
+            .iter()
+            .map(|lookup| {
+                (
+                    fixed_values(&lookup.input_expressions),
+                    fixed_values(&lookup.table_expressions),
+                )
+            })
+            .collect();
+        let shuffles = pk
+            .vk
+            .cs
+            .shuffles
+            .iter()
+            .map(|shuffle| {
+                (
+                    fixed_values(&shuffle.input_expressions),
+                    fixed_values(&shuffle.shuffle_expressions),
+                )
+            })
+            .collect();
+
+        ProverCache {
+            permutation_identities,
+            lookups,
+            shuffles,
+        }
+    }
+
+    /// Returns the number of field elements in the cache.
+    pub fn len(&self) -> usize {
+        let expressions = |values: &ExpressionValues<C::Scalar>| {
+            values
+                .iter()
+                .flatten()
+                .map(|poly| poly.len())
+                .sum::<usize>()
+        };
+        self.permutation_identities
+            .iter()
+            .map(|poly| poly.len())
+            .chain(
+                self.lookups
+                    .iter()
+                    .chain(&self.shuffles)
+                    .map(|(inputs, tables)| expressions(inputs) + expressions(tables)),
+            )
+            .sum()
+    }
+
+    /// Returns `true` if the cache is empty, which is the case for circuits without a
+    /// permutation argument and fixed lookup or shuffle expressions.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Returns `true` if `expression` only queries fixed columns.
+fn is_fixed<F: Field>(expression: &Expression<F>) -> bool {
+    expression.evaluate(
+        &|_| true,
+        &|_| panic!("virtual selectors are removed during optimization"),
+        &|_| true,
+        &|_| false,
+        &|_| false,
+        &|_| false,
+        &|a| a,
+        &|a, b| a && b,
+        &|a, b| a && b,
+        &|a, _| a,
+    )
+}
@@ -3,12 +3,17 @@ use crate::plonk::{lookup, permutation, ProvingKey};
 use crate::poly::Basis;
 use crate::{
     arithmetic::{parallelize, CurveAffine},
-    poly::{Coeff, ExtendedLagrangeCoeff, Polynomial},
+    poly::{Coeff, EvaluationDomain, ExtendedLagrangeCoeff, Polynomial, PolynomialEngine},
 };
 use group::ff::{Field, PrimeField, WithSmallOrderMulGroup};
 use halo2_common::plonk::{ConstraintSystem, Expression};
 use halo2_middleware::circuit::Any;
 use halo2_middleware::poly::Rotation;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::iter;
+use std::marker::PhantomData;
+use std::ops::Range;
 
 use super::shuffle;
 
@@ -18,7 +23,7 @@ fn get_rotation_idx(idx: usize, rot: i32, rot_scale: i32, isize: i32) -> usize {
 }
 
 /// Value used in a calculation
-#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Hash)]
 pub enum ValueSource {
     /// This is a constant value
     Constant(usize),
@@ -91,7 +96,7 @@ impl ValueSource {
 }
 
 /// Calculation
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub enum Calculation {
     /// This is an addition
     Add(ValueSource, ValueSource),
@@ -187,8 +192,16 @@ pub struct GraphEvaluator<C: CurveAffine> {
     pub calculations: Vec<CalculationInfo>,
     /// Number of intermediates
     pub num_intermediates: usize,
+    /// The targets of the calculations, to share the intermediate of identical calculations.
+    calculation_targets: HashMap<Calculation, usize>,
+    /// The maximum number of entries of `calculation_targets`.
+    expression_cache_limit: usize,
 }
 
+/// The default maximum number of intermediates that a [`GraphEvaluator`] shares between
+/// identical sub-expressions, see [`Evaluator::with_expression_cache_limit`].
+pub const DEFAULT_EXPRESSION_CACHE_LIMIT: usize = 1 << 12;
+
 /// EvaluationData
 #[derive(Default, Debug)]
 pub struct EvaluationData<C: CurveAffine> {
@@ -210,7 +223,29 @@ pub struct CalculationInfo {
 impl<C: CurveAffine> Evaluator<C> {
     /// Creates a new evaluation structure
     pub fn new(cs: &ConstraintSystem<C::ScalarExt>) -> Self {
-        let mut ev = Evaluator::default();
+        Self::with_expression_cache_limit(cs, DEFAULT_EXPRESSION_CACHE_LIMIT)
+    }
+
+    /// Creates a new evaluation structure, sharing at most `limit` intermediates per graph
+    /// between identical sub-expressions.
+    ///
+    /// Identical sub-expressions, e.g. those of gates that are repeated by
+    /// macro-generated configurations, are added to the graph once: a sub-expression is
+    /// keyed on its operation and the intermediates of its children, so later occurrences
+    /// reuse the intermediate of the first one. Past the limit, the calculations that are
+    /// added get an intermediate of their own even if they are repeated, which bounds the
+    /// table of shared intermediates. The values are the same for any limit, and a limit
+    /// of 0 disables the sharing.
+    pub fn with_expression_cache_limit(cs: &ConstraintSystem<C::ScalarExt>, limit: usize) -> Self {
+        let new_graph = || GraphEvaluator {
+            expression_cache_limit: limit,
+            ..GraphEvaluator::default()
+        };
+        let mut ev = Evaluator {
+            custom_gates: new_graph(),
+            lookups: Vec::new(),
+            shuffles: Vec::new(),
+        };
 
         // Custom gates
         let mut parts = Vec::new();
@@ -229,7 +264,7 @@ impl<C: CurveAffine> Evaluator<C> {
 
         // Lookups
         for lookup in cs.lookups.iter() {
-            let mut graph = GraphEvaluator::default();
+            let mut graph = new_graph();
 
             let mut evaluate_lc = |expressions: &Vec<Expression<_>>| {
                 let parts = expressions
@@ -275,14 +310,14 @@ impl<C: CurveAffine> Evaluator<C> {
                 ))
             };
 
-            let mut graph_input = GraphEvaluator::default();
+            let mut graph_input = new_graph();
             let compressed_input_coset = evaluate_lc(&shuffle.input_expressions, &mut graph_input);
             let _ = graph_input.add_calculation(Calculation::Add(
                 compressed_input_coset,
                 ValueSource::Gamma(),
             ));
 
-            let mut graph_shuffle = GraphEvaluator::default();
+            let mut graph_shuffle = new_graph();
             let compressed_shuffle_coset =
                 evaluate_lc(&shuffle.shuffle_expressions, &mut graph_shuffle);
             let _ = graph_shuffle.add_calculation(Calculation::Add(
@@ -294,14 +329,30 @@ impl<C: CurveAffine> Evaluator<C> {
             ev.shuffles.push(graph_shuffle);
         }
 
+        // The caches are only needed while building the graphs.
+        for graph in iter::once(&mut ev.custom_gates)
+            .chain(ev.lookups.iter_mut())
+            .chain(ev.shuffles.iter_mut())
+        {
+            graph.calculation_targets = HashMap::new();
+        }
+
         ev
     }
 
     /// Evaluate h poly
+    ///
+    /// The advice and instance columns of the circuits are evaluated over the extended
+    /// domain for the [`ExtendedWindow`]s returned by [`extended_windows`] in turn, so that
+    /// at most `max_extended_columns` of them are held in memory at once. Over a part of the
+    /// extended domain, see
+    /// [`EvaluationDomain::coeff_to_extended_part_with_engine`](crate::poly::EvaluationDomain::coeff_to_extended_part_with_engine),
+    /// every column is extended once per part instead of once. The result is the same.
     #[allow(clippy::too_many_arguments)]
     pub(in crate::plonk) fn evaluate_h(
         &self,
         pk: &ProvingKey<C>,
+        engine: &dyn PolynomialEngine<C>,
         advice_polys: &[&[Polynomial<C::ScalarExt, Coeff>]],
         instance_polys: &[&[Polynomial<C::ScalarExt, Coeff>]],
         challenges: &[C::ScalarExt],
@@ -312,184 +363,208 @@ impl<C: CurveAffine> Evaluator<C> {
         lookups: &[Vec<lookup::prover::Committed<C>>],
         shuffles: &[Vec<shuffle::prover::Committed<C>>],
         permutations: &[permutation::prover::Committed<C>],
+        max_extended_columns: Option<usize>,
     ) -> Polynomial<C::ScalarExt, ExtendedLagrangeCoeff> {
         let domain = &pk.vk.domain;
-        let size = domain.extended_len();
-        let rot_scale = 1 << (domain.extended_k() - domain.k());
-        let fixed = &pk.fixed_cosets[..];
-        let extended_omega = domain.get_extended_omega();
-        let isize = size as i32;
-        let one = C::ScalarExt::ONE;
-        let l0 = &pk.l0;
-        let l_last = &pk.l_last;
-        let l_active_row = &pk.l_active_row;
-        let p = &pk.vk.cs.permutation;
-
-        // Calculate the advice and instance cosets
-        let advice: Vec<Vec<Polynomial<C::Scalar, ExtendedLagrangeCoeff>>> = advice_polys
-            .iter()
-            .map(|advice_polys| {
-                advice_polys
-                    .iter()
-                    .map(|poly| domain.coeff_to_extended(poly.clone()))
-                    .collect()
-            })
-            .collect();
-        let instance: Vec<Vec<Polynomial<C::Scalar, ExtendedLagrangeCoeff>>> = instance_polys
-            .iter()
-            .map(|instance_polys| {
-                instance_polys
-                    .iter()
-                    .map(|poly| domain.coeff_to_extended(poly.clone()))
-                    .collect()
-            })
-            .collect();
+        let parts = 1 << (domain.extended_k() - domain.k());
+        let num_columns = pk.vk.cs.num_advice_columns + pk.vk.cs.num_instance_columns;
 
         let mut values = domain.empty_extended();
+        let constants = Constants {
+            challenges,
+            y,
+            beta,
+            gamma,
+            theta,
+        };
 
-        // Core expression evaluations
-        let num_threads = multicore::current_num_threads();
-        for ((((advice, instance), lookups), shuffles), permutation) in advice
-            .iter()
-            .zip(instance.iter())
-            .zip(lookups.iter())
-            .zip(shuffles.iter())
-            .zip(permutations.iter())
-        {
-            // Custom gates
-            multicore::scope(|scope| {
-                let chunk_size = (size + num_threads - 1) / num_threads;
-                for (thread_idx, values) in values.chunks_mut(chunk_size).enumerate() {
-                    let start = thread_idx * chunk_size;
-                    scope.spawn(move |_| {
-                        let mut eval_data = self.custom_gates.instance();
-                        for (i, value) in values.iter_mut().enumerate() {
-                            let idx = start + i;
-                            *value = self.custom_gates.evaluate(
-                                &mut eval_data,
-                                fixed,
-                                advice,
-                                instance,
-                                challenges,
-                                &beta,
-                                &gamma,
-                                &theta,
-                                &y,
-                                value,
-                                idx,
-                                rot_scale,
-                                isize,
-                            );
-                        }
-                    });
-                }
-            });
-
-            // Permutations
-            let sets = &permutation.sets;
-            if !sets.is_empty() {
-                let blinding_factors = pk.vk.cs.blinding_factors();
-                let last_rotation = Rotation(-((blinding_factors + 1) as i32));
-                let chunk_len = pk.vk.cs.degree() - 2;
-                let delta_start = beta * C::Scalar::ZETA;
-
-                let first_set = sets.first().unwrap();
-                let last_set = sets.last().unwrap();
-
-                // Permutation constraints
-                parallelize(&mut values, |values, start| {
-                    let mut beta_term = extended_omega.pow_vartime([start as u64, 0, 0, 0]);
-                    for (i, value) in values.iter_mut().enumerate() {
-                        let idx = start + i;
-                        let r_next = get_rotation_idx(idx, 1, rot_scale, isize);
-                        let r_last = get_rotation_idx(idx, last_rotation.0, rot_scale, isize);
-
-                        // Enforce only for the first set.
-                        // l_0(X) * (1 - z_0(X)) = 0
-                        *value = *value * y
-                            + ((one - first_set.permutation_product_coset[idx]) * l0[idx]);
-                        // Enforce only for the last set.
-                        // l_last(X) * (z_l(X)^2 - z_l(X)) = 0
-                        *value = *value * y
-                            + ((last_set.permutation_product_coset[idx]
-                                * last_set.permutation_product_coset[idx]
-                                - last_set.permutation_product_coset[idx])
-                                * l_last[idx]);
-                        // Except for the first set, enforce.
-                        // l_0(X) * (z_i(X) - z_{i-1}(\omega^(last) X)) = 0
-                        for (set_idx, set) in sets.iter().enumerate() {
-                            if set_idx != 0 {
-                                *value = *value * y
-                                    + ((set.permutation_product_coset[idx]
-                                        - permutation.sets[set_idx - 1].permutation_product_coset
-                                            [r_last])
-                                        * l0[idx]);
-                            }
-                        }
-                        // And for all the sets we enforce:
-                        // (1 - (l_last(X) + l_blind(X))) * (
-                        //   z_i(\omega X) \prod_j (p(X) + \beta s_j(X) + \gamma)
-                        // - z_i(X) \prod_j (p(X) + \delta^j \beta X + \gamma)
-                        // )
-                        let mut current_delta = delta_start * beta_term;
-                        for ((set, columns), cosets) in sets
+        let windows =
+            extended_windows(advice_polys.len(), num_columns, parts, max_extended_columns);
+        for ExtendedWindow { circuits, part } in windows {
+            match part {
+                None => {
+                    // Calculate the advice and instance cosets of the circuits of the window
+                    let extend_all = |polys: &[&[Polynomial<C::Scalar, Coeff>]]| {
+                        polys[circuits.clone()]
                             .iter()
-                            .zip(p.columns.chunks(chunk_len))
-                            .zip(pk.permutation.cosets.chunks(chunk_len))
-                        {
-                            let mut left = set.permutation_product_coset[r_next];
-                            for (values, permutation) in columns
+                            .map(|polys| {
+                                polys
+                                    .iter()
+                                    .map(|poly| {
+                                        domain.coeff_to_extended_with_engine(engine, poly.clone())
+                                    })
+                                    .collect::<Vec<_>>()
+                            })
+                            .collect::<Vec<_>>()
+                    };
+                    let advice = extend_all(advice_polys);
+                    let instance = extend_all(instance_polys);
+
+                    for ((((advice, instance), lookups), shuffles), permutation) in advice
+                        .iter()
+                        .zip(instance.iter())
+                        .zip(lookups[circuits.clone()].iter())
+                        .zip(shuffles[circuits.clone()].iter())
+                        .zip(permutations[circuits.clone()].iter())
+                    {
+                        let window = Window {
+                            fixed: Cow::Borrowed(&pk.fixed_cosets[..]),
+                            advice: Cow::Borrowed(&advice[..]),
+                            instance: Cow::Borrowed(&instance[..]),
+                            l0: Cow::Borrowed(&pk.l0[..]),
+                            l_last: Cow::Borrowed(&pk.l_last[..]),
+                            l_active_row: Cow::Borrowed(&pk.l_active_row[..]),
+                            permutation_cosets: Cow::Borrowed(&pk.permutation.cosets[..]),
+                            permutation_product_cosets: permutation
+                                .sets
+                                .iter()
+                                .map(|set| Cow::Borrowed(&set.permutation_product_coset.values[..]))
+                                .collect(),
+                            rot_scale: parts as i32,
+                            omega_start: C::ScalarExt::ZETA,
+                            omega_step: domain.get_extended_omega(),
+                        };
+                        let extend = |poly: &Polynomial<C::Scalar, Coeff>| {
+                            Cow::Owned(
+                                domain
+                                    .coeff_to_extended_with_engine(engine, poly.clone())
+                                    .values,
+                            )
+                        };
+                        self.evaluate_window(
+                            &pk.vk.cs,
+                            &window,
+                            &constants,
+                            &|n| {
+                                let lookup = &lookups[n];
+                                [
+                                    extend(&lookup.product_poly),
+                                    extend(&lookup.permuted_input_poly),
+                                    extend(&lookup.permuted_table_poly),
+                                ]
+                            },
+                            &|n| extend(&shuffles[n].product_poly),
+                            &mut values,
+                        );
+                    }
+                }
+                Some(part) => {
+                    let part_of = |values: &[C::Scalar]| -> Vec<C::Scalar> {
+                        values.iter().skip(part).step_by(parts).copied().collect()
+                    };
+                    let polys_part_of =
+                        |polys: &[Polynomial<C::Scalar, ExtendedLagrangeCoeff>]| {
+                            polys
                                 .iter()
-                                .map(|&column| match column.column_type() {
-                                    Any::Advice(_) => &advice[column.index()],
-                                    Any::Fixed => &fixed[column.index()],
-                                    Any::Instance => &instance[column.index()],
+                                .map(|poly| Polynomial {
+                                    values: part_of(&poly[..]),
+                                    _marker: PhantomData,
                                 })
-                                .zip(cosets.iter())
-                            {
-                                left *= values[idx] + beta * permutation[idx] + gamma;
-                            }
+                                .collect::<Vec<_>>()
+                        };
+                    let extend = |poly: &Polynomial<C::Scalar, Coeff>| {
+                        domain.coeff_to_extended_part_with_engine(engine, poly, part)
+                    };
 
-                            let mut right = set.permutation_product_coset[idx];
-                            for values in columns.iter().map(|&column| match column.column_type() {
-                                Any::Advice(_) => &advice[column.index()],
-                                Any::Fixed => &fixed[column.index()],
-                                Any::Instance => &instance[column.index()],
-                            }) {
-                                right *= values[idx] + current_delta + gamma;
-                                current_delta *= &C::Scalar::DELTA;
-                            }
+                    for circuit in circuits {
+                        let (lookups, shuffles) = (&lookups[circuit], &shuffles[circuit]);
+                        let window = Window {
+                            fixed: Cow::Owned(polys_part_of(&pk.fixed_cosets)),
+                            advice: Cow::Owned(advice_polys[circuit].iter().map(&extend).collect()),
+                            instance: Cow::Owned(
+                                instance_polys[circuit].iter().map(&extend).collect(),
+                            ),
+                            l0: Cow::Owned(part_of(&pk.l0)),
+                            l_last: Cow::Owned(part_of(&pk.l_last)),
+                            l_active_row: Cow::Owned(part_of(&pk.l_active_row)),
+                            permutation_cosets: Cow::Owned(polys_part_of(&pk.permutation.cosets)),
+                            permutation_product_cosets: permutations[circuit]
+                                .sets
+                                .iter()
+                                .map(|set| Cow::Owned(part_of(&set.permutation_product_coset)))
+                                .collect(),
+                            rot_scale: 1,
+                            omega_start: C::ScalarExt::ZETA
+                                * domain.get_extended_omega().pow_vartime([part as u64]),
+                            omega_step: domain.get_omega(),
+                        };
 
-                            *value = *value * y + ((left - right) * l_active_row[idx]);
+                        let extend =
+                            |poly: &Polynomial<C::Scalar, Coeff>| Cow::Owned(extend(poly).values);
+                        let mut part_values = part_of(&values);
+                        self.evaluate_window(
+                            &pk.vk.cs,
+                            &window,
+                            &constants,
+                            &|n| {
+                                let lookup = &lookups[n];
+                                [
+                                    extend(&lookup.product_poly),
+                                    extend(&lookup.permuted_input_poly),
+                                    extend(&lookup.permuted_table_poly),
+                                ]
+                            },
+                            &|n| extend(&shuffles[n].product_poly),
+                            &mut part_values,
+                        );
+                        for (value, part_value) in
+                            values.iter_mut().skip(part).step_by(parts).zip(part_values)
+                        {
+                            *value = part_value;
                         }
-                        beta_term *= &extended_omega;
                     }
-                });
+                }
             }
+        }
+        values
+    }
 
-            // Lookups
-            for (n, lookup) in lookups.iter().enumerate() {
-                // Polynomials required for this lookup.
-                // Calculated here so these only have to be kept in memory for the short time
-                // they are actually needed.
-                let product_coset = pk.vk.domain.coeff_to_extended(lookup.product_poly.clone());
-                let permuted_input_coset = pk
-                    .vk
-                    .domain
-                    .coeff_to_extended(lookup.permuted_input_poly.clone());
-                let permuted_table_coset = pk
-                    .vk
-                    .domain
-                    .coeff_to_extended(lookup.permuted_table_poly.clone());
-
-                // Lookup constraints
-                parallelize(&mut values, |values, start| {
-                    let lookup_evaluator = &self.lookups[n];
-                    let mut eval_data = lookup_evaluator.instance();
+    /// Evaluates the constraints of a circuit instance over `window`, into `values`, which
+    /// holds the evaluations of the constraints of the previous circuit instances.
+    /// `lookup` returns the evaluations over the window of the product, permuted input and
+    /// permuted table columns of a lookup argument, and `shuffle` of the product column of
+    /// a shuffle argument.
+    fn evaluate_window<'a>(
+        &self,
+        cs: &ConstraintSystem<C::ScalarExt>,
+        window: &Window<'_, C::ScalarExt>,
+        constants: &Constants<'_, C::ScalarExt>,
+        lookup: &dyn Fn(usize) -> [Cow<'a, [C::ScalarExt]>; 3],
+        shuffle: &dyn Fn(usize) -> Cow<'a, [C::ScalarExt]>,
+        values: &mut [C::ScalarExt],
+    ) {
+        let size = values.len();
+        let rot_scale = window.rot_scale;
+        let fixed = &window.fixed[..];
+        let advice = &window.advice[..];
+        let instance = &window.instance[..];
+        let isize = size as i32;
+        let one = C::ScalarExt::ONE;
+        let l0 = &window.l0[..];
+        let l_last = &window.l_last[..];
+        let l_active_row = &window.l_active_row[..];
+        let p = &cs.permutation;
+        let Constants {
+            challenges,
+            y,
+            beta,
+            gamma,
+            theta,
+        } = *constants;
+
+        // Core expression evaluations
+        let num_threads = multicore::current_num_threads();
+
+        // Custom gates
+        multicore::scope(|scope| {
+            let chunk_size = (size + num_threads - 1) / num_threads;
+            for (thread_idx, values) in values.chunks_mut(chunk_size).enumerate() {
+                let start = thread_idx * chunk_size;
+                scope.spawn(move |_| {
+                    let mut eval_data = self.custom_gates.instance();
                     for (i, value) in values.iter_mut().enumerate() {
                         let idx = start + i;
-
-                        let table_value = lookup_evaluator.evaluate(
+                        *value = self.custom_gates.evaluate(
                             &mut eval_data,
                             fixed,
                             advice,
@@ -499,115 +574,414 @@ impl<C: CurveAffine> Evaluator<C> {
                             &gamma,
                             &theta,
                             &y,
-                            &C::ScalarExt::ZERO,
+                            value,
                             idx,
                             rot_scale,
                             isize,
                         );
-
-                        let r_next = get_rotation_idx(idx, 1, rot_scale, isize);
-                        let r_prev = get_rotation_idx(idx, -1, rot_scale, isize);
-
-                        let a_minus_s = permuted_input_coset[idx] - permuted_table_coset[idx];
-                        // l_0(X) * (1 - z(X)) = 0
-                        *value = *value * y + ((one - product_coset[idx]) * l0[idx]);
-                        // l_last(X) * (z(X)^2 - z(X)) = 0
-                        *value = *value * y
-                            + ((product_coset[idx] * product_coset[idx] - product_coset[idx])
-                                * l_last[idx]);
-                        // (1 - (l_last(X) + l_blind(X))) * (
-                        //   z(\omega X) (a'(X) + \beta) (s'(X) + \gamma)
-                        //   - z(X) (\theta^{m-1} a_0(X) + ... + a_{m-1}(X) + \beta)
-                        //          (\theta^{m-1} s_0(X) + ... + s_{m-1}(X) + \gamma)
-                        // ) = 0
-                        *value = *value * y
-                            + ((product_coset[r_next]
-                                * (permuted_input_coset[idx] + beta)
-                                * (permuted_table_coset[idx] + gamma)
-                                - product_coset[idx] * table_value)
-                                * l_active_row[idx]);
-                        // Check that the first values in the permuted input expression and permuted
-                        // fixed expression are the same.
-                        // l_0(X) * (a'(X) - s'(X)) = 0
-                        *value = *value * y + (a_minus_s * l0[idx]);
-                        // Check that each value in the permuted lookup input expression is either
-                        // equal to the value above it, or the value at the same index in the
-                        // permuted table expression.
-                        // (1 - (l_last + l_blind)) * (a′(X) − s′(X))⋅(a′(X) − a′(\omega^{-1} X)) = 0
-                        *value = *value * y
-                            + (a_minus_s
-                                * (permuted_input_coset[idx] - permuted_input_coset[r_prev])
-                                * l_active_row[idx]);
                     }
                 });
             }
+        });
 
-            // Shuffle constraints
-            for (n, shuffle) in shuffles.iter().enumerate() {
-                let product_coset = pk.vk.domain.coeff_to_extended(shuffle.product_poly.clone());
-
-                // Shuffle constraints
-                parallelize(&mut values, |values, start| {
-                    let input_evaluator = &self.shuffles[2 * n];
-                    let shuffle_evaluator = &self.shuffles[2 * n + 1];
-                    let mut eval_data_input = shuffle_evaluator.instance();
-                    let mut eval_data_shuffle = shuffle_evaluator.instance();
-                    for (i, value) in values.iter_mut().enumerate() {
-                        let idx = start + i;
+        // Permutations
+        let sets = &window.permutation_product_cosets;
+        if !sets.is_empty() {
+            let blinding_factors = cs.blinding_factors();
+            let last_rotation = Rotation(-((blinding_factors + 1) as i32));
+            let chunk_len = cs.degree() - 2;
 
-                        let input_value = input_evaluator.evaluate(
-                            &mut eval_data_input,
-                            fixed,
-                            advice,
-                            instance,
-                            challenges,
-                            &beta,
-                            &gamma,
-                            &theta,
-                            &y,
-                            &C::ScalarExt::ZERO,
-                            idx,
-                            rot_scale,
-                            isize,
-                        );
+            let first_set = sets.first().unwrap();
+            let last_set = sets.last().unwrap();
 
-                        let shuffle_value = shuffle_evaluator.evaluate(
-                            &mut eval_data_shuffle,
-                            fixed,
-                            advice,
-                            instance,
-                            challenges,
-                            &beta,
-                            &gamma,
-                            &theta,
-                            &y,
-                            &C::ScalarExt::ZERO,
-                            idx,
-                            rot_scale,
-                            isize,
-                        );
+            // Permutation constraints
+            parallelize(values, |values, start| {
+                let mut beta_term =
+                    window.omega_start * window.omega_step.pow_vartime([start as u64, 0, 0, 0]);
+                for (i, value) in values.iter_mut().enumerate() {
+                    let idx = start + i;
+                    let r_next = get_rotation_idx(idx, 1, rot_scale, isize);
+                    let r_last = get_rotation_idx(idx, last_rotation.0, rot_scale, isize);
 
-                        let r_next = get_rotation_idx(idx, 1, rot_scale, isize);
-
-                        // l_0(X) * (1 - z(X)) = 0
-                        *value = *value * y + ((one - product_coset[idx]) * l0[idx]);
-                        // l_last(X) * (z(X)^2 - z(X)) = 0
-                        *value = *value * y
-                            + ((product_coset[idx] * product_coset[idx] - product_coset[idx])
-                                * l_last[idx]);
-                        // (1 - (l_last(X) + l_blind(X))) * (z(\omega X) (s(X) + \gamma) - z(X) (a(X) + \gamma)) = 0
-                        *value = *value * y
-                            + l_active_row[idx]
-                                * (product_coset[r_next] * shuffle_value
-                                    - product_coset[idx] * input_value)
+                    // Enforce only for the first set.
+                    // l_0(X) * (1 - z_0(X)) = 0
+                    *value = *value * y + ((one - first_set[idx]) * l0[idx]);
+                    // Enforce only for the last set.
+                    // l_last(X) * (z_l(X)^2 - z_l(X)) = 0
+                    *value = *value * y
+                        + ((last_set[idx] * last_set[idx] - last_set[idx]) * l_last[idx]);
+                    // Except for the first set, enforce.
+                    // l_0(X) * (z_i(X) - z_{i-1}(\omega^(last) X)) = 0
+                    for (set_idx, set) in sets.iter().enumerate() {
+                        if set_idx != 0 {
+                            *value =
+                                *value * y + ((set[idx] - sets[set_idx - 1][r_last]) * l0[idx]);
+                        }
                     }
-                });
-            }
+                    // And for all the sets we enforce:
+                    // (1 - (l_last(X) + l_blind(X))) * (
+                    //   z_i(\omega X) \prod_j (p(X) + \beta s_j(X) + \gamma)
+                    // - z_i(X) \prod_j (p(X) + \delta^j \beta X + \gamma)
+                    // )
+                    let mut current_delta = beta * beta_term;
+                    for ((set, columns), cosets) in sets
+                        .iter()
+                        .zip(p.columns.chunks(chunk_len))
+                        .zip(window.permutation_cosets.chunks(chunk_len))
+                    {
+                        let mut left = set[r_next];
+                        for (values, permutation) in columns
+                            .iter()
+                            .map(|&column| match column.column_type() {
+                                Any::Advice(_) => &advice[column.index()],
+                                Any::Fixed => &fixed[column.index()],
+                                Any::Instance => &instance[column.index()],
+                            })
+                            .zip(cosets.iter())
+                        {
+                            left *= values[idx] + beta * permutation[idx] + gamma;
+                        }
+
+                        let mut right = set[idx];
+                        for values in columns.iter().map(|&column| match column.column_type() {
+                            Any::Advice(_) => &advice[column.index()],
+                            Any::Fixed => &fixed[column.index()],
+                            Any::Instance => &instance[column.index()],
+                        }) {
+                            right *= values[idx] + current_delta + gamma;
+                            current_delta *= &C::Scalar::DELTA;
+                        }
+
+                        *value = *value * y + ((left - right) * l_active_row[idx]);
+                    }
+                    beta_term *= &window.omega_step;
+                }
+            });
+        }
+
+        // Lookups
+        for n in 0..self.lookups.len() {
+            // Polynomials required for this lookup.
+            // Calculated here so these only have to be kept in memory for the short time
+            // they are actually needed.
+            let [product_coset, permuted_input_coset, permuted_table_coset] = lookup(n);
+
+            // Lookup constraints
+            parallelize(values, |values, start| {
+                let lookup_evaluator = &self.lookups[n];
+                let mut eval_data = lookup_evaluator.instance();
+                for (i, value) in values.iter_mut().enumerate() {
+                    let idx = start + i;
+
+                    let table_value = lookup_evaluator.evaluate(
+                        &mut eval_data,
+                        fixed,
+                        advice,
+                        instance,
+                        challenges,
+                        &beta,
+                        &gamma,
+                        &theta,
+                        &y,
+                        &C::ScalarExt::ZERO,
+                        idx,
+                        rot_scale,
+                        isize,
+                    );
+
+                    let r_next = get_rotation_idx(idx, 1, rot_scale, isize);
+                    let r_prev = get_rotation_idx(idx, -1, rot_scale, isize);
+
+                    let a_minus_s = permuted_input_coset[idx] - permuted_table_coset[idx];
+                    // l_0(X) * (1 - z(X)) = 0
+                    *value = *value * y + ((one - product_coset[idx]) * l0[idx]);
+                    // l_last(X) * (z(X)^2 - z(X)) = 0
+                    *value = *value * y
+                        + ((product_coset[idx] * product_coset[idx] - product_coset[idx])
+                            * l_last[idx]);
+                    // (1 - (l_last(X) + l_blind(X))) * (
+                    //   z(\omega X) (a'(X) + \beta) (s'(X) + \gamma)
+                    //   - z(X) (\theta^{m-1} a_0(X) + ... + a_{m-1}(X) + \beta)
+                    //          (\theta^{m-1} s_0(X) + ... + s_{m-1}(X) + \gamma)
+                    // ) = 0
+                    *value = *value * y
+                        + ((product_coset[r_next]
+                            * (permuted_input_coset[idx] + beta)
+                            * (permuted_table_coset[idx] + gamma)
+                            - product_coset[idx] * table_value)
+                            * l_active_row[idx]);
+                    // Check that the first values in the permuted input expression and permuted
+                    // fixed expression are the same.
+                    // l_0(X) * (a'(X) - s'(X)) = 0
+                    *value = *value * y + (a_minus_s * l0[idx]);
+                    // Check that each value in the permuted lookup input expression is either
+                    // equal to the value above it, or the value at the same index in the
+                    // permuted table expression.
+                    // (1 - (l_last + l_blind)) * (a′(X) − s′(X))⋅(a′(X) − a′(\omega^{-1} X)) = 0
+                    *value = *value * y
+                        + (a_minus_s
+                            * (permuted_input_coset[idx] - permuted_input_coset[r_prev])
+                            * l_active_row[idx]);
+                }
+            });
+        }
+
+        // Shuffle constraints
+        for n in 0..self.shuffles.len() / 2 {
+            let product_coset = shuffle(n);
+
+            // Shuffle constraints
+            parallelize(values, |values, start| {
+                let input_evaluator = &self.shuffles[2 * n];
+                let shuffle_evaluator = &self.shuffles[2 * n + 1];
+                let mut eval_data_input = shuffle_evaluator.instance();
+                let mut eval_data_shuffle = shuffle_evaluator.instance();
+                for (i, value) in values.iter_mut().enumerate() {
+                    let idx = start + i;
+
+                    let input_value = input_evaluator.evaluate(
+                        &mut eval_data_input,
+                        fixed,
+                        advice,
+                        instance,
+                        challenges,
+                        &beta,
+                        &gamma,
+                        &theta,
+                        &y,
+                        &C::ScalarExt::ZERO,
+                        idx,
+                        rot_scale,
+                        isize,
+                    );
+
+                    let shuffle_value = shuffle_evaluator.evaluate(
+                        &mut eval_data_shuffle,
+                        fixed,
+                        advice,
+                        instance,
+                        challenges,
+                        &beta,
+                        &gamma,
+                        &theta,
+                        &y,
+                        &C::ScalarExt::ZERO,
+                        idx,
+                        rot_scale,
+                        isize,
+                    );
+
+                    let r_next = get_rotation_idx(idx, 1, rot_scale, isize);
+
+                    // l_0(X) * (1 - z(X)) = 0
+                    *value = *value * y + ((one - product_coset[idx]) * l0[idx]);
+                    // l_last(X) * (z(X)^2 - z(X)) = 0
+                    *value = *value * y
+                        + ((product_coset[idx] * product_coset[idx] - product_coset[idx])
+                            * l_last[idx]);
+                    // (1 - (l_last(X) + l_blind(X))) * (z(\omega X) (s(X) + \gamma) - z(X) (a(X) + \gamma)) = 0
+                    *value = *value * y
+                        + l_active_row[idx]
+                            * (product_coset[r_next] * shuffle_value
+                                - product_coset[idx] * input_value)
+                }
+            });
         }
-        values
     }
 }
 
+/// The constants of the constraints evaluated by [`Evaluator::evaluate_h`].
+#[derive(Clone, Copy)]
+struct Constants<'a, F> {
+    challenges: &'a [F],
+    y: F,
+    beta: F,
+    gamma: F,
+    theta: F,
+}
+
+/// The evaluations of the columns of a circuit instance over either the whole extended
+/// domain, one of its parts, or the rows of the original domain, for
+/// [`evaluate_constraints`].
+struct Window<'a, F: Field> {
+    fixed: Cow<'a, [Polynomial<F, ExtendedLagrangeCoeff>]>,
+    advice: Cow<'a, [Polynomial<F, ExtendedLagrangeCoeff>]>,
+    instance: Cow<'a, [Polynomial<F, ExtendedLagrangeCoeff>]>,
+    l0: Cow<'a, [F]>,
+    l_last: Cow<'a, [F]>,
+    l_active_row: Cow<'a, [F]>,
+    permutation_cosets: Cow<'a, [Polynomial<F, ExtendedLagrangeCoeff>]>,
+    permutation_product_cosets: Vec<Cow<'a, [F]>>,
+    /// The number of indices of the window between two consecutive rows.
+    rot_scale: i32,
+    /// The point of the first index of the window.
+    omega_start: F,
+    /// The ratio of the points of two consecutive indices of the window.
+    omega_step: F,
+}
+
+/// The values over the rows of the columns of the lookup, shuffle and permutation arguments
+/// of a circuit, and the challenges they were computed with, for [`evaluate_constraints`].
+#[derive(Clone, Debug)]
+pub struct ArgumentValues<F> {
+    /// The challenge compressing the expressions of a lookup or shuffle argument.
+    pub theta: F,
+    /// The challenge `beta` of the lookup and permutation arguments.
+    pub beta: F,
+    /// The challenge `gamma` of the lookup, shuffle and permutation arguments.
+    pub gamma: F,
+    /// The permutation `sigma_j` of each column of the permutation argument, see
+    /// [`ProvingKey::permutation_values`](crate::plonk::ProvingKey::permutation_values).
+    pub permutation_sigmas: Vec<Vec<F>>,
+    /// The grand product of each set of columns of the permutation argument.
+    pub permutation_products: Vec<Vec<F>>,
+    /// The columns of each lookup argument.
+    pub lookups: Vec<LookupValues<F>>,
+    /// The grand product of each shuffle argument.
+    pub shuffle_products: Vec<Vec<F>>,
+}
+
+/// The values over the rows of the columns of a lookup argument.
+#[derive(Clone, Debug)]
+pub struct LookupValues<F> {
+    /// The grand product.
+    pub product: Vec<F>,
+    /// The permuted compressed input expressions.
+    pub permuted_input: Vec<F>,
+    /// The permuted compressed table expressions.
+    pub permuted_table: Vec<F>,
+}
+
+/// Evaluates the constraints of a circuit at each row in `rows`, combined with powers of
+/// `y` exactly as in the numerator of the quotient polynomial `h(X)` of the prover: the
+/// custom gates, then the permutation, lookup and shuffle arguments.
+///
+/// The columns hold the values of all the `n` rows of the circuit, where `n` is a power of
+/// two, and the fixed columns include the selectors, as in
+/// [`ProvingKey::fixed_values`](crate::plonk::ProvingKey::fixed_values). Rotations wrap
+/// around the `n` rows. The evaluations are zero at every row for the witness of a proof
+/// that verifies, since the numerator of `h(X)` vanishes over the rows.
+///
+/// Panics if the columns don't match `cs`, or don't all have the same length.
+#[allow(clippy::too_many_arguments)]
+pub fn evaluate_constraints<C: CurveAffine>(
+    cs: &ConstraintSystem<C::Scalar>,
+    fixed: &[Vec<C::Scalar>],
+    advice: &[Vec<C::Scalar>],
+    instance: &[Vec<C::Scalar>],
+    challenges: &[C::Scalar],
+    arguments: &ArgumentValues<C::Scalar>,
+    y: C::Scalar,
+    rows: Range<usize>,
+) -> Vec<C::Scalar> {
+    assert_eq!(fixed.len(), cs.num_fixed_columns());
+    assert_eq!(advice.len(), cs.num_advice_columns());
+    assert_eq!(instance.len(), cs.num_instance_columns());
+    assert_eq!(challenges.len(), cs.num_challenges());
+    assert_eq!(arguments.lookups.len(), cs.lookups().len());
+    assert_eq!(arguments.shuffle_products.len(), cs.shuffles().len());
+    assert_eq!(
+        arguments.permutation_sigmas.len(),
+        cs.permutation().columns.len()
+    );
+    assert_eq!(
+        arguments.permutation_products.len(),
+        cs.permutation().columns.chunks(cs.degree() - 2).count()
+    );
+
+    let n = fixed
+        .iter()
+        .chain(advice)
+        .chain(instance)
+        .chain(&arguments.permutation_sigmas)
+        .chain(&arguments.permutation_products)
+        .chain(&arguments.shuffle_products)
+        .chain(arguments.lookups.iter().flat_map(|lookup| {
+            [
+                &lookup.product,
+                &lookup.permuted_input,
+                &lookup.permuted_table,
+            ]
+        }))
+        .fold(None, |n, column| {
+            assert!(
+                n.map_or(true, |n| n == column.len()),
+                "the columns don't all have the same length"
+            );
+            Some(column.len())
+        })
+        .expect("the circuit has columns");
+    assert!(
+        n.is_power_of_two(),
+        "the number of rows is not a power of two"
+    );
+    let omega = EvaluationDomain::new(cs.degree() as u32, n.trailing_zeros()).get_omega();
+
+    let polys = |columns: &[Vec<C::Scalar>]| {
+        columns
+            .iter()
+            .map(|values| Polynomial {
+                values: values.clone(),
+                _marker: PhantomData,
+            })
+            .collect::<Vec<_>>()
+    };
+    let last_row = n - cs.blinding_factors() - 1;
+    let indicator = |active: &dyn Fn(usize) -> bool| {
+        (0..n)
+            .map(|row| {
+                if active(row) {
+                    C::Scalar::ONE
+                } else {
+                    C::Scalar::ZERO
+                }
+            })
+            .collect::<Vec<_>>()
+    };
+    let window = Window {
+        fixed: Cow::Owned(polys(fixed)),
+        advice: Cow::Owned(polys(advice)),
+        instance: Cow::Owned(polys(instance)),
+        l0: Cow::Owned(indicator(&|row| row == 0)),
+        l_last: Cow::Owned(indicator(&|row| row == last_row)),
+        l_active_row: Cow::Owned(indicator(&|row| row < last_row)),
+        permutation_cosets: Cow::Owned(polys(&arguments.permutation_sigmas)),
+        permutation_product_cosets: arguments
+            .permutation_products
+            .iter()
+            .map(|values| Cow::Borrowed(&values[..]))
+            .collect(),
+        rot_scale: 1,
+        omega_start: C::Scalar::ONE,
+        omega_step: omega,
+    };
+    let constants = Constants {
+        challenges,
+        y,
+        beta: arguments.beta,
+        gamma: arguments.gamma,
+        theta: arguments.theta,
+    };
+
+    let mut values = vec![C::Scalar::ZERO; n];
+    Evaluator::<C>::new(cs).evaluate_window(
+        cs,
+        &window,
+        &constants,
+        &|n| {
+            let lookup = &arguments.lookups[n];
+            [
+                Cow::Borrowed(&lookup.product[..]),
+                Cow::Borrowed(&lookup.permuted_input[..]),
+                Cow::Borrowed(&lookup.permuted_table[..]),
+            ]
+        },
+        &|n| Cow::Borrowed(&arguments.shuffle_products[n][..]),
+        &mut values,
+    );
+    values[rows].to_vec()
+}
+
 impl<C: CurveAffine> Default for GraphEvaluator<C> {
     fn default() -> Self {
         Self {
@@ -620,6 +994,8 @@ impl<C: CurveAffine> Default for GraphEvaluator<C> {
             rotations: Vec::new(),
             calculations: Vec::new(),
             num_intermediates: 0,
+            calculation_targets: HashMap::new(),
+            expression_cache_limit: DEFAULT_EXPRESSION_CACHE_LIMIT,
         }
     }
 }
@@ -652,27 +1028,29 @@ impl<C: CurveAffine> GraphEvaluator<C> {
     /// Adds a calculation.
     /// Currently does the simplest thing possible: just stores the
     /// resulting value so the result can be reused  when that calculation
-    /// is done multiple times.
+    /// is done multiple times, as long as fewer than `expression_cache_limit`
+    /// results are shared.
     fn add_calculation(&mut self, calculation: Calculation) -> ValueSource {
-        let existing_calculation = self
-            .calculations
-            .iter()
-            .find(|c| c.calculation == calculation);
-        match existing_calculation {
-            Some(existing_calculation) => ValueSource::Intermediate(existing_calculation.target),
-            None => {
-                let target = self.num_intermediates;
-                self.calculations.push(CalculationInfo {
-                    calculation,
-                    target,
-                });
-                self.num_intermediates += 1;
-                ValueSource::Intermediate(target)
-            }
+        if let Some(target) = self.calculation_targets.get(&calculation) {
+            return ValueSource::Intermediate(*target);
+        }
+        let target = self.num_intermediates;
+        if self.calculation_targets.len() < self.expression_cache_limit {
+            self.calculation_targets.insert(calculation.clone(), target);
         }
+        self.calculations.push(CalculationInfo {
+            calculation,
+            target,
+        });
+        self.num_intermediates += 1;
+        ValueSource::Intermediate(target)
     }
 
     /// Generates an optimized evaluation for the expression
+    ///
+    /// The children are added first, so the calculation of a sub-expression is keyed on the
+    /// intermediates of its children in `add_calculation`: identical sub-expressions share
+    /// an intermediate in a single walk of the expression.
     fn add_expression(&mut self, expr: &Expression<C::ScalarExt>) -> ValueSource {
         match expr {
             Expression::Constant(scalar) => self.add_constant(scalar),
@@ -872,3 +1250,222 @@ pub fn evaluate<F: Field, B: Basis>(
     });
     values
 }
+
+/// The circuits whose advice and instance columns are held over the extended domain, or
+/// over a part of it, at once by [`Evaluator::evaluate_h`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(in crate::plonk) struct ExtendedWindow {
+    pub(in crate::plonk) circuits: Range<usize>,
+    /// The part of the extended domain, or `None` for the whole extended domain.
+    pub(in crate::plonk) part: Option<usize>,
+}
+
+impl ExtendedWindow {
+    /// Returns the number of extended columns held over the window, in `1 / parts` of an
+    /// extended column, with `num_columns` advice and instance columns per circuit.
+    pub(in crate::plonk) fn live_parts(&self, num_columns: usize, parts: usize) -> usize {
+        let parts = if self.part.is_some() { 1 } else { parts };
+        self.circuits.len() * num_columns * parts
+    }
+}
+
+/// Returns the windows over which the quotient polynomial of `num_circuits` circuits with
+/// `num_columns` advice and instance columns each is evaluated, in order, so that at most
+/// `max_extended_columns` columns are held over the extended domain at once, if any.
+///
+/// The circuits are evaluated over the whole extended domain as many at a time as the bound
+/// allows. If it is below the columns of a single circuit, they are evaluated one at a
+/// time, over each of the `parts` parts of the extended domain in turn, which holds a
+/// `1 / parts` of each column: the bound can't be lower than that.
+pub(in crate::plonk) fn extended_windows(
+    num_circuits: usize,
+    num_columns: usize,
+    parts: usize,
+    max_extended_columns: Option<usize>,
+) -> Vec<ExtendedWindow> {
+    let circuits_per_window = match max_extended_columns {
+        Some(max) if num_circuits * num_columns > max => max / num_columns,
+        _ => num_circuits.max(1),
+    };
+    if circuits_per_window > 0 {
+        (0..num_circuits)
+            .step_by(circuits_per_window)
+            .map(|start| ExtendedWindow {
+                circuits: start..num_circuits.min(start + circuits_per_window),
+                part: None,
+            })
+            .collect()
+    } else {
+        (0..num_circuits)
+            .flat_map(|circuit| {
+                (0..parts).map(move |part| ExtendedWindow {
+                    circuits: circuit..circuit + 1,
+                    part: Some(part),
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{extended_windows, Calculation, Evaluator, DEFAULT_EXPRESSION_CACHE_LIMIT};
+    use crate::poly::{LagrangeCoeff, Polynomial};
+    use group::ff::Field;
+    use halo2_common::plonk::ConstraintSystem;
+    use halo2_middleware::poly::Rotation;
+    use halo2curves::pasta::{EqAffine, Fp};
+    use std::marker::PhantomData;
+
+    /// Returns a constraint system with a `square` gate, repeated `copies` times, and an
+    /// `extended` gate that shares a sub-expression with it.
+    fn constraint_system(copies: usize) -> ConstraintSystem<Fp> {
+        let mut cs = ConstraintSystem::default();
+        let a = cs.advice_column();
+        let b = cs.advice_column();
+        let f = cs.fixed_column();
+        for _ in 0..copies {
+            cs.create_gate("square", |meta| {
+                let a = meta.query_advice(a, Rotation::cur());
+                let b = meta.query_advice(b, Rotation::cur());
+                let f = meta.query_fixed(f, Rotation::cur());
+                vec![f * (a.clone() * a - b)]
+            });
+        }
+        cs.create_gate("extended", |meta| {
+            let a = meta.query_advice(a, Rotation::cur());
+            let b = meta.query_advice(b, Rotation::cur());
+            let next = meta.query_advice(b, Rotation::next());
+            let f = meta.query_fixed(f, Rotation::cur());
+            vec![f * (a.clone() * a - b) * Fp::from(3) - next]
+        });
+        cs
+    }
+
+    fn calculations(ev: &Evaluator<EqAffine>) -> Vec<(Calculation, usize)> {
+        ev.custom_gates
+            .calculations
+            .iter()
+            .map(|info| (info.calculation.clone(), info.target))
+            .collect()
+    }
+
+    #[test]
+    fn duplicated_gates_share_intermediates() {
+        let single = Evaluator::<EqAffine>::new(&constraint_system(1));
+        let duplicated = Evaluator::<EqAffine>::new(&constraint_system(4));
+        assert_eq!(
+            duplicated.custom_gates.num_intermediates,
+            single.custom_gates.num_intermediates
+        );
+
+        // The repeated gates are still all part of the quotient.
+        let Some((Calculation::Horner(_, parts, _), _)) = calculations(&duplicated).pop() else {
+            panic!("the custom gates should be combined last");
+        };
+        assert_eq!(parts.len(), 5);
+        assert!(parts[..4].iter().all(|part| *part == parts[0]));
+    }
+
+    #[test]
+    fn expression_cache_limit_bounds_the_shared_intermediates() {
+        const N: usize = 4;
+        let poly = |offset: u64| Polynomial::<Fp, LagrangeCoeff> {
+            values: (0..N as u64).map(|i| Fp::from(i * i + offset)).collect(),
+            _marker: PhantomData,
+        };
+        let (fixed, advice) = ([poly(1)], [poly(2), poly(3)]);
+        let evaluate = |ev: &Evaluator<EqAffine>| {
+            let graph = &ev.custom_gates;
+            let mut data = graph.instance();
+            let one = Fp::ONE;
+            (0..N)
+                .map(|idx| {
+                    graph.evaluate(
+                        &mut data,
+                        &fixed,
+                        &advice,
+                        &[],
+                        &[],
+                        &one,
+                        &one,
+                        &one,
+                        &Fp::from(5),
+                        &one,
+                        idx,
+                        1,
+                        N as i32,
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let cs = constraint_system(4);
+        let shared = Evaluator::<EqAffine>::new(&cs);
+        for limit in [0, 1, 2, 4, DEFAULT_EXPRESSION_CACHE_LIMIT] {
+            let limited = Evaluator::<EqAffine>::with_expression_cache_limit(&cs, limit);
+            // The first `limit` intermediates are shared, and the others are recomputed
+            // where they are repeated.
+            let cached = limit.min(shared.custom_gates.num_intermediates);
+            assert_eq!(
+                calculations(&limited)[..cached],
+                calculations(&shared)[..cached]
+            );
+            assert!(
+                limited.custom_gates.num_intermediates >= shared.custom_gates.num_intermediates
+            );
+            assert_eq!(evaluate(&limited), evaluate(&shared), "limit {limit}");
+        }
+    }
+
+    #[test]
+    fn extended_windows_bound_the_live_columns() {
+        const CIRCUITS: usize = 5;
+        const COLUMNS: usize = 3;
+        const PARTS: usize = 4;
+
+        for max in [
+            None,
+            Some(100),
+            Some(15),
+            Some(7),
+            Some(6),
+            Some(3),
+            Some(2),
+            Some(0),
+        ] {
+            // The live extended columns, in `1 / PARTS` of an extended column.
+            let mut live = 0;
+            let mut peak = 0;
+            let mut evaluated = vec![];
+            for window in extended_windows(CIRCUITS, COLUMNS, PARTS, max) {
+                let parts = match window.part {
+                    Some(part) => part..part + 1,
+                    None => 0..PARTS,
+                };
+                for circuit in window.circuits.clone() {
+                    for part in parts.clone() {
+                        live += COLUMNS;
+                        evaluated.push((circuit, part));
+                    }
+                }
+                peak = peak.max(live);
+                assert_eq!(live, window.live_parts(COLUMNS, PARTS));
+                live = 0;
+            }
+
+            let bound = match max {
+                // Every column of a circuit over one part at least.
+                Some(max) => (max * PARTS).max(COLUMNS),
+                None => CIRCUITS * COLUMNS * PARTS,
+            };
+            assert!(peak <= bound, "{peak} live parts with max {max:?}");
+            assert_eq!(
+                evaluated,
+                (0..CIRCUITS)
+                    .flat_map(|circuit| (0..PARTS).map(move |part| (circuit, part)))
+                    .collect::<Vec<_>>()
+            );
+        }
+    }
+}
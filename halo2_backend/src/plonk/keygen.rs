@@ -15,6 +15,27 @@ use halo2_common::plonk::circuit::{Circuit, ConstraintSystem};
 use halo2_common::plonk::Error;
 use halo2_middleware::circuit::CompiledCircuitV2;
 
+/// Options of a circuit that are not part of its constraint system, but change the
+/// proofs for it. They are recorded in the `VerifyingKey`, and in its fingerprint.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CircuitOptions {
+    /// Whether the prover writes commitments to the instance columns in the proof, and
+    /// opens them at the instance queries.
+    ///
+    /// The verifier reads these commitments instead of computing them with a
+    /// multi-scalar multiplication of the size of the domain per column, as it does for
+    /// commitment schemes which query the instance, such as IPA. It still evaluates the
+    /// instance polynomials from the instance values, in a number of field operations
+    /// linear in the number of these values, and checks that the commitments open to
+    /// these evaluations. The proof holds one more point per instance column, a point
+    /// marking the mode, and one more scalar per instance query.
+    ///
+    /// Verifying a proof created in one mode with a key of the other fails with
+    /// [`VerificationError::InstanceModeMismatch`](halo2_common::plonk::VerificationError::InstanceModeMismatch),
+    /// which converts into [`Error::InstanceModeMismatch`].
+    pub commit_instances: bool,
+}
+
 pub(crate) fn create_domain<C, ConcreteCircuit>(
     k: u32,
     #[cfg(feature = "circuit-params")] params: ConcreteCircuit::Params,
@@ -40,7 +61,60 @@ where
     (domain, cs, config)
 }
 
+/// Checks the invariants of a `CompiledCircuitV2` with [`CompiledCircuitV2::validate`], for
+/// circuits that are built directly from the middleware structs.
+fn validate_compiled_circuit<F: Field>(
+    k: u32,
+    circuit: &CompiledCircuitV2<F>,
+) -> Result<(), Error> {
+    circuit.validate(k).map_err(|errors| {
+        Error::Other(
+            errors
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join("; "),
+        )
+    })
+}
+
+/// Checks that the copy constraints of a valid `CompiledCircuitV2` are on usable rows.
+fn check_compiled_circuit<F: Field>(
+    n: usize,
+    cs: &ConstraintSystem<F>,
+    circuit: &CompiledCircuitV2<F>,
+) -> Result<(), Error> {
+    let usable_rows = n - (cs.blinding_factors() + 1);
+    for (left, right) in &circuit.preprocessing.permutation.copies {
+        for cell in [left, right] {
+            if cell.row >= usable_rows {
+                return Err(Error::Other(format!(
+                    "copy constraint on {:?} at row {}, but only rows 0..{} are usable",
+                    cell.column, cell.row, usable_rows
+                )));
+            }
+        }
+    }
+    Ok(())
+}
+
 /// Generate a `VerifyingKey` from an instance of `CompiledCircuit`.
+///
+/// This does not require a `Circuit` implementation, so the circuit can be built directly
+/// from the middleware structs. In that case, the caller must uphold the invariants that
+/// the frontend guarantees:
+/// - there is one fixed column per fixed column of `circuit.cs`, with exactly `params.n()`
+///   values each, including the unusable rows at the end,
+/// - the copy constraints only involve columns of `circuit.cs.permutation`, at usable rows,
+///   that is below `params.n() - (cs.blinding_factors() + 1)`,
+/// - selectors have already been converted into fixed columns, as `ExpressionMid` has no
+///   selectors.
+///
+/// The invariants of the middleware structs are checked with [`CompiledCircuitV2::validate`].
+///
+/// Returns `Error::Other` if the circuit is invalid or the copy constraints are malformed, and
+/// `Error::ColumnNotInPermutation` if a copy constraint involves a column that is not in
+/// the permutation.
 pub fn keygen_vk_v2<'params, C, P>(
     params: &P,
     circuit: &CompiledCircuitV2<C::Scalar>,
@@ -50,6 +124,24 @@ where
     P: Params<'params, C>,
     C::Scalar: FromUniformBytes<64>,
 {
+    keygen_vk_v2_with_options(params, circuit, &CircuitOptions::default())
+}
+
+/// Generate a `VerifyingKey` from an instance of `CompiledCircuit`, with the given
+/// [`CircuitOptions`].
+///
+/// See [`keygen_vk_v2`] for the invariants that `circuit` must uphold.
+pub fn keygen_vk_v2_with_options<'params, C, P>(
+    params: &P,
+    circuit: &CompiledCircuitV2<C::Scalar>,
+    options: &CircuitOptions,
+) -> Result<VerifyingKey<C>, Error>
+where
+    C: CurveAffine,
+    P: Params<'params, C>,
+    C::Scalar: FromUniformBytes<64>,
+{
+    validate_compiled_circuit(params.k(), circuit)?;
     let cs2 = &circuit.cs;
     let cs: ConstraintSystem<C::Scalar> = cs2.clone().into();
     let domain = EvaluationDomain::new(cs.degree() as u32, params.k());
@@ -57,6 +149,7 @@ where
     if (params.n() as usize) < cs.minimum_rows() {
         return Err(Error::not_enough_rows_available(params.k()));
     }
+    check_compiled_circuit(params.n() as usize, &cs, circuit)?;
 
     let permutation_vk = permutation::keygen::Assembly::new_from_assembly_mid(
         params.n() as usize,
@@ -86,10 +179,14 @@ where
         cs,
         Vec::new(),
         false,
+        options.commit_instances,
     ))
 }
 
 /// Generate a `ProvingKey` from a `VerifyingKey` and an instance of `CompiledCircuit`.
+///
+/// `circuit` must be the circuit that `vk` was generated from, and uphold the invariants
+/// documented in [`keygen_vk_v2`].
 pub fn keygen_pk_v2<'params, C, P>(
     params: &P,
     vk: VerifyingKey<C>,
@@ -99,11 +196,13 @@ where
     C: CurveAffine,
     P: Params<'params, C>,
 {
+    validate_compiled_circuit(params.k(), circuit)?;
     let cs = &circuit.cs;
 
     if (params.n() as usize) < vk.cs.minimum_rows() {
         return Err(Error::not_enough_rows_available(params.k()));
     }
+    check_compiled_circuit(params.n() as usize, &vk.cs, circuit)?;
 
     let fixed_polys: Vec<_> = circuit
         .preprocessing
@@ -180,5 +279,6 @@ where
         fixed_cosets,
         permutation: permutation_pk,
         ev,
+        cache: None,
     })
 }
@@ -0,0 +1,137 @@
+use halo2_middleware::ff::{Field, PrimeField};
+use halo2curves::Coordinates;
+
+use super::VerifyingKey;
+use crate::arithmetic::CurveAffine;
+use crate::transcript::base_to_limbs;
+
+/// Version of the ordering of [`VerifyingKey::to_field_elements`], which is bumped on any
+/// change to it.
+pub const VK_LAYOUT_VERSION: u32 = 1;
+
+/// Number of bytes of a coordinate in each limb of [`VerifyingKey::to_field_elements`], so
+/// that a limb fits in any scalar field of at least 128 bits.
+const LIMB_BYTES: usize = 16;
+
+/// Describes the field elements returned by [`VerifyingKey::to_field_elements`], and the
+/// shape of the circuit of the key, so that a verifier circuit can be generated for it.
+///
+/// The field elements are, in order:
+/// 1. [`VK_LAYOUT_VERSION`],
+/// 2. the [`transcript_repr`](VerifyingKey::transcript_repr) of the key,
+/// 3. `k`, and the generator `omega` of the evaluation domain,
+/// 4. the [`num_fixed_commitments`](VkLayout::num_fixed_commitments) commitments to the fixed
+///    columns,
+/// 5. the [`num_permutation_commitments`](VkLayout::num_permutation_commitments) commitments
+///    to the permutation polynomials.
+///
+/// Each commitment is `x` followed by `y`, and each coordinate is its
+/// [`to_repr`](PrimeField::to_repr) split into [`VkLayout::limbs_per_coordinate`]
+/// little-endian limbs of 128 bits, least significant first. The point at infinity has both coordinates zero.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct VkLayout {
+    /// The [`VK_LAYOUT_VERSION`] of the layout.
+    pub version: u32,
+    /// `log2` of the number of rows of the circuit.
+    pub k: u32,
+    /// Number of limbs of each coordinate of a commitment.
+    pub limbs_per_coordinate: usize,
+    /// Number of commitments to fixed columns.
+    pub num_fixed_commitments: usize,
+    /// Number of commitments to permutation polynomials.
+    pub num_permutation_commitments: usize,
+    /// Number of advice columns, each committed to in proofs.
+    pub num_advice_columns: usize,
+    /// Number of instance columns.
+    pub num_instance_columns: usize,
+    /// Whether the instance columns are committed to in proofs.
+    pub commit_instances: bool,
+    /// Number of challenges squeezed during the proof.
+    pub num_challenges: usize,
+    /// Number of lookup arguments.
+    pub num_lookups: usize,
+    /// Number of shuffle arguments.
+    pub num_shuffles: usize,
+    /// Number of pieces of the quotient polynomial `h(X)` committed to in proofs.
+    pub num_quotient_pieces: usize,
+    /// Number of blinding rows at the end of each column.
+    pub blinding_factors: usize,
+}
+
+impl VkLayout {
+    /// Number of field elements encoding a commitment.
+    pub fn commitment_len(&self) -> usize {
+        2 * self.limbs_per_coordinate
+    }
+
+    /// Offset of the first fixed commitment in the field elements.
+    pub fn fixed_commitments_offset(&self) -> usize {
+        4
+    }
+
+    /// Offset of the first permutation commitment in the field elements.
+    pub fn permutation_commitments_offset(&self) -> usize {
+        self.fixed_commitments_offset() + self.num_fixed_commitments * self.commitment_len()
+    }
+
+    /// Total number of field elements.
+    pub fn num_elements(&self) -> usize {
+        self.permutation_commitments_offset()
+            + self.num_permutation_commitments * self.commitment_len()
+    }
+}
+
+impl<C: CurveAffine> VerifyingKey<C> {
+    /// Returns the [`VkLayout`] of [`VerifyingKey::to_field_elements`].
+    pub fn io_layout(&self) -> VkLayout {
+        VkLayout {
+            version: VK_LAYOUT_VERSION,
+            k: self.domain.k(),
+            limbs_per_coordinate: limbs_per_coordinate::<C>(),
+            num_fixed_commitments: self.fixed_commitments.len(),
+            num_permutation_commitments: self.permutation.commitments().len(),
+            num_advice_columns: self.cs.num_advice_columns(),
+            num_instance_columns: self.cs.num_instance_columns(),
+            commit_instances: self.commit_instances,
+            num_challenges: self.cs.num_challenges(),
+            num_lookups: self.cs.lookups().len(),
+            num_shuffles: self.cs.shuffles().len(),
+            num_quotient_pieces: self.domain.get_quotient_poly_degree(),
+            blinding_factors: self.cs.blinding_factors(),
+        }
+    }
+
+    /// Returns the contents of this key as scalars, to be absorbed by an in-circuit
+    /// transcript of a recursive verifier. See [`VkLayout`] for their order.
+    pub fn to_field_elements(&self) -> Vec<C::Scalar> {
+        let layout = self.io_layout();
+        let mut elements = Vec::with_capacity(layout.num_elements());
+        elements.push(C::Scalar::from(u64::from(layout.version)));
+        elements.push(self.transcript_repr);
+        elements.push(C::Scalar::from(u64::from(layout.k)));
+        elements.push(self.domain.get_omega());
+        for commitment in self
+            .fixed_commitments
+            .iter()
+            .chain(self.permutation.commitments())
+        {
+            push_commitment(&mut elements, commitment);
+        }
+        debug_assert_eq!(elements.len(), layout.num_elements());
+        elements
+    }
+}
+
+fn limbs_per_coordinate<C: CurveAffine>() -> usize {
+    let repr = <C::Base as PrimeField>::Repr::default();
+    (repr.as_ref().len() + LIMB_BYTES - 1) / LIMB_BYTES
+}
+
+fn push_commitment<C: CurveAffine>(elements: &mut Vec<C::Scalar>, commitment: &C) {
+    let coordinates: Option<Coordinates<C>> = Option::from(commitment.coordinates());
+    let (x, y) = coordinates.map_or((C::Base::ZERO, C::Base::ZERO), |coordinates| {
+        (*coordinates.x(), *coordinates.y())
+    });
+    elements.extend(base_to_limbs::<C>(&x));
+    elements.extend(base_to_limbs::<C>(&y));
+}
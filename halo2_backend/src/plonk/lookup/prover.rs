@@ -1,11 +1,13 @@
 use super::super::ProvingKey;
 use super::Argument;
+use crate::plonk::blinder::{Blinder, ProductBlinds};
+use crate::plonk::cache::ExpressionValues;
 use crate::plonk::evaluation::evaluate;
 use crate::{
-    arithmetic::{eval_polynomial, parallelize, CurveAffine},
+    arithmetic::{eval_polynomial, parallelize, prefix_product, CurveAffine},
     poly::{
         commitment::{Blind, Params},
-        Coeff, EvaluationDomain, LagrangeCoeff, Polynomial, ProverQuery,
+        Coeff, EvaluationDomain, LagrangeCoeff, Polynomial, PolynomialEngine, ProverQuery,
     },
     transcript::{EncodedChallenge, TranscriptWrite},
 };
@@ -20,6 +22,7 @@ use halo2_middleware::ff::WithSmallOrderMulGroup;
 use halo2_middleware::poly::Rotation;
 use rand_core::RngCore;
 use std::{
+    borrow::Cow,
     collections::BTreeMap,
     iter,
     ops::{Mul, MulAssign},
@@ -35,6 +38,32 @@ pub(in crate::plonk) struct Permuted<C: CurveAffine> {
     permuted_table_expression: Polynomial<C::Scalar, LagrangeCoeff>,
     permuted_table_poly: Polynomial<C::Scalar, Coeff>,
     permuted_table_blind: Blind<C::Scalar>,
+    permuted_input_commitment: C,
+    permuted_table_commitment: C,
+}
+
+/// The compressed and permuted columns of a lookup argument, which are computed in
+/// parallel for all the lookups, then committed in order by [`PermutedValues::commit`].
+#[derive(Debug)]
+pub(in crate::plonk) struct PermutedValues<C: CurveAffine> {
+    compressed_input_expression: Polynomial<C::Scalar, LagrangeCoeff>,
+    permuted_input_expression: Polynomial<C::Scalar, LagrangeCoeff>,
+    compressed_table_expression: Polynomial<C::Scalar, LagrangeCoeff>,
+    permuted_table_expression: Polynomial<C::Scalar, LagrangeCoeff>,
+    input_blind: Blind<C::Scalar>,
+    table_blind: Blind<C::Scalar>,
+}
+
+/// The grand product column of a lookup argument, which is computed in parallel for all
+/// the lookups, then committed in order by [`Product::commit`].
+#[derive(Debug)]
+pub(in crate::plonk) struct Product<C: CurveAffine> {
+    permuted_input_poly: Polynomial<C::Scalar, Coeff>,
+    permuted_input_blind: Blind<C::Scalar>,
+    permuted_table_poly: Polynomial<C::Scalar, Coeff>,
+    permuted_table_blind: Blind<C::Scalar>,
+    z: Polynomial<C::Scalar, LagrangeCoeff>,
+    blind: Blind<C::Scalar>,
 }
 
 #[derive(Debug)]
@@ -45,6 +74,34 @@ pub(in crate::plonk) struct Committed<C: CurveAffine> {
     permuted_table_blind: Blind<C::Scalar>,
     pub(in crate::plonk) product_poly: Polynomial<C::Scalar, Coeff>,
     product_blind: Blind<C::Scalar>,
+    product_commitment: C,
+}
+
+/// The blinding factors of the permuted columns of a lookup argument, in the order they are
+/// sampled: the blinding rows of the permuted input column, those of the permuted table
+/// column, and the blinds of the commitments to the two columns.
+#[derive(Debug)]
+pub(in crate::plonk) struct PermutedBlinds<F> {
+    input_rows: Vec<F>,
+    table_rows: Vec<F>,
+    input_blind: Blind<F>,
+    table_blind: Blind<F>,
+}
+
+impl<F: Field> PermutedBlinds<F> {
+    pub(in crate::plonk) fn sample<R: RngCore>(
+        blinding_factors: usize,
+        rng: &mut Blinder<R>,
+    ) -> Self {
+        let input_rows = (0..(blinding_factors + 1)).map(|_| rng.blind()).collect();
+        let table_rows = (0..(blinding_factors + 1)).map(|_| rng.blind()).collect();
+        PermutedBlinds {
+            input_rows,
+            table_rows,
+            input_blind: Blind(rng.blind()),
+            table_blind: Blind(rng.blind()),
+        }
+    }
 }
 
 pub(in crate::plonk) struct Evaluated<C: CurveAffine> {
@@ -57,128 +114,155 @@ pub(in crate::plonk) struct Evaluated<C: CurveAffine> {
 ///   and S_compressed = \theta^{m-1} S_0 + theta^{m-2} S_1 + ... + \theta S_{m-2} + S_{m-1},
 /// - permutes A_compressed and S_compressed using permute_expression_pair() helper,
 ///   obtaining A' and S', and
-/// - constructs Permuted<C> struct using permuted_input_value = A', and
+/// - constructs PermutedValues<C> struct using permuted_input_value = A', and
 ///   permuted_table_expression = S'.
-/// The Permuted<C> struct is used to update the Lookup, and is then returned.
+/// The PermutedValues<C> struct is committed by [`PermutedValues::commit`].
+///
+/// This doesn't use the parameters, so that several lookups can be computed in parallel.
 #[allow(clippy::too_many_arguments)]
-pub(in crate::plonk) fn lookup_commit_permuted<
-    'a,
-    'params: 'a,
-    F: WithSmallOrderMulGroup<3>,
-    C,
-    P: Params<'params, C>,
-    E: EncodedChallenge<C>,
-    R: RngCore,
-    T: TranscriptWrite<C, E>,
->(
+pub(in crate::plonk) fn lookup_permute<'a, F: WithSmallOrderMulGroup<3>, C>(
     arg: &Argument<F>,
+    cached: Option<&(ExpressionValues<F>, ExpressionValues<F>)>,
     pk: &ProvingKey<C>,
-    params: &P,
     domain: &EvaluationDomain<C::Scalar>,
     theta: ChallengeTheta<C>,
     advice_values: &'a [Polynomial<C::Scalar, LagrangeCoeff>],
     fixed_values: &'a [Polynomial<C::Scalar, LagrangeCoeff>],
     instance_values: &'a [Polynomial<C::Scalar, LagrangeCoeff>],
     challenges: &'a [C::Scalar],
-    mut rng: R,
-    transcript: &mut T,
-) -> Result<Permuted<C>, Error>
+    blinds: PermutedBlinds<C::Scalar>,
+) -> Result<PermutedValues<C>, Error>
 where
     C: CurveAffine<ScalarExt = F>,
     C::Curve: Mul<F, Output = C::Curve> + MulAssign<F>,
 {
-    // Closure to get values of expressions and compress them
-    let compress_expressions = |expressions: &[Expression<C::Scalar>]| {
-        let compressed_expression = expressions
-            .iter()
-            .map(|expression| {
-                pk.vk.domain.lagrange_from_vec(evaluate(
-                    expression,
-                    params.n() as usize,
-                    1,
-                    fixed_values,
-                    advice_values,
-                    instance_values,
-                    challenges,
-                ))
-            })
-            .fold(domain.empty_lagrange(), |acc, expression| {
-                acc * *theta + &expression
-            });
-        compressed_expression
-    };
+    let n = 1 << domain.k();
+
+    // Closure to get values of expressions, or their cached values, and compress them
+    let compress_expressions =
+        |expressions: &[Expression<C::Scalar>], cached: Option<&ExpressionValues<C::Scalar>>| {
+            let compressed_expression = expressions
+                .iter()
+                .enumerate()
+                .map(
+                    |(i, expression)| match cached.and_then(|cached| cached[i].as_ref()) {
+                        Some(values) => Cow::Borrowed(values),
+                        None => Cow::Owned(pk.vk.domain.lagrange_from_vec(evaluate(
+                            expression,
+                            n,
+                            1,
+                            fixed_values,
+                            advice_values,
+                            instance_values,
+                            challenges,
+                        ))),
+                    },
+                )
+                .fold(domain.empty_lagrange(), |acc, expression| {
+                    acc * *theta + &*expression
+                });
+            compressed_expression
+        };
 
     // Get values of input expressions involved in the lookup and compress them
-    let compressed_input_expression = compress_expressions(&arg.input_expressions);
+    let compressed_input_expression =
+        compress_expressions(&arg.input_expressions, cached.map(|cached| &cached.0));
 
     // Get values of table expressions involved in the lookup and compress them
-    let compressed_table_expression = compress_expressions(&arg.table_expressions);
+    let compressed_table_expression =
+        compress_expressions(&arg.table_expressions, cached.map(|cached| &cached.1));
 
     // Permute compressed (InputExpression, TableExpression) pair
     let (permuted_input_expression, permuted_table_expression) = permute_expression_pair(
         pk,
-        params,
         domain,
-        &mut rng,
+        blinds.input_rows,
+        blinds.table_rows,
         &compressed_input_expression,
         &compressed_table_expression,
     )?;
 
-    // Closure to construct commitment to vector of values
-    let mut commit_values = |values: &Polynomial<C::Scalar, LagrangeCoeff>| {
-        let poly = pk.vk.domain.lagrange_to_coeff(values.clone());
-        let blind = Blind(C::Scalar::random(&mut rng));
-        let commitment = params.commit_lagrange(values, blind).to_affine();
-        (poly, blind, commitment)
-    };
-
-    // Commit to permuted input expression
-    let (permuted_input_poly, permuted_input_blind, permuted_input_commitment) =
-        commit_values(&permuted_input_expression);
-
-    // Commit to permuted table expression
-    let (permuted_table_poly, permuted_table_blind, permuted_table_commitment) =
-        commit_values(&permuted_table_expression);
-
-    // Hash permuted input commitment
-    transcript.write_point(permuted_input_commitment)?;
-
-    // Hash permuted table commitment
-    transcript.write_point(permuted_table_commitment)?;
-
-    Ok(Permuted {
+    Ok(PermutedValues {
         compressed_input_expression,
         permuted_input_expression,
-        permuted_input_poly,
-        permuted_input_blind,
         compressed_table_expression,
         permuted_table_expression,
-        permuted_table_poly,
-        permuted_table_blind,
+        input_blind: blinds.input_blind,
+        table_blind: blinds.table_blind,
     })
 }
 
+impl<C: CurveAffine> PermutedValues<C> {
+    /// Commits to the permuted input and table columns.
+    pub(in crate::plonk) fn commit<'params, P: Params<'params, C>>(
+        self,
+        pk: &ProvingKey<C>,
+        params: &P,
+        engine: &dyn PolynomialEngine<C>,
+    ) -> Permuted<C> {
+        // Closure to construct commitment to vector of values
+        let commit_values = |values: &Polynomial<C::Scalar, LagrangeCoeff>, blind| {
+            let poly = pk
+                .vk
+                .domain
+                .lagrange_to_coeff_with_engine(engine, values.clone());
+            let commitment = params
+                .commit_lagrange_with_engine(engine, values, blind)
+                .to_affine();
+            (poly, commitment)
+        };
+
+        // Commit to permuted input expression
+        let permuted_input_blind = self.input_blind;
+        let (permuted_input_poly, permuted_input_commitment) =
+            commit_values(&self.permuted_input_expression, permuted_input_blind);
+
+        // Commit to permuted table expression
+        let permuted_table_blind = self.table_blind;
+        let (permuted_table_poly, permuted_table_commitment) =
+            commit_values(&self.permuted_table_expression, permuted_table_blind);
+
+        Permuted {
+            compressed_input_expression: self.compressed_input_expression,
+            permuted_input_expression: self.permuted_input_expression,
+            permuted_input_poly,
+            permuted_input_blind,
+            compressed_table_expression: self.compressed_table_expression,
+            permuted_table_expression: self.permuted_table_expression,
+            permuted_table_poly,
+            permuted_table_blind,
+            permuted_input_commitment,
+            permuted_table_commitment,
+        }
+    }
+}
+
 impl<C: CurveAffine> Permuted<C> {
+    /// Hashes the commitments to the permuted input and table columns.
+    pub(in crate::plonk) fn write_commitments<E: EncodedChallenge<C>, T: TranscriptWrite<C, E>>(
+        &self,
+        transcript: &mut T,
+    ) -> Result<(), Error> {
+        transcript.write_point(self.permuted_input_commitment)?;
+        transcript.write_point(self.permuted_table_commitment)?;
+        Ok(())
+    }
+
     /// Given a Lookup with input expressions, table expressions, and the permuted
     /// input expression and permuted table expression, this method constructs the
     /// grand product polynomial over the lookup. The grand product polynomial
-    /// is used to populate the Product<C> struct. The Product<C> struct is
-    /// added to the Lookup and finally returned by the method.
-    pub(in crate::plonk) fn commit_product<
-        'params,
-        P: Params<'params, C>,
-        E: EncodedChallenge<C>,
-        R: RngCore,
-        T: TranscriptWrite<C, E>,
-    >(
+    /// is used to populate the Product<C> struct, which is committed by
+    /// [`Product::commit`].
+    ///
+    /// This doesn't use the parameters, so that several lookups can be computed in parallel.
+    pub(in crate::plonk) fn product(
         self,
         pk: &ProvingKey<C>,
-        params: &P,
         beta: ChallengeBeta<C>,
         gamma: ChallengeGamma<C>,
-        mut rng: R,
-        transcript: &mut T,
-    ) -> Result<Committed<C>, Error> {
+        blinds: ProductBlinds<C::Scalar>,
+    ) -> Product<C> {
+        let n = 1 << pk.vk.domain.k();
         let blinding_factors = pk.vk.cs.blinding_factors();
         // Goal is to compute the products of fractions
         //
@@ -191,7 +275,7 @@ impl<C: CurveAffine> Permuted<C> {
         // s_j(X) is the jth table expression in this lookup,
         // s'(X) is the compression of the permuted table expressions,
         // and i is the ith row of the expression.
-        let mut lookup_product = vec![C::Scalar::ZERO; params.n() as usize];
+        let mut lookup_product = vec![C::Scalar::ZERO; n];
         // Denominator uses the permuted input expression and permuted table expression
         parallelize(&mut lookup_product, |lookup_product, start| {
             for ((lookup_product, permuted_input_value), permuted_table_value) in lookup_product
@@ -233,20 +317,16 @@ impl<C: CurveAffine> Permuted<C> {
         // and i is the ith row of the expression.
 
         // Compute the evaluations of the lookup product polynomial
-        // over our domain, starting with z[0] = 1
-        let z = iter::once(C::Scalar::ONE)
-            .chain(lookup_product)
-            .scan(C::Scalar::ONE, |state, cur| {
-                *state *= &cur;
-                Some(*state)
-            })
-            // Take all rows including the "last" row which should
-            // be a boolean (and ideally 1, else soundness is broken)
-            .take(params.n() as usize - blinding_factors)
-            // Chain random blinding factors.
-            .chain((0..blinding_factors).map(|_| C::Scalar::random(&mut rng)))
-            .collect::<Vec<_>>();
-        assert_eq!(z.len(), params.n() as usize);
+        // over our domain, starting with z[0] = 1, on all rows including
+        // the "last" row which should be a boolean (and ideally 1, else
+        // soundness is broken)
+        let mut z = Vec::with_capacity(n);
+        z.push(C::Scalar::ONE);
+        z.extend_from_slice(&lookup_product[..n - blinding_factors - 1]);
+        prefix_product(&mut z);
+        // Chain random blinding factors.
+        z.extend(blinds.rows);
+        assert_eq!(z.len(), n);
         let z = pk.vk.domain.lagrange_from_vec(z);
 
         #[cfg(feature = "sanity-checks")]
@@ -254,7 +334,7 @@ impl<C: CurveAffine> Permuted<C> {
         // It can be used for debugging purposes.
         {
             // While in Lagrange basis, check that product is correctly constructed
-            let u = (params.n() as usize) - (blinding_factors + 1);
+            let u = n - (blinding_factors + 1);
 
             // l_0(X) * (1 - z(X)) = 0
             assert_eq!(z[0], C::Scalar::ONE);
@@ -287,25 +367,52 @@ impl<C: CurveAffine> Permuted<C> {
             assert_eq!(z[u], C::Scalar::ONE);
         }
 
-        let product_blind = Blind(C::Scalar::random(rng));
-        let product_commitment = params.commit_lagrange(&z, product_blind).to_affine();
-        let z = pk.vk.domain.lagrange_to_coeff(z);
-
-        // Hash product commitment
-        transcript.write_point(product_commitment)?;
+        Product {
+            permuted_input_poly: self.permuted_input_poly,
+            permuted_input_blind: self.permuted_input_blind,
+            permuted_table_poly: self.permuted_table_poly,
+            permuted_table_blind: self.permuted_table_blind,
+            z,
+            blind: blinds.blind,
+        }
+    }
+}
 
-        Ok(Committed::<C> {
+impl<C: CurveAffine> Product<C> {
+    /// Commits to the grand product column.
+    pub(in crate::plonk) fn commit<'params, P: Params<'params, C>>(
+        self,
+        pk: &ProvingKey<C>,
+        params: &P,
+        engine: &dyn PolynomialEngine<C>,
+    ) -> Committed<C> {
+        let product_commitment = params
+            .commit_lagrange_with_engine(engine, &self.z, self.blind)
+            .to_affine();
+        let z = pk.vk.domain.lagrange_to_coeff_with_engine(engine, self.z);
+
+        Committed::<C> {
             permuted_input_poly: self.permuted_input_poly,
             permuted_input_blind: self.permuted_input_blind,
             permuted_table_poly: self.permuted_table_poly,
             permuted_table_blind: self.permuted_table_blind,
             product_poly: z,
-            product_blind,
-        })
+            product_blind: self.blind,
+            product_commitment,
+        }
     }
 }
 
 impl<C: CurveAffine> Committed<C> {
+    /// Hashes the commitment to the product column.
+    pub(in crate::plonk) fn write_commitment<E: EncodedChallenge<C>, T: TranscriptWrite<C, E>>(
+        &self,
+        transcript: &mut T,
+    ) -> Result<(), Error> {
+        transcript.write_point(self.product_commitment)?;
+        Ok(())
+    }
+
     pub(in crate::plonk) fn evaluate<E: EncodedChallenge<C>, T: TranscriptWrite<C, E>>(
         self,
         pk: &ProvingKey<C>,
@@ -388,16 +495,17 @@ type ExpressionPair<F> = (Polynomial<F, LagrangeCoeff>, Polynomial<F, LagrangeCo
 /// - the first row in a sequence of like values in A' is the row
 ///   that has the corresponding value in S'.
 /// This method returns (A', S') if no errors are encountered.
-fn permute_expression_pair<'params, C: CurveAffine, P: Params<'params, C>, R: RngCore>(
+fn permute_expression_pair<C: CurveAffine>(
     pk: &ProvingKey<C>,
-    params: &P,
     domain: &EvaluationDomain<C::Scalar>,
-    mut rng: R,
+    input_blinding_rows: Vec<C::Scalar>,
+    table_blinding_rows: Vec<C::Scalar>,
     input_expression: &Polynomial<C::Scalar, LagrangeCoeff>,
     table_expression: &Polynomial<C::Scalar, LagrangeCoeff>,
 ) -> Result<ExpressionPair<C::Scalar>, Error> {
+    let n = 1 << domain.k();
     let blinding_factors = pk.vk.cs.blinding_factors();
-    let usable_rows = params.n() as usize - (blinding_factors + 1);
+    let usable_rows = n - (blinding_factors + 1);
 
     let mut permuted_input_expression: Vec<C::Scalar> = input_expression.to_vec();
     permuted_input_expression.truncate(usable_rows);
@@ -447,11 +555,10 @@ fn permute_expression_pair<'params, C: CurveAffine, P: Params<'params, C>, R: Rn
     }
     assert!(repeated_input_rows.is_empty());
 
-    permuted_input_expression
-        .extend((0..(blinding_factors + 1)).map(|_| C::Scalar::random(&mut rng)));
-    permuted_table_coeffs.extend((0..(blinding_factors + 1)).map(|_| C::Scalar::random(&mut rng)));
-    assert_eq!(permuted_input_expression.len(), params.n() as usize);
-    assert_eq!(permuted_table_coeffs.len(), params.n() as usize);
+    permuted_input_expression.extend(input_blinding_rows);
+    permuted_table_coeffs.extend(table_blinding_rows);
+    assert_eq!(permuted_input_expression.len(), n);
+    assert_eq!(permuted_table_coeffs.len(), n);
 
     #[cfg(feature = "sanity-checks")]
     {
@@ -2,7 +2,10 @@
 
 use crate::{
     arithmetic::CurveAffine,
-    helpers::{polynomial_slice_byte_length, read_polynomial_vec, write_polynomial_slice},
+    helpers::{
+        polynomial_slice_byte_length, polynomial_vec_from_slice, read_polynomial_vec,
+        write_polynomial_slice,
+    },
     poly::{Coeff, ExtendedLagrangeCoeff, LagrangeCoeff, Polynomial},
     SerdeFormat,
 };
@@ -62,7 +65,7 @@ impl<C: CurveAffine> VerifyingKey<C> {
 /// The proving key for a single permutation argument.
 #[derive(Clone, Debug)]
 pub(crate) struct ProvingKey<C: CurveAffine> {
-    permutations: Vec<Polynomial<C::Scalar, LagrangeCoeff>>,
+    pub(super) permutations: Vec<Polynomial<C::Scalar, LagrangeCoeff>>,
     polys: Vec<Polynomial<C::Scalar, Coeff>>,
     pub(super) cosets: Vec<Polynomial<C::Scalar, ExtendedLagrangeCoeff>>,
 }
@@ -83,6 +86,19 @@ where
         })
     }
 
+    /// Parses the proving key for a single permutation argument from the front of `bytes`,
+    /// and advances `bytes` past it. See [`Self::read`].
+    pub(super) fn from_slice(bytes: &mut &[u8], format: SerdeFormat) -> io::Result<Self> {
+        let permutations = polynomial_vec_from_slice(bytes, format)?;
+        let polys = polynomial_vec_from_slice(bytes, format)?;
+        let cosets = polynomial_vec_from_slice(bytes, format)?;
+        Ok(ProvingKey {
+            permutations,
+            polys,
+            cosets,
+        })
+    }
+
     /// Writes proving key for a single permutation argument to buffer using `Polynomial::write`.  
     pub(super) fn write<W: io::Write>(
         &self,
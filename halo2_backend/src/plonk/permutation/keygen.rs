@@ -11,7 +11,7 @@ use crate::{
 };
 use halo2_common::plonk::Error;
 use halo2_middleware::circuit::ColumnMid;
-use halo2_middleware::permutation::{ArgumentV2, AssemblyMid};
+use halo2_middleware::permutation::{ArgumentV2, AssemblyMid, CycleStats};
 
 // NOTE: Temporarily disabled thread-safe-region feature.  Regions are a frontend concept, so the
 // thread-safe support for them should be only in the frontend package.
@@ -38,15 +38,15 @@ pub struct Assembly {
     aux: Vec<Vec<(usize, usize)>>,
     /// More aux data
     sizes: Vec<Vec<usize>>,
+    /// Number of copies done, including the redundant ones.
+    copies: usize,
 }
 
 // #[cfg(not(feature = "thread-safe-region"))]
 impl Assembly {
-    pub(crate) fn new_from_assembly_mid(
-        n: usize,
-        p: &ArgumentV2,
-        a: &AssemblyMid,
-    ) -> Result<Self, Error> {
+    /// Builds the permutation of the copy constraints of `a` between the columns of `p`, in
+    /// a circuit with `n` rows.
+    pub fn new_from_assembly_mid(n: usize, p: &ArgumentV2, a: &AssemblyMid) -> Result<Self, Error> {
         let mut assembly = Self::new(n, &p.clone().into());
         for copy in &a.copies {
             assembly.copy(copy.0.column, copy.0.row, copy.1.column, copy.1.row)?;
@@ -71,6 +71,7 @@ impl Assembly {
             mapping: columns.clone(),
             aux: columns,
             sizes: vec![vec![1usize; n]; p.columns.len()],
+            copies: 0,
         }
     }
 
@@ -98,6 +99,7 @@ impl Assembly {
         {
             return Err(Error::BoundsFailure);
         }
+        self.copies += 1;
 
         // See book/src/design/permutation.md for a description of this algorithm.
 
@@ -131,6 +133,67 @@ impl Assembly {
         Ok(())
     }
 
+    /// Returns an iterator over the cycles of the permutation with more than one cell, which
+    /// are the equality classes of the copied `(column, row)` cells. Each cycle is listed
+    /// from its distinguished cell, in the order of the permutation, and only the cycle
+    /// being returned is allocated.
+    pub fn cycles(&self) -> impl Iterator<Item = Vec<(ColumnMid, usize)>> + '_ {
+        self.aux.iter().enumerate().flat_map(move |(i, column)| {
+            column
+                .iter()
+                .enumerate()
+                .filter(move |&(j, aux)| *aux == (i, j) && self.sizes[i][j] > 1)
+                .map(move |(j, _)| {
+                    let mut cycle = Vec::with_capacity(self.sizes[i][j]);
+                    let mut cell = (i, j);
+                    loop {
+                        cycle.push((self.columns[cell.0], cell.1));
+                        cell = self.mapping[cell.0][cell.1];
+                        if cell == (i, j) {
+                            break cycle;
+                        }
+                    }
+                })
+        })
+    }
+
+    /// Returns the cycles of the permutation with more than one cell, as listed by
+    /// [`Assembly::cycles`].
+    pub fn equality_classes(&self) -> Vec<Vec<(ColumnMid, usize)>> {
+        self.cycles().collect()
+    }
+
+    /// Returns the summary statistics of the cycles of the permutation.
+    pub fn cycle_stats(&self) -> CycleStats {
+        let mut stats = CycleStats {
+            copies: self.copies,
+            ..CycleStats::default()
+        };
+        for (i, column) in self.aux.iter().enumerate() {
+            for (j, aux) in column.iter().enumerate() {
+                if *aux == (i, j) && self.sizes[i][j] > 1 {
+                    stats.cycles += 1;
+                    stats.largest_cycle = stats.largest_cycle.max(self.sizes[i][j]);
+                }
+            }
+        }
+        stats
+    }
+
+    /// Returns whether the cells `a` and `b` are in the same cycle, that is whether they are
+    /// constrained to be equal. Cells outside of the permutation are only equal to
+    /// themselves.
+    pub fn contains(&self, a: (ColumnMid, usize), b: (ColumnMid, usize)) -> bool {
+        let aux = |(column, row): (ColumnMid, usize)| {
+            let i = self.columns.iter().position(|c| *c == column)?;
+            self.aux[i].get(row).copied()
+        };
+        match (aux(a), aux(b)) {
+            (Some(aux_a), Some(aux_b)) => aux_a == aux_b,
+            _ => a == b,
+        }
+    }
+
     pub(crate) fn build_vk<'params, C: CurveAffine, P: Params<'params, C>>(
         self,
         params: &P,
@@ -1,3 +1,4 @@
+use crate::plonk::blinder::Blinder;
 use group::{
     ff::{BatchInvert, Field},
     Curve,
@@ -12,7 +13,7 @@ use crate::{
     plonk::{self, permutation::ProvingKey},
     poly::{
         commitment::{Blind, Params},
-        Coeff, ExtendedLagrangeCoeff, LagrangeCoeff, Polynomial, ProverQuery,
+        Coeff, ExtendedLagrangeCoeff, LagrangeCoeff, Polynomial, PolynomialEngine, ProverQuery,
     },
     transcript::{EncodedChallenge, TranscriptWrite},
 };
@@ -57,6 +58,7 @@ pub(in crate::plonk) fn permutation_commit<
 >(
     arg: &Argument,
     params: &P,
+    engine: &dyn PolynomialEngine<C>,
     pk: &plonk::ProvingKey<C>,
     pkey: &ProvingKey<C>,
     advice: &[Polynomial<C::Scalar, LagrangeCoeff>],
@@ -64,7 +66,7 @@ pub(in crate::plonk) fn permutation_commit<
     instance: &[Polynomial<C::Scalar, LagrangeCoeff>],
     beta: ChallengeBeta<C>,
     gamma: ChallengeGamma<C>,
-    mut rng: R,
+    rng: &mut Blinder<R>,
     transcript: &mut T,
 ) -> Result<Committed<C>, Error> {
     let domain = &pk.vk.domain;
@@ -85,11 +87,19 @@ pub(in crate::plonk) fn permutation_commit<
 
     let mut sets = vec![];
 
+    // The cached values of \delta^j \omega^i for each set, if any.
+    let mut identities = pk
+        .cache
+        .as_ref()
+        .map(|cache| cache.permutation_identities.chunks(chunk_len));
+
     for (columns, permutations) in arg
         .columns
         .chunks(chunk_len)
         .zip(pkey.permutations.chunks(chunk_len))
     {
+        let identities = identities.as_mut().and_then(Iterator::next);
+
         // Goal is to compute the products of fractions
         //
         // (p_j(\omega^i) + \delta^j \omega^i \beta + \gamma) /
@@ -123,24 +133,36 @@ pub(in crate::plonk) fn permutation_commit<
 
         // Iterate over each column again, this time finishing the computation
         // of the entire fraction by computing the numerators
-        for &column in columns.iter() {
+        for (j, &column) in columns.iter().enumerate() {
             let omega = domain.get_omega();
             let values = match column.column_type() {
                 Any::Advice(_) => advice,
                 Any::Fixed => fixed,
                 Any::Instance => instance,
             };
-            parallelize(&mut modified_values, |modified_values, start| {
-                let mut deltaomega = deltaomega * omega.pow_vartime([start as u64, 0, 0, 0]);
-                for (modified_values, value) in modified_values
-                    .iter_mut()
-                    .zip(values[column.index()][start..].iter())
-                {
-                    // Multiply by p_j(\omega^i) + \delta^j \omega^i \beta
-                    *modified_values *= deltaomega * *beta + *gamma + value;
-                    deltaomega *= &omega;
-                }
-            });
+            match identities {
+                Some(identities) => parallelize(&mut modified_values, |modified_values, start| {
+                    for ((modified_values, value), deltaomega) in modified_values
+                        .iter_mut()
+                        .zip(values[column.index()][start..].iter())
+                        .zip(identities[j][start..].iter())
+                    {
+                        // Multiply by p_j(\omega^i) + \delta^j \omega^i \beta
+                        *modified_values *= *deltaomega * *beta + *gamma + value;
+                    }
+                }),
+                None => parallelize(&mut modified_values, |modified_values, start| {
+                    let mut deltaomega = deltaomega * omega.pow_vartime([start as u64, 0, 0, 0]);
+                    for (modified_values, value) in modified_values
+                        .iter_mut()
+                        .zip(values[column.index()][start..].iter())
+                    {
+                        // Multiply by p_j(\omega^i) + \delta^j \omega^i \beta
+                        *modified_values *= deltaomega * *beta + *gamma + value;
+                        deltaomega *= &omega;
+                    }
+                }),
+            }
             deltaomega *= &<C::Scalar as PrimeField>::DELTA;
         }
 
@@ -165,19 +187,20 @@ pub(in crate::plonk) fn permutation_commit<
         let mut z = domain.lagrange_from_vec(z);
         // Set blinding factors
         for z in &mut z[params.n() as usize - blinding_factors..] {
-            *z = C::Scalar::random(&mut rng);
+            *z = rng.blind();
         }
         // Set new last_z
         last_z = z[params.n() as usize - (blinding_factors + 1)];
 
-        let blind = Blind(C::Scalar::random(&mut rng));
+        let blind = Blind(rng.blind());
 
-        let permutation_product_commitment_projective = params.commit_lagrange(&z, blind);
+        let permutation_product_commitment_projective =
+            params.commit_lagrange_with_engine(engine, &z, blind);
         let permutation_product_blind = blind;
-        let z = domain.lagrange_to_coeff(z);
+        let z = domain.lagrange_to_coeff_with_engine(engine, z);
         let permutation_product_poly = z.clone();
 
-        let permutation_product_coset = domain.coeff_to_extended(z.clone());
+        let permutation_product_coset = domain.coeff_to_extended_with_engine(engine, z.clone());
 
         let permutation_product_commitment = permutation_product_commitment_projective.to_affine();
 
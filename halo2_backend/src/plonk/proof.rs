@@ -0,0 +1,79 @@
+//! Helpers to tie a serialized proof to the verifying key it was created for.
+//!
+//! A proof with a header is the raw proof bytes prefixed by:
+//! - `k`, as a `u32` in little-endian,
+//! - the verifying key fingerprint, which is the canonical byte representation of
+//!   [`VerifyingKey::transcript_repr`].
+//!
+//! The raw proof format is unchanged; the header is only added and checked by
+//! [`write_with_header`] and [`read_with_header`].
+//!
+//! Since [`CircuitOptions::commit_instances`](super::CircuitOptions::commit_instances) is
+//! part of the fingerprint, the header also tells whether the proof was created with
+//! committed or evaluated instance columns.
+
+use std::io;
+
+use group::ff::{FromUniformBytes, PrimeField};
+use halo2_common::arithmetic::CurveAffine;
+
+use super::{Error, VerifyingKey};
+
+/// Returns the lowercase hex encoding of `bytes`.
+pub(crate) fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+/// Writes `proof`, created for `vk`, prefixed by a header identifying `vk`.
+pub fn write_with_header<C: CurveAffine, W: io::Write>(
+    vk: &VerifyingKey<C>,
+    proof: &[u8],
+    writer: &mut W,
+) -> io::Result<()> {
+    writer.write_all(&vk.get_domain().k().to_le_bytes())?;
+    writer.write_all(vk.transcript_repr().to_repr().as_ref())?;
+    writer.write_all(proof)
+}
+
+/// Reads a proof written by [`write_with_header`], and returns the raw proof bytes.
+///
+/// Returns [`Error::InstanceModeMismatch`] if the proof was written for `vk` generated
+/// with the other choice of `commit_instances`, [`Error::VerifyingKeyMismatch`] if it was
+/// written for a different verifying key than `vk`, and [`Error::Transcript`] if the
+/// header cannot be read.
+pub fn read_with_header<C: CurveAffine, R: io::Read>(
+    vk: &VerifyingKey<C>,
+    reader: &mut R,
+) -> Result<Vec<u8>, Error>
+where
+    C::Scalar: FromUniformBytes<64>,
+{
+    let mut k = [0u8; 4];
+    reader.read_exact(&mut k)?;
+    let k = u32::from_le_bytes(k);
+
+    let expected = vk.transcript_repr().to_repr();
+    let mut found = <C::Scalar as PrimeField>::Repr::default();
+    reader.read_exact(found.as_mut())?;
+
+    let expected_k = vk.get_domain().k();
+    if k == expected_k
+        && found.as_ref() == vk.fingerprint(!vk.commit_instances()).to_repr().as_ref()
+    {
+        return Err(Error::InstanceModeMismatch {
+            expected_commit_instances: vk.commit_instances(),
+        });
+    }
+    if k != expected_k || found.as_ref() != expected.as_ref() {
+        return Err(Error::VerifyingKeyMismatch {
+            expected_k,
+            expected: to_hex(expected.as_ref()),
+            found_k: k,
+            found: to_hex(found.as_ref()),
+        });
+    }
+
+    let mut proof = vec![];
+    reader.read_to_end(&mut proof)?;
+    Ok(proof)
+}
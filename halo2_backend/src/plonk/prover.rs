@@ -4,13 +4,17 @@ use rand_core::RngCore;
 use std::collections::{BTreeSet, HashSet};
 use std::{collections::HashMap, iter};
 
-use crate::plonk::lookup::prover::lookup_commit_permuted;
+use crate::multicore::{
+    IndexedParallelIterator, IntoParallelIterator, IntoParallelRefIterator, ParallelIterator,
+};
+use crate::plonk::blinder::Blinder;
+use crate::plonk::lookup::prover::{lookup_permute, PermutedBlinds};
 use crate::plonk::permutation::prover::permutation_commit;
-use crate::plonk::shuffle::prover::shuffle_commit_product;
-use crate::plonk::{lookup, permutation, shuffle, vanishing, ProvingKey};
+use crate::plonk::shuffle::prover::shuffle_product;
+use crate::plonk::{lookup, permutation, shuffle, vanishing, ProvingKey, VerifyingKey};
 use crate::poly::{
     commitment::{Blind, CommitmentScheme, Params, Prover},
-    Basis, Coeff, LagrangeCoeff, Polynomial, ProverQuery,
+    Basis, Coeff, CpuEngine, LagrangeCoeff, Polynomial, PolynomialEngine, ProverQuery,
 };
 use halo2_common::plonk::{
     circuit::sealed, ChallengeBeta, ChallengeGamma, ChallengeTheta, ChallengeX, ChallengeY, Error,
@@ -79,6 +83,40 @@ impl<
         )?))
     }
 
+    /// Create a new prover object, which does every commitment and FFT with `engine`.
+    pub fn new_with_engine(
+        params: &'params Scheme::ParamsProver,
+        pk: &'a ProvingKey<Scheme::Curve>,
+        instance: &[&[Scheme::Scalar]],
+        rng: R,
+        transcript: &'a mut T,
+        engine: &'a dyn PolynomialEngine<Scheme::Curve>,
+    ) -> Result<Self, Error>
+    where
+        Scheme::Scalar: WithSmallOrderMulGroup<3> + FromUniformBytes<64>,
+    {
+        Ok(Self(ProverV2::new_with_engine(
+            params,
+            pk,
+            &[instance],
+            rng,
+            transcript,
+            engine,
+        )?))
+    }
+
+    /// Sets every blinding factor to one. See [`ProverV2::with_unit_blinds`].
+    #[cfg(feature = "unit-blinds")]
+    pub fn with_unit_blinds(self) -> Self {
+        Self(self.0.with_unit_blinds())
+    }
+
+    /// Bounds the columns held over the extended domain at once. See
+    /// [`ProverV2::with_max_extended_columns`].
+    pub fn with_max_extended_columns(self, max: usize) -> Self {
+        Self(self.0.with_max_extended_columns(max))
+    }
+
     /// Commit the `witness` at `phase` and return the challenges after `phase`.
     pub fn commit_phase(
         &mut self,
@@ -102,6 +140,31 @@ impl<
 
 /// The prover object used to create proofs interactively by passing the witnesses to commit at
 /// each phase.  This supports batch proving.
+///
+/// The proof only depends on the witnesses, the instances and the values drawn from the RNG,
+/// which are drawn in the following order, with `m = blinding_factors()`:
+/// 1. In each [`ProverV2::commit_phase`], for each circuit:
+///    - for each blinded advice column of the phase, its `m + 1` unusable rows,
+///    - then the commitment blind of each blinded advice column of the phase.
+/// 2. In [`ProverV2::create_proof`]:
+///    - for each circuit and lookup, the `m + 1` unusable rows of the permuted input and then
+///      of the permuted table, then the commitment blinds of both,
+///    - for each circuit, and each set of columns of the permutation argument, the `m`
+///      blinding rows of its product followed by its commitment blind,
+///    - for each circuit and lookup, the `m` blinding rows of its product followed by its
+///      commitment blind,
+///    - for each circuit and shuffle, the `m` blinding rows of its product followed by its
+///      commitment blind,
+///    - a 32-byte seed for each chunk of 1024 coefficients of the random polynomial of the
+///      vanishing argument, followed by its commitment blind,
+///    - the commitment blind of each piece of `h(X)`,
+///    - finally, the RNG is passed to the multi-open prover.
+///
+/// Field elements are sampled with `Field::random`. In particular, the values drawn don't
+/// depend on the number of threads, so that the same RNG seed gives the same proof.
+///
+/// Every commitment and FFT is done by a [`PolynomialEngine`], which is the [`CpuEngine`]
+/// unless the prover is created with [`ProverV2::new_with_engine`].
 #[derive(Debug)]
 pub struct ProverV2<
     'a,
@@ -115,6 +178,7 @@ pub struct ProverV2<
     // Circuit and setup fields
     params: &'params Scheme::ParamsProver,
     pk: &'a ProvingKey<Scheme::Curve>,
+    engine: &'a dyn PolynomialEngine<Scheme::Curve>,
     // TODO: Add getter
     pub phases: Vec<sealed::Phase>,
     // State
@@ -122,8 +186,9 @@ pub struct ProverV2<
     advice: Vec<AdviceSingle<Scheme::Curve, LagrangeCoeff>>,
     challenges: HashMap<usize, Scheme::Scalar>,
     next_phase_index: usize,
-    rng: R,
+    rng: Blinder<R>,
     transcript: &'a mut T,
+    max_extended_columns: Option<usize>,
     _marker: std::marker::PhantomData<(P, E)>,
 }
 
@@ -147,6 +212,21 @@ impl<
         rng: R,
         transcript: &'a mut T,
     ) -> Result<Self, Error>
+    where
+        Scheme::Scalar: WithSmallOrderMulGroup<3> + FromUniformBytes<64>,
+    {
+        Self::new_with_engine(params, pk, instances, rng, transcript, &CpuEngine)
+    }
+
+    /// Create a new prover object, which does every commitment and FFT with `engine`.
+    pub fn new_with_engine(
+        params: &'params Scheme::ParamsProver,
+        pk: &'a ProvingKey<Scheme::Curve>,
+        instances: &[&[&[Scheme::Scalar]]],
+        rng: R,
+        transcript: &'a mut T,
+        engine: &'a dyn PolynomialEngine<Scheme::Curve>,
+    ) -> Result<Self, Error>
     where
         Scheme::Scalar: WithSmallOrderMulGroup<3> + FromUniformBytes<64>,
     {
@@ -163,6 +243,11 @@ impl<
         let phases = meta.phases().collect();
 
         let domain = &pk.vk.domain;
+        let commit_instances = pk.vk.commit_instances;
+        let query_instance = P::QUERY_INSTANCE || commit_instances;
+        if commit_instances {
+            transcript.write_point(VerifyingKey::<Scheme::Curve>::instance_mode_marker())?;
+        }
 
         let mut commit_instance_fn =
             |instance: &[&[Scheme::Scalar]]| -> Result<InstanceSingle<Scheme::Curve>, Error> {
@@ -175,7 +260,7 @@ impl<
                             return Err(Error::InstanceTooLarge);
                         }
                         for (poly, value) in poly.iter_mut().zip(values.iter()) {
-                            if !P::QUERY_INSTANCE {
+                            if !query_instance {
                                 transcript.common_scalar(*value)?;
                             }
                             *poly = *value;
@@ -184,10 +269,12 @@ impl<
                     })
                     .collect::<Result<Vec<_>, _>>()?;
 
-                if P::QUERY_INSTANCE {
+                if query_instance {
                     let instance_commitments_projective: Vec<_> = instance_values
                         .iter()
-                        .map(|poly| params.commit_lagrange(poly, Blind::default()))
+                        .map(|poly| {
+                            params.commit_lagrange_with_engine(engine, poly, Blind::default())
+                        })
                         .collect();
                     let mut instance_commitments =
                         vec![Scheme::Curve::identity(); instance_commitments_projective.len()];
@@ -199,7 +286,13 @@ impl<
                     drop(instance_commitments_projective);
 
                     for commitment in &instance_commitments {
-                        transcript.common_point(*commitment)?;
+                        // The verifier reads the commitments of committed instance columns
+                        // instead of computing them.
+                        if commit_instances {
+                            transcript.write_point(*commitment)?;
+                        } else {
+                            transcript.common_point(*commitment)?;
+                        }
                     }
                 }
 
@@ -207,7 +300,7 @@ impl<
                     .iter()
                     .map(|poly| {
                         let lagrange_vec = domain.lagrange_from_vec(poly.to_vec());
-                        domain.lagrange_to_coeff(lagrange_vec)
+                        domain.lagrange_to_coeff_with_engine(engine, lagrange_vec)
                     })
                     .collect();
 
@@ -237,17 +330,41 @@ impl<
         Ok(ProverV2 {
             params,
             pk,
+            engine,
             phases,
             instance,
-            rng,
+            rng: Blinder::new(rng),
             transcript,
             advice,
             challenges,
             next_phase_index: 0,
+            max_extended_columns: None,
             _marker: std::marker::PhantomData {},
         })
     }
 
+    /// Sets every blinding factor to one, instead of sampling it from the RNG.
+    ///
+    /// The resulting proofs are not zero-knowledge; this is only meant to compare proofs
+    /// with other implementations. The RNG is still passed to the multi-open prover.
+    #[cfg(feature = "unit-blinds")]
+    pub fn with_unit_blinds(mut self) -> Self {
+        self.rng.set_unit_blinds(true);
+        self
+    }
+
+    /// Computes the quotient polynomial with at most `max` advice and instance columns held
+    /// over the extended domain at once, by evaluating as many circuits at a time as fit.
+    ///
+    /// Below the columns of a single circuit, each circuit is evaluated over each of the
+    /// `2^(extended_k - k)` parts of the extended domain in turn, which holds a part of each
+    /// of its columns only, at the cost of redoing their FFTs for each part: `max` can't go
+    /// lower than that. The proof is the same.
+    pub fn with_max_extended_columns(mut self, max: usize) -> Self {
+        self.max_extended_columns = Some(max);
+        self
+    }
+
     /// Commit the `witness` at `phase` and return the challenges after `phase`.
     #[allow(clippy::type_complexity)]
     pub fn commit_phase(
@@ -272,9 +389,10 @@ impl<
         }
 
         let params = self.params;
+        let engine = self.engine;
         let meta = &self.pk.vk.cs;
 
-        let mut rng = &mut self.rng;
+        let rng = &mut self.rng;
 
         let advice = &mut self.advice;
         let challenges = &mut self.challenges;
@@ -345,7 +463,7 @@ impl<
                 for (column_index, advice_values) in column_indices.iter().zip(&mut advice_values) {
                     if !unblinded_advice.contains(column_index) {
                         for cell in &mut advice_values[unusable_rows_start..] {
-                            *cell = Scheme::Scalar::random(&mut rng);
+                            *cell = rng.blind();
                         }
                     } else {
                         #[cfg(feature = "sanity-checks")]
@@ -362,14 +480,14 @@ impl<
                         if unblinded_advice.contains(i) {
                             Blind::default()
                         } else {
-                            Blind(Scheme::Scalar::random(&mut rng))
+                            Blind(rng.blind())
                         }
                     })
                     .collect();
                 let advice_commitments_projective: Vec<_> = advice_values
                     .iter()
                     .zip(blinds.iter())
-                    .map(|(poly, blind)| params.commit_lagrange(poly, *blind))
+                    .map(|(poly, blind)| params.commit_lagrange_with_engine(engine, poly, *blind))
                     .collect();
                 let mut advice_commitments =
                     vec![Scheme::Curve::identity(); advice_commitments_projective.len()];
@@ -420,10 +538,12 @@ impl<
         Scheme::Scalar: WithSmallOrderMulGroup<3> + FromUniformBytes<64>,
     {
         let params = self.params;
+        let engine = self.engine;
         let meta = &self.pk.vk.cs;
         // let queries = &self.pk.vk.queries;
         let pk = self.pk;
         let domain = &self.pk.vk.domain;
+        let query_instance = P::QUERY_INSTANCE || pk.vk.commit_instances;
 
         let mut rng = self.rng;
 
@@ -439,37 +559,60 @@ impl<
         // Sample theta challenge for keeping lookup columns linearly independent
         let theta: ChallengeTheta<_> = self.transcript.squeeze_challenge_scalar();
 
-        let mut lookups_fn =
-            |instance: &InstanceSingle<Scheme::Curve>,
-             advice: &AdviceSingle<Scheme::Curve, LagrangeCoeff>|
-             -> Result<Vec<lookup::prover::Permuted<Scheme::Curve>>, Error> {
+        // The columns of the lookup and shuffle arguments are computed in parallel, but
+        // their blinding factors are sampled, and they are committed, in the order of the
+        // instances and of the arguments, so that the proof doesn't depend on the
+        // parallelism.
+        let blinding_factors = meta.blinding_factors();
+        let permuted_blinds: Vec<Vec<_>> = instance
+            .iter()
+            .map(|_| {
                 meta.lookups
                     .iter()
-                    .map(|lookup| {
-                        lookup_commit_permuted(
+                    .map(|_| PermutedBlinds::sample(blinding_factors, &mut rng))
+                    .collect()
+            })
+            .collect();
+        let lookups: Vec<Vec<lookup::prover::PermutedValues<Scheme::Curve>>> = instance
+            .par_iter()
+            .zip(advice.par_iter())
+            .zip(permuted_blinds.into_par_iter())
+            .map(|((instance, advice), blinds)| -> Result<Vec<_>, Error> {
+                // Construct permuted values for each lookup
+                meta.lookups
+                    .par_iter()
+                    .zip(blinds.into_par_iter())
+                    .enumerate()
+                    .map(|(i, (lookup, blinds))| {
+                        lookup_permute(
                             lookup,
+                            pk.cache.as_ref().map(|cache| &cache.lookups[i]),
                             pk,
-                            params,
                             domain,
                             theta,
                             &advice.advice_polys,
                             &pk.fixed_values,
                             &instance.instance_values,
                             &challenges,
-                            &mut rng,
-                            self.transcript,
+                            blinds,
                         )
                     })
                     .collect::<Result<Vec<_>, _>>()
-            };
-        let lookups: Vec<Vec<lookup::prover::Permuted<Scheme::Curve>>> = instance
-            .iter()
-            .zip(advice.iter())
-            .map(|(instance, advice)| -> Result<Vec<_>, Error> {
-                // Construct and commit to permuted values for each lookup
-                lookups_fn(instance, advice)
             })
             .collect::<Result<Vec<_>, _>>()?;
+        // Commit to the permuted values of each lookup
+        let lookups: Vec<Vec<lookup::prover::Permuted<Scheme::Curve>>> = lookups
+            .into_iter()
+            .map(|lookups| {
+                lookups
+                    .into_iter()
+                    .map(|lookup| lookup.commit(pk, params, engine))
+                    .collect()
+            })
+            .collect();
+        for lookup in lookups.iter().flatten() {
+            lookup.write_commitments(self.transcript)?;
+        }
 
         // Sample beta challenge
         let beta: ChallengeBeta<_> = self.transcript.squeeze_challenge_scalar();
@@ -485,6 +628,7 @@ impl<
                 permutation_commit(
                     &meta.permutation,
                     params,
+                    engine,
                     pk,
                     &pk.permutation,
                     &advice.advice_polys,
@@ -498,31 +642,65 @@ impl<
             })
             .collect::<Result<Vec<_>, _>>()?;
 
+        let product_blinds: Vec<Vec<_>> = lookups
+            .iter()
+            .map(|lookups| {
+                lookups
+                    .iter()
+                    .map(|_| rng.product_blinds(blinding_factors))
+                    .collect()
+            })
+            .collect();
+        let lookups: Vec<Vec<lookup::prover::Product<Scheme::Curve>>> = lookups
+            .into_par_iter()
+            .zip(product_blinds.into_par_iter())
+            .map(|(lookups, blinds)| {
+                // Construct products for each lookup
+                lookups
+                    .into_par_iter()
+                    .zip(blinds.into_par_iter())
+                    .map(|(lookup, blinds)| lookup.product(pk, beta, gamma, blinds))
+                    .collect()
+            })
+            .collect();
+        // Commit to the products of each lookup
         let lookups: Vec<Vec<lookup::prover::Committed<Scheme::Curve>>> = lookups
             .into_iter()
-            .map(|lookups| -> Result<Vec<_>, _> {
-                // Construct and commit to products for each lookup
+            .map(|lookups| {
                 lookups
                     .into_iter()
-                    .map(|lookup| {
-                        lookup.commit_product(pk, params, beta, gamma, &mut rng, self.transcript)
-                    })
-                    .collect::<Result<Vec<_>, _>>()
+                    .map(|lookup| lookup.commit(pk, params, engine))
+                    .collect()
             })
-            .collect::<Result<Vec<_>, _>>()?;
+            .collect();
+        for lookup in lookups.iter().flatten() {
+            lookup.write_commitment(self.transcript)?;
+        }
 
-        let shuffles: Vec<Vec<shuffle::prover::Committed<Scheme::Curve>>> = instance
+        let product_blinds: Vec<Vec<_>> = instance
             .iter()
-            .zip(advice.iter())
-            .map(|(instance, advice)| -> Result<Vec<_>, _> {
-                // Compress expressions for each shuffle
+            .map(|_| {
                 meta.shuffles
                     .iter()
-                    .map(|shuffle| {
-                        shuffle_commit_product(
+                    .map(|_| rng.product_blinds(blinding_factors))
+                    .collect()
+            })
+            .collect();
+        let shuffles: Vec<Vec<shuffle::prover::Product<Scheme::Curve>>> = instance
+            .par_iter()
+            .zip(advice.par_iter())
+            .zip(product_blinds.into_par_iter())
+            .map(|((instance, advice), blinds)| {
+                // Compress expressions for each shuffle
+                meta.shuffles
+                    .par_iter()
+                    .zip(blinds.into_par_iter())
+                    .enumerate()
+                    .map(|(i, (shuffle, blinds))| {
+                        shuffle_product(
                             shuffle,
+                            pk.cache.as_ref().map(|cache| &cache.shuffles[i]),
                             pk,
-                            params,
                             domain,
                             theta,
                             gamma,
@@ -530,16 +708,29 @@ impl<
                             &pk.fixed_values,
                             &instance.instance_values,
                             &challenges,
-                            &mut rng,
-                            self.transcript,
+                            blinds,
                         )
                     })
-                    .collect::<Result<Vec<_>, _>>()
+                    .collect()
             })
-            .collect::<Result<Vec<_>, _>>()?;
+            .collect();
+        // Commit to the products of each shuffle
+        let shuffles: Vec<Vec<shuffle::prover::Committed<Scheme::Curve>>> = shuffles
+            .into_iter()
+            .map(|shuffles| {
+                shuffles
+                    .into_iter()
+                    .map(|shuffle| shuffle.commit(pk, params, engine))
+                    .collect()
+            })
+            .collect();
+        for shuffle in shuffles.iter().flatten() {
+            shuffle.write_commitment(self.transcript)?;
+        }
 
         // Commit to the vanishing argument's random polynomial for blinding h(x_3)
-        let vanishing = vanishing::Argument::commit(params, domain, &mut rng, self.transcript)?;
+        let vanishing =
+            vanishing::Argument::commit(params, engine, domain, &mut rng, self.transcript)?;
 
         // Obtain challenge for keeping all separate gates linearly independent
         let y: ChallengeY<_> = self.transcript.squeeze_challenge_scalar();
@@ -555,7 +746,7 @@ impl<
                     AdviceSingle {
                         advice_polys: advice_polys
                             .into_iter()
-                            .map(|poly| domain.lagrange_to_coeff(poly))
+                            .map(|poly| domain.lagrange_to_coeff_with_engine(engine, poly))
                             .collect::<Vec<_>>(),
                         advice_blinds,
                     }
@@ -566,6 +757,7 @@ impl<
         // Evaluate the h(X) polynomial
         let h_poly = pk.ev.evaluate_h(
             pk,
+            engine,
             &advice
                 .iter()
                 .map(|a| a.advice_polys.as_slice())
@@ -582,15 +774,17 @@ impl<
             &lookups,
             &shuffles,
             &permutations,
+            self.max_extended_columns,
         );
 
         // Construct the vanishing argument's h(X) commitments
-        let vanishing = vanishing.construct(params, domain, h_poly, &mut rng, self.transcript)?;
+        let vanishing =
+            vanishing.construct(params, engine, domain, h_poly, &mut rng, self.transcript)?;
 
         let x: ChallengeX<_> = self.transcript.squeeze_challenge_scalar();
         let xn = x.pow([params.n()]);
 
-        if P::QUERY_INSTANCE {
+        if query_instance {
             // Compute and hash instance evals for the circuit instance
             for instance in instance.iter() {
                 // Evaluate polynomials at omega^i x
@@ -690,7 +884,7 @@ impl<
             .flat_map(|((((instance, advice), permutation), lookups), shuffles)| {
                 iter::empty()
                     .chain(
-                        P::QUERY_INSTANCE
+                        query_instance
                             .then_some(meta.instance_queries.iter().map(move |&(column, at)| {
                                 ProverQuery {
                                     point: domain.rotate_omega(*x, at),
@@ -725,7 +919,7 @@ impl<
 
         let prover = P::new(params);
         prover
-            .create_proof(rng, self.transcript, instances)
+            .create_proof_with_engine(engine, rng.into_rng(), self.transcript, instances)
             .map_err(|_| Error::ConstraintSystemFailure)?;
 
         Ok(())
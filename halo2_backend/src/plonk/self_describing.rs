@@ -0,0 +1,619 @@
+//! A self-describing serialization of [`VerifyingKey`] and [`ProvingKey`].
+//!
+//! Unlike [`VerifyingKey::write`], the self-describing format contains the constraint
+//! system of the circuit and the [`SerdeFormat`] used to encode the key, so that a key can
+//! be read without the circuit type. It consists of:
+//! - the magic bytes [`MAGIC`] and a version byte,
+//! - the `SerdeFormat` of the payload, `k` and the flags byte of [`VerifyingKey::write`],
+//! - the constraint system: column counts, phases, gates as encoded expressions,
+//!   permutation columns, lookups, shuffles and column annotations,
+//! - the fixed commitments, the permutation verifying key and the compressed selectors,
+//! - for a proving key, the same payload as [`ProvingKey::write`] after the verifying key.
+//!
+//! Queries are not serialized, since they are collected from the expressions when the
+//! constraint system is rebuilt.
+
+use std::collections::HashMap;
+use std::io;
+
+use group::ff::{FromUniformBytes, PrimeField};
+use halo2_common::plonk::ConstraintSystem;
+use halo2_middleware::circuit::{
+    Advice, AdviceQueryMid, Any, ChallengeMid, ColumnMid, ConstraintSystemV2Backend, ExpressionMid,
+    FixedQueryMid, GateV2Backend, InstanceQueryMid,
+};
+use halo2_middleware::poly::Rotation;
+use halo2_middleware::{lookup, metadata, permutation as permutation_mid, shuffle};
+
+use super::{evaluation::Evaluator, permutation, ProvingKey, VerifyingKey};
+use crate::helpers::{
+    self, read_polynomial_vec, write_polynomial_slice, SerdeCurveAffine, SerdeFormat,
+    SerdePrimeField,
+};
+use crate::poly::{EvaluationDomain, Polynomial};
+
+/// The magic bytes that start a key in the self-describing format.
+pub const MAGIC: [u8; 4] = *b"H2KY";
+
+// Current version of the self-describing format
+const SELF_DESCRIBING_VERSION: u8 = 0x01;
+
+fn invalid_data(msg: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.into())
+}
+
+fn write_u8<W: io::Write>(writer: &mut W, value: u8) -> io::Result<()> {
+    writer.write_all(&[value])
+}
+
+fn read_u8<R: io::Read>(reader: &mut R) -> io::Result<u8> {
+    let mut value = [0u8; 1];
+    reader.read_exact(&mut value)?;
+    Ok(value[0])
+}
+
+fn write_len<W: io::Write>(writer: &mut W, len: usize) -> io::Result<()> {
+    let len = u32::try_from(len).map_err(|_| invalid_data("length does not fit in u32"))?;
+    writer.write_all(&len.to_le_bytes())
+}
+
+fn read_len<R: io::Read>(reader: &mut R) -> io::Result<usize> {
+    let mut len = [0u8; 4];
+    reader.read_exact(&mut len)?;
+    Ok(u32::from_le_bytes(len) as usize)
+}
+
+fn write_string<W: io::Write>(writer: &mut W, s: &str) -> io::Result<()> {
+    write_len(writer, s.len())?;
+    writer.write_all(s.as_bytes())
+}
+
+fn read_string<R: io::Read>(reader: &mut R) -> io::Result<String> {
+    let len = read_len(reader)?;
+    let mut bytes = vec![];
+    reader.take(len as u64).read_to_end(&mut bytes)?;
+    if bytes.len() != len {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+    String::from_utf8(bytes).map_err(|_| invalid_data("string is not valid UTF-8"))
+}
+
+fn write_rotation<W: io::Write>(writer: &mut W, rotation: Rotation) -> io::Result<()> {
+    writer.write_all(&rotation.0.to_le_bytes())
+}
+
+fn read_rotation<R: io::Read>(reader: &mut R) -> io::Result<Rotation> {
+    let mut rotation = [0u8; 4];
+    reader.read_exact(&mut rotation)?;
+    Ok(Rotation(i32::from_le_bytes(rotation)))
+}
+
+fn write_format<W: io::Write>(writer: &mut W, format: SerdeFormat) -> io::Result<()> {
+    write_u8(
+        writer,
+        match format {
+            SerdeFormat::Processed => 0,
+            SerdeFormat::RawBytes => 1,
+            SerdeFormat::RawBytesUnchecked => 2,
+        },
+    )
+}
+
+fn read_format<R: io::Read>(reader: &mut R) -> io::Result<SerdeFormat> {
+    match read_u8(reader)? {
+        0 => Ok(SerdeFormat::Processed),
+        1 => Ok(SerdeFormat::RawBytes),
+        2 => Ok(SerdeFormat::RawBytesUnchecked),
+        tag => Err(invalid_data(format!("unexpected serde format: {tag}"))),
+    }
+}
+
+fn write_column_type<W: io::Write>(writer: &mut W, column_type: Any) -> io::Result<()> {
+    match column_type {
+        Any::Advice(advice) => {
+            write_u8(writer, 0)?;
+            write_u8(writer, advice.phase())
+        }
+        Any::Fixed => write_u8(writer, 1),
+        Any::Instance => write_u8(writer, 2),
+    }
+}
+
+fn read_column_type<R: io::Read>(reader: &mut R) -> io::Result<Any> {
+    match read_u8(reader)? {
+        0 => Ok(Any::Advice(Advice::new(read_u8(reader)?))),
+        1 => Ok(Any::Fixed),
+        2 => Ok(Any::Instance),
+        tag => Err(invalid_data(format!("unexpected column type: {tag}"))),
+    }
+}
+
+fn write_expression<W: io::Write, F: SerdePrimeField>(
+    writer: &mut W,
+    expr: &ExpressionMid<F>,
+) -> io::Result<()> {
+    match expr {
+        ExpressionMid::Constant(c) => {
+            write_u8(writer, 0)?;
+            c.write(writer, SerdeFormat::Processed)
+        }
+        ExpressionMid::Fixed(query) => {
+            write_u8(writer, 1)?;
+            write_len(writer, query.column_index)?;
+            write_rotation(writer, query.rotation)
+        }
+        ExpressionMid::Advice(query) => {
+            write_u8(writer, 2)?;
+            write_len(writer, query.column_index)?;
+            write_rotation(writer, query.rotation)?;
+            write_u8(writer, query.phase)
+        }
+        ExpressionMid::Instance(query) => {
+            write_u8(writer, 3)?;
+            write_len(writer, query.column_index)?;
+            write_rotation(writer, query.rotation)
+        }
+        ExpressionMid::Challenge(challenge) => {
+            write_u8(writer, 4)?;
+            write_len(writer, challenge.index())?;
+            write_u8(writer, challenge.phase())
+        }
+        ExpressionMid::Negated(e) => {
+            write_u8(writer, 5)?;
+            write_expression(writer, e)
+        }
+        ExpressionMid::Sum(lhs, rhs) => {
+            write_u8(writer, 6)?;
+            write_expression(writer, lhs)?;
+            write_expression(writer, rhs)
+        }
+        ExpressionMid::Product(lhs, rhs) => {
+            write_u8(writer, 7)?;
+            write_expression(writer, lhs)?;
+            write_expression(writer, rhs)
+        }
+        ExpressionMid::Scaled(e, c) => {
+            write_u8(writer, 8)?;
+            write_expression(writer, e)?;
+            c.write(writer, SerdeFormat::Processed)
+        }
+    }
+}
+
+fn read_expression<R: io::Read, F: SerdePrimeField>(
+    reader: &mut R,
+) -> io::Result<ExpressionMid<F>> {
+    Ok(match read_u8(reader)? {
+        0 => ExpressionMid::Constant(F::read(reader, SerdeFormat::Processed)?),
+        1 => ExpressionMid::Fixed(FixedQueryMid {
+            column_index: read_len(reader)?,
+            rotation: read_rotation(reader)?,
+        }),
+        2 => ExpressionMid::Advice(AdviceQueryMid {
+            column_index: read_len(reader)?,
+            rotation: read_rotation(reader)?,
+            phase: read_u8(reader)?,
+        }),
+        3 => ExpressionMid::Instance(InstanceQueryMid {
+            column_index: read_len(reader)?,
+            rotation: read_rotation(reader)?,
+        }),
+        4 => ExpressionMid::Challenge(ChallengeMid {
+            index: read_len(reader)?,
+            phase: read_u8(reader)?,
+        }),
+        5 => ExpressionMid::Negated(Box::new(read_expression(reader)?)),
+        6 => ExpressionMid::Sum(
+            Box::new(read_expression(reader)?),
+            Box::new(read_expression(reader)?),
+        ),
+        7 => ExpressionMid::Product(
+            Box::new(read_expression(reader)?),
+            Box::new(read_expression(reader)?),
+        ),
+        8 => {
+            let e = read_expression(reader)?;
+            ExpressionMid::Scaled(Box::new(e), F::read(reader, SerdeFormat::Processed)?)
+        }
+        tag => return Err(invalid_data(format!("unexpected expression: {tag}"))),
+    })
+}
+
+fn write_expressions<W: io::Write, F: SerdePrimeField>(
+    writer: &mut W,
+    exprs: &[ExpressionMid<F>],
+) -> io::Result<()> {
+    write_len(writer, exprs.len())?;
+    for expr in exprs {
+        write_expression(writer, expr)?;
+    }
+    Ok(())
+}
+
+fn read_expressions<R: io::Read, F: SerdePrimeField>(
+    reader: &mut R,
+) -> io::Result<Vec<ExpressionMid<F>>> {
+    (0..read_len(reader)?)
+        .map(|_| read_expression(reader))
+        .collect()
+}
+
+/// Checks that the columns and challenges of `expr` exist in `cs`, since rebuilding the
+/// constraint system indexes them.
+fn check_expression<F: SerdePrimeField>(
+    cs: &ConstraintSystemV2Backend<F>,
+    expr: &ExpressionMid<F>,
+) -> io::Result<()> {
+    let valid = match expr {
+        ExpressionMid::Constant(_) => true,
+        ExpressionMid::Fixed(query) => query.column_index < cs.num_fixed_columns,
+        ExpressionMid::Advice(query) => query.column_index < cs.num_advice_columns,
+        ExpressionMid::Instance(query) => query.column_index < cs.num_instance_columns,
+        ExpressionMid::Challenge(challenge) => challenge.index() < cs.num_challenges,
+        ExpressionMid::Negated(e) | ExpressionMid::Scaled(e, _) => return check_expression(cs, e),
+        ExpressionMid::Sum(lhs, rhs) | ExpressionMid::Product(lhs, rhs) => {
+            check_expression(cs, lhs)?;
+            return check_expression(cs, rhs);
+        }
+    };
+    if !valid {
+        return Err(invalid_data(format!(
+            "expression {expr:?} is out of bounds"
+        )));
+    }
+    Ok(())
+}
+
+fn write_constraint_system<W: io::Write, F: SerdePrimeField>(
+    writer: &mut W,
+    cs: &ConstraintSystemV2Backend<F>,
+) -> io::Result<()> {
+    write_len(writer, cs.num_fixed_columns)?;
+    write_len(writer, cs.num_advice_columns)?;
+    write_len(writer, cs.num_instance_columns)?;
+    write_len(writer, cs.num_challenges)?;
+
+    write_len(writer, cs.unblinded_advice_columns.len())?;
+    for column in &cs.unblinded_advice_columns {
+        write_len(writer, *column)?;
+    }
+    write_len(writer, cs.advice_column_phase.len())?;
+    writer.write_all(&cs.advice_column_phase)?;
+    write_len(writer, cs.challenge_phase.len())?;
+    writer.write_all(&cs.challenge_phase)?;
+
+    write_len(writer, cs.gates.len())?;
+    for gate in &cs.gates {
+        write_string(writer, gate.name())?;
+        write_expression(writer, gate.polynomial())?;
+    }
+
+    write_len(writer, cs.permutation.columns.len())?;
+    for column in &cs.permutation.columns {
+        write_len(writer, column.index)?;
+        write_column_type(writer, column.column_type)?;
+    }
+
+    write_len(writer, cs.lookups.len())?;
+    for lookup in &cs.lookups {
+        write_string(writer, &lookup.name)?;
+        write_expressions(writer, &lookup.input_expressions)?;
+        write_expressions(writer, &lookup.table_expressions)?;
+    }
+
+    write_len(writer, cs.shuffles.len())?;
+    for shuffle in &cs.shuffles {
+        write_string(writer, &shuffle.name)?;
+        write_expressions(writer, &shuffle.input_expressions)?;
+        write_expressions(writer, &shuffle.shuffle_expressions)?;
+    }
+
+    // Sort the annotations so that the encoding is deterministic.
+    let mut annotations: Vec<_> = cs.general_column_annotations.iter().collect();
+    annotations.sort();
+    write_len(writer, annotations.len())?;
+    for (column, annotation) in annotations {
+        write_len(writer, column.index)?;
+        write_column_type(writer, column.column_type)?;
+        write_string(writer, annotation)?;
+    }
+
+    Ok(())
+}
+
+fn read_constraint_system<R: io::Read, F: SerdePrimeField>(
+    reader: &mut R,
+) -> io::Result<ConstraintSystemV2Backend<F>> {
+    let num_fixed_columns = read_len(reader)?;
+    let num_advice_columns = read_len(reader)?;
+    let num_instance_columns = read_len(reader)?;
+    let num_challenges = read_len(reader)?;
+
+    let unblinded_advice_columns = (0..read_len(reader)?)
+        .map(|_| read_len(reader))
+        .collect::<io::Result<_>>()?;
+    let advice_column_phase = (0..read_len(reader)?)
+        .map(|_| read_u8(reader))
+        .collect::<io::Result<Vec<_>>>()?;
+    let challenge_phase = (0..read_len(reader)?)
+        .map(|_| read_u8(reader))
+        .collect::<io::Result<Vec<_>>>()?;
+    if advice_column_phase.len() != num_advice_columns || challenge_phase.len() != num_challenges {
+        return Err(invalid_data("unexpected number of phases"));
+    }
+
+    let gates = (0..read_len(reader)?)
+        .map(|_| {
+            Ok(GateV2Backend {
+                name: read_string(reader)?,
+                poly: read_expression(reader)?,
+            })
+        })
+        .collect::<io::Result<_>>()?;
+
+    let columns = (0..read_len(reader)?)
+        .map(|_| {
+            Ok(ColumnMid {
+                index: read_len(reader)?,
+                column_type: read_column_type(reader)?,
+            })
+        })
+        .collect::<io::Result<_>>()?;
+
+    let lookups = (0..read_len(reader)?)
+        .map(|_| {
+            Ok(lookup::ArgumentV2 {
+                name: read_string(reader)?,
+                input_expressions: read_expressions(reader)?,
+                table_expressions: read_expressions(reader)?,
+            })
+        })
+        .collect::<io::Result<_>>()?;
+
+    let shuffles = (0..read_len(reader)?)
+        .map(|_| {
+            Ok(shuffle::ArgumentV2 {
+                name: read_string(reader)?,
+                input_expressions: read_expressions(reader)?,
+                shuffle_expressions: read_expressions(reader)?,
+            })
+        })
+        .collect::<io::Result<_>>()?;
+
+    let general_column_annotations = (0..read_len(reader)?)
+        .map(|_| {
+            let column = metadata::Column {
+                index: read_len(reader)?,
+                column_type: read_column_type(reader)?,
+            };
+            Ok((column, read_string(reader)?))
+        })
+        .collect::<io::Result<HashMap<_, _>>>()?;
+
+    let cs = ConstraintSystemV2Backend {
+        num_fixed_columns,
+        num_advice_columns,
+        num_instance_columns,
+        num_challenges,
+        unblinded_advice_columns,
+        advice_column_phase,
+        challenge_phase,
+        gates,
+        permutation: permutation_mid::ArgumentV2 { columns },
+        lookups,
+        shuffles,
+        general_column_annotations,
+    };
+
+    let exprs = cs.gates.iter().map(|gate| gate.polynomial()).chain(
+        cs.lookups
+            .iter()
+            .flat_map(|l| l.input_expressions.iter().chain(&l.table_expressions)),
+    );
+    let exprs = exprs.chain(
+        cs.shuffles
+            .iter()
+            .flat_map(|s| s.input_expressions.iter().chain(&s.shuffle_expressions)),
+    );
+    for expr in exprs {
+        check_expression(&cs, expr)?;
+    }
+    for column in &cs.permutation.columns {
+        let num_columns = match column.column_type {
+            Any::Advice(_) => cs.num_advice_columns,
+            Any::Fixed => cs.num_fixed_columns,
+            Any::Instance => cs.num_instance_columns,
+        };
+        if column.index >= num_columns {
+            return Err(invalid_data(format!("column {column:?} is out of bounds")));
+        }
+    }
+
+    Ok(cs)
+}
+
+impl<C: SerdeCurveAffine> VerifyingKey<C>
+where
+    C::Scalar: SerdePrimeField + FromUniformBytes<64>,
+{
+    /// Writes a verifying key to a buffer in the self-describing format, which can be
+    /// read with [`Self::read_self_describing`] without the circuit type.
+    ///
+    /// Curve and field elements of the key are written according to `format`, as in
+    /// [`Self::write`].
+    pub fn write_self_describing<W: io::Write>(
+        &self,
+        writer: &mut W,
+        format: SerdeFormat,
+    ) -> io::Result<()> {
+        writer.write_all(&MAGIC)?;
+        write_u8(writer, SELF_DESCRIBING_VERSION)?;
+        write_format(writer, format)?;
+        let k = self.domain.k();
+        assert!(k <= C::Scalar::S);
+        // k value fits in 1 byte
+        write_u8(writer, k as u8)?;
+        write_u8(writer, self.flags())?;
+
+        write_constraint_system(writer, &ConstraintSystemV2Backend::from(self.cs.clone()))?;
+
+        write_len(writer, self.fixed_commitments.len())?;
+        for commitment in &self.fixed_commitments {
+            commitment.write(writer, format)?;
+        }
+        self.permutation.write(writer, format)?;
+
+        write_len(writer, self.selectors.len())?;
+        for selector in &self.selectors {
+            for bits in selector.chunks(8) {
+                writer.write_all(&[helpers::pack(bits)])?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads a verifying key written by [`Self::write_self_describing`].
+    ///
+    /// The `SerdeFormat` is read from the header, and the constraint system is read from
+    /// the key instead of being configured from the circuit. Returns an error of kind
+    /// [`io::ErrorKind::InvalidData`] if the header is not a valid self-describing header.
+    pub fn read_self_describing<R: io::Read>(reader: &mut R) -> io::Result<Self> {
+        Self::read_self_describing_with_format(reader).map(|(vk, _)| vk)
+    }
+
+    /// Reads a verifying key written by [`Self::write_self_describing`], and returns it
+    /// along with the `SerdeFormat` of the rest of the payload.
+    fn read_self_describing_with_format<R: io::Read>(
+        reader: &mut R,
+    ) -> io::Result<(Self, SerdeFormat)> {
+        let mut magic = [0u8; 4];
+        reader.read_exact(&mut magic)?;
+        if magic != MAGIC {
+            return Err(invalid_data("unexpected magic bytes"));
+        }
+        if read_u8(reader)? != SELF_DESCRIBING_VERSION {
+            return Err(invalid_data("unexpected version byte"));
+        }
+        let format = read_format(reader)?;
+        let k = read_u8(reader)?;
+        if k as u32 > C::Scalar::S {
+            return Err(invalid_data(format!(
+                "circuit size value (k): {} exceeds maxium: {}",
+                k,
+                C::Scalar::S
+            )));
+        }
+        let (compress_selectors, commit_instances) = Self::parse_flags(read_u8(reader)?)?;
+
+        let cs: ConstraintSystem<C::Scalar> = read_constraint_system(reader)?.into();
+        let domain = EvaluationDomain::new(cs.degree() as u32, k as u32);
+
+        let fixed_commitments: Vec<_> = (0..read_len(reader)?)
+            .map(|_| C::read(reader, format))
+            .collect::<Result<_, _>>()?;
+        if fixed_commitments.len() != cs.num_fixed_columns {
+            return Err(invalid_data("unexpected number of fixed commitments"));
+        }
+
+        let permutation = permutation::VerifyingKey::read(reader, &cs.permutation, format)?;
+
+        let selectors = (0..read_len(reader)?)
+            .map(|_| {
+                let mut selector = vec![false; 1 << k];
+                let mut selector_bytes = vec![0u8; (selector.len() + 7) / 8];
+                reader.read_exact(&mut selector_bytes)?;
+                for (bits, byte) in selector.chunks_mut(8).zip(selector_bytes) {
+                    helpers::unpack(byte, bits);
+                }
+                Ok(selector)
+            })
+            .collect::<io::Result<_>>()?;
+
+        let vk = Self::from_parts(
+            domain,
+            fixed_commitments,
+            permutation,
+            cs,
+            selectors,
+            compress_selectors,
+            commit_instances,
+        );
+        Ok((vk, format))
+    }
+
+    /// Writes a verifying key to a vector of bytes using [`Self::write_self_describing`].
+    pub fn to_bytes_self_describing(&self, format: SerdeFormat) -> Vec<u8> {
+        let mut bytes = vec![];
+        self.write_self_describing(&mut bytes, format)
+            .expect("Writing to vector should not fail");
+        bytes
+    }
+
+    /// Reads a verifying key from a slice of bytes using [`Self::read_self_describing`].
+    pub fn from_bytes_self_describing(mut bytes: &[u8]) -> io::Result<Self> {
+        Self::read_self_describing(&mut bytes)
+    }
+}
+
+impl<C: SerdeCurveAffine> ProvingKey<C>
+where
+    C::Scalar: SerdePrimeField + FromUniformBytes<64>,
+{
+    /// Writes a proving key to a buffer in the self-describing format, by writing the
+    /// verifying key with [`VerifyingKey::write_self_describing`] followed by the rest of
+    /// the data as in [`Self::write`].
+    pub fn write_self_describing<W: io::Write>(
+        &self,
+        writer: &mut W,
+        format: SerdeFormat,
+    ) -> io::Result<()> {
+        self.vk.write_self_describing(writer, format)?;
+        self.l0.write(writer, format)?;
+        self.l_last.write(writer, format)?;
+        self.l_active_row.write(writer, format)?;
+        write_polynomial_slice(&self.fixed_values, writer, format)?;
+        write_polynomial_slice(&self.fixed_polys, writer, format)?;
+        write_polynomial_slice(&self.fixed_cosets, writer, format)?;
+        self.permutation.write(writer, format)?;
+        Ok(())
+    }
+
+    /// Reads a proving key written by [`Self::write_self_describing`], without the
+    /// circuit type.
+    pub fn read_self_describing<R: io::Read>(reader: &mut R) -> io::Result<Self> {
+        let (vk, format) = VerifyingKey::<C>::read_self_describing_with_format(reader)?;
+        let l0 = Polynomial::read(reader, format)?;
+        let l_last = Polynomial::read(reader, format)?;
+        let l_active_row = Polynomial::read(reader, format)?;
+        let fixed_values = read_polynomial_vec(reader, format)?;
+        let fixed_polys = read_polynomial_vec(reader, format)?;
+        let fixed_cosets = read_polynomial_vec(reader, format)?;
+        let permutation = permutation::ProvingKey::read(reader, format)?;
+        let ev = Evaluator::new(vk.cs());
+        Ok(Self {
+            vk,
+            l0,
+            l_last,
+            l_active_row,
+            fixed_values,
+            fixed_polys,
+            fixed_cosets,
+            permutation,
+            ev,
+            cache: None,
+        })
+    }
+
+    /// Writes a proving key to a vector of bytes using [`Self::write_self_describing`].
+    pub fn to_bytes_self_describing(&self, format: SerdeFormat) -> Vec<u8> {
+        let mut bytes = vec![];
+        self.write_self_describing(&mut bytes, format)
+            .expect("Writing to vector should not fail");
+        bytes
+    }
+
+    /// Reads a proving key from a slice of bytes using [`Self::read_self_describing`].
+    pub fn from_bytes_self_describing(mut bytes: &[u8]) -> io::Result<Self> {
+        Self::read_self_describing(&mut bytes)
+    }
+}
@@ -1,11 +1,13 @@
 use super::super::ProvingKey;
 use super::Argument;
+use crate::plonk::blinder::ProductBlinds;
+use crate::plonk::cache::ExpressionValues;
 use crate::plonk::evaluation::evaluate;
 use crate::{
-    arithmetic::{eval_polynomial, parallelize, CurveAffine},
+    arithmetic::{eval_polynomial, parallelize, prefix_product, CurveAffine},
     poly::{
         commitment::{Blind, Params},
-        Coeff, EvaluationDomain, LagrangeCoeff, Polynomial, ProverQuery,
+        Coeff, EvaluationDomain, LagrangeCoeff, Polynomial, PolynomialEngine, ProverQuery,
     },
     transcript::{EncodedChallenge, TranscriptWrite},
 };
@@ -13,8 +15,8 @@ use group::{ff::BatchInvert, Curve};
 use halo2_common::plonk::{ChallengeGamma, ChallengeTheta, ChallengeX, Error, Expression};
 use halo2_middleware::ff::WithSmallOrderMulGroup;
 use halo2_middleware::poly::Rotation;
-use rand_core::RngCore;
 use std::{
+    borrow::Cow,
     iter,
     ops::{Mul, MulAssign},
 };
@@ -25,10 +27,19 @@ struct Compressed<C: CurveAffine> {
     shuffle_expression: Polynomial<C::Scalar, LagrangeCoeff>,
 }
 
+/// The grand product column of a shuffle argument, which is computed in parallel for all
+/// the shuffles, then committed in order by [`Product::commit`].
+#[derive(Debug)]
+pub(in crate::plonk) struct Product<C: CurveAffine> {
+    z: Polynomial<C::Scalar, LagrangeCoeff>,
+    blind: Blind<C::Scalar>,
+}
+
 #[derive(Debug)]
 pub(in crate::plonk) struct Committed<C: CurveAffine> {
     pub(in crate::plonk) product_poly: Polynomial<C::Scalar, Coeff>,
     product_blind: Blind<C::Scalar>,
+    product_commitment: C,
 }
 
 pub(in crate::plonk) struct Evaluated<C: CurveAffine> {
@@ -40,10 +51,10 @@ pub(in crate::plonk) struct Evaluated<C: CurveAffine> {
 /// - constructs A_compressed = \theta^{m-1} A_0 + theta^{m-2} A_1 + ... + \theta A_{m-2} + A_{m-1}
 ///   and S_compressed = \theta^{m-1} S_0 + theta^{m-2} S_1 + ... + \theta S_{m-2} + S_{m-1},
 #[allow(clippy::too_many_arguments)]
-fn shuffle_compress<'a, 'params: 'a, F: WithSmallOrderMulGroup<3>, C, P: Params<'params, C>>(
+fn shuffle_compress<'a, F: WithSmallOrderMulGroup<3>, C>(
     arg: &Argument<F>,
+    cached: Option<&(ExpressionValues<F>, ExpressionValues<F>)>,
     pk: &ProvingKey<C>,
-    params: &P,
     domain: &EvaluationDomain<C::Scalar>,
     theta: ChallengeTheta<C>,
     advice_values: &'a [Polynomial<C::Scalar, LagrangeCoeff>],
@@ -55,32 +66,39 @@ where
     C: CurveAffine<ScalarExt = F>,
     C::Curve: Mul<F, Output = C::Curve> + MulAssign<F>,
 {
-    // Closure to get values of expressions and compress them
-    let compress_expressions = |expressions: &[Expression<C::Scalar>]| {
-        let compressed_expression = expressions
-            .iter()
-            .map(|expression| {
-                pk.vk.domain.lagrange_from_vec(evaluate(
-                    expression,
-                    params.n() as usize,
-                    1,
-                    fixed_values,
-                    advice_values,
-                    instance_values,
-                    challenges,
-                ))
-            })
-            .fold(domain.empty_lagrange(), |acc, expression| {
-                acc * *theta + &expression
-            });
-        compressed_expression
-    };
+    // Closure to get values of expressions, or their cached values, and compress them
+    let compress_expressions =
+        |expressions: &[Expression<C::Scalar>], cached: Option<&ExpressionValues<C::Scalar>>| {
+            let compressed_expression = expressions
+                .iter()
+                .enumerate()
+                .map(
+                    |(i, expression)| match cached.and_then(|cached| cached[i].as_ref()) {
+                        Some(values) => Cow::Borrowed(values),
+                        None => Cow::Owned(pk.vk.domain.lagrange_from_vec(evaluate(
+                            expression,
+                            1 << domain.k(),
+                            1,
+                            fixed_values,
+                            advice_values,
+                            instance_values,
+                            challenges,
+                        ))),
+                    },
+                )
+                .fold(domain.empty_lagrange(), |acc, expression| {
+                    acc * *theta + &*expression
+                });
+            compressed_expression
+        };
 
     // Get values of input expressions involved in the shuffle and compress them
-    let input_expression = compress_expressions(&arg.input_expressions);
+    let input_expression =
+        compress_expressions(&arg.input_expressions, cached.map(|cached| &cached.0));
 
     // Get values of table expressions involved in the shuffle and compress them
-    let shuffle_expression = compress_expressions(&arg.shuffle_expressions);
+    let shuffle_expression =
+        compress_expressions(&arg.shuffle_expressions, cached.map(|cached| &cached.1));
 
     Compressed {
         input_expression,
@@ -90,22 +108,15 @@ where
 
 /// Given a Shuffle with input expressions and table expressions this method
 /// constructs the grand product polynomial over the shuffle.
-/// The grand product polynomial is used to populate the Product<C> struct.
-/// The Product<C> struct is added to the Shuffle and finally returned by the method.
+/// The grand product polynomial is used to populate the Product<C> struct,
+/// which is committed by [`Product::commit`].
+///
+/// This doesn't use the parameters, so that several shuffles can be computed in parallel.
 #[allow(clippy::too_many_arguments)]
-pub(in crate::plonk) fn shuffle_commit_product<
-    'a,
-    'params: 'a,
-    F: WithSmallOrderMulGroup<3>,
-    C,
-    P: Params<'params, C>,
-    E: EncodedChallenge<C>,
-    R: RngCore,
-    T: TranscriptWrite<C, E>,
->(
+pub(in crate::plonk) fn shuffle_product<'a, F: WithSmallOrderMulGroup<3>, C>(
     arg: &Argument<F>,
+    cached: Option<&(ExpressionValues<F>, ExpressionValues<F>)>,
     pk: &ProvingKey<C>,
-    params: &P,
     domain: &EvaluationDomain<C::Scalar>,
     theta: ChallengeTheta<C>,
     gamma: ChallengeGamma<C>,
@@ -113,17 +124,17 @@ pub(in crate::plonk) fn shuffle_commit_product<
     fixed_values: &'a [Polynomial<C::Scalar, LagrangeCoeff>],
     instance_values: &'a [Polynomial<C::Scalar, LagrangeCoeff>],
     challenges: &'a [C::Scalar],
-    mut rng: R,
-    transcript: &mut T,
-) -> Result<Committed<C>, Error>
+    blinds: ProductBlinds<C::Scalar>,
+) -> Product<C>
 where
     C: CurveAffine<ScalarExt = F>,
     C::Curve: Mul<F, Output = C::Curve> + MulAssign<F>,
 {
+    let n = 1 << domain.k();
     let compressed = shuffle_compress(
         arg,
+        cached,
         pk,
-        params,
         domain,
         theta,
         advice_values,
@@ -134,7 +145,7 @@ where
 
     let blinding_factors = pk.vk.cs.blinding_factors();
 
-    let mut shuffle_product = vec![C::Scalar::ZERO; params.n() as usize];
+    let mut shuffle_product = vec![C::Scalar::ZERO; n];
     parallelize(&mut shuffle_product, |shuffle_product, start| {
         for (shuffle_product, shuffle_value) in shuffle_product
             .iter_mut()
@@ -154,26 +165,22 @@ where
     });
 
     // Compute the evaluations of the shuffle product polynomial
-    // over our domain, starting with z[0] = 1
-    let z = iter::once(C::Scalar::ONE)
-        .chain(shuffle_product)
-        .scan(C::Scalar::ONE, |state, cur| {
-            *state *= &cur;
-            Some(*state)
-        })
-        // Take all rows including the "last" row which should
-        // be a boolean (and ideally 1, else soundness is broken)
-        .take(params.n() as usize - blinding_factors)
-        // Chain random blinding factors.
-        .chain((0..blinding_factors).map(|_| C::Scalar::random(&mut rng)))
-        .collect::<Vec<_>>();
-    assert_eq!(z.len(), params.n() as usize);
+    // over our domain, starting with z[0] = 1, on all rows including
+    // the "last" row which should be a boolean (and ideally 1, else
+    // soundness is broken)
+    let mut z = Vec::with_capacity(n);
+    z.push(C::Scalar::ONE);
+    z.extend_from_slice(&shuffle_product[..n - blinding_factors - 1]);
+    prefix_product(&mut z);
+    // Chain random blinding factors.
+    z.extend(blinds.rows);
+    assert_eq!(z.len(), n);
     let z = pk.vk.domain.lagrange_from_vec(z);
 
     #[cfg(feature = "sanity-checks")]
     {
         // While in Lagrange basis, check that product is correctly constructed
-        let u = (params.n() as usize) - (blinding_factors + 1);
+        let u = n - (blinding_factors + 1);
         assert_eq!(z[0], C::Scalar::ONE);
         for i in 0..u {
             let mut left = z[i + 1];
@@ -187,20 +194,43 @@ where
         assert_eq!(z[u], C::Scalar::ONE);
     }
 
-    let product_blind = Blind(C::Scalar::random(rng));
-    let product_commitment = params.commit_lagrange(&z, product_blind).to_affine();
-    let z = pk.vk.domain.lagrange_to_coeff(z);
+    Product {
+        z,
+        blind: blinds.blind,
+    }
+}
 
-    // Hash product commitment
-    transcript.write_point(product_commitment)?;
+impl<C: CurveAffine> Product<C> {
+    /// Commits to the grand product column.
+    pub(in crate::plonk) fn commit<'params, P: Params<'params, C>>(
+        self,
+        pk: &ProvingKey<C>,
+        params: &P,
+        engine: &dyn PolynomialEngine<C>,
+    ) -> Committed<C> {
+        let product_commitment = params
+            .commit_lagrange_with_engine(engine, &self.z, self.blind)
+            .to_affine();
+        let z = pk.vk.domain.lagrange_to_coeff_with_engine(engine, self.z);
 
-    Ok(Committed::<C> {
-        product_poly: z,
-        product_blind,
-    })
+        Committed::<C> {
+            product_poly: z,
+            product_blind: self.blind,
+            product_commitment,
+        }
+    }
 }
 
 impl<C: CurveAffine> Committed<C> {
+    /// Hashes the commitment to the product column.
+    pub(in crate::plonk) fn write_commitment<E: EncodedChallenge<C>, T: TranscriptWrite<C, E>>(
+        &self,
+        transcript: &mut T,
+    ) -> Result<(), Error> {
+        transcript.write_point(self.product_commitment)?;
+        Ok(())
+    }
+
     pub(in crate::plonk) fn evaluate<E: EncodedChallenge<C>, T: TranscriptWrite<C, E>>(
         self,
         pk: &ProvingKey<C>,
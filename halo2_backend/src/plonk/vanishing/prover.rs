@@ -1,18 +1,17 @@
-use std::{collections::HashMap, iter};
+use std::iter;
 
 use group::Curve;
 use halo2_common::plonk::{ChallengeX, Error};
 use halo2_middleware::ff::Field;
-use rand_chacha::ChaCha20Rng;
-use rand_core::{RngCore, SeedableRng};
+use rand_core::RngCore;
 
 use super::Argument;
 use crate::{
-    arithmetic::{eval_polynomial, parallelize, CurveAffine},
-    multicore::current_num_threads,
+    arithmetic::{eval_polynomial, CurveAffine},
+    plonk::blinder::Blinder,
     poly::{
         commitment::{Blind, ParamsProver},
-        Coeff, EvaluationDomain, ExtendedLagrangeCoeff, Polynomial, ProverQuery,
+        Coeff, EvaluationDomain, ExtendedLagrangeCoeff, Polynomial, PolynomialEngine, ProverQuery,
     },
     transcript::{EncodedChallenge, TranscriptWrite},
 };
@@ -43,47 +42,25 @@ impl<C: CurveAffine> Argument<C> {
         T: TranscriptWrite<C, E>,
     >(
         params: &P,
+        engine: &dyn PolynomialEngine<C>,
         domain: &EvaluationDomain<C::Scalar>,
-        mut rng: R,
+        rng: &mut Blinder<R>,
         transcript: &mut T,
     ) -> Result<Committed<C>, Error> {
         // Sample a random polynomial of degree n - 1
         let n = 1usize << domain.k() as usize;
         let mut rand_vec = vec![C::Scalar::ZERO; n];
-
-        let num_threads = current_num_threads();
-        let chunk_size = n / num_threads;
-        let thread_seeds = (0..)
-            .step_by(chunk_size + 1)
-            .take(n % num_threads)
-            .chain(
-                (chunk_size != 0)
-                    .then(|| ((n % num_threads) * (chunk_size + 1)..).step_by(chunk_size))
-                    .into_iter()
-                    .flatten(),
-            )
-            .take(num_threads)
-            .zip(iter::repeat_with(|| {
-                let mut seed = [0u8; 32];
-                rng.fill_bytes(&mut seed);
-                ChaCha20Rng::from_seed(seed)
-            }))
-            .collect::<HashMap<_, _>>();
-
-        parallelize(&mut rand_vec, |chunk, offset| {
-            let mut rng = thread_seeds[&offset].clone();
-            chunk
-                .iter_mut()
-                .for_each(|v| *v = C::Scalar::random(&mut rng));
-        });
+        rng.fill(&mut rand_vec);
 
         let random_poly: Polynomial<C::Scalar, Coeff> = domain.coeff_from_vec(rand_vec);
 
         // Sample a random blinding factor
-        let random_blind = Blind(C::Scalar::random(rng));
+        let random_blind = Blind(rng.blind());
 
         // Commit
-        let c = params.commit(&random_poly, random_blind).to_affine();
+        let c = params
+            .commit_with_engine(engine, &random_poly, random_blind)
+            .to_affine();
         transcript.write_point(c)?;
 
         Ok(Committed {
@@ -103,16 +80,17 @@ impl<C: CurveAffine> Committed<C> {
     >(
         self,
         params: &P,
+        engine: &dyn PolynomialEngine<C>,
         domain: &EvaluationDomain<C::Scalar>,
         h_poly: Polynomial<C::Scalar, ExtendedLagrangeCoeff>,
-        mut rng: R,
+        rng: &mut Blinder<R>,
         transcript: &mut T,
     ) -> Result<Constructed<C>, Error> {
         // Divide by t(X) = X^{params.n} - 1.
         let h_poly = domain.divide_by_vanishing_poly(h_poly);
 
         // Obtain final h(X) polynomial
-        let h_poly = domain.extended_to_coeff(h_poly);
+        let h_poly = domain.extended_to_coeff_with_engine(engine, h_poly);
 
         // Split h(X) up into pieces
         let h_pieces = h_poly
@@ -120,16 +98,13 @@ impl<C: CurveAffine> Committed<C> {
             .map(|v| domain.coeff_from_vec(v.to_vec()))
             .collect::<Vec<_>>();
         drop(h_poly);
-        let h_blinds: Vec<_> = h_pieces
-            .iter()
-            .map(|_| Blind(C::Scalar::random(&mut rng)))
-            .collect();
+        let h_blinds: Vec<_> = h_pieces.iter().map(|_| Blind(rng.blind())).collect();
 
         // Compute commitments to each h(X) piece
         let h_commitments_projective: Vec<_> = h_pieces
             .iter()
             .zip(h_blinds.iter())
-            .map(|(h_piece, blind)| params.commit(h_piece, *blind))
+            .map(|(h_piece, blind)| params.commit_with_engine(engine, h_piece, *blind))
             .collect();
         let mut h_commitments = vec![C::identity(); h_commitments_projective.len()];
         C::Curve::batch_normalize(&h_commitments_projective, &mut h_commitments);
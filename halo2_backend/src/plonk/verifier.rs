@@ -1,12 +1,13 @@
 use group::Curve;
 use halo2_common::plonk::{
     ChallengeBeta, ChallengeGamma, ChallengeTheta, ChallengeX, ChallengeY, Error,
+    VerificationError, VerificationStage,
 };
 use halo2_middleware::ff::{Field, FromUniformBytes, WithSmallOrderMulGroup};
-use std::iter;
+use std::{io, iter};
 
 use super::{vanishing, VerifyingKey};
-use crate::arithmetic::compute_inner_product;
+use crate::arithmetic::{compute_inner_product, CurveAffine};
 use crate::plonk::lookup::verifier::lookup_read_permuted_commitments;
 use crate::plonk::permutation::verifier::permutation_read_product_commitments;
 use crate::plonk::shuffle::verifier::shuffle_read_product_commitment;
@@ -16,13 +17,19 @@ use crate::poly::{
     commitment::{Blind, Params},
     VerifierQuery,
 };
-use crate::transcript::{read_n_scalars, EncodedChallenge, TranscriptRead};
+use crate::transcript::{read_n_points, read_n_scalars, EncodedChallenge, TranscriptRead};
 
 #[cfg(feature = "batch")]
 mod batch;
 #[cfg(feature = "batch")]
 pub use batch::BatchVerifier;
 
+mod batch_kzg;
+pub use batch_kzg::{
+    verify_proofs_batch, verify_proofs_batch_gwc, verify_proofs_batch_shplonk, BatchVerifierGWC,
+    BatchVerifierKZG, BatchVerifierSHPLONK,
+};
+
 /// Returns a boolean indicating whether or not the proof is valid.  Verifies a single proof (not
 /// batched).
 pub fn verify_proof_single<
@@ -38,7 +45,7 @@ pub fn verify_proof_single<
     strategy: Strategy,
     instance: &[&[Scheme::Scalar]],
     transcript: &mut T,
-) -> Result<Strategy::Output, Error>
+) -> Result<Strategy::Output, VerificationError>
 where
     Scheme::Scalar: WithSmallOrderMulGroup<3> + FromUniformBytes<64>,
 {
@@ -46,6 +53,9 @@ where
 }
 
 /// Returns a boolean indicating whether or not the proof is valid
+///
+/// The returned [`VerificationError`] identifies the check that failed, and converts into
+/// the [`Error`] that this returned before.
 pub fn verify_proof<
     'params,
     Scheme: CommitmentScheme,
@@ -59,67 +69,215 @@ pub fn verify_proof<
     strategy: Strategy,
     instances: &[&[&[Scheme::Scalar]]],
     transcript: &mut T,
-) -> Result<Strategy::Output, Error>
+) -> Result<Strategy::Output, VerificationError>
+where
+    Scheme::Scalar: WithSmallOrderMulGroup<3> + FromUniformBytes<64>,
+{
+    verify_proof_inner(
+        params,
+        vk,
+        strategy,
+        Instances::Values(instances),
+        transcript,
+    )
+}
+
+/// Verifies proofs for a verifying key which commits to the instance columns, given the
+/// commitments to these columns instead of their values.
+///
+/// The instance columns are neither committed nor evaluated by the verifier: the openings
+/// of the proof are only checked against `instance_commitments`, one commitment per
+/// instance column and per proof. Returns [`VerificationError::InvalidInstances`] if the
+/// verifying key doesn't commit to the instance columns.
+pub fn verify_proof_with_instance_commitments<
+    'params,
+    Scheme: CommitmentScheme,
+    V: Verifier<'params, Scheme>,
+    E: EncodedChallenge<Scheme::Curve>,
+    T: TranscriptRead<Scheme::Curve, E>,
+    Strategy: VerificationStrategy<'params, Scheme, V>,
+>(
+    params: &'params Scheme::ParamsVerifier,
+    vk: &VerifyingKey<Scheme::Curve>,
+    strategy: Strategy,
+    instance_commitments: &[&[Scheme::Curve]],
+    transcript: &mut T,
+) -> Result<Strategy::Output, VerificationError>
+where
+    Scheme::Scalar: WithSmallOrderMulGroup<3> + FromUniformBytes<64>,
+{
+    if !vk.commit_instances {
+        return Err(VerificationError::InvalidInstances);
+    }
+    verify_proof_inner(
+        params,
+        vk,
+        strategy,
+        Instances::Commitments(instance_commitments),
+        transcript,
+    )
+}
+
+/// The instances of the proofs to verify.
+#[derive(Clone, Copy)]
+enum Instances<'a, C: CurveAffine> {
+    /// The values of the instance columns.
+    Values(&'a [&'a [&'a [C::Scalar]]]),
+    /// The commitments to the instance columns, for keys which commit to them.
+    Commitments(&'a [&'a [C]]),
+}
+
+impl<'a, C: CurveAffine> Instances<'a, C> {
+    fn num_proofs(&self) -> usize {
+        match self {
+            Instances::Values(instances) => instances.len(),
+            Instances::Commitments(commitments) => commitments.len(),
+        }
+    }
+
+    /// Returns the number of instance columns given for each proof.
+    fn num_columns(&self) -> Vec<usize> {
+        match self {
+            Instances::Values(instances) => instances.iter().map(|i| i.len()).collect(),
+            Instances::Commitments(commitments) => commitments.iter().map(|c| c.len()).collect(),
+        }
+    }
+
+    /// Returns the values of the instance columns, which are given unless the verifying key
+    /// commits to the instance columns.
+    fn values(&self) -> &'a [&'a [&'a [C::Scalar]]] {
+        match *self {
+            Instances::Values(instances) => instances,
+            Instances::Commitments(_) => {
+                unreachable!("only committed instance columns are verified from commitments")
+            }
+        }
+    }
+}
+
+fn verify_proof_inner<
+    'params,
+    Scheme: CommitmentScheme,
+    V: Verifier<'params, Scheme>,
+    E: EncodedChallenge<Scheme::Curve>,
+    T: TranscriptRead<Scheme::Curve, E>,
+    Strategy: VerificationStrategy<'params, Scheme, V>,
+>(
+    params: &'params Scheme::ParamsVerifier,
+    vk: &VerifyingKey<Scheme::Curve>,
+    strategy: Strategy,
+    instances: Instances<'_, Scheme::Curve>,
+    transcript: &mut T,
+) -> Result<Strategy::Output, VerificationError>
 where
     Scheme::Scalar: WithSmallOrderMulGroup<3> + FromUniformBytes<64>,
 {
     // Check that instances matches the expected number of instance columns
-    for instances in instances.iter() {
-        if instances.len() != vk.cs.num_instance_columns {
-            return Err(Error::InvalidInstances);
+    if instances
+        .num_columns()
+        .into_iter()
+        .any(|num_columns| num_columns != vk.cs.num_instance_columns)
+    {
+        return Err(VerificationError::InvalidInstances);
+    }
+
+    let commit_instances = vk.commit_instances;
+    let query_instance = V::QUERY_INSTANCE || commit_instances;
+    if let Instances::Values(instances) = instances {
+        if query_instance {
+            for instance in instances.iter().flat_map(|instance| instance.iter()) {
+                if instance.len() > params.n() as usize - (vk.cs.blinding_factors() + 1) {
+                    return Err(VerificationError::InstanceTooLarge);
+                }
+            }
         }
     }
 
-    let instance_commitments = if V::QUERY_INSTANCE {
+    let num_proofs = instances.num_proofs();
+
+    // Commits to the instance columns from their values.
+    let commit_instance_values = |instances: &[&[&[Scheme::Scalar]]]| {
         instances
             .iter()
             .map(|instance| {
                 instance
                     .iter()
                     .map(|instance| {
-                        if instance.len() > params.n() as usize - (vk.cs.blinding_factors() + 1) {
-                            return Err(Error::InstanceTooLarge);
-                        }
                         let mut poly = instance.to_vec();
                         poly.resize(params.n() as usize, Scheme::Scalar::ZERO);
                         let poly = vk.domain.lagrange_from_vec(poly);
 
-                        Ok(params.commit_lagrange(&poly, Blind::default()).to_affine())
+                        params.commit_lagrange(&poly, Blind::default()).to_affine()
                     })
-                    .collect::<Result<Vec<_>, _>>()
+                    .collect::<Vec<_>>()
             })
-            .collect::<Result<Vec<_>, _>>()?
-    } else {
-        vec![vec![]; instances.len()]
+            .collect::<Vec<_>>()
     };
 
-    let num_proofs = instance_commitments.len();
-
     // Hash verification key into transcript
-    vk.hash_into(transcript)?;
-
-    if V::QUERY_INSTANCE {
+    vk.hash_into(transcript)
+        .map_err(read_error(VerificationStage::Instances))?;
+
+    let instance_commitments = if commit_instances {
+        // The commitments are read from the proof, and must be the commitments to the
+        // instances. The openings of the instance columns are then checked against them
+        // by the multi-open argument.
+        let marker = transcript
+            .read_point()
+            .map_err(read_error(VerificationStage::Instances))?;
+        if marker != VerifyingKey::<Scheme::Curve>::instance_mode_marker() {
+            return Err(VerificationError::InstanceModeMismatch {
+                expected_commit_instances: true,
+            });
+        }
+        let instance_commitments = (0..num_proofs)
+            .map(|_| read_n_points(transcript, vk.cs.num_instance_columns))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(read_error(VerificationStage::Instances))?;
+        let matches = match instances {
+            Instances::Values(instances) => {
+                instance_commitments == commit_instance_values(instances)
+            }
+            Instances::Commitments(expected) => instance_commitments
+                .iter()
+                .zip(expected.iter())
+                .all(|(commitments, expected)| commitments[..] == expected[..]),
+        };
+        // The proof was created for other instances.
+        if !matches {
+            return Err(VerificationError::FinalCheck);
+        }
+        instance_commitments
+    } else if query_instance {
+        let instance_commitments = commit_instance_values(instances.values());
         for instance_commitments in instance_commitments.iter() {
             // Hash the instance (external) commitments into the transcript
             for commitment in instance_commitments {
-                transcript.common_point(*commitment)?
+                transcript
+                    .common_point(*commitment)
+                    .map_err(read_error(VerificationStage::Instances))?;
             }
         }
+        instance_commitments
     } else {
-        for instance in instances.iter() {
+        for instance in instances.values().iter() {
             for instance in instance.iter() {
                 for value in instance.iter() {
-                    transcript.common_scalar(*value)?;
+                    transcript
+                        .common_scalar(*value)
+                        .map_err(read_error(VerificationStage::Instances))?;
                 }
             }
         }
-    }
+        vec![vec![]; num_proofs]
+    };
 
     // Hash the prover's advice commitments into the transcript and squeeze challenges
     let (advice_commitments, challenges) = {
         let mut advice_commitments =
             vec![vec![Scheme::Curve::default(); vk.cs.num_advice_columns]; num_proofs];
         let mut challenges = vec![Scheme::Scalar::ZERO; vk.cs.num_challenges];
+        let mut first_point = true;
 
         for current_phase in vk.cs.phases() {
             for advice_commitments in advice_commitments.iter_mut() {
@@ -130,7 +288,20 @@ where
                     .zip(advice_commitments.iter_mut())
                 {
                     if current_phase == *phase {
-                        *commitment = transcript.read_point()?;
+                        *commitment = transcript
+                            .read_point()
+                            .map_err(read_error(VerificationStage::AdviceCommitments))?;
+                        // The proofs which commit to the instance columns start with the
+                        // marker instead.
+                        if !commit_instances
+                            && first_point
+                            && *commitment == VerifyingKey::<Scheme::Curve>::instance_mode_marker()
+                        {
+                            return Err(VerificationError::InstanceModeMismatch {
+                                expected_commit_instances: false,
+                            });
+                        }
+                        first_point = false;
                     }
                 }
             }
@@ -156,7 +327,8 @@ where
                 .map(|_argument| lookup_read_permuted_commitments(transcript))
                 .collect::<Result<Vec<_>, _>>()
         })
-        .collect::<Result<Vec<_>, _>>()?;
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(read_error(VerificationStage::LookupPermutedCommitments))?;
 
     // Sample beta challenge
     let beta: ChallengeBeta<_> = transcript.squeeze_challenge_scalar();
@@ -169,7 +341,8 @@ where
             // Hash each permutation product commitment
             permutation_read_product_commitments(&vk.cs.permutation, vk, transcript)
         })
-        .collect::<Result<Vec<_>, _>>()?;
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(read_error(VerificationStage::PermutationProductCommitments))?;
 
     let lookups_committed = lookups_permuted
         .into_iter()
@@ -180,7 +353,8 @@ where
                 .map(|lookup| lookup.read_product_commitment(transcript))
                 .collect::<Result<Vec<_>, _>>()
         })
-        .collect::<Result<Vec<_>, _>>()?;
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(read_error(VerificationStage::LookupProductCommitments))?;
 
     let shuffles_committed = (0..num_proofs)
         .map(|_| -> Result<Vec<_>, _> {
@@ -191,25 +365,32 @@ where
                 .map(|_argument| shuffle_read_product_commitment(transcript))
                 .collect::<Result<Vec<_>, _>>()
         })
-        .collect::<Result<Vec<_>, _>>()?;
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(read_error(VerificationStage::ShuffleProductCommitments))?;
 
-    let vanishing = vanishing::Argument::read_commitments_before_y(transcript)?;
+    let vanishing = vanishing::Argument::read_commitments_before_y(transcript)
+        .map_err(read_error(VerificationStage::VanishingCommitments))?;
 
     // Sample y challenge, which keeps the gates linearly independent.
     let y: ChallengeY<_> = transcript.squeeze_challenge_scalar();
 
-    let vanishing = vanishing.read_commitments_after_y(vk, transcript)?;
+    let vanishing = vanishing
+        .read_commitments_after_y(vk, transcript)
+        .map_err(read_error(VerificationStage::VanishingCommitments))?;
 
     // Sample x challenge, which is used to ensure the circuit is
     // satisfied with high probability.
     let x: ChallengeX<_> = transcript.squeeze_challenge_scalar();
-    let instance_evals = if V::QUERY_INSTANCE {
+    let instance_evals = if query_instance {
         (0..num_proofs)
             .map(|_| -> Result<Vec<_>, _> {
                 read_n_scalars(transcript, vk.cs.instance_queries.len())
             })
-            .collect::<Result<Vec<_>, _>>()?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(read_error(VerificationStage::InstanceEvaluations))?
     } else {
+        // Evaluate the instance polynomials at their queries from the instance values.
+        let instances = instances.values();
         let xn = x.pow([params.n()]);
         let (min_rotation, max_rotation) =
             vk.cs
@@ -252,18 +433,26 @@ where
 
     let advice_evals = (0..num_proofs)
         .map(|_| -> Result<Vec<_>, _> { read_n_scalars(transcript, vk.cs.advice_queries.len()) })
-        .collect::<Result<Vec<_>, _>>()?;
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(read_error(VerificationStage::AdviceEvaluations))?;
 
-    let fixed_evals = read_n_scalars(transcript, vk.cs.fixed_queries.len())?;
+    let fixed_evals = read_n_scalars(transcript, vk.cs.fixed_queries.len())
+        .map_err(read_error(VerificationStage::FixedEvaluations))?;
 
-    let vanishing = vanishing.evaluate_after_x(transcript)?;
+    let vanishing = vanishing
+        .evaluate_after_x(transcript)
+        .map_err(read_error(VerificationStage::VanishingEvaluations))?;
 
-    let permutations_common = vk.permutation.evaluate(transcript)?;
+    let permutations_common = vk
+        .permutation
+        .evaluate(transcript)
+        .map_err(read_error(VerificationStage::PermutationEvaluations))?;
 
     let permutations_evaluated = permutations_committed
         .into_iter()
         .map(|permutation| permutation.evaluate(transcript))
-        .collect::<Result<Vec<_>, _>>()?;
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(read_error(VerificationStage::PermutationEvaluations))?;
 
     let lookups_evaluated = lookups_committed
         .into_iter()
@@ -273,7 +462,8 @@ where
                 .map(|lookup| lookup.evaluate(transcript))
                 .collect::<Result<Vec<_>, _>>()
         })
-        .collect::<Result<Vec<_>, _>>()?;
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(read_error(VerificationStage::LookupEvaluations))?;
 
     let shuffles_evaluated = shuffles_committed
         .into_iter()
@@ -283,7 +473,8 @@ where
                 .map(|shuffle| shuffle.evaluate(transcript))
                 .collect::<Result<Vec<_>, _>>()
         })
-        .collect::<Result<Vec<_>, _>>()?;
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(read_error(VerificationStage::ShuffleEvaluations))?;
 
     // This check ensures the circuit is satisfied so long as the polynomial
     // commitments open to the correct values.
@@ -410,7 +601,7 @@ where
             )| {
                 iter::empty()
                     .chain(
-                        V::QUERY_INSTANCE
+                        query_instance
                             .then_some(vk.cs.instance_queries.iter().enumerate().map(
                                 move |(query_index, &(column, at))| {
                                     VerifierQuery::new_commitment(
@@ -457,9 +648,25 @@ where
     // polynomial commitments open to the correct values.
 
     let verifier = V::new(params);
-    strategy.process(|msm| {
-        verifier
-            .verify_proof(transcript, queries, msm)
-            .map_err(|_| Error::Opening)
-    })
+    strategy
+        .process(|msm| {
+            verifier
+                .verify_proof(transcript, queries, msm)
+                .map_err(|_| Error::Opening)
+        })
+        .map_err(|error| match error {
+            Error::Opening => VerificationError::Opening,
+            _ => VerificationError::FinalCheck,
+        })
+}
+
+/// Attributes a failure to read the proof to `stage`.
+fn read_error<E: Into<Error>>(stage: VerificationStage) -> impl Fn(E) -> VerificationError {
+    move |error| VerificationError::Transcript {
+        stage,
+        error: match error.into() {
+            Error::Transcript(error) => error,
+            error => io::Error::new(io::ErrorKind::Other, error.to_string()),
+        },
+    }
 }
@@ -0,0 +1,340 @@
+use std::fmt::Debug;
+use std::marker::PhantomData;
+
+use halo2_common::plonk::Error;
+use halo2_middleware::ff::{Field, FromUniformBytes, WithSmallOrderMulGroup};
+use halo2curves::{
+    pairing::{Engine, MultiMillerLoop},
+    CurveExt,
+};
+use rand_core::RngCore;
+
+use super::{verify_proof, VerificationStrategy};
+use crate::{
+    helpers::SerdeCurveAffine,
+    plonk::VerifyingKey,
+    poly::{
+        commitment::Verifier,
+        kzg::{
+            commitment::{KZGCommitmentScheme, ParamsKZG},
+            msm::DualMSM,
+            multiopen::{VerifierGWC, VerifierSHPLONK},
+            strategy::GuardKZG,
+        },
+    },
+    transcript::{Blake2bRead, Challenge255, TranscriptReadBuffer},
+};
+
+/// A proof verification strategy that returns the proof's MSM.
+///
+/// `BatchVerifierKZG` handles the accumulation of the MSMs for the batched proofs.
+#[derive(Debug)]
+struct BatchStrategyKZG<'params, E: Engine>
+where
+    E::G1Affine: SerdeCurveAffine<ScalarExt = <E as Engine>::Fr, CurveExt = <E as Engine>::G1>,
+    E::G1: CurveExt<AffineExt = E::G1Affine>,
+{
+    msm: DualMSM<'params, E>,
+}
+
+impl<
+        'params,
+        E: MultiMillerLoop + Debug,
+        V: Verifier<
+            'params,
+            KZGCommitmentScheme<E>,
+            MSMAccumulator = DualMSM<'params, E>,
+            Guard = GuardKZG<'params, E>,
+        >,
+    > VerificationStrategy<'params, KZGCommitmentScheme<E>, V> for BatchStrategyKZG<'params, E>
+where
+    E::G1Affine: SerdeCurveAffine<ScalarExt = <E as Engine>::Fr, CurveExt = <E as Engine>::G1>,
+    E::G1: CurveExt<AffineExt = E::G1Affine>,
+    E::G2Affine: SerdeCurveAffine,
+{
+    type Output = DualMSM<'params, E>;
+
+    fn new(params: &'params ParamsKZG<E>) -> Self {
+        BatchStrategyKZG {
+            msm: DualMSM::new(params),
+        }
+    }
+
+    fn process(
+        self,
+        f: impl FnOnce(V::MSMAccumulator) -> Result<V::Guard, Error>,
+    ) -> Result<Self::Output, Error> {
+        let guard = f(self.msm)?;
+        Ok(guard.msm_accumulator)
+    }
+
+    fn finalize(self) -> bool {
+        unreachable!()
+    }
+}
+
+/// A verifier that checks multiple KZG proofs for the same verifying key in a batch,
+/// with a single pairing check.
+///
+/// Proofs are read as they are added with [`BatchVerifierKZG::add_proof`], and their
+/// final MSMs are accumulated with random factors, so that the batch is only valid if
+/// every proof is valid (with high probability). The multi-open verifier `V` is one of
+/// [`VerifierGWC`] and [`VerifierSHPLONK`], and must match the prover used to create
+/// the proofs, as must the transcript, which is [`Blake2bRead`] with [`Challenge255`].
+#[derive(Debug)]
+pub struct BatchVerifierKZG<'params, E: Engine, V, R>
+where
+    E::G1Affine: SerdeCurveAffine<ScalarExt = <E as Engine>::Fr, CurveExt = <E as Engine>::G1>,
+    E::G1: CurveExt<AffineExt = E::G1Affine>,
+{
+    params: &'params ParamsKZG<E>,
+    vk: &'params VerifyingKey<E::G1Affine>,
+    rng: R,
+    /// The accumulated MSM of the proofs that were read.
+    msm: DualMSM<'params, E>,
+    /// The scaled MSM of each proof, or `None` if it could not be read. This is only kept
+    /// to bisect a failing batch.
+    proofs: Option<Vec<Option<DualMSM<'params, E>>>>,
+    /// Whether some proof could not be read.
+    unreadable: bool,
+    len: usize,
+    _marker: PhantomData<V>,
+}
+
+/// A [`BatchVerifierKZG`] for proofs created with the GWC multi-open prover.
+pub type BatchVerifierGWC<'params, E, R> = BatchVerifierKZG<'params, E, VerifierGWC<'params, E>, R>;
+
+/// A [`BatchVerifierKZG`] for proofs created with the SHPLONK multi-open prover.
+pub type BatchVerifierSHPLONK<'params, E, R> =
+    BatchVerifierKZG<'params, E, VerifierSHPLONK<'params, E>, R>;
+
+impl<'params, E, V, R> BatchVerifierKZG<'params, E, V, R>
+where
+    E: MultiMillerLoop + Debug,
+    E::G1Affine: SerdeCurveAffine<ScalarExt = <E as Engine>::Fr, CurveExt = <E as Engine>::G1>,
+    E::G1: CurveExt<AffineExt = E::G1Affine>,
+    E::G2Affine: SerdeCurveAffine,
+    E::Fr: WithSmallOrderMulGroup<3> + FromUniformBytes<64>,
+    V: Verifier<
+        'params,
+        KZGCommitmentScheme<E>,
+        MSMAccumulator = DualMSM<'params, E>,
+        Guard = GuardKZG<'params, E>,
+    >,
+    R: RngCore,
+{
+    /// Constructs an empty batch verifier, which samples the random factors of the
+    /// batched proofs from `rng`.
+    pub fn new(
+        params: &'params ParamsKZG<E>,
+        vk: &'params VerifyingKey<E::G1Affine>,
+        rng: R,
+    ) -> Self {
+        Self {
+            params,
+            vk,
+            rng,
+            msm: DualMSM::new(params),
+            proofs: None,
+            unreadable: false,
+            len: 0,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Keeps the MSM of each proof, so that [`BatchVerifierKZG::finalize`] can find the
+    /// invalid proofs of a failing batch by bisection.
+    ///
+    /// This must be called before any proof is added.
+    pub fn with_bisection(mut self) -> Self {
+        assert_eq!(
+            self.len, 0,
+            "bisection must be enabled before adding proofs"
+        );
+        self.proofs = Some(vec![]);
+        self
+    }
+
+    /// Returns the number of proofs in the batch.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if no proof was added to the batch.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Reads a proof of the circuits with the given `instances`, and adds it to the
+    /// batch.
+    ///
+    /// A proof that cannot be read makes the batch fail in
+    /// [`BatchVerifierKZG::finalize`].
+    pub fn add_proof(&mut self, instances: &[Vec<Vec<E::Fr>>], proof: &[u8]) {
+        let instances: Vec<Vec<_>> = instances
+            .iter()
+            .map(|i| i.iter().map(|c| &c[..]).collect())
+            .collect();
+        let instances: Vec<_> = instances.iter().map(|i| &i[..]).collect();
+
+        let strategy = BatchStrategyKZG::new(self.params);
+        let mut transcript = Blake2bRead::<_, _, Challenge255<_>>::init(proof);
+        let msm = verify_proof::<_, V, _, _, _>(
+            self.params,
+            self.vk,
+            strategy,
+            &instances,
+            &mut transcript,
+        )
+        .map(|mut msm| {
+            // Scale the MSM by a random factor to ensure that if the existing MSM has
+            // `is_zero() == false` then this argument won't be able to interfere with it
+            // to make it true, with high probability.
+            msm.scale(E::Fr::random(&mut self.rng));
+            msm
+        })
+        .map_err(|e| {
+            tracing::debug!("Batch item {} could not be read: {}", self.len, e);
+            e
+        })
+        .ok();
+
+        if let Some(proofs) = &mut self.proofs {
+            proofs.push(msm.clone());
+        }
+        match msm {
+            Some(msm) => self.msm.add_msm(msm),
+            None => self.unreadable = true,
+        }
+        self.len += 1;
+    }
+
+    /// Checks the batch with a single pairing check.
+    ///
+    /// Returns [`Error::BatchVerificationFailed`] if *some* proof is invalid. If
+    /// bisection was enabled with [`BatchVerifierKZG::with_bisection`], the error
+    /// contains the indices of the invalid proofs, in the order in which they were
+    /// added. An empty batch is valid.
+    pub fn finalize(self) -> Result<(), Error> {
+        if !self.unreadable && self.msm.check() {
+            return Ok(());
+        }
+
+        let invalid = self.proofs.map(|proofs| {
+            let mut invalid = vec![];
+            bisect(self.params, &proofs, 0, &mut invalid);
+            invalid
+        });
+        Err(Error::BatchVerificationFailed { invalid })
+    }
+}
+
+/// Returns `true` if all the `proofs` could be read and are valid together.
+fn check_all<'params, E: MultiMillerLoop + Debug>(
+    params: &'params ParamsKZG<E>,
+    proofs: &[Option<DualMSM<'params, E>>],
+) -> bool
+where
+    E::G1Affine: SerdeCurveAffine<ScalarExt = <E as Engine>::Fr, CurveExt = <E as Engine>::G1>,
+    E::G1: CurveExt<AffineExt = E::G1Affine>,
+{
+    proofs
+        .iter()
+        .try_fold(DualMSM::new(params), |mut acc, msm| {
+            acc.add_msm(msm.clone()?);
+            Some(acc)
+        })
+        .map_or(false, DualMSM::check)
+}
+
+/// Appends to `invalid` the indices (offset by `offset`) of the invalid proofs among
+/// `proofs`, which contain at least one, by recursively checking halves of them.
+fn bisect<'params, E: MultiMillerLoop + Debug>(
+    params: &'params ParamsKZG<E>,
+    proofs: &[Option<DualMSM<'params, E>>],
+    offset: usize,
+    invalid: &mut Vec<usize>,
+) where
+    E::G1Affine: SerdeCurveAffine<ScalarExt = <E as Engine>::Fr, CurveExt = <E as Engine>::G1>,
+    E::G1: CurveExt<AffineExt = E::G1Affine>,
+{
+    if proofs.len() == 1 {
+        invalid.push(offset);
+        return;
+    }
+    let (left, right) = proofs.split_at(proofs.len() / 2);
+    for (half, offset) in [(left, offset), (right, offset + left.len())] {
+        if !check_all(params, half) {
+            bisect(params, half, offset, invalid);
+        }
+    }
+}
+
+/// Verifies a batch of KZG proofs for the same verifying key, each given with the
+/// instances of its circuits, with a single pairing check.
+///
+/// See [`BatchVerifierKZG`] for the choice of `V`; [`verify_proofs_batch_gwc`] and
+/// [`verify_proofs_batch_shplonk`] fix it. Returns
+/// [`Error::BatchVerificationFailed`] if some proof is invalid.
+pub fn verify_proofs_batch<'params, E, V, R>(
+    params: &'params ParamsKZG<E>,
+    vk: &'params VerifyingKey<E::G1Affine>,
+    proofs: &[(Vec<Vec<Vec<E::Fr>>>, Vec<u8>)],
+    rng: R,
+) -> Result<(), Error>
+where
+    E: MultiMillerLoop + Debug,
+    E::G1Affine: SerdeCurveAffine<ScalarExt = <E as Engine>::Fr, CurveExt = <E as Engine>::G1>,
+    E::G1: CurveExt<AffineExt = E::G1Affine>,
+    E::G2Affine: SerdeCurveAffine,
+    E::Fr: WithSmallOrderMulGroup<3> + FromUniformBytes<64>,
+    V: Verifier<
+        'params,
+        KZGCommitmentScheme<E>,
+        MSMAccumulator = DualMSM<'params, E>,
+        Guard = GuardKZG<'params, E>,
+    >,
+    R: RngCore,
+{
+    let mut batch = BatchVerifierKZG::<E, V, R>::new(params, vk, rng);
+    for (instances, proof) in proofs {
+        batch.add_proof(instances, proof);
+    }
+    batch.finalize()
+}
+
+/// [`verify_proofs_batch`] for proofs created with the GWC multi-open prover.
+pub fn verify_proofs_batch_gwc<'params, E, R>(
+    params: &'params ParamsKZG<E>,
+    vk: &'params VerifyingKey<E::G1Affine>,
+    proofs: &[(Vec<Vec<Vec<E::Fr>>>, Vec<u8>)],
+    rng: R,
+) -> Result<(), Error>
+where
+    E: MultiMillerLoop + Debug,
+    E::G1Affine: SerdeCurveAffine<ScalarExt = <E as Engine>::Fr, CurveExt = <E as Engine>::G1>,
+    E::G1: CurveExt<AffineExt = E::G1Affine>,
+    E::G2Affine: SerdeCurveAffine,
+    E::Fr: WithSmallOrderMulGroup<3> + FromUniformBytes<64>,
+    R: RngCore,
+{
+    verify_proofs_batch::<E, VerifierGWC<'params, E>, R>(params, vk, proofs, rng)
+}
+
+/// [`verify_proofs_batch`] for proofs created with the SHPLONK multi-open prover.
+pub fn verify_proofs_batch_shplonk<'params, E, R>(
+    params: &'params ParamsKZG<E>,
+    vk: &'params VerifyingKey<E::G1Affine>,
+    proofs: &[(Vec<Vec<Vec<E::Fr>>>, Vec<u8>)],
+    rng: R,
+) -> Result<(), Error>
+where
+    E: MultiMillerLoop + Debug,
+    E::G1Affine: SerdeCurveAffine<ScalarExt = <E as Engine>::Fr, CurveExt = <E as Engine>::G1>,
+    E::G1: CurveExt<AffineExt = E::G1Affine>,
+    E::G2Affine: SerdeCurveAffine,
+    E::Fr: WithSmallOrderMulGroup<3> + FromUniformBytes<64>,
+    R: RngCore,
+{
+    verify_proofs_batch::<E, VerifierSHPLONK<'params, E>, R>(params, vk, proofs, rng)
+}
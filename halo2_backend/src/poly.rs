@@ -4,7 +4,7 @@
 
 use crate::arithmetic::parallelize;
 use crate::SerdeFormat;
-use halo2_common::helpers::SerdePrimeField;
+use halo2_common::helpers::{scalars_from_slice, split_slice, SerdePrimeField};
 
 use group::ff::Field;
 use halo2_middleware::poly::Rotation;
@@ -16,6 +16,7 @@ use std::ops::{Add, Deref, DerefMut, Index, IndexMut, Mul, RangeFrom, RangeFull,
 /// Generic commitment scheme structures
 pub mod commitment;
 mod domain;
+mod engine;
 mod query;
 mod strategy;
 
@@ -29,6 +30,7 @@ pub mod kzg;
 mod multiopen_test;
 
 pub use domain::*;
+pub use engine::{CpuEngine, PolynomialEngine};
 pub use query::{ProverQuery, VerifierQuery};
 pub use strategy::{Guard, VerificationStrategy};
 
@@ -186,6 +188,18 @@ impl<F: SerdePrimeField, B> Polynomial<F, B> {
             })
     }
 
+    /// Parses a polynomial from the front of `bytes`, in the format of [`Self::read`], and
+    /// advances `bytes` past it. See [`scalars_from_slice`].
+    pub(crate) fn from_slice(bytes: &mut &[u8], format: SerdeFormat) -> io::Result<Self> {
+        let poly_len = split_slice(bytes, 4)?;
+        let poly_len = u32::from_be_bytes(poly_len.try_into().expect("4 bytes were split"));
+
+        Ok(Self {
+            values: scalars_from_slice(bytes, poly_len as usize, format)?,
+            _marker: PhantomData,
+        })
+    }
+
     /// Writes polynomial to buffer using `SerdePrimeField::write`.  
     pub(crate) fn write<W: io::Write>(
         &self,
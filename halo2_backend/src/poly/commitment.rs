@@ -1,7 +1,7 @@
 use super::{
     query::{ProverQuery, VerifierQuery},
     strategy::Guard,
-    Coeff, LagrangeCoeff, Polynomial,
+    Coeff, CpuEngine, LagrangeCoeff, Polynomial, PolynomialEngine,
 };
 use crate::poly::Error;
 use crate::transcript::{EncodedChallenge, TranscriptRead, TranscriptWrite};
@@ -64,6 +64,16 @@ pub trait Params<'params, C: CurveAffine>: Sized + Clone + Debug {
         &self,
         poly: &Polynomial<C::ScalarExt, LagrangeCoeff>,
         r: Blind<C::ScalarExt>,
+    ) -> C::CurveExt {
+        self.commit_lagrange_with_engine(&CpuEngine, poly, r)
+    }
+
+    /// [`Params::commit_lagrange`], with the multi-scalar multiplication done by `engine`.
+    fn commit_lagrange_with_engine(
+        &self,
+        engine: &dyn PolynomialEngine<C>,
+        poly: &Polynomial<C::ScalarExt, LagrangeCoeff>,
+        r: Blind<C::ScalarExt>,
     ) -> C::CurveExt;
 
     /// Writes params to a buffer.
@@ -84,8 +94,21 @@ pub trait ParamsProver<'params, C: CurveAffine>: Params<'params, C> {
     /// This computes a commitment to a polynomial described by the provided
     /// slice of coefficients. The commitment may be blinded by the blinding
     /// factor `r`.
-    fn commit(&self, poly: &Polynomial<C::ScalarExt, Coeff>, r: Blind<C::ScalarExt>)
-        -> C::CurveExt;
+    fn commit(
+        &self,
+        poly: &Polynomial<C::ScalarExt, Coeff>,
+        r: Blind<C::ScalarExt>,
+    ) -> C::CurveExt {
+        self.commit_with_engine(&CpuEngine, poly, r)
+    }
+
+    /// [`ParamsProver::commit`], with the multi-scalar multiplication done by `engine`.
+    fn commit_with_engine(
+        &self,
+        engine: &dyn PolynomialEngine<C>,
+        poly: &Polynomial<C::ScalarExt, Coeff>,
+        r: Blind<C::ScalarExt>,
+    ) -> C::CurveExt;
 
     /// Getter for g generators
     fn get_g(&self) -> &[C];
@@ -144,6 +167,28 @@ pub trait Prover<'params, Scheme: CommitmentScheme> {
         transcript: &mut T,
         queries: I,
     ) -> io::Result<()>
+    where
+        I: IntoIterator<Item = ProverQuery<'com, Scheme::Curve>> + Clone,
+        R: RngCore,
+    {
+        self.create_proof_with_engine(&CpuEngine, rng, transcript, queries)
+    }
+
+    /// Create a multi-opening proof, with the multi-scalar multiplications done by
+    /// `engine`
+    fn create_proof_with_engine<
+        'com,
+        E: EncodedChallenge<Scheme::Curve>,
+        T: TranscriptWrite<Scheme::Curve, E>,
+        R,
+        I,
+    >(
+        &self,
+        engine: &dyn PolynomialEngine<Scheme::Curve>,
+        rng: R,
+        transcript: &mut T,
+        queries: I,
+    ) -> io::Result<()>
     where
         I: IntoIterator<Item = ProverQuery<'com, Scheme::Curve>> + Clone,
         R: RngCore;
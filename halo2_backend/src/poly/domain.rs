@@ -1,9 +1,9 @@
 //! Contains utilities for performing polynomial arithmetic over an evaluation
 //! domain that is of a suitable size for the application.
 
-use crate::arithmetic::{best_fft, parallelize};
+use crate::arithmetic::{best_fft, parallelize, CurveAffine};
 
-use super::{Coeff, ExtendedLagrangeCoeff, LagrangeCoeff, Polynomial};
+use super::{Coeff, ExtendedLagrangeCoeff, LagrangeCoeff, Polynomial, PolynomialEngine};
 use group::ff::{BatchInvert, Field};
 use halo2_middleware::ff::WithSmallOrderMulGroup;
 use halo2_middleware::poly::Rotation;
@@ -212,11 +212,30 @@ impl<F: WithSmallOrderMulGroup<3>> EvaluationDomain<F> {
     ///
     /// This function will panic if the provided vector is not the correct
     /// length.
-    pub fn lagrange_to_coeff(&self, mut a: Polynomial<F, LagrangeCoeff>) -> Polynomial<F, Coeff> {
+    pub fn lagrange_to_coeff(&self, a: Polynomial<F, LagrangeCoeff>) -> Polynomial<F, Coeff> {
+        self.lagrange_to_coeff_by(a, Self::ifft)
+    }
+
+    /// [`Self::lagrange_to_coeff`], with the inverse FFT done by `engine`.
+    pub fn lagrange_to_coeff_with_engine<C: CurveAffine<ScalarExt = F>>(
+        &self,
+        engine: &dyn PolynomialEngine<C>,
+        a: Polynomial<F, LagrangeCoeff>,
+    ) -> Polynomial<F, Coeff> {
+        self.lagrange_to_coeff_by(a, |a, omega_inv, log_n, divisor| {
+            engine.ifft(a, omega_inv, log_n, divisor)
+        })
+    }
+
+    fn lagrange_to_coeff_by(
+        &self,
+        mut a: Polynomial<F, LagrangeCoeff>,
+        ifft: impl FnOnce(&mut [F], F, u32, F),
+    ) -> Polynomial<F, Coeff> {
         assert_eq!(a.values.len(), 1 << self.k);
 
         // Perform inverse FFT to obtain the polynomial in coefficient form
-        Self::ifft(&mut a.values, self.omega_inv, self.k, self.ifft_divisor);
+        ifft(&mut a.values, self.omega_inv, self.k, self.ifft_divisor);
 
         Polynomial {
             values: a.values,
@@ -227,14 +246,31 @@ impl<F: WithSmallOrderMulGroup<3>> EvaluationDomain<F> {
     /// This takes us from an n-length coefficient vector into a coset of the extended
     /// evaluation domain, rotating by `rotation` if desired.
     pub fn coeff_to_extended(
+        &self,
+        a: Polynomial<F, Coeff>,
+    ) -> Polynomial<F, ExtendedLagrangeCoeff> {
+        self.coeff_to_extended_by(a, |a, omega, log_n| best_fft(a, omega, log_n))
+    }
+
+    /// [`Self::coeff_to_extended`], with the FFT done by `engine`.
+    pub fn coeff_to_extended_with_engine<C: CurveAffine<ScalarExt = F>>(
+        &self,
+        engine: &dyn PolynomialEngine<C>,
+        a: Polynomial<F, Coeff>,
+    ) -> Polynomial<F, ExtendedLagrangeCoeff> {
+        self.coeff_to_extended_by(a, |a, omega, log_n| engine.fft(a, omega, log_n))
+    }
+
+    fn coeff_to_extended_by(
         &self,
         mut a: Polynomial<F, Coeff>,
+        fft: impl FnOnce(&mut [F], F, u32),
     ) -> Polynomial<F, ExtendedLagrangeCoeff> {
         assert_eq!(a.values.len(), 1 << self.k);
 
         self.distribute_powers_zeta(&mut a.values, true);
         a.values.resize(self.extended_len(), F::ZERO);
-        best_fft(&mut a.values, self.extended_omega, self.extended_k);
+        fft(&mut a.values, self.extended_omega, self.extended_k);
 
         Polynomial {
             values: a.values,
@@ -242,6 +278,37 @@ impl<F: WithSmallOrderMulGroup<3>> EvaluationDomain<F> {
         }
     }
 
+    /// Returns the `part`-th of the `2^(extended_k - k)` parts of [`Self::coeff_to_extended`],
+    /// that is its evaluations at the indices `part + i * 2^(extended_k - k)` of the extended
+    /// domain, which form a coset of the original domain. This needs only an `n`-length FFT,
+    /// and `n` field elements of memory.
+    pub fn coeff_to_extended_part_with_engine<C: CurveAffine<ScalarExt = F>>(
+        &self,
+        engine: &dyn PolynomialEngine<C>,
+        a: &Polynomial<F, Coeff>,
+        part: usize,
+    ) -> Polynomial<F, ExtendedLagrangeCoeff> {
+        assert_eq!(a.values.len(), 1 << self.k);
+        assert!(part < 1 << (self.extended_k - self.k));
+
+        let mut values = a.values.clone();
+        self.distribute_powers_zeta(&mut values, true);
+        let shift = self.extended_omega.pow_vartime([part as u64]);
+        parallelize(&mut values, |values, start| {
+            let mut power = shift.pow_vartime([start as u64]);
+            for value in values {
+                *value *= &power;
+                power *= &shift;
+            }
+        });
+        engine.fft(&mut values, self.omega, self.k);
+
+        Polynomial {
+            values,
+            _marker: PhantomData,
+        }
+    }
+
     /// Rotate the extended domain polynomial over the original domain.
     pub fn rotate_extended(
         &self,
@@ -267,11 +334,30 @@ impl<F: WithSmallOrderMulGroup<3>> EvaluationDomain<F> {
     /// This function will panic if the provided vector is not the correct
     /// length.
     // TODO/FIXME: caller should be responsible for truncating
-    pub fn extended_to_coeff(&self, mut a: Polynomial<F, ExtendedLagrangeCoeff>) -> Vec<F> {
+    pub fn extended_to_coeff(&self, a: Polynomial<F, ExtendedLagrangeCoeff>) -> Vec<F> {
+        self.extended_to_coeff_by(a, Self::ifft)
+    }
+
+    /// [`Self::extended_to_coeff`], with the inverse FFT done by `engine`.
+    pub fn extended_to_coeff_with_engine<C: CurveAffine<ScalarExt = F>>(
+        &self,
+        engine: &dyn PolynomialEngine<C>,
+        a: Polynomial<F, ExtendedLagrangeCoeff>,
+    ) -> Vec<F> {
+        self.extended_to_coeff_by(a, |a, omega_inv, log_n, divisor| {
+            engine.ifft(a, omega_inv, log_n, divisor)
+        })
+    }
+
+    fn extended_to_coeff_by(
+        &self,
+        mut a: Polynomial<F, ExtendedLagrangeCoeff>,
+        ifft: impl FnOnce(&mut [F], F, u32, F),
+    ) -> Vec<F> {
         assert_eq!(a.values.len(), self.extended_len());
 
         // Inverse FFT
-        Self::ifft(
+        ifft(
             &mut a.values,
             self.extended_omega_inv,
             self.extended_k,
@@ -0,0 +1,49 @@
+use std::fmt::Debug;
+
+use crate::arithmetic::{best_fft, best_multiexp, parallelize, CurveAffine};
+
+/// The multi-scalar multiplications and FFTs done by the prover, so that they can be
+/// offloaded to an accelerator such as a GPU.
+///
+/// Every commitment and every FFT of the prover goes through the engine passed to
+/// [`ProverV2::new_with_engine`](crate::plonk::prover::ProverV2::new_with_engine). The
+/// verifier and key generation always use the [`CpuEngine`].
+///
+/// The trait is object safe, and engines are passed around as
+/// `&dyn PolynomialEngine<C>`.
+pub trait PolynomialEngine<C: CurveAffine>: Debug + Send + Sync {
+    /// Returns `sum_i coeffs[i] * bases[i]`.
+    ///
+    /// `coeffs` and `bases` have the same length.
+    fn msm(&self, coeffs: &[C::Scalar], bases: &[C]) -> C::Curve;
+
+    /// Replaces `values`, the coefficients of a polynomial of degree less than
+    /// `n = 2^log_n`, with its evaluations at the `n` powers of `omega`, an element of
+    /// multiplicative order `n`.
+    fn fft(&self, values: &mut [C::Scalar], omega: C::Scalar, log_n: u32);
+
+    /// Inverts [`PolynomialEngine::fft`], given `omega_inv`, the inverse of `omega`, and
+    /// `divisor`, the inverse of `n`.
+    fn ifft(&self, values: &mut [C::Scalar], omega_inv: C::Scalar, log_n: u32, divisor: C::Scalar) {
+        self.fft(values, omega_inv, log_n);
+        parallelize(values, |values, _| {
+            for value in values {
+                *value *= &divisor;
+            }
+        });
+    }
+}
+
+/// The default engine, which runs on the CPU with [`best_multiexp`] and [`best_fft`].
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CpuEngine;
+
+impl<C: CurveAffine> PolynomialEngine<C> for CpuEngine {
+    fn msm(&self, coeffs: &[C::Scalar], bases: &[C]) -> C::Curve {
+        best_multiexp(coeffs, bases)
+    }
+
+    fn fft(&self, values: &mut [C::Scalar], omega: C::Scalar, log_n: u32) {
+        best_fft(values, omega, log_n)
+    }
+}
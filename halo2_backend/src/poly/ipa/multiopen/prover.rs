@@ -4,7 +4,7 @@ use crate::poly::commitment::ParamsProver;
 use crate::poly::commitment::{Blind, Prover};
 use crate::poly::ipa::commitment::{self, IPACommitmentScheme, ParamsIPA};
 use crate::poly::query::ProverQuery;
-use crate::poly::{Coeff, Polynomial};
+use crate::poly::{Coeff, Polynomial, PolynomialEngine};
 use crate::transcript::{EncodedChallenge, TranscriptWrite};
 
 use group::Curve;
@@ -27,8 +27,9 @@ impl<'params, C: CurveAffine> Prover<'params, IPACommitmentScheme<C>> for Prover
     }
 
     /// Create a multi-opening proof
-    fn create_proof<'com, Z: EncodedChallenge<C>, T: TranscriptWrite<C, Z>, R, I>(
+    fn create_proof_with_engine<'com, Z: EncodedChallenge<C>, T: TranscriptWrite<C, Z>, R, I>(
         &self,
+        engine: &dyn PolynomialEngine<C>,
         mut rng: R,
         transcript: &mut T,
         queries: I,
@@ -93,7 +94,10 @@ impl<'params, C: CurveAffine> Prover<'params, IPACommitmentScheme<C>> for Prover
             .unwrap();
 
         let q_prime_blind = Blind(C::Scalar::random(&mut rng));
-        let q_prime_commitment = self.params.commit(&q_prime_poly, q_prime_blind).to_affine();
+        let q_prime_commitment = self
+            .params
+            .commit_with_engine(engine, &q_prime_poly, q_prime_blind)
+            .to_affine();
 
         transcript.write_point(q_prime_commitment)?;
 
@@ -117,6 +121,14 @@ impl<'params, C: CurveAffine> Prover<'params, IPACommitmentScheme<C>> for Prover
             },
         );
 
-        commitment::create_proof(self.params, rng, transcript, &p_poly, p_poly_blind, *x_3)
+        commitment::create_proof_with_engine(
+            engine,
+            self.params,
+            rng,
+            transcript,
+            &p_poly,
+            p_poly_blind,
+            *x_3,
+        )
     }
 }
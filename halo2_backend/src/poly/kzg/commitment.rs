@@ -1,10 +1,11 @@
-use crate::arithmetic::{best_multiexp, g_to_lagrange, parallelize};
-use crate::helpers::SerdeCurveAffine;
+use crate::arithmetic::{g_to_lagrange, parallelize};
+use crate::helpers::{split_slice, SerdeCurveAffine};
 use crate::poly::commitment::{Blind, CommitmentScheme, Params, ParamsProver, ParamsVerifier};
-use crate::poly::{Coeff, LagrangeCoeff, Polynomial};
+use crate::poly::{Coeff, LagrangeCoeff, Polynomial, PolynomialEngine};
 use crate::SerdeFormat;
 
 use group::{prime::PrimeCurveAffine, Curve, Group};
+use halo2_common::helpers::points_from_slice;
 use halo2_middleware::ff::{Field, PrimeField};
 use halo2curves::pairing::Engine;
 use halo2curves::CurveExt;
@@ -153,6 +154,40 @@ where
         }
     }
 
+    /// Returns parameters for the smaller domain of size `2^k`.
+    ///
+    /// The bases `g` in monomial form are the first `2^k` powers of the secret, so they are
+    /// truncated. The bases `g_lagrange` cannot be: the Lagrange basis of the smaller domain
+    /// is not a prefix of the one of the larger domain, so it is recomputed from `g` with an
+    /// FFT of size `2^k`. `g2` and `s_g2` do not depend on the size of the domain and are
+    /// kept as they are.
+    ///
+    /// Proofs created with the downsized parameters are identical to the ones created with
+    /// parameters generated natively for `k` from the same secret.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `k` is larger than the `k` of these parameters.
+    #[must_use]
+    pub fn downsized(&self, k: u32) -> Self {
+        assert!(
+            k <= self.k,
+            "cannot downsize parameters for k = {} to the larger k = {}",
+            self.k,
+            k
+        );
+        let g = self.g[..1 << k].to_vec();
+        let g_lagrange = g_to_lagrange(g.iter().map(PrimeCurveAffine::to_curve).collect(), k);
+        Self {
+            k,
+            n: 1 << k,
+            g,
+            g_lagrange,
+            g2: self.g2,
+            s_g2: self.s_g2,
+        }
+    }
+
     /// Returns gernerator on G2
     pub fn g2(&self) -> E::G2Affine {
         self.g2
@@ -190,67 +225,122 @@ where
         let k = u32::from_le_bytes(k);
         let n = 1 << k;
 
-        let (g, g_lagrange) = match format {
-            SerdeFormat::Processed => {
-                use group::GroupEncoding;
-                let load_points_from_file_parallelly =
-                    |reader: &mut R| -> io::Result<Vec<Option<E::G1Affine>>> {
-                        let mut points_compressed =
-                            vec![<<E as Engine>::G1Affine as GroupEncoding>::Repr::default(); n];
-                        for points_compressed in points_compressed.iter_mut() {
-                            reader.read_exact((*points_compressed).as_mut())?;
-                        }
-
-                        let mut points = vec![Option::<E::G1Affine>::None; n];
-                        parallelize(&mut points, |points, chunks| {
-                            for (i, point) in points.iter_mut().enumerate() {
-                                *point = Option::from(E::G1Affine::from_bytes(
-                                    &points_compressed[chunks + i],
-                                ));
-                            }
-                        });
-                        Ok(points)
-                    };
-
-                let g = load_points_from_file_parallelly(reader)?;
-                let g: Vec<<E as Engine>::G1Affine> = g
-                    .iter()
-                    .map(|point| {
-                        point.ok_or_else(|| {
-                            io::Error::new(io::ErrorKind::Other, "invalid point encoding")
-                        })
-                    })
-                    .collect::<Result<_, _>>()?;
-                let g_lagrange = load_points_from_file_parallelly(reader)?;
-                let g_lagrange: Vec<<E as Engine>::G1Affine> = g_lagrange
-                    .iter()
-                    .map(|point| {
-                        point.ok_or_else(|| {
-                            io::Error::new(io::ErrorKind::Other, "invalid point encoding")
-                        })
-                    })
-                    .collect::<Result<_, _>>()?;
-                (g, g_lagrange)
-            }
-            SerdeFormat::RawBytes => {
-                let g = (0..n)
-                    .map(|_| <E::G1Affine as SerdeCurveAffine>::read(reader, format))
-                    .collect::<Result<Vec<_>, _>>()?;
-                let g_lagrange = (0..n)
-                    .map(|_| <E::G1Affine as SerdeCurveAffine>::read(reader, format))
-                    .collect::<Result<Vec<_>, _>>()?;
-                (g, g_lagrange)
-            }
-            SerdeFormat::RawBytesUnchecked => {
-                // avoid try branching for performance
-                let g = (0..n)
-                    .map(|_| <E::G1Affine as SerdeCurveAffine>::read(reader, format).unwrap())
-                    .collect::<Vec<_>>();
-                let g_lagrange = (0..n)
-                    .map(|_| <E::G1Affine as SerdeCurveAffine>::read(reader, format).unwrap())
-                    .collect::<Vec<_>>();
-                (g, g_lagrange)
+        let g = Self::read_points(reader, n, format)?;
+        let g_lagrange = Self::read_points(reader, n, format)?;
+
+        let g2 = E::G2Affine::read(reader, format)?;
+        let s_g2 = E::G2Affine::read(reader, format)?;
+
+        Ok(Self {
+            k,
+            n: n as u64,
+            g,
+            g_lagrange,
+            g2,
+            s_g2,
+        })
+    }
+
+    /// Reads params from a slice of bytes in the [`SerdeFormat::RawBytes`] format, such as a
+    /// memory-mapped file.
+    ///
+    /// See [`ParamsKZG::from_bytes_custom`].
+    pub fn from_bytes(bytes: &[u8]) -> io::Result<Self>
+    where
+        E::G2Affine: SerdeCurveAffine,
+    {
+        Self::from_bytes_custom(bytes, SerdeFormat::RawBytes)
+    }
+
+    /// Reads params from a slice of bytes in the format of [`ParamsKZG::read_custom`], such
+    /// as a memory-mapped file.
+    ///
+    /// The bases are parsed, and checked or decompressed according to `format`, from `bytes`
+    /// in parallel, straight into their vectors, without the intermediate buffers of
+    /// [`ParamsKZG::read_custom`]. They are still copied once: the in-memory representation
+    /// of a point doesn't match [`SerdeFormat::Processed`], and `bytes` needn't be aligned
+    /// for it in the raw formats. The trailing bytes after the params, if any, are ignored.
+    pub fn from_bytes_custom(mut bytes: &[u8], format: SerdeFormat) -> io::Result<Self>
+    where
+        E::G2Affine: SerdeCurveAffine,
+    {
+        let k = split_slice(&mut bytes, 4)?;
+        let k = u32::from_le_bytes(k.try_into().expect("4 bytes were split"));
+        let n = 1 << k;
+
+        let g = points_from_slice(&mut bytes, n, format)?;
+        let g_lagrange = points_from_slice(&mut bytes, n, format)?;
+
+        let g2 = E::G2Affine::read(&mut bytes, format)?;
+        let s_g2 = E::G2Affine::read(&mut bytes, format)?;
+
+        Ok(Self {
+            k,
+            n: n as u64,
+            g,
+            g_lagrange,
+            g2,
+            s_g2,
+        })
+    }
+
+    /// Reads the parameters for the domain of size `2^k` from a buffer holding parameters
+    /// for a domain at least as large, in the [`SerdeFormat::RawBytes`] format.
+    ///
+    /// See [`ParamsKZG::read_partial_custom`].
+    pub fn read_partial<R: io::Read>(reader: &mut R, k: u32) -> io::Result<Self>
+    where
+        E::G2Affine: SerdeCurveAffine,
+    {
+        Self::read_partial_custom(reader, k, SerdeFormat::RawBytes)
+    }
+
+    /// Reads the parameters for the domain of size `2^k` from a buffer holding parameters
+    /// for a domain at least as large.
+    ///
+    /// Only the first `2^k` bases `g` are deserialized. The remaining bases are skipped
+    /// over, and `g_lagrange` is recomputed as in [`ParamsKZG::downsized`]. When `k` is the
+    /// `k` of the buffer, this is the same as [`ParamsKZG::read_custom`].
+    ///
+    /// Returns an error of kind [`io::ErrorKind::InvalidInput`] if `k` is larger than the
+    /// `k` of the buffer.
+    pub fn read_partial_custom<R: io::Read>(
+        reader: &mut R,
+        k: u32,
+        format: SerdeFormat,
+    ) -> io::Result<Self>
+    where
+        E::G2Affine: SerdeCurveAffine,
+    {
+        let mut file_k = [0u8; 4];
+        reader.read_exact(&mut file_k[..])?;
+        let file_k = u32::from_le_bytes(file_k);
+        if k > file_k {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "parameters for k = {} requested, but the buffer only holds parameters for k = {}",
+                    k, file_k
+                ),
+            ));
+        }
+        let file_n = 1u64 << file_k;
+        let n = 1 << k;
+
+        let g = Self::read_points(reader, n, format)?;
+        let g_lagrange = if k == file_k {
+            Self::read_points(reader, n, format)?
+        } else {
+            // The rest of `g`, and the whole of `g_lagrange` of the larger domain.
+            let skip = (2 * file_n - n as u64) * E::G1Affine::byte_length(format) as u64;
+            let skipped = io::copy(&mut reader.by_ref().take(skip), &mut io::sink())?;
+            if skipped != skip {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "unexpected end of the parameters",
+                ));
             }
+            g_to_lagrange(g.iter().map(PrimeCurveAffine::to_curve).collect(), k)
         };
 
         let g2 = E::G2Affine::read(reader, format)?;
@@ -265,6 +355,49 @@ where
             s_g2,
         })
     }
+
+    /// Reads `n` points of G1 from a buffer.
+    fn read_points<R: io::Read>(
+        reader: &mut R,
+        n: usize,
+        format: SerdeFormat,
+    ) -> io::Result<Vec<E::G1Affine>> {
+        match format {
+            SerdeFormat::Processed => {
+                use group::GroupEncoding;
+                let mut points_compressed =
+                    vec![<<E as Engine>::G1Affine as GroupEncoding>::Repr::default(); n];
+                for points_compressed in points_compressed.iter_mut() {
+                    reader.read_exact((*points_compressed).as_mut())?;
+                }
+
+                let mut points = vec![Option::<E::G1Affine>::None; n];
+                parallelize(&mut points, |points, chunks| {
+                    for (i, point) in points.iter_mut().enumerate() {
+                        *point =
+                            Option::from(E::G1Affine::from_bytes(&points_compressed[chunks + i]));
+                    }
+                });
+                points
+                    .iter()
+                    .map(|point| {
+                        point.ok_or_else(|| {
+                            io::Error::new(io::ErrorKind::Other, "invalid point encoding")
+                        })
+                    })
+                    .collect()
+            }
+            SerdeFormat::RawBytes => (0..n)
+                .map(|_| <E::G1Affine as SerdeCurveAffine>::read(reader, format))
+                .collect(),
+            SerdeFormat::RawBytesUnchecked => {
+                // avoid try branching for performance
+                Ok((0..n)
+                    .map(|_| <E::G1Affine as SerdeCurveAffine>::read(reader, format).unwrap())
+                    .collect())
+            }
+        }
+    }
 }
 
 // TODO: see the issue at https://github.com/appliedzkp/halo2/issues/45
@@ -289,26 +422,25 @@ where
     }
 
     fn downsize(&mut self, k: u32) {
-        assert!(k <= self.k);
-
-        self.k = k;
-        self.n = 1 << k;
-
-        self.g.truncate(self.n as usize);
-        self.g_lagrange = g_to_lagrange(self.g.iter().map(|g| g.to_curve()).collect(), k);
+        *self = self.downsized(k);
     }
 
     fn empty_msm(&'params self) -> MSMKZG<E> {
         MSMKZG::new()
     }
 
-    fn commit_lagrange(&self, poly: &Polynomial<E::Fr, LagrangeCoeff>, _: Blind<E::Fr>) -> E::G1 {
+    fn commit_lagrange_with_engine(
+        &self,
+        engine: &dyn PolynomialEngine<E::G1Affine>,
+        poly: &Polynomial<E::Fr, LagrangeCoeff>,
+        _: Blind<E::Fr>,
+    ) -> E::G1 {
         let mut scalars = Vec::with_capacity(poly.len());
         scalars.extend(poly.iter());
         let bases = &self.g_lagrange;
         let size = scalars.len();
         assert!(bases.len() >= size);
-        best_multiexp(&scalars, &bases[0..size])
+        engine.msm(&scalars, &bases[0..size])
     }
 
     /// Writes params to a buffer.
@@ -346,13 +478,18 @@ where
         Self::setup(k, OsRng)
     }
 
-    fn commit(&self, poly: &Polynomial<E::Fr, Coeff>, _: Blind<E::Fr>) -> E::G1 {
+    fn commit_with_engine(
+        &self,
+        engine: &dyn PolynomialEngine<E::G1Affine>,
+        poly: &Polynomial<E::Fr, Coeff>,
+        _: Blind<E::Fr>,
+    ) -> E::G1 {
         let mut scalars = Vec::with_capacity(poly.len());
         scalars.extend(poly.iter());
         let bases = &self.g;
         let size = scalars.len();
         assert!(bases.len() >= size);
-        best_multiexp(&scalars, &bases[0..size])
+        engine.msm(&scalars, &bases[0..size])
     }
 
     fn get_g(&self) -> &[E::G1Affine] {
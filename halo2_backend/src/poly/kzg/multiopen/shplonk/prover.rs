@@ -9,7 +9,7 @@ use crate::helpers::SerdeCurveAffine;
 use crate::poly::commitment::{Blind, ParamsProver, Prover};
 use crate::poly::kzg::commitment::{KZGCommitmentScheme, ParamsKZG};
 use crate::poly::query::{PolynomialPointer, ProverQuery};
-use crate::poly::{Coeff, Polynomial};
+use crate::poly::{Coeff, Polynomial, PolynomialEngine};
 use crate::transcript::{EncodedChallenge, TranscriptWrite};
 
 use crate::multicore::{IntoParallelIterator, ParallelIterator};
@@ -117,7 +117,7 @@ where
     }
 
     /// Create a multi-opening proof
-    fn create_proof<
+    fn create_proof_with_engine<
         'com,
         Ch: EncodedChallenge<E::G1Affine>,
         T: TranscriptWrite<E::G1Affine, Ch>,
@@ -125,6 +125,7 @@ where
         I,
     >(
         &self,
+        engine: &dyn PolynomialEngine<E::G1Affine>,
         _: R,
         transcript: &mut T,
         queries: I,
@@ -208,7 +209,10 @@ where
             .reduce(|acc, poly| acc + &poly)
             .unwrap();
 
-        let h = self.params.commit(&h_x, Blind::default()).to_affine();
+        let h = self
+            .params
+            .commit_with_engine(engine, &h_x, Blind::default())
+            .to_affine();
         transcript.write_point(h)?;
         let u: ChallengeU<_> = transcript.squeeze_challenge_scalar();
 
@@ -290,7 +294,10 @@ where
             _marker: PhantomData,
         };
 
-        let h = self.params.commit(&h_x, Blind::default()).to_affine();
+        let h = self
+            .params
+            .commit_with_engine(engine, &h_x, Blind::default())
+            .to_affine();
         transcript.write_point(h)?;
 
         Ok(())
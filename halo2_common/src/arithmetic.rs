@@ -433,6 +433,44 @@ pub fn parallelize<T: Send, F: Fn(&mut [T], usize) + Send + Sync + Clone>(v: &mu
     });
 }
 
+/// Replaces each element of `v` with the product of the elements up to and including it.
+///
+/// This is a parallel prefix product: `v` is split into one chunk per thread, the running
+/// products of the chunks are computed in parallel, and then each chunk is scaled by the
+/// product of the chunks before it.
+pub fn prefix_product<F: Field>(v: &mut [F]) {
+    use multicore::{IndexedParallelIterator, ParallelIterator, ParallelSliceMut};
+
+    if v.is_empty() {
+        return;
+    }
+    let chunk_size =
+        (v.len() + multicore::current_num_threads() - 1) / multicore::current_num_threads();
+    let mut offsets: Vec<F> = v
+        .par_chunks_mut(chunk_size)
+        .map(|chunk| {
+            for i in 1..chunk.len() {
+                chunk[i] = chunk[i - 1] * chunk[i];
+            }
+            chunk[chunk.len() - 1]
+        })
+        .collect();
+    let mut acc = F::ONE;
+    for offset in offsets.iter_mut() {
+        let product = *offset;
+        *offset = acc;
+        acc *= product;
+    }
+    v.par_chunks_mut(chunk_size)
+        .zip(offsets)
+        .skip(1)
+        .for_each(|(chunk, offset)| {
+            for value in chunk.iter_mut() {
+                *value *= offset;
+            }
+        });
+}
+
 fn log2_floor(num: usize) -> u32 {
     assert!(num > 0);
 
@@ -533,6 +571,26 @@ use rand_core::OsRng;
 #[cfg(test)]
 use crate::halo2curves::pasta::Fp;
 
+#[test]
+fn test_prefix_product() {
+    let rng = OsRng;
+
+    for len in [0, 1, 2, 7, 100, 1025] {
+        let values = (0..len).map(|_| Fp::random(rng)).collect::<Vec<_>>();
+        let expected = values
+            .iter()
+            .scan(Fp::ONE, |acc, value| {
+                *acc *= value;
+                Some(*acc)
+            })
+            .collect::<Vec<_>>();
+
+        let mut products = values;
+        prefix_product(&mut products);
+        assert_eq!(products, expected);
+    }
+}
+
 #[test]
 fn test_lagrange_interpolate() {
     let rng = OsRng;
@@ -1,15 +1,17 @@
 //! Traits and structs for implementing circuit components.
 
-use std::{fmt, marker::PhantomData};
+use std::{fmt, marker::PhantomData, ops::Range};
 
 use halo2_middleware::ff::Field;
 
 use crate::plonk::Assigned;
 use crate::plonk::{
     circuit::{Challenge, Column},
-    Error, Selector, TableColumn,
+    sealed::SealedPhase,
+    Error, Expression, Phase, Selector, TableColumn,
 };
 use halo2_middleware::circuit::{Advice, Any, Fixed, Instance};
+use halo2_middleware::poly::Rotation;
 
 mod value;
 pub use value::Value;
@@ -54,9 +56,16 @@ pub trait Chip<F: Field>: Sized {
 }
 
 /// Index of a region in a layouter
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct RegionIndex(usize);
 
+impl RegionIndex {
+    /// Returns the index of the region.
+    pub fn index(&self) -> usize {
+        self.0
+    }
+}
+
 impl From<usize> for RegionIndex {
     fn from(idx: usize) -> RegionIndex {
         RegionIndex(idx)
@@ -179,6 +188,13 @@ where
 /// The [`Layouter`] is allowed to optimise between regions as it sees fit. Chips must use
 /// [`Region::constrain_equal`] to copy in variables assigned in other regions.
 ///
+/// Only the usable rows of the circuit can be assigned: the rows reserved for blinding
+/// factors at the end of the circuit are excluded. Chips that need to know how many rows
+/// are available should use [`ConstraintSystem::usable_rows`] (or
+/// `MockProver::usable_rows` and `ProvingKey::usable_rows`) rather than computing it.
+///
+/// [`ConstraintSystem::usable_rows`]: crate::plonk::ConstraintSystem::usable_rows
+///
 /// TODO: It would be great if we could constrain the columns in these types to be
 /// "logical" columns that are guaranteed to correspond to the chip (and have come from
 /// `Chip::Config`).
@@ -209,6 +225,21 @@ impl<'r, F: Field> Region<'r, F> {
             .enable_selector(&|| annotation().into(), selector, offset)
     }
 
+    /// Enables a selector at every offset in `offsets` within this region.
+    pub fn enable_selector_range<A, AR>(
+        &mut self,
+        annotation: A,
+        selector: &Selector,
+        offsets: Range<usize>,
+    ) -> Result<(), Error>
+    where
+        A: Fn() -> AR,
+        AR: Into<String>,
+    {
+        self.region
+            .enable_selector_range(&|| annotation().into(), selector, offsets)
+    }
+
     /// Allows the circuit implementor to name/annotate a Column within a Region context.
     ///
     /// This is useful in order to improve the amount of information that `prover.verify()`
@@ -256,6 +287,97 @@ impl<'r, F: Field> Region<'r, F> {
         })
     }
 
+    /// Assign an advice column value (witness), where computing the value may fail.
+    ///
+    /// If `to` returns an error, synthesis is aborted with [`Error::WitnessFailure`],
+    /// which records the region name, the annotation and the offset of the cell.
+    ///
+    /// Even though `to` has `FnMut` bounds, it is guaranteed to be called at most once.
+    pub fn try_assign_advice<'v, V, VR, A, AR>(
+        &'v mut self,
+        annotation: A,
+        column: Column<Advice>,
+        offset: usize,
+        mut to: V,
+    ) -> Result<AssignedCell<VR, F>, Error>
+    where
+        V: FnMut() -> Result<Value<VR>, Error> + 'v,
+        for<'vr> Assigned<F>: From<&'vr VR>,
+        A: Fn() -> AR,
+        AR: Into<String>,
+    {
+        let mut value = Value::unknown();
+        let cell =
+            self.region
+                .try_assign_advice(&|| annotation().into(), column, offset, &mut || {
+                    let v = to()?;
+                    let value_f = v.to_field();
+                    value = v;
+                    Ok(value_f)
+                })?;
+
+        Ok(AssignedCell {
+            value,
+            cell,
+            _marker: PhantomData,
+        })
+    }
+
+    /// Assigns the value of `expr` to the column `advice` at `offset` within this region.
+    ///
+    /// This lets a chip compute a witness with the expression that its gate constrains the
+    /// cell to, e.g. `a * b + 3` for `s * (a * b + 3 - c)`, instead of duplicating it in
+    /// Rust code. Each query of `expr` is evaluated with `bindings`, given the column and
+    /// the rotation of the query, which typically returns the value of a cell assigned
+    /// earlier relative to `offset`. Selectors are evaluated to one, since the value is
+    /// only constrained on the rows where the gate is enabled, and challenges, which are
+    /// not available within a region, are evaluated to an unknown value.
+    ///
+    /// If any of the values used is unknown, the assigned value is unknown.
+    pub fn assign_advice_from_expression<A, AR>(
+        &mut self,
+        annotation: A,
+        advice: Column<Advice>,
+        offset: usize,
+        expr: &Expression<F>,
+        bindings: &dyn Fn(Column<Any>, Rotation) -> Value<F>,
+    ) -> Result<AssignedCell<F, F>, Error>
+    where
+        A: Fn() -> AR,
+        AR: Into<String>,
+    {
+        let value = expr.evaluate(
+            &|scalar| Value::known(scalar),
+            &|_| Value::known(F::ONE),
+            &|query| {
+                bindings(
+                    Column::new(query.column_index(), Any::Fixed),
+                    query.rotation(),
+                )
+            },
+            &|query| {
+                let column = Column::new(
+                    query.column_index(),
+                    Any::Advice(Advice::new(query.phase())),
+                );
+                bindings(column, query.rotation())
+            },
+            &|query| {
+                bindings(
+                    Column::new(query.column_index(), Any::Instance),
+                    query.rotation(),
+                )
+            },
+            &|_| Value::unknown(),
+            &|a| -a,
+            &|a, b| a + b,
+            &|a, b| a * b,
+            &|a, scalar| a * Value::known(scalar),
+        );
+
+        self.assign_advice(annotation, advice, offset, || value)
+    }
+
     /// Assigns a constant value to the column `advice` at `offset` within this region.
     ///
     /// The constant value will be assigned to a cell within one of the fixed columns
@@ -365,13 +487,48 @@ impl<'r, F: Field> Region<'r, F> {
         })
     }
 
+    /// Fills the advice `column` with the value `to`, from `offset` within this region up
+    /// to the last usable row of the circuit.
+    ///
+    /// This is meant for padding tables held in advice columns. The cells of the column
+    /// assigned by this or other regions, before or after, take precedence over the
+    /// padding, whichever rows the floor planner places these regions at. The rows reserved for
+    /// blinding factors are not filled, and filling from the end of the usable rows
+    /// assigns nothing.
+    ///
+    /// Only the row at `offset` is part of the region; the padding below it does not
+    /// extend the region.
+    ///
+    /// # Soundness
+    ///
+    /// Filling a column only assigns a witness; it does not constrain anything. Any
+    /// selector or tag column that distinguishes the padding rows from the content of
+    /// the table must still be handled by the circuit.
+    pub fn fill_advice_from_offset<VR>(
+        &mut self,
+        column: Column<Advice>,
+        offset: usize,
+        to: Value<VR>,
+    ) -> Result<(), Error>
+    where
+        VR: Into<Assigned<F>>,
+    {
+        self.region
+            .fill_advice_from_offset(column, offset, to.map(|to| to.into()))
+    }
+
     /// Constrains a cell to have a constant value.
     ///
-    /// Returns an error if the cell is in a column where equality has not been enabled.
+    /// Returns an error if the cell is in a column where equality has not been enabled,
+    /// or if the cell is in a fixed column (fixed cells should be assigned the constant
+    /// directly with [`Region::assign_fixed`]).
     pub fn constrain_constant<VR>(&mut self, cell: Cell, constant: VR) -> Result<(), Error>
     where
         VR: Into<Assigned<F>>,
     {
+        if matches!(cell.column.column_type(), Any::Fixed) {
+            return Err(Error::ConstrainConstantOnFixed(cell.column));
+        }
         self.region.constrain_constant(cell, constant.into())
     }
 
@@ -382,6 +539,72 @@ impl<'r, F: Field> Region<'r, F> {
     pub fn constrain_equal(&mut self, left: Cell, right: Cell) -> Result<(), Error> {
         self.region.constrain_equal(left, right)
     }
+
+    /// Constrains two assigned cells to have the same value, like
+    /// [`Region::constrain_equal`], and checks that their values match if both are known.
+    ///
+    /// Returns an `Error::CopyValueMismatch` naming both cells if they hold different
+    /// known values, instead of leaving the mismatch to be reported as a permutation
+    /// failure once the circuit is checked.
+    pub fn constrain_equal_checked(
+        &mut self,
+        left: &AssignedCell<F, F>,
+        right: &AssignedCell<F, F>,
+    ) -> Result<(), Error> {
+        let mut mismatch = None;
+        left.value()
+            .zip(right.value())
+            .map(|(left_value, right_value)| {
+                if left_value != right_value {
+                    mismatch = Some(Error::CopyValueMismatch {
+                        left: left.cell(),
+                        right: right.cell(),
+                        left_value: format!("{left_value:?}"),
+                        right_value: format!("{right_value:?}"),
+                    });
+                }
+            });
+        match mismatch {
+            Some(error) => Err(error),
+            None => self.constrain_equal(left.cell(), right.cell()),
+        }
+    }
+
+    /// Copies the given cells into consecutive rows of the advice column `column`,
+    /// starting at `start_offset` within this region, and constrains each copy to be
+    /// equal to its original.
+    ///
+    /// The copy of `cells[i]` is annotated as `"{annotation}[{i}]"`.
+    ///
+    /// Returns an error if `column`, or the column of any of the given cells, does not
+    /// have equality enabled.
+    pub fn copy_advice_from<A, AR>(
+        &mut self,
+        annotation: A,
+        cells: &[AssignedCell<F, F>],
+        column: Column<Advice>,
+        start_offset: usize,
+    ) -> Result<Vec<AssignedCell<F, F>>, Error>
+    where
+        A: Fn() -> AR,
+        AR: Into<String>,
+    {
+        let annotation: String = annotation().into();
+        cells
+            .iter()
+            .enumerate()
+            .map(|(i, cell)| {
+                let copy = self.assign_advice(
+                    || format!("{}[{}]", annotation, i),
+                    column,
+                    start_offset + i,
+                    || cell.value().copied(),
+                )?;
+                self.constrain_equal(copy.cell(), cell.cell())?;
+                Ok(copy)
+            })
+            .collect()
+    }
 }
 
 /// A lookup table in the circuit.
@@ -420,6 +643,34 @@ impl<'r, F: Field> Table<'r, F> {
                 to().into_field()
             })
     }
+
+    /// Sets the value to fill `column` with after its last assigned row.
+    ///
+    /// By default, table columns are filled with the value assigned at row 0. This must be
+    /// set for columns whose row 0 does not hold a sound default, e.g. a table whose
+    /// padding rows must contain a sentinel that no lookup input can take. Row 0 must
+    /// still be assigned.
+    ///
+    /// Returns an error if the default value of `column` has already been set, or if
+    /// `column` was assigned in a previous table.
+    pub fn set_default<VR>(&mut self, column: TableColumn, value: Value<VR>) -> Result<(), Error>
+    where
+        VR: Into<Assigned<F>>,
+    {
+        self.table
+            .set_default(column, value.map(|value| value.into()))
+    }
+
+    /// Selectors cannot be enabled inside [`Layouter::assign_table`]: the layouters of
+    /// this crate plan the rows of a selector with the regions that enable it, and a
+    /// table does not reserve them. Tag the rows of a table with a fixed tag column
+    /// instead, e.g. another [`TableColumn`] looked up together with the table.
+    ///
+    /// Returns a `TableError::SelectorInTable` error unless the [`TableLayouter`] of the
+    /// table supports it.
+    pub fn enable_selector(&mut self, selector: &Selector, offset: usize) -> Result<(), Error> {
+        self.table.enable_selector(*selector, offset)
+    }
 }
 
 /// A layout strategy within a circuit. The layouter is chip-agnostic and applies its
@@ -450,6 +701,24 @@ pub trait Layouter<F: Field> {
         N: Fn() -> NR,
         NR: Into<String>;
 
+    /// Assigns a region like [`Layouter::assign_region`], and also returns the index of
+    /// the region.
+    ///
+    /// Regions are indexed in the order they are assigned, which is also the order of the
+    /// region records of the floor planner, such as the [`RegionShape`]s of the measurement
+    /// pass of [`floor_planner::V1`].
+    ///
+    /// [`RegionShape`]: layouter::RegionShape
+    fn assign_region_indexed<A, AR, N, NR>(
+        &mut self,
+        name: N,
+        assignment: A,
+    ) -> Result<(RegionIndex, AR), Error>
+    where
+        A: FnMut(Region<'_, F>) -> Result<AR, Error>,
+        N: Fn() -> NR,
+        NR: Into<String>;
+
     /// Assign a table region to an absolute row number.
     ///
     /// ```ignore
@@ -473,11 +742,69 @@ pub trait Layouter<F: Field> {
         row: usize,
     ) -> Result<(), Error>;
 
+    /// Returns the next unused absolute row of the instance column `column`, as handed
+    /// out by the [`InstanceAllocator`] of the layouter root.
+    ///
+    /// Rows are handed out consecutively from row 0 in the order of the calls, so the
+    /// verifier-side instance vector lists the exposed values in synthesis order.
+    ///
+    /// The default implementation, for layouters without an allocator, returns an error.
+    ///
+    /// [`InstanceAllocator`]: layouter::InstanceAllocator
+    fn next_instance_slot(&mut self, column: Column<Instance>) -> Result<usize, Error> {
+        Err(Error::Other(format!(
+            "The layouter does not allocate rows of instance column {}",
+            column.index()
+        )))
+    }
+
+    /// Constrains a [`Cell`] to equal the next unused row of an instance column, as
+    /// returned by [`Layouter::next_instance_slot`].
+    ///
+    /// Returns the absolute row of the instance column.
+    fn constrain_instance_next(
+        &mut self,
+        cell: Cell,
+        column: Column<Instance>,
+    ) -> Result<usize, Error> {
+        let row = self.next_instance_slot(column)?;
+        self.constrain_instance(cell, column, row)?;
+        Ok(row)
+    }
+
     /// Queries the value of the given challenge.
     ///
     /// Returns `Value::unknown()` if the current synthesis phase is before the challenge can be queried.
     fn get_challenge(&self, challenge: Challenge) -> Value<F>;
 
+    /// Returns the phase that the circuit is currently being synthesized in.
+    ///
+    /// Passes that synthesize the circuit only once for all phases (such as the
+    /// measurement pass of [`floor_planner::V1`], which runs on
+    /// [`Circuit::without_witnesses`], and key generation) report the maximum phase, so
+    /// that work gated on the phase is always included in the measured layout.
+    ///
+    /// Only witness assignment should depend on the phase. Selectors, fixed cells,
+    /// copy constraints and the shape of each region must be the same in every phase.
+    ///
+    /// The default implementation, for layouters that do not track the phase, also
+    /// reports the maximum phase, so that chips gated on [`Layouter::in_phase`] never skip
+    /// assignments and behave as if the phase were not exposed.
+    ///
+    /// [`Circuit::without_witnesses`]: crate::plonk::Circuit::without_witnesses
+    fn current_phase(&self) -> u8 {
+        u8::MAX
+    }
+
+    /// Returns `true` if the current synthesis phase is `phase` or a later one, in which
+    /// case the challenges usable after the phase before `phase` are available.
+    ///
+    /// This lets chips skip assignments of later-phase advice columns entirely in earlier
+    /// phases, instead of assigning `Value::unknown()` everywhere.
+    fn in_phase<P: Phase>(&self, phase: P) -> bool {
+        self.current_phase() >= phase.to_sealed().0
+    }
+
     /// Gets the "root" of this assignment, bypassing the namespacing.
     ///
     /// Not intended for downstream consumption; use [`Layouter::namespace`] instead.
@@ -525,6 +852,19 @@ impl<'a, F: Field, L: Layouter<F> + 'a> Layouter<F> for NamespacedLayouter<'a, F
         self.0.assign_region(name, assignment)
     }
 
+    fn assign_region_indexed<A, AR, N, NR>(
+        &mut self,
+        name: N,
+        assignment: A,
+    ) -> Result<(RegionIndex, AR), Error>
+    where
+        A: FnMut(Region<'_, F>) -> Result<AR, Error>,
+        N: Fn() -> NR,
+        NR: Into<String>,
+    {
+        self.0.assign_region_indexed(name, assignment)
+    }
+
     fn assign_table<A, N, NR>(&mut self, name: N, assignment: A) -> Result<(), Error>
     where
         A: FnMut(Table<'_, F>) -> Result<(), Error>,
@@ -543,10 +883,18 @@ impl<'a, F: Field, L: Layouter<F> + 'a> Layouter<F> for NamespacedLayouter<'a, F
         self.0.constrain_instance(cell, column, row)
     }
 
+    fn next_instance_slot(&mut self, column: Column<Instance>) -> Result<usize, Error> {
+        self.0.next_instance_slot(column)
+    }
+
     fn get_challenge(&self, challenge: Challenge) -> Value<F> {
         self.0.get_challenge(challenge)
     }
 
+    fn current_phase(&self) -> u8 {
+        self.0.current_phase()
+    }
+
     fn get_root(&mut self) -> &mut Self::Root {
         self.0.get_root()
     }
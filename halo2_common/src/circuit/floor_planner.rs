@@ -3,4 +3,4 @@
 pub mod single_pass;
 
 pub mod v1;
-pub use v1::{V1Pass, V1};
+pub use v1::{V1Pass, V1SinglePass, V1};
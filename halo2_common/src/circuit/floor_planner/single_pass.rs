@@ -2,17 +2,23 @@ use std::cmp;
 use std::collections::HashMap;
 use std::fmt;
 use std::marker::PhantomData;
+use std::ops::Range;
 
 use halo2_middleware::ff::Field;
 
 use crate::plonk::Assigned;
 use crate::{
     circuit::{
-        layouter::{RegionColumn, RegionLayouter, RegionShape, SyncDeps, TableLayouter},
+        layouter::{
+            InstanceAllocator, RegionColumn, RegionLayouter, RegionShape, SyncDeps, TableLayouter,
+        },
         table_layouter::{compute_table_lengths, SimpleTableLayouter},
         Cell, Column, Layouter, Region, RegionIndex, RegionStart, Table, Value,
     },
-    plonk::{circuit::Challenge, Assignment, Circuit, Error, FloorPlanner, Selector, TableColumn},
+    plonk::{
+        circuit::Challenge, Assignment, AssignmentContext, Circuit, Error, FloorPlanner, Selector,
+        TableColumn,
+    },
 };
 use halo2_middleware::circuit::{Advice, Any, Fixed, Instance};
 
@@ -46,6 +52,11 @@ pub struct SingleChipLayouter<'a, F: Field, CS: Assignment<F> + 'a> {
     columns: HashMap<RegionColumn, usize>,
     /// Stores the table fixed columns.
     table_columns: Vec<TableColumn>,
+    /// Hands out the instance rows for `Layouter::next_instance_slot`.
+    instances: InstanceAllocator,
+    /// The namespaces that regions are currently assigned within, used to give context to
+    /// assignment errors.
+    namespace: Vec<String>,
     _marker: PhantomData<F>,
 }
 
@@ -67,6 +78,8 @@ impl<'a, F: Field, CS: Assignment<F>> SingleChipLayouter<'a, F, CS> {
             regions: vec![],
             columns: HashMap::default(),
             table_columns: vec![],
+            instances: InstanceAllocator::new(),
+            namespace: vec![],
             _marker: PhantomData,
         };
         Ok(ret)
@@ -78,7 +91,21 @@ impl<'a, F: Field, CS: Assignment<F> + 'a + SyncDeps> Layouter<F>
 {
     type Root = Self;
 
-    fn assign_region<A, AR, N, NR>(&mut self, name: N, mut assignment: A) -> Result<AR, Error>
+    fn assign_region<A, AR, N, NR>(&mut self, name: N, assignment: A) -> Result<AR, Error>
+    where
+        A: FnMut(Region<'_, F>) -> Result<AR, Error>,
+        N: Fn() -> NR,
+        NR: Into<String>,
+    {
+        self.assign_region_indexed(name, assignment)
+            .map(|(_, result)| result)
+    }
+
+    fn assign_region_indexed<A, AR, N, NR>(
+        &mut self,
+        name: N,
+        mut assignment: A,
+    ) -> Result<(RegionIndex, AR), Error>
     where
         A: FnMut(Region<'_, F>) -> Result<AR, Error>,
         N: Fn() -> NR,
@@ -107,8 +134,9 @@ impl<'a, F: Field, CS: Assignment<F> + 'a + SyncDeps> Layouter<F>
         }
 
         // Assign region cells.
-        self.cs.enter_region(name);
-        let mut region = SingleChipLayouterRegion::new(self, region_index.into());
+        self.cs.enter_region(&name);
+        let region_name = || name().into();
+        let mut region = SingleChipLayouterRegion::new(self, region_index.into(), &region_name);
         let result = {
             let region: &mut dyn RegionLayouter<F> = &mut region;
             assignment(region.into())
@@ -145,7 +173,7 @@ impl<'a, F: Field, CS: Assignment<F> + 'a + SyncDeps> Layouter<F>
             }
         }
 
-        Ok(result)
+        Ok((region_index.into(), result))
     }
 
     fn assign_table<A, N, NR>(&mut self, name: N, mut assignment: A) -> Result<(), Error>
@@ -156,13 +184,14 @@ impl<'a, F: Field, CS: Assignment<F> + 'a + SyncDeps> Layouter<F>
     {
         // Maintenance hazard: there is near-duplicate code in `v1::AssignmentPass::assign_table`.
         // Assign table cells.
-        self.cs.enter_region(name);
-        let mut table = SimpleTableLayouter::new(self.cs, &self.table_columns);
+        self.cs.enter_region(&name);
+        let mut table = SimpleTableLayouter::new(self.cs, &self.table_columns)
+            .with_context(name().into(), self.namespace.clone());
         {
             let table: &mut dyn TableLayouter<F> = &mut table;
             assignment(table.into())
         }?;
-        let default_and_assigned = table.default_and_assigned;
+        let default_and_assigned = table.into_default_and_assigned();
         self.cs.exit_region();
 
         // Check that all table columns have the same length `first_unused`,
@@ -199,10 +228,18 @@ impl<'a, F: Field, CS: Assignment<F> + 'a + SyncDeps> Layouter<F>
         )
     }
 
+    fn next_instance_slot(&mut self, column: Column<Instance>) -> Result<usize, Error> {
+        Ok(self.instances.next_slot(column))
+    }
+
     fn get_challenge(&self, challenge: Challenge) -> Value<F> {
         self.cs.get_challenge(challenge)
     }
 
+    fn current_phase(&self) -> u8 {
+        self.cs.current_phase()
+    }
+
     fn get_root(&mut self) -> &mut Self::Root {
         self
     }
@@ -212,10 +249,13 @@ impl<'a, F: Field, CS: Assignment<F> + 'a + SyncDeps> Layouter<F>
         NR: Into<String>,
         N: FnOnce() -> NR,
     {
-        self.cs.push_namespace(name_fn)
+        let name = name_fn().into();
+        self.namespace.push(name.clone());
+        self.cs.push_namespace(|| name)
     }
 
     fn pop_namespace(&mut self, gadget_name: Option<String>) {
+        self.namespace.pop();
         self.cs.pop_namespace(gadget_name)
     }
 }
@@ -223,6 +263,10 @@ impl<'a, F: Field, CS: Assignment<F> + 'a + SyncDeps> Layouter<F>
 struct SingleChipLayouterRegion<'r, 'a, F: Field, CS: Assignment<F> + 'a> {
     layouter: &'r mut SingleChipLayouter<'a, F, CS>,
     region_index: RegionIndex,
+    /// The name of the region, used to give context to witness and assignment errors.
+    name: &'r dyn Fn() -> String,
+    /// The annotations given to columns within the region.
+    column_annotations: HashMap<Column<Any>, String>,
     /// Stores the constants to be assigned, and the cells to which they are copied.
     constants: Vec<(Assigned<F>, Cell)>,
 }
@@ -239,13 +283,37 @@ impl<'r, 'a, F: Field, CS: Assignment<F> + 'a> fmt::Debug
 }
 
 impl<'r, 'a, F: Field, CS: Assignment<F> + 'a> SingleChipLayouterRegion<'r, 'a, F, CS> {
-    fn new(layouter: &'r mut SingleChipLayouter<'a, F, CS>, region_index: RegionIndex) -> Self {
+    fn new(
+        layouter: &'r mut SingleChipLayouter<'a, F, CS>,
+        region_index: RegionIndex,
+        name: &'r dyn Fn() -> String,
+    ) -> Self {
         SingleChipLayouterRegion {
             layouter,
             region_index,
+            name,
+            column_annotations: HashMap::default(),
             constants: vec![],
         }
     }
+
+    /// Returns the context of a failed assignment within this region.
+    fn context(
+        &self,
+        operation: &'static str,
+        column: Option<Column<Any>>,
+        offset: Option<usize>,
+    ) -> AssignmentContext {
+        AssignmentContext {
+            operation,
+            region: (self.name)(),
+            namespace: self.layouter.namespace.clone(),
+            column,
+            column_annotation: column
+                .and_then(|column| self.column_annotations.get(&column).cloned()),
+            offset,
+        }
+    }
 }
 
 impl<'r, 'a, F: Field, CS: Assignment<F> + 'a + SyncDeps> RegionLayouter<F>
@@ -257,11 +325,33 @@ impl<'r, 'a, F: Field, CS: Assignment<F> + 'a + SyncDeps> RegionLayouter<F>
         selector: &Selector,
         offset: usize,
     ) -> Result<(), Error> {
-        self.layouter.cs.enable_selector(
-            annotation,
-            selector,
-            *self.layouter.regions[*self.region_index] + offset,
-        )
+        self.layouter
+            .cs
+            .enable_selector(
+                annotation,
+                selector,
+                *self.layouter.regions[*self.region_index] + offset,
+            )
+            .map_err(|error| error.in_region(self.context("enable_selector", None, Some(offset))))
+    }
+
+    fn enable_selector_range<'v>(
+        &'v mut self,
+        annotation: &'v (dyn Fn() -> String + 'v),
+        selector: &Selector,
+        offsets: Range<usize>,
+    ) -> Result<(), Error> {
+        let region_start = *self.layouter.regions[*self.region_index];
+        self.layouter
+            .cs
+            .enable_selector_range(
+                annotation,
+                selector,
+                region_start + offsets.start..region_start + offsets.end,
+            )
+            .map_err(|error| {
+                error.in_region(self.context("enable_selector_range", None, Some(offsets.start)))
+            })
     }
 
     fn name_column<'v>(
@@ -269,6 +359,7 @@ impl<'r, 'a, F: Field, CS: Assignment<F> + 'a + SyncDeps> RegionLayouter<F>
         annotation: &'v (dyn Fn() -> String + 'v),
         column: Column<Any>,
     ) {
+        self.column_annotations.insert(column, annotation());
         self.layouter.cs.annotate_column(annotation, column);
     }
 
@@ -279,12 +370,48 @@ impl<'r, 'a, F: Field, CS: Assignment<F> + 'a + SyncDeps> RegionLayouter<F>
         offset: usize,
         to: &'v mut (dyn FnMut() -> Value<Assigned<F>> + 'v),
     ) -> Result<Cell, Error> {
-        self.layouter.cs.assign_advice(
-            annotation,
-            column,
-            *self.layouter.regions[*self.region_index] + offset,
-            to,
-        )?;
+        self.layouter
+            .cs
+            .assign_advice(
+                annotation,
+                column,
+                *self.layouter.regions[*self.region_index] + offset,
+                to,
+            )
+            .map_err(|error| {
+                error.in_region(self.context("assign_advice", Some(column.into()), Some(offset)))
+            })?;
+
+        Ok(Cell {
+            region_index: self.region_index,
+            row_offset: offset,
+            column: column.into(),
+        })
+    }
+
+    fn try_assign_advice<'v>(
+        &'v mut self,
+        annotation: &'v (dyn Fn() -> String + 'v),
+        column: Column<Advice>,
+        offset: usize,
+        to: &'v mut (dyn FnMut() -> Result<Value<Assigned<F>>, Error> + 'v),
+    ) -> Result<Cell, Error> {
+        let name = self.name;
+        self.layouter
+            .cs
+            .try_assign_advice(
+                annotation,
+                column,
+                *self.layouter.regions[*self.region_index] + offset,
+                || {
+                    to().map_err(|error| {
+                        Error::witness_failure(name(), annotation(), offset, error)
+                    })
+                },
+            )
+            .map_err(|error| {
+                error.in_region(self.context("assign_advice", Some(column.into()), Some(offset)))
+            })?;
 
         Ok(Cell {
             region_index: self.region_index,
@@ -315,16 +442,25 @@ impl<'r, 'a, F: Field, CS: Assignment<F> + 'a + SyncDeps> RegionLayouter<F>
         advice: Column<Advice>,
         offset: usize,
     ) -> Result<(Cell, Value<F>), Error> {
-        let value = self.layouter.cs.query_instance(instance, row)?;
+        let value = self.instance_value(instance, row)?;
 
         let cell = self.assign_advice(annotation, advice, offset, &mut || value.to_field())?;
 
-        self.layouter.cs.copy(
-            cell.column,
-            *self.layouter.regions[*cell.region_index] + cell.row_offset,
-            instance.into(),
-            row,
-        )?;
+        self.layouter
+            .cs
+            .copy(
+                cell.column,
+                *self.layouter.regions[*cell.region_index] + cell.row_offset,
+                instance.into(),
+                row,
+            )
+            .map_err(|error| {
+                error.in_region(self.context(
+                    "assign_advice_from_instance",
+                    Some(cell.column),
+                    Some(offset),
+                ))
+            })?;
 
         Ok((cell, value))
     }
@@ -334,7 +470,12 @@ impl<'r, 'a, F: Field, CS: Assignment<F> + 'a + SyncDeps> RegionLayouter<F>
         instance: Column<Instance>,
         row: usize,
     ) -> Result<Value<F>, Error> {
-        self.layouter.cs.query_instance(instance, row)
+        self.layouter
+            .cs
+            .query_instance(instance, row)
+            .map_err(|error| {
+                error.in_region(self.context("query_instance", Some(instance.into()), None))
+            })
     }
 
     fn assign_fixed<'v>(
@@ -344,12 +485,17 @@ impl<'r, 'a, F: Field, CS: Assignment<F> + 'a + SyncDeps> RegionLayouter<F>
         offset: usize,
         to: &'v mut (dyn FnMut() -> Value<Assigned<F>> + 'v),
     ) -> Result<Cell, Error> {
-        self.layouter.cs.assign_fixed(
-            annotation,
-            column,
-            *self.layouter.regions[*self.region_index] + offset,
-            to,
-        )?;
+        self.layouter
+            .cs
+            .assign_fixed(
+                annotation,
+                column,
+                *self.layouter.regions[*self.region_index] + offset,
+                to,
+            )
+            .map_err(|error| {
+                error.in_region(self.context("assign_fixed", Some(column.into()), Some(offset)))
+            })?;
 
         Ok(Cell {
             region_index: self.region_index,
@@ -358,18 +504,48 @@ impl<'r, 'a, F: Field, CS: Assignment<F> + 'a + SyncDeps> RegionLayouter<F>
         })
     }
 
+    fn fill_advice_from_offset(
+        &mut self,
+        column: Column<Advice>,
+        offset: usize,
+        to: Value<Assigned<F>>,
+    ) -> Result<(), Error> {
+        self.layouter
+            .cs
+            .fill_advice_from_row(
+                column,
+                *self.layouter.regions[*self.region_index] + offset,
+                to,
+            )
+            .map_err(|error| {
+                error.in_region(self.context(
+                    "fill_advice_from_offset",
+                    Some(column.into()),
+                    Some(offset),
+                ))
+            })
+    }
+
     fn constrain_constant(&mut self, cell: Cell, constant: Assigned<F>) -> Result<(), Error> {
         self.constants.push((constant, cell));
         Ok(())
     }
 
     fn constrain_equal(&mut self, left: Cell, right: Cell) -> Result<(), Error> {
-        self.layouter.cs.copy(
-            left.column,
-            *self.layouter.regions[*left.region_index] + left.row_offset,
-            right.column,
-            *self.layouter.regions[*right.region_index] + right.row_offset,
-        )?;
+        self.layouter
+            .cs
+            .copy(
+                left.column,
+                *self.layouter.regions[*left.region_index] + left.row_offset,
+                right.column,
+                *self.layouter.regions[*right.region_index] + right.row_offset,
+            )
+            .map_err(|error| {
+                // The offset of the left cell is only meaningful within this region.
+                let offset =
+                    Some(left.row_offset).filter(|_| *left.region_index == *self.region_index);
+                error.in_region(self.context("constrain_equal", Some(left.column), offset))
+            })?;
 
         Ok(())
     }
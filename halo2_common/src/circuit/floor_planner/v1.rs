@@ -1,15 +1,22 @@
+use std::collections::HashMap;
 use std::fmt;
+use std::ops::Range;
 
 use halo2_middleware::ff::Field;
 
 use crate::plonk::Assigned;
 use crate::{
     circuit::{
-        layouter::{RegionColumn, RegionLayouter, RegionShape, SyncDeps, TableLayouter},
+        layouter::{
+            InstanceAllocator, RegionColumn, RegionLayouter, RegionShape, SyncDeps, TableLayouter,
+        },
         table_layouter::{compute_table_lengths, SimpleTableLayouter},
         Cell, Column, Layouter, Region, RegionIndex, RegionStart, Table, Value,
     },
-    plonk::{circuit::Challenge, Assignment, Circuit, Error, FloorPlanner, Selector, TableColumn},
+    plonk::{
+        circuit::Challenge, Assignment, AssignmentContext, Circuit, Error, FloorPlanner, Selector,
+        TableColumn,
+    },
 };
 use halo2_middleware::circuit::{Advice, Any, Fixed, Instance};
 
@@ -26,14 +33,34 @@ pub mod strategy;
 #[derive(Debug)]
 pub struct V1;
 
+/// A variant of the [`V1`] floor planner that synthesizes the circuit only once, for
+/// circuits that declare the shapes of their regions with [`Circuit::region_shapes`].
+///
+/// - The declared shapes are used to lay out the regions in the same way as [`V1`] lays
+///   out measured regions, so both floor planners produce the same layout for circuits
+///   that declare their shapes accurately.
+/// - Assignments outside of the declared shape of their region are reported as
+///   [`Error::RegionShapeExceeded`].
+/// - Circuits that don't declare their region shapes are measured as with [`V1`].
+#[derive(Debug)]
+pub struct V1SinglePass;
+
 struct V1Plan<'a, F: Field, CS: Assignment<F> + 'a> {
     cs: &'a mut CS,
     /// Stores the starting row for each region.
     regions: Vec<RegionStart>,
+    /// Stores the number of rows of each region.
+    region_rows: Vec<usize>,
     /// Stores the constants to be assigned, and the cells to which they are copied.
     constants: Vec<(Assigned<F>, Cell)>,
     /// Stores the table fixed columns.
     table_columns: Vec<TableColumn>,
+    /// The shapes declared by the circuit for its regions, which assignments are checked
+    /// against. `None` if the regions were measured.
+    declared_shapes: Option<Vec<RegionShape>>,
+    /// The namespaces that regions are currently assigned within, used to give context to
+    /// assignment errors.
+    namespace: Vec<String>,
 }
 
 impl<'a, F: Field, CS: Assignment<F> + 'a> fmt::Debug for V1Plan<'a, F, CS> {
@@ -48,8 +75,11 @@ impl<'a, F: Field, CS: Assignment<F> + SyncDeps> V1Plan<'a, F, CS> {
         let ret = V1Plan {
             cs,
             regions: vec![],
+            region_rows: vec![],
             constants: vec![],
             table_columns: vec![],
+            declared_shapes: None,
+            namespace: vec![],
         };
         Ok(ret)
     }
@@ -62,81 +92,146 @@ impl FloorPlanner for V1 {
         config: C::Config,
         constants: Vec<Column<Fixed>>,
     ) -> Result<(), Error> {
-        let mut plan = V1Plan::new(cs)?;
-
-        // First pass: measure the regions within the circuit.
-        let mut measure = MeasurementPass::new();
-        {
-            let pass = &mut measure;
-            circuit
-                .without_witnesses()
-                .synthesize(config.clone(), V1Pass::<_, CS>::measure(pass))?;
+        let shapes = measure::<_, CS, _>(circuit, &config)?;
+        plan_and_assign(cs, circuit, config, constants, shapes, false)
+    }
+}
+
+impl FloorPlanner for V1SinglePass {
+    fn synthesize<F: Field, CS: Assignment<F> + SyncDeps, C: Circuit<F>>(
+        cs: &mut CS,
+        circuit: &C,
+        config: C::Config,
+        constants: Vec<Column<Fixed>>,
+    ) -> Result<(), Error> {
+        match circuit.region_shapes(&config) {
+            Some(shapes) => {
+                // The shapes are laid out by their index, which is their position in the
+                // order of assignment.
+                let shapes = shapes
+                    .into_iter()
+                    .enumerate()
+                    .map(|(index, shape)| RegionShape {
+                        region_index: index.into(),
+                        ..shape
+                    })
+                    .collect();
+                plan_and_assign(cs, circuit, config, constants, shapes, true)
+            }
+            None => {
+                let shapes = measure::<_, CS, _>(circuit, &config)?;
+                plan_and_assign(cs, circuit, config, constants, shapes, false)
+            }
         }
+    }
+}
 
-        // Planning:
-        // - Position the regions.
-        let (regions, column_allocations) = strategy::slot_in_biggest_advice_first(measure.regions);
-        plan.regions = regions;
-
-        // - Determine how many rows our planned circuit will require.
-        let first_unassigned_row = column_allocations
-            .values()
-            .map(|a| a.unbounded_interval_start())
-            .max()
-            .unwrap_or(0);
-
-        // - Position the constants within those rows.
-        let fixed_allocations: Vec<_> = constants
-            .into_iter()
-            .map(|c| {
-                (
-                    c,
-                    column_allocations
-                        .get(&Column::<Any>::from(c).into())
-                        .cloned()
-                        .unwrap_or_default(),
-                )
-            })
-            .collect();
-        let constant_positions = || {
-            fixed_allocations.iter().flat_map(|(c, a)| {
-                let c = *c;
-                a.free_intervals(0, Some(first_unassigned_row))
-                    .flat_map(move |e| e.range().unwrap().map(move |i| (c, i)))
-            })
-        };
+/// Measures the regions of `circuit` by synthesizing it without witnesses.
+fn measure<F: Field, CS: Assignment<F> + SyncDeps, C: Circuit<F>>(
+    circuit: &C,
+    config: &C::Config,
+) -> Result<Vec<RegionShape>, Error> {
+    let mut measure = MeasurementPass::new();
+    {
+        let pass = &mut measure;
+        circuit
+            .without_witnesses()
+            .synthesize(config.clone(), V1Pass::<_, CS>::measure(pass))?;
+    }
+    Ok(measure.regions)
+}
 
-        // Second pass:
-        // - Assign the regions.
-        let mut assign = AssignmentPass::new(&mut plan);
-        {
-            let pass = &mut assign;
-            circuit.synthesize(config, V1Pass::assign(pass))?;
-        }
+/// Lays out the regions with the given `shapes`, and assigns the circuit.
+///
+/// If `declared` is true, the shapes were declared by the circuit rather than measured,
+/// and assignments are checked against them.
+fn plan_and_assign<F: Field, CS: Assignment<F> + SyncDeps, C: Circuit<F>>(
+    cs: &mut CS,
+    circuit: &C,
+    config: C::Config,
+    constants: Vec<Column<Fixed>>,
+    shapes: Vec<RegionShape>,
+    declared: bool,
+) -> Result<(), Error> {
+    let mut plan = V1Plan::new(cs)?;
+
+    // Planning:
+    // - Position the regions.
+    plan.region_rows = shapes.iter().map(|r| r.row_count()).collect();
+    if declared {
+        plan.declared_shapes = Some(shapes.clone());
+    }
+    let (regions, column_allocations) = strategy::slot_in_biggest_advice_first(shapes);
+    plan.regions = regions;
+
+    // - Determine how many rows our planned circuit will require.
+    let first_unassigned_row = column_allocations
+        .values()
+        .map(|a| a.unbounded_interval_start())
+        .max()
+        .unwrap_or(0);
+
+    // - Position the constants within those rows.
+    let fixed_allocations: Vec<_> = constants
+        .into_iter()
+        .map(|c| {
+            (
+                c,
+                column_allocations
+                    .get(&Column::<Any>::from(c).into())
+                    .cloned()
+                    .unwrap_or_default(),
+            )
+        })
+        .collect();
+    let constant_positions = || {
+        fixed_allocations.iter().flat_map(|(c, a)| {
+            let c = *c;
+            a.free_intervals(0, Some(first_unassigned_row))
+                .flat_map(move |e| e.range().unwrap().map(move |i| (c, i)))
+        })
+    };
 
-        // - Assign the constants.
-        if constant_positions().count() < plan.constants.len() {
-            return Err(Error::NotEnoughColumnsForConstants);
-        }
-        for ((fixed_column, fixed_row), (value, advice)) in
-            constant_positions().zip(plan.constants.into_iter())
-        {
-            plan.cs.assign_fixed(
-                || format!("Constant({:?})", value.evaluate()),
-                fixed_column,
-                fixed_row,
-                || Value::known(value),
-            )?;
-            plan.cs.copy(
-                fixed_column.into(),
-                fixed_row,
-                advice.column,
-                *plan.regions[*advice.region_index] + advice.row_offset,
-            )?;
+    // Assignment pass:
+    // - Assign the regions.
+    let mut assign = AssignmentPass::new(&mut plan);
+    {
+        let pass = &mut assign;
+        circuit.synthesize(config, V1Pass::assign(pass))?;
+    }
+    let assigned_regions = assign.region_index;
+    if let Some(shapes) = &plan.declared_shapes {
+        if assigned_regions < shapes.len() {
+            return Err(Error::Other(format!(
+                "the circuit declares the shapes of {} regions, but only assigns {} regions",
+                shapes.len(),
+                assigned_regions
+            )));
         }
+    }
 
-        Ok(())
+    // - Assign the constants.
+    if constant_positions().count() < plan.constants.len() {
+        return Err(Error::NotEnoughColumnsForConstants);
+    }
+    for ((fixed_column, fixed_row), (value, advice)) in
+        constant_positions().zip(plan.constants.into_iter())
+    {
+        plan.cs.assign_fixed(
+            || format!("Constant({:?})", value.evaluate()),
+            fixed_column,
+            fixed_row,
+            || Value::known(value),
+        )?;
+        plan.cs.copy(
+            fixed_column.into(),
+            fixed_row,
+            advice.column,
+            *plan.regions[*advice.region_index] + advice.row_offset,
+        )?;
     }
+
+    Ok(())
 }
 
 #[derive(Debug)]
@@ -163,6 +258,20 @@ impl<'p, 'a, F: Field, CS: Assignment<F> + SyncDeps> Layouter<F> for V1Pass<'p,
     type Root = Self;
 
     fn assign_region<A, AR, N, NR>(&mut self, name: N, assignment: A) -> Result<AR, Error>
+    where
+        A: FnMut(Region<'_, F>) -> Result<AR, Error>,
+        N: Fn() -> NR,
+        NR: Into<String>,
+    {
+        self.assign_region_indexed(name, assignment)
+            .map(|(_, result)| result)
+    }
+
+    fn assign_region_indexed<A, AR, N, NR>(
+        &mut self,
+        name: N,
+        assignment: A,
+    ) -> Result<(RegionIndex, AR), Error>
     where
         A: FnMut(Region<'_, F>) -> Result<AR, Error>,
         N: Fn() -> NR,
@@ -198,6 +307,14 @@ impl<'p, 'a, F: Field, CS: Assignment<F> + SyncDeps> Layouter<F> for V1Pass<'p,
         }
     }
 
+    fn next_instance_slot(&mut self, column: Column<Instance>) -> Result<usize, Error> {
+        // Each pass owns its own allocator, so that both passes hand out the same rows.
+        Ok(match &mut self.0 {
+            Pass::Measurement(pass) => pass.instances.next_slot(column),
+            Pass::Assignment(pass) => pass.instances.next_slot(column),
+        })
+    }
+
     fn get_challenge(&self, challenge: Challenge) -> Value<F> {
         match &self.0 {
             Pass::Measurement(_) => Value::unknown(),
@@ -205,6 +322,15 @@ impl<'p, 'a, F: Field, CS: Assignment<F> + SyncDeps> Layouter<F> for V1Pass<'p,
         }
     }
 
+    fn current_phase(&self) -> u8 {
+        match &self.0 {
+            // Measure the regions as if all phases run, so that the shapes include the
+            // assignments of every phase.
+            Pass::Measurement(_) => u8::MAX,
+            Pass::Assignment(pass) => pass.plan.cs.current_phase(),
+        }
+    }
+
     fn get_root(&mut self) -> &mut Self::Root {
         self
     }
@@ -215,12 +341,15 @@ impl<'p, 'a, F: Field, CS: Assignment<F> + SyncDeps> Layouter<F> for V1Pass<'p,
         N: FnOnce() -> NR,
     {
         if let Pass::Assignment(pass) = &mut self.0 {
-            pass.plan.cs.push_namespace(name_fn);
+            let name = name_fn().into();
+            pass.plan.namespace.push(name.clone());
+            pass.plan.cs.push_namespace(|| name);
         }
     }
 
     fn pop_namespace(&mut self, gadget_name: Option<String>) {
         if let Pass::Assignment(pass) = &mut self.0 {
+            pass.plan.namespace.pop();
             pass.plan.cs.pop_namespace(gadget_name);
         }
     }
@@ -230,14 +359,21 @@ impl<'p, 'a, F: Field, CS: Assignment<F> + SyncDeps> Layouter<F> for V1Pass<'p,
 #[derive(Debug)]
 pub struct MeasurementPass {
     regions: Vec<RegionShape>,
+    instances: InstanceAllocator,
 }
 
 impl MeasurementPass {
     fn new() -> Self {
-        MeasurementPass { regions: vec![] }
+        MeasurementPass {
+            regions: vec![],
+            instances: InstanceAllocator::new(),
+        }
     }
 
-    fn assign_region<F: Field, A, AR>(&mut self, mut assignment: A) -> Result<AR, Error>
+    fn assign_region<F: Field, A, AR>(
+        &mut self,
+        mut assignment: A,
+    ) -> Result<(RegionIndex, AR), Error>
     where
         A: FnMut(Region<'_, F>) -> Result<AR, Error>,
     {
@@ -251,7 +387,7 @@ impl MeasurementPass {
         }?;
         self.regions.push(shape);
 
-        Ok(result)
+        Ok((region_index.into(), result))
     }
 }
 
@@ -261,6 +397,8 @@ pub struct AssignmentPass<'p, 'a, F: Field, CS: Assignment<F> + 'a> {
     plan: &'p mut V1Plan<'a, F, CS>,
     /// Counter tracking which region we need to assign next.
     region_index: usize,
+    /// Hands out the instance rows for `Layouter::next_instance_slot`.
+    instances: InstanceAllocator,
 }
 
 impl<'p, 'a, F: Field, CS: Assignment<F> + SyncDeps> AssignmentPass<'p, 'a, F, CS> {
@@ -268,10 +406,15 @@ impl<'p, 'a, F: Field, CS: Assignment<F> + SyncDeps> AssignmentPass<'p, 'a, F, C
         AssignmentPass {
             plan,
             region_index: 0,
+            instances: InstanceAllocator::new(),
         }
     }
 
-    fn assign_region<A, AR, N, NR>(&mut self, name: N, mut assignment: A) -> Result<AR, Error>
+    fn assign_region<A, AR, N, NR>(
+        &mut self,
+        name: N,
+        mut assignment: A,
+    ) -> Result<(RegionIndex, AR), Error>
     where
         A: FnMut(Region<'_, F>) -> Result<AR, Error>,
         N: Fn() -> NR,
@@ -281,15 +424,35 @@ impl<'p, 'a, F: Field, CS: Assignment<F> + SyncDeps> AssignmentPass<'p, 'a, F, C
         let region_index = self.region_index;
         self.region_index += 1;
 
-        self.plan.cs.enter_region(name);
-        let mut region = V1Region::new(self.plan, region_index.into());
+        if let Some(shapes) = &self.plan.declared_shapes {
+            if region_index >= shapes.len() {
+                return Err(Error::Other(format!(
+                    "region \"{}\" is not one of the {} regions whose shapes the circuit declares",
+                    name().into(),
+                    shapes.len()
+                )));
+            }
+        }
+
+        // Check that the region fits in the circuit before assigning any of its cells.
+        if let Some(usable_rows) = self.plan.cs.usable_rows() {
+            let start = *self.plan.regions[region_index];
+            let rows = start..start + self.plan.region_rows[region_index];
+            if !rows.is_empty() && rows.end > usable_rows.end {
+                return Err(Error::region_out_of_rows(name().into(), rows, usable_rows));
+            }
+        }
+
+        self.plan.cs.enter_region(&name);
+        let region_name = || name().into();
+        let mut region = V1Region::new(self.plan, region_index.into(), &region_name);
         let result = {
             let region: &mut dyn RegionLayouter<F> = &mut region;
             assignment(region.into())
         }?;
         self.plan.cs.exit_region();
 
-        Ok(result)
+        Ok((region_index.into(), result))
     }
 
     fn assign_table<A, AR, N, NR>(&mut self, name: N, mut assignment: A) -> Result<AR, Error>
@@ -301,13 +464,14 @@ impl<'p, 'a, F: Field, CS: Assignment<F> + SyncDeps> AssignmentPass<'p, 'a, F, C
         // Maintenance hazard: there is near-duplicate code in `SingleChipLayouter::assign_table`.
 
         // Assign table cells.
-        self.plan.cs.enter_region(name);
-        let mut table = SimpleTableLayouter::new(self.plan.cs, &self.plan.table_columns);
+        self.plan.cs.enter_region(&name);
+        let mut table = SimpleTableLayouter::new(self.plan.cs, &self.plan.table_columns)
+            .with_context(name().into(), self.plan.namespace.clone());
         let result = {
             let table: &mut dyn TableLayouter<F> = &mut table;
             assignment(table.into())
         }?;
-        let default_and_assigned = table.default_and_assigned;
+        let default_and_assigned = table.into_default_and_assigned();
         self.plan.cs.exit_region();
 
         // Check that all table columns have the same length `first_unused`,
@@ -349,6 +513,10 @@ impl<'p, 'a, F: Field, CS: Assignment<F> + SyncDeps> AssignmentPass<'p, 'a, F, C
 struct V1Region<'r, 'a, F: Field, CS: Assignment<F> + 'a> {
     plan: &'r mut V1Plan<'a, F, CS>,
     region_index: RegionIndex,
+    /// The name of the region, used to give context to witness and assignment errors.
+    name: &'r dyn Fn() -> String,
+    /// The annotations given to columns within the region.
+    column_annotations: HashMap<Column<Any>, String>,
 }
 
 impl<'r, 'a, F: Field, CS: Assignment<F> + 'a> fmt::Debug for V1Region<'r, 'a, F, CS> {
@@ -361,8 +529,51 @@ impl<'r, 'a, F: Field, CS: Assignment<F> + 'a> fmt::Debug for V1Region<'r, 'a, F
 }
 
 impl<'r, 'a, F: Field, CS: Assignment<F> + 'a> V1Region<'r, 'a, F, CS> {
-    fn new(plan: &'r mut V1Plan<'a, F, CS>, region_index: RegionIndex) -> Self {
-        V1Region { plan, region_index }
+    fn new(
+        plan: &'r mut V1Plan<'a, F, CS>,
+        region_index: RegionIndex,
+        name: &'r dyn Fn() -> String,
+    ) -> Self {
+        V1Region {
+            plan,
+            region_index,
+            name,
+            column_annotations: HashMap::default(),
+        }
+    }
+
+    /// Returns the context of a failed assignment within this region.
+    fn context(
+        &self,
+        operation: &'static str,
+        column: Option<Column<Any>>,
+        offset: Option<usize>,
+    ) -> AssignmentContext {
+        AssignmentContext {
+            operation,
+            region: (self.name)(),
+            namespace: self.plan.namespace.clone(),
+            column,
+            column_annotation: column
+                .and_then(|column| self.column_annotations.get(&column).cloned()),
+            offset,
+        }
+    }
+
+    /// Checks that an assignment to `column` at `offset` is within the shape declared for
+    /// this region, if the circuit declared one.
+    fn check_shape(&self, column: RegionColumn, offset: usize) -> Result<(), Error> {
+        match &self.plan.declared_shapes {
+            Some(shapes) if !shapes[*self.region_index].contains(column, offset) => {
+                Err(Error::RegionShapeExceeded {
+                    region: (self.name)(),
+                    column,
+                    offset,
+                    row_count: shapes[*self.region_index].row_count(),
+                })
+            }
+            _ => Ok(()),
+        }
     }
 }
 
@@ -373,11 +584,37 @@ impl<'r, 'a, F: Field, CS: Assignment<F> + SyncDeps> RegionLayouter<F> for V1Reg
         selector: &Selector,
         offset: usize,
     ) -> Result<(), Error> {
-        self.plan.cs.enable_selector(
-            annotation,
-            selector,
-            *self.plan.regions[*self.region_index] + offset,
-        )
+        self.check_shape((*selector).into(), offset)?;
+        self.plan
+            .cs
+            .enable_selector(
+                annotation,
+                selector,
+                *self.plan.regions[*self.region_index] + offset,
+            )
+            .map_err(|error| error.in_region(self.context("enable_selector", None, Some(offset))))
+    }
+
+    fn enable_selector_range<'v>(
+        &'v mut self,
+        annotation: &'v (dyn Fn() -> String + 'v),
+        selector: &Selector,
+        offsets: Range<usize>,
+    ) -> Result<(), Error> {
+        if !offsets.is_empty() {
+            self.check_shape((*selector).into(), offsets.end - 1)?;
+        }
+        let region_start = *self.plan.regions[*self.region_index];
+        self.plan
+            .cs
+            .enable_selector_range(
+                annotation,
+                selector,
+                region_start + offsets.start..region_start + offsets.end,
+            )
+            .map_err(|error| {
+                error.in_region(self.context("enable_selector_range", None, Some(offsets.start)))
+            })
     }
 
     fn assign_advice<'v>(
@@ -387,12 +624,50 @@ impl<'r, 'a, F: Field, CS: Assignment<F> + SyncDeps> RegionLayouter<F> for V1Reg
         offset: usize,
         to: &'v mut (dyn FnMut() -> Value<Assigned<F>> + 'v),
     ) -> Result<Cell, Error> {
-        self.plan.cs.assign_advice(
-            annotation,
-            column,
-            *self.plan.regions[*self.region_index] + offset,
-            to,
-        )?;
+        self.check_shape(Column::<Any>::from(column).into(), offset)?;
+        self.plan
+            .cs
+            .assign_advice(
+                annotation,
+                column,
+                *self.plan.regions[*self.region_index] + offset,
+                to,
+            )
+            .map_err(|error| {
+                error.in_region(self.context("assign_advice", Some(column.into()), Some(offset)))
+            })?;
+
+        Ok(Cell {
+            region_index: self.region_index,
+            row_offset: offset,
+            column: column.into(),
+        })
+    }
+
+    fn try_assign_advice<'v>(
+        &'v mut self,
+        annotation: &'v (dyn Fn() -> String + 'v),
+        column: Column<Advice>,
+        offset: usize,
+        to: &'v mut (dyn FnMut() -> Result<Value<Assigned<F>>, Error> + 'v),
+    ) -> Result<Cell, Error> {
+        self.check_shape(Column::<Any>::from(column).into(), offset)?;
+        let name = self.name;
+        self.plan
+            .cs
+            .try_assign_advice(
+                annotation,
+                column,
+                *self.plan.regions[*self.region_index] + offset,
+                || {
+                    to().map_err(|error| {
+                        Error::witness_failure(name(), annotation(), offset, error)
+                    })
+                },
+            )
+            .map_err(|error| {
+                error.in_region(self.context("assign_advice", Some(column.into()), Some(offset)))
+            })?;
 
         Ok(Cell {
             region_index: self.region_index,
@@ -423,16 +698,25 @@ impl<'r, 'a, F: Field, CS: Assignment<F> + SyncDeps> RegionLayouter<F> for V1Reg
         advice: Column<Advice>,
         offset: usize,
     ) -> Result<(Cell, Value<F>), Error> {
-        let value = self.plan.cs.query_instance(instance, row)?;
+        let value = self.instance_value(instance, row)?;
 
         let cell = self.assign_advice(annotation, advice, offset, &mut || value.to_field())?;
 
-        self.plan.cs.copy(
-            cell.column,
-            *self.plan.regions[*cell.region_index] + cell.row_offset,
-            instance.into(),
-            row,
-        )?;
+        self.plan
+            .cs
+            .copy(
+                cell.column,
+                *self.plan.regions[*cell.region_index] + cell.row_offset,
+                instance.into(),
+                row,
+            )
+            .map_err(|error| {
+                error.in_region(self.context(
+                    "assign_advice_from_instance",
+                    Some(cell.column),
+                    Some(offset),
+                ))
+            })?;
 
         Ok((cell, value))
     }
@@ -442,7 +726,9 @@ impl<'r, 'a, F: Field, CS: Assignment<F> + SyncDeps> RegionLayouter<F> for V1Reg
         instance: Column<Instance>,
         row: usize,
     ) -> Result<Value<F>, Error> {
-        self.plan.cs.query_instance(instance, row)
+        self.plan.cs.query_instance(instance, row).map_err(|error| {
+            error.in_region(self.context("query_instance", Some(instance.into()), None))
+        })
     }
 
     fn assign_fixed<'v>(
@@ -452,12 +738,18 @@ impl<'r, 'a, F: Field, CS: Assignment<F> + SyncDeps> RegionLayouter<F> for V1Reg
         offset: usize,
         to: &'v mut (dyn FnMut() -> Value<Assigned<F>> + 'v),
     ) -> Result<Cell, Error> {
-        self.plan.cs.assign_fixed(
-            annotation,
-            column,
-            *self.plan.regions[*self.region_index] + offset,
-            to,
-        )?;
+        self.check_shape(Column::<Any>::from(column).into(), offset)?;
+        self.plan
+            .cs
+            .assign_fixed(
+                annotation,
+                column,
+                *self.plan.regions[*self.region_index] + offset,
+                to,
+            )
+            .map_err(|error| {
+                error.in_region(self.context("assign_fixed", Some(column.into()), Some(offset)))
+            })?;
 
         Ok(Cell {
             region_index: self.region_index,
@@ -466,6 +758,25 @@ impl<'r, 'a, F: Field, CS: Assignment<F> + SyncDeps> RegionLayouter<F> for V1Reg
         })
     }
 
+    fn fill_advice_from_offset(
+        &mut self,
+        column: Column<Advice>,
+        offset: usize,
+        to: Value<Assigned<F>>,
+    ) -> Result<(), Error> {
+        self.check_shape(Column::<Any>::from(column).into(), offset)?;
+        self.plan
+            .cs
+            .fill_advice_from_row(column, *self.plan.regions[*self.region_index] + offset, to)
+            .map_err(|error| {
+                error.in_region(self.context(
+                    "fill_advice_from_offset",
+                    Some(column.into()),
+                    Some(offset),
+                ))
+            })
+    }
+
     fn constrain_constant(&mut self, cell: Cell, constant: Assigned<F>) -> Result<(), Error> {
         self.plan.constants.push((constant, cell));
         Ok(())
@@ -476,16 +787,25 @@ impl<'r, 'a, F: Field, CS: Assignment<F> + SyncDeps> RegionLayouter<F> for V1Reg
         annotation: &'v (dyn Fn() -> String + 'v),
         column: Column<Any>,
     ) {
+        self.column_annotations.insert(column, annotation());
         self.plan.cs.annotate_column(annotation, column)
     }
 
     fn constrain_equal(&mut self, left: Cell, right: Cell) -> Result<(), Error> {
-        self.plan.cs.copy(
-            left.column,
-            *self.plan.regions[*left.region_index] + left.row_offset,
-            right.column,
-            *self.plan.regions[*right.region_index] + right.row_offset,
-        )?;
+        self.plan
+            .cs
+            .copy(
+                left.column,
+                *self.plan.regions[*left.region_index] + left.row_offset,
+                right.column,
+                *self.plan.regions[*right.region_index] + right.row_offset,
+            )
+            .map_err(|error| {
+                // The offset of the left cell is only meaningful within this region.
+                let offset =
+                    Some(left.row_offset).filter(|_| *left.region_index == *self.region_index);
+                error.in_region(self.context("constrain_equal", Some(left.column), offset))
+            })?;
 
         Ok(())
     }
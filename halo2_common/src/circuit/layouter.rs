@@ -1,8 +1,9 @@
 //! Implementations of common circuit layouters.
 
 use std::cmp;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::fmt;
+use std::ops::Range;
 
 use halo2_middleware::ff::Field;
 
@@ -65,6 +66,22 @@ pub trait RegionLayouter<F: Field>: fmt::Debug + SyncDeps {
         offset: usize,
     ) -> Result<(), Error>;
 
+    /// Enables a selector at every offset in `offsets`.
+    ///
+    /// The default implementation calls [`RegionLayouter::enable_selector`] for each
+    /// offset.
+    fn enable_selector_range<'v>(
+        &'v mut self,
+        annotation: &'v (dyn Fn() -> String + 'v),
+        selector: &Selector,
+        offsets: Range<usize>,
+    ) -> Result<(), Error> {
+        for offset in offsets {
+            self.enable_selector(annotation, selector, offset)?;
+        }
+        Ok(())
+    }
+
     /// Allows the circuit implementor to name/annotate a Column within a Region context.
     ///
     /// This is useful in order to improve the amount of information that `prover.verify()`
@@ -84,6 +101,27 @@ pub trait RegionLayouter<F: Field>: fmt::Debug + SyncDeps {
         to: &'v mut (dyn FnMut() -> Value<Assigned<F>> + 'v),
     ) -> Result<Cell, Error>;
 
+    /// Assign an advice column value (witness), where computing the value may fail.
+    ///
+    /// The default implementation wraps [`RegionLayouter::assign_advice`] and returns the
+    /// error of `to` as is; layouters that know the region name should attach it.
+    fn try_assign_advice<'v>(
+        &'v mut self,
+        annotation: &'v (dyn Fn() -> String + 'v),
+        column: Column<Advice>,
+        offset: usize,
+        to: &'v mut (dyn FnMut() -> Result<Value<Assigned<F>>, Error> + 'v),
+    ) -> Result<Cell, Error> {
+        let mut result = Ok(());
+        let cell = self.assign_advice(annotation, column, offset, &mut || {
+            to().unwrap_or_else(|error| {
+                result = Err(error);
+                Value::unknown()
+            })
+        });
+        result.and(cell)
+    }
+
     /// Assigns a constant value to the column `advice` at `offset` within this region.
     ///
     /// The constant value will be assigned to a cell within one of the fixed columns
@@ -125,6 +163,15 @@ pub trait RegionLayouter<F: Field>: fmt::Debug + SyncDeps {
         to: &'v mut (dyn FnMut() -> Value<Assigned<F>> + 'v),
     ) -> Result<Cell, Error>;
 
+    /// Fills an advice `column` with the value `to`, from `offset` within this region up
+    /// to the last usable row of the circuit.
+    fn fill_advice_from_offset(
+        &mut self,
+        column: Column<Advice>,
+        offset: usize,
+        to: Value<Assigned<F>>,
+    ) -> Result<(), Error>;
+
     /// Constrains a cell to have a constant value.
     ///
     /// Returns an error if the cell is in a column where equality has not been enabled.
@@ -194,6 +241,28 @@ impl RegionShape {
         }
     }
 
+    /// Declare the shape of the region at `region_index`, spanning `row_count` rows of the
+    /// given `columns`.
+    ///
+    /// This is used by circuits whose regions have statically known shapes; see
+    /// [`Circuit::region_shapes`](crate::plonk::Circuit::region_shapes).
+    pub fn declare(
+        region_index: RegionIndex,
+        columns: impl IntoIterator<Item = RegionColumn>,
+        row_count: usize,
+    ) -> Self {
+        RegionShape {
+            region_index,
+            columns: columns.into_iter().collect(),
+            row_count,
+        }
+    }
+
+    /// Returns true if `column` is part of this shape, and `offset` is within its rows.
+    pub fn contains(&self, column: RegionColumn, offset: usize) -> bool {
+        offset < self.row_count && self.columns.contains(&column)
+    }
+
     /// Get the `region_index` of a `RegionShape`.
     pub fn region_index(&self) -> RegionIndex {
         self.region_index
@@ -223,6 +292,20 @@ impl<F: Field> RegionLayouter<F> for RegionShape {
         Ok(())
     }
 
+    fn enable_selector_range<'v>(
+        &'v mut self,
+        _: &'v (dyn Fn() -> String + 'v),
+        selector: &Selector,
+        offsets: Range<usize>,
+    ) -> Result<(), Error> {
+        if !offsets.is_empty() {
+            // Track the selector's fixed column as part of the region's shape.
+            self.columns.insert((*selector).into());
+            self.row_count = cmp::max(self.row_count, offsets.end);
+        }
+        Ok(())
+    }
+
     fn assign_advice<'v>(
         &'v mut self,
         _: &'v (dyn Fn() -> String + 'v),
@@ -297,6 +380,19 @@ impl<F: Field> RegionLayouter<F> for RegionShape {
         })
     }
 
+    fn fill_advice_from_offset(
+        &mut self,
+        column: Column<Advice>,
+        offset: usize,
+        _: Value<Assigned<F>>,
+    ) -> Result<(), Error> {
+        // Only the first filled row is part of the region's shape; the rest of the column
+        // can still be used by other regions, whose assignments take precedence.
+        self.columns.insert(Column::<Any>::from(column).into());
+        self.row_count = cmp::max(self.row_count, offset + 1);
+        Ok(())
+    }
+
     fn name_column<'v>(
         &'v mut self,
         _annotation: &'v (dyn Fn() -> String + 'v),
@@ -315,3 +411,39 @@ impl<F: Field> RegionLayouter<F> for RegionShape {
         Ok(())
     }
 }
+
+/// Hands out consecutive absolute rows of instance columns.
+///
+/// This lets gadgets expose values as public inputs in the order in which they are
+/// synthesized, without being told the absolute rows by their callers. Each layouter
+/// root owns one allocator, which is used by [`Layouter::next_instance_slot`]; rows are
+/// handed out from row 0 of each column, independently of rows used directly with
+/// [`Layouter::constrain_instance`].
+///
+/// [`Layouter::next_instance_slot`]: super::Layouter::next_instance_slot
+/// [`Layouter::constrain_instance`]: super::Layouter::constrain_instance
+#[derive(Clone, Debug, Default)]
+pub struct InstanceAllocator {
+    /// The number of rows handed out for each instance column.
+    allocated: HashMap<Column<Instance>, usize>,
+}
+
+impl InstanceAllocator {
+    /// Creates an allocator with no rows handed out.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the next unused row of `column`, and marks it as used.
+    pub fn next_slot(&mut self, column: Column<Instance>) -> usize {
+        let next = self.allocated.entry(column).or_default();
+        let row = *next;
+        *next += 1;
+        row
+    }
+
+    /// Returns the number of rows of `column` handed out so far.
+    pub fn allocated(&self, column: Column<Instance>) -> usize {
+        self.allocated.get(&column).copied().unwrap_or_default()
+    }
+}
@@ -8,7 +8,7 @@ use std::{
 use halo2_middleware::ff::Field;
 
 use crate::plonk::Assigned;
-use crate::plonk::{Assignment, Error, TableColumn, TableError};
+use crate::plonk::{Assignment, AssignmentContext, Error, Selector, TableColumn, TableError};
 
 use super::Value;
 
@@ -28,6 +28,21 @@ pub trait TableLayouter<F: Field>: std::fmt::Debug {
         offset: usize,
         to: &'v mut (dyn FnMut() -> Value<Assigned<F>> + 'v),
     ) -> Result<(), Error>;
+
+    /// Sets the value to fill a table column with after its last assigned row, instead of
+    /// the value assigned at row 0.
+    ///
+    /// Returns an error if the default value of the table column has already been set.
+    fn set_default(&mut self, column: TableColumn, value: Value<Assigned<F>>) -> Result<(), Error>;
+
+    /// Enables a selector on a table row.
+    ///
+    /// Selectors are planned with the regions that enable them, so the default
+    /// implementation returns a `TableError::SelectorInTable` error before anything is
+    /// assigned.
+    fn enable_selector(&mut self, selector: Selector, _offset: usize) -> Result<(), Error> {
+        Err(Error::TableError(TableError::SelectorInTable(selector)))
+    }
 }
 
 /// The default value to fill a table column with.
@@ -43,8 +58,15 @@ type DefaultTableValue<F> = Option<Value<Assigned<F>>>;
 pub struct SimpleTableLayouter<'r, 'a, F: Field, CS: Assignment<F> + 'a> {
     cs: &'a mut CS,
     used_columns: &'r [TableColumn],
+    /// The name of the table and the namespaces it is assigned within, used to give context
+    /// to assignment errors.
+    name: String,
+    namespace: Vec<String>,
     /// maps from a fixed column to a pair (default value, vector saying which rows are assigned)
     pub default_and_assigned: HashMap<TableColumn, (DefaultTableValue<F>, Vec<bool>)>,
+    /// The default values set with [`TableLayouter::set_default`], which take precedence
+    /// over the values assigned at row 0.
+    pub explicit_defaults: HashMap<TableColumn, Value<Assigned<F>>>,
 }
 
 impl<'r, 'a, F: Field, CS: Assignment<F> + 'a> fmt::Debug for SimpleTableLayouter<'r, 'a, F, CS> {
@@ -52,6 +74,7 @@ impl<'r, 'a, F: Field, CS: Assignment<F> + 'a> fmt::Debug for SimpleTableLayoute
         f.debug_struct("SimpleTableLayouter")
             .field("used_columns", &self.used_columns)
             .field("default_and_assigned", &self.default_and_assigned)
+            .field("explicit_defaults", &self.explicit_defaults)
             .finish()
     }
 }
@@ -62,8 +85,32 @@ impl<'r, 'a, F: Field, CS: Assignment<F> + 'a> SimpleTableLayouter<'r, 'a, F, CS
         SimpleTableLayouter {
             cs,
             used_columns,
+            name: String::new(),
+            namespace: vec![],
             default_and_assigned: HashMap::default(),
+            explicit_defaults: HashMap::default(),
+        }
+    }
+
+    /// Sets the name of the table and the namespaces it is assigned within, which are
+    /// attached to the errors of its assignments.
+    pub fn with_context(mut self, name: String, namespace: Vec<String>) -> Self {
+        self.name = name;
+        self.namespace = namespace;
+        self
+    }
+
+    /// Returns the default value and the assigned rows of each table column, where the
+    /// default values set with [`TableLayouter::set_default`] replace those assigned at
+    /// row 0.
+    pub fn into_default_and_assigned(
+        self,
+    ) -> HashMap<TableColumn, (DefaultTableValue<F>, Vec<bool>)> {
+        let mut default_and_assigned = self.default_and_assigned;
+        for (column, value) in self.explicit_defaults {
+            default_and_assigned.entry(column).or_default().0 = Some(value);
         }
+        default_and_assigned
     }
 }
 
@@ -84,16 +131,27 @@ impl<'r, 'a, F: Field, CS: Assignment<F> + 'a> TableLayouter<F>
         let entry = self.default_and_assigned.entry(column).or_default();
 
         let mut value = Value::unknown();
-        self.cs.assign_fixed(
-            annotation,
-            column.inner(),
-            offset, // tables are always assigned starting at row 0
-            || {
-                let res = to();
-                value = res;
-                res
-            },
-        )?;
+        self.cs
+            .assign_fixed(
+                annotation,
+                column.inner(),
+                offset, // tables are always assigned starting at row 0
+                || {
+                    let res = to();
+                    value = res;
+                    res
+                },
+            )
+            .map_err(|error| {
+                error.in_region(AssignmentContext {
+                    operation: "assign_cell",
+                    region: self.name.clone(),
+                    namespace: self.namespace.clone(),
+                    column: Some(column.inner().into()),
+                    column_annotation: None,
+                    offset: Some(offset),
+                })
+            })?;
 
         match (entry.0.is_none(), offset) {
             // Use the value at offset 0 as the default value for this table column.
@@ -116,6 +174,23 @@ impl<'r, 'a, F: Field, CS: Assignment<F> + 'a> TableLayouter<F>
 
         Ok(())
     }
+
+    fn set_default(&mut self, column: TableColumn, value: Value<Assigned<F>>) -> Result<(), Error> {
+        // The columns of previous tables have already been filled with their default.
+        if self.used_columns.contains(&column) {
+            return Err(Error::TableError(TableError::UsedColumn(column)));
+        }
+
+        if let Some(default) = self.explicit_defaults.insert(column, value) {
+            return Err(Error::TableError(TableError::OverwriteDefault(
+                column,
+                format!("{default:?}"),
+                format!("{value:?}"),
+            )));
+        }
+
+        Ok(())
+    }
 }
 
 pub(crate) fn compute_table_lengths<F: Debug>(
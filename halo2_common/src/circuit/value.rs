@@ -78,6 +78,22 @@ impl<V> Value<V> {
         }
     }
 
+    /// Enforces an assertion on the contained value, if known, panicking with `msg` if it
+    /// does not hold.
+    ///
+    /// The assertion is ignored if `self` is [`Value::unknown()`]. Do not try to enforce
+    /// circuit constraints with this method!
+    ///
+    /// # Panics
+    ///
+    /// Panics with `msg` if `f` returns `false`.
+    #[track_caller]
+    pub fn assert_if_known_msg<F: FnOnce(&V) -> bool>(&self, f: F, msg: &str) {
+        if let Some(value) = self.inner.as_ref() {
+            assert!(f(value), "{}", msg);
+        }
+    }
+
     /// Checks the contained value for an error condition, if known.
     ///
     /// The error check is ignored if `self` is [`Value::unknown()`]. Do not try to
@@ -217,6 +233,55 @@ where
     }
 }
 
+impl<V> Value<Vec<V>> {
+    /// Transposes a `&Value<Vec<V>>` into a `Vec<Value<&V>>`, without consuming `self`.
+    ///
+    /// [`Value::unknown()`] will be mapped to `vec![Value::unknown(); length]`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `self` is `Value::known(values)` and `values.len() != length`.
+    pub fn transpose_vec_ref(&self, length: usize) -> Vec<Value<&V>> {
+        match self.inner.as_ref() {
+            Some(values) => {
+                assert_eq!(values.len(), length);
+                values.iter().map(Value::known).collect()
+            }
+            None => (0..length).map(|_| Value::unknown()).collect(),
+        }
+    }
+
+    /// Collects a slice of values into a `Value<Vec<V>>`.
+    ///
+    /// Returns `Value::known` of all the contained values if every value in `values` is
+    /// known, and [`Value::unknown()`] otherwise.
+    pub fn zip_all(values: &[Value<V>]) -> Self
+    where
+        V: Clone,
+    {
+        values.iter().map(|value| value.as_ref().cloned()).collect()
+    }
+
+    /// Applies `f` to every contained value of `values`, collecting the results.
+    ///
+    /// Returns [`Value::unknown()`] without calling `f` if any value in `values` is
+    /// unknown.
+    pub fn map_slice<U, F: FnMut(&U) -> V>(values: &[Value<U>], f: F) -> Self {
+        if values.iter().any(|value| value.inner.is_none()) {
+            return Value::unknown();
+        }
+        Value {
+            inner: Some(
+                values
+                    .iter()
+                    .filter_map(|value| value.inner.as_ref())
+                    .map(f)
+                    .collect(),
+            ),
+        }
+    }
+}
+
 //
 // FromIterator
 //
@@ -697,4 +762,120 @@ impl<F: Field> Value<Assigned<F>> {
             inner: self.inner.map(|v| v.evaluate()),
         }
     }
+
+    /// Evaluates a slice of values with a single batched inversion.
+    ///
+    /// Returns an unknown value if any of the values are unknown. Values with a zero
+    /// denominator evaluate to zero.
+    pub fn evaluate_batch(values: &[Self]) -> Value<Vec<F>> {
+        let assigned: Value<Vec<_>> = values.iter().copied().collect();
+        assigned.map(|assigned| Assigned::batch_evaluate(&assigned))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Value;
+
+    fn is_known<V>(value: &Value<V>) -> bool {
+        value.inner.is_some()
+    }
+
+    #[test]
+    fn zip_all() {
+        let all_known = [Value::known(1), Value::known(2), Value::known(3)];
+        assert_eq!(Value::zip_all(&all_known).inner, Some(vec![1, 2, 3]));
+
+        for unknown_index in 0..3 {
+            let mut values = all_known;
+            values[unknown_index] = Value::unknown();
+            assert!(!is_known(&Value::zip_all(&values)));
+        }
+
+        let empty: [Value<u64>; 0] = [];
+        assert_eq!(Value::zip_all(&empty).inner, Some(vec![]));
+    }
+
+    #[test]
+    fn map_slice() {
+        let all_known = [Value::known(1u64), Value::known(2), Value::known(3)];
+        assert_eq!(
+            Value::map_slice(&all_known, |v| v * 10).inner,
+            Some(vec![10, 20, 30])
+        );
+
+        // `f` must not be called at all if any value is unknown.
+        let mixed = [Value::known(1u64), Value::unknown(), Value::known(3)];
+        let mut calls = 0;
+        let result: Value<Vec<u64>> = Value::map_slice(&mixed, |v| {
+            calls += 1;
+            *v
+        });
+        assert!(!is_known(&result));
+        assert_eq!(calls, 0);
+
+        let all_unknown = [Value::<u64>::unknown(); 2];
+        assert!(!is_known(&Value::<Vec<u64>>::map_slice(
+            &all_unknown,
+            |v| *v
+        )));
+    }
+
+    #[test]
+    fn transpose_vec_ref() {
+        let known = Value::known(vec![1, 2, 3]);
+        let transposed = known.transpose_vec_ref(3);
+        assert_eq!(
+            transposed
+                .iter()
+                .map(|v| v.inner.copied())
+                .collect::<Vec<_>>(),
+            vec![Some(1), Some(2), Some(3)]
+        );
+        // `self` is not consumed.
+        assert_eq!(known.inner, Some(vec![1, 2, 3]));
+
+        let unknown = Value::<Vec<u64>>::unknown();
+        let transposed = unknown.transpose_vec_ref(2);
+        assert_eq!(transposed.len(), 2);
+        assert!(transposed.iter().all(|v| !is_known(v)));
+    }
+
+    #[test]
+    #[should_panic]
+    fn transpose_vec_ref_wrong_length() {
+        Value::known(vec![1, 2, 3]).transpose_vec_ref(2);
+    }
+
+    #[test]
+    fn round_trip() {
+        // Collecting the transposed values gives back the original value.
+        let known = Value::known(vec![4, 5, 6]);
+        let round_trip: Value<Vec<u64>> = known
+            .transpose_vec_ref(3)
+            .into_iter()
+            .map(|v| v.copied())
+            .collect();
+        assert_eq!(round_trip.inner, known.inner);
+
+        let unknown = Value::<Vec<u64>>::unknown();
+        let round_trip: Value<Vec<u64>> = unknown
+            .transpose_vec_ref(3)
+            .into_iter()
+            .map(|v| v.copied())
+            .collect();
+        assert!(!is_known(&round_trip));
+    }
+
+    #[test]
+    fn assert_if_known_msg() {
+        Value::known(1).assert_if_known_msg(|v| *v == 1, "value must be one");
+        Value::<u64>::unknown().assert_if_known_msg(|_| false, "ignored when unknown");
+    }
+
+    #[test]
+    #[should_panic(expected = "value must be one")]
+    fn assert_if_known_msg_panics() {
+        Value::known(2).assert_if_known_msg(|v| *v == 1, "value must be one");
+    }
 }
@@ -0,0 +1,302 @@
+//! Developer tools for checking that a circuit has not changed.
+
+use std::fmt;
+
+use halo2_middleware::ff::Field;
+
+use crate::plonk::{Circuit, ConstraintSystem};
+
+/// A gate polynomial, identified by its position in the `gates` list of a pinned
+/// constraint system.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PinnedPolynomial {
+    /// The index of the polynomial in the `gates` list, which contains the polynomials
+    /// of all the gates in the order in which they were created.
+    pub index: usize,
+    /// The name of the gate, the index of the polynomial within the gate, and the name
+    /// of the constraint, or `None` if the current circuit has fewer polynomials.
+    pub identifier: Option<(String, usize, String)>,
+}
+
+impl fmt::Display for PinnedPolynomial {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "polynomial {}", self.index)?;
+        match &self.identifier {
+            Some((gate, index, name)) if name.is_empty() => {
+                write!(f, " (constraint {index} in gate '{gate}')")
+            }
+            Some((gate, index, name)) => {
+                write!(f, " (constraint {index} ('{name}') in gate '{gate}')")
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+/// The first difference between a pinned constraint system and the one of the current
+/// circuit.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PinnedDiff {
+    /// The number (starting at 1) of the first line that differs.
+    pub line: usize,
+    /// The field of the pinned constraint system that contains the line, such as
+    /// `gates`, `advice_queries` or `permutation`.
+    pub field: Option<String>,
+    /// The gate polynomial that contains the line, if the line is in `gates`.
+    pub polynomial: Option<PinnedPolynomial>,
+    /// The line of the pinned constraint system, or `None` if it has fewer lines.
+    pub expected: Option<String>,
+    /// The line of the current constraint system, or `None` if it has fewer lines.
+    pub actual: Option<String>,
+}
+
+impl fmt::Display for PinnedDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "The constraint system differs from the pinned one at line {}",
+            self.line
+        )?;
+        if let Some(field) = &self.field {
+            write!(f, ", in `{field}`")?;
+        }
+        writeln!(f)?;
+        if let Some(polynomial) = &self.polynomial {
+            writeln!(f, "  in {polynomial}")?;
+        }
+        let line = |line: &Option<String>| line.clone().unwrap_or_else(|| "<end>".to_string());
+        writeln!(f, "  expected: {}", line(&self.expected))?;
+        writeln!(f, "  actual:   {}", line(&self.actual))
+    }
+}
+
+/// Returns the first difference between `expected_pinned` and the canonical
+/// representation of `cs.pinned()`, or `None` if they are equal.
+///
+/// See [`PinnedConstraintSystem::to_string_stable`].
+///
+/// [`PinnedConstraintSystem::to_string_stable`]: crate::plonk::PinnedConstraintSystem::to_string_stable
+pub fn pinned_diff<F: Field>(
+    cs: &ConstraintSystem<F>,
+    expected_pinned: &str,
+) -> Option<PinnedDiff> {
+    let actual = cs.pinned().to_string_stable();
+    let mut expected_lines = expected_pinned.trim().lines();
+    let mut actual_lines = actual.lines();
+
+    let mut field: Option<String> = None;
+    let mut polynomial: Option<usize> = None;
+    for line in 1.. {
+        let (expected, actual) = (expected_lines.next(), actual_lines.next());
+        if expected.is_none() && actual.is_none() {
+            return None;
+        }
+
+        // Both inputs are equal up to this line, so the position can be tracked on either.
+        if let Some(text) = actual.or(expected) {
+            let indent = text.len() - text.trim_start().len();
+            let text = text.trim_start();
+            if indent == 4 {
+                // A field of the pinned constraint system, or the end of a list.
+                if let Some((name, _)) = text.split_once(':') {
+                    field = Some(name.to_string());
+                    polynomial = None;
+                }
+            } else if indent == 8 && field.as_deref() == Some("gates") && !text.starts_with(']') {
+                // The start of a polynomial in the list of gates.
+                polynomial = Some(polynomial.map_or(0, |index| index + 1));
+            }
+        }
+
+        if expected != actual {
+            let polynomial = polynomial.map(|index| PinnedPolynomial {
+                index,
+                identifier: cs
+                    .gates
+                    .iter()
+                    .flat_map(|gate| {
+                        (0..gate.polynomials().len()).map(move |i| {
+                            (
+                                gate.name().to_string(),
+                                i,
+                                gate.constraint_name(i).to_string(),
+                            )
+                        })
+                    })
+                    .nth(index),
+            });
+            return Some(PinnedDiff {
+                line,
+                field,
+                polynomial,
+                expected: expected.map(str::to_string),
+                actual: actual.map(str::to_string),
+            });
+        }
+    }
+    unreachable!()
+}
+
+fn configure<F: Field, C: Circuit<F>>() -> ConstraintSystem<F> {
+    let mut cs = ConstraintSystem::default();
+    #[cfg(feature = "circuit-params")]
+    C::configure_with_params(&mut cs, C::Params::default());
+    #[cfg(not(feature = "circuit-params"))]
+    C::configure(&mut cs);
+    cs
+}
+
+/// Returns the canonical representation of the pinned constraint system of `C`, to be
+/// stored and later checked with [`assert_circuit_unchanged`].
+///
+/// The constraint system is the one returned by `Circuit::configure`, before selectors
+/// are compressed into fixed columns, so it does not depend on the witness or on the
+/// assignment of the selectors.
+pub fn pinned_circuit<F: Field, C: Circuit<F>>() -> String {
+    configure::<F, C>().pinned().to_string_stable()
+}
+
+/// Asserts that the pinned constraint system of `C` is equal to `expected_pinned`, as
+/// previously returned by [`pinned_circuit`].
+///
+/// On a mismatch, this panics with the first differing line, the field of the pinned
+/// constraint system that it belongs to, and for gates, the index and name of the first
+/// differing polynomial.
+///
+/// ```ignore
+/// assert_circuit_unchanged::<Fr, MyCircuit>(include_str!("my_circuit.pinned"));
+/// ```
+#[track_caller]
+pub fn assert_circuit_unchanged<F: Field, C: Circuit<F>>(expected_pinned: &str) {
+    if let Some(diff) = pinned_diff(&configure::<F, C>(), expected_pinned) {
+        panic!("{diff}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2curves::bn256::Fr;
+
+    use super::*;
+    use crate::circuit::layouter::SyncDeps;
+    use crate::circuit::Layouter;
+    use crate::plonk::{circuit::Column, Assignment, Error, Expression, FloorPlanner};
+    use halo2_middleware::circuit::Fixed;
+    use halo2_middleware::poly::Rotation;
+
+    /// A floor planner for circuits that are only configured.
+    struct ConfigureOnly;
+
+    impl FloorPlanner for ConfigureOnly {
+        fn synthesize<F: Field, CS: Assignment<F> + SyncDeps, C: Circuit<F>>(
+            _: &mut CS,
+            _: &C,
+            _: C::Config,
+            _: Vec<Column<Fixed>>,
+        ) -> Result<(), Error> {
+            unreachable!("the circuit is only configured")
+        }
+    }
+
+    macro_rules! arithmetic_circuit {
+        ($name:ident, $add:path, $equality:expr) => {
+            struct $name;
+
+            impl Circuit<Fr> for $name {
+                type Config = ();
+                type FloorPlanner = ConfigureOnly;
+                #[cfg(feature = "circuit-params")]
+                type Params = ();
+
+                fn without_witnesses(&self) -> Self {
+                    Self
+                }
+
+                fn configure(meta: &mut ConstraintSystem<Fr>) {
+                    let a = meta.advice_column();
+                    let b = meta.advice_column();
+                    let c = meta.advice_column();
+                    let s = meta.selector();
+
+                    meta.create_gate("mul", |meta| {
+                        let a = meta.query_advice(a, Rotation::cur());
+                        let b = meta.query_advice(b, Rotation::cur());
+                        let c = meta.query_advice(c, Rotation::cur());
+                        let s = meta.query_selector(s);
+                        vec![("a * b = c", s * (a * b - c))]
+                    });
+                    meta.create_gate("add", |meta| {
+                        let a = meta.query_advice(a, Rotation::cur());
+                        let b = meta.query_advice(b, Rotation::cur());
+                        let c = meta.query_advice(c, Rotation::next());
+                        vec![$add(a, b, c)]
+                    });
+                    if $equality {
+                        meta.enable_equality(c);
+                    }
+                }
+
+                fn synthesize(&self, _: (), _: impl Layouter<Fr>) -> Result<(), Error> {
+                    unreachable!()
+                }
+            }
+        };
+    }
+
+    fn add(a: Expression<Fr>, b: Expression<Fr>, c: Expression<Fr>) -> Expression<Fr> {
+        a + b - c
+    }
+
+    fn sub(a: Expression<Fr>, b: Expression<Fr>, c: Expression<Fr>) -> Expression<Fr> {
+        a - b - c
+    }
+
+    arithmetic_circuit!(AddCircuit, add, false);
+    // The addition gate is tweaked into a subtraction.
+    arithmetic_circuit!(SubCircuit, sub, false);
+    arithmetic_circuit!(EqualityCircuit, add, true);
+
+    #[test]
+    fn unchanged_circuit() {
+        let pinned = pinned_circuit::<Fr, AddCircuit>();
+        assert!(pinned.starts_with("PinnedConstraintSystem {"));
+        assert_circuit_unchanged::<Fr, AddCircuit>(&pinned);
+        // Surrounding whitespace is ignored.
+        assert_circuit_unchanged::<Fr, AddCircuit>(&format!("\n{pinned}\n"));
+    }
+
+    #[test]
+    fn tweaked_gate() {
+        let pinned = pinned_circuit::<Fr, AddCircuit>();
+        let diff = pinned_diff(&configure::<Fr, SubCircuit>(), &pinned).unwrap();
+
+        assert_eq!(diff.field.as_deref(), Some("gates"));
+        assert_eq!(
+            diff.polynomial,
+            Some(PinnedPolynomial {
+                index: 1,
+                identifier: Some(("add".to_string(), 0, "".to_string())),
+            })
+        );
+        assert_eq!(diff.expected.as_deref().map(str::trim), Some("Advice {"));
+        assert_eq!(diff.actual.as_deref().map(str::trim), Some("Negated("));
+        assert!(diff
+            .to_string()
+            .contains("in `gates`\n  in polynomial 1 (constraint 0 in gate 'add')\n"));
+    }
+
+    #[test]
+    fn changed_permutation() {
+        let pinned = pinned_circuit::<Fr, AddCircuit>();
+        let diff = pinned_diff(&configure::<Fr, EqualityCircuit>(), &pinned).unwrap();
+
+        assert_eq!(diff.field.as_deref(), Some("permutation"));
+        assert_eq!(diff.polynomial, None);
+    }
+
+    #[test]
+    #[should_panic(expected = "in `gates`")]
+    fn assert_tweaked_gate() {
+        assert_circuit_unchanged::<Fr, SubCircuit>(&pinned_circuit::<Fr, AddCircuit>());
+    }
+}
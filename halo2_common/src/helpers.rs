@@ -1,3 +1,4 @@
+use crate::multicore::{ParallelIterator, ParallelSlice};
 use halo2_middleware::ff::PrimeField;
 use halo2curves::{serde::SerdeObject, CurveAffine};
 use std::io;
@@ -62,6 +63,24 @@ pub trait SerdeCurveAffine: CurveAffine + SerdeObject {
             _ => Self::Repr::default().as_ref().len() * 2,
         }
     }
+
+    /// Parses an element from `bytes`, of [`Self::byte_length`] bytes, with the same checks
+    /// as [`Self::read`].
+    fn from_slice(bytes: &[u8], format: SerdeFormat) -> io::Result<Self> {
+        match format {
+            SerdeFormat::Processed => {
+                let mut compressed = Self::Repr::default();
+                compressed.as_mut().copy_from_slice(bytes);
+                Option::from(Self::from_bytes(&compressed))
+                    .ok_or_else(|| io::Error::new(io::ErrorKind::Other, "Invalid point encoding"))
+            }
+            SerdeFormat::RawBytes => <Self as SerdeObject>::from_raw_bytes(bytes)
+                .ok_or_else(|| io::Error::new(io::ErrorKind::Other, "Invalid point encoding")),
+            SerdeFormat::RawBytesUnchecked => {
+                Ok(<Self as SerdeObject>::from_raw_bytes_unchecked(bytes))
+            }
+        }
+    }
 }
 impl<C: CurveAffine + SerdeObject> SerdeCurveAffine for C {}
 
@@ -97,9 +116,88 @@ pub trait SerdePrimeField: PrimeField + SerdeObject {
             _ => self.write_raw(writer),
         }
     }
+
+    /// Byte length of a field element according to `format`.
+    fn byte_length(format: SerdeFormat) -> usize {
+        match format {
+            SerdeFormat::Processed => Self::Repr::default().as_ref().len(),
+            _ => Self::default().to_raw_bytes().len(),
+        }
+    }
+
+    /// Parses a field element from `bytes`, of [`SerdePrimeField::byte_length`] bytes, with
+    /// the same checks as [`SerdePrimeField::read`].
+    fn from_slice(bytes: &[u8], format: SerdeFormat) -> io::Result<Self> {
+        match format {
+            SerdeFormat::Processed => {
+                let mut compressed = Self::Repr::default();
+                compressed.as_mut().copy_from_slice(bytes);
+                Option::from(Self::from_repr(compressed)).ok_or_else(|| {
+                    io::Error::new(io::ErrorKind::Other, "Invalid prime field point encoding")
+                })
+            }
+            SerdeFormat::RawBytes => {
+                <Self as SerdeObject>::from_raw_bytes(bytes).ok_or_else(|| {
+                    io::Error::new(io::ErrorKind::Other, "Invalid prime field point encoding")
+                })
+            }
+            SerdeFormat::RawBytesUnchecked => {
+                Ok(<Self as SerdeObject>::from_raw_bytes_unchecked(bytes))
+            }
+        }
+    }
 }
 impl<F: PrimeField + SerdeObject> SerdePrimeField for F {}
 
+/// Splits off the first `len` bytes of `bytes`, and advances `bytes` past them.
+///
+/// Returns an error of kind [`io::ErrorKind::UnexpectedEof`] if `bytes` is shorter.
+pub fn split_slice<'a>(bytes: &mut &'a [u8], len: usize) -> io::Result<&'a [u8]> {
+    if bytes.len() < len {
+        return Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "unexpected end of the bytes",
+        ));
+    }
+    let (head, tail) = bytes.split_at(len);
+    *bytes = tail;
+    Ok(head)
+}
+
+/// Parses `n` curve elements from the front of `bytes` according to `format`, in parallel,
+/// and advances `bytes` past them.
+///
+/// Each element is parsed from `bytes` straight into the returned vector, without the
+/// intermediate buffers of reading them through [`io::Read`], so that `bytes` can be a
+/// memory-mapped file. The elements are still copied: their in-memory representation
+/// differs from [`SerdeFormat::Processed`], and a slice of bytes may not be aligned for them
+/// even in the raw formats.
+pub fn points_from_slice<C: SerdeCurveAffine>(
+    bytes: &mut &[u8],
+    n: usize,
+    format: SerdeFormat,
+) -> io::Result<Vec<C>> {
+    let len = C::byte_length(format);
+    split_slice(bytes, n * len)?
+        .par_chunks_exact(len)
+        .map(|bytes| C::from_slice(bytes, format))
+        .collect()
+}
+
+/// Parses `n` field elements from the front of `bytes` according to `format`, in parallel,
+/// and advances `bytes` past them. See [`points_from_slice`].
+pub fn scalars_from_slice<F: SerdePrimeField>(
+    bytes: &mut &[u8],
+    n: usize,
+    format: SerdeFormat,
+) -> io::Result<Vec<F>> {
+    let len = F::byte_length(format);
+    split_slice(bytes, n * len)?
+        .par_chunks_exact(len)
+        .map(|bytes| F::from_slice(bytes, format))
+        .collect()
+}
+
 /// Convert a slice of `bool` into a `u8`.
 ///
 /// Panics if the slice has length greater than 8.
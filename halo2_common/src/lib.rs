@@ -8,6 +8,7 @@
 
 pub mod arithmetic;
 pub mod circuit;
+pub mod dev;
 pub use halo2curves;
 pub mod multicore;
 pub mod plonk;
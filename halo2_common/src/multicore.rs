@@ -3,8 +3,8 @@ pub use rayon::{
     iter::{IndexedParallelIterator, IntoParallelRefIterator},
     iter::{IntoParallelIterator, IntoParallelRefMutIterator, ParallelIterator},
     join, scope,
-    slice::ParallelSliceMut,
-    Scope,
+    slice::{ParallelSlice, ParallelSliceMut},
+    Scope, ThreadPoolBuilder,
 };
 
 pub trait TryFoldAndReduce<T, E> {
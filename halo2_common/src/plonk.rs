@@ -8,12 +8,13 @@
 use crate::plonk::circuit::Column;
 use crate::transcript::ChallengeScalar;
 use halo2_middleware::circuit::{Advice, Fixed, Instance};
-use halo2_middleware::ff::Field;
+use halo2_middleware::ff::{BatchInvert, Field};
 use halo2_middleware::poly::Rotation;
 use std::ops::{Add, AddAssign, Mul, MulAssign, Neg, Sub, SubAssign};
 
 pub mod circuit;
 pub mod error;
+pub mod instrument;
 pub mod keygen;
 pub mod lookup;
 pub mod permutation;
@@ -21,6 +22,7 @@ pub mod shuffle;
 
 pub use circuit::*;
 pub use error::*;
+pub use instrument::{AssignmentHooks, InstrumentedAssignment};
 pub use keygen::*;
 
 /// A value assigned to a cell within a circuit.
@@ -299,6 +301,8 @@ impl<F: Field> MulAssign<&Assigned<F>> for Assigned<F> {
 
 impl<F: Field> Assigned<F> {
     /// Returns the numerator.
+    ///
+    /// This is the value itself for trivial values, and zero for [`Assigned::Zero`].
     pub fn numerator(&self) -> F {
         match self {
             Self::Zero => F::ZERO,
@@ -308,6 +312,9 @@ impl<F: Field> Assigned<F> {
     }
 
     /// Returns the denominator, if non-trivial.
+    ///
+    /// Trivial values (including [`Assigned::Zero`]) have an implicit denominator of one, and
+    /// return `None`.
     pub fn denominator(&self) -> Option<F> {
         match self {
             Self::Zero => None,
@@ -384,9 +391,40 @@ impl<F: Field> Assigned<F> {
             }
         }
     }
-}
 
-/// List of queries (columns and rotations) used by a circuit
+    /// Evaluates a slice of assigned values, inverting all of their non-trivial denominators
+    /// with a single batched inversion.
+    ///
+    /// The result is the same as calling [`Assigned::evaluate`] on each value: values with a
+    /// zero denominator evaluate to zero.
+    pub fn batch_evaluate(values: &[Self]) -> Vec<F> {
+        let mut inv_denominators: Vec<_> = values.iter().map(Self::denominator).collect();
+        // Trivial denominators are skipped, reducing the size of the batch inversion.
+        // `batch_invert` leaves zero denominators as zero.
+        inv_denominators.iter_mut().flatten().batch_invert();
+
+        values
+            .iter()
+            .zip(inv_denominators)
+            .map(|(value, inv_denominator)| match inv_denominator {
+                Some(inv_denominator) => value.numerator() * inv_denominator,
+                None => value.numerator(),
+            })
+            .collect()
+    }
+}
+
+/// List of queries (columns and rotations) used by a circuit.
+///
+/// Each `(column, rotation)` pair appears at most once in its list, and the position of a
+/// query in its list is the index used to refer to it, both by the queries in expressions
+/// and in the order of the evaluations in a proof. [`collect_queries`] lists queries in the order
+/// they first appear in the gates, then the lookups, then the shuffles.
+///
+/// Every column involved in the permutation argument is also queried at
+/// [`Rotation::cur`], as the argument opens the column at `x` to check the copy
+/// constraints. That query is shared with the gates that query the column at the current
+/// row, and is added after the others otherwise.
 #[derive(Debug, Clone)]
 pub struct Queries {
     /// List of unique advice queries
@@ -402,6 +440,11 @@ pub struct Queries {
 }
 
 impl Queries {
+    /// Returns the number of advice, instance and fixed queries, in that order.
+    pub fn len_by_kind(&self) -> (usize, usize, usize) {
+        (self.advice.len(), self.instance.len(), self.fixed.len())
+    }
+
     /// Returns the minimum necessary rows that need to exist in order to
     /// account for e.g. blinding factors.
     pub fn minimum_rows(&self) -> usize {
@@ -553,6 +596,7 @@ mod proptests {
     use proptest::{collection::vec, prelude::*, sample::select};
 
     use super::Assigned;
+    use crate::circuit::Value;
 
     trait UnaryOperand: Neg<Output = Self> {
         fn double(&self) -> Self;
@@ -760,5 +804,26 @@ mod proptests {
             // list of operations.
             assert_eq!(deferred_result.evaluate(), evaluated_result);
         }
+
+        #[test]
+        fn batch_evaluation(
+            values in vec(
+                prop_oneof![
+                    1 => Just(Assigned::Zero),
+                    2 => arb_trivial(),
+                    2 => arb_rational(),
+                ],
+                0..50,
+            )
+        ) {
+            // Batch evaluation should agree with evaluating each value on its own, including
+            // for values with zero denominators.
+            let evaluated: Vec<_> = values.iter().map(|v| v.evaluate()).collect();
+            assert_eq!(Assigned::batch_evaluate(&values), evaluated);
+
+            let known: Vec<_> = values.iter().map(|v| Value::known(*v)).collect();
+            Value::<Assigned<_>>::evaluate_batch(&known)
+                .assert_if_known(|batch| batch == &evaluated);
+        }
     }
 }
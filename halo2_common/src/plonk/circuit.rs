@@ -1,5 +1,5 @@
 use super::{lookup, permutation, shuffle, Error, Queries};
-use crate::circuit::layouter::SyncDeps;
+use crate::circuit::layouter::{RegionShape, SyncDeps};
 use crate::circuit::{Layouter, Region, Value};
 use crate::plonk::Assigned;
 use core::cmp::max;
@@ -15,12 +15,15 @@ use sealed::SealedPhase;
 use std::collections::HashMap;
 use std::fmt::Debug;
 use std::iter::{Product, Sum};
+use std::sync::Arc;
 use std::{
     convert::TryFrom,
-    ops::{Neg, Sub},
+    ops::{Neg, Range, Sub},
 };
 
 mod compress_selectors;
+#[cfg(feature = "serde")]
+pub mod interchange;
 
 /// A column with an index and type
 #[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
@@ -99,6 +102,32 @@ impl<C: ColumnType> Column<C> {
     }
 }
 
+impl Column<Advice> {
+    /// Creates an advice column in the given phase, checking that the phase is supported
+    /// (see [`PhaseId::MAX`]).
+    pub fn try_new_in_phase(index: usize, phase: u8) -> Result<Self, &'static str> {
+        let phase = PhaseId::try_from(phase)?;
+        Ok(Column::new(index, Advice::new(phase.index())))
+    }
+
+    /// Phase of this column, starting from 0 for the first phase.
+    pub fn phase(&self) -> u8 {
+        self.column_type.phase()
+    }
+
+    /// Returns whether this column is in the given phase.
+    pub fn in_phase<P: Phase>(&self, phase: P) -> bool {
+        self.phase() == phase.to_sealed().0
+    }
+}
+
+impl<C: ColumnType> std::fmt::Display for Column<C> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let column: metadata::Column = (self.column_type.into(), self.index).into();
+        write!(f, "{column}")
+    }
+}
+
 impl<C: ColumnType> Ord for Column<C> {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
         // This ordering is consensus-critical! The layouters rely on deterministic column
@@ -263,6 +292,76 @@ impl SealedPhase for super::ThirdPhase {
     }
 }
 
+/// Phase chosen at runtime, for code that is generic over the phase it allocates
+/// advice columns and challenges in. It is accepted wherever a [`Phase`] is.
+///
+/// Phases range from [`PhaseId::FIRST`] to [`PhaseId::MAX`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct PhaseId(u8);
+
+impl PhaseId {
+    /// First phase, same as [`FirstPhase`].
+    pub const FIRST: PhaseId = PhaseId(0);
+    /// Second phase, same as [`SecondPhase`].
+    pub const SECOND: PhaseId = PhaseId(1);
+    /// Third phase, same as [`ThirdPhase`].
+    pub const THIRD: PhaseId = PhaseId(2);
+    /// Last supported phase.
+    pub const MAX: PhaseId = PhaseId::THIRD;
+
+    /// Index of this phase, starting from 0 for the first phase.
+    pub fn index(&self) -> u8 {
+        self.0
+    }
+
+    /// Returns all the supported phases, in order.
+    pub fn all() -> impl Iterator<Item = PhaseId> {
+        (0..=PhaseId::MAX.0).map(PhaseId)
+    }
+}
+
+impl TryFrom<u8> for PhaseId {
+    type Error = &'static str;
+
+    fn try_from(index: u8) -> Result<Self, Self::Error> {
+        if index <= PhaseId::MAX.0 {
+            Ok(PhaseId(index))
+        } else {
+            Err("Phase index is greater than PhaseId::MAX")
+        }
+    }
+}
+
+impl SealedPhase for PhaseId {
+    fn to_sealed(self) -> sealed::Phase {
+        sealed::Phase(self.0)
+    }
+}
+
+impl From<PhaseId> for sealed::Phase {
+    fn from(phase: PhaseId) -> Self {
+        phase.to_sealed()
+    }
+}
+
+impl From<FirstPhase> for PhaseId {
+    fn from(_: FirstPhase) -> Self {
+        PhaseId::FIRST
+    }
+}
+
+impl From<SecondPhase> for PhaseId {
+    fn from(_: SecondPhase) -> Self {
+        PhaseId::SECOND
+    }
+}
+
+impl From<ThirdPhase> for PhaseId {
+    fn from(_: ThirdPhase) -> Self {
+        PhaseId::THIRD
+    }
+}
+
 /// A selector, representing a fixed boolean value per row of the circuit.
 ///
 /// Selectors can be used to conditionally enable (portions of) gates:
@@ -323,6 +422,18 @@ impl Selector {
         region.enable_selector(|| "", self, offset)
     }
 
+    /// Enable this selector at every offset in `offsets` within the given region.
+    ///
+    /// This is equivalent to calling [`Selector::enable`] for each offset, but lets the
+    /// layouter and the backend enable the whole range at once.
+    pub fn enable_range<F: Field>(
+        &self,
+        region: &mut Region<F>,
+        offsets: Range<usize>,
+    ) -> Result<(), Error> {
+        region.enable_selector_range(|| "", self, offsets)
+    }
+
     /// Is this selector "simple"? Simple selectors can only be multiplied
     /// by expressions that contain no other simple selectors.
     pub fn is_simple(&self) -> bool {
@@ -334,6 +445,15 @@ impl Selector {
         self.0
     }
 
+    /// Returns the selector with the given index that is simple if `is_simple` is set.
+    ///
+    /// This rebuilds a selector from a serialized form, e.g. when a witness is read back
+    /// with `MockProver::read_witness`. The selector must have been allocated with the
+    /// same index and simplicity by the [`ConstraintSystem`] it is used with.
+    pub fn from_parts(index: usize, is_simple: bool) -> Self {
+        Selector(index, is_simple)
+    }
+
     /// Return expression from selector
     pub fn expr<F: Field>(&self) -> Expression<F> {
         Expression::Selector(*self)
@@ -530,6 +650,26 @@ pub trait Assignment<F: Field> {
         A: FnOnce() -> AR,
         AR: Into<String>;
 
+    /// Enables a selector at every row in `rows`.
+    ///
+    /// The default implementation calls [`Assignment::enable_selector`] for each row;
+    /// implementations that store selectors as vectors can set the whole range at once.
+    fn enable_selector_range<A, AR>(
+        &mut self,
+        annotation: A,
+        selector: &Selector,
+        rows: Range<usize>,
+    ) -> Result<(), Error>
+    where
+        A: Fn() -> AR,
+        AR: Into<String>,
+    {
+        for row in rows {
+            self.enable_selector(&annotation, selector, row)?;
+        }
+        Ok(())
+    }
+
     /// Queries the cell of an instance column at a particular absolute row.
     ///
     /// Returns the cell's value, if known.
@@ -549,6 +689,44 @@ pub trait Assignment<F: Field> {
         A: FnOnce() -> AR,
         AR: Into<String>;
 
+    /// Assign an advice column value (witness), where computing the value may fail.
+    ///
+    /// The default implementation evaluates `to` and forwards the value to
+    /// [`Assignment::assign_advice`], returning the error of `to` if there is one.
+    fn try_assign_advice<V, VR, A, AR>(
+        &mut self,
+        annotation: A,
+        column: Column<Advice>,
+        row: usize,
+        to: V,
+    ) -> Result<(), Error>
+    where
+        V: FnOnce() -> Result<Value<VR>, Error>,
+        VR: Into<Assigned<F>>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        let value = to()?;
+        self.assign_advice(annotation, column, row, || value)
+    }
+
+    /// Assigns `values` to consecutive rows of an advice column, starting at `start_row`.
+    ///
+    /// The default implementation calls [`Assignment::assign_advice`] for each value;
+    /// implementations that store advice columns as vectors can write the whole slice at
+    /// once.
+    fn assign_advice_column(
+        &mut self,
+        column: Column<Advice>,
+        start_row: usize,
+        values: &[Value<Assigned<F>>],
+    ) -> Result<(), Error> {
+        for (row, value) in (start_row..).zip(values.iter()) {
+            self.assign_advice(|| "", column, row, || *value)?;
+        }
+        Ok(())
+    }
+
     /// Assign a fixed value
     fn assign_fixed<V, VR, A, AR>(
         &mut self,
@@ -580,11 +758,61 @@ pub trait Assignment<F: Field> {
         to: Value<Assigned<F>>,
     ) -> Result<(), Error>;
 
+    /// Fills an advice `column` starting from the given `from_row` with value `to`, up to
+    /// the last usable row. The rows reserved for blinding factors are left untouched.
+    /// Filling from the end of the usable rows assigns nothing.
+    ///
+    /// Implementations that keep the values of the advice cells must not overwrite the
+    /// cells that are already assigned: the floor planner may place regions that were
+    /// synthesized before within the filled rows.
+    ///
+    /// The default implementation calls [`Assignment::assign_advice`] for each of the
+    /// [`Assignment::usable_rows`] from `from_row`, or only at `from_row` if the usable
+    /// rows are not known or do not include `from_row`, so it only suits implementations
+    /// that don't keep these values.
+    fn fill_advice_from_row(
+        &mut self,
+        column: Column<Advice>,
+        from_row: usize,
+        to: Value<Assigned<F>>,
+    ) -> Result<(), Error> {
+        let rows = match self.usable_rows() {
+            Some(usable_rows) if from_row <= usable_rows.end => from_row..usable_rows.end,
+            // Let `assign_advice` report a row outside of the usable rows.
+            _ => from_row..from_row + 1,
+        };
+        for row in rows {
+            self.assign_advice(|| "", column, row, || to)?;
+        }
+        Ok(())
+    }
+
     /// Queries the value of the given challenge.
     ///
     /// Returns `Value::unknown()` if the current synthesis phase is before the challenge can be queried.
     fn get_challenge(&self, challenge: Challenge) -> Value<F>;
 
+    /// Returns the phase that the circuit is currently being synthesized in.
+    ///
+    /// Defaults to the first phase. Implementations that synthesize the circuit once for
+    /// all phases should return `u8::MAX`.
+    ///
+    /// Not intended for downstream consumption; use [`Layouter::current_phase`] instead.
+    ///
+    /// [`Layouter::current_phase`]: crate::circuit::Layouter#method.current_phase
+    fn current_phase(&self) -> u8 {
+        0
+    }
+
+    /// Returns the rows that can be assigned, i.e. all the rows of the circuit except
+    /// those reserved for blinding factors.
+    ///
+    /// Defaults to `None`, for implementations that do not know the size of the circuit
+    /// (e.g. because they only measure its shape).
+    fn usable_rows(&self) -> Option<Range<usize>> {
+        None
+    }
+
     /// Creates a new (sub)namespace and enters into it.
     ///
     /// Not intended for downstream consumption; use [`Layouter::namespace`] instead.
@@ -666,6 +894,18 @@ pub trait Circuit<F: Field> {
     /// arrangement, column arrangement, etc.
     fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config;
 
+    /// Returns the shapes of the regions that [`Circuit::synthesize`] assigns, in the order
+    /// in which they are assigned, if they are known without synthesizing the circuit.
+    ///
+    /// Floor planners that support it (such as [`V1SinglePass`]) use these shapes to plan
+    /// the circuit instead of measuring it with an additional synthesis pass. The default
+    /// implementation returns `None`.
+    ///
+    /// [`V1SinglePass`]: crate::circuit::floor_planner::V1SinglePass
+    fn region_shapes(&self, _config: &Self::Config) -> Option<Vec<RegionShape>> {
+        None
+    }
+
     /// Given the provided `cs`, synthesize the circuit. The concrete type of
     /// the caller will be different depending on the context, and they may or
     /// may not expect to have a witness present.
@@ -785,7 +1025,7 @@ impl<F: Field> Expression<F> {
                     query.index = Some(cells.meta.query_instance_index(col, query.rotation));
                 }
             }
-            Expression::Challenge(_) => (),
+            Expression::Challenge(challenge) => cells.queried_challenges.push(*challenge),
             Expression::Negated(a) => a.query_cells(cells),
             Expression::Sum(a, b) => {
                 a.query_cells(cells);
@@ -1036,7 +1276,13 @@ impl<F: Field> Expression<F> {
         }
     }
 
-    fn write_identifier<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+    /// Writes the identifier of this expression, with the challenges in `challenge_names`
+    /// written by name rather than by index.
+    fn write_identifier<W: std::io::Write>(
+        &self,
+        writer: &mut W,
+        challenge_names: &HashMap<usize, String>,
+    ) -> std::io::Result<()> {
         match self {
             Expression::Constant(scalar) => write!(writer, "{scalar:?}"),
             Expression::Selector(selector) => write!(writer, "selector[{}]", selector.0),
@@ -1061,30 +1307,31 @@ impl<F: Field> Expression<F> {
                     query.column_index, query.rotation.0
                 )
             }
-            Expression::Challenge(challenge) => {
-                write!(writer, "challenge[{}]", challenge.index())
-            }
+            Expression::Challenge(challenge) => match challenge_names.get(&challenge.index()) {
+                Some(name) => write!(writer, "challenge[{name}]"),
+                None => write!(writer, "challenge[{}]", challenge.index()),
+            },
             Expression::Negated(a) => {
                 writer.write_all(b"(-")?;
-                a.write_identifier(writer)?;
+                a.write_identifier(writer, challenge_names)?;
                 writer.write_all(b")")
             }
             Expression::Sum(a, b) => {
                 writer.write_all(b"(")?;
-                a.write_identifier(writer)?;
+                a.write_identifier(writer, challenge_names)?;
                 writer.write_all(b"+")?;
-                b.write_identifier(writer)?;
+                b.write_identifier(writer, challenge_names)?;
                 writer.write_all(b")")
             }
             Expression::Product(a, b) => {
                 writer.write_all(b"(")?;
-                a.write_identifier(writer)?;
+                a.write_identifier(writer, challenge_names)?;
                 writer.write_all(b"*")?;
-                b.write_identifier(writer)?;
+                b.write_identifier(writer, challenge_names)?;
                 writer.write_all(b")")
             }
             Expression::Scaled(a, f) => {
-                a.write_identifier(writer)?;
+                a.write_identifier(writer, challenge_names)?;
                 write!(writer, "*{f:?}")
             }
         }
@@ -1095,7 +1342,7 @@ impl<F: Field> Expression<F> {
     /// in how they are composed e.g. `1 + 2` and `2 + 1` can have the same identifier).
     pub fn identifier(&self) -> String {
         let mut cursor = std::io::Cursor::new(Vec::new());
-        self.write_identifier(&mut cursor).unwrap();
+        self.write_identifier(&mut cursor, &HashMap::new()).unwrap();
         String::from_utf8(cursor.into_inner()).unwrap()
     }
 
@@ -1131,6 +1378,116 @@ impl<F: Field> Expression<F> {
         }
     }
 
+    /// Renders this expression as an indented tree, one node per line, for auditing the
+    /// cost of high-degree gates.
+    ///
+    /// Every node is annotated with its degree, its [`Expression::complexity`] and the
+    /// number of multiplications (products and scalings) evaluating it costs per row,
+    /// counting repeated subexpressions once per occurrence. Column queries are written
+    /// with their rotation and, given the constraint system, the column annotation;
+    /// challenges are written with their name.
+    pub fn annotated_tree(&self, cs: Option<&ConstraintSystem<F>>) -> String {
+        let mut lines = Vec::new();
+        self.write_annotated_tree(cs, 0, &mut lines);
+        lines.join("\n")
+    }
+
+    /// Appends the lines of [`Expression::annotated_tree`] for this node, indented by
+    /// `depth`, and returns the number of multiplications of the node.
+    fn write_annotated_tree(
+        &self,
+        cs: Option<&ConstraintSystem<F>>,
+        depth: usize,
+        lines: &mut Vec<String>,
+    ) -> usize {
+        let column_name = |column_type: Any, index: usize| {
+            cs.and_then(|cs| {
+                cs.general_column_annotations
+                    .get(&metadata::Column::from((column_type, index)))
+            })
+            .map(|name| format!(" ({name})"))
+            .unwrap_or_default()
+        };
+
+        let line = lines.len();
+        lines.push(String::new());
+        let (label, multiplications) = match self {
+            Expression::Constant(scalar) => (format!("Constant {scalar:?}"), 0),
+            Expression::Selector(selector) => (
+                format!(
+                    "Selector selector[{}] ({})",
+                    selector.0,
+                    if selector.is_simple() {
+                        "simple"
+                    } else {
+                        "complex"
+                    }
+                ),
+                0,
+            ),
+            Expression::Fixed(query) => (
+                format!(
+                    "Fixed fixed[{}]{} rotation {}",
+                    query.column_index,
+                    column_name(Any::Fixed, query.column_index),
+                    query.rotation.0
+                ),
+                0,
+            ),
+            Expression::Advice(query) => (
+                format!(
+                    "Advice advice[{}]{} rotation {} phase {}",
+                    query.column_index,
+                    column_name(Any::Advice(Advice::new(query.phase())), query.column_index),
+                    query.rotation.0,
+                    query.phase()
+                ),
+                0,
+            ),
+            Expression::Instance(query) => (
+                format!(
+                    "Instance instance[{}]{} rotation {}",
+                    query.column_index,
+                    column_name(Any::Instance, query.column_index),
+                    query.rotation.0
+                ),
+                0,
+            ),
+            Expression::Challenge(challenge) => (
+                match cs.and_then(|cs| cs.challenge_annotation(*challenge)) {
+                    Some(name) => format!("Challenge challenge[{}] ({name})", challenge.index()),
+                    None => format!("Challenge challenge[{}]", challenge.index()),
+                },
+                0,
+            ),
+            Expression::Negated(a) => (
+                "Negated".to_string(),
+                a.write_annotated_tree(cs, depth + 1, lines),
+            ),
+            Expression::Sum(a, b) => {
+                let a = a.write_annotated_tree(cs, depth + 1, lines);
+                let b = b.write_annotated_tree(cs, depth + 1, lines);
+                ("Sum".to_string(), a + b)
+            }
+            Expression::Product(a, b) => {
+                let a = a.write_annotated_tree(cs, depth + 1, lines);
+                let b = b.write_annotated_tree(cs, depth + 1, lines);
+                ("Product".to_string(), a + b + 1)
+            }
+            Expression::Scaled(a, f) => (
+                format!("Scaled by {f:?}"),
+                a.write_annotated_tree(cs, depth + 1, lines) + 1,
+            ),
+        };
+        lines[line] = format!(
+            "{}{label} [degree {}, complexity {}, multiplications {multiplications}]",
+            "  ".repeat(depth),
+            self.degree(),
+            self.complexity(),
+        );
+        multiplications
+    }
+
     /// Square this expression.
     pub fn square(self) -> Self {
         self.clone() * self
@@ -1152,6 +1509,22 @@ impl<F: Field> Expression<F> {
         )
     }
 
+    /// Returns whether or not this expression contains the selector with the given index.
+    fn contains_selector(&self, index: usize) -> bool {
+        self.evaluate(
+            &|_| false,
+            &|selector| selector.0 == index,
+            &|_| false,
+            &|_| false,
+            &|_| false,
+            &|_| false,
+            &|a| a,
+            &|a, b| a || b,
+            &|a, b| a || b,
+            &|a, _| a,
+        )
+    }
+
     /// Extracts a simple selector from this gate, if present
     fn extract_simple_selector(&self) -> Option<Selector> {
         let op = |a, b| match (a, b) {
@@ -1237,6 +1610,29 @@ impl<F: std::fmt::Debug> std::fmt::Debug for Expression<F> {
     }
 }
 
+/// Renders an expression with the names of the challenges of a constraint system, see
+/// [`ConstraintSystem::display_expression`].
+pub struct ExpressionDisplay<'a, F> {
+    expression: &'a Expression<F>,
+    challenge_names: &'a HashMap<usize, String>,
+}
+
+impl<F: Field> std::fmt::Display for ExpressionDisplay<'_, F> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut cursor = std::io::Cursor::new(Vec::new());
+        self.expression
+            .write_identifier(&mut cursor, self.challenge_names)
+            .map_err(|_| std::fmt::Error)?;
+        f.write_str(&String::from_utf8_lossy(&cursor.into_inner()))
+    }
+}
+
+impl<F: Field> std::fmt::Debug for ExpressionDisplay<'_, F> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Display::fmt(self, f)
+    }
+}
+
 impl<F: Field> Neg for Expression<F> {
     type Output = Expression<F>;
     fn neg(self) -> Self::Output {
@@ -1406,6 +1802,108 @@ impl<F: Field, C: Into<Constraint<F>>, Iter: IntoIterator<Item = C>> Constraints
             constraints,
         }
     }
+
+    /// Constructs a set of constraints, each controlled by its own selector.
+    ///
+    /// The `i`-th constraint `c` in `constraints` will be converted into the constraint
+    /// `selectors[i] * c`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `selectors` and `constraints` have different lengths.
+    pub fn with_selectors(
+        selectors: impl IntoIterator<Item = Expression<F>>,
+        constraints: Iter,
+    ) -> Vec<Constraint<F>> {
+        let selectors: Vec<_> = selectors.into_iter().collect();
+        let constraints: Vec<_> = constraints.into_iter().collect();
+        assert_eq!(
+            selectors.len(),
+            constraints.len(),
+            "Constraints::with_selectors requires one selector per constraint"
+        );
+        selectors
+            .into_iter()
+            .zip(constraints)
+            .map(apply_selector_to_constraint)
+            .collect()
+    }
+}
+
+/// A set of polynomial constraints with a common selector, some of which are controlled by
+/// an additional selector.
+///
+/// ```
+/// use halo2_common::plonk::{ConstraintsBuilder, Expression};
+/// use halo2_middleware::poly::Rotation;
+/// use halo2curves::pasta::Fp;
+/// # use halo2_common::plonk::ConstraintSystem;
+///
+/// # let mut meta = ConstraintSystem::<Fp>::default();
+/// let a = meta.advice_column();
+/// let acc = meta.advice_column();
+/// let q_main = meta.selector();
+/// let q_last = meta.complex_selector();
+///
+/// meta.create_gate("accumulate", |meta| {
+///     let a = meta.query_advice(a, Rotation::cur());
+///     let acc_next = meta.query_advice(acc, Rotation::next());
+///     let acc = meta.query_advice(acc, Rotation::cur());
+///     let q_main = meta.query_selector(q_main);
+///     let q_last = meta.query_selector(q_last);
+///
+///     ConstraintsBuilder::new(q_main)
+///         .add("a is boolean", a.clone() * (Expression::Constant(Fp::one()) - a.clone()))
+///         .add_gated("acc is zero on the last row", q_last, acc.clone())
+///         .add("acc_next == acc + a", acc_next - (acc + a))
+/// });
+/// ```
+#[derive(Debug)]
+pub struct ConstraintsBuilder<F: Field> {
+    selector: Expression<F>,
+    constraints: Vec<Constraint<F>>,
+}
+
+impl<F: Field> ConstraintsBuilder<F> {
+    /// Starts a set of constraints that are controlled by the given selector.
+    pub fn new(selector: Expression<F>) -> Self {
+        ConstraintsBuilder {
+            selector,
+            constraints: vec![],
+        }
+    }
+
+    /// Adds the constraint `selector * poly`.
+    pub fn add<S: AsRef<str>>(mut self, name: S, poly: Expression<F>) -> Self {
+        self.constraints.push(apply_selector_to_constraint((
+            self.selector.clone(),
+            (name, poly),
+        )));
+        self
+    }
+
+    /// Adds the constraint `selector * extra_selector * poly`.
+    pub fn add_gated<S: AsRef<str>>(
+        mut self,
+        name: S,
+        extra_selector: Expression<F>,
+        poly: Expression<F>,
+    ) -> Self {
+        self.constraints.push(apply_selector_to_constraint((
+            self.selector.clone() * extra_selector,
+            (name, poly),
+        )));
+        self
+    }
+}
+
+impl<F: Field> IntoIterator for ConstraintsBuilder<F> {
+    type Item = Constraint<F>;
+    type IntoIter = std::vec::IntoIter<Constraint<F>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.constraints.into_iter()
+    }
 }
 
 fn apply_selector_to_constraint<F: Field, C: Into<Constraint<F>>>(
@@ -1571,7 +2069,10 @@ impl<F: Field> From<ConstraintSystem<F>> for ConstraintSystemV2Backend<F> {
                     let constraint_names = std::mem::take(&mut g.constraint_names);
                     let gate_name = g.name.clone();
                     g.polys.into_iter().enumerate().map(move |(i, e)| {
-                        let name = match constraint_names[i].as_str() {
+                        // Gates rebuilt from a `ConstraintSystemV2Backend` have no
+                        // constraint names.
+                        let constraint_name = constraint_names.get(i).map_or("", String::as_str);
+                        let name = match constraint_name {
                             "" => gate_name.clone(),
                             constraint_name => format!("{gate_name}:{constraint_name}"),
                         };
@@ -1735,6 +2236,85 @@ pub fn collect_queries<F: Field>(
     (queries, gates, lookups, shuffles)
 }
 
+/// How a selector was turned into a fixed column, see [`SelectorCompressionReport`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SelectorReport {
+    /// The selector, by index.
+    pub selector: usize,
+    /// The indices of the gates that use the selector, see
+    /// [`ConstraintSystem::gates_using_selector`].
+    pub gates: Vec<usize>,
+    /// The maximum degree of the gate constraints that use the selector as a simple selector,
+    /// or zero if the selector is complex or not used by any gate.
+    pub max_degree: usize,
+    /// The fixed column that holds the selector.
+    pub column: Column<Fixed>,
+    /// The value of `column` on the rows where the selector is active. It is zero on the rows
+    /// where none of the selectors packed into `column` is active.
+    pub value: usize,
+}
+
+/// The selectors that were packed into a new fixed column, see [`SelectorCompressionReport`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CombinationReport {
+    /// The fixed column.
+    pub column: Column<Fixed>,
+    /// The selectors packed into the column, by index and in the order of their values.
+    pub selectors: Vec<usize>,
+}
+
+/// A description of how the selectors of a constraint system were turned into fixed columns,
+/// returned by [`ConstraintSystem::compress_selectors_with_report`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SelectorCompressionReport {
+    /// The degree of the constraint system, which bounds the degree of the combinations.
+    pub max_degree: usize,
+    /// A report for each selector, indexed by selector.
+    pub selectors: Vec<SelectorReport>,
+    /// A report for each new fixed column, in the order of the returned polynomials.
+    pub columns: Vec<CombinationReport>,
+}
+
+/// A gate that queries advice columns of more than one phase, recorded by
+/// [`ConstraintSystem::create_gate`] when [`ConstraintSystem::set_strict_phases`] is set.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PhaseMix {
+    /// The name of the gate.
+    pub gate: String,
+    /// The phases of the advice columns queried by the gate, in ascending order. The last
+    /// one is the latest phase that the gate depends on.
+    pub advice_phases: Vec<u8>,
+    /// The phases of the challenges used by the gate, in ascending order.
+    pub challenge_phases: Vec<u8>,
+}
+
+/// A fixed column whose values are computed from the row index, allocated with
+/// [`ConstraintSystem::preprocessed_fixed_column`].
+#[derive(Clone)]
+pub struct PreprocessedFixedColumn<F: Field> {
+    /// The column.
+    pub column: Column<Fixed>,
+    /// The name of the column.
+    pub name: String,
+    generator: Arc<dyn Fn(usize) -> F + Send + Sync>,
+}
+
+impl<F: Field> PreprocessedFixedColumn<F> {
+    /// Returns the value of the column at `row`.
+    pub fn value(&self, row: usize) -> F {
+        (self.generator)(row)
+    }
+}
+
+impl<F: Field> std::fmt::Debug for PreprocessedFixedColumn<F> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PreprocessedFixedColumn")
+            .field("column", &self.column)
+            .field("name", &self.name)
+            .finish_non_exhaustive()
+    }
+}
+
 /// This is a description of the circuit environment, such as the gate, column and
 /// permutation arrangements.
 #[derive(Debug, Clone)]
@@ -1755,9 +2335,13 @@ pub struct ConstraintSystem<F: Field> {
 
     /// This is a cached vector that maps virtual selectors to the concrete
     /// fixed column that they were compressed into. This is just used by dev
-    /// tooling right now.
+    /// tooling right now. See [`ConstraintSystem::selector_map`].
     pub selector_map: Vec<Column<Fixed>>,
 
+    /// Contains the index of each simple selector that is not combined with other selectors
+    /// by `compress_selectors`, see [`ConstraintSystem::selector_uncompressed`].
+    pub uncompressed_selectors: Vec<usize>,
+
     pub gates: Vec<Gate<F>>,
     pub advice_queries: Vec<(Column<Advice>, Rotation)>,
     // Contains an integer for each advice column
@@ -1781,11 +2365,38 @@ pub struct ConstraintSystem<F: Field> {
     // List of indexes of Fixed columns which are associated to a circuit-general Column tied to their annotation.
     pub general_column_annotations: HashMap<metadata::Column, String>,
 
+    /// Contains the name of each challenge requested with
+    /// [`ConstraintSystem::challenge_usable_after_named`], indexed by challenge index.
+    pub challenge_annotations: HashMap<usize, String>,
+
     // Vector of fixed columns, which can be used to store constant values
     // that are copied into advice columns.
     pub constants: Vec<Column<Fixed>>,
 
+    // Vector of fixed columns allocated as `TableColumn`s, whose unused rows are filled
+    // with a default value by `Layouter::assign_table`. This is just used by dev tooling.
+    pub table_columns: Vec<Column<Fixed>>,
+
+    /// The fixed columns holding `l_first` and `l_last`, allocated by
+    /// [`ConstraintSystem::boundary_fixed_columns`].
+    pub boundary_columns: Option<(Column<Fixed>, Column<Fixed>)>,
+
+    /// The fixed columns computed from the row index, allocated with
+    /// [`ConstraintSystem::preprocessed_fixed_column`].
+    pub preprocessed_fixed_columns: Vec<PreprocessedFixedColumn<F>>,
+
     pub minimum_degree: Option<usize>,
+
+    /// The degree budget set with [`ConstraintSystem::set_max_degree`].
+    pub max_degree: Option<usize>,
+
+    /// Whether the gates that mix phases are recorded, see
+    /// [`ConstraintSystem::set_strict_phases`].
+    pub strict_phases: bool,
+    /// The gates that query advice columns of more than one phase, recorded in strict mode.
+    pub phase_mixes: Vec<PhaseMix>,
+    /// The names of the gates allowed with [`ConstraintSystem::allow_phase_mix`].
+    pub allowed_phase_mixes: Vec<String>,
 }
 
 impl<F: Field> From<ConstraintSystemV2Backend<F>> for ConstraintSystem<F> {
@@ -1805,6 +2416,7 @@ impl<F: Field> From<ConstraintSystemV2Backend<F>> for ConstraintSystem<F> {
                 .collect(),
             challenge_phase: cs2.challenge_phase.into_iter().map(sealed::Phase).collect(),
             selector_map: Vec::new(),
+            uncompressed_selectors: Vec::new(),
             gates,
             advice_queries: queries.advice,
             num_advice_queries: queries.num_advice_queries,
@@ -1814,8 +2426,16 @@ impl<F: Field> From<ConstraintSystemV2Backend<F>> for ConstraintSystem<F> {
             lookups,
             shuffles,
             general_column_annotations: cs2.general_column_annotations,
+            challenge_annotations: HashMap::new(),
             constants: Vec::new(),
+            table_columns: Vec::new(),
+            boundary_columns: None,
+            preprocessed_fixed_columns: Vec::new(),
             minimum_degree: None,
+            max_degree: None,
+            strict_phases: false,
+            phase_mixes: Vec::new(),
+            allowed_phase_mixes: Vec::new(),
         }
     }
 }
@@ -1873,6 +2493,17 @@ impl<'a, F: Field> std::fmt::Debug for PinnedConstraintSystem<'a, F> {
     }
 }
 
+impl<'a, F: Field> PinnedConstraintSystem<'a, F> {
+    /// Returns the canonical string representation of this pinned constraint system.
+    ///
+    /// This is the pretty-printed `Debug` form (`format!("{:#?}", pinned)`), which is the
+    /// representation that pinned circuits have historically been compared against. Any
+    /// change to it changes the circuit, and so its verifying key.
+    pub fn to_string_stable(&self) -> String {
+        format!("{self:#?}")
+    }
+}
+
 struct PinnedGates<'a, F: Field>(&'a Vec<Gate<F>>);
 
 impl<'a, F: Field> std::fmt::Debug for PinnedGates<'a, F> {
@@ -1895,6 +2526,7 @@ impl<F: Field> Default for ConstraintSystem<F> {
             advice_column_phase: Vec::new(),
             challenge_phase: Vec::new(),
             selector_map: vec![],
+            uncompressed_selectors: vec![],
             gates: vec![],
             fixed_queries: Vec::new(),
             advice_queries: Vec::new(),
@@ -1904,8 +2536,16 @@ impl<F: Field> Default for ConstraintSystem<F> {
             lookups: Vec::new(),
             shuffles: Vec::new(),
             general_column_annotations: HashMap::new(),
+            challenge_annotations: HashMap::new(),
             constants: vec![],
+            table_columns: vec![],
+            boundary_columns: None,
+            preprocessed_fixed_columns: Vec::new(),
             minimum_degree: None,
+            max_degree: None,
+            strict_phases: false,
+            phase_mixes: Vec::new(),
+            allowed_phase_mixes: Vec::new(),
         }
     }
 }
@@ -1978,8 +2618,56 @@ impl<F: Field> ConstraintSystem<F> {
             .collect();
         let index = self.lookups.len();
 
-        self.lookups
-            .push(lookup::Argument::new(name.as_ref(), table_map));
+        let argument = lookup::Argument::new(name.as_ref(), table_map);
+        self.assert_within_max_degree(
+            || format!("lookup {:?}", name.as_ref()),
+            argument.required_degree(),
+        );
+        self.lookups.push(argument);
+
+        index
+    }
+
+    /// Add a lookup argument for some input expressions and table columns, that
+    /// is only enforced on the rows where `selector` is enabled.
+    ///
+    /// Unlike [`ConstraintSystem::lookup`], `selector` may be a simple selector,
+    /// which can then also be used in gates. It is excluded from selector
+    /// compression (see [`ConstraintSystem::selector_uncompressed`]), so that it
+    /// is replaced by a query of its own fixed column, and that query is
+    /// multiplied onto every input expression. The table columns must thus
+    /// contain zero.
+    pub fn lookup_gated<S: AsRef<str>>(
+        &mut self,
+        name: S,
+        selector: Selector,
+        table_map: impl FnOnce(&mut VirtualCells<'_, F>) -> Vec<(Expression<F>, TableColumn)>,
+    ) -> usize {
+        if selector.is_simple() && !self.uncompressed_selectors.contains(&selector.0) {
+            self.uncompressed_selectors.push(selector.0);
+        }
+        let mut cells = VirtualCells::new(self);
+        let table_map = table_map(&mut cells)
+            .into_iter()
+            .map(|(input, table)| {
+                if input.contains_simple_selector() {
+                    panic!("expression containing simple selector supplied to lookup argument");
+                }
+                let mut input = selector.expr() * input;
+                let mut table = cells.query_fixed(table.inner(), Rotation::cur());
+                input.query_cells(&mut cells);
+                table.query_cells(&mut cells);
+                (input, table)
+            })
+            .collect();
+        let index = self.lookups.len();
+
+        let argument = lookup::Argument::new(name.as_ref(), table_map);
+        self.assert_within_max_degree(
+            || format!("lookup {:?}", name.as_ref()),
+            argument.required_degree(),
+        );
+        self.lookups.push(argument);
 
         index
     }
@@ -2010,8 +2698,12 @@ impl<F: Field> ConstraintSystem<F> {
             .collect();
         let index = self.lookups.len();
 
-        self.lookups
-            .push(lookup::Argument::new(name.as_ref(), table_map));
+        let argument = lookup::Argument::new(name.as_ref(), table_map);
+        self.assert_within_max_degree(
+            || format!("lookup {:?}", name.as_ref()),
+            argument.required_degree(),
+        );
+        self.lookups.push(argument);
 
         index
     }
@@ -2033,8 +2725,12 @@ impl<F: Field> ConstraintSystem<F> {
             .collect();
         let index = self.shuffles.len();
 
-        self.shuffles
-            .push(shuffle::Argument::new(name.as_ref(), shuffle_map));
+        let argument = shuffle::Argument::new(name.as_ref(), shuffle_map);
+        self.assert_within_max_degree(
+            || format!("shuffle {:?}", name.as_ref()),
+            argument.required_degree(),
+        );
+        self.shuffles.push(argument);
 
         index
     }
@@ -2143,9 +2839,125 @@ impl<F: Field> ConstraintSystem<F> {
 
     /// Sets the minimum degree required by the circuit, which can be set to a
     /// larger amount than actually needed. This can be used, for example, to
-    /// force the permutation argument to involve more columns in the same set.
-    pub fn set_minimum_degree(&mut self, degree: usize) {
-        self.minimum_degree = Some(degree);
+    /// force the permutation argument to involve more columns in the same set,
+    /// see [`ConstraintSystem::permutation_chunks`].
+    ///
+    /// Returns an error if `degree` is below the degree that the constraint system
+    /// already requires, since it would have no effect. Constraints that are added
+    /// later can still raise the degree above the minimum.
+    pub fn set_minimum_degree(&mut self, degree: usize) -> Result<(), Error> {
+        let required = self.required_degree();
+        if degree < required {
+            return Err(Error::Other(format!(
+                "minimum degree {degree} is below the degree {required} that the \
+                 constraint system already requires"
+            )));
+        }
+        if let Some(max_degree) = self.max_degree {
+            if degree > max_degree {
+                return Err(Error::Other(format!(
+                    "minimum degree {degree} exceeds the maximum degree {max_degree}"
+                )));
+            }
+        }
+        self.minimum_degree = Some(degree);
+        Ok(())
+    }
+
+    /// Sets a budget for the degree of the circuit. Once it is set, adding a gate, a lookup
+    /// or a shuffle whose degree exceeds the budget panics, naming the offending
+    /// constraint and its degree. The degrees are accounted for as in
+    /// [`ConstraintSystem::degree`], which can be checked instead when no budget is set.
+    ///
+    /// Returns an error if the constraint system already exceeds `degree`. The
+    /// permutation argument alone requires degree 3.
+    pub fn set_max_degree(&mut self, degree: usize) -> Result<(), Error> {
+        let current = self.degree();
+        if current > degree {
+            return Err(Error::Other(format!(
+                "maximum degree {degree} is below the degree {current} that the \
+                 constraint system already requires"
+            )));
+        }
+        self.max_degree = Some(degree);
+        Ok(())
+    }
+
+    /// Enables or disables strict phase checking.
+    ///
+    /// In strict mode, every gate created afterwards that queries advice columns of more
+    /// than one phase is recorded in [`ConstraintSystem::phase_mixes`], with the phases of
+    /// the challenges it uses. Such a gate is legal, but a gate meant for an earlier phase
+    /// may query a column of a later phase by mistake, so [`ConstraintSystem::validate`]
+    /// rejects the recorded gates unless they are allowed with
+    /// [`ConstraintSystem::allow_phase_mix`]. Strict mode is disabled by default.
+    pub fn set_strict_phases(&mut self, strict: bool) {
+        self.strict_phases = strict;
+    }
+
+    /// Marks the gate named `gate` as intentionally querying advice columns of more than
+    /// one phase, so that [`ConstraintSystem::validate`] does not reject it.
+    pub fn allow_phase_mix<S: AsRef<str>>(&mut self, gate: S) {
+        self.allowed_phase_mixes.push(gate.as_ref().to_string());
+    }
+
+    /// Returns the gates recorded in strict mode that query advice columns of more than
+    /// one phase and were not allowed with [`ConstraintSystem::allow_phase_mix`].
+    pub fn unintended_phase_mixes(&self) -> impl Iterator<Item = &PhaseMix> {
+        self.phase_mixes
+            .iter()
+            .filter(|mix| !self.allowed_phase_mixes.contains(&mix.gate))
+    }
+
+    /// Checks the constraint system for mistakes that are only reported on request.
+    ///
+    /// Returns [`Error::UnintendedPhaseMix`] if, in strict mode (see
+    /// [`ConstraintSystem::set_strict_phases`]), gates were created that query advice
+    /// columns of more than one phase without being allowed with
+    /// [`ConstraintSystem::allow_phase_mix`].
+    pub fn validate(&self) -> Result<(), Error> {
+        let mixes: Vec<_> = self.unintended_phase_mixes().cloned().collect();
+        if mixes.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::UnintendedPhaseMix(mixes))
+        }
+    }
+
+    /// Records `gate` in [`ConstraintSystem::phase_mixes`] if the `cells` it queries belong
+    /// to advice columns of more than one phase.
+    fn record_phase_mix(&mut self, gate: &str, cells: &[VirtualCell], challenges: &[Challenge]) {
+        let mut advice_phases: Vec<u8> = cells
+            .iter()
+            .filter_map(|cell| match cell.column.column_type() {
+                Any::Advice(advice) => Some(advice.phase()),
+                _ => None,
+            })
+            .collect();
+        advice_phases.sort_unstable();
+        advice_phases.dedup();
+        if advice_phases.len() > 1 {
+            let mut challenge_phases: Vec<u8> = challenges.iter().map(|c| c.phase()).collect();
+            challenge_phases.sort_unstable();
+            challenge_phases.dedup();
+            self.phase_mixes.push(PhaseMix {
+                gate: gate.to_string(),
+                advice_phases,
+                challenge_phases,
+            });
+        }
+    }
+
+    /// Panics if `degree` exceeds the budget set with [`ConstraintSystem::set_max_degree`].
+    fn assert_within_max_degree(&self, what: impl FnOnce() -> String, degree: usize) {
+        if let Some(max_degree) = self.max_degree {
+            if degree > max_degree {
+                panic!(
+                    "{} has degree {degree}, which exceeds the maximum degree {max_degree}",
+                    what()
+                );
+            }
+        }
     }
 
     /// Creates a new gate.
@@ -2159,9 +2971,38 @@ impl<F: Field> ConstraintSystem<F> {
         name: S,
         constraints: impl FnOnce(&mut VirtualCells<'_, F>) -> Iter,
     ) {
+        assert!(
+            self.create_gate_optional(name, constraints).is_some(),
+            "Gates must contain at least one constraint."
+        );
+    }
+
+    /// Creates a new gate, unless `constraints` returns an empty iterator, in which case
+    /// nothing is registered. Returns the index of the new gate in
+    /// [`ConstraintSystem::gates`], if any.
+    ///
+    /// Skipping a gate changes the constraint system, and so the verifying key, relative
+    /// to a build where the gate has constraints.
+    pub fn create_gate_optional<
+        C: Into<Constraint<F>>,
+        Iter: IntoIterator<Item = C>,
+        S: AsRef<str>,
+    >(
+        &mut self,
+        name: S,
+        constraints: impl FnOnce(&mut VirtualCells<'_, F>) -> Iter,
+    ) -> Option<usize> {
+        // Queries are registered as the closure makes them, so remember where they end to
+        // roll them back if the gate is skipped.
+        let num_queries = (
+            self.advice_queries.len(),
+            self.instance_queries.len(),
+            self.fixed_queries.len(),
+        );
+
         let mut cells = VirtualCells::new(self);
         let constraints = constraints(&mut cells);
-        let (constraint_names, polys): (_, Vec<_>) = constraints
+        let (constraint_names, polys): (Vec<String>, Vec<_>) = constraints
             .into_iter()
             .map(|c| c.into())
             .map(|mut c: Constraint<F>| {
@@ -2169,14 +3010,29 @@ impl<F: Field> ConstraintSystem<F> {
                 (c.name, c.poly)
             })
             .unzip();
-
         let queried_selectors = cells.queried_selectors;
         let queried_cells = cells.queried_cells;
+        let queried_challenges = cells.queried_challenges;
 
-        assert!(
-            !polys.is_empty(),
-            "Gates must contain at least one constraint."
-        );
+        if polys.is_empty() {
+            let (advice, instance, fixed) = num_queries;
+            for (column, _) in self.advice_queries.drain(advice..) {
+                self.num_advice_queries[column.index] -= 1;
+            }
+            self.instance_queries.truncate(instance);
+            self.fixed_queries.truncate(fixed);
+            return None;
+        }
+
+        for (constraint_name, poly) in constraint_names.iter().zip(polys.iter()) {
+            self.assert_within_max_degree(
+                || format!("constraint {constraint_name:?} of gate {:?}", name.as_ref()),
+                poly.degree(),
+            );
+        }
+        if self.strict_phases {
+            self.record_phase_mix(name.as_ref(), &queried_cells, &queried_challenges);
+        }
 
         self.gates.push(Gate {
             name: name.as_ref().to_string(),
@@ -2185,6 +3041,7 @@ impl<F: Field> ConstraintSystem<F> {
             queried_selectors,
             queried_cells,
         });
+        Some(self.gates.len() - 1)
     }
 
     /// This will compress selectors together depending on their provided
@@ -2193,8 +3050,24 @@ impl<F: Field> ConstraintSystem<F> {
     /// polynomials for those columns. Finally, an internal map is updated to
     /// find which fixed column corresponds with a given `Selector`.
     ///
+    /// The result only depends on the activations of the selectors, on their
+    /// degrees and on the degree of the constraint system: the selectors that
+    /// can't be combined get their own column first, in index order, and the
+    /// others are then combined greedily in index order, each combination
+    /// being started by the lowest unassigned index.
+    ///
     /// Do not call this twice. Yes, this should be a builder pattern instead.
-    pub fn compress_selectors(mut self, selectors: Vec<Vec<bool>>) -> (Self, Vec<Vec<F>>) {
+    pub fn compress_selectors(self, selectors: Vec<Vec<bool>>) -> (Self, Vec<Vec<F>>) {
+        let (cs, polys, _) = self.compress_selectors_with_report(selectors);
+        (cs, polys)
+    }
+
+    /// Compresses the selectors like [`ConstraintSystem::compress_selectors`], and also
+    /// returns a report of which selectors were packed into which new fixed column.
+    pub fn compress_selectors_with_report(
+        mut self,
+        selectors: Vec<Vec<bool>>,
+    ) -> (Self, Vec<Vec<F>>, SelectorCompressionReport) {
         // The number of provided selector assignments must be the number we
         // counted for this constraint system.
         assert_eq!(selectors.len(), self.num_selectors);
@@ -2218,13 +3091,19 @@ impl<F: Field> ConstraintSystem<F> {
         let (polys, selector_assignment) = compress_selectors::process(
             selectors
                 .into_iter()
-                .zip(degrees)
+                .zip(degrees.iter().copied())
                 .enumerate()
                 .map(
                     |(i, (activations, max_degree))| compress_selectors::SelectorDescription {
                         selector: i,
                         activations,
-                        max_degree,
+                        // Selectors of degree zero are placed in their own column,
+                        // which is what uncompressed selectors need.
+                        max_degree: if self.uncompressed_selectors.contains(&i) {
+                            0
+                        } else {
+                            max_degree
+                        },
                     },
                 )
                 .collect(),
@@ -2242,10 +3121,38 @@ impl<F: Field> ConstraintSystem<F> {
 
         let mut selector_map = vec![None; selector_assignment.len()];
         let mut selector_replacements = vec![None; selector_assignment.len()];
+        let mut selector_reports = vec![None; selector_assignment.len()];
+        let mut combination_reports = new_columns
+            .iter()
+            .map(|column| CombinationReport {
+                column: *column,
+                selectors: vec![],
+            })
+            .collect::<Vec<_>>();
         for assignment in selector_assignment {
+            let column = new_columns[assignment.combination_index];
+            let selector = assignment.selector;
             selector_replacements[assignment.selector] = Some(assignment.expression);
-            selector_map[assignment.selector] = Some(new_columns[assignment.combination_index]);
+            selector_map[assignment.selector] = Some(column);
+            selector_reports[assignment.selector] = Some(SelectorReport {
+                selector,
+                gates: self.gates_using(selector),
+                max_degree: degrees[assignment.selector],
+                column,
+                value: assignment.combination_value,
+            });
+            combination_reports[assignment.combination_index]
+                .selectors
+                .push(selector);
         }
+        let report = SelectorCompressionReport {
+            max_degree,
+            selectors: selector_reports
+                .into_iter()
+                .map(|report| report.unwrap())
+                .collect(),
+            columns: combination_reports,
+        };
 
         self.selector_map = selector_map
             .into_iter()
@@ -2257,7 +3164,74 @@ impl<F: Field> ConstraintSystem<F> {
             .collect::<Vec<_>>();
         self.replace_selectors_with_fixed(&selector_replacements);
 
-        (self, polys)
+        (self, polys, report)
+    }
+
+    /// Returns the indices of the gates that use the selector with the given index, either
+    /// through `query_selector` or directly in their expressions.
+    fn gates_using(&self, selector: usize) -> Vec<usize> {
+        self.gates
+            .iter()
+            .enumerate()
+            .filter(|(_, gate)| {
+                gate.queried_selectors()
+                    .iter()
+                    .any(|queried| queried.0 == selector)
+                    || gate
+                        .polynomials()
+                        .iter()
+                        .any(|poly| poly.contains_selector(selector))
+            })
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    /// Returns the indices of the gates that use `selector`, either through
+    /// `query_selector` or directly in their expressions.
+    pub fn gates_using_selector(&self, selector: Selector) -> Vec<usize> {
+        self.gates_using(selector.0)
+    }
+
+    /// Returns the indices of the gates that use each selector, indexed by
+    /// selector. Unused selectors have no gates.
+    pub fn selector_usage(&self) -> Vec<Vec<usize>> {
+        (0..self.num_selectors)
+            .map(|selector| self.gates_using(selector))
+            .collect()
+    }
+
+    /// Returns the indices of the lookup arguments whose input or table
+    /// expressions use `selector`, which must then be complex.
+    pub fn lookups_using_selector(&self, selector: Selector) -> Vec<usize> {
+        self.lookups
+            .iter()
+            .enumerate()
+            .filter(|(_, lookup)| {
+                lookup
+                    .input_expressions
+                    .iter()
+                    .chain(lookup.table_expressions.iter())
+                    .any(|expr| expr.contains_selector(selector.0))
+            })
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    /// Returns the indices of the shuffle arguments whose input or shuffle
+    /// expressions use `selector`, which must then be complex.
+    pub fn shuffles_using_selector(&self, selector: Selector) -> Vec<usize> {
+        self.shuffles
+            .iter()
+            .enumerate()
+            .filter(|(_, shuffle)| {
+                shuffle
+                    .input_expressions
+                    .iter()
+                    .chain(shuffle.shuffle_expressions.iter())
+                    .any(|expr| expr.contains_selector(selector.0))
+            })
+            .map(|(index, _)| index)
+            .collect()
     }
 
     /// Does not combine selectors and directly replaces them everywhere with fixed columns.
@@ -2269,6 +3243,7 @@ impl<F: Field> ConstraintSystem<F> {
         // counted for this constraint system.
         assert_eq!(selectors.len(), self.num_selectors);
 
+        let mut selector_map = vec![];
         let (polys, selector_replacements): (Vec<_>, Vec<_>) = selectors
             .into_iter()
             .map(|selector| {
@@ -2277,6 +3252,7 @@ impl<F: Field> ConstraintSystem<F> {
                     .map(|b| if *b { F::ONE } else { F::ZERO })
                     .collect::<Vec<_>>();
                 let column = self.fixed_column();
+                selector_map.push(column);
                 let rotation = Rotation::cur();
                 let expr = Expression::Fixed(FixedQuery {
                     index: Some(self.query_fixed_index(column, rotation)),
@@ -2286,6 +3262,7 @@ impl<F: Field> ConstraintSystem<F> {
                 (poly, expr)
             })
             .unzip();
+        self.selector_map = selector_map;
 
         self.replace_selectors_with_fixed(&selector_replacements);
         self.num_selectors = 0;
@@ -2293,23 +3270,143 @@ impl<F: Field> ConstraintSystem<F> {
         (self, polys)
     }
 
+    /// Replaces the selectors with the given indices by the given expressions in all the
+    /// gates, lookups and shuffles, and leaves the other selectors untouched.
+    ///
+    /// This is the substitution done by [`ConstraintSystem::compress_selectors`], for
+    /// tooling that lowers selectors itself, e.g. to control the placement of their fixed
+    /// columns. The replacements are typically queries of fixed columns (e.g. built with
+    /// [`Column::cur`]) that are assigned like the selectors they replace, and their
+    /// queries are registered in this constraint system. [`ConstraintSystem::selector_map`]
+    /// is not updated. Once every selector is replaced, `num_selectors` is reset to 0, so
+    /// that no selector assignments are expected anymore.
+    ///
+    /// Returns the degree of the constraint system after the replacement. Returns an
+    /// error, and leaves the constraint system unchanged, if a replaced index is not a
+    /// selector, or if a simple selector that is neither replaced nor uncompressed (see
+    /// [`ConstraintSystem::selector_uncompressed`]) is used in a lookup or a shuffle.
+    pub fn replace_selectors(
+        &mut self,
+        replacements: &HashMap<usize, Expression<F>>,
+    ) -> Result<usize, Error> {
+        if let Some(index) = replacements
+            .keys()
+            .find(|index| **index >= self.num_selectors)
+        {
+            return Err(Error::Other(format!(
+                "cannot replace selector {index}, there are only {} selectors",
+                self.num_selectors
+            )));
+        }
+
+        // Simple selectors may only remain in gates, unless they are uncompressed.
+        let remaining_simple = |expr: &Expression<F>| {
+            expr.evaluate(
+                &|_| None,
+                &|selector| {
+                    Some(selector.0).filter(|index| {
+                        selector.is_simple()
+                            && !replacements.contains_key(index)
+                            && !self.uncompressed_selectors.contains(index)
+                    })
+                },
+                &|_| None,
+                &|_| None,
+                &|_| None,
+                &|_| None,
+                &|a| a,
+                &|a, b| a.or(b),
+                &|a, b| a.or(b),
+                &|a, _| a,
+            )
+        };
+        let arguments = self
+            .lookups
+            .iter()
+            .map(|lookup| {
+                (
+                    "lookup",
+                    &lookup.name,
+                    lookup
+                        .input_expressions
+                        .iter()
+                        .chain(lookup.table_expressions.iter())
+                        .collect::<Vec<_>>(),
+                )
+            })
+            .chain(self.shuffles.iter().map(|shuffle| {
+                (
+                    "shuffle",
+                    &shuffle.name,
+                    shuffle
+                        .input_expressions
+                        .iter()
+                        .chain(shuffle.shuffle_expressions.iter())
+                        .collect(),
+                )
+            }));
+        for (kind, name, expressions) in arguments {
+            if let Some(index) = expressions.into_iter().find_map(&remaining_simple) {
+                return Err(Error::Other(format!(
+                    "simple selector {index} is used in {kind} \"{name}\", but is not replaced"
+                )));
+            }
+        }
+
+        let mut replacements = replacements.clone();
+        let mut cells = VirtualCells::new(self);
+        for replacement in replacements.values_mut() {
+            replacement.query_cells(&mut cells);
+        }
+        self.substitute_selectors(|selector| replacements.get(&selector.0).cloned(), false);
+        if (0..self.num_selectors).all(|index| replacements.contains_key(&index)) {
+            self.num_selectors = 0;
+            self.uncompressed_selectors.clear();
+        }
+
+        Ok(self.degree())
+    }
+
     fn replace_selectors_with_fixed(&mut self, selector_replacements: &[Expression<F>]) {
+        self.substitute_selectors(
+            |selector| Some(selector_replacements[selector.0].clone()),
+            true,
+        );
+    }
+
+    /// Substitutes the selectors for which `replacement` returns an expression, in all the
+    /// gates, lookups and shuffles.
+    ///
+    /// If `check_arguments` is true, panics if a replaced simple selector that is not
+    /// uncompressed is found in a lookup or a shuffle.
+    fn substitute_selectors(
+        &mut self,
+        replacement: impl Fn(Selector) -> Option<Expression<F>>,
+        check_arguments: bool,
+    ) {
         fn replace_selectors<F: Field>(
             expr: &mut Expression<F>,
-            selector_replacements: &[Expression<F>],
+            replacement: &impl Fn(Selector) -> Option<Expression<F>>,
+            uncompressed_selectors: &[usize],
             must_be_nonsimple: bool,
         ) {
             *expr = expr.evaluate(
                 &|constant| Expression::Constant(constant),
                 &|selector| {
+                    let Some(replaced) = replacement(selector) else {
+                        return Expression::Selector(selector);
+                    };
                     if must_be_nonsimple {
                         // Simple selectors are prohibited from appearing in
                         // expressions in the lookup argument by
-                        // `ConstraintSystem`.
-                        assert!(!selector.is_simple());
+                        // `ConstraintSystem`, unless they are uncompressed
+                        // and thus replaced by a plain fixed query.
+                        assert!(
+                            !selector.is_simple() || uncompressed_selectors.contains(&selector.0)
+                        );
                     }
 
-                    selector_replacements[selector.0].clone()
+                    replaced
                 },
                 &|query| Expression::Fixed(query),
                 &|query| Expression::Advice(query),
@@ -2322,9 +3419,11 @@ impl<F: Field> ConstraintSystem<F> {
             );
         }
 
+        let uncompressed = &self.uncompressed_selectors;
+
         // Substitute selectors for the real fixed columns in all gates
         for expr in self.gates.iter_mut().flat_map(|gate| gate.polys.iter_mut()) {
-            replace_selectors(expr, selector_replacements, false);
+            replace_selectors(expr, &replacement, uncompressed, false);
         }
 
         // Substitute non-simple selectors for the real fixed columns in all
@@ -2335,7 +3434,7 @@ impl<F: Field> ConstraintSystem<F> {
                 .iter_mut()
                 .chain(lookup.table_expressions.iter_mut())
         }) {
-            replace_selectors(expr, selector_replacements, true);
+            replace_selectors(expr, &replacement, uncompressed, check_arguments);
         }
 
         for expr in self.shuffles.iter_mut().flat_map(|shuffle| {
@@ -2344,7 +3443,7 @@ impl<F: Field> ConstraintSystem<F> {
                 .iter_mut()
                 .chain(shuffle.shuffle_expressions.iter_mut())
         }) {
-            replace_selectors(expr, selector_replacements, true);
+            replace_selectors(expr, &replacement, uncompressed, check_arguments);
         }
     }
 
@@ -2358,6 +3457,16 @@ impl<F: Field> ConstraintSystem<F> {
         Selector(index, true)
     }
 
+    /// Allocate a new (simple) selector that is never combined with other
+    /// selectors by `compress_selectors`. It gets its own fixed column, and
+    /// is replaced by a query of that column, so it does not raise the degree
+    /// of the gates that use it.
+    pub fn selector_uncompressed(&mut self) -> Selector {
+        let selector = self.selector();
+        self.uncompressed_selectors.push(selector.0);
+        selector
+    }
+
     /// Allocate a new complex selector that can appear anywhere
     /// within expressions.
     pub fn complex_selector(&mut self) -> Selector {
@@ -2368,9 +3477,9 @@ impl<F: Field> ConstraintSystem<F> {
 
     /// Allocates a new fixed column that can be used in a lookup table.
     pub fn lookup_table_column(&mut self) -> TableColumn {
-        TableColumn {
-            inner: self.fixed_column(),
-        }
+        let inner = self.fixed_column();
+        self.table_columns.push(inner);
+        TableColumn { inner }
     }
 
     /// Annotate a Lookup column.
@@ -2411,6 +3520,96 @@ impl<F: Field> ConstraintSystem<F> {
         tmp
     }
 
+    /// Returns queries of `l_first` and `l_last`, which are one on the first and on the
+    /// last usable row of the circuit respectively, and zero everywhere else.
+    ///
+    /// They make constraints on the boundaries of the circuit easy to write, e.g.
+    /// `l_last * (acc - expected)` to check a running sum on the last usable row, for any
+    /// `k`. They are held in two fixed columns that are allocated on the first call, and
+    /// assigned by the keygen and the `MockProver` (see
+    /// [`ConstraintSystem::boundary_cells`]).
+    pub fn boundary_fixed_columns(&mut self) -> (Expression<F>, Expression<F>) {
+        let (l_first, l_last) = match self.boundary_columns {
+            Some(columns) => columns,
+            None => {
+                let columns = (self.fixed_column(), self.fixed_column());
+                for (column, name) in [(columns.0, "l_first"), (columns.1, "l_last")] {
+                    self.annotate_lookup_any_column(column, || name);
+                }
+                self.boundary_columns = Some(columns);
+                columns
+            }
+        };
+        let mut query = |column: Column<Fixed>| {
+            Expression::Fixed(FixedQuery {
+                index: Some(self.query_fixed_index(column, Rotation::cur())),
+                column_index: column.index,
+                rotation: Rotation::cur(),
+            })
+        };
+        (query(l_first), query(l_last))
+    }
+
+    /// Returns the cells of the columns behind [`ConstraintSystem::boundary_fixed_columns`]
+    /// that hold one, given the usable rows of the circuit. All their other cells hold
+    /// zero.
+    pub fn boundary_cells(&self, usable_rows: &Range<usize>) -> Vec<(Column<Fixed>, usize)> {
+        match self.boundary_columns {
+            Some((l_first, l_last)) if !usable_rows.is_empty() => {
+                vec![(l_first, usable_rows.start), (l_last, usable_rows.end - 1)]
+            }
+            _ => vec![],
+        }
+    }
+
+    /// Allocates a fixed column named `name` whose value at each usable row is
+    /// `generator(row)`, e.g. a row counter or the powers of a constant.
+    ///
+    /// The column is filled by the keygen and the `MockProver` directly (see
+    /// [`ConstraintSystem::preprocessed_fixed_cells`]), without going through the
+    /// layouter, and its unusable rows hold zero. Assigning a cell of the column during
+    /// synthesis returns [`Error::PreprocessedFixedAssigned`].
+    pub fn preprocessed_fixed_column<S: AsRef<str>>(
+        &mut self,
+        name: S,
+        generator: impl Fn(usize) -> F + Send + Sync + 'static,
+    ) -> Column<Fixed> {
+        let column = self.fixed_column();
+        let name = name.as_ref().to_string();
+        self.annotate_lookup_any_column(column, || name.clone());
+        self.preprocessed_fixed_columns
+            .push(PreprocessedFixedColumn {
+                column,
+                name,
+                generator: Arc::new(generator),
+            });
+        column
+    }
+
+    /// Returns `true` if `column` was allocated with
+    /// [`ConstraintSystem::preprocessed_fixed_column`].
+    pub fn is_preprocessed_fixed(&self, column: Column<Fixed>) -> bool {
+        self.preprocessed_fixed_columns
+            .iter()
+            .any(|preprocessed| preprocessed.column == column)
+    }
+
+    /// Returns the cells of the columns allocated with
+    /// [`ConstraintSystem::preprocessed_fixed_column`] in the given usable rows of the
+    /// circuit, with their values, column by column.
+    pub fn preprocessed_fixed_cells<'a>(
+        &'a self,
+        usable_rows: &'a Range<usize>,
+    ) -> impl Iterator<Item = (Column<Fixed>, usize, F)> + 'a {
+        self.preprocessed_fixed_columns
+            .iter()
+            .flat_map(move |preprocessed| {
+                usable_rows
+                    .clone()
+                    .map(move |row| (preprocessed.column, row, preprocessed.value(row)))
+            })
+    }
+
     /// Allocate a new unblinded advice column at `FirstPhase`
     pub fn unblinded_advice_column(&mut self) -> Column<Advice> {
         self.unblinded_advice_column_in(FirstPhase)
@@ -2477,6 +3676,15 @@ impl<F: Field> ConstraintSystem<F> {
         tmp
     }
 
+    /// Returns a builder allocating named groups of columns, see [`ColumnsBuilder`].
+    pub fn columns(&mut self) -> ColumnsBuilder<'_, F> {
+        ColumnsBuilder {
+            cs: self,
+            columns: AllocatedColumns::default(),
+            last_group: None,
+        }
+    }
+
     /// Requests a challenge that is usable after the given phase.
     ///
     /// # Panics
@@ -2498,6 +3706,23 @@ impl<F: Field> ConstraintSystem<F> {
         tmp
     }
 
+    /// Requests a challenge that is usable after the given phase, and names it. The name is
+    /// used when rendering expressions and failures that involve the challenge.
+    ///
+    /// # Panics
+    ///
+    /// It panics if the given phase doesn't have advice column allocated.
+    pub fn challenge_usable_after_named<P: Phase, S: AsRef<str>>(
+        &mut self,
+        phase: P,
+        name: S,
+    ) -> Challenge {
+        let challenge = self.challenge_usable_after(phase);
+        self.challenge_annotations
+            .insert(challenge.index(), name.as_ref().to_string());
+        challenge
+    }
+
     /// Helper funciotn to assert phase exists, to make sure phase-aware resources
     /// are allocated in order, and to avoid any phase to be skipped accidentally
     /// to cause unexpected issue in the future.
@@ -2526,6 +3751,19 @@ impl<F: Field> ConstraintSystem<F> {
     /// Compute the degree of the constraint system (the maximum degree of all
     /// constraints).
     pub fn degree(&self) -> usize {
+        std::cmp::max(self.required_degree(), self.minimum_degree.unwrap_or(1))
+    }
+
+    /// Returns the sets of columns that the permutation argument is split into, with one
+    /// permutation product polynomial for each set. The size of the sets is determined by
+    /// [`ConstraintSystem::degree`].
+    pub fn permutation_chunks(&self) -> Vec<Vec<Column<Any>>> {
+        self.permutation.chunk_layout(self.degree())
+    }
+
+    /// Compute the degree required by the constraints and arguments of the constraint
+    /// system, without the minimum degree.
+    fn required_degree(&self) -> usize {
         // The permutation argument will serve alongside the gates, so must be
         // accounted for.
         let mut degree = self.permutation.required_degree();
@@ -2563,7 +3801,7 @@ impl<F: Field> ConstraintSystem<F> {
                 .unwrap_or(0),
         );
 
-        std::cmp::max(degree, self.minimum_degree.unwrap_or(1))
+        degree
     }
 
     /// Compute the number of blinding factors necessary to perfectly blind
@@ -2607,6 +3845,15 @@ impl<F: Field> ConstraintSystem<F> {
             + 1 // for at least one row
     }
 
+    /// Returns the rows that can be assigned in a circuit of `2^k` rows with this
+    /// constraint system: all of its rows except those reserved for blinding factors and
+    /// the last row of the permutation argument.
+    ///
+    /// The range is empty if `k` is too small for this constraint system.
+    pub fn usable_rows(&self, k: u32) -> Range<usize> {
+        0..(1usize << k).saturating_sub(self.blinding_factors() + 1)
+    }
+
     /// Returns number of fixed columns
     pub fn num_fixed_columns(&self) -> usize {
         self.num_fixed_columns
@@ -2627,6 +3874,16 @@ impl<F: Field> ConstraintSystem<F> {
         self.num_selectors
     }
 
+    /// Returns the fixed column that holds each selector, indexed by selector.
+    ///
+    /// This is only populated once the selectors have been turned into fixed
+    /// columns by [`ConstraintSystem::compress_selectors`] or
+    /// [`ConstraintSystem::directly_convert_selectors_to_fixed`], and is empty
+    /// before. Several selectors share a column when they are compressed.
+    pub fn selector_map(&self) -> &[Column<Fixed>] {
+        &self.selector_map
+    }
+
     /// Returns number of challenges
     pub fn num_challenges(&self) -> usize {
         self.num_challenges
@@ -2645,6 +3902,57 @@ impl<F: Field> ConstraintSystem<F> {
         self.challenge_phase.iter().map(|phase| phase.0).collect()
     }
 
+    /// Returns the challenges that have been requested, in the order of their indices.
+    pub fn challenges(&self) -> Vec<Challenge> {
+        self.challenge_phase
+            .iter()
+            .enumerate()
+            .map(|(index, phase)| Challenge {
+                index,
+                phase: phase.0,
+            })
+            .collect()
+    }
+
+    /// Returns the name of the given challenge, if it was requested with
+    /// [`ConstraintSystem::challenge_usable_after_named`].
+    pub fn challenge_annotation(&self, challenge: Challenge) -> Option<&str> {
+        self.challenge_annotations
+            .get(&challenge.index())
+            .map(String::as_str)
+    }
+
+    /// Returns a value that renders `expression` like [`Expression::identifier`], with named
+    /// challenges written by name.
+    pub fn display_expression<'a>(
+        &'a self,
+        expression: &'a Expression<F>,
+    ) -> ExpressionDisplay<'a, F> {
+        ExpressionDisplay {
+            expression,
+            challenge_names: &self.challenge_annotations,
+        }
+    }
+
+    /// Renders every gate with the [`Expression::annotated_tree`] of each of its
+    /// constraints, in the order the gates were created.
+    pub fn dump_gates(&self) -> String {
+        let mut lines = Vec::new();
+        for (gate_index, gate) in self.gates.iter().enumerate() {
+            lines.push(format!("gate[{gate_index}] {:?}", gate.name()));
+            for (poly_index, poly) in gate.polynomials().iter().enumerate() {
+                lines.push(format!(
+                    "  constraint[{poly_index}] {:?}",
+                    gate.constraint_name(poly_index)
+                ));
+                for line in poly.annotated_tree(Some(self)).lines() {
+                    lines.push(format!("    {line}"));
+                }
+            }
+        }
+        lines.join("\n")
+    }
+
     /// Returns gates
     pub fn gates(&self) -> &Vec<Gate<F>> {
         &self.gates
@@ -2670,6 +3978,18 @@ impl<F: Field> ConstraintSystem<F> {
         &self.fixed_queries
     }
 
+    /// Returns the queries of this constraint system, the same as those that
+    /// [`collect_queries`] finds once it is converted to a [`ConstraintSystemV2Backend`],
+    /// but in the order they were made while configuring the circuit.
+    pub fn queries(&self) -> Queries {
+        Queries {
+            advice: self.advice_queries.clone(),
+            instance: self.instance_queries.clone(),
+            fixed: self.fixed_queries.clone(),
+            num_advice_queries: self.num_advice_queries.clone(),
+        }
+    }
+
     /// Returns permutation argument
     pub fn permutation(&self) -> &permutation::Argument {
         &self.permutation
@@ -2691,6 +4011,181 @@ impl<F: Field> ConstraintSystem<F> {
     }
 }
 
+/// Allocates named groups of columns, returned by [`ConstraintSystem::columns`].
+///
+/// Columns are allocated as the methods are called, so a chain of calls allocates the
+/// same columns as the equivalent calls to [`ConstraintSystem::advice_column`],
+/// [`ConstraintSystem::fixed_column`] and [`ConstraintSystem::instance_column`] in the same
+/// order, and migrating a configuration to the builder keeps its verifying key. The
+/// columns of a group `name` are annotated `name[i]`, a single column is annotated `name`.
+///
+/// ```
+/// use halo2_common::plonk::{ConstraintSystem, SecondPhase};
+/// use halo2curves::pasta::Fp;
+///
+/// let mut meta = ConstraintSystem::<Fp>::default();
+/// // Replaces:
+/// //     let state = (0..8).map(|_| meta.advice_column()).collect::<Vec<_>>();
+/// //     for column in &state {
+/// //         meta.enable_equality(*column);
+/// //     }
+/// //     let round_constants = (0..4).map(|_| meta.fixed_column()).collect::<Vec<_>>();
+/// //     let rlc = meta.advice_column_in(SecondPhase);
+/// let columns = meta
+///     .columns()
+///     .advice_group("state", 8)
+///     .enable_equality()
+///     .fixed_group("round_constants", 4)
+///     .advice_in(SecondPhase, "rlc")
+///     .build();
+///
+/// let state = columns.advice("state");
+/// let rlc = columns.advice("rlc")[0];
+/// assert_eq!(state.len(), 8);
+/// assert_eq!(rlc.index(), 8);
+/// assert_eq!(columns.fixed("round_constants").len(), 4);
+/// ```
+#[derive(Debug)]
+pub struct ColumnsBuilder<'a, F: Field> {
+    cs: &'a mut ConstraintSystem<F>,
+    columns: AllocatedColumns,
+    last_group: Option<Vec<Column<Any>>>,
+}
+
+impl<'a, F: Field> ColumnsBuilder<'a, F> {
+    /// Allocates a group of `len` advice columns in the first phase.
+    pub fn advice_group(self, name: &str, len: usize) -> Self {
+        self.advice_group_in(FirstPhase, name, len)
+    }
+
+    /// Allocates a group of `len` advice columns in the given phase.
+    pub fn advice_group_in<P: Phase>(mut self, phase: P, name: &str, len: usize) -> Self {
+        let phase = phase.to_sealed();
+        let group: Vec<_> = (0..len).map(|_| self.cs.advice_column_in(phase)).collect();
+        self.annotate(name, &group, true);
+        self.columns.advice.insert(name.to_string(), group);
+        self
+    }
+
+    /// Allocates a single advice column in the given phase.
+    pub fn advice_in<P: Phase>(mut self, phase: P, name: &str) -> Self {
+        let column = self.cs.advice_column_in(phase);
+        self.annotate(name, &[column], false);
+        self.columns.advice.insert(name.to_string(), vec![column]);
+        self
+    }
+
+    /// Allocates a group of `len` fixed columns.
+    pub fn fixed_group(mut self, name: &str, len: usize) -> Self {
+        let group: Vec<_> = (0..len).map(|_| self.cs.fixed_column()).collect();
+        self.annotate(name, &group, true);
+        self.columns.fixed.insert(name.to_string(), group);
+        self
+    }
+
+    /// Allocates a group of `len` instance columns.
+    pub fn instance_group(mut self, name: &str, len: usize) -> Self {
+        let group: Vec<_> = (0..len).map(|_| self.cs.instance_column()).collect();
+        self.annotate(name, &group, true);
+        self.columns.instance.insert(name.to_string(), group);
+        self
+    }
+
+    /// Enables equality on every column of the last allocated group, in order.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no group has been allocated yet.
+    pub fn enable_equality(mut self) -> Self {
+        let group = self
+            .last_group
+            .as_ref()
+            .expect("enable_equality must follow the allocation of a group");
+        for column in group {
+            self.cs.enable_equality(*column);
+        }
+        self
+    }
+
+    /// Returns the allocated columns.
+    pub fn build(self) -> AllocatedColumns {
+        self.columns
+    }
+
+    /// Annotates the columns of a group and remembers them as the last allocated group.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a group with the same name has already been allocated.
+    fn annotate<C: ColumnType>(&mut self, name: &str, group: &[Column<C>], indexed: bool)
+    where
+        Column<C>: Into<Column<Any>>,
+    {
+        assert!(
+            !self.columns.contains(name),
+            "a group of columns named {name:?} has already been allocated"
+        );
+        for (i, column) in group.iter().enumerate() {
+            if indexed {
+                self.cs
+                    .annotate_lookup_any_column(*column, || format!("{name}[{i}]"));
+            } else {
+                self.cs.annotate_lookup_any_column(*column, || name);
+            }
+        }
+        self.last_group = Some(group.iter().map(|column| (*column).into()).collect());
+    }
+}
+
+/// The named groups of columns allocated by a [`ColumnsBuilder`].
+#[derive(Clone, Debug, Default)]
+pub struct AllocatedColumns {
+    advice: HashMap<String, Vec<Column<Advice>>>,
+    fixed: HashMap<String, Vec<Column<Fixed>>>,
+    instance: HashMap<String, Vec<Column<Instance>>>,
+}
+
+impl AllocatedColumns {
+    /// Returns the advice columns of the group `name`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no group of advice columns is named `name`.
+    pub fn advice(&self, name: &str) -> &[Column<Advice>] {
+        self.advice
+            .get(name)
+            .unwrap_or_else(|| panic!("no group of advice columns named {name:?}"))
+    }
+
+    /// Returns the fixed columns of the group `name`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no group of fixed columns is named `name`.
+    pub fn fixed(&self, name: &str) -> &[Column<Fixed>] {
+        self.fixed
+            .get(name)
+            .unwrap_or_else(|| panic!("no group of fixed columns named {name:?}"))
+    }
+
+    /// Returns the instance columns of the group `name`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no group of instance columns is named `name`.
+    pub fn instance(&self, name: &str) -> &[Column<Instance>] {
+        self.instance
+            .get(name)
+            .unwrap_or_else(|| panic!("no group of instance columns named {name:?}"))
+    }
+
+    fn contains(&self, name: &str) -> bool {
+        self.advice.contains_key(name)
+            || self.fixed.contains_key(name)
+            || self.instance.contains_key(name)
+    }
+}
+
 /// Exposes the "virtual cells" that can be queried while creating a custom gate or lookup
 /// table.
 #[derive(Debug)]
@@ -2698,6 +4193,7 @@ pub struct VirtualCells<'a, F: Field> {
     meta: &'a mut ConstraintSystem<F>,
     queried_selectors: Vec<Selector>,
     queried_cells: Vec<VirtualCell>,
+    queried_challenges: Vec<Challenge>,
 }
 
 impl<'a, F: Field> VirtualCells<'a, F> {
@@ -2706,6 +4202,7 @@ impl<'a, F: Field> VirtualCells<'a, F> {
             meta,
             queried_selectors: vec![],
             queried_cells: vec![],
+            queried_challenges: vec![],
         }
     }
 
@@ -2732,11 +4229,15 @@ impl<'a, F: Field> VirtualCells<'a, F> {
             index: Some(self.meta.query_advice_index(column, at)),
             column_index: column.index,
             rotation: at,
-            phase: sealed::Phase(column.column_type().phase),
+            phase: sealed::Phase(column.phase()),
         })
     }
 
     /// Query an instance column at a relative position
+    ///
+    /// Any rotation is supported, with the same semantics as for the other column types:
+    /// the rows after the public inputs are zero, and rotations wrap around the `n` rows
+    /// of the column.
     pub fn query_instance(&mut self, column: Column<Instance>, at: Rotation) -> Expression<F> {
         self.queried_cells.push((column, at).into());
         Expression::Instance(InstanceQuery {
@@ -2758,14 +4259,207 @@ impl<'a, F: Field> VirtualCells<'a, F> {
 
     /// Query a challenge
     pub fn query_challenge(&mut self, challenge: Challenge) -> Expression<F> {
+        self.queried_challenges.push(challenge);
         Expression::Challenge(challenge)
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::Expression;
+    use super::{
+        collect_queries, sealed, AdviceQuery, Challenge, Column, Constraint, ConstraintSystem,
+        ConstraintSystemV2Backend, Constraints, ConstraintsBuilder, Error, Expression, FirstPhase,
+        FixedQuery, InstanceQuery, PhaseId, PhaseMix, SecondPhase, ThirdPhase,
+    };
+    use crate::poly::Rotation;
+    use halo2_middleware::circuit::ExpressionMid;
+    use halo2_middleware::circuit::{Advice, Any, Fixed};
+    use halo2_middleware::metadata;
     use halo2curves::bn256::Fr;
+    use halo2curves::ff::Field;
+
+    /// Expressions without selectors, with every kind of node.
+    fn corpus() -> Vec<Expression<Fr>> {
+        let fixed = |column_index, rotation| {
+            Expression::Fixed(FixedQuery {
+                index: None,
+                column_index,
+                rotation: Rotation(rotation),
+            })
+        };
+        let advice = |column_index, rotation, phase| {
+            Expression::Advice(AdviceQuery {
+                index: None,
+                column_index,
+                rotation: Rotation(rotation),
+                phase: sealed::Phase(phase),
+            })
+        };
+        let instance = |column_index| {
+            Expression::Instance(InstanceQuery {
+                index: None,
+                column_index,
+                rotation: Rotation::cur(),
+            })
+        };
+        let challenge = Expression::Challenge(Challenge { index: 0, phase: 0 });
+        let constant = |value: u64| Expression::Constant(Fr::from(value));
+
+        vec![
+            constant(7),
+            fixed(0, 0),
+            advice(1, -1, 1),
+            instance(2),
+            challenge.clone(),
+            -advice(0, 1, 0),
+            fixed(1, 0) * (advice(0, 0, 0) - advice(0, 1, 0)),
+            (advice(0, 0, 0) + constant(3)).square() * Fr::from(5),
+            fixed(0, 0) * advice(0, 0, 0) * advice(1, 0, 1) - challenge * instance(0),
+            [advice(0, 0, 0), advice(1, 0, 1), fixed(0, 1)]
+                .into_iter()
+                .product::<Expression<Fr>>()
+                + [constant(1), instance(0)].into_iter().sum(),
+        ]
+    }
+
+    #[test]
+    fn expression_mid_matches_expression() {
+        let values = |expr: &Expression<Fr>| {
+            expr.evaluate(
+                &|scalar| scalar,
+                &|_| unreachable!(),
+                &|query| {
+                    Fr::from(10 + query.column_index as u64) + Fr::from(query.rotation.0 as u64)
+                },
+                &|query| {
+                    Fr::from(20 + query.column_index as u64) * Fr::from(query.phase.0 as u64 + 2)
+                },
+                &|query| Fr::from(30 + query.column_index as u64),
+                &|challenge| Fr::from(40 + challenge.index() as u64),
+                &|a| -a,
+                &|a, b| a + b,
+                &|a, b| a * b,
+                &|a, scalar| a * scalar,
+            )
+        };
+        let values_mid = |expr: &ExpressionMid<Fr>| {
+            expr.evaluate(
+                &|scalar| scalar,
+                &|query| {
+                    Fr::from(10 + query.column_index as u64) + Fr::from(query.rotation.0 as u64)
+                },
+                &|query| {
+                    Fr::from(20 + query.column_index as u64) * Fr::from(query.phase as u64 + 2)
+                },
+                &|query| Fr::from(30 + query.column_index as u64),
+                &|challenge| Fr::from(40 + challenge.index() as u64),
+                &|a| -a,
+                &|a, b| a + b,
+                &|a, b| a * b,
+                &|a, scalar| a * scalar,
+            )
+        };
+
+        for expr in corpus() {
+            let mid: ExpressionMid<Fr> = expr.clone().into();
+            assert_eq!(mid.degree(), expr.degree());
+            assert_eq!(mid.complexity(), expr.complexity());
+            assert_eq!(mid.identifier(), expr.identifier());
+            assert_eq!(values_mid(&mid), values(&expr));
+        }
+    }
+
+    #[test]
+    fn expression_mid_operators_match_expression() {
+        let corpus = corpus();
+        let mid = |expr: &Expression<Fr>| ExpressionMid::<Fr>::from(expr.clone());
+        for (a, b) in corpus.iter().zip(corpus.iter().rev()) {
+            assert_eq!(-mid(a), mid(&-a.clone()));
+            assert_eq!(mid(a) + mid(b), mid(&(a.clone() + b.clone())));
+            assert_eq!(mid(a) - mid(b), mid(&(a.clone() - b.clone())));
+            assert_eq!(mid(a) * mid(b), mid(&(a.clone() * b.clone())));
+            assert_eq!(mid(a) * Fr::from(3), mid(&(a.clone() * Fr::from(3))));
+        }
+        assert_eq!(
+            corpus.iter().map(mid).sum::<ExpressionMid<Fr>>(),
+            mid(&corpus.iter().cloned().sum()),
+        );
+        assert_eq!(
+            corpus.iter().map(mid).product::<ExpressionMid<Fr>>(),
+            mid(&corpus.iter().cloned().product()),
+        );
+        assert_eq!(
+            std::iter::empty::<ExpressionMid<Fr>>().product::<ExpressionMid<Fr>>(),
+            ExpressionMid::Constant(Fr::ONE)
+        );
+    }
+
+    #[test]
+    fn advice_column_phase() {
+        let mut cs = ConstraintSystem::<Fr>::default();
+        let first = cs.advice_column();
+        let second = cs.advice_column_in(SecondPhase);
+        assert_eq!((first.phase(), second.phase()), (0, 1));
+        assert!(first.in_phase(FirstPhase) && !first.in_phase(SecondPhase));
+        assert!(second.in_phase(SecondPhase) && second.in_phase(PhaseId::SECOND));
+
+        assert_eq!(Column::<Advice>::try_new_in_phase(1, 1), Ok(second));
+        assert!(Column::<Advice>::try_new_in_phase(0, PhaseId::MAX.index() + 1).is_err());
+
+        // Like the queries, advice columns only show their phase if it's not the first.
+        assert_eq!(first.to_string(), "Column('Advice', 0)");
+        assert_eq!(second.to_string(), "Column('Advice { phase: 1 }', 1)");
+        assert_eq!(
+            format!("{second:?}"),
+            "Column { index: 1, column_type: Advice { phase: 1 } }"
+        );
+        assert_eq!(Column::new(2, Fixed).to_string(), "Column('Fixed', 2)");
+    }
+
+    #[test]
+    fn queries_are_deduplicated_and_forced_by_the_permutation() {
+        let mut cs = ConstraintSystem::<Fr>::default();
+        let a = cs.advice_column();
+        let b = cs.advice_column();
+        let i = cs.instance_column();
+        let f = cs.fixed_column();
+        cs.create_gate("a", |meta| {
+            let a_cur = meta.query_advice(a, Rotation::cur());
+            let a_next = meta.query_advice(a, Rotation::next());
+            let f = meta.query_fixed(f, Rotation::cur());
+            vec![f * (a_next - a_cur)]
+        });
+        cs.lookup_any("a in i", |meta| {
+            let a = meta.query_advice(a, Rotation::cur());
+            let i = meta.query_instance(i, Rotation::cur());
+            vec![(a, i)]
+        });
+        // `a` is already queried at the current row, `b` isn't queried by any gate.
+        cs.enable_equality(a);
+        cs.enable_equality(b);
+
+        let queries = cs.queries();
+        assert_eq!(
+            queries.advice,
+            [
+                (a, Rotation::cur()),
+                (a, Rotation::next()),
+                (b, Rotation::cur())
+            ]
+        );
+        assert_eq!(queries.instance, [(i, Rotation::cur())]);
+        assert_eq!(queries.fixed, [(f, Rotation::cur())]);
+        assert_eq!(queries.num_advice_queries, [2, 1]);
+        assert_eq!(queries.len_by_kind(), (3, 1, 1));
+
+        // The backend finds the same queries, in the same order here.
+        let cs2: ConstraintSystemV2Backend<Fr> = cs.into();
+        let (collected, _, _, _) = collect_queries(&cs2);
+        assert_eq!(collected.advice, queries.advice);
+        assert_eq!(collected.instance, queries.instance);
+        assert_eq!(collected.fixed, queries.fixed);
+        assert_eq!(collected.num_advice_queries, queries.num_advice_queries);
+    }
 
     #[test]
     fn iter_sum() {
@@ -2804,4 +4498,701 @@ mod tests {
 
         assert_eq!(happened, expected);
     }
+
+    /// Returns a constraint system of degree 4 with a fixed column, three simple selectors
+    /// used in gates of degree 2, 3 and 4, and a complex selector, and the activations of the
+    /// selectors on 8 rows.
+    fn selector_cs() -> (ConstraintSystem<Fr>, Vec<Vec<bool>>) {
+        let mut cs = ConstraintSystem::<Fr>::default();
+        let a = cs.advice_column();
+        cs.fixed_column();
+        let simple = [(); 3].map(|_| cs.selector());
+        let complex = cs.complex_selector();
+        for (degree, selector) in simple.iter().enumerate() {
+            cs.create_gate("simple", |meta| {
+                let q = meta.query_selector(*selector);
+                let a = meta.query_advice(a, Rotation::cur());
+                vec![(0..degree).fold(q * a.clone(), |expr, _| expr * a.clone())]
+            });
+        }
+        cs.create_gate("complex", |meta| {
+            let q = meta.query_selector(complex);
+            vec![q * meta.query_advice(a, Rotation::cur())]
+        });
+        let activations = [vec![0, 4], vec![1], vec![2, 5], vec![0, 3]]
+            .into_iter()
+            .map(|rows| (0..8).map(|row| rows.contains(&row)).collect())
+            .collect();
+        (cs, activations)
+    }
+
+    #[test]
+    fn selector_compression_report_matches_polynomials() {
+        let (cs, activations) = selector_cs();
+        let (cs, polys, report) = cs.compress_selectors_with_report(activations.clone());
+
+        assert_eq!(report.max_degree, 4);
+        assert_eq!(report.selectors.len(), 4);
+        assert_eq!(report.columns.len(), polys.len());
+        assert_eq!(
+            report
+                .selectors
+                .iter()
+                .map(|selector| (
+                    selector.selector,
+                    selector.gates.clone(),
+                    selector.max_degree
+                ))
+                .collect::<Vec<_>>(),
+            [
+                (0, vec![0], 2),
+                (1, vec![1], 3),
+                (2, vec![2], 4),
+                (3, vec![3], 0),
+            ]
+        );
+        assert_eq!(
+            cs.selector_map,
+            report
+                .selectors
+                .iter()
+                .map(|selector| selector.column)
+                .collect::<Vec<_>>()
+        );
+
+        // The new fixed columns follow the existing one, in the order of the polynomials.
+        for (index, (combination, poly)) in report.columns.iter().zip(polys.iter()).enumerate() {
+            assert_eq!(combination.column, Column::new(index + 1, Fixed));
+            for row in 0..8 {
+                let active = combination
+                    .selectors
+                    .iter()
+                    .map(|selector| &report.selectors[*selector])
+                    .find(|selector| activations[selector.selector][row]);
+                let expected = active.map_or(0, |selector| selector.value);
+                assert_eq!(poly[row], Fr::from(expected as u64));
+            }
+        }
+        for selector in report.selectors.iter() {
+            let combination = report
+                .columns
+                .iter()
+                .find(|combination| combination.column == selector.column)
+                .unwrap();
+            assert!(combination.selectors.contains(&selector.selector));
+        }
+
+        // The complex selector is not combined with any other one.
+        let complex = &report.selectors[3];
+        assert_eq!(complex.value, 1);
+        assert!(report
+            .columns
+            .iter()
+            .any(|combination| combination.selectors == [3]));
+    }
+
+    #[test]
+    fn uncompressed_selectors_get_their_own_column() {
+        // Three selectors in gates of degree 2 and one in a gate of degree 4. Without the
+        // opt-out, the first three would be combined, which would raise the degree of their
+        // gates to 4.
+        let configure = |uncompressed: bool| {
+            let mut cs = ConstraintSystem::<Fr>::default();
+            let a = cs.advice_column();
+            let first = if uncompressed {
+                cs.selector_uncompressed()
+            } else {
+                cs.selector()
+            };
+            let selectors = [first, cs.selector(), cs.selector(), cs.selector()];
+            for (index, selector) in selectors.iter().enumerate() {
+                cs.create_gate("gate", |meta| {
+                    let q = meta.query_selector(*selector);
+                    let a = meta.query_advice(a, Rotation::cur());
+                    let extra = if index == 3 { 2 } else { 0 };
+                    vec![(0..extra).fold(q * a.clone(), |expr, _| expr * a.clone())]
+                });
+            }
+            let activations = (0..4)
+                .map(|selector| (0..8).map(|row| row % 4 == selector).collect())
+                .collect();
+            cs.compress_selectors_with_report(activations)
+        };
+
+        let (cs, _, report) = configure(false);
+        assert_eq!(cs.degree(), 4);
+        assert_eq!(cs.gates[0].polynomials()[0].degree(), 4);
+        assert_eq!(report.columns[0].selectors, [0, 1, 2]);
+
+        let (cs, polys, report) = configure(true);
+        assert_eq!(cs.uncompressed_selectors, [0]);
+        assert_eq!(cs.degree(), 4);
+        assert_eq!(
+            cs.gates
+                .iter()
+                .map(|gate| gate.polynomials()[0].degree())
+                .collect::<Vec<_>>(),
+            [2, 3, 3, 4]
+        );
+        let column = report.selectors[0].column;
+        let combination = report
+            .columns
+            .iter()
+            .position(|combination| combination.column == column)
+            .unwrap();
+        assert_eq!(report.columns[combination].selectors, [0]);
+        assert_eq!(report.selectors[0].value, 1);
+        assert_eq!(report.selectors[0].max_degree, 2);
+        assert_eq!(
+            polys[combination],
+            (0..8)
+                .map(|row| if row % 4 == 0 { Fr::ONE } else { Fr::ZERO })
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(report.columns.len(), 3);
+    }
+
+    #[test]
+    fn selector_usage() {
+        let mut cs = ConstraintSystem::<Fr>::default();
+        let a = cs.advice_column();
+        let shared = cs.selector();
+        let complex = cs.complex_selector();
+        let unused = cs.selector();
+        let lookup_only = cs.complex_selector();
+        for _ in 0..2 {
+            cs.create_gate("shared", |meta| {
+                let q = meta.query_selector(shared);
+                vec![q * meta.query_advice(a, Rotation::cur())]
+            });
+        }
+        // The complex selector is used without `query_selector`.
+        cs.create_gate("complex", |meta| {
+            vec![complex.expr() * meta.query_advice(a, Rotation::cur())]
+        });
+        cs.lookup_any("lookup", |meta| {
+            let a = meta.query_advice(a, Rotation::cur());
+            let q = meta.query_selector(lookup_only);
+            vec![(q * a.clone(), a)]
+        });
+        cs.shuffle("shuffle", |meta| {
+            let a = meta.query_advice(a, Rotation::cur());
+            vec![(complex.expr() * a.clone(), a)]
+        });
+
+        assert_eq!(cs.gates_using_selector(shared), [0, 1]);
+        assert_eq!(cs.gates_using_selector(complex), [2]);
+        assert!(cs.gates_using_selector(unused).is_empty());
+        assert_eq!(cs.selector_usage(), [vec![0, 1], vec![2], vec![], vec![]]);
+
+        assert_eq!(cs.lookups_using_selector(lookup_only), [0]);
+        assert!(cs.lookups_using_selector(complex).is_empty());
+        assert_eq!(cs.shuffles_using_selector(complex), [0]);
+        assert!(cs.shuffles_using_selector(lookup_only).is_empty());
+    }
+
+    #[test]
+    fn partial_selector_replacement() {
+        let mut cs = ConstraintSystem::<Fr>::default();
+        let a = cs.advice_column();
+        let f = cs.fixed_column();
+        let q = cs.selector();
+        let r = cs.selector();
+        let complex = cs.complex_selector();
+        cs.create_gate("q", |meta| {
+            let q = meta.query_selector(q);
+            vec![q * meta.query_advice(a, Rotation::cur())]
+        });
+        cs.create_gate("r", |meta| {
+            let r = meta.query_selector(r);
+            let a = meta.query_advice(a, Rotation::cur());
+            vec![r * a.clone() * a]
+        });
+        cs.lookup_any("lookup", |meta| {
+            let a = meta.query_advice(a, Rotation::cur());
+            let complex = meta.query_selector(complex);
+            vec![(complex * a.clone(), a)]
+        });
+
+        // An index that is not a selector is rejected without touching the gates.
+        let invalid = [(3, f.cur())].into_iter().collect();
+        assert!(cs.replace_selectors(&invalid).is_err());
+        assert_eq!(cs.gates_using_selector(q), [0]);
+        assert!(cs.fixed_queries.is_empty());
+
+        let partial = [(q.0, f.cur())].into_iter().collect();
+        let degree = cs.replace_selectors(&partial).unwrap();
+        assert_eq!(degree, cs.degree());
+        assert!(cs.gates_using_selector(q).is_empty());
+        assert_eq!(cs.gates_using_selector(r), [1]);
+        assert_eq!(cs.lookups_using_selector(complex), [0]);
+        assert_eq!(cs.num_selectors, 3);
+        assert_eq!(
+            cs.fixed_queries,
+            [(Column::new(f.index, Fixed), Rotation::cur())]
+        );
+        match &cs.gates[0].polynomials()[0] {
+            Expression::Product(replaced, _) => assert_eq!(
+                **replaced,
+                Expression::Fixed(FixedQuery {
+                    index: Some(0),
+                    column_index: f.index,
+                    rotation: Rotation::cur(),
+                })
+            ),
+            expr => panic!("unexpected gate polynomial {expr:?}"),
+        }
+
+        // Replacing q again is a no-op, but completes the mapping.
+        let all = [(q.0, f.cur()), (r.0, f.cur()), (complex.0, f.cur())]
+            .into_iter()
+            .collect();
+        let degree = cs.replace_selectors(&all).unwrap();
+        assert_eq!(degree, cs.degree());
+        assert!(cs.lookups_using_selector(complex).is_empty());
+        assert_eq!(cs.num_selectors, 0);
+    }
+
+    #[test]
+    fn direct_selector_conversion_populates_selector_map() {
+        let (cs, activations) = selector_cs();
+        let (cs, polys) = cs.directly_convert_selectors_to_fixed(activations);
+        assert_eq!(polys.len(), 4);
+        assert_eq!(
+            cs.selector_map,
+            (1..5)
+                .map(|index| Column::new(index, Fixed))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn permutation_chunks() {
+        let mut cs = ConstraintSystem::<Fr>::default();
+        let columns: Vec<_> = (0..7).map(|_| cs.advice_column()).collect();
+        for column in &columns {
+            cs.enable_equality(*column);
+        }
+        let sizes = |cs: &ConstraintSystem<Fr>| {
+            cs.permutation_chunks()
+                .iter()
+                .map(Vec::len)
+                .collect::<Vec<_>>()
+        };
+
+        // The permutation argument alone requires degree 3, with one column per chunk.
+        assert_eq!(cs.degree(), 3);
+        assert_eq!(sizes(&cs), [1; 7]);
+
+        cs.set_minimum_degree(5).unwrap();
+        assert_eq!(sizes(&cs), [3, 3, 1]);
+        assert_eq!(
+            cs.permutation_chunks()[2],
+            [Column::<Any>::from(columns[6])]
+        );
+        assert!(cs.set_minimum_degree(2).is_err());
+
+        cs.create_gate("quartic", |meta| {
+            let [a, b, c, d] = [0, 1, 2, 3].map(|i| meta.query_advice(columns[i], Rotation::cur()));
+            vec![a * b * c * d]
+        });
+        assert_eq!(cs.degree(), 5);
+        cs.set_minimum_degree(6).unwrap();
+        assert_eq!(sizes(&cs), [4, 3]);
+        assert!(cs.set_minimum_degree(3).is_err());
+        assert_eq!(cs.degree(), 6);
+    }
+
+    #[test]
+    fn max_degree_accepts_gates_within_budget() {
+        let mut cs = ConstraintSystem::<Fr>::default();
+        let columns: Vec<_> = (0..5).map(|_| cs.advice_column()).collect();
+        cs.set_max_degree(4).unwrap();
+
+        cs.create_gate("quartic", |meta| {
+            let [a, b, c, d] = [0, 1, 2, 3].map(|i| meta.query_advice(columns[i], Rotation::cur()));
+            vec![("abcd", a * b * c * d)]
+        });
+        assert_eq!(cs.degree(), 4);
+        assert!(cs.set_minimum_degree(5).is_err());
+        assert!(cs.set_max_degree(3).is_err());
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "constraint \"abcde\" of gate \"quintic\" has degree 5, which exceeds the maximum degree 4"
+    )]
+    fn max_degree_rejects_gates_over_budget() {
+        let mut cs = ConstraintSystem::<Fr>::default();
+        let columns: Vec<_> = (0..5).map(|_| cs.advice_column()).collect();
+        cs.set_max_degree(4).unwrap();
+
+        cs.create_gate("quintic", |meta| {
+            let [a, b, c, d, e] =
+                [0, 1, 2, 3, 4].map(|i| meta.query_advice(columns[i], Rotation::cur()));
+            vec![
+                ("abcd", a.clone() * b.clone() * c.clone() * d.clone()),
+                ("abcde", a * b * c * d * e),
+            ]
+        });
+    }
+
+    #[test]
+    #[should_panic(expected = "lookup \"square\" has degree 5, which exceeds the maximum degree 4")]
+    fn max_degree_rejects_lookups_over_budget() {
+        let mut cs = ConstraintSystem::<Fr>::default();
+        let a = cs.advice_column();
+        let table = cs.lookup_table_column();
+        cs.set_max_degree(4).unwrap();
+
+        // A lookup requires degree 2 plus the degrees of its input and table expressions.
+        cs.lookup("square", |meta| {
+            let a = meta.query_advice(a, Rotation::cur());
+            vec![(a.clone() * a, table)]
+        });
+    }
+
+    #[test]
+    fn strict_phases_report_gates_mixing_phases() {
+        let build = |strict: bool| {
+            let mut cs = ConstraintSystem::<Fr>::default();
+            cs.set_strict_phases(strict);
+            let a = cs.advice_column_in(FirstPhase);
+            let b = cs.advice_column_in(SecondPhase);
+            let r = cs.challenge_usable_after(FirstPhase);
+            let s = cs.selector();
+            // Only queries second-phase columns, so it's not reported.
+            cs.create_gate("second", |meta| {
+                let s = meta.query_selector(s);
+                let b_next = meta.query_advice(b, Rotation::next());
+                let b = meta.query_advice(b, Rotation::cur());
+                let r = meta.query_challenge(r);
+                vec![s * (b_next - b * r)]
+            });
+            // Meant to be a first-phase gate, but queries `b` by mistake.
+            cs.create_gate("first", |meta| {
+                let s = meta.query_selector(s);
+                let a_next = meta.query_advice(a, Rotation::next());
+                let a = meta.query_advice(a, Rotation::cur());
+                vec![s * (a_next - a) * b.cur()]
+            });
+            cs
+        };
+
+        // Nothing is recorded by default.
+        let cs = build(false);
+        assert!(cs.phase_mixes.is_empty());
+        assert!(cs.validate().is_ok());
+
+        let mut cs = build(true);
+        let mix = PhaseMix {
+            gate: "first".to_string(),
+            advice_phases: vec![0, 1],
+            challenge_phases: vec![],
+        };
+        assert_eq!(cs.phase_mixes, [mix.clone()]);
+        match cs.validate() {
+            Err(Error::UnintendedPhaseMix(mixes)) => assert_eq!(mixes, [mix]),
+            result => panic!("unexpected result: {result:?}"),
+        }
+
+        cs.allow_phase_mix("first");
+        assert_eq!(cs.unintended_phase_mixes().count(), 0);
+        assert!(cs.validate().is_ok());
+    }
+
+    #[test]
+    fn runtime_phases() {
+        assert_eq!(
+            PhaseId::all().collect::<Vec<_>>(),
+            [PhaseId::FIRST, PhaseId::SECOND, PhaseId::THIRD]
+        );
+        assert_eq!(PhaseId::try_from(2), Ok(PhaseId::MAX));
+        assert!(PhaseId::try_from(3).is_err());
+        assert_eq!(PhaseId::from(SecondPhase), PhaseId::SECOND);
+
+        let mut cs = ConstraintSystem::<Fr>::default();
+        let mut challenges = Vec::new();
+        for index in 0..=PhaseId::MAX.index() {
+            let phase = PhaseId::try_from(index).unwrap();
+            cs.advice_column_in(phase);
+            cs.unblinded_advice_column_in(phase);
+            if phase < PhaseId::MAX {
+                challenges.push(cs.challenge_usable_after(phase));
+            }
+        }
+        assert_eq!(cs.advice_column_phase(), [0, 0, 1, 1, 2, 2]);
+        assert_eq!(cs.unblinded_advice_columns, [1, 3, 5]);
+        assert_eq!(cs.challenge_phase(), [0, 1]);
+        assert_eq!(cs.phases().count(), 3);
+
+        // The marker types allocate in the same phases.
+        let mut markers = ConstraintSystem::<Fr>::default();
+        markers.advice_column_in(FirstPhase);
+        markers.unblinded_advice_column_in(FirstPhase);
+        markers.challenge_usable_after(FirstPhase);
+        markers.advice_column_in(SecondPhase);
+        markers.unblinded_advice_column_in(SecondPhase);
+        markers.challenge_usable_after(SecondPhase);
+        markers.advice_column_in(ThirdPhase);
+        markers.unblinded_advice_column_in(ThirdPhase);
+        assert_eq!(markers.advice_column_phase(), cs.advice_column_phase());
+        assert_eq!(markers.challenges(), challenges);
+        assert_eq!(sealed::Phase::from(PhaseId::THIRD), sealed::Phase(2));
+    }
+
+    #[test]
+    fn named_challenges() {
+        let mut cs = ConstraintSystem::<Fr>::default();
+        cs.advice_column();
+        cs.advice_column_in(SecondPhase);
+        let theta = cs.challenge_usable_after_named(FirstPhase, "theta");
+        let unnamed = cs.challenge_usable_after(FirstPhase);
+        let gamma = cs.challenge_usable_after_named(SecondPhase, "gamma");
+
+        assert_eq!(cs.challenges(), [theta, unnamed, gamma]);
+        assert_eq!((gamma.index(), gamma.phase()), (2, 1));
+        assert_eq!(cs.challenge_annotation(theta), Some("theta"));
+        assert_eq!(cs.challenge_annotation(unnamed), None);
+        assert_eq!(cs.challenge_annotation(gamma), Some("gamma"));
+
+        let expr = theta.expr() * unnamed.expr() + gamma.expr();
+        assert_eq!(
+            cs.display_expression(&expr).to_string(),
+            "((challenge[theta]*challenge[1])+challenge[gamma])"
+        );
+        assert_eq!(
+            format!("{:?}", cs.display_expression(&expr)),
+            cs.display_expression(&expr).to_string()
+        );
+        // The identifier doesn't depend on the names.
+        assert_eq!(
+            expr.identifier(),
+            "((challenge[0]*challenge[1])+challenge[2])"
+        );
+    }
+
+    #[test]
+    fn dump_gates_annotates_degree_and_cost() {
+        let mut cs = ConstraintSystem::<Fr>::default();
+        let s = cs.selector();
+        let a = cs.advice_column();
+        let b = cs.advice_column();
+        let c = cs.advice_column();
+        cs.annotate_lookup_any_column(a, || "a");
+        cs.annotate_lookup_any_column(b, || "b");
+        cs.annotate_lookup_any_column(c, || "c");
+        cs.create_gate("cube", |meta| {
+            let s = meta.query_selector(s);
+            let a = meta.query_advice(a, Rotation::cur());
+            let b = meta.query_advice(b, Rotation::cur());
+            let c = meta.query_advice(c, Rotation::next());
+            // `(a + b)^3` written with clones, the degree blowup the dump should reveal.
+            let sum = a + b;
+            vec![("cube", s * (sum.clone() * sum.clone() * sum - c))]
+        });
+
+        let expected = [
+            "gate[0] \"cube\"",
+            "  constraint[0] \"cube\"",
+            "    Product [degree 4, complexity 163, multiplications 3]",
+            "      Selector selector[0] (simple) [degree 1, complexity 1, multiplications 0]",
+            "      Sum [degree 3, complexity 132, multiplications 2]",
+            "        Product [degree 3, complexity 111, multiplications 2]",
+            "          Product [degree 2, complexity 64, multiplications 1]",
+            "            Sum [degree 1, complexity 17, multiplications 0]",
+            "              Advice advice[0] (a) rotation 0 phase 0 [degree 1, complexity 1, multiplications 0]",
+            "              Advice advice[1] (b) rotation 0 phase 0 [degree 1, complexity 1, multiplications 0]",
+            "            Sum [degree 1, complexity 17, multiplications 0]",
+            "              Advice advice[0] (a) rotation 0 phase 0 [degree 1, complexity 1, multiplications 0]",
+            "              Advice advice[1] (b) rotation 0 phase 0 [degree 1, complexity 1, multiplications 0]",
+            "          Sum [degree 1, complexity 17, multiplications 0]",
+            "            Advice advice[0] (a) rotation 0 phase 0 [degree 1, complexity 1, multiplications 0]",
+            "            Advice advice[1] (b) rotation 0 phase 0 [degree 1, complexity 1, multiplications 0]",
+            "        Negated [degree 1, complexity 6, multiplications 0]",
+            "          Advice advice[2] (c) rotation 1 phase 0 [degree 1, complexity 1, multiplications 0]",
+        ]
+        .join("\n");
+        assert_eq!(cs.dump_gates(), expected);
+
+        // Without a constraint system the columns are not named.
+        let tree = cs.gates()[0].polynomials()[0].annotated_tree(None);
+        assert_eq!(
+            tree.lines().last().unwrap(),
+            "      Advice advice[2] rotation 1 phase 0 [degree 1, complexity 1, multiplications 0]"
+        );
+    }
+
+    #[test]
+    fn constraints_with_per_constraint_selectors() {
+        let mut cs = ConstraintSystem::<Fr>::default();
+        let a = cs.advice_column();
+        let q_main = cs.selector();
+        let q_last = cs.complex_selector();
+
+        let gate = |cs: &mut ConstraintSystem<Fr>, name: &str, manual: bool| {
+            cs.create_gate(name, |meta| {
+                let a_next = meta.query_advice(a, Rotation::next());
+                let a = meta.query_advice(a, Rotation::cur());
+                let q_main = meta.query_selector(q_main);
+                let q_last = meta.query_selector(q_last);
+                let boolean = a.clone() * (Expression::Constant(Fr::ONE) - a.clone());
+                let step = a_next - a.clone();
+                if manual {
+                    vec![
+                        Constraint::from(("boolean", q_main.clone() * boolean)),
+                        ("step", q_main.clone() * step).into(),
+                        ("last", q_main * q_last * a).into(),
+                    ]
+                } else {
+                    ConstraintsBuilder::new(q_main)
+                        .add("boolean", boolean)
+                        .add("step", step)
+                        .add_gated("last", q_last, a)
+                        .into_iter()
+                        .collect()
+                }
+            })
+        };
+        gate(&mut cs, "manual", true);
+        gate(&mut cs, "builder", false);
+        cs.create_gate("with_selectors", |meta| {
+            let a_next = meta.query_advice(a, Rotation::next());
+            let a = meta.query_advice(a, Rotation::cur());
+            let q_main = meta.query_selector(q_main);
+            let q_last = meta.query_selector(q_last);
+            Constraints::with_selectors(
+                [q_main.clone(), q_main.clone(), q_main * q_last],
+                [
+                    (
+                        "boolean",
+                        a.clone() * (Expression::Constant(Fr::ONE) - a.clone()),
+                    ),
+                    ("step", a_next - a.clone()),
+                    ("last", a),
+                ],
+            )
+        });
+
+        let gates = cs.gates();
+        for gate in &gates[1..] {
+            assert_eq!(gate.polynomials(), gates[0].polynomials());
+            assert_eq!(gate.constraint_names, gates[0].constraint_names);
+            assert_eq!(gate.queried_selectors(), gates[0].queried_selectors());
+        }
+        assert_eq!(gates[0].polynomials()[2].degree(), 3);
+    }
+
+    #[test]
+    #[should_panic(expected = "requires one selector per constraint")]
+    fn constraints_with_selectors_checks_lengths() {
+        let mut cs = ConstraintSystem::<Fr>::default();
+        let a = cs.advice_column();
+        let s = cs.selector();
+        cs.create_gate("mismatch", |meta| {
+            let a = meta.query_advice(a, Rotation::cur());
+            let s = meta.query_selector(s);
+            Constraints::with_selectors([s], [a.clone(), a])
+        });
+    }
+
+    #[test]
+    #[should_panic(expected = "attempted to multiply two expressions containing simple selectors")]
+    fn constraints_builder_rejects_two_simple_selectors() {
+        let mut cs = ConstraintSystem::<Fr>::default();
+        let a = cs.advice_column();
+        let q_main = cs.selector();
+        let q_last = cs.selector();
+        cs.create_gate("two simple selectors", |meta| {
+            let a = meta.query_advice(a, Rotation::cur());
+            let q_main = meta.query_selector(q_main);
+            let q_last = meta.query_selector(q_last);
+            ConstraintsBuilder::new(q_main).add_gated("last", q_last, a)
+        });
+    }
+
+    #[test]
+    fn create_gate_optional_skips_empty_gates() {
+        let mut cs = ConstraintSystem::<Fr>::default();
+        let a = cs.advice_column();
+        let s = cs.selector();
+
+        let skipped = cs.create_gate_optional("disabled", |meta| {
+            let _ = meta.query_advice(a, Rotation::next());
+            Vec::<Expression<Fr>>::new()
+        });
+        assert_eq!(skipped, None);
+        assert!(cs.gates().is_empty());
+        assert!(cs.advice_queries().is_empty());
+
+        let added = cs.create_gate_optional("enabled", |meta| {
+            let s = meta.query_selector(s);
+            let a = meta.query_advice(a, Rotation::cur());
+            vec![s * a]
+        });
+        assert_eq!(added, Some(0));
+        assert_eq!(cs.gates().len(), 1);
+        assert_eq!(cs.gates()[0].name(), "enabled");
+        assert_eq!(cs.advice_queries(), &vec![(a, Rotation::cur())]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Gates must contain at least one constraint.")]
+    fn create_gate_rejects_empty_gates() {
+        let mut cs = ConstraintSystem::<Fr>::default();
+        cs.create_gate("empty", |_| Vec::<Expression<Fr>>::new());
+    }
+
+    #[test]
+    fn columns_builder_matches_manual_allocation() {
+        let mut manual = ConstraintSystem::<Fr>::default();
+        let state: Vec<_> = (0..3).map(|_| manual.advice_column()).collect();
+        for column in &state {
+            manual.enable_equality(*column);
+        }
+        let round_constants: Vec<_> = (0..2).map(|_| manual.fixed_column()).collect();
+        let rlc = manual.advice_column_in(SecondPhase);
+        let instance = manual.instance_column();
+        manual.enable_equality(instance);
+
+        let mut cs = ConstraintSystem::<Fr>::default();
+        let columns = cs
+            .columns()
+            .advice_group("state", 3)
+            .enable_equality()
+            .fixed_group("round_constants", 2)
+            .advice_in(SecondPhase, "rlc")
+            .instance_group("instance", 1)
+            .enable_equality()
+            .build();
+
+        assert_eq!(columns.advice("state"), state.as_slice());
+        assert_eq!(columns.fixed("round_constants"), round_constants.as_slice());
+        assert_eq!(columns.advice("rlc"), &[rlc]);
+        assert_eq!(columns.instance("instance"), &[instance]);
+        assert_eq!(
+            format!("{:?}", cs.pinned()),
+            format!("{:?}", manual.pinned())
+        );
+
+        let annotation = |column_type, index| {
+            cs.general_column_annotations()
+                .get(&metadata::Column::from((column_type, index)))
+                .map(String::as_str)
+        };
+        assert_eq!(annotation(Any::advice(), 0), Some("state[0]"));
+        assert_eq!(annotation(Any::advice(), 2), Some("state[2]"));
+        assert_eq!(annotation(Any::Fixed, 1), Some("round_constants[1]"));
+        assert_eq!(annotation(Any::Advice(Advice::new(1)), 3), Some("rlc"));
+        assert_eq!(annotation(Any::Instance, 0), Some("instance[0]"));
+    }
+
+    #[test]
+    #[should_panic(expected = "a group of columns named \"state\" has already been allocated")]
+    fn columns_builder_rejects_duplicate_names() {
+        let mut cs = ConstraintSystem::<Fr>::default();
+        cs.columns()
+            .advice_group("state", 2)
+            .fixed_group("state", 1);
+    }
 }
@@ -27,6 +27,9 @@ pub struct SelectorAssignment<F> {
     /// The combination this selector was assigned to
     pub combination_index: usize,
 
+    /// The value of the combination on the rows where this selector is active
+    pub combination_value: usize,
+
     /// The expression we wish to substitute with
     pub expression: Expression<F>,
 }
@@ -47,7 +50,25 @@ pub struct SelectorAssignment<F> {
 /// `SelectorAssignment` that the caller can use to perform the necessary
 /// substitutions to the constraint system.
 ///
-/// This function is completely deterministic.
+/// This function is completely deterministic, and its output only depends on
+/// the activations and degrees of the selectors and on `max_degree`:
+/// 1. The selectors are processed in the order of their index, whatever the
+///    order of `selectors`.
+/// 2. Each selector of degree zero (complex selectors, unused selectors and
+///    uncompressed ones) gets its own combination, in index order, before any
+///    other combination.
+/// 3. The remaining selectors are combined greedily: the unassigned selector
+///    with the lowest index starts a new combination, and every later
+///    unassigned selector is then considered in index order, and joins the
+///    combination if it is never active on the same row as a selector of the
+///    combination and the degree bound still holds. Ties are thus always
+///    broken in favor of the lowest index.
+/// 4. Within a combination, the selectors are assigned the values `1, 2, ...`
+///    in index order.
+///
+/// Adding a selector or a gate therefore only changes the combinations of the
+/// existing selectors if it changes `max_degree`, the degree of one of them,
+/// or if the new selector has a lower index than some of them.
 pub fn process<F: Field, E>(
     mut selectors: Vec<SelectorDescription>,
     max_degree: usize,
@@ -61,6 +82,8 @@ where
         return (vec![], vec![]);
     }
 
+    selectors.sort_by_key(|selector| selector.selector);
+
     // The length of all provided selectors must be the same.
     let n = selectors[0].activations.len();
     assert!(selectors.iter().all(|a| a.activations.len() == n));
@@ -86,6 +109,7 @@ where
             selector_assignments.push(SelectorAssignment {
                 selector: selector.selector,
                 combination_index,
+                combination_value: 1,
                 expression,
             });
 
@@ -183,6 +207,7 @@ where
         let query = allocate_fixed_column();
 
         let mut assigned_root = F::ONE;
+        let mut combination_value = 0;
         selector_assignments.extend(combination.into_iter().map(|selector| {
             // Compute the expression for substitution. This produces an expression of the
             // form
@@ -213,10 +238,12 @@ where
             }
 
             assigned_root += F::ONE;
+            combination_value += 1;
 
             SelectorAssignment {
                 selector: selector.selector,
                 combination_index,
+                combination_value,
                 expression,
             }
         }));
@@ -335,6 +362,7 @@ mod tests {
 
                     if activation {
                         assert!(!eval.is_zero_vartime());
+                        assert_eq!(assignment, Fp::from(selector.combination_value as u64));
                     } else {
                         assert!(eval.is_zero_vartime());
                     }
@@ -350,4 +378,54 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn selector_combination_is_pinned() {
+        let selector = |selector: usize, max_degree: usize, rows: [bool; 4]| SelectorDescription {
+            selector,
+            activations: rows.to_vec(),
+            max_degree,
+        };
+        let (t, f) = (true, false);
+        // Shuffled, to check that the selectors are processed in index order.
+        let selectors = vec![
+            selector(5, 4, [f, f, f, t]),
+            selector(0, 2, [t, f, f, f]),
+            selector(1, 0, [t, t, f, f]),
+            selector(2, 3, [f, t, f, f]),
+            selector(3, 2, [t, f, f, f]),
+            selector(4, 2, [f, f, t, f]),
+        ];
+        let mut query = 0;
+        let (combination_assignments, selector_assignments) =
+            process::<Fp, _>(selectors, 4, || {
+                let tmp = Expression::Fixed(FixedQuery {
+                    index: Some(query),
+                    column_index: query,
+                    rotation: Rotation::cur(),
+                });
+                query += 1;
+                tmp
+            });
+
+        assert_eq!(
+            selector_assignments
+                .iter()
+                .map(|a| (a.selector, a.combination_index, a.combination_value))
+                .collect::<Vec<_>>(),
+            [
+                (1, 0, 1),
+                (0, 1, 1),
+                (2, 1, 2),
+                (3, 2, 1),
+                (4, 2, 2),
+                (5, 3, 1)
+            ]
+        );
+        assert_eq!(
+            combination_assignments,
+            [[1, 1, 0, 0], [1, 2, 0, 0], [1, 0, 2, 0], [0, 0, 0, 1]]
+                .map(|column| column.map(Fp::from).to_vec())
+        );
+    }
 }
@@ -0,0 +1,690 @@
+//! A JSON interchange format for constraint systems, to inspect circuits with external
+//! tooling and compare them across implementations.
+//!
+//! A constraint system is described by an [`Interchange`] object:
+//!
+//! ```json
+//! {
+//!   "version": 1,
+//!   "counts": { "fixed": 1, "advice": 1, "instance": 1, "selectors": 0, "challenges": 0 },
+//!   "columns": [
+//!     { "kind": "fixed", "index": 0, "annotation": "q" },
+//!     { "kind": "advice", "index": 0, "phase": 0, "unblinded": false },
+//!     { "kind": "instance", "index": 0 }
+//!   ],
+//!   "challenges": [],
+//!   "gates": [
+//!     { "name": "double", "polynomial": ["*", ["fixed", 0, 0], ["+", ["advice", 0, 1], ["-", ["scale", ["advice", 0, 0], "0x02"]]]] }
+//!   ],
+//!   "lookups": [{ "name": "range", "inputs": [["advice", 0, 0]], "tables": [["fixed", 0, 0]] }],
+//!   "shuffles": [{ "name": "sorted", "inputs": [["advice", 0, 0]], "shuffles": [["advice", 1, 0]] }],
+//!   "permutation": [{ "kind": "advice", "index": 0 }, { "kind": "instance", "index": 0 }],
+//!   "constants": [0]
+//! }
+//! ```
+//!
+//! - `columns` lists every column, with the phase and whether it's left unblinded for advice
+//!   columns, and the annotation of the column if there is one.
+//! - `gates` holds one entry per polynomial identity, named `gate` or `gate:constraint`.
+//! - Expressions are written in prefix notation, as arrays whose first element is one of:
+//!   - `["constant", c]` and `["challenge", index]`;
+//!   - `["fixed", column, rotation]`, `["advice", column, rotation]` and
+//!     `["instance", column, rotation]`;
+//!   - `["selector", index]`, for constraint systems whose selectors have not been
+//!     converted to fixed columns yet;
+//!   - `["-", a]`, `["+", a, b]`, `["*", a, b]` and `["scale", a, c]`.
+//! - Field elements are strings holding the `0x`-prefixed big-endian hexadecimal encoding of
+//!   their canonical representation, which assumes that [`PrimeField::to_repr`] is
+//!   little-endian, as it is for the fields of `halo2curves`.
+//!
+//! Fields that are not described here are ignored when reading an [`Interchange`], so that
+//! objects written by later versions can still be read, as long as their `version` is the
+//! same.
+
+use super::{Column, ConstraintSystem, Expression};
+use crate::plonk::Error;
+use halo2_middleware::circuit::{
+    Advice, AdviceQueryMid, Any, ChallengeMid, ColumnMid, ConstraintSystemV2Backend, ExpressionMid,
+    FixedQueryMid, GateV2Backend, InstanceQueryMid,
+};
+use halo2_middleware::ff::PrimeField;
+use halo2_middleware::poly::Rotation;
+use halo2_middleware::{lookup, metadata, permutation, shuffle};
+use serde::de::{self, SeqAccess, Visitor};
+use serde::ser::SerializeSeq;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::HashMap;
+use std::fmt;
+
+/// The version of the interchange format, bumped on incompatible changes only.
+pub const INTERCHANGE_VERSION: u32 = 1;
+
+/// A constraint system in the interchange format.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Interchange {
+    /// The version of the format, [`INTERCHANGE_VERSION`].
+    pub version: u32,
+    /// The number of columns of each kind, of selectors and of challenges.
+    pub counts: InterchangeCounts,
+    /// Every column of the constraint system.
+    pub columns: Vec<InterchangeColumn>,
+    /// Every challenge of the constraint system.
+    pub challenges: Vec<InterchangeChallenge>,
+    /// The polynomial identities of the gates.
+    pub gates: Vec<InterchangeGate>,
+    /// The lookup arguments.
+    pub lookups: Vec<InterchangeLookup>,
+    /// The shuffle arguments.
+    pub shuffles: Vec<InterchangeShuffle>,
+    /// The columns involved in the permutation argument.
+    pub permutation: Vec<InterchangeColumnRef>,
+    /// The indices of the fixed columns enabled for constants.
+    #[serde(default)]
+    pub constants: Vec<usize>,
+}
+
+/// The sizes of a constraint system.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InterchangeCounts {
+    pub fixed: usize,
+    pub advice: usize,
+    pub instance: usize,
+    pub selectors: usize,
+    pub challenges: usize,
+}
+
+/// The kind of a column.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum InterchangeColumnKind {
+    Fixed,
+    Advice,
+    Instance,
+}
+
+/// A column of a constraint system.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InterchangeColumn {
+    pub kind: InterchangeColumnKind,
+    pub index: usize,
+    /// The phase of an advice column.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub phase: Option<u8>,
+    /// Whether an advice column is left unblinded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unblinded: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub annotation: Option<String>,
+}
+
+/// A reference to a column of a constraint system.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InterchangeColumnRef {
+    pub kind: InterchangeColumnKind,
+    pub index: usize,
+}
+
+/// A challenge of a constraint system.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InterchangeChallenge {
+    pub index: usize,
+    /// The phase after which the challenge is squeezed.
+    pub phase: u8,
+}
+
+/// A polynomial identity of a gate.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InterchangeGate {
+    pub name: String,
+    pub polynomial: InterchangeExpression,
+}
+
+/// A lookup argument.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InterchangeLookup {
+    pub name: String,
+    pub inputs: Vec<InterchangeExpression>,
+    pub tables: Vec<InterchangeExpression>,
+}
+
+/// A shuffle argument.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InterchangeShuffle {
+    pub name: String,
+    pub inputs: Vec<InterchangeExpression>,
+    pub shuffles: Vec<InterchangeExpression>,
+}
+
+/// An expression, written in prefix notation. Field elements are kept hex-encoded.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum InterchangeExpression {
+    Constant(String),
+    Selector(usize),
+    Fixed { column: usize, rotation: i32 },
+    Advice { column: usize, rotation: i32 },
+    Instance { column: usize, rotation: i32 },
+    Challenge(usize),
+    Negated(Box<InterchangeExpression>),
+    Sum(Box<InterchangeExpression>, Box<InterchangeExpression>),
+    Product(Box<InterchangeExpression>, Box<InterchangeExpression>),
+    Scaled(Box<InterchangeExpression>, String),
+}
+
+impl Serialize for InterchangeExpression {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use InterchangeExpression::*;
+        let len = match self {
+            Constant(_) | Selector(_) | Challenge(_) | Negated(_) => 2,
+            Fixed { .. } | Advice { .. } | Instance { .. } => 3,
+            Sum(..) | Product(..) | Scaled(..) => 3,
+        };
+        let mut seq = serializer.serialize_seq(Some(len))?;
+        match self {
+            Constant(value) => {
+                seq.serialize_element("constant")?;
+                seq.serialize_element(value)?;
+            }
+            Selector(index) => {
+                seq.serialize_element("selector")?;
+                seq.serialize_element(index)?;
+            }
+            Fixed { column, rotation } => {
+                seq.serialize_element("fixed")?;
+                seq.serialize_element(column)?;
+                seq.serialize_element(rotation)?;
+            }
+            Advice { column, rotation } => {
+                seq.serialize_element("advice")?;
+                seq.serialize_element(column)?;
+                seq.serialize_element(rotation)?;
+            }
+            Instance { column, rotation } => {
+                seq.serialize_element("instance")?;
+                seq.serialize_element(column)?;
+                seq.serialize_element(rotation)?;
+            }
+            Challenge(index) => {
+                seq.serialize_element("challenge")?;
+                seq.serialize_element(index)?;
+            }
+            Negated(a) => {
+                seq.serialize_element("-")?;
+                seq.serialize_element(a)?;
+            }
+            Sum(a, b) => {
+                seq.serialize_element("+")?;
+                seq.serialize_element(a)?;
+                seq.serialize_element(b)?;
+            }
+            Product(a, b) => {
+                seq.serialize_element("*")?;
+                seq.serialize_element(a)?;
+                seq.serialize_element(b)?;
+            }
+            Scaled(a, value) => {
+                seq.serialize_element("scale")?;
+                seq.serialize_element(a)?;
+                seq.serialize_element(value)?;
+            }
+        }
+        seq.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for InterchangeExpression {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct ExpressionVisitor;
+
+        impl<'de> Visitor<'de> for ExpressionVisitor {
+            type Value = InterchangeExpression;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "an expression in prefix notation")
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+                use InterchangeExpression::*;
+                // Reads the operand at position `i` of the expression.
+                fn operand<'de, A: SeqAccess<'de>, T: Deserialize<'de>>(
+                    seq: &mut A,
+                    i: usize,
+                ) -> Result<T, A::Error> {
+                    seq.next_element()?
+                        .ok_or_else(|| de::Error::invalid_length(i, &"more operands"))
+                }
+
+                let op: String = operand(&mut seq, 0)?;
+                Ok(match op.as_str() {
+                    "constant" => Constant(operand(&mut seq, 1)?),
+                    "selector" => Selector(operand(&mut seq, 1)?),
+                    "fixed" => Fixed {
+                        column: operand(&mut seq, 1)?,
+                        rotation: operand(&mut seq, 2)?,
+                    },
+                    "advice" => Advice {
+                        column: operand(&mut seq, 1)?,
+                        rotation: operand(&mut seq, 2)?,
+                    },
+                    "instance" => Instance {
+                        column: operand(&mut seq, 1)?,
+                        rotation: operand(&mut seq, 2)?,
+                    },
+                    "challenge" => Challenge(operand(&mut seq, 1)?),
+                    "-" => Negated(operand(&mut seq, 1)?),
+                    "+" => Sum(operand(&mut seq, 1)?, operand(&mut seq, 2)?),
+                    "*" => Product(operand(&mut seq, 1)?, operand(&mut seq, 2)?),
+                    "scale" => Scaled(operand(&mut seq, 1)?, operand(&mut seq, 2)?),
+                    op => {
+                        return Err(de::Error::unknown_variant(
+                            op,
+                            &[
+                                "constant",
+                                "selector",
+                                "fixed",
+                                "advice",
+                                "instance",
+                                "challenge",
+                                "-",
+                                "+",
+                                "*",
+                                "scale",
+                            ],
+                        ))
+                    }
+                })
+            }
+        }
+
+        deserializer.deserialize_seq(ExpressionVisitor)
+    }
+}
+
+fn to_hex<F: PrimeField>(value: &F) -> String {
+    let repr = value.to_repr();
+    let digits: String = repr
+        .as_ref()
+        .iter()
+        .rev()
+        .map(|byte| format!("{byte:02x}"))
+        .collect();
+    format!("0x{digits}")
+}
+
+fn from_hex<F: PrimeField>(hex: &str) -> Result<F, Error> {
+    let invalid = || Error::Other(format!("Invalid field element {hex:?} in interchange"));
+    let digits = hex.strip_prefix("0x").ok_or_else(invalid)?.as_bytes();
+    let mut repr = F::Repr::default();
+    let bytes = repr.as_mut();
+    if digits.is_empty()
+        || digits.len() > 2 * bytes.len()
+        || !digits.iter().all(u8::is_ascii_hexdigit)
+    {
+        return Err(invalid());
+    }
+    // The digits are read from the least significant one, so they may omit leading zeros.
+    for (byte, chunk) in bytes.iter_mut().zip(digits.rchunks(2)) {
+        let chunk = std::str::from_utf8(chunk).map_err(|_| invalid())?;
+        *byte = u8::from_str_radix(chunk, 16).map_err(|_| invalid())?;
+    }
+    Option::from(F::from_repr(repr)).ok_or_else(invalid)
+}
+
+impl<F: PrimeField> From<&Expression<F>> for InterchangeExpression {
+    fn from(expression: &Expression<F>) -> Self {
+        use InterchangeExpression::*;
+        match expression {
+            Expression::Constant(value) => Constant(to_hex(value)),
+            Expression::Selector(selector) => Selector(selector.0),
+            Expression::Fixed(query) => Fixed {
+                column: query.column_index,
+                rotation: query.rotation.0,
+            },
+            Expression::Advice(query) => Advice {
+                column: query.column_index,
+                rotation: query.rotation.0,
+            },
+            Expression::Instance(query) => Instance {
+                column: query.column_index,
+                rotation: query.rotation.0,
+            },
+            Expression::Challenge(challenge) => Challenge(challenge.index()),
+            Expression::Negated(a) => Negated(Box::new(a.as_ref().into())),
+            Expression::Sum(a, b) => Sum(Box::new(a.as_ref().into()), Box::new(b.as_ref().into())),
+            Expression::Product(a, b) => {
+                Product(Box::new(a.as_ref().into()), Box::new(b.as_ref().into()))
+            }
+            Expression::Scaled(a, value) => Scaled(Box::new(a.as_ref().into()), to_hex(value)),
+        }
+    }
+}
+
+impl InterchangeExpression {
+    /// Converts the expression, given the phases of the advice columns and of the challenges.
+    fn to_mid<F: PrimeField>(
+        &self,
+        advice_phase: &[u8],
+        challenge_phase: &[u8],
+    ) -> Result<ExpressionMid<F>, Error> {
+        use InterchangeExpression::*;
+        let to_mid =
+            |a: &InterchangeExpression| a.to_mid::<F>(advice_phase, challenge_phase).map(Box::new);
+        let out_of_range = |kind: &str, index: usize| {
+            Error::Other(format!(
+                "Expression in interchange refers to {kind} {index}, which doesn't exist"
+            ))
+        };
+        Ok(match self {
+            Constant(value) => ExpressionMid::Constant(from_hex(value)?),
+            Selector(_) => {
+                return Err(Error::Other(
+                    "Interchange has selectors, convert them to fixed columns first".to_string(),
+                ))
+            }
+            Fixed { column, rotation } => ExpressionMid::Fixed(FixedQueryMid {
+                column_index: *column,
+                rotation: Rotation(*rotation),
+            }),
+            Advice { column, rotation } => ExpressionMid::Advice(AdviceQueryMid {
+                column_index: *column,
+                rotation: Rotation(*rotation),
+                phase: *advice_phase
+                    .get(*column)
+                    .ok_or_else(|| out_of_range("advice column", *column))?,
+            }),
+            Instance { column, rotation } => ExpressionMid::Instance(InstanceQueryMid {
+                column_index: *column,
+                rotation: Rotation(*rotation),
+            }),
+            Challenge(index) => ExpressionMid::Challenge(ChallengeMid {
+                index: *index,
+                phase: *challenge_phase
+                    .get(*index)
+                    .ok_or_else(|| out_of_range("challenge", *index))?,
+            }),
+            Negated(a) => ExpressionMid::Negated(to_mid(a)?),
+            Sum(a, b) => ExpressionMid::Sum(to_mid(a)?, to_mid(b)?),
+            Product(a, b) => ExpressionMid::Product(to_mid(a)?, to_mid(b)?),
+            Scaled(a, value) => ExpressionMid::Scaled(to_mid(a)?, from_hex(value)?),
+        })
+    }
+}
+
+impl InterchangeColumnKind {
+    fn column_type(&self, advice_phase: &[u8], index: usize) -> Result<Any, Error> {
+        Ok(match self {
+            InterchangeColumnKind::Fixed => Any::Fixed,
+            InterchangeColumnKind::Instance => Any::Instance,
+            InterchangeColumnKind::Advice => {
+                Any::advice_in(*advice_phase.get(index).ok_or_else(|| {
+                    Error::Other(format!(
+                        "Interchange refers to advice column {index}, which doesn't exist"
+                    ))
+                })?)
+            }
+        })
+    }
+}
+
+impl From<Any> for InterchangeColumnKind {
+    fn from(column_type: Any) -> Self {
+        match column_type {
+            Any::Fixed => InterchangeColumnKind::Fixed,
+            Any::Advice(_) => InterchangeColumnKind::Advice,
+            Any::Instance => InterchangeColumnKind::Instance,
+        }
+    }
+}
+
+impl<F: PrimeField> ConstraintSystem<F> {
+    /// Describes this constraint system in the [interchange format](self).
+    pub fn to_interchange(&self) -> Interchange {
+        let annotation = |column_type, index| {
+            self.general_column_annotations
+                .get(&metadata::Column { column_type, index })
+                .cloned()
+        };
+        let fixed = (0..self.num_fixed_columns).map(|index| InterchangeColumn {
+            kind: InterchangeColumnKind::Fixed,
+            index,
+            phase: None,
+            unblinded: None,
+            annotation: annotation(Any::Fixed, index),
+        });
+        let advice = self
+            .advice_column_phase
+            .iter()
+            .enumerate()
+            .map(|(index, phase)| InterchangeColumn {
+                kind: InterchangeColumnKind::Advice,
+                index,
+                phase: Some(phase.0),
+                unblinded: Some(self.unblinded_advice_columns.contains(&index)),
+                annotation: annotation(Any::advice_in(phase.0), index),
+            });
+        let instance = (0..self.num_instance_columns).map(|index| InterchangeColumn {
+            kind: InterchangeColumnKind::Instance,
+            index,
+            phase: None,
+            unblinded: None,
+            annotation: annotation(Any::Instance, index),
+        });
+
+        let gates = self
+            .gates
+            .iter()
+            .flat_map(|gate| {
+                gate.polynomials()
+                    .iter()
+                    .enumerate()
+                    .map(move |(i, polynomial)| {
+                        // Named as in the conversion into a `ConstraintSystemV2Backend`.
+                        let constraint_name =
+                            gate.constraint_names.get(i).map_or("", String::as_str);
+                        let name = match constraint_name {
+                            "" => gate.name.clone(),
+                            constraint_name => format!("{}:{constraint_name}", gate.name),
+                        };
+                        InterchangeGate {
+                            name,
+                            polynomial: polynomial.into(),
+                        }
+                    })
+            })
+            .collect();
+        let expressions = |expressions: &[Expression<F>]| {
+            expressions
+                .iter()
+                .map(InterchangeExpression::from)
+                .collect()
+        };
+
+        Interchange {
+            version: INTERCHANGE_VERSION,
+            counts: InterchangeCounts {
+                fixed: self.num_fixed_columns,
+                advice: self.num_advice_columns,
+                instance: self.num_instance_columns,
+                selectors: self.num_selectors,
+                challenges: self.num_challenges,
+            },
+            columns: fixed.chain(advice).chain(instance).collect(),
+            challenges: self
+                .challenge_phase
+                .iter()
+                .enumerate()
+                .map(|(index, phase)| InterchangeChallenge {
+                    index,
+                    phase: phase.0,
+                })
+                .collect(),
+            gates,
+            lookups: self
+                .lookups
+                .iter()
+                .map(|lookup| InterchangeLookup {
+                    name: lookup.name.clone(),
+                    inputs: expressions(&lookup.input_expressions),
+                    tables: expressions(&lookup.table_expressions),
+                })
+                .collect(),
+            shuffles: self
+                .shuffles
+                .iter()
+                .map(|shuffle| InterchangeShuffle {
+                    name: shuffle.name.clone(),
+                    inputs: expressions(&shuffle.input_expressions),
+                    shuffles: expressions(&shuffle.shuffle_expressions),
+                })
+                .collect(),
+            permutation: self
+                .permutation
+                .columns
+                .iter()
+                .map(|column| InterchangeColumnRef {
+                    kind: (*column.column_type()).into(),
+                    index: column.index(),
+                })
+                .collect(),
+            constants: self.constants.iter().map(|column| column.index()).collect(),
+        }
+    }
+
+    /// Reads a constraint system from the [interchange format](self).
+    ///
+    /// Selectors have no counterpart in a `ConstraintSystemV2Backend`, so this fails on
+    /// constraint systems that still have selectors, such as the one of a circuit before
+    /// keygen. The enabled constants are not part of it either.
+    pub fn from_interchange(
+        interchange: &Interchange,
+    ) -> Result<ConstraintSystemV2Backend<F>, Error> {
+        if interchange.version != INTERCHANGE_VERSION {
+            return Err(Error::Other(format!(
+                "Interchange version {} is not supported, expected {INTERCHANGE_VERSION}",
+                interchange.version
+            )));
+        }
+        let counts = &interchange.counts;
+        if counts.selectors != 0 {
+            return Err(Error::Other(
+                "Interchange has selectors, convert them to fixed columns first".to_string(),
+            ));
+        }
+
+        let mut advice_phase = vec![0; counts.advice];
+        let mut unblinded_advice_columns = vec![];
+        let mut annotated = vec![];
+        for column in interchange.columns.iter() {
+            let count = match column.kind {
+                InterchangeColumnKind::Fixed => counts.fixed,
+                InterchangeColumnKind::Advice => counts.advice,
+                InterchangeColumnKind::Instance => counts.instance,
+            };
+            if column.index >= count {
+                return Err(Error::Other(format!(
+                    "Interchange has {:?} column {}, but only {count} of them",
+                    column.kind, column.index
+                )));
+            }
+            if column.kind == InterchangeColumnKind::Advice {
+                let phase = column.phase.unwrap_or(0);
+                advice_phase[column.index] =
+                    Column::<Advice>::try_new_in_phase(column.index, phase)
+                        .map_err(|err| {
+                            Error::Other(format!(
+                                "Interchange has advice column {} in phase {phase}: {err}",
+                                column.index
+                            ))
+                        })?
+                        .phase();
+                if column.unblinded == Some(true) {
+                    unblinded_advice_columns.push(column.index);
+                }
+            }
+            if let Some(annotation) = &column.annotation {
+                annotated.push((column.kind, column.index, annotation));
+            }
+        }
+        let mut challenge_phase = vec![0; counts.challenges];
+        for challenge in interchange.challenges.iter() {
+            *challenge_phase.get_mut(challenge.index).ok_or_else(|| {
+                Error::Other(format!(
+                    "Interchange has challenge {}, but only {} of them",
+                    challenge.index, counts.challenges
+                ))
+            })? = challenge.phase;
+        }
+
+        // Annotations are keyed by the column type, which includes the phase of advice columns.
+        let general_column_annotations = annotated
+            .into_iter()
+            .map(|(kind, index, annotation)| {
+                let column_type = kind.column_type(&advice_phase, index)?;
+                Ok((metadata::Column { column_type, index }, annotation.clone()))
+            })
+            .collect::<Result<HashMap<_, _>, Error>>()?;
+        let to_mid = |expression: &InterchangeExpression| {
+            expression.to_mid::<F>(&advice_phase, &challenge_phase)
+        };
+        let expressions = |expressions: &[InterchangeExpression]| {
+            expressions
+                .iter()
+                .map(to_mid)
+                .collect::<Result<Vec<_>, _>>()
+        };
+
+        Ok(ConstraintSystemV2Backend {
+            num_fixed_columns: counts.fixed,
+            num_advice_columns: counts.advice,
+            num_instance_columns: counts.instance,
+            num_challenges: counts.challenges,
+            unblinded_advice_columns,
+            gates: interchange
+                .gates
+                .iter()
+                .map(|gate| {
+                    Ok(GateV2Backend {
+                        name: gate.name.clone(),
+                        poly: to_mid(&gate.polynomial)?,
+                    })
+                })
+                .collect::<Result<_, Error>>()?,
+            permutation: permutation::ArgumentV2 {
+                columns: interchange
+                    .permutation
+                    .iter()
+                    .map(|column| {
+                        Ok(ColumnMid {
+                            index: column.index,
+                            column_type: column.kind.column_type(&advice_phase, column.index)?,
+                        })
+                    })
+                    .collect::<Result<_, Error>>()?,
+            },
+            lookups: interchange
+                .lookups
+                .iter()
+                .map(|lookup| {
+                    Ok(lookup::ArgumentV2 {
+                        name: lookup.name.clone(),
+                        input_expressions: expressions(&lookup.inputs)?,
+                        table_expressions: expressions(&lookup.tables)?,
+                    })
+                })
+                .collect::<Result<_, Error>>()?,
+            shuffles: interchange
+                .shuffles
+                .iter()
+                .map(|shuffle| {
+                    Ok(shuffle::ArgumentV2 {
+                        name: shuffle.name.clone(),
+                        input_expressions: expressions(&shuffle.inputs)?,
+                        shuffle_expressions: expressions(&shuffle.shuffles)?,
+                    })
+                })
+                .collect::<Result<_, Error>>()?,
+            general_column_annotations,
+            advice_column_phase: advice_phase,
+            challenge_phase,
+        })
+    }
+}
@@ -1,10 +1,13 @@
 use std::error;
 use std::fmt;
 use std::io;
+use std::ops::Range;
 
-use super::TableColumn;
+use super::{PhaseMix, Selector, TableColumn};
+use crate::circuit::layouter::RegionColumn;
+use crate::circuit::Cell;
 use crate::plonk::circuit::Column;
-use halo2_middleware::circuit::Any;
+use halo2_middleware::circuit::{Advice, Any, Fixed};
 
 // TODO: Split this Error into a frontend and backend version
 // https://github.com/privacy-scaling-explorations/halo2/issues/266
@@ -24,12 +27,79 @@ pub enum Error {
     BoundsFailure,
     /// Opening error
     Opening,
+    /// Some proof of a batch is invalid.
+    BatchVerificationFailed {
+        /// The indices of the invalid proofs in the batch, if they were searched for.
+        invalid: Option<Vec<usize>>,
+    },
     /// Transcript error
     Transcript(io::Error),
+    /// A proof header identifies a different verifying key than the one used to read it.
+    VerifyingKeyMismatch {
+        /// The `k` of the verifying key used to read the proof.
+        expected_k: u32,
+        /// The fingerprint of the verifying key used to read the proof.
+        expected: String,
+        /// The `k` in the proof header.
+        found_k: u32,
+        /// The fingerprint in the proof header.
+        found: String,
+    },
+    /// A proof was created for the verifying key used to read it, but generated with the
+    /// other choice of `commit_instances` in its `CircuitOptions`.
+    InstanceModeMismatch {
+        /// Whether the verifying key used to read the proof commits to instance columns.
+        expected_commit_instances: bool,
+    },
     /// `k` is too small for the given circuit.
     NotEnoughRowsAvailable {
         /// The current value of `k` being used.
         current_k: u32,
+        /// The row that was attempted, if the error comes from an assignment.
+        row: Option<usize>,
+    },
+    /// A region was planned to span rows beyond the usable rows of the circuit.
+    RegionOutOfRows {
+        /// The name of the region.
+        region: String,
+        /// The rows that the region was planned to span.
+        rows: Range<usize>,
+        /// The usable rows of the circuit.
+        usable_rows: Range<usize>,
+    },
+    /// A selector was enabled at a row outside of the usable rows of the circuit, i.e. in
+    /// the rows reserved for blinding factors.
+    SelectorOutsideUsableRows {
+        /// The selector that was enabled.
+        selector: Selector,
+        /// The row at which the selector was enabled.
+        row: usize,
+        /// The usable rows of the circuit.
+        usable_rows: Range<usize>,
+    },
+    /// An advice cell was assigned at a row outside of the usable rows of the circuit, i.e.
+    /// in the rows reserved for blinding factors.
+    AdviceOutsideUsableRows {
+        /// The column of the cell.
+        column: Column<Advice>,
+        /// The row of the cell.
+        row: usize,
+        /// The usable rows of the circuit.
+        usable_rows: Range<usize>,
+    },
+    /// An assignment within a region falls outside of the shape declared for the region by
+    /// [`Circuit::region_shapes`].
+    ///
+    /// [`Circuit::region_shapes`]: crate::plonk::Circuit::region_shapes
+    RegionShapeExceeded {
+        /// The name of the region.
+        region: String,
+        /// The column that was assigned.
+        column: RegionColumn,
+        /// The offset of the assignment within the region.
+        offset: usize,
+        /// The number of rows of the declared shape.
+        row_count: usize,
     },
     /// Instance provided exceeds number of available rows
     InstanceTooLarge,
@@ -41,8 +111,63 @@ pub enum Error {
     /// The instance sets up a copy constraint involving a column that has not been
     /// included in the permutation.
     ColumnNotInPermutation(Column<Any>),
+    /// The instance sets up a copy constraint between advice columns of different phases,
+    /// where the caller opted to reject such copies.
+    CopyPhaseMismatch(Column<Any>, Column<Any>),
+    /// The cells passed to [`Region::constrain_equal_checked`] hold different known values.
+    ///
+    /// [`Region::constrain_equal_checked`]: crate::circuit::Region::constrain_equal_checked
+    CopyValueMismatch {
+        /// The first cell of the copy.
+        left: Cell,
+        /// The second cell of the copy.
+        right: Cell,
+        /// The value of the first cell.
+        left_value: String,
+        /// The value of the second cell.
+        right_value: String,
+    },
+    /// A cell in a fixed column was passed to `constrain_constant`. Fixed cells already
+    /// hold constant values, so the constraint would require an equality-enabled copy
+    /// between two fixed columns.
+    ConstrainConstantOnFixed(Column<Any>),
+    /// A cell was assigned in a fixed column allocated with
+    /// [`ConstraintSystem::preprocessed_fixed_column`], whose values are computed from the
+    /// row index instead.
+    ///
+    /// [`ConstraintSystem::preprocessed_fixed_column`]: crate::plonk::ConstraintSystem::preprocessed_fixed_column
+    PreprocessedFixedAssigned(Column<Fixed>),
+    /// The witness computation passed to [`Region::try_assign_advice`] returned an error.
+    ///
+    /// [`Region::try_assign_advice`]: crate::circuit::Region::try_assign_advice
+    WitnessFailure {
+        /// The name of the region containing the cell.
+        region: String,
+        /// The annotation of the cell.
+        annotation: String,
+        /// The offset of the cell within the region.
+        offset: usize,
+        /// The error returned by the witness computation.
+        error: Box<Error>,
+    },
+    /// An assignment within a region or table failed. The layouters attach the context of
+    /// the assignment to the errors returned by [`Assignment`] methods.
+    ///
+    /// [`Assignment`]: crate::plonk::Assignment
+    InRegion {
+        /// Where the assignment was attempted.
+        context: Box<AssignmentContext>,
+        /// The error returned by the assignment.
+        error: Box<Error>,
+    },
     /// An error relating to a lookup table.
     TableError(TableError),
+    /// [`ConstraintSystem::validate`] found gates that query advice columns of more than
+    /// one phase without being allowed with [`ConstraintSystem::allow_phase_mix`].
+    ///
+    /// [`ConstraintSystem::validate`]: crate::plonk::ConstraintSystem::validate
+    /// [`ConstraintSystem::allow_phase_mix`]: crate::plonk::ConstraintSystem::allow_phase_mix
+    UnintendedPhaseMix(Vec<PhaseMix>),
     /// Generic error not covered by previous cases
     Other(String),
 }
@@ -57,7 +182,76 @@ impl From<io::Error> for Error {
 impl Error {
     /// Constructs an `Error::NotEnoughRowsAvailable`.
     pub fn not_enough_rows_available(current_k: u32) -> Self {
-        Error::NotEnoughRowsAvailable { current_k }
+        Error::NotEnoughRowsAvailable {
+            current_k,
+            row: None,
+        }
+    }
+
+    /// Constructs an `Error::NotEnoughRowsAvailable` for an assignment at `row`.
+    pub fn not_enough_rows_available_at(current_k: u32, row: usize) -> Self {
+        Error::NotEnoughRowsAvailable {
+            current_k,
+            row: Some(row),
+        }
+    }
+
+    /// Attaches the context of a failed assignment to this error, as an `Error::InRegion`.
+    ///
+    /// Errors that already carry the context of their region, `Error::InRegion` and
+    /// `Error::WitnessFailure`, are returned unchanged.
+    pub fn in_region(self, context: AssignmentContext) -> Self {
+        match self {
+            Error::InRegion { .. } | Error::WitnessFailure { .. } => self,
+            error => Error::InRegion {
+                context: Box::new(context),
+                error: Box::new(error),
+            },
+        }
+    }
+
+    /// Returns the error without the context of its region, if any.
+    pub fn root(&self) -> &Error {
+        match self {
+            Error::InRegion { error, .. } => error.root(),
+            error => error,
+        }
+    }
+
+    /// Returns the error without the context of its region, if any.
+    pub fn into_root(self) -> Error {
+        match self {
+            Error::InRegion { error, .. } => error.into_root(),
+            error => error,
+        }
+    }
+
+    /// Constructs an `Error::RegionOutOfRows`.
+    pub fn region_out_of_rows(
+        region: String,
+        rows: Range<usize>,
+        usable_rows: Range<usize>,
+    ) -> Self {
+        Error::RegionOutOfRows {
+            region,
+            rows,
+            usable_rows,
+        }
+    }
+
+    /// Constructs an `Error::WitnessFailure`.
+    pub fn witness_failure(
+        region: String,
+        annotation: String,
+        offset: usize,
+        error: Error,
+    ) -> Self {
+        Error::WitnessFailure {
+            region,
+            annotation,
+            offset,
+            error: Box::new(error),
+        }
     }
 }
 
@@ -69,11 +263,87 @@ impl fmt::Display for Error {
             Error::ConstraintSystemFailure => write!(f, "The constraint system is not satisfied"),
             Error::BoundsFailure => write!(f, "An out-of-bounds index was passed to the backend"),
             Error::Opening => write!(f, "Multi-opening proof was invalid"),
+            Error::BatchVerificationFailed { invalid: None } => {
+                write!(f, "Batch verification failed")
+            }
+            Error::BatchVerificationFailed {
+                invalid: Some(invalid),
+            } => write!(f, "Batch verification failed, invalid proofs: {invalid:?}"),
             Error::Transcript(e) => write!(f, "Transcript error: {e}"),
-            Error::NotEnoughRowsAvailable { current_k } => write!(
+            Error::VerifyingKeyMismatch {
+                expected_k,
+                expected,
+                found_k,
+                found,
+            } => write!(
+                f,
+                "Proof was created for verifying key {found} (k = {found_k}), but verifying key {expected} (k = {expected_k}) was provided",
+            ),
+            Error::InstanceModeMismatch {
+                expected_commit_instances,
+            } => {
+                let mode = |commit| if commit { "committed" } else { "evaluated" };
+                write!(
+                    f,
+                    "Proof was created with {} instance columns, but the verifying key expects {} instance columns",
+                    mode(!expected_commit_instances),
+                    mode(*expected_commit_instances),
+                )
+            }
+            Error::NotEnoughRowsAvailable {
+                current_k,
+                row: None,
+            } => write!(
                 f,
                 "k = {current_k} is too small for the given circuit. Try using a larger value of k",
             ),
+            Error::NotEnoughRowsAvailable {
+                current_k,
+                row: Some(row),
+            } => write!(
+                f,
+                "k = {current_k} is too small for the given circuit, which assigns row {row}. Try using a larger value of k",
+            ),
+            Error::RegionOutOfRows {
+                region,
+                rows,
+                usable_rows,
+            } => write!(
+                f,
+                "Region \"{region}\" is planned at rows {rows:?}, but only rows {usable_rows:?} are usable. Try using a larger value of k",
+            ),
+            Error::SelectorOutsideUsableRows {
+                selector,
+                row,
+                usable_rows,
+            } => write!(
+                f,
+                "{selector:?} is enabled at row {row}, but only rows {usable_rows:?} are usable; the following rows hold blinding factors",
+            ),
+            Error::AdviceOutsideUsableRows {
+                column,
+                row,
+                usable_rows,
+            } => write!(
+                f,
+                "{column:?} is assigned at row {row}, but only rows {usable_rows:?} are usable; the following rows hold blinding factors",
+            ),
+            Error::RegionShapeExceeded {
+                region,
+                column,
+                offset,
+                row_count,
+            } => {
+                write!(
+                    f,
+                    "Region \"{region}\" assigns {column:?} at offset {offset}, outside of its declared shape: "
+                )?;
+                if offset >= row_count {
+                    write!(f, "the declared shape has {row_count} rows")
+                } else {
+                    write!(f, "the declared shape does not include the column")
+                }
+            }
             Error::InstanceTooLarge => write!(f, "Instance vectors are larger than the circuit"),
             Error::NotEnoughColumnsForConstants => {
                 write!(
@@ -85,7 +355,65 @@ impl fmt::Display for Error {
                 f,
                 "Column {column:?} must be included in the permutation. Help: try applying `meta.enable_equalty` on the column",
             ),
+            Error::CopyPhaseMismatch(left, right) => {
+                let phase = |column: &Column<Any>| match column.column_type() {
+                    Any::Advice(advice) => advice.phase(),
+                    _ => 0,
+                };
+                write!(
+                    f,
+                    "Cannot copy between column {left:?} in phase {} and column {right:?} in phase {}. Help: copy between advice columns of the same phase, or copy the value through an instance or fixed column",
+                    phase(left),
+                    phase(right),
+                )
+            }
+            Error::CopyValueMismatch {
+                left,
+                right,
+                left_value,
+                right_value,
+            } => write!(
+                f,
+                "Cannot constrain cell at offset {} of {} in region {} to equal cell at offset {} of {} in region {}: they hold different values {left_value} and {right_value}",
+                left.row_offset,
+                left.column,
+                *left.region_index,
+                right.row_offset,
+                right.column,
+                *right.region_index,
+            ),
+            Error::ConstrainConstantOnFixed(column) => write!(
+                f,
+                "Cannot constrain a cell of fixed column {column:?} to a constant. Help: assign the constant with `assign_fixed`, or use an advice cell with `assign_advice_from_constant` after applying `meta.enable_constant` on a fixed column",
+            ),
+            Error::PreprocessedFixedAssigned(column) => write!(
+                f,
+                "Cannot assign a cell of fixed column {column:?}, whose values are computed by the generator given to `preprocessed_fixed_column`",
+            ),
+            Error::WitnessFailure {
+                region,
+                annotation,
+                offset,
+                error,
+            } => write!(
+                f,
+                "Failed to compute the witness for \"{annotation}\" at offset {offset} in region \"{region}\": {error}",
+            ),
+            Error::InRegion { context, error } => write!(f, "Failed to {context}: {error}"),
             Error::TableError(error) => write!(f, "{error}"),
+            Error::UnintendedPhaseMix(mixes) => {
+                for (i, mix) in mixes.iter().enumerate() {
+                    if i > 0 {
+                        writeln!(f)?;
+                    }
+                    write!(
+                        f,
+                        "Gate \"{}\" queries advice columns of phases {:?} and uses challenges of phases {:?}. Help: if this is intended, call `meta.allow_phase_mix(\"{}\")`",
+                        mix.gate, mix.advice_phases, mix.challenge_phases, mix.gate,
+                    )?;
+                }
+                Ok(())
+            }
             Error::Other(error) => write!(f, "Other: {error}"),
         }
     }
@@ -95,11 +423,209 @@ impl error::Error for Error {
     fn source(&self) -> Option<&(dyn error::Error + 'static)> {
         match self {
             Error::Transcript(e) => Some(e),
+            Error::WitnessFailure { error, .. } => Some(error.as_ref()),
+            Error::InRegion { error, .. } => Some(error.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+/// Where an assignment that failed was attempted, see [`Error::InRegion`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AssignmentContext {
+    /// The assignment that was attempted, such as `"assign_advice"`.
+    pub operation: &'static str,
+    /// The name of the region or table.
+    pub region: String,
+    /// The namespaces that the region was assigned within, outermost first.
+    pub namespace: Vec<String>,
+    /// The column of the assignment, if it involves one.
+    pub column: Option<Column<Any>>,
+    /// The annotation given to the column within the region, if any.
+    pub column_annotation: Option<String>,
+    /// The offset of the assignment within the region, if it involves one.
+    pub offset: Option<usize>,
+}
+
+impl AssignmentContext {
+    /// Returns the namespaces and the name of the region, joined with `/`.
+    pub fn path(&self) -> String {
+        self.namespace
+            .iter()
+            .chain(Some(&self.region))
+            .cloned()
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+}
+
+impl fmt::Display for AssignmentContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.operation)?;
+        if let Some(offset) = self.offset {
+            write!(f, " at offset {offset}")?;
+        }
+        if let Some(column) = self.column {
+            write!(f, " of column {column:?}")?;
+            if let Some(annotation) = &self.column_annotation {
+                write!(f, " (\"{annotation}\")")?;
+            }
+        }
+        write!(f, " in region \"{}\"", self.path())
+    }
+}
+
+/// The part of a proof that the verifier was reading when it failed, see
+/// [`VerificationError::Transcript`]. The parts are listed in the order of the proof.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VerificationStage {
+    /// The verifying key and the instances, hashed into the transcript.
+    Instances,
+    /// The commitments to the advice columns.
+    AdviceCommitments,
+    /// The commitments to the permuted input and table columns of the lookups.
+    LookupPermutedCommitments,
+    /// The commitments to the product polynomials of the permutation argument.
+    PermutationProductCommitments,
+    /// The commitments to the product polynomials of the lookups.
+    LookupProductCommitments,
+    /// The commitments to the product polynomials of the shuffles.
+    ShuffleProductCommitments,
+    /// The commitments to the random polynomial and to the pieces of `h(X)` of the
+    /// vanishing argument.
+    VanishingCommitments,
+    /// The evaluations of the instance columns, when they are committed to.
+    InstanceEvaluations,
+    /// The evaluations of the advice columns.
+    AdviceEvaluations,
+    /// The evaluations of the fixed columns.
+    FixedEvaluations,
+    /// The evaluation of the random polynomial of the vanishing argument.
+    VanishingEvaluations,
+    /// The evaluations of the permutation polynomials and of the permutation products.
+    PermutationEvaluations,
+    /// The evaluations of the lookups.
+    LookupEvaluations,
+    /// The evaluations of the shuffles.
+    ShuffleEvaluations,
+}
+
+impl fmt::Display for VerificationStage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let stage = match self {
+            VerificationStage::Instances => "instances",
+            VerificationStage::AdviceCommitments => "advice commitments",
+            VerificationStage::LookupPermutedCommitments => "lookup permuted commitments",
+            VerificationStage::PermutationProductCommitments => "permutation product commitments",
+            VerificationStage::LookupProductCommitments => "lookup product commitments",
+            VerificationStage::ShuffleProductCommitments => "shuffle product commitments",
+            VerificationStage::VanishingCommitments => "vanishing argument commitments",
+            VerificationStage::InstanceEvaluations => "instance evaluations",
+            VerificationStage::AdviceEvaluations => "advice evaluations",
+            VerificationStage::FixedEvaluations => "fixed evaluations",
+            VerificationStage::VanishingEvaluations => "vanishing argument evaluations",
+            VerificationStage::PermutationEvaluations => "permutation evaluations",
+            VerificationStage::LookupEvaluations => "lookup evaluations",
+            VerificationStage::ShuffleEvaluations => "shuffle evaluations",
+        };
+        write!(f, "{stage}")
+    }
+}
+
+/// This is an error that could occur during proof verification, identifying the check
+/// that failed.
+///
+/// The gates, the lookups, the permutation and the shuffles are not checked one by one:
+/// the verifier derives the evaluation of the quotient polynomial `h(X)` from all of
+/// their expressions, and the multi-open argument checks that the commitment to `h(X)`
+/// opens to it. A proof of an unsatisfied circuit is therefore rejected by the
+/// [`FinalCheck`](VerificationError::FinalCheck), and which expression is not satisfied
+/// cannot be determined from the proof; use the `MockProver` on the circuit to find it.
+#[derive(Debug)]
+pub enum VerificationError {
+    /// The instances do not match the number of instance columns of the circuit.
+    InvalidInstances,
+    /// An instance column has more values than the usable rows of the circuit.
+    InstanceTooLarge,
+    /// The proof could not be read at `stage`: it is truncated, or holds an invalid
+    /// encoding of a point or a scalar.
+    Transcript {
+        /// The part of the proof being read.
+        stage: VerificationStage,
+        /// The error returned by the transcript.
+        error: io::Error,
+    },
+    /// The proof was created with the other choice of `commit_instances` than the
+    /// verifying key, see [`Error::InstanceModeMismatch`].
+    InstanceModeMismatch {
+        /// Whether the verifying key commits to the instance columns.
+        expected_commit_instances: bool,
+    },
+    /// The multi-open argument could not be read or verified.
+    Opening,
+    /// The final check of the commitment scheme failed (the pairing check for KZG, the
+    /// MSM check for IPA): the proof is invalid, or was created for other instances.
+    FinalCheck,
+}
+
+impl fmt::Display for VerificationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VerificationError::InvalidInstances => {
+                write!(f, "Provided instances do not match the circuit")
+            }
+            VerificationError::InstanceTooLarge => {
+                write!(f, "Instance vectors are larger than the circuit")
+            }
+            VerificationError::Transcript { stage, error } => {
+                write!(f, "Cannot read the {stage} of the proof: {error}")
+            }
+            VerificationError::InstanceModeMismatch {
+                expected_commit_instances,
+            } => write!(
+                f,
+                "{}",
+                Error::InstanceModeMismatch {
+                    expected_commit_instances: *expected_commit_instances,
+                }
+            ),
+            VerificationError::Opening => write!(f, "Multi-opening proof was invalid"),
+            VerificationError::FinalCheck => write!(
+                f,
+                "The final check of the proof failed: the constraint system is not satisfied"
+            ),
+        }
+    }
+}
+
+impl error::Error for VerificationError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            VerificationError::Transcript { error, .. } => Some(error),
             _ => None,
         }
     }
 }
 
+/// Maps a [`VerificationError`] to the [`Error`] that the verifier returned before it was
+/// introduced.
+impl From<VerificationError> for Error {
+    fn from(error: VerificationError) -> Self {
+        match error {
+            VerificationError::InvalidInstances => Error::InvalidInstances,
+            VerificationError::InstanceTooLarge => Error::InstanceTooLarge,
+            VerificationError::Transcript { error, .. } => Error::Transcript(error),
+            VerificationError::InstanceModeMismatch {
+                expected_commit_instances,
+            } => Error::InstanceModeMismatch {
+                expected_commit_instances,
+            },
+            VerificationError::Opening => Error::Opening,
+            VerificationError::FinalCheck => Error::ConstraintSystemFailure,
+        }
+    }
+}
+
 /// This is an error that could occur during table synthesis.
 #[derive(Debug)]
 pub enum TableError {
@@ -111,6 +637,8 @@ pub enum TableError {
     UsedColumn(TableColumn),
     /// Attempt to overwrite a default value
     OverwriteDefault(TableColumn, String, String),
+    /// Attempt to enable a selector inside `assign_table`
+    SelectorInTable(Selector),
 }
 
 impl fmt::Display for TableError {
@@ -135,6 +663,12 @@ impl fmt::Display for TableError {
                     "Attempted to overwrite default value {default} with {val} in {col:?}",
                 )
             }
+            TableError::SelectorInTable(selector) => {
+                write!(
+                    f,
+                    "{selector:?} cannot be enabled inside assign_table. Help: tag the rows of the table with a fixed tag column, e.g. another TableColumn",
+                )
+            }
         }
     }
 }
@@ -0,0 +1,278 @@
+//! Instrumentation of [`Assignment`] implementations.
+
+use std::ops::Range;
+
+use halo2_middleware::circuit::{Advice, Any, Fixed, Instance};
+use halo2_middleware::ff::Field;
+
+use super::{Assigned, Assignment, Challenge, Column, Error, Selector};
+use crate::circuit::Value;
+
+/// Callbacks invoked by [`InstrumentedAssignment`] while a circuit is being synthesized.
+///
+/// All methods have empty default implementations, so implementors only need to provide
+/// the callbacks they are interested in.
+pub trait AssignmentHooks {
+    /// Called when synthesis of the given phase starts.
+    fn phase_started(&mut self, _phase: u8) {}
+
+    /// Called when the circuit enters a region.
+    fn region_started(&mut self, _name: &str) {}
+
+    /// Called when the circuit exits a region, with the number of rows spanned by the
+    /// cells assigned and the selectors enabled within it.
+    fn region_finished(&mut self, _name: &str, _rows: usize) {}
+
+    /// Called after `n` cells have been assigned.
+    fn cells_assigned(&mut self, _n: usize) {}
+}
+
+/// Hooks that do nothing.
+impl AssignmentHooks for () {}
+
+impl<H: AssignmentHooks + ?Sized> AssignmentHooks for &mut H {
+    fn phase_started(&mut self, phase: u8) {
+        (**self).phase_started(phase)
+    }
+
+    fn region_started(&mut self, name: &str) {
+        (**self).region_started(name)
+    }
+
+    fn region_finished(&mut self, name: &str, rows: usize) {
+        (**self).region_finished(name, rows)
+    }
+
+    fn cells_assigned(&mut self, n: usize) {
+        (**self).cells_assigned(n)
+    }
+}
+
+/// An [`Assignment`] that forwards every call to the wrapped assignment, invoking the
+/// given [`AssignmentHooks`] along the way.
+#[derive(Debug)]
+pub struct InstrumentedAssignment<'a, CS, H> {
+    cs: &'a mut CS,
+    hooks: H,
+    /// The name and the rows spanned so far of the region we are currently in.
+    region: Option<(String, Option<Range<usize>>)>,
+}
+
+impl<'a, CS, H: AssignmentHooks> InstrumentedAssignment<'a, CS, H> {
+    /// Wraps `cs`, and reports the start of its current phase to `hooks`.
+    pub fn new<F: Field>(cs: &'a mut CS, mut hooks: H) -> Self
+    where
+        CS: Assignment<F>,
+    {
+        hooks.phase_started(cs.current_phase());
+        InstrumentedAssignment {
+            cs,
+            hooks,
+            region: None,
+        }
+    }
+
+    /// Returns the hooks.
+    pub fn into_hooks(self) -> H {
+        self.hooks
+    }
+
+    fn track_rows(&mut self, rows: Range<usize>) {
+        if rows.is_empty() {
+            return;
+        }
+        if let Some((_, extent)) = self.region.as_mut() {
+            *extent = Some(match extent.take() {
+                Some(extent) => extent.start.min(rows.start)..extent.end.max(rows.end),
+                None => rows,
+            });
+        }
+    }
+}
+
+impl<'a, F: Field, CS: Assignment<F>, H: AssignmentHooks> Assignment<F>
+    for InstrumentedAssignment<'a, CS, H>
+{
+    fn enter_region<NR, N>(&mut self, name_fn: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+        let name: String = name_fn().into();
+        self.hooks.region_started(&name);
+        self.cs.enter_region(|| name.clone());
+        self.region = Some((name, None));
+    }
+
+    fn annotate_column<A, AR>(&mut self, annotation: A, column: Column<Any>)
+    where
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.cs.annotate_column(annotation, column)
+    }
+
+    fn exit_region(&mut self) {
+        self.cs.exit_region();
+        if let Some((name, extent)) = self.region.take() {
+            self.hooks
+                .region_finished(&name, extent.map_or(0, |extent| extent.len()));
+        }
+    }
+
+    fn enable_selector<A, AR>(
+        &mut self,
+        annotation: A,
+        selector: &Selector,
+        row: usize,
+    ) -> Result<(), Error>
+    where
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.cs.enable_selector(annotation, selector, row)?;
+        self.track_rows(row..row + 1);
+        Ok(())
+    }
+
+    fn enable_selector_range<A, AR>(
+        &mut self,
+        annotation: A,
+        selector: &Selector,
+        rows: Range<usize>,
+    ) -> Result<(), Error>
+    where
+        A: Fn() -> AR,
+        AR: Into<String>,
+    {
+        self.cs
+            .enable_selector_range(annotation, selector, rows.clone())?;
+        self.track_rows(rows);
+        Ok(())
+    }
+
+    fn query_instance(&self, column: Column<Instance>, row: usize) -> Result<Value<F>, Error> {
+        self.cs.query_instance(column, row)
+    }
+
+    fn assign_advice<V, VR, A, AR>(
+        &mut self,
+        annotation: A,
+        column: Column<Advice>,
+        row: usize,
+        to: V,
+    ) -> Result<(), Error>
+    where
+        V: FnOnce() -> Value<VR>,
+        VR: Into<Assigned<F>>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.cs.assign_advice(annotation, column, row, to)?;
+        self.track_rows(row..row + 1);
+        self.hooks.cells_assigned(1);
+        Ok(())
+    }
+
+    fn try_assign_advice<V, VR, A, AR>(
+        &mut self,
+        annotation: A,
+        column: Column<Advice>,
+        row: usize,
+        to: V,
+    ) -> Result<(), Error>
+    where
+        V: FnOnce() -> Result<Value<VR>, Error>,
+        VR: Into<Assigned<F>>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.cs.try_assign_advice(annotation, column, row, to)?;
+        self.track_rows(row..row + 1);
+        self.hooks.cells_assigned(1);
+        Ok(())
+    }
+
+    fn assign_advice_column(
+        &mut self,
+        column: Column<Advice>,
+        start_row: usize,
+        values: &[Value<Assigned<F>>],
+    ) -> Result<(), Error> {
+        self.cs.assign_advice_column(column, start_row, values)?;
+        self.track_rows(start_row..start_row + values.len());
+        self.hooks.cells_assigned(values.len());
+        Ok(())
+    }
+
+    fn assign_fixed<V, VR, A, AR>(
+        &mut self,
+        annotation: A,
+        column: Column<Fixed>,
+        row: usize,
+        to: V,
+    ) -> Result<(), Error>
+    where
+        V: FnOnce() -> Value<VR>,
+        VR: Into<Assigned<F>>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.cs.assign_fixed(annotation, column, row, to)?;
+        self.track_rows(row..row + 1);
+        self.hooks.cells_assigned(1);
+        Ok(())
+    }
+
+    fn copy(
+        &mut self,
+        left_column: Column<Any>,
+        left_row: usize,
+        right_column: Column<Any>,
+        right_row: usize,
+    ) -> Result<(), Error> {
+        self.cs.copy(left_column, left_row, right_column, right_row)
+    }
+
+    fn fill_from_row(
+        &mut self,
+        column: Column<Fixed>,
+        row: usize,
+        to: Value<Assigned<F>>,
+    ) -> Result<(), Error> {
+        self.cs.fill_from_row(column, row, to)
+    }
+
+    fn fill_advice_from_row(
+        &mut self,
+        column: Column<Advice>,
+        from_row: usize,
+        to: Value<Assigned<F>>,
+    ) -> Result<(), Error> {
+        self.cs.fill_advice_from_row(column, from_row, to)
+    }
+
+    fn get_challenge(&self, challenge: Challenge) -> Value<F> {
+        self.cs.get_challenge(challenge)
+    }
+
+    fn current_phase(&self) -> u8 {
+        self.cs.current_phase()
+    }
+
+    fn usable_rows(&self) -> Option<Range<usize>> {
+        self.cs.usable_rows()
+    }
+
+    fn push_namespace<NR, N>(&mut self, name_fn: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+        self.cs.push_namespace(name_fn)
+    }
+
+    fn pop_namespace(&mut self, gadget_name: Option<String>) {
+        self.cs.pop_namespace(gadget_name)
+    }
+}
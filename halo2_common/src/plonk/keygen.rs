@@ -19,6 +19,8 @@ pub struct Assembly<F: Field> {
     pub selectors: Vec<Vec<bool>>,
     // A range of available rows for assignment and copies.
     pub usable_rows: Range<usize>,
+    // The fixed columns filled from their generator, which can't be assigned.
+    pub preprocessed_fixed_columns: Vec<Column<Fixed>>,
     pub _marker: std::marker::PhantomData<F>,
 }
 
@@ -41,7 +43,7 @@ impl<F: Field> Assignment<F> for Assembly<F> {
         AR: Into<String>,
     {
         if !self.usable_rows.contains(&row) {
-            return Err(Error::not_enough_rows_available(self.k));
+            return Err(Error::not_enough_rows_available_at(self.k, row));
         }
 
         self.selectors[selector.0][row] = true;
@@ -49,9 +51,34 @@ impl<F: Field> Assignment<F> for Assembly<F> {
         Ok(())
     }
 
+    fn enable_selector_range<A, AR>(
+        &mut self,
+        _: A,
+        selector: &Selector,
+        rows: Range<usize>,
+    ) -> Result<(), Error>
+    where
+        A: Fn() -> AR,
+        AR: Into<String>,
+    {
+        if rows.is_empty() {
+            return Ok(());
+        }
+        if !self.usable_rows.contains(&rows.start) {
+            return Err(Error::not_enough_rows_available_at(self.k, rows.start));
+        }
+        if !self.usable_rows.contains(&(rows.end - 1)) {
+            return Err(Error::not_enough_rows_available_at(self.k, rows.end - 1));
+        }
+
+        self.selectors[selector.0][rows].fill(true);
+
+        Ok(())
+    }
+
     fn query_instance(&self, _: Column<Instance>, row: usize) -> Result<Value<F>, Error> {
         if !self.usable_rows.contains(&row) {
-            return Err(Error::not_enough_rows_available(self.k));
+            return Err(Error::not_enough_rows_available_at(self.k, row));
         }
 
         // There is no instance in this context.
@@ -75,6 +102,33 @@ impl<F: Field> Assignment<F> for Assembly<F> {
         Ok(())
     }
 
+    fn assign_advice_column(
+        &mut self,
+        _: Column<Advice>,
+        _: usize,
+        _: &[Value<Assigned<F>>],
+    ) -> Result<(), Error> {
+        // We only care about fixed columns here
+        Ok(())
+    }
+
+    fn try_assign_advice<V, VR, A, AR>(
+        &mut self,
+        _: A,
+        _: Column<Advice>,
+        _: usize,
+        _: V,
+    ) -> Result<(), Error>
+    where
+        V: FnOnce() -> Result<Value<VR>, Error>,
+        VR: Into<Assigned<F>>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        // We only care about fixed columns here
+        Ok(())
+    }
+
     fn assign_fixed<V, VR, A, AR>(
         &mut self,
         _: A,
@@ -89,7 +143,10 @@ impl<F: Field> Assignment<F> for Assembly<F> {
         AR: Into<String>,
     {
         if !self.usable_rows.contains(&row) {
-            return Err(Error::not_enough_rows_available(self.k));
+            return Err(Error::not_enough_rows_available_at(self.k, row));
+        }
+        if self.preprocessed_fixed_columns.contains(&column) {
+            return Err(Error::PreprocessedFixedAssigned(column));
         }
 
         *self
@@ -108,8 +165,10 @@ impl<F: Field> Assignment<F> for Assembly<F> {
         right_column: Column<Any>,
         right_row: usize,
     ) -> Result<(), Error> {
-        if !self.usable_rows.contains(&left_row) || !self.usable_rows.contains(&right_row) {
-            return Err(Error::not_enough_rows_available(self.k));
+        for row in [left_row, right_row] {
+            if !self.usable_rows.contains(&row) {
+                return Err(Error::not_enough_rows_available_at(self.k, row));
+            }
         }
 
         self.permutation
@@ -123,7 +182,10 @@ impl<F: Field> Assignment<F> for Assembly<F> {
         to: Value<Assigned<F>>,
     ) -> Result<(), Error> {
         if !self.usable_rows.contains(&from_row) {
-            return Err(Error::not_enough_rows_available(self.k));
+            return Err(Error::not_enough_rows_available_at(self.k, from_row));
+        }
+        if self.preprocessed_fixed_columns.contains(&column) {
+            return Err(Error::PreprocessedFixedAssigned(column));
         }
 
         let col = self
@@ -139,10 +201,29 @@ impl<F: Field> Assignment<F> for Assembly<F> {
         Ok(())
     }
 
+    fn fill_advice_from_row(
+        &mut self,
+        _: Column<Advice>,
+        _: usize,
+        _: Value<Assigned<F>>,
+    ) -> Result<(), Error> {
+        // We only care about fixed columns here
+        Ok(())
+    }
+
     fn get_challenge(&self, _: Challenge) -> Value<F> {
         Value::unknown()
     }
 
+    fn current_phase(&self) -> u8 {
+        // Keygen synthesizes the circuit once, so all phases must be included.
+        u8::MAX
+    }
+
+    fn usable_rows(&self) -> Option<Range<usize>> {
+        Some(self.usable_rows.clone())
+    }
+
     fn annotate_column<A, AR>(&mut self, _annotation: A, _column: Column<Any>)
     where
         A: FnOnce() -> AR,
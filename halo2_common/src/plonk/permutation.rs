@@ -68,6 +68,25 @@ impl Argument {
     pub fn get_columns(&self) -> Vec<Column<Any>> {
         self.columns.clone()
     }
+
+    /// Returns the sets of columns that the argument is split into for a circuit of the given
+    /// degree. Each set has its own permutation product polynomial, and holds `degree - 2`
+    /// columns, except for the last one which may hold fewer.
+    ///
+    /// # Panics
+    ///
+    /// It panics if `degree` is below the degree required by the argument.
+    pub fn chunk_layout(&self, degree: usize) -> Vec<Vec<Column<Any>>> {
+        assert!(
+            degree >= self.required_degree(),
+            "the permutation argument requires a degree of at least {}",
+            self.required_degree()
+        );
+        self.columns
+            .chunks(degree - 2)
+            .map(|chunk| chunk.to_vec())
+            .collect()
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -11,6 +11,11 @@ use halo2curves::{Coordinates, CurveAffine};
 use std::io::{self, Read, Write};
 use std::marker::PhantomData;
 
+mod poseidon;
+pub use poseidon::{
+    base_to_limbs, PoseidonChallenge, PoseidonRead, PoseidonSpec, PoseidonSponge, PoseidonWrite,
+};
+
 /// Prefix to a prover's message soliciting a challenge
 const BLAKE2B_PREFIX_CHALLENGE: u8 = 0;
 
@@ -80,6 +85,9 @@ pub trait TranscriptWrite<C: CurveAffine, E: EncodedChallenge<C>>: Transcript<C,
 }
 
 /// Initializes transcript at verifier side.
+///
+/// The proof is read sequentially, so `reader` can be any [`Read`], such as a file or a
+/// socket, and the proof doesn't need to be loaded in memory.
 pub trait TranscriptReadBuffer<R: Read, C: CurveAffine, E: EncodedChallenge<C>>:
     TranscriptRead<C, E>
 {
@@ -88,6 +96,8 @@ pub trait TranscriptReadBuffer<R: Read, C: CurveAffine, E: EncodedChallenge<C>>:
 }
 
 /// Manages beginning and finishing of transcript pipeline.
+///
+/// The proof is only ever appended to `writer`. See [`StreamWriter`] to stream it.
 pub trait TranscriptWriterBuffer<W: Write, C: CurveAffine, E: EncodedChallenge<C>>:
     TranscriptWrite<C, E>
 {
@@ -384,6 +394,72 @@ where
     }
 }
 
+/// A writer which flushes the underlying writer after every write, and counts the bytes
+/// written, so that a proof can be streamed to a file or a socket.
+///
+/// The transcript writers only ever append to their writer, so the proof never needs to
+/// be buffered in memory.
+#[derive(Debug, Clone)]
+pub struct StreamWriter<W: Write> {
+    writer: W,
+    bytes_written: usize,
+}
+
+impl<W: Write> StreamWriter<W> {
+    /// Wraps `writer`.
+    pub fn new(writer: W) -> Self {
+        StreamWriter {
+            writer,
+            bytes_written: 0,
+        }
+    }
+
+    /// Returns the number of bytes written so far.
+    pub fn bytes_written(&self) -> usize {
+        self.bytes_written
+    }
+
+    /// Returns the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: Write> Write for StreamWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.writer.write(buf)?;
+        self.bytes_written += written;
+        self.writer.flush()?;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+impl<W: Write, C: CurveAffine> Blake2bWrite<StreamWriter<W>, C, Challenge255<C>>
+where
+    C::Scalar: FromUniformBytes<64>,
+{
+    /// Initialize a transcript which streams the proof to `writer`, flushing it after
+    /// every point and scalar.
+    pub fn init_streamed(writer: W) -> Self {
+        Self::init(StreamWriter::new(writer))
+    }
+}
+
+impl<W: Write, C: CurveAffine> Keccak256Write<StreamWriter<W>, C, Challenge255<C>>
+where
+    C::Scalar: FromUniformBytes<64>,
+{
+    /// Initialize a transcript which streams the proof to `writer`, flushing it after
+    /// every point and scalar.
+    pub fn init_streamed(writer: W) -> Self {
+        Self::init(StreamWriter::new(writer))
+    }
+}
+
 impl<W: Write, C: CurveAffine> Transcript<C, Challenge255<C>>
     for Blake2bWrite<W, C, Challenge255<C>>
 where
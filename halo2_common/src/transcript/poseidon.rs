@@ -0,0 +1,493 @@
+//! A transcript hashing with Poseidon over the scalar field of the curve, so that a
+//! circuit verifying the proof can re-hash it cheaply.
+
+use group::ff::{Field, PrimeField};
+use halo2curves::{Coordinates, CurveAffine};
+use std::io::{self, Read, Write};
+use std::marker::PhantomData;
+
+use super::{
+    EncodedChallenge, Transcript, TranscriptRead, TranscriptReadBuffer, TranscriptWrite,
+    TranscriptWriterBuffer,
+};
+
+/// The number of partial rounds for a width of `2 + i`, for 128 bits of security over a
+/// field of about 254 bits with the `x^5` S-box, as in circomlib.
+const PARTIAL_ROUNDS: [usize; 16] = [
+    56, 57, 56, 60, 60, 63, 64, 63, 60, 66, 60, 65, 70, 60, 64, 68,
+];
+
+/// The number of bytes of a limb of [`base_to_limbs`].
+const LIMB_BYTES: usize = 16;
+
+/// The parameters of the Poseidon permutation of width `T` over `F`, with the `x^5` S-box.
+///
+/// The round constants and the MDS matrix are generated with the Grain LFSR, as specified
+/// by the Poseidon paper and its reference implementation. With `T = 3` over the scalar
+/// field of BN254, they are those of circomlib.
+///
+/// The `x^5` S-box is a permutation only if 5 does not divide `p - 1`, which holds for the
+/// scalar fields of BN254 and of the Pasta curves.
+#[derive(Clone, Debug)]
+pub struct PoseidonSpec<F: PrimeField, const T: usize> {
+    full_rounds: usize,
+    partial_rounds: usize,
+    round_constants: Vec<[F; T]>,
+    mds: [[F; T]; T],
+}
+
+impl<F: PrimeField, const T: usize> Default for PoseidonSpec<F, T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<F: PrimeField, const T: usize> PoseidonSpec<F, T> {
+    /// Returns the parameters with 8 full rounds, and the number of partial rounds of
+    /// circomlib for the width `T`, which must be between 2 and 17.
+    pub fn new() -> Self {
+        assert!(
+            (2..2 + PARTIAL_ROUNDS.len()).contains(&T),
+            "no default number of rounds for width {T}"
+        );
+        Self::with_rounds(8, PARTIAL_ROUNDS[T - 2])
+    }
+
+    /// Returns the parameters with the given numbers of rounds.
+    pub fn with_rounds(full_rounds: usize, partial_rounds: usize) -> Self {
+        assert!(
+            full_rounds % 2 == 0,
+            "the number of full rounds must be even"
+        );
+
+        let mut grain = Grain::new(F::NUM_BITS, T, full_rounds, partial_rounds);
+        let round_constants = (0..full_rounds + partial_rounds)
+            .map(|_| {
+                let mut constants = [F::ZERO; T];
+                for constant in constants.iter_mut() {
+                    *constant = grain.next_field_element();
+                }
+                constants
+            })
+            .collect();
+
+        // A Cauchy matrix, `1 / (x_i + y_j)` for distinct `x_i` and `y_j`.
+        let mds = loop {
+            let elements: Vec<F> = (0..2 * T)
+                .map(|_| grain.next_field_element_without_rejection())
+                .collect();
+            let distinct = elements
+                .iter()
+                .enumerate()
+                .all(|(i, a)| elements[i + 1..].iter().all(|b| a != b));
+            let (xs, ys) = elements.split_at(T);
+            if !distinct
+                || xs
+                    .iter()
+                    .any(|x| ys.iter().any(|y| (*x + y).is_zero_vartime()))
+            {
+                continue;
+            }
+
+            let mut mds = [[F::ZERO; T]; T];
+            for (row, x) in mds.iter_mut().zip(xs) {
+                for (entry, y) in row.iter_mut().zip(ys) {
+                    *entry = (*x + y).invert().unwrap();
+                }
+            }
+            break mds;
+        };
+
+        Self {
+            full_rounds,
+            partial_rounds,
+            round_constants,
+            mds,
+        }
+    }
+
+    /// Returns the number of full rounds, half of them before the partial rounds.
+    pub fn full_rounds(&self) -> usize {
+        self.full_rounds
+    }
+
+    /// Returns the number of partial rounds, which apply the S-box to `state[0]` only.
+    pub fn partial_rounds(&self) -> usize {
+        self.partial_rounds
+    }
+
+    /// Returns the constants added to the state at the start of each round.
+    pub fn round_constants(&self) -> &[[F; T]] {
+        &self.round_constants
+    }
+
+    /// Returns the MDS matrix, which maps the state to `mds * state` at the end of each
+    /// round.
+    pub fn mds(&self) -> &[[F; T]; T] {
+        &self.mds
+    }
+
+    /// Applies the Poseidon permutation to `state`.
+    pub fn permute(&self, state: &mut [F; T]) {
+        let sbox = |x: F| x.square().square() * x;
+        let half = self.full_rounds / 2;
+        for (round, constants) in self.round_constants.iter().enumerate() {
+            for (value, constant) in state.iter_mut().zip(constants) {
+                *value += constant;
+            }
+            if round < half || round >= half + self.partial_rounds {
+                for value in state.iter_mut() {
+                    *value = sbox(*value);
+                }
+            } else {
+                state[0] = sbox(state[0]);
+            }
+
+            let mut mixed = [F::ZERO; T];
+            for (mixed, row) in mixed.iter_mut().zip(self.mds.iter()) {
+                *mixed = row
+                    .iter()
+                    .zip(state.iter())
+                    .fold(F::ZERO, |acc, (entry, value)| acc + *entry * value);
+            }
+            *state = mixed;
+        }
+    }
+}
+
+/// The Grain LFSR of the Poseidon reference implementation, which generates the round
+/// constants and the MDS matrix.
+struct Grain {
+    bits: [bool; 80],
+    head: usize,
+    field_size: u32,
+}
+
+impl Grain {
+    fn new(field_size: u32, width: usize, full_rounds: usize, partial_rounds: usize) -> Self {
+        let mut bits = Vec::with_capacity(80);
+        let mut append = |value: u64, len: usize| {
+            bits.extend((0..len).rev().map(|i| (value >> i) & 1 == 1));
+        };
+        // A prime field, the `x^alpha` S-box, and the parameters.
+        append(1, 2);
+        append(0, 4);
+        append(u64::from(field_size), 12);
+        append(width as u64, 12);
+        append(full_rounds as u64, 10);
+        append(partial_rounds as u64, 10);
+        append((1 << 30) - 1, 30);
+
+        let mut grain = Grain {
+            bits: bits.try_into().expect("80 bits"),
+            head: 0,
+            field_size,
+        };
+        for _ in 0..160 {
+            grain.step();
+        }
+        grain
+    }
+
+    fn step(&mut self) -> bool {
+        let bit = |i: usize| self.bits[(self.head + i) % 80];
+        let new_bit = bit(62) ^ bit(51) ^ bit(38) ^ bit(23) ^ bit(13) ^ bit(0);
+        self.bits[self.head] = new_bit;
+        self.head = (self.head + 1) % 80;
+        new_bit
+    }
+
+    /// Returns the next output bit: of each pair of bits, the second one is output if the
+    /// first one is set.
+    fn next_bit(&mut self) -> bool {
+        loop {
+            let output = self.step();
+            let bit = self.step();
+            if output {
+                return bit;
+            }
+        }
+    }
+
+    /// Returns the next `field_size` bits, most significant first, reduced modulo `p`.
+    fn next_field_element_without_rejection<F: PrimeField>(&mut self) -> F {
+        (0..self.field_size).fold(F::ZERO, |acc, _| {
+            let bit = if self.next_bit() { F::ONE } else { F::ZERO };
+            acc.double() + bit
+        })
+    }
+
+    /// Returns the first sequence of `field_size` bits, most significant first, which is
+    /// smaller than `p`.
+    ///
+    /// This assumes that the representation of `F` is little-endian.
+    fn next_field_element<F: PrimeField>(&mut self) -> F {
+        loop {
+            let mut repr = F::Repr::default();
+            for position in (0..self.field_size as usize).rev() {
+                if self.next_bit() {
+                    repr.as_mut()[position / 8] |= 1 << (position % 8);
+                }
+            }
+            if let Some(element) = Option::from(F::from_repr(repr)) {
+                return element;
+            }
+        }
+    }
+}
+
+/// A duplex sponge over the Poseidon permutation of width `T`, absorbing and squeezing
+/// `RATE` elements of `F` per permutation.
+///
+/// The state starts at zero, with the capacity in the first `T - RATE` elements.
+/// Absorbed elements are buffered, and added to the rate part of the state, followed by
+/// a permutation, every `RATE` elements. Squeezing adds the buffered elements, then one
+/// after them, permutes, and returns the first element of the rate part, so that every
+/// challenge is separated from the next one by a permutation.
+#[derive(Clone, Debug)]
+pub struct PoseidonSponge<F: PrimeField, const T: usize, const RATE: usize> {
+    spec: PoseidonSpec<F, T>,
+    state: [F; T],
+    buffer: Vec<F>,
+}
+
+impl<F: PrimeField, const T: usize, const RATE: usize> PoseidonSponge<F, T, RATE> {
+    /// Returns an empty sponge over the permutation of `spec`.
+    pub fn new(spec: PoseidonSpec<F, T>) -> Self {
+        assert!(
+            0 < RATE && RATE < T,
+            "the rate must be positive and smaller than the width"
+        );
+        Self {
+            spec,
+            state: [F::ZERO; T],
+            buffer: Vec::with_capacity(RATE),
+        }
+    }
+
+    /// Absorbs `element`.
+    pub fn absorb(&mut self, element: F) {
+        self.buffer.push(element);
+        if self.buffer.len() == RATE {
+            self.add_buffer();
+            self.spec.permute(&mut self.state);
+        }
+    }
+
+    /// Squeezes an element, which depends on all the elements absorbed so far.
+    pub fn squeeze(&mut self) -> F {
+        let padding = T - RATE + self.buffer.len();
+        self.add_buffer();
+        self.state[padding] += F::ONE;
+        self.spec.permute(&mut self.state);
+        self.state[T - RATE]
+    }
+
+    fn add_buffer(&mut self) {
+        for (value, element) in self.state[T - RATE..].iter_mut().zip(self.buffer.drain(..)) {
+            *value += element;
+        }
+    }
+}
+
+/// Returns the limbs of `coordinate` absorbed by the Poseidon transcript: its
+/// [`to_repr`](PrimeField::to_repr), as little-endian limbs of 128 bits, least significant
+/// first, so that each limb fits in the scalar field.
+///
+/// This assumes that the representation of the base field is little-endian.
+pub fn base_to_limbs<C: CurveAffine>(coordinate: &C::Base) -> Vec<C::Scalar> {
+    coordinate
+        .to_repr()
+        .as_ref()
+        .chunks(LIMB_BYTES)
+        .map(|limb| {
+            let mut bytes = [0u8; LIMB_BYTES];
+            bytes[..limb.len()].copy_from_slice(limb);
+            C::Scalar::from_u128(u128::from_le_bytes(bytes))
+        })
+        .collect()
+}
+
+/// A challenge squeezed from a Poseidon transcript, which is a scalar.
+#[derive(Copy, Clone, Debug)]
+pub struct PoseidonChallenge<C: CurveAffine>(C::Scalar);
+
+impl<C: CurveAffine> EncodedChallenge<C> for PoseidonChallenge<C> {
+    type Input = C::Scalar;
+
+    fn new(challenge_input: &C::Scalar) -> Self {
+        PoseidonChallenge(*challenge_input)
+    }
+
+    fn get_scalar(&self) -> C::Scalar {
+        self.0
+    }
+}
+
+fn absorb_point<C: CurveAffine, const T: usize, const RATE: usize>(
+    sponge: &mut PoseidonSponge<C::Scalar, T, RATE>,
+    point: C,
+) -> io::Result<()> {
+    let coords: Coordinates<C> = Option::from(point.coordinates()).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::Other,
+            "cannot write points at infinity to the transcript",
+        )
+    })?;
+    for limb in base_to_limbs::<C>(coords.x())
+        .into_iter()
+        .chain(base_to_limbs::<C>(coords.y()))
+    {
+        sponge.absorb(limb);
+    }
+    Ok(())
+}
+
+/// A transcript reader hashing with a [`PoseidonSponge`] of width `T` and rate `RATE` over
+/// the scalar field of `C`, with the default [`PoseidonSpec`].
+///
+/// The proof is encoded like with [`Blake2bRead`](super::Blake2bRead). A scalar is
+/// absorbed as itself, and a point as the [`base_to_limbs`] of its `x` coordinate followed
+/// by those of its `y` coordinate. Since the order of the points and scalars of a proof is
+/// fixed by the verifying key, they are absorbed without domain separation. A challenge
+/// is a squeezed element.
+#[derive(Debug, Clone)]
+pub struct PoseidonRead<
+    R: Read,
+    C: CurveAffine,
+    E: EncodedChallenge<C>,
+    const T: usize,
+    const RATE: usize,
+> {
+    sponge: PoseidonSponge<C::Scalar, T, RATE>,
+    reader: R,
+    _marker: PhantomData<(C, E)>,
+}
+
+impl<R: Read, C: CurveAffine, const T: usize, const RATE: usize>
+    TranscriptReadBuffer<R, C, PoseidonChallenge<C>>
+    for PoseidonRead<R, C, PoseidonChallenge<C>, T, RATE>
+{
+    /// Initialize a transcript given an input buffer.
+    fn init(reader: R) -> Self {
+        PoseidonRead {
+            sponge: PoseidonSponge::new(PoseidonSpec::new()),
+            reader,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<R: Read, C: CurveAffine, const T: usize, const RATE: usize>
+    TranscriptRead<C, PoseidonChallenge<C>> for PoseidonRead<R, C, PoseidonChallenge<C>, T, RATE>
+{
+    fn read_point(&mut self) -> io::Result<C> {
+        let mut compressed = C::Repr::default();
+        self.reader.read_exact(compressed.as_mut())?;
+        let point: C = Option::from(C::from_bytes(&compressed)).ok_or_else(|| {
+            io::Error::new(io::ErrorKind::Other, "invalid point encoding in proof")
+        })?;
+        self.common_point(point)?;
+
+        Ok(point)
+    }
+
+    fn read_scalar(&mut self) -> io::Result<C::Scalar> {
+        let mut data = <C::Scalar as PrimeField>::Repr::default();
+        self.reader.read_exact(data.as_mut())?;
+        let scalar: C::Scalar = Option::from(C::Scalar::from_repr(data)).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::Other,
+                "invalid field element encoding in proof",
+            )
+        })?;
+        self.common_scalar(scalar)?;
+
+        Ok(scalar)
+    }
+}
+
+impl<R: Read, C: CurveAffine, const T: usize, const RATE: usize> Transcript<C, PoseidonChallenge<C>>
+    for PoseidonRead<R, C, PoseidonChallenge<C>, T, RATE>
+{
+    fn squeeze_challenge(&mut self) -> PoseidonChallenge<C> {
+        PoseidonChallenge::new(&self.sponge.squeeze())
+    }
+
+    fn common_point(&mut self, point: C) -> io::Result<()> {
+        absorb_point(&mut self.sponge, point)
+    }
+
+    fn common_scalar(&mut self, scalar: C::Scalar) -> io::Result<()> {
+        self.sponge.absorb(scalar);
+
+        Ok(())
+    }
+}
+
+/// A transcript writer hashing with a [`PoseidonSponge`], see [`PoseidonRead`].
+#[derive(Debug, Clone)]
+pub struct PoseidonWrite<
+    W: Write,
+    C: CurveAffine,
+    E: EncodedChallenge<C>,
+    const T: usize,
+    const RATE: usize,
+> {
+    sponge: PoseidonSponge<C::Scalar, T, RATE>,
+    writer: W,
+    _marker: PhantomData<(C, E)>,
+}
+
+impl<W: Write, C: CurveAffine, const T: usize, const RATE: usize>
+    TranscriptWriterBuffer<W, C, PoseidonChallenge<C>>
+    for PoseidonWrite<W, C, PoseidonChallenge<C>, T, RATE>
+{
+    /// Initialize a transcript given an output buffer.
+    fn init(writer: W) -> Self {
+        PoseidonWrite {
+            sponge: PoseidonSponge::new(PoseidonSpec::new()),
+            writer,
+            _marker: PhantomData,
+        }
+    }
+
+    fn finalize(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: Write, C: CurveAffine, const T: usize, const RATE: usize>
+    TranscriptWrite<C, PoseidonChallenge<C>>
+    for PoseidonWrite<W, C, PoseidonChallenge<C>, T, RATE>
+{
+    fn write_point(&mut self, point: C) -> io::Result<()> {
+        self.common_point(point)?;
+        let compressed = point.to_bytes();
+        self.writer.write_all(compressed.as_ref())
+    }
+
+    fn write_scalar(&mut self, scalar: C::Scalar) -> io::Result<()> {
+        self.common_scalar(scalar)?;
+        let data = scalar.to_repr();
+        self.writer.write_all(data.as_ref())
+    }
+}
+
+impl<W: Write, C: CurveAffine, const T: usize, const RATE: usize>
+    Transcript<C, PoseidonChallenge<C>> for PoseidonWrite<W, C, PoseidonChallenge<C>, T, RATE>
+{
+    fn squeeze_challenge(&mut self) -> PoseidonChallenge<C> {
+        PoseidonChallenge::new(&self.sponge.squeeze())
+    }
+
+    fn common_point(&mut self, point: C) -> io::Result<()> {
+        absorb_point(&mut self.sponge, point)
+    }
+
+    fn common_scalar(&mut self, scalar: C::Scalar) -> io::Result<()> {
+        self.sponge.absorb(scalar);
+
+        Ok(())
+    }
+}
@@ -4,22 +4,81 @@ use halo2_common::plonk::{
     circuit::{Challenge, Column},
     permutation,
     sealed::{self, SealedPhase},
-    Assigned, Assignment, Circuit, ConstraintSystem, Error, FirstPhase, FloorPlanner, SecondPhase,
-    Selector, ThirdPhase,
+    Assigned, Assignment, AssignmentHooks, Circuit, ConstraintSystem, Error, FloorPlanner,
+    InstrumentedAssignment, PhaseId, Selector,
 };
 use halo2_middleware::circuit::{Advice, Any, CompiledCircuitV2, Fixed, Instance, PreprocessingV2};
-use halo2_middleware::ff::{BatchInvert, Field};
+use halo2_middleware::ff::Field;
 use std::collections::BTreeSet;
 use std::collections::HashMap;
 use std::fmt::Debug;
-use std::ops::RangeTo;
+use std::ops::{Range, RangeTo};
 
 pub mod floor_planner;
 mod table_layouter;
+mod witness;
 
 // Re-exports from common
 pub use halo2_common::circuit::floor_planner::single_pass::SimpleFloorPlanner;
 pub use halo2_common::circuit::{layouter, Layouter, Value};
+pub use witness::{generate_witness, Witness};
+
+/// How the selectors of a circuit are turned into fixed columns during compilation.
+///
+/// The strategy determines the fixed columns and the gates of the compiled circuit, and thus
+/// its verifying key: compiling the same circuit with [`SelectorStrategy::Compress`] and with
+/// [`SelectorStrategy::DirectToFixed`] results in different verifying keys, whenever the
+/// circuit has selectors. A proof can only be verified with a key that was generated with the
+/// same strategy as the proving key.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SelectorStrategy {
+    /// Multiplex the selectors into as few fixed columns as the degree of the constraint system
+    /// allows. This is what [`compile_circuit`] does when `compress_selectors` is `true`.
+    #[default]
+    Compress,
+    /// Turn every selector into its own fixed column. This is what [`compile_circuit`] does
+    /// when `compress_selectors` is `false`.
+    DirectToFixed,
+    /// Keep the selectors as they are. The constraint system still contains
+    /// `Expression::Selector` nodes, so it can't be used for keygen and is meant for tooling.
+    KeepRaw,
+}
+
+/// Options of [`compile_circuit_with_options`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CompileOptions {
+    /// How the selectors are turned into fixed columns.
+    pub selector_strategy: SelectorStrategy,
+}
+
+/// The output of [`compile_circuit_with_options`], depending on its [`SelectorStrategy`].
+#[derive(Debug)]
+pub enum CompileOutput<F: Field, Config> {
+    /// The selectors were turned into fixed columns, with [`SelectorStrategy::Compress`] or
+    /// [`SelectorStrategy::DirectToFixed`].
+    Fixed {
+        /// The compiled circuit, whose last fixed columns hold the selectors.
+        circuit: CompiledCircuitV2<F>,
+        /// The configuration returned by `Circuit::configure`.
+        config: Config,
+        /// The constraint system, with the selectors replaced by fixed columns.
+        cs: ConstraintSystem<F>,
+        /// The fixed column that holds each selector, indexed by selector. Several selectors
+        /// share a column when they are compressed.
+        selector_map: Vec<Column<Fixed>>,
+    },
+    /// The selectors were kept, with [`SelectorStrategy::KeepRaw`].
+    Raw {
+        /// The fixed columns and copy constraints of the circuit, without the selectors.
+        preprocessing: PreprocessingV2<F>,
+        /// The configuration returned by `Circuit::configure`.
+        config: Config,
+        /// The constraint system, which still contains the selectors.
+        cs: ConstraintSystem<F>,
+        /// The activations of each selector, arranged as [selector][row].
+        selectors: Vec<Vec<bool>>,
+    },
+}
 
 /// Compile a circuit.  Runs configure and synthesize on the circuit in order to materialize the
 /// circuit into its columns and the column configuration; as well as doing the fixed column and
@@ -39,6 +98,29 @@ pub fn compile_circuit<F: Field, ConcreteCircuit: Circuit<F>>(
     ),
     Error,
 > {
+    let selector_strategy = if compress_selectors {
+        SelectorStrategy::Compress
+    } else {
+        SelectorStrategy::DirectToFixed
+    };
+    match compile_circuit_with_options(k, circuit, &CompileOptions { selector_strategy })? {
+        CompileOutput::Fixed {
+            circuit,
+            config,
+            cs,
+            ..
+        } => Ok((circuit, config, cs)),
+        CompileOutput::Raw { .. } => unreachable!("the selectors are turned into fixed columns"),
+    }
+}
+
+/// Compile a circuit like [`compile_circuit`], turning its selectors into fixed columns
+/// according to the [`SelectorStrategy`] of the `options`.
+pub fn compile_circuit_with_options<F: Field, ConcreteCircuit: Circuit<F>>(
+    k: u32,
+    circuit: &ConcreteCircuit,
+    options: &CompileOptions,
+) -> Result<CompileOutput<F, ConcreteCircuit::Config>, Error> {
     let n = 2usize.pow(k);
     let mut cs = ConstraintSystem::default();
     #[cfg(feature = "circuit-params")]
@@ -56,9 +138,20 @@ pub fn compile_circuit<F: Field, ConcreteCircuit: Circuit<F>>(
         fixed: vec![vec![F::ZERO.into(); n]; cs.num_fixed_columns],
         permutation: permutation::Assembly::new(n, &cs.permutation),
         selectors: vec![vec![false; n]; cs.num_selectors],
-        usable_rows: 0..n - (cs.blinding_factors() + 1),
+        usable_rows: cs.usable_rows(k),
+        preprocessed_fixed_columns: cs
+            .preprocessed_fixed_columns
+            .iter()
+            .map(|preprocessed| preprocessed.column)
+            .collect(),
         _marker: std::marker::PhantomData,
     };
+    for (column, row) in cs.boundary_cells(&assembly.usable_rows) {
+        assembly.fixed[column.index()][row] = F::ONE.into();
+    }
+    for (column, row, value) in cs.preprocessed_fixed_cells(&assembly.usable_rows) {
+        assembly.fixed[column.index()][row] = value.into();
+    }
 
     // Synthesize the circuit to obtain URS
     ConcreteCircuit::FloorPlanner::synthesize(
@@ -69,36 +162,52 @@ pub fn compile_circuit<F: Field, ConcreteCircuit: Circuit<F>>(
     )?;
 
     let mut fixed = batch_invert_assigned(assembly.fixed);
-    let (cs, selector_polys) = if compress_selectors {
-        cs.compress_selectors(assembly.selectors.clone())
-    } else {
-        // After this, the ConstraintSystem should not have any selectors: `verify` does not need them, and `keygen_pk` regenerates `cs` from scratch anyways.
-        let selectors = std::mem::take(&mut assembly.selectors);
-        cs.directly_convert_selectors_to_fixed(selectors)
+    let copies = halo2_middleware::permutation::AssemblyMid {
+        copies: assembly.permutation.copies,
+    };
+    let (cs, selector_polys) = match options.selector_strategy {
+        SelectorStrategy::Compress => cs.compress_selectors(assembly.selectors),
+        SelectorStrategy::DirectToFixed => {
+            // After this, the ConstraintSystem should not have any selectors: `verify` does not need them, and `keygen_pk` regenerates `cs` from scratch anyways.
+            cs.directly_convert_selectors_to_fixed(assembly.selectors)
+        }
+        SelectorStrategy::KeepRaw => {
+            return Ok(CompileOutput::Raw {
+                preprocessing: PreprocessingV2 {
+                    permutation: copies,
+                    fixed,
+                },
+                config,
+                cs,
+                selectors: assembly.selectors,
+            });
+        }
     };
-    fixed.extend(selector_polys.into_iter());
+    fixed.extend(selector_polys);
 
     let preprocessing = PreprocessingV2 {
-        permutation: halo2_middleware::permutation::AssemblyMid {
-            copies: assembly.permutation.copies,
-        },
+        permutation: copies,
         fixed,
     };
 
-    Ok((
-        CompiledCircuitV2 {
+    Ok(CompileOutput::Fixed {
+        circuit: CompiledCircuitV2 {
             cs: cs.clone().into(),
             preprocessing,
         },
         config,
+        selector_map: cs.selector_map.clone(),
         cs,
-    ))
+    })
 }
 
 pub struct WitnessCollection<'a, F: Field> {
     pub k: u32,
     pub current_phase: sealed::Phase,
     pub advice: Vec<Vec<Assigned<F>>>,
+    /// Whether each advice cell was assigned, so that filling a column doesn't overwrite
+    /// the cells assigned by other regions.
+    pub assigned: Vec<Vec<bool>>,
     // pub unblinded_advice: HashSet<usize>,
     pub challenges: &'a HashMap<usize, F>,
     pub instances: &'a [&'a [F]],
@@ -129,6 +238,21 @@ impl<'a, F: Field> Assignment<F> for WitnessCollection<'a, F> {
         Ok(())
     }
 
+    fn enable_selector_range<A, AR>(
+        &mut self,
+        _: A,
+        _: &Selector,
+        _: Range<usize>,
+    ) -> Result<(), Error>
+    where
+        A: Fn() -> AR,
+        AR: Into<String>,
+    {
+        // We only care about advice columns here
+
+        Ok(())
+    }
+
     fn annotate_column<A, AR>(&mut self, _annotation: A, _column: Column<Any>)
     where
         A: FnOnce() -> AR,
@@ -139,7 +263,7 @@ impl<'a, F: Field> Assignment<F> for WitnessCollection<'a, F> {
 
     fn query_instance(&self, column: Column<Instance>, row: usize) -> Result<Value<F>, Error> {
         if !self.usable_rows.contains(&row) {
-            return Err(Error::not_enough_rows_available(self.k));
+            return Err(Error::not_enough_rows_available_at(self.k, row));
         }
 
         self.instances
@@ -163,12 +287,12 @@ impl<'a, F: Field> Assignment<F> for WitnessCollection<'a, F> {
         AR: Into<String>,
     {
         // Ignore assignment of advice column in different phase than current one.
-        if self.current_phase.0 != column.column_type().phase {
+        if !column.in_phase(self.current_phase) {
             return Ok(());
         }
 
         if !self.usable_rows.contains(&row) {
-            return Err(Error::not_enough_rows_available(self.k));
+            return Err(Error::not_enough_rows_available_at(self.k, row));
         }
 
         *self
@@ -176,6 +300,62 @@ impl<'a, F: Field> Assignment<F> for WitnessCollection<'a, F> {
             .get_mut(column.index())
             .and_then(|v| v.get_mut(row))
             .ok_or(Error::BoundsFailure)? = to().into_field().assign()?;
+        self.assigned[column.index()][row] = true;
+
+        Ok(())
+    }
+
+    fn try_assign_advice<V, VR, A, AR>(
+        &mut self,
+        annotation: A,
+        column: Column<Advice>,
+        row: usize,
+        to: V,
+    ) -> Result<(), Error>
+    where
+        V: FnOnce() -> Result<Value<VR>, Error>,
+        VR: Into<Assigned<F>>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        // Ignore assignment of advice column in different phase than current one, without
+        // running the witness computation.
+        if !column.in_phase(self.current_phase) {
+            return Ok(());
+        }
+
+        let value = to()?;
+        self.assign_advice(annotation, column, row, || value)
+    }
+
+    fn assign_advice_column(
+        &mut self,
+        column: Column<Advice>,
+        start_row: usize,
+        values: &[Value<Assigned<F>>],
+    ) -> Result<(), Error> {
+        // Ignore assignment of advice column in different phase than current one.
+        if !column.in_phase(self.current_phase) {
+            return Ok(());
+        }
+
+        let end_row = start_row + values.len();
+        if end_row > self.usable_rows.end {
+            return Err(Error::not_enough_rows_available_at(
+                self.k,
+                start_row.max(self.usable_rows.end),
+            ));
+        }
+
+        let cells = self
+            .advice
+            .get_mut(column.index())
+            .and_then(|v| v.get_mut(start_row..end_row))
+            .ok_or(Error::BoundsFailure)?;
+        for (cell, value) in cells.iter_mut().zip(values.iter()) {
+            *cell = value.assign()?;
+        }
+        self.assigned[column.index()][start_row..end_row].fill(true);
 
         Ok(())
     }
@@ -213,6 +393,42 @@ impl<'a, F: Field> Assignment<F> for WitnessCollection<'a, F> {
         Ok(())
     }
 
+    fn fill_advice_from_row(
+        &mut self,
+        column: Column<Advice>,
+        from_row: usize,
+        to: Value<Assigned<F>>,
+    ) -> Result<(), Error> {
+        // Ignore assignment of advice column in different phase than current one.
+        if !column.in_phase(self.current_phase) {
+            return Ok(());
+        }
+
+        if from_row > self.usable_rows.end {
+            return Err(Error::not_enough_rows_available_at(self.k, from_row));
+        }
+
+        let filler = to.assign()?;
+        let cells = self
+            .advice
+            .get_mut(column.index())
+            .and_then(|v| v.get_mut(from_row..self.usable_rows.end))
+            .ok_or(Error::BoundsFailure)?;
+        // The cells assigned by other regions, which may be placed below this one, keep
+        // their values.
+        for (cell, assigned) in cells
+            .iter_mut()
+            .zip(self.assigned[column.index()][from_row..].iter_mut())
+        {
+            if !*assigned {
+                *cell = filler;
+                *assigned = true;
+            }
+        }
+
+        Ok(())
+    }
+
     fn get_challenge(&self, challenge: Challenge) -> Value<F> {
         self.challenges
             .get(&challenge.index())
@@ -221,6 +437,14 @@ impl<'a, F: Field> Assignment<F> for WitnessCollection<'a, F> {
             .unwrap_or_else(Value::unknown)
     }
 
+    fn current_phase(&self) -> u8 {
+        self.current_phase.0
+    }
+
+    fn usable_rows(&self) -> Option<Range<usize>> {
+        Some(0..self.usable_rows.end)
+    }
+
     fn push_namespace<NR, N>(&mut self, _: N)
     where
         NR: Into<String>,
@@ -234,6 +458,30 @@ impl<'a, F: Field> Assignment<F> for WitnessCollection<'a, F> {
     }
 }
 
+/// The values of the challenges squeezed after a phase of proof creation, and of those
+/// of the previous phases, returned by `ProverState::commit_phase` in `halo2_proofs` and
+/// passed to [`WitnessCalculator::next_phase`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ChallengeValues<F: Field>(HashMap<usize, F>);
+
+impl<F: Field> ChallengeValues<F> {
+    /// Returns the value of `challenge`, or `None` if it hasn't been squeezed yet.
+    pub fn get(&self, challenge: Challenge) -> Option<F> {
+        self.0.get(&challenge.index()).copied()
+    }
+
+    /// Returns the values of the challenges, by index.
+    pub fn as_map(&self) -> &HashMap<usize, F> {
+        &self.0
+    }
+}
+
+impl<F: Field> From<HashMap<usize, F>> for ChallengeValues<F> {
+    fn from(values: HashMap<usize, F>) -> Self {
+        ChallengeValues(values)
+    }
+}
+
 /// Witness calculator.  Frontend function
 #[derive(Debug)]
 pub struct WitnessCalculator<'a, F: Field, ConcreteCircuit: Circuit<F>> {
@@ -270,11 +518,35 @@ impl<'a, F: Field, ConcreteCircuit: Circuit<F>> WitnessCalculator<'a, F, Concret
         }
     }
 
+    /// Returns the phase of the next call to [`WitnessCalculator::next_phase`].
+    pub fn phase(&self) -> u8 {
+        self.next_phase
+    }
+
+    /// Calculate the witness of the next phase, given the challenges squeezed after the
+    /// previous phases, which are empty for the first phase.
+    pub fn next_phase(
+        &mut self,
+        challenges: &ChallengeValues<F>,
+    ) -> Result<Vec<Option<Vec<F>>>, Error> {
+        self.calc(self.next_phase, challenges.as_map())
+    }
+
     /// Calculate witness at phase
     pub fn calc(
         &mut self,
         phase: u8,
         challenges: &HashMap<usize, F>,
+    ) -> Result<Vec<Option<Vec<F>>>, Error> {
+        self.calc_with_hooks(phase, challenges, ())
+    }
+
+    /// Calculate witness at phase, reporting the progress of synthesis to `hooks`.
+    pub fn calc_with_hooks<H: AssignmentHooks + layouter::SyncDeps>(
+        &mut self,
+        phase: u8,
+        challenges: &HashMap<usize, F>,
+        hooks: H,
     ) -> Result<Vec<Option<Vec<F>>>, Error> {
         if phase != self.next_phase {
             return Err(Error::Other(format!(
@@ -282,17 +554,15 @@ impl<'a, F: Field, ConcreteCircuit: Circuit<F>> WitnessCalculator<'a, F, Concret
                 self.next_phase, phase
             )));
         }
-        let current_phase = match phase {
-            0 => FirstPhase.to_sealed(),
-            1 => SecondPhase.to_sealed(),
-            2 => ThirdPhase.to_sealed(),
-            _ => unreachable!("only phase [0,2] supported"),
-        };
+        let current_phase = PhaseId::try_from(phase)
+            .expect("only phase [0,2] supported")
+            .to_sealed();
 
         let mut witness = WitnessCollection {
             k: self.k,
             current_phase,
             advice: vec![vec![Assigned::Zero; self.n]; self.cs.num_advice_columns],
+            assigned: vec![vec![false; self.n]; self.cs.num_advice_columns],
             instances: self.instances,
             challenges,
             // The prover will not be allowed to assign values to advice
@@ -305,12 +575,11 @@ impl<'a, F: Field, ConcreteCircuit: Circuit<F>> WitnessCalculator<'a, F, Concret
 
         // Synthesize the circuit to obtain the witness and other information.
         ConcreteCircuit::FloorPlanner::synthesize(
-            &mut witness,
+            &mut InstrumentedAssignment::new(&mut witness, hooks),
             self.circuit,
             self.config.clone(),
             self.cs.constants.clone(),
-        )
-        .expect("todo");
+        )?;
 
         let column_indices = self
             .cs
@@ -342,47 +611,11 @@ impl<'a, F: Field, ConcreteCircuit: Circuit<F>> WitnessCalculator<'a, F, Concret
     }
 }
 
-// Turn vectors of `Assigned<F>` into vectors of `F` by evaluation the divisions in `Assigned<F>`
-// using batched inversions.
+// Turn vectors of `Assigned<F>` into vectors of `F` by evaluating the divisions in
+// `Assigned<F>` using batched inversions.
 fn batch_invert_assigned<F: Field>(assigned: Vec<Vec<Assigned<F>>>) -> Vec<Vec<F>> {
-    let mut assigned_denominators: Vec<_> = assigned
-        .iter()
-        .map(|f| {
-            f.iter()
-                .map(|value| value.denominator())
-                .collect::<Vec<_>>()
-        })
-        .collect();
-
-    assigned_denominators
-        .iter_mut()
-        .flat_map(|f| {
-            f.iter_mut()
-                // If the denominator is trivial, we can skip it, reducing the
-                // size of the batch inversion.
-                .filter_map(|d| d.as_mut())
-        })
-        .batch_invert();
-
     assigned
         .iter()
-        .zip(assigned_denominators)
-        .map(|(poly, inv_denoms)| {
-            poly_invert(poly, inv_denoms.into_iter().map(|d| d.unwrap_or(F::ONE)))
-        })
-        .collect()
-}
-
-// Turn a slice of `Assigned<F>` into a vector of F by multiplying each numerator with the elements
-// from `inv_denoms`, assuming that `inv_denoms` are the inverted denominators of the
-// `Assigned<F>`.
-fn poly_invert<F: Field>(
-    poly: &[Assigned<F>],
-    inv_denoms: impl Iterator<Item = F> + ExactSizeIterator,
-) -> Vec<F> {
-    assert_eq!(inv_denoms.len(), poly.len());
-    poly.iter()
-        .zip(inv_denoms)
-        .map(|(a, inv_den)| a.numerator() * inv_den)
+        .map(|poly| Assigned::batch_evaluate(poly))
         .collect()
 }
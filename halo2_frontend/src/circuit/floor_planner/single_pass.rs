@@ -5,8 +5,9 @@ mod tests {
 
     use super::SimpleFloorPlanner;
     use crate::dev::MockProver;
+    use halo2_common::circuit::Value;
     use halo2_common::plonk::{circuit::Column, Circuit, ConstraintSystem, Error};
-    use halo2_middleware::circuit::Advice;
+    use halo2_middleware::circuit::{Advice, Any, Fixed};
 
     #[test]
     fn not_enough_columns_for_constants() {
@@ -53,4 +54,53 @@ mod tests {
             Error::NotEnoughColumnsForConstants,
         ));
     }
+
+    #[test]
+    fn constrain_constant_on_fixed_cell() {
+        struct MyCircuit {}
+
+        impl Circuit<vesta::Scalar> for MyCircuit {
+            type Config = Column<Fixed>;
+            type FloorPlanner = SimpleFloorPlanner;
+            #[cfg(feature = "circuit-params")]
+            type Params = ();
+
+            fn without_witnesses(&self) -> Self {
+                MyCircuit {}
+            }
+
+            fn configure(meta: &mut ConstraintSystem<vesta::Scalar>) -> Self::Config {
+                let fixed = meta.fixed_column();
+                meta.enable_equality(fixed);
+                fixed
+            }
+
+            fn synthesize(
+                &self,
+                config: Self::Config,
+                mut layouter: impl crate::circuit::Layouter<vesta::Scalar>,
+            ) -> Result<(), Error> {
+                layouter.assign_region(
+                    || "constrain fixed to constant",
+                    |mut region| {
+                        let cell = region.assign_fixed(
+                            || "one",
+                            config,
+                            0,
+                            || Value::known(vesta::Scalar::one()),
+                        )?;
+                        region.constrain_constant(cell.cell(), vesta::Scalar::one())
+                    },
+                )?;
+
+                Ok(())
+            }
+        }
+
+        let circuit = MyCircuit {};
+        assert!(matches!(
+            MockProver::run(3, &circuit, vec![]).unwrap_err(),
+            Error::ConstrainConstantOnFixed(column) if column == Column::new(0, Any::Fixed),
+        ));
+    }
 }
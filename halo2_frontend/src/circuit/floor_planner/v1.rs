@@ -7,8 +7,9 @@ mod tests {
     use halo2curves::pasta::vesta;
 
     use crate::dev::MockProver;
+    use halo2_common::circuit::Value;
     use halo2_common::plonk::{circuit::Column, Circuit, ConstraintSystem, Error};
-    use halo2_middleware::circuit::Advice;
+    use halo2_middleware::circuit::{Advice, Any, Fixed};
 
     #[test]
     fn not_enough_columns_for_constants() {
@@ -55,4 +56,210 @@ mod tests {
             Error::NotEnoughColumnsForConstants,
         ));
     }
+
+    #[test]
+    fn constrain_constant_on_fixed_cell() {
+        struct MyCircuit {}
+
+        impl Circuit<vesta::Scalar> for MyCircuit {
+            type Config = Column<Fixed>;
+            type FloorPlanner = super::V1;
+            #[cfg(feature = "circuit-params")]
+            type Params = ();
+
+            fn without_witnesses(&self) -> Self {
+                MyCircuit {}
+            }
+
+            fn configure(meta: &mut ConstraintSystem<vesta::Scalar>) -> Self::Config {
+                let fixed = meta.fixed_column();
+                meta.enable_equality(fixed);
+                fixed
+            }
+
+            fn synthesize(
+                &self,
+                config: Self::Config,
+                mut layouter: impl crate::circuit::Layouter<vesta::Scalar>,
+            ) -> Result<(), Error> {
+                layouter.assign_region(
+                    || "constrain fixed to constant",
+                    |mut region| {
+                        let cell = region.assign_fixed(
+                            || "one",
+                            config,
+                            0,
+                            || Value::known(vesta::Scalar::one()),
+                        )?;
+                        region.constrain_constant(cell.cell(), vesta::Scalar::one())
+                    },
+                )?;
+
+                Ok(())
+            }
+        }
+
+        let circuit = MyCircuit {};
+        assert!(matches!(
+            MockProver::run(3, &circuit, vec![]).unwrap_err(),
+            Error::ConstrainConstantOnFixed(column) if column == Column::new(0, Any::Fixed),
+        ));
+    }
+
+    #[test]
+    fn assign_region_indexed() {
+        use crate::circuit::{Layouter, SimpleFloorPlanner};
+        use halo2_common::plonk::FloorPlanner;
+        use std::{cell::RefCell, marker::PhantomData, rc::Rc};
+
+        struct MyCircuit<P> {
+            indices: Rc<RefCell<Vec<usize>>>,
+            _marker: PhantomData<P>,
+        }
+
+        impl<P: FloorPlanner> Circuit<vesta::Scalar> for MyCircuit<P> {
+            type Config = Column<Advice>;
+            type FloorPlanner = P;
+            #[cfg(feature = "circuit-params")]
+            type Params = ();
+
+            fn without_witnesses(&self) -> Self {
+                MyCircuit {
+                    indices: self.indices.clone(),
+                    _marker: PhantomData,
+                }
+            }
+
+            fn configure(meta: &mut ConstraintSystem<vesta::Scalar>) -> Self::Config {
+                meta.advice_column()
+            }
+
+            fn synthesize(
+                &self,
+                config: Self::Config,
+                mut layouter: impl Layouter<vesta::Scalar>,
+            ) -> Result<(), Error> {
+                for rows in 1..=3 {
+                    let (index, cell) = layouter.assign_region_indexed(
+                        || format!("{rows} rows"),
+                        |mut region| {
+                            let mut cell = None;
+                            for offset in 0..rows {
+                                cell = Some(region.assign_advice(
+                                    || "zero",
+                                    config,
+                                    offset,
+                                    || Value::known(vesta::Scalar::zero()),
+                                )?);
+                            }
+                            Ok(cell.unwrap())
+                        },
+                    )?;
+                    // In the measurement pass, the cells are those of the region's shape.
+                    assert_eq!(index, cell.cell().region_index);
+                    self.indices.borrow_mut().push(index.index());
+                }
+
+                Ok(())
+            }
+        }
+
+        fn indices<P: FloorPlanner>() -> Vec<usize> {
+            let circuit = MyCircuit::<P> {
+                indices: Rc::new(RefCell::new(vec![])),
+                _marker: PhantomData,
+            };
+            MockProver::run(4, &circuit, vec![])
+                .unwrap()
+                .verify()
+                .unwrap();
+            circuit.indices.take()
+        }
+
+        // The measurement pass and the assignment pass see the same indices.
+        assert_eq!(indices::<super::V1>(), [0, 1, 2, 0, 1, 2]);
+        assert_eq!(indices::<SimpleFloorPlanner>(), [0, 1, 2]);
+    }
+
+    #[test]
+    fn next_instance_slot() {
+        use crate::circuit::{Layouter, SimpleFloorPlanner};
+        use halo2_common::plonk::FloorPlanner;
+        use halo2_middleware::circuit::Instance;
+        use std::{cell::RefCell, marker::PhantomData, rc::Rc};
+
+        struct MyCircuit<P> {
+            rows: Rc<RefCell<Vec<usize>>>,
+            _marker: PhantomData<P>,
+        }
+
+        impl<P: FloorPlanner> Circuit<vesta::Scalar> for MyCircuit<P> {
+            type Config = (Column<Advice>, Column<Instance>, Column<Instance>);
+            type FloorPlanner = P;
+            #[cfg(feature = "circuit-params")]
+            type Params = ();
+
+            fn without_witnesses(&self) -> Self {
+                MyCircuit {
+                    rows: self.rows.clone(),
+                    _marker: PhantomData,
+                }
+            }
+
+            fn configure(meta: &mut ConstraintSystem<vesta::Scalar>) -> Self::Config {
+                let advice = meta.advice_column();
+                let instance = meta.instance_column();
+                let other = meta.instance_column();
+                meta.enable_equality(advice);
+                meta.enable_equality(instance);
+                (advice, instance, other)
+            }
+
+            fn synthesize(
+                &self,
+                (advice, instance, other): Self::Config,
+                mut layouter: impl Layouter<vesta::Scalar>,
+            ) -> Result<(), Error> {
+                for value in 0..3u64 {
+                    let cell = layouter.assign_region(
+                        || "value",
+                        |mut region| {
+                            region.assign_advice(
+                                || "value",
+                                advice,
+                                0,
+                                || Value::known(vesta::Scalar::from(value)),
+                            )
+                        },
+                    )?;
+                    let row = layouter
+                        .namespace(|| "expose")
+                        .constrain_instance_next(cell.cell(), instance)?;
+                    self.rows.borrow_mut().push(row);
+                }
+                // Each instance column is allocated independently.
+                let row = layouter.next_instance_slot(other)?;
+                self.rows.borrow_mut().push(row);
+
+                Ok(())
+            }
+        }
+
+        fn rows<P: FloorPlanner>() -> Vec<usize> {
+            let circuit = MyCircuit::<P> {
+                rows: Rc::new(RefCell::new(vec![])),
+                _marker: PhantomData,
+            };
+            let instances = vec![(0..3u64).map(vesta::Scalar::from).collect(), vec![]];
+            MockProver::run(4, &circuit, instances)
+                .unwrap()
+                .verify()
+                .unwrap();
+            circuit.rows.take()
+        }
+
+        // The measurement pass and the assignment pass hand out the same rows.
+        assert_eq!(rows::<super::V1>(), [0, 1, 2, 0, 0, 1, 2, 0]);
+        assert_eq!(rows::<SimpleFloorPlanner>(), [0, 1, 2, 0]);
+    }
 }
@@ -7,7 +7,8 @@ mod tests {
         dev::MockProver,
     };
     use halo2_common::circuit::Value;
-    use halo2_common::plonk::{Circuit, ConstraintSystem, Error, TableColumn};
+    use halo2_common::plonk::{Circuit, Column, ConstraintSystem, Error, Selector, TableColumn};
+    use halo2_middleware::circuit::Advice;
     use halo2_middleware::poly::Rotation;
 
     #[test]
@@ -256,4 +257,221 @@ mod tests {
             "TableColumn { inner: Column { index: 0, column_type: Fixed } } has length 2 while TableColumn { inner: Column { index: 1, column_type: Fixed } } has length 1"
         );
     }
+
+    #[test]
+    fn table_set_default() {
+        const K: u32 = 4;
+
+        #[derive(Clone)]
+        struct DefaultCircuitConfig {
+            a: Column<Advice>,
+            q: Selector,
+            table: TableColumn,
+        }
+
+        /// Looks up `input` in the table `[1, 0, 2]`, padded with `default` if it is set.
+        struct DefaultCircuit {
+            default: Option<u64>,
+            input: u64,
+        }
+
+        impl Circuit<Fp> for DefaultCircuit {
+            type Config = DefaultCircuitConfig;
+            type FloorPlanner = SimpleFloorPlanner;
+            #[cfg(feature = "circuit-params")]
+            type Params = ();
+
+            fn without_witnesses(&self) -> Self {
+                Self {
+                    default: self.default,
+                    input: self.input,
+                }
+            }
+
+            fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+                let a = meta.advice_column();
+                let q = meta.complex_selector();
+                let table = meta.lookup_table_column();
+
+                meta.lookup("", |cells| {
+                    let a = cells.query_advice(a, Rotation::cur());
+                    let q = cells.query_selector(q);
+                    vec![(q * a, table)]
+                });
+
+                Self::Config { a, q, table }
+            }
+
+            fn synthesize(
+                &self,
+                config: Self::Config,
+                mut layouter: impl Layouter<Fp>,
+            ) -> Result<(), Error> {
+                layouter.assign_table(
+                    || "table with a default",
+                    |mut table| {
+                        // The default may be set before row 0 is assigned.
+                        if let Some(default) = self.default {
+                            table.set_default(config.table, Value::known(Fp::from(default)))?;
+                        }
+                        for (offset, value) in [1, 0, 2].into_iter().enumerate() {
+                            table.assign_cell(
+                                || "",
+                                config.table,
+                                offset,
+                                || Value::known(Fp::from(value)),
+                            )?;
+                        }
+                        Ok(())
+                    },
+                )?;
+
+                layouter.assign_region(
+                    || "input",
+                    |mut region| {
+                        config.q.enable(&mut region, 0)?;
+                        region.assign_advice(
+                            || "input",
+                            config.a,
+                            0,
+                            || Value::known(Fp::from(self.input)),
+                        )?;
+                        Ok(())
+                    },
+                )
+            }
+        }
+
+        let verify = |default, input| {
+            MockProver::run(K, &DefaultCircuit { default, input }, vec![])
+                .unwrap()
+                .verify()
+        };
+
+        // The filled rows hold the default, rather than the value at row 0.
+        assert_eq!(verify(Some(7), 7), Ok(()));
+        assert_eq!(verify(Some(7), 1), Ok(()));
+        assert!(verify(Some(7), 3).is_err());
+        assert!(verify(None, 7).is_err());
+    }
+
+    #[test]
+    fn table_set_default_twice() {
+        const K: u32 = 4;
+
+        #[derive(Clone)]
+        struct FaultyCircuitConfig {
+            table: TableColumn,
+        }
+
+        struct FaultyCircuit;
+
+        impl Circuit<Fp> for FaultyCircuit {
+            type Config = FaultyCircuitConfig;
+            type FloorPlanner = SimpleFloorPlanner;
+            #[cfg(feature = "circuit-params")]
+            type Params = ();
+
+            fn without_witnesses(&self) -> Self {
+                Self
+            }
+
+            fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+                let a = meta.advice_column();
+                let table = meta.lookup_table_column();
+
+                meta.lookup("", |cells| {
+                    let a = cells.query_advice(a, Rotation::cur());
+                    vec![(a, table)]
+                });
+
+                Self::Config { table }
+            }
+
+            fn synthesize(
+                &self,
+                config: Self::Config,
+                mut layouter: impl Layouter<Fp>,
+            ) -> Result<(), Error> {
+                layouter.assign_table(
+                    || "duplicate default",
+                    |mut table| {
+                        table.assign_cell(|| "", config.table, 0, || Value::known(Fp::zero()))?;
+                        table.set_default(config.table, Value::known(Fp::one()))?;
+                        table.set_default(config.table, Value::known(Fp::one()))
+                    },
+                )
+            }
+        }
+
+        let prover = MockProver::run(K, &FaultyCircuit, vec![]);
+        assert_eq!(
+            format!("{}", prover.unwrap_err()),
+            "Attempted to overwrite default value Value { inner: Some(Trivial(0x0000000000000000000000000000000000000000000000000000000000000001)) } with Value { inner: Some(Trivial(0x0000000000000000000000000000000000000000000000000000000000000001)) } in TableColumn { inner: Column { index: 0, column_type: Fixed } }"
+        );
+    }
+
+    #[test]
+    fn table_enable_selector() {
+        use crate::circuit::floor_planner::V1;
+        use halo2_common::plonk::FloorPlanner;
+        use std::marker::PhantomData;
+
+        const K: u32 = 4;
+
+        #[derive(Clone)]
+        struct FaultyCircuitConfig {
+            s: Selector,
+            table: TableColumn,
+        }
+
+        struct FaultyCircuit<P>(PhantomData<P>);
+
+        impl<P: FloorPlanner> Circuit<Fp> for FaultyCircuit<P> {
+            type Config = FaultyCircuitConfig;
+            type FloorPlanner = P;
+            #[cfg(feature = "circuit-params")]
+            type Params = ();
+
+            fn without_witnesses(&self) -> Self {
+                Self(PhantomData)
+            }
+
+            fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+                let s = meta.complex_selector();
+                let a = meta.advice_column();
+                let table = meta.lookup_table_column();
+
+                meta.lookup("", |cells| {
+                    let s = cells.query_selector(s);
+                    let a = cells.query_advice(a, Rotation::cur());
+                    vec![(s * a, table)]
+                });
+
+                Self::Config { s, table }
+            }
+
+            fn synthesize(
+                &self,
+                config: Self::Config,
+                mut layouter: impl Layouter<Fp>,
+            ) -> Result<(), Error> {
+                layouter.assign_table(
+                    || "selector in table",
+                    |mut table| {
+                        table.assign_cell(|| "", config.table, 0, || Value::known(Fp::zero()))?;
+                        table.enable_selector(&config.s, 0)
+                    },
+                )
+            }
+        }
+
+        let expected = "Selector(0, false) cannot be enabled inside assign_table. Help: tag the rows of the table with a fixed tag column, e.g. another TableColumn";
+
+        let prover = MockProver::run(K, &FaultyCircuit::<SimpleFloorPlanner>(PhantomData), vec![]);
+        assert_eq!(format!("{}", prover.unwrap_err()), expected);
+
+        let prover = MockProver::run(K, &FaultyCircuit::<V1>(PhantomData), vec![]);
+        assert_eq!(format!("{}", prover.unwrap_err()), expected);
+    }
 }
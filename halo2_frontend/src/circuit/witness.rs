@@ -0,0 +1,229 @@
+use std::collections::HashMap;
+use std::io;
+
+use halo2_common::helpers::{SerdeFormat, SerdePrimeField};
+use halo2_common::plonk::{Circuit, ConstraintSystem, Error};
+use halo2_middleware::ff::Field;
+
+use super::WitnessCalculator;
+
+/// Version byte that is checked when reading a [`Witness`].
+const VERSION: u8 = 0x01;
+
+/// The advice columns of a circuit, computed ahead of proof creation.
+///
+/// A `Witness` is produced by [`generate_witness`], and can be serialized in order to
+/// create the proof on another machine, without synthesizing the circuit again.
+///
+/// # Phases
+///
+/// The advice columns of later phases are computed from challenges that are derived
+/// from the proof transcript, so a witness for them would have to capture those
+/// challenges and would only be valid for a single proof. Instead, a `Witness` captures
+/// no challenges, and holds the advice columns of the phases that can be computed ahead
+/// of proof creation: all of them for a circuit with a single phase, and only the first
+/// one otherwise.
+///
+/// The advice columns of the later phases are computed during proof creation by a
+/// per-phase closure that is given the challenges of the proof, see
+/// `create_proof_from_witness_phases` in `halo2_proofs`. The machine that generated the
+/// witness can keep a [`WitnessCalculator`] for this, whose first phase gives the
+/// `Witness` with [`Witness::new`].
+///
+/// # Format
+///
+/// [`Witness::write`] writes, in order:
+/// - a version byte;
+/// - `k`, as one byte;
+/// - the number of phases, as a little-endian `u32`;
+/// - for each phase, the number of advice columns as a little-endian `u32`, followed by,
+///   for each advice column, the byte `0` if the column is not assigned in this phase,
+///   or the byte `1` followed by the `2^k` values of the column, serialized according to
+///   the given [`SerdeFormat`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Witness<F: Field> {
+    k: u32,
+    advice: Vec<Vec<Option<Vec<F>>>>,
+}
+
+impl<F: Field> Witness<F> {
+    /// Creates a witness for `k` from the advice columns of its first phases, for example
+    /// as returned by [`WitnessCalculator::next_phase`].
+    pub fn new(k: u32, advice: Vec<Vec<Option<Vec<F>>>>) -> Self {
+        Witness { k, advice }
+    }
+
+    /// Returns the `k` that this witness was generated for.
+    pub fn k(&self) -> u32 {
+        self.k
+    }
+
+    /// Returns, for each phase, the advice columns assigned in that phase.
+    pub fn advice(&self) -> &[Vec<Option<Vec<F>>>] {
+        &self.advice
+    }
+
+    /// Returns, for each phase, the advice columns assigned in that phase.
+    pub fn into_advice(self) -> Vec<Vec<Option<Vec<F>>>> {
+        self.advice
+    }
+}
+
+impl<F: SerdePrimeField> Witness<F> {
+    /// Writes the witness to a buffer, in the format described in the [`Witness`]
+    /// documentation.
+    pub fn write<W: io::Write>(&self, writer: &mut W, format: SerdeFormat) -> io::Result<()> {
+        writer.write_all(&[VERSION])?;
+        // k value fits in 1 byte
+        writer.write_all(&[self.k as u8])?;
+        writer.write_all(&(self.advice.len() as u32).to_le_bytes())?;
+        for phase in &self.advice {
+            writer.write_all(&(phase.len() as u32).to_le_bytes())?;
+            for column in phase {
+                match column {
+                    Some(values) => {
+                        writer.write_all(&[1])?;
+                        for value in values {
+                            value.write(writer, format)?;
+                        }
+                    }
+                    None => writer.write_all(&[0])?,
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads a witness from a buffer, in the format described in the [`Witness`]
+    /// documentation.
+    pub fn read<R: io::Read>(reader: &mut R, format: SerdeFormat) -> io::Result<Self> {
+        let mut version_byte = [0u8; 1];
+        reader.read_exact(&mut version_byte)?;
+        if VERSION != version_byte[0] {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "unexpected version byte",
+            ));
+        }
+
+        let mut k = [0u8; 1];
+        reader.read_exact(&mut k)?;
+        let k = u32::from(k[0]);
+        if k > F::S {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("k = {} is larger than the two-adicity of the field", k),
+            ));
+        }
+        let n = 1usize << k;
+
+        let advice = (0..read_u32(reader)?)
+            .map(|_| {
+                (0..read_u32(reader)?)
+                    .map(|_| {
+                        let mut tag = [0u8; 1];
+                        reader.read_exact(&mut tag)?;
+                        match tag[0] {
+                            0 => Ok(None),
+                            1 => (0..n)
+                                .map(|_| F::read(reader, format))
+                                .collect::<io::Result<Vec<_>>>()
+                                .map(Some),
+                            _ => Err(io::Error::new(
+                                io::ErrorKind::InvalidData,
+                                "unexpected advice column tag",
+                            )),
+                        }
+                    })
+                    .collect::<io::Result<Vec<_>>>()
+            })
+            .collect::<io::Result<Vec<_>>>()?;
+
+        Ok(Witness { k, advice })
+    }
+
+    /// Writes the witness to a vector of bytes using [`Self::write`].
+    pub fn to_bytes(&self, format: SerdeFormat) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        Self::write(self, &mut bytes, format).expect("Writing to vector should not fail");
+        bytes
+    }
+
+    /// Reads a witness from a slice of bytes using [`Self::read`].
+    pub fn from_bytes(mut bytes: &[u8], format: SerdeFormat) -> io::Result<Self> {
+        Self::read(&mut bytes, format)
+    }
+}
+
+fn read_u32<R: io::Read>(reader: &mut R) -> io::Result<u32> {
+    let mut bytes = [0u8; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+/// Synthesizes `circuit` and returns its advice columns, so that the proof can be created
+/// later, with [`Witness`] as the only input from the circuit.
+///
+/// If the circuit has advice columns in several phases, only those of the first phase are
+/// returned (see [`Witness`]).
+pub fn generate_witness<F: Field, ConcreteCircuit: Circuit<F>>(
+    k: u32,
+    circuit: &ConcreteCircuit,
+    instances: &[&[F]],
+) -> Result<Witness<F>, Error> {
+    let mut cs = ConstraintSystem::default();
+    #[cfg(feature = "circuit-params")]
+    let config = ConcreteCircuit::configure_with_params(&mut cs, circuit.params());
+    #[cfg(not(feature = "circuit-params"))]
+    let config = ConcreteCircuit::configure(&mut cs);
+
+    if instances.len() != cs.num_instance_columns {
+        return Err(Error::InvalidInstances);
+    }
+
+    let mut witness_calc = WitnessCalculator::new(k, circuit, &config, &cs, instances);
+    let advice = witness_calc.calc(0, &HashMap::new())?;
+
+    Ok(Witness {
+        k,
+        advice: vec![advice],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2curves::pasta::Fp;
+
+    use super::*;
+
+    #[test]
+    fn witness_round_trip() {
+        let k = 3;
+        let witness = Witness {
+            k,
+            advice: vec![vec![
+                Some((0..1 << k).map(Fp::from).collect()),
+                None,
+                Some(vec![-Fp::ONE; 1 << k]),
+            ]],
+        };
+
+        for format in [
+            SerdeFormat::Processed,
+            SerdeFormat::RawBytes,
+            SerdeFormat::RawBytesUnchecked,
+        ] {
+            let bytes = witness.to_bytes(format);
+            assert_eq!(Witness::from_bytes(&bytes, format).unwrap(), witness);
+        }
+
+        // Truncated witnesses and unknown versions are rejected.
+        let bytes = witness.to_bytes(SerdeFormat::Processed);
+        assert!(
+            Witness::<Fp>::from_bytes(&bytes[..bytes.len() - 1], SerdeFormat::Processed).is_err()
+        );
+        let mut bytes = bytes;
+        bytes[0] = VERSION + 1;
+        assert!(Witness::<Fp>::from_bytes(&bytes, SerdeFormat::Processed).is_err());
+    }
+}
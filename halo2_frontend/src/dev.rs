@@ -10,19 +10,21 @@ use halo2_middleware::ff::Field;
 use halo2_middleware::ff::FromUniformBytes;
 
 use halo2_common::{
-    circuit,
+    circuit::{self, layouter::SyncDeps},
     plonk::{
         circuit::{Challenge, Column},
         permutation,
         sealed::{self, SealedPhase},
-        Assigned, Assignment, Circuit, ConstraintSystem, Error, Expression, FirstPhase,
-        FloorPlanner, Phase, Selector,
+        Assigned, Assignment, AssignmentHooks, Circuit, ConstraintSystem, Error, Expression,
+        FirstPhase, FloorPlanner, InstrumentedAssignment, Phase, Selector,
     },
 };
 use halo2_middleware::circuit::{Advice, Any, ColumnMid, Fixed, Instance};
+use halo2_middleware::permutation::{ArgumentV2, AssemblyMid, CopyCycles};
 
 use halo2_common::multicore::{
-    IntoParallelIterator, IntoParallelRefIterator, ParallelIterator, ParallelSliceMut,
+    IndexedParallelIterator, IntoParallelIterator, IntoParallelRefIterator, ParallelIterator,
+    ParallelSliceMut,
 };
 
 pub mod metadata;
@@ -35,27 +37,70 @@ pub use failure::{FailureLocation, VerifyFailure};
 pub mod cost;
 pub use cost::CircuitCost;
 
+mod measure;
+pub use measure::{CircuitMeasure, MeasureReport};
+
 #[cfg(feature = "cost-estimator")]
 pub mod cost_model;
 
 mod gates;
 pub use gates::CircuitGates;
 
+#[cfg(feature = "tracing")]
 mod tfp;
+#[cfg(feature = "tracing")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tracing")))]
 pub use tfp::TracingFloorPlanner;
 
+#[cfg(feature = "tracing")]
+mod hooks;
+#[cfg(feature = "tracing")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tracing")))]
+pub use hooks::LoggingHooks;
+
+mod unconstrained;
+pub use unconstrained::{UnconstrainedAllowlist, UnconstrainedCell};
+
+mod unused;
+pub use unused::UnusedAllowlist;
+
+mod diff;
+pub use diff::{CellDiff, ColumnDiff, CopyConstraint, DiffColumn, WitnessDiff};
+
+mod lint;
+pub use lint::{lint, lint_with, LintAllowlist, LintWarning};
+
+mod render;
+pub use render::{
+    render_layout, ColumnExtent, LayoutCell, LayoutColumn, LayoutJson, RegionPlacement,
+};
+
+mod mutate;
+
+mod witness;
+
+mod view;
+pub use view::RegionView;
+
+mod streaming;
+
+mod attribution;
+pub use attribution::{attribute_costs, ColumnShare, RegionCost, RegionCostTable};
+
 #[cfg(feature = "dev-graph")]
 mod graph;
 
 #[cfg(feature = "dev-graph")]
 #[cfg_attr(docsrs, doc(cfg(feature = "dev-graph")))]
-pub use graph::{circuit_dot_graph, layout::CircuitLayout};
+pub use graph::{circuit_dot_graph, circuit_dot_graph_detailed, layout::CircuitLayout};
 
 /// Region of assignments that are done during synthesis.
 #[derive(Debug)]
 pub struct Region {
     /// The name of the region. Not required to be unique.
     name: String,
+    /// The namespaces that were active when the region was entered, outermost first.
+    namespace: Vec<String>,
     /// The columns involved in this region.
     columns: HashSet<Column<Any>>,
     /// The rows that this region starts and ends on, if known.
@@ -92,6 +137,12 @@ impl Region {
         &self.name
     }
 
+    /// Returns the namespaces that were active when this region was entered, outermost
+    /// first.
+    pub fn namespace(&self) -> &[String] {
+        &self.namespace
+    }
+
     /// Returns the columns involved in this region.
     pub fn columns(&self) -> &HashSet<Column<Any>> {
         &self.columns
@@ -116,6 +167,39 @@ impl Region {
     pub fn cells(&self) -> &HashMap<(Column<Any>, usize), usize> {
         &self.cells
     }
+
+    /// Returns the annotations of the columns used in failure reports for this region.
+    ///
+    /// Annotations given within the region take precedence over the circuit-wide
+    /// `general` annotations, so that two regions annotating the same column differently
+    /// are each reported with their own annotation.
+    fn scoped_annotations(
+        &self,
+        general: &HashMap<ColumnMetadata, String>,
+    ) -> HashMap<ColumnMetadata, String> {
+        let mut annotations = general.clone();
+        annotations.extend(
+            self.annotations
+                .iter()
+                .map(|(column, annotation)| (*column, annotation.clone())),
+        );
+        annotations
+    }
+
+    /// Returns the metadata of this region, which is the `index`-th one, with the given
+    /// column annotations.
+    fn metadata(
+        &self,
+        index: usize,
+        column_annotations: Option<HashMap<ColumnMetadata, String>>,
+    ) -> metadata::Region {
+        metadata::Region {
+            index,
+            name: self.name.clone(),
+            namespace: self.namespace.clone(),
+            column_annotations,
+        }
+    }
 }
 
 /// The value of a particular cell within the circuit.
@@ -326,12 +410,18 @@ pub struct MockProver<F: Field> {
     k: u32,
     n: u32,
     cs: ConstraintSystem<F>,
+    /// The constraint system before selectors are compressed into fixed columns.
+    configured_cs: ConstraintSystem<F>,
 
     /// The regions in the circuit.
     regions: Vec<Region>,
     /// The current region being assigned to. Will be `None` after the circuit has been
     /// synthesized.
     current_region: Option<Region>,
+    /// The index in `regions` of the region being revisited in a later phase.
+    revisited_region: Option<usize>,
+    /// The number of regions entered so far in the current (non-first) phase.
+    regions_revisited: usize,
 
     // The fixed cells in the circuit, arranged as [column][row].
     fixed: Vec<Vec<CellValue<F>>>,
@@ -350,6 +440,9 @@ pub struct MockProver<F: Field> {
     usable_rows: Range<usize>,
 
     current_phase: sealed::Phase,
+
+    // The namespaces pushed during synthesis, outermost first.
+    namespace: Vec<String>,
 }
 
 /// Instance Value
@@ -371,10 +464,126 @@ impl<F: Field> InstanceValue<F> {
     }
 }
 
+/// The constraints checked by a verification of a [`MockProver`].
+struct VerifyScope {
+    /// Whether each gate is checked, by gate index.
+    gates: Vec<bool>,
+    /// Whether each lookup is checked, by lookup index.
+    lookups: Vec<bool>,
+    shuffles: bool,
+    permutation: bool,
+    /// The rows that failures are reported at, or `None` for all rows.
+    rows: Option<Range<usize>>,
+}
+
+impl VerifyScope {
+    /// Checks all the constraints of `cs`.
+    fn all<F: Field>(cs: &ConstraintSystem<F>) -> Self {
+        VerifyScope {
+            gates: vec![true; cs.gates.len()],
+            lookups: vec![true; cs.lookups.len()],
+            shuffles: true,
+            permutation: true,
+            rows: None,
+        }
+    }
+
+    /// Checks none of the constraints of `cs`.
+    fn none<F: Field>(cs: &ConstraintSystem<F>) -> Self {
+        VerifyScope {
+            gates: vec![false; cs.gates.len()],
+            lookups: vec![false; cs.lookups.len()],
+            shuffles: false,
+            permutation: false,
+            rows: None,
+        }
+    }
+
+    /// Returns `true` if failures at `row` are reported.
+    fn contains_row(&self, row: usize) -> bool {
+        self.rows.as_ref().map_or(true, |rows| rows.contains(&row))
+    }
+}
+
 impl<F: Field> MockProver<F> {
     fn in_phase<P: Phase>(&self, phase: P) -> bool {
         self.current_phase == phase.to_sealed()
     }
+
+    /// Compresses the selectors into fixed columns, replacing the previously compressed
+    /// selectors, if any.
+    fn compress_selectors(&mut self) {
+        let (cs, selector_polys) = self
+            .configured_cs
+            .clone()
+            .compress_selectors(self.selectors.clone());
+        self.cs = cs;
+        self.fixed.truncate(self.configured_cs.num_fixed_columns);
+        self.fixed.extend(selector_polys.into_iter().map(|poly| {
+            let mut v = vec![CellValue::Unassigned; self.n as usize];
+            for (v, p) in v.iter_mut().zip(&poly[..]) {
+                *v = CellValue::Assigned(*p);
+            }
+            v
+        }));
+    }
+
+    /// Records an assignment to the advice cell at `(column, row)` in the current region.
+    fn track_advice_cell(&mut self, column: Column<Advice>, row: usize) -> Result<(), Error> {
+        if self.in_phase(FirstPhase) {
+            if !self.usable_rows.contains(&row) {
+                return Err(Error::AdviceOutsideUsableRows {
+                    column,
+                    row,
+                    usable_rows: self.usable_rows.clone(),
+                });
+            }
+
+            if let Some(region) = self.current_region.as_mut() {
+                region.update_extent(column.into(), row);
+                region
+                    .cells
+                    .entry((column.into(), row))
+                    .and_modify(|count| *count += 1)
+                    .or_default();
+            }
+        } else if column.in_phase(self.current_phase) {
+            // Cells of later-phase columns may be skipped in earlier phases (see
+            // `Layouter::in_phase`), so record them in their own phase if needed.
+            if let Some(region) = self
+                .revisited_region
+                .and_then(|index| self.regions.get_mut(index))
+            {
+                if !region.cells.contains_key(&(column.into(), row)) {
+                    region.update_extent(column.into(), row);
+                    region.cells.insert((column.into(), row), 0);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Evaluates `expression` at `row`, with queries wrapping around the domain.
+    fn evaluate_at(&self, expression: &Expression<F>, row: usize) -> Value<F> {
+        let n = self.n as i32;
+        let rotated = |rotation: i32| (row as i32 + n + rotation) as usize % n as usize;
+        expression.evaluate_lazy(
+            &|scalar| Value::Real(scalar),
+            &|_| panic!("virtual selectors are removed during optimization"),
+            &|query| self.fixed[query.column_index][rotated(query.rotation.0)].into(),
+            &|query| self.advice[query.column_index][rotated(query.rotation.0)].into(),
+            &|query| {
+                Value::Real(self.instance[query.column_index][rotated(query.rotation.0)].value())
+            },
+            &|challenge| Value::Real(self.challenges[challenge.index()]),
+            &|a| -a,
+            &|a, b| a + b,
+            &|a, b| a * b,
+            &|a, scalar| a * scalar,
+            &Value::Real(F::ZERO),
+        )
+    }
 }
 
 impl<F: Field> Assignment<F> for MockProver<F> {
@@ -384,12 +593,18 @@ impl<F: Field> Assignment<F> for MockProver<F> {
         N: FnOnce() -> NR,
     {
         if !self.in_phase(FirstPhase) {
+            // Regions are entered in the same order in every phase, so we can find the
+            // region recorded in the first phase and track cells that are only assigned
+            // in later phases.
+            self.revisited_region = Some(self.regions_revisited);
+            self.regions_revisited += 1;
             return;
         }
 
         assert!(self.current_region.is_none());
         self.current_region = Some(Region {
             name: name().into(),
+            namespace: self.namespace.clone(),
             columns: HashSet::default(),
             rows: None,
             annotations: HashMap::default(),
@@ -400,6 +615,7 @@ impl<F: Field> Assignment<F> for MockProver<F> {
 
     fn exit_region(&mut self) {
         if !self.in_phase(FirstPhase) {
+            self.revisited_region = None;
             return;
         }
 
@@ -431,13 +647,13 @@ impl<F: Field> Assignment<F> for MockProver<F> {
             return Ok(());
         }
 
-        assert!(
-            self.usable_rows.contains(&row),
-            "row={} not in usable_rows={:?}, k={}",
-            row,
-            self.usable_rows,
-            self.k,
-        );
+        if !self.usable_rows.contains(&row) {
+            return Err(Error::SelectorOutsideUsableRows {
+                selector: *selector,
+                row,
+                usable_rows: self.usable_rows.clone(),
+            });
+        }
 
         // Track that this selector was enabled. We require that all selectors are enabled
         // inside some region (i.e. no floating selectors).
@@ -454,6 +670,46 @@ impl<F: Field> Assignment<F> for MockProver<F> {
         Ok(())
     }
 
+    fn enable_selector_range<A, AR>(
+        &mut self,
+        _: A,
+        selector: &Selector,
+        rows: Range<usize>,
+    ) -> Result<(), Error>
+    where
+        A: Fn() -> AR,
+        AR: Into<String>,
+    {
+        if !self.in_phase(FirstPhase) || rows.is_empty() {
+            return Ok(());
+        }
+
+        if let Some(row) = [rows.start, rows.end - 1]
+            .into_iter()
+            .find(|row| !self.usable_rows.contains(row))
+        {
+            return Err(Error::SelectorOutsideUsableRows {
+                selector: *selector,
+                row,
+                usable_rows: self.usable_rows.clone(),
+            });
+        }
+
+        // Track that this selector was enabled. We require that all selectors are enabled
+        // inside some region (i.e. no floating selectors).
+        self.current_region
+            .as_mut()
+            .unwrap()
+            .enabled_selectors
+            .entry(*selector)
+            .or_default()
+            .extend(rows.clone());
+
+        self.selectors[selector.0][rows].fill(true);
+
+        Ok(())
+    }
+
     fn query_instance(
         &self,
         column: Column<Instance>,
@@ -488,24 +744,7 @@ impl<F: Field> Assignment<F> for MockProver<F> {
         A: FnOnce() -> AR,
         AR: Into<String>,
     {
-        if self.in_phase(FirstPhase) {
-            assert!(
-                self.usable_rows.contains(&row),
-                "row={}, usable_rows={:?}, k={}",
-                row,
-                self.usable_rows,
-                self.k,
-            );
-
-            if let Some(region) = self.current_region.as_mut() {
-                region.update_extent(column.into(), row);
-                region
-                    .cells
-                    .entry((column.into(), row))
-                    .and_modify(|count| *count += 1)
-                    .or_default();
-            }
-        }
+        self.track_advice_cell(column, row)?;
 
         match to().into_field().evaluate().assign() {
             Ok(to) => {
@@ -518,7 +757,7 @@ impl<F: Field> Assignment<F> for MockProver<F> {
             }
             Err(err) => {
                 // Propagate `assign` error if the column is in current phase.
-                if self.in_phase(sealed::Phase(column.column_type().phase)) {
+                if column.in_phase(self.current_phase) {
                     return Err(err);
                 }
             }
@@ -527,6 +766,30 @@ impl<F: Field> Assignment<F> for MockProver<F> {
         Ok(())
     }
 
+    fn assign_advice_column(
+        &mut self,
+        column: Column<Advice>,
+        start_row: usize,
+        values: &[circuit::Value<Assigned<F>>],
+    ) -> Result<(), Error> {
+        for row in start_row..start_row + values.len() {
+            self.track_advice_cell(column, row)?;
+        }
+
+        let in_column_phase = column.in_phase(self.current_phase);
+        let cells = &mut self.advice[column.index()][start_row..start_row + values.len()];
+        for (cell, value) in cells.iter_mut().zip(values.iter()) {
+            match value.evaluate().assign() {
+                Ok(value) => *cell = CellValue::Assigned(value),
+                // Propagate `assign` error if the column is in current phase.
+                Err(err) if in_column_phase => return Err(err),
+                Err(_) => {}
+            }
+        }
+
+        Ok(())
+    }
+
     fn assign_fixed<V, VR, A, AR>(
         &mut self,
         _: A,
@@ -551,6 +814,9 @@ impl<F: Field> Assignment<F> for MockProver<F> {
             self.usable_rows,
             self.k,
         );
+        if self.cs.is_preprocessed_fixed(column) {
+            return Err(Error::PreprocessedFixedAssigned(column));
+        }
 
         if let Some(region) = self.current_region.as_mut() {
             region.update_extent(column.into(), row);
@@ -619,6 +885,49 @@ impl<F: Field> Assignment<F> for MockProver<F> {
         Ok(())
     }
 
+    fn fill_advice_from_row(
+        &mut self,
+        column: Column<Advice>,
+        from_row: usize,
+        to: circuit::Value<Assigned<F>>,
+    ) -> Result<(), Error> {
+        if from_row > self.usable_rows.end {
+            return Err(Error::AdviceOutsideUsableRows {
+                column,
+                row: from_row,
+                usable_rows: self.usable_rows.clone(),
+            });
+        }
+
+        let rows = from_row..self.usable_rows.end;
+        if rows.is_empty() {
+            return Ok(());
+        }
+        // As in `RegionShape`, only the first row belongs to the region, so that the
+        // padding does not widen its extent or count as cells assigned by the region.
+        self.track_advice_cell(column, from_row)?;
+
+        match to.evaluate().assign() {
+            Ok(to) => {
+                // The cells assigned by other regions, which may be placed below this one,
+                // keep their values.
+                for cell in self.advice[column.index()][rows].iter_mut() {
+                    if *cell == CellValue::Unassigned {
+                        *cell = CellValue::Assigned(to);
+                    }
+                }
+            }
+            Err(err) => {
+                // Propagate `assign` error if the column is in current phase.
+                if column.in_phase(self.current_phase) {
+                    return Err(err);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
     fn get_challenge(&self, challenge: Challenge) -> circuit::Value<F> {
         if self.current_phase.0 <= challenge.phase() {
             return circuit::Value::unknown();
@@ -627,16 +936,24 @@ impl<F: Field> Assignment<F> for MockProver<F> {
         circuit::Value::known(self.challenges[challenge.index()])
     }
 
-    fn push_namespace<NR, N>(&mut self, _: N)
+    fn current_phase(&self) -> u8 {
+        self.current_phase.0
+    }
+
+    fn usable_rows(&self) -> Option<Range<usize>> {
+        Some(self.usable_rows.clone())
+    }
+
+    fn push_namespace<NR, N>(&mut self, name_fn: N)
     where
         NR: Into<String>,
         N: FnOnce() -> NR,
     {
-        // TODO: Do something with namespaces :)
+        self.namespace.push(name_fn().into());
     }
 
     fn pop_namespace(&mut self, _: Option<String>) {
-        // TODO: Do something with namespaces :)
+        self.namespace.pop();
     }
 }
 
@@ -647,6 +964,46 @@ impl<F: FromUniformBytes<64> + Ord> MockProver<F> {
         k: u32,
         circuit: &ConcreteCircuit,
         instance: Vec<Vec<F>>,
+    ) -> Result<Self, Error> {
+        Self::run_with_hooks(k, circuit, instance, ())
+    }
+
+    /// Runs [`MockProver::run`], reporting the progress of synthesis to `hooks`.
+    ///
+    /// Pass `&mut hooks` to inspect the hooks after the run.
+    pub fn run_with_hooks<ConcreteCircuit: Circuit<F>, H: AssignmentHooks + SyncDeps>(
+        k: u32,
+        circuit: &ConcreteCircuit,
+        instance: Vec<Vec<F>>,
+        hooks: H,
+    ) -> Result<Self, Error> {
+        Self::run_inner(k, circuit, instance, None, hooks)
+    }
+
+    /// Runs [`MockProver::run`] with the given challenges, instead of deriving them
+    /// internally.
+    ///
+    /// This can be used to reproduce the witness of a real proof, given the challenges
+    /// sampled by the real prover, or to re-run a circuit with the challenges captured
+    /// with [`MockProver::challenges`].
+    ///
+    /// Returns an error if the number of challenges does not match the circuit's constraint
+    /// system.
+    pub fn run_with_challenges<ConcreteCircuit: Circuit<F>>(
+        k: u32,
+        circuit: &ConcreteCircuit,
+        instance: Vec<Vec<F>>,
+        challenges: Vec<F>,
+    ) -> Result<Self, Error> {
+        Self::run_inner(k, circuit, instance, Some(challenges), ())
+    }
+
+    fn run_inner<ConcreteCircuit: Circuit<F>, H: AssignmentHooks + SyncDeps>(
+        k: u32,
+        circuit: &ConcreteCircuit,
+        instance: Vec<Vec<F>>,
+        challenges: Option<Vec<F>>,
+        mut hooks: H,
     ) -> Result<Self, Error> {
         let n = 1 << k;
 
@@ -688,16 +1045,29 @@ impl<F: FromUniformBytes<64> + Ord> MockProver<F> {
             .collect::<Vec<_>>();
 
         // Fixed columns contain no blinding factors.
-        let fixed = vec![vec![CellValue::Unassigned; n]; cs.num_fixed_columns];
+        let mut fixed = vec![vec![CellValue::Unassigned; n]; cs.num_fixed_columns];
         let selectors = vec![vec![false; n]; cs.num_selectors];
         // Advice columns contain blinding factors.
-        let blinding_factors = cs.blinding_factors();
-        let usable_rows = n - (blinding_factors + 1);
+        let usable_rows = cs.usable_rows(k);
+        if let Some((l_first, l_last)) = cs.boundary_columns {
+            for column in [l_first, l_last] {
+                fixed[column.index()] = vec![CellValue::Assigned(F::ZERO); n];
+            }
+        }
+        for (column, row) in cs.boundary_cells(&usable_rows) {
+            fixed[column.index()][row] = CellValue::Assigned(F::ONE);
+        }
+        for preprocessed in &cs.preprocessed_fixed_columns {
+            fixed[preprocessed.column.index()] = vec![CellValue::Assigned(F::ZERO); n];
+        }
+        for (column, row, value) in cs.preprocessed_fixed_cells(&usable_rows) {
+            fixed[column.index()][row] = CellValue::Assigned(value);
+        }
         let advice = vec![
             {
                 let mut column = vec![CellValue::Unassigned; n];
                 // Poison unusable rows.
-                for (i, cell) in column.iter_mut().enumerate().skip(usable_rows) {
+                for (i, cell) in column.iter_mut().enumerate().skip(usable_rows.end) {
                     *cell = CellValue::Poison(i);
                 }
                 column
@@ -707,52 +1077,53 @@ impl<F: FromUniformBytes<64> + Ord> MockProver<F> {
         let permutation = permutation::Assembly::new(n, &cs.permutation);
         let constants = cs.constants.clone();
 
-        // Use hash chain to derive deterministic challenges for testing
-        let challenges = {
-            let mut hash: [u8; 64] = blake2b(b"Halo2-MockProver").as_bytes().try_into().unwrap();
-            iter::repeat_with(|| {
-                hash = blake2b(&hash).as_bytes().try_into().unwrap();
-                F::from_uniform_bytes(&hash)
-            })
-            .take(cs.num_challenges)
-            .collect()
+        let challenges = match challenges {
+            Some(challenges) => {
+                if challenges.len() != cs.num_challenges {
+                    return Err(Error::Other(format!(
+                        "Expected {} challenges, got {}",
+                        cs.num_challenges,
+                        challenges.len()
+                    )));
+                }
+                challenges
+            }
+            None => Self::derive_challenges(cs.num_challenges),
         };
 
         let mut prover = MockProver {
             k,
             n: n as u32,
+            configured_cs: cs.clone(),
             cs,
             regions: vec![],
             current_region: None,
+            revisited_region: None,
+            regions_revisited: 0,
             fixed,
             advice,
             instance,
             selectors,
             challenges,
             permutation,
-            usable_rows: 0..usable_rows,
+            usable_rows,
             current_phase: FirstPhase.to_sealed(),
+            namespace: vec![],
         };
 
         for current_phase in prover.cs.phases() {
             prover.current_phase = current_phase;
+            prover.regions_revisited = 0;
             ConcreteCircuit::FloorPlanner::synthesize(
-                &mut prover,
+                &mut InstrumentedAssignment::new(&mut prover, &mut hooks),
                 circuit,
                 config.clone(),
                 constants.clone(),
             )?;
         }
 
-        let (cs, selector_polys) = prover.cs.compress_selectors(prover.selectors.clone());
-        prover.cs = cs;
-        prover.fixed.extend(selector_polys.into_iter().map(|poly| {
-            let mut v = vec![CellValue::Unassigned; n];
-            for (v, p) in v.iter_mut().zip(&poly[..]) {
-                *v = CellValue::Assigned(*p);
-            }
-            v
-        }));
+        prover.configured_cs = prover.cs.clone();
+        prover.compress_selectors();
 
         // #[cfg(feature = "thread-safe-region")]
         // prover.permutation.build_ordered_mapping();
@@ -760,6 +1131,23 @@ impl<F: FromUniformBytes<64> + Ord> MockProver<F> {
         Ok(prover)
     }
 
+    /// Derives deterministic challenges for testing from a hash chain.
+    fn derive_challenges(num_challenges: usize) -> Vec<F> {
+        let mut hash: [u8; 64] = blake2b(b"Halo2-MockProver").as_bytes().try_into().unwrap();
+        iter::repeat_with(|| {
+            hash = blake2b(&hash).as_bytes().try_into().unwrap();
+            F::from_uniform_bytes(&hash)
+        })
+        .take(num_challenges)
+        .collect()
+    }
+
+    /// Returns the values of the challenges used by this run, indexed by
+    /// [`Challenge::index`].
+    pub fn challenges(&self) -> &[F] {
+        &self.challenges
+    }
+
     /// Return the content of an advice column as assigned by the circuit.
     pub fn advice_values(&self, column: Column<Advice>) -> &[CellValue<F>] {
         &self.advice[column.index()]
@@ -773,6 +1161,9 @@ impl<F: FromUniformBytes<64> + Ord> MockProver<F> {
     /// Returns `Ok(())` if this `MockProver` is satisfied, or a list of errors indicating
     /// the reasons that the circuit is not satisfied.
     /// Constraints and lookup are checked at `usable_rows`, parallelly.
+    ///
+    /// The failures are grouped by kind, and the gate, lookup and shuffle failures are
+    /// ordered by row, then by constraint, regardless of the number of threads.
     pub fn verify(&self) -> Result<(), Vec<VerifyFailure>> {
         self.verify_at_rows(self.usable_rows.clone(), self.usable_rows.clone())
     }
@@ -784,6 +1175,116 @@ impl<F: FromUniformBytes<64> + Ord> MockProver<F> {
         &self,
         gate_row_ids: I,
         lookup_input_row_ids: I,
+    ) -> Result<(), Vec<VerifyFailure>> {
+        self.verify_scope(
+            gate_row_ids,
+            lookup_input_row_ids,
+            &VerifyScope::all(&self.cs),
+        )
+    }
+
+    /// Returns `Ok(())` if the gates for which `gate_filter` returns `true` when given
+    /// their name are satisfied, or a list of errors indicating the reasons that they are
+    /// not satisfied.
+    ///
+    /// The gates are checked at the same rows as in [`MockProver::verify`], and the
+    /// returned errors are the ones that [`MockProver::verify`] returns for these gates.
+    /// No lookup, shuffle or permutation is checked.
+    pub fn verify_gates(
+        &self,
+        gate_filter: impl Fn(&str) -> bool,
+    ) -> Result<(), Vec<VerifyFailure>> {
+        let mut scope = VerifyScope::none(&self.cs);
+        for (gate, checked) in self.cs.gates.iter().zip(scope.gates.iter_mut()) {
+            *checked = gate_filter(gate.name());
+        }
+        self.verify_scope(self.usable_rows.clone(), self.usable_rows.clone(), &scope)
+    }
+
+    /// Returns `Ok(())` if the gates with the given indices are satisfied, or a list of
+    /// errors indicating the reasons that they are not satisfied.
+    ///
+    /// See [`MockProver::verify_gates`].
+    ///
+    /// Panics if a gate index is out of bounds.
+    pub fn verify_gate_indices(&self, gate_indices: &[usize]) -> Result<(), Vec<VerifyFailure>> {
+        let mut scope = VerifyScope::none(&self.cs);
+        for gate_index in gate_indices {
+            scope.gates[*gate_index] = true;
+        }
+        self.verify_scope(self.usable_rows.clone(), self.usable_rows.clone(), &scope)
+    }
+
+    /// Returns `Ok(())` if the lookups for which `lookup_filter` returns `true` when given
+    /// their name are satisfied, or a list of errors indicating the reasons that they are
+    /// not satisfied.
+    ///
+    /// The lookups are checked at the same rows as in [`MockProver::verify`], and the
+    /// returned errors are the ones that [`MockProver::verify`] returns for these lookups.
+    /// No gate, shuffle or permutation is checked.
+    pub fn verify_lookups(
+        &self,
+        lookup_filter: impl Fn(&str) -> bool,
+    ) -> Result<(), Vec<VerifyFailure>> {
+        let mut scope = VerifyScope::none(&self.cs);
+        for (lookup, checked) in self.cs.lookups.iter().zip(scope.lookups.iter_mut()) {
+            *checked = lookup_filter(&lookup.name);
+        }
+        self.verify_scope(self.usable_rows.clone(), self.usable_rows.clone(), &scope)
+    }
+
+    /// Returns `Ok(())` if the equality constraints are satisfied, or a list of errors
+    /// indicating the reasons that they are not satisfied.
+    ///
+    /// The returned errors are the ones that [`MockProver::verify`] returns for the
+    /// equality constraints. No gate, lookup or shuffle is checked.
+    pub fn verify_permutation_only(&self) -> Result<(), Vec<VerifyFailure>> {
+        let mut scope = VerifyScope::none(&self.cs);
+        scope.permutation = true;
+        self.verify_scope(self.usable_rows.clone(), self.usable_rows.clone(), &scope)
+    }
+
+    /// Returns `Ok(())` if this `MockProver` is satisfied at `rows`, or a list of errors
+    /// indicating the reasons that the circuit is not satisfied there.
+    ///
+    /// Gates and lookup inputs are only checked at `rows`, while lookup tables are still
+    /// built from all the usable rows. All the returned failures are located at `rows`:
+    /// - shuffles are checked over all the usable rows, but only the mismatches at `rows`
+    ///   are returned;
+    /// - an equality constraint is only checked if its first cell is at `rows`. This means
+    ///   that permutation cycles that leave `rows` are only partially checked.
+    ///
+    /// Panics if `rows` is not within the usable rows.
+    pub fn verify_at_rows_with_lookups(
+        &self,
+        rows: Range<usize>,
+    ) -> Result<(), Vec<VerifyFailure>> {
+        self.verify_gates_at_rows_with_lookups(rows, |_| true)
+    }
+
+    /// Returns `Ok(())` if this `MockProver` is satisfied at `rows`, or a list of errors
+    /// indicating the reasons that the circuit is not satisfied there, only checking the
+    /// gates for which `gate_filter` returns `true` when given their name.
+    ///
+    /// See [`MockProver::verify_at_rows_with_lookups`] and [`MockProver::verify_gates`].
+    pub fn verify_gates_at_rows_with_lookups(
+        &self,
+        rows: Range<usize>,
+        gate_filter: impl Fn(&str) -> bool,
+    ) -> Result<(), Vec<VerifyFailure>> {
+        let mut scope = VerifyScope::all(&self.cs);
+        for (gate, checked) in self.cs.gates.iter().zip(scope.gates.iter_mut()) {
+            *checked = gate_filter(gate.name());
+        }
+        scope.rows = Some(rows.clone());
+        self.verify_scope(rows.clone(), rows, &scope)
+    }
+
+    fn verify_scope<I: Clone + Iterator<Item = usize>>(
+        &self,
+        gate_row_ids: I,
+        lookup_input_row_ids: I,
+        scope: &VerifyScope,
     ) -> Result<(), Vec<VerifyFailure>> {
         let n = self.n as i32;
 
@@ -818,9 +1319,12 @@ impl<F: FromUniformBytes<64> + Ord> MockProver<F> {
                     // every selector is explicitly enabled or disabled on every row? But that
                     // seems messy and confusing.
                     .enumerate()
-                    .filter(move |(_, g)| g.queried_selectors().contains(selector))
+                    .filter(move |(gate_index, g)| {
+                        scope.gates[*gate_index] && g.queried_selectors().contains(selector)
+                    })
                     .flat_map(move |(gate_index, gate)| {
                         at.par_iter()
+                            .filter(move |selector_row| scope.contains_row(**selector_row))
                             .flat_map(move |selector_row| {
                                 // Selectors are queried with no rotation.
                                 let gate_row = *selector_row as i32;
@@ -842,7 +1346,7 @@ impl<F: FromUniformBytes<64> + Ord> MockProver<F> {
                                                     _ => Some(
                                                         VerifyFailure::InstanceCellNotAssigned {
                                                             gate: (gate_index, gate.name()).into(),
-                                                            region: (r_i, r.name.clone()).into(),
+                                                            region: r.metadata(r_i, None),
                                                             gate_offset: *selector_row,
                                                             column: cell.column.try_into().unwrap(),
                                                             row: cell_row,
@@ -857,12 +1361,12 @@ impl<F: FromUniformBytes<64> + Ord> MockProver<F> {
                                                 } else {
                                                     Some(VerifyFailure::CellNotAssigned {
                                                         gate: (gate_index, gate.name()).into(),
-                                                        region: (
+                                                        region: r.metadata(
                                                             r_i,
-                                                            r.name.clone(),
-                                                            r.annotations.clone(),
-                                                        )
-                                                            .into(),
+                                                            Some(r.scoped_annotations(
+                                                                &self.cs.general_column_annotations,
+                                                            )),
+                                                        ),
                                                         gate_offset: *selector_row,
                                                         column: cell.column,
                                                         offset: cell_row as isize
@@ -879,26 +1383,28 @@ impl<F: FromUniformBytes<64> + Ord> MockProver<F> {
             })
         });
 
-        // Check that all gates are satisfied for all rows.
-        let gate_errors = self
-            .cs
-            .gates
-            .iter()
-            .enumerate()
-            .flat_map(|(gate_index, gate)| {
-                let blinding_rows =
-                    (self.n as usize - (self.cs.blinding_factors() + 1))..(self.n as usize);
-                (gate_row_ids
-                    .clone()
+        // Check that all gates are satisfied for all rows. The rows are checked in
+        // parallel, and the failures are collected in order of row, then gate and
+        // polynomial.
+        let blinding_rows = (self.n as usize - (self.cs.blinding_factors() + 1))..(self.n as usize);
+        let gate_errors = gate_row_ids
+            .clone()
+            .into_par_iter()
+            .chain(
+                blinding_rows
                     .into_par_iter()
-                    .chain(blinding_rows.into_par_iter()))
-                .flat_map(move |row| {
-                    let row = row as i32 + n;
-                    gate.polynomials()
-                        .iter()
-                        .enumerate()
-                        .filter_map(move |(poly_index, poly)| {
-                            match poly.evaluate_lazy(
+                    .filter(|row| scope.contains_row(*row)),
+            )
+            .flat_map_iter(move |row| {
+                let row = row as i32 + n;
+                self.cs
+                    .gates
+                    .iter()
+                    .enumerate()
+                    .filter(move |(gate_index, _)| scope.gates[*gate_index])
+                    .flat_map(move |(gate_index, gate)| {
+                        gate.polynomials().iter().enumerate().filter_map(
+                            move |(poly_index, poly)| match poly.evaluate_lazy(
                                 &|scalar| Value::Real(scalar),
                                 &|_| panic!("virtual selectors are removed during optimization"),
                                 &util::load(n, row, &self.cs.fixed_queries, &self.fixed),
@@ -951,184 +1457,155 @@ impl<F: FromUniformBytes<64> + Ord> MockProver<F> {
                                     )
                                         .into(),
                                 }),
-                            }
-                        })
-                        .collect::<Vec<_>>()
-                })
-                .collect::<Vec<_>>()
-            });
-
-        let load = |expression: &Expression<F>, row| {
-            expression.evaluate_lazy(
-                &|scalar| Value::Real(scalar),
-                &|_| panic!("virtual selectors are removed during optimization"),
-                &|query| {
-                    self.fixed[query.column_index]
-                        [(row as i32 + n + query.rotation.0) as usize % n as usize]
-                        .into()
-                },
-                &|query| {
-                    self.advice[query.column_index]
-                        [(row as i32 + n + query.rotation.0) as usize % n as usize]
-                        .into()
-                },
-                &|query| {
-                    Value::Real(
-                        self.instance[query.column_index]
-                            [(row as i32 + n + query.rotation.0) as usize % n as usize]
-                            .value(),
-                    )
-                },
-                &|challenge| Value::Real(self.challenges[challenge.index()]),
-                &|a| -a,
-                &|a, b| a + b,
-                &|a, b| a * b,
-                &|a, scalar| a * scalar,
-                &Value::Real(F::ZERO),
-            )
-        };
+                            },
+                        )
+                    })
+            })
+            .collect::<Vec<_>>();
+
+        let load = |expression: &Expression<F>, row| self.evaluate_at(expression, row);
 
         let mut cached_table = Vec::new();
         let mut cached_table_identifier = Vec::new();
         // Check that all lookups exist in their respective tables.
-        let lookup_errors =
-            self.cs
-                .lookups
-                .iter()
-                .enumerate()
-                .flat_map(|(lookup_index, lookup)| {
-                    assert!(lookup.table_expressions.len() == lookup.input_expressions.len());
-                    assert!(self.usable_rows.end > 0);
-
-                    // We optimize on the basis that the table might have been filled so that the last
-                    // usable row now has the fill contents (it doesn't matter if there was no filling).
-                    // Note that this "fill row" necessarily exists in the table, and we use that fact to
-                    // slightly simplify the optimization: we're only trying to check that all input rows
-                    // are contained in the table, and so we can safely just drop input rows that
-                    // match the fill row.
-                    let fill_row: Vec<_> = lookup
-                        .table_expressions
-                        .iter()
-                        .map(move |c| load(c, self.usable_rows.end - 1))
-                        .collect();
-
-                    let table_identifier = lookup
-                        .table_expressions
-                        .iter()
-                        .map(Expression::identifier)
-                        .collect::<Vec<_>>();
-                    if table_identifier != cached_table_identifier {
-                        cached_table_identifier = table_identifier;
-
-                        // In the real prover, the lookup expressions are never enforced on
-                        // unusable rows, due to the (1 - (l_last(X) + l_blind(X))) term.
-                        cached_table = self
-                            .usable_rows
-                            .clone()
-                            .into_par_iter()
-                            .filter_map(|table_row| {
-                                let t = lookup
-                                    .table_expressions
-                                    .iter()
-                                    .map(move |c| load(c, table_row))
-                                    .collect();
-
-                                if t != fill_row {
-                                    Some(t)
-                                } else {
-                                    None
-                                }
-                            })
-                            .collect();
-                        cached_table.par_sort_unstable();
-                    }
-                    let table = &cached_table;
+        let mut lookup_errors: Vec<_> = self
+            .cs
+            .lookups
+            .iter()
+            .enumerate()
+            .filter(|(lookup_index, _)| scope.lookups[*lookup_index])
+            .flat_map(|(lookup_index, lookup)| {
+                assert!(lookup.table_expressions.len() == lookup.input_expressions.len());
+                assert!(self.usable_rows.end > 0);
+
+                // We optimize on the basis that the table might have been filled so that the last
+                // usable row now has the fill contents (it doesn't matter if there was no filling).
+                // Note that this "fill row" necessarily exists in the table, and we use that fact to
+                // slightly simplify the optimization: we're only trying to check that all input rows
+                // are contained in the table, and so we can safely just drop input rows that
+                // match the fill row.
+                let fill_row: Vec<_> = lookup
+                    .table_expressions
+                    .iter()
+                    .map(move |c| load(c, self.usable_rows.end - 1))
+                    .collect();
 
-                    let mut inputs: Vec<(Vec<_>, usize)> = lookup_input_row_ids
+                let table_identifier = lookup
+                    .table_expressions
+                    .iter()
+                    .map(Expression::identifier)
+                    .collect::<Vec<_>>();
+                if table_identifier != cached_table_identifier {
+                    cached_table_identifier = table_identifier;
+
+                    // In the real prover, the lookup expressions are never enforced on
+                    // unusable rows, due to the (1 - (l_last(X) + l_blind(X))) term.
+                    cached_table = self
+                        .usable_rows
                         .clone()
                         .into_par_iter()
-                        .filter_map(|input_row| {
+                        .filter_map(|table_row| {
                             let t = lookup
-                                .input_expressions
+                                .table_expressions
                                 .iter()
-                                .map(move |c| load(c, input_row))
+                                .map(move |c| load(c, table_row))
                                 .collect();
 
                             if t != fill_row {
-                                // Also keep track of the original input row, since we're going to sort.
-                                Some((t, input_row))
+                                Some(t)
                             } else {
                                 None
                             }
                         })
                         .collect();
-                    inputs.par_sort_unstable();
+                    cached_table.par_sort_unstable();
+                }
+                let table = &cached_table;
+
+                // The input rows are checked in parallel, and the failures are collected
+                // in order of row.
+                lookup_input_row_ids
+                    .clone()
+                    .into_par_iter()
+                    .filter_map(move |input_row| {
+                        let input: Vec<_> = lookup
+                            .input_expressions
+                            .iter()
+                            .map(|c| load(c, input_row))
+                            .collect();
 
-                    inputs
-                        .par_iter()
-                        .filter_map(move |(input, input_row)| {
-                            if table.binary_search(input).is_err() {
-                                Some(VerifyFailure::Lookup {
+                        if input != fill_row && table.binary_search(&input).is_err() {
+                            Some((
+                                input_row,
+                                VerifyFailure::Lookup {
                                     name: lookup.name.clone(),
                                     lookup_index,
                                     location: FailureLocation::find_expressions(
                                         &self.cs,
                                         &self.regions,
-                                        *input_row,
+                                        input_row,
                                         lookup.input_expressions.iter(),
                                     ),
-                                })
-                            } else {
-                                None
-                            }
-                        })
-                        .collect::<Vec<_>>()
-                });
+                                },
+                            ))
+                        } else {
+                            None
+                        }
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        // Order the failures by row, then lookup.
+        lookup_errors.par_sort_by_key(|(row, _)| *row);
 
-        let shuffle_errors =
-            self.cs
-                .shuffles
-                .iter()
-                .enumerate()
-                .flat_map(|(shuffle_index, shuffle)| {
-                    assert!(shuffle.shuffle_expressions.len() == shuffle.input_expressions.len());
-                    assert!(self.usable_rows.end > 0);
+        let mut shuffle_errors: Vec<_> = self
+            .cs
+            .shuffles
+            .iter()
+            .enumerate()
+            .filter(|_| scope.shuffles)
+            .flat_map(|(shuffle_index, shuffle)| {
+                assert!(shuffle.shuffle_expressions.len() == shuffle.input_expressions.len());
+                assert!(self.usable_rows.end > 0);
 
-                    let mut shuffle_rows: Vec<Vec<Value<F>>> = self
-                        .usable_rows
-                        .clone()
-                        .map(|row| {
-                            let t = shuffle
-                                .shuffle_expressions
-                                .iter()
-                                .map(move |c| load(c, row))
-                                .collect();
-                            t
-                        })
-                        .collect();
-                    shuffle_rows.sort();
+                let mut shuffle_rows: Vec<Vec<Value<F>>> = self
+                    .usable_rows
+                    .clone()
+                    .into_par_iter()
+                    .map(|row| {
+                        shuffle
+                            .shuffle_expressions
+                            .iter()
+                            .map(|c| load(c, row))
+                            .collect()
+                    })
+                    .collect();
+                shuffle_rows.par_sort();
 
-                    let mut input_rows: Vec<(Vec<Value<F>>, usize)> = self
-                        .usable_rows
-                        .clone()
-                        .map(|input_row| {
-                            let t = shuffle
-                                .input_expressions
-                                .iter()
-                                .map(move |c| load(c, input_row))
-                                .collect();
+                let mut input_rows: Vec<(Vec<Value<F>>, usize)> = self
+                    .usable_rows
+                    .clone()
+                    .into_par_iter()
+                    .map(|input_row| {
+                        let t = shuffle
+                            .input_expressions
+                            .iter()
+                            .map(|c| load(c, input_row))
+                            .collect();
 
-                            (t, input_row)
-                        })
-                        .collect();
-                    input_rows.sort();
-
-                    input_rows
-                        .iter()
-                        .zip(shuffle_rows.iter())
-                        .filter_map(|((input_value, row), shuffle_value)| {
-                            if shuffle_value != input_value {
-                                Some(VerifyFailure::Shuffle {
+                        (t, input_row)
+                    })
+                    .collect();
+                input_rows.par_sort();
+
+                input_rows
+                    .par_iter()
+                    .zip(shuffle_rows.par_iter())
+                    .filter_map(|((input_value, row), shuffle_value)| {
+                        if shuffle_value != input_value && scope.contains_row(*row) {
+                            Some((
+                                *row,
+                                VerifyFailure::Shuffle {
                                     name: shuffle.name.clone(),
                                     shuffle_index,
                                     location: FailureLocation::find_expressions(
@@ -1137,13 +1614,18 @@ impl<F: FromUniformBytes<64> + Ord> MockProver<F> {
                                         *row,
                                         shuffle.input_expressions.iter(),
                                     ),
-                                })
-                            } else {
-                                None
-                            }
-                        })
-                        .collect::<Vec<_>>()
-                });
+                                },
+                            ))
+                        } else {
+                            None
+                        }
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        // The mismatches are found in order of value; order the failures by row, then
+        // shuffle.
+        shuffle_errors.par_sort_by_key(|(row, _)| *row);
 
         // Check that permutations preserve the original values of the cells.
         // Original values of columns involved in the permutation.
@@ -1158,29 +1640,35 @@ impl<F: FromUniformBytes<64> + Ord> MockProver<F> {
 
         // Iterate over each pair of copied cells to check that the cell's value is preserved
         // by the copy.
-        let perm_errors = self.permutation.copies.iter().flat_map(|(cell_a, cell_b)| {
-            let original_cell = original(cell_a.column, cell_a.row);
-            let permuted_cell = original(cell_b.column, cell_b.row);
-            if original_cell == permuted_cell {
-                None
-            } else {
-                Some(VerifyFailure::Permutation {
-                    column: cell_a.column.into(),
-                    location: FailureLocation::find(
-                        &self.regions,
-                        cell_a.row,
-                        Some(&cell_a.column.into()).into_iter().cloned().collect(),
-                    ),
-                })
-            }
-        });
+        let perm_errors = self
+            .permutation
+            .copies
+            .iter()
+            .filter(|(cell_a, _)| scope.permutation && scope.contains_row(cell_a.row))
+            .flat_map(|(cell_a, cell_b)| {
+                let original_cell = original(cell_a.column, cell_a.row);
+                let permuted_cell = original(cell_b.column, cell_b.row);
+                if original_cell == permuted_cell {
+                    None
+                } else {
+                    Some(VerifyFailure::Permutation {
+                        column: cell_a.column.into(),
+                        location: FailureLocation::find(
+                            &self.regions,
+                            &self.cs.general_column_annotations,
+                            cell_a.row,
+                            Some(&cell_a.column.into()).into_iter().cloned().collect(),
+                        ),
+                    })
+                }
+            });
 
         let mut errors: Vec<_> = iter::empty()
             .chain(selector_errors)
             .chain(gate_errors)
-            .chain(lookup_errors)
+            .chain(lookup_errors.into_iter().map(|(_, error)| error))
             .chain(perm_errors)
-            .chain(shuffle_errors)
+            .chain(shuffle_errors.into_iter().map(|(_, error)| error))
             .collect();
         if errors.is_empty() {
             Ok(())
@@ -1188,12 +1676,17 @@ impl<F: FromUniformBytes<64> + Ord> MockProver<F> {
             // Remove any duplicate `ConstraintPoisoned` errors (we check all unavailable
             // rows in case the trigger is row-specific, but the error message only points
             // at the constraint).
-            errors.dedup_by(|a, b| match (a, b) {
-                (
-                    a @ VerifyFailure::ConstraintPoisoned { .. },
-                    b @ VerifyFailure::ConstraintPoisoned { .. },
-                ) => a == b,
-                _ => false,
+            let mut poisoned = vec![];
+            errors.retain(|error| match error {
+                VerifyFailure::ConstraintPoisoned { constraint } => {
+                    if poisoned.contains(constraint) {
+                        false
+                    } else {
+                        poisoned.push(constraint.clone());
+                        true
+                    }
+                }
+                _ => true,
             });
             Err(errors)
         }
@@ -1243,14 +1736,85 @@ impl<F: FromUniformBytes<64> + Ord> MockProver<F> {
         }
     }
 
+    /// Panics if the circuit being checked by this `MockProver` is not satisfied, reporting
+    /// at most `max_failures` of the failures.
+    ///
+    /// The reported failures are pretty-printed to stderr, and listed in the panic message
+    /// with the names of their regions and the annotations of their columns, followed by
+    /// the number of failures that were suppressed. This makes the panic message useful on
+    /// its own, e.g. when asserting on it in tests.
+    ///
+    /// The markers of the failures in the panic message are colored if the `HALO2_COLOR`
+    /// environment variable is set to `always` (or `1`), and are plain text otherwise.
+    pub fn assert_satisfied_with(&self, max_failures: usize) {
+        if let Err(failures) = self.verify() {
+            self.panic_with_failures(failures, max_failures, None);
+        }
+    }
+
+    /// Panics if the circuit being checked by this `MockProver` is not satisfied at
+    /// `rows`, reporting at most `max_failures` of the failures.
+    ///
+    /// The circuit is checked as with [`MockProver::verify_at_rows_with_lookups`], and
+    /// the failures are reported as with [`MockProver::assert_satisfied_with`].
+    ///
+    /// Panics if `rows` is not within the usable rows.
+    pub fn assert_satisfied_at_rows_with_lookups(&self, rows: Range<usize>, max_failures: usize) {
+        if let Err(failures) = self.verify_at_rows_with_lookups(rows.clone()) {
+            self.panic_with_failures(failures, max_failures, Some(rows));
+        }
+    }
+
+    /// Emits the first `max_failures` of `failures`, and panics with a message listing
+    /// them.
+    fn panic_with_failures(
+        &self,
+        failures: Vec<VerifyFailure>,
+        max_failures: usize,
+        rows: Option<Range<usize>>,
+    ) -> ! {
+        let color = matches!(
+            std::env::var("HALO2_COLOR").as_deref(),
+            Ok("always") | Ok("1")
+        );
+        let total = failures.len();
+
+        let mut message = match rows {
+            Some(rows) => format!("circuit was not satisfied at rows {rows:?}: "),
+            None => "circuit was not satisfied: ".to_string(),
+        };
+        message.push_str(&match total {
+            1 => "1 failure".to_string(),
+            _ => format!("{total} failures"),
+        });
+        for (index, failure) in failures.iter().take(max_failures).enumerate() {
+            failure.emit(self);
+            eprintln!();
+
+            let marker = format!("[{}/{}]", index + 1, total);
+            let marker = if color {
+                format!("\x1b[1;31m{marker}\x1b[0m")
+            } else {
+                marker
+            };
+            message.push_str(&format!("\n{marker} {}", failure.to_string().trim_end()));
+        }
+        let suppressed = total.saturating_sub(max_failures);
+        if suppressed > 0 {
+            message.push_str(&format!("\n... {suppressed} more suppressed"));
+        }
+        panic!("{message}");
+    }
+
     /// Returns the constraint system
     pub fn cs(&self) -> &ConstraintSystem<F> {
         &self.cs
     }
 
-    /// Returns the usable rows
-    pub fn usable_rows(&self) -> &Range<usize> {
-        &self.usable_rows
+    /// Returns the rows that the circuit can assign, as computed by
+    /// [`ConstraintSystem::usable_rows`].
+    pub fn usable_rows(&self) -> Range<usize> {
+        self.usable_rows.clone()
     }
 
     /// Returns the list of Advice Columns used within a MockProver instance and the associated values contained on each Cell.
@@ -1278,6 +1842,42 @@ impl<F: FromUniformBytes<64> + Ord> MockProver<F> {
         &self.permutation
     }
 
+    /// Returns [`Error::CopyPhaseMismatch`] for the first copy constraint between advice
+    /// columns of different phases, if any.
+    ///
+    /// These copies are sound, as the permutation argument is committed after every phase,
+    /// so [`MockProver::run`] and keygen accept them. This check catches circuits which
+    /// expect a cell of an earlier phase to hold a value that is only known in a later phase.
+    pub fn check_copy_phases(&self) -> Result<(), Error> {
+        for (left, right) in self.permutation.copies.iter() {
+            let (left, right): (Column<Any>, Column<Any>) =
+                (left.column.into(), right.column.into());
+            if let (Any::Advice(l), Any::Advice(r)) = (left.column_type(), right.column_type()) {
+                if l.phase() != r.phase() {
+                    return Err(Error::CopyPhaseMismatch(left, right));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the cycles of the copy constraints of the circuit, which are the equality
+    /// classes of its copied cells.
+    pub fn copy_cycles(&self) -> CopyCycles {
+        let argument = ArgumentV2 {
+            columns: self
+                .permutation
+                .columns
+                .iter()
+                .map(|column| (*column).into())
+                .collect(),
+        };
+        let assembly = AssemblyMid {
+            copies: self.permutation.copies.clone(),
+        };
+        CopyCycles::new(self.permutation.n, &argument, &assembly)
+    }
+
     /// Returns the Regions used during synthesis.
     pub fn regions(&self) -> &[Region] {
         &self.regions
@@ -1287,13 +1887,17 @@ impl<F: FromUniformBytes<64> + Ord> MockProver<F> {
 #[cfg(test)]
 mod tests {
     use halo2curves::pasta::Fp;
+    use std::marker::PhantomData;
 
     use super::{FailureLocation, MockProver, VerifyFailure};
-    use crate::circuit::{Layouter, SimpleFloorPlanner, Value};
+    use crate::circuit::{compile_circuit, floor_planner::V1, Layouter, SimpleFloorPlanner, Value};
     use halo2_common::plonk::{
-        circuit::Column, Circuit, ConstraintSystem, Error, Expression, Selector, TableColumn,
+        circuit::{Challenge, Column},
+        Circuit, ConstraintSystem, Error, Expression, FirstPhase, FloorPlanner, SecondPhase,
+        Selector, TableColumn,
     };
     use halo2_middleware::circuit::{Advice, Any, Fixed, Instance};
+    use halo2_middleware::ff::Field;
     use halo2_middleware::poly::Rotation;
 
     #[test]
@@ -1836,4 +2440,2169 @@ mod tests {
             },])
         )
     }
+
+    #[test]
+    fn replaced_selector_assigned_as_fixed() {
+        use std::collections::HashMap;
+
+        const K: u32 = 4;
+
+        #[derive(Clone)]
+        struct ReplacedConfig {
+            a: Column<Advice>,
+            b: Column<Advice>,
+            s: Column<Fixed>,
+        }
+
+        struct ReplacedCircuit {
+            b: u64,
+        }
+
+        impl Circuit<Fp> for ReplacedCircuit {
+            type Config = ReplacedConfig;
+            type FloorPlanner = SimpleFloorPlanner;
+            #[cfg(feature = "circuit-params")]
+            type Params = ();
+
+            fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+                let a = meta.advice_column();
+                let b = meta.advice_column();
+                let s = meta.fixed_column();
+                let q = meta.selector();
+
+                meta.create_gate("Equality check", |cells| {
+                    let a = cells.query_advice(a, Rotation::cur());
+                    let b = cells.query_advice(b, Rotation::cur());
+                    let q = cells.query_selector(q);
+                    vec![q * (a - b)]
+                });
+
+                // Lower the selector onto `s` ourselves.
+                let replacements = HashMap::from([(0, s.cur())]);
+                meta.replace_selectors(&replacements).unwrap();
+                assert_eq!(meta.num_selectors(), 0);
+
+                ReplacedConfig { a, b, s }
+            }
+
+            fn without_witnesses(&self) -> Self {
+                Self { b: self.b }
+            }
+
+            fn synthesize(
+                &self,
+                config: Self::Config,
+                mut layouter: impl Layouter<Fp>,
+            ) -> Result<(), Error> {
+                layouter.assign_region(
+                    || "Equality",
+                    |mut region| {
+                        // Assign `s` where the selector would have been enabled.
+                        region.assign_fixed(|| "s", config.s, 0, || Value::known(Fp::one()))?;
+                        region.assign_advice(|| "a", config.a, 0, || Value::known(Fp::one()))?;
+                        region.assign_advice(
+                            || "b",
+                            config.b,
+                            0,
+                            || Value::known(Fp::from(self.b)),
+                        )?;
+                        Ok(())
+                    },
+                )
+            }
+        }
+
+        let prover = MockProver::run(K, &ReplacedCircuit { b: 1 }, vec![]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+
+        let prover = MockProver::run(K, &ReplacedCircuit { b: 2 }, vec![]).unwrap();
+        assert!(matches!(
+            prover.verify().unwrap_err()[..],
+            [VerifyFailure::ConstraintNotSatisfied { .. }]
+        ));
+    }
+
+    #[test]
+    fn skip_later_phase_assignment() {
+        const K: u32 = 4;
+
+        #[derive(Clone)]
+        struct RlcConfig {
+            a: Column<Advice>,
+            rlc: Column<Advice>,
+            c: Challenge,
+            s: Selector,
+        }
+
+        struct RlcCircuit {
+            a: Value<Fp>,
+        }
+
+        impl Circuit<Fp> for RlcCircuit {
+            type Config = RlcConfig;
+            type FloorPlanner = V1;
+            #[cfg(feature = "circuit-params")]
+            type Params = ();
+
+            fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+                let a = meta.advice_column();
+                let rlc = meta.advice_column_in(SecondPhase);
+                let c = meta.challenge_usable_after(FirstPhase);
+                let s = meta.selector();
+
+                meta.create_gate("rlc", |cells| {
+                    let a = cells.query_advice(a, Rotation::cur());
+                    let rlc = cells.query_advice(rlc, Rotation::cur());
+                    let c = cells.query_challenge(c);
+                    let s = cells.query_selector(s);
+
+                    vec![s * (rlc - a * c)]
+                });
+
+                RlcConfig { a, rlc, c, s }
+            }
+
+            fn without_witnesses(&self) -> Self {
+                Self {
+                    a: Value::unknown(),
+                }
+            }
+
+            fn synthesize(
+                &self,
+                config: Self::Config,
+                mut layouter: impl Layouter<Fp>,
+            ) -> Result<(), Error> {
+                let c = layouter.get_challenge(config.c);
+                let second_phase = layouter.in_phase(SecondPhase);
+
+                layouter.assign_region(
+                    || "rlc",
+                    |mut region| {
+                        config.s.enable(&mut region, 0)?;
+                        region.assign_advice(|| "a", config.a, 0, || self.a)?;
+                        // Only assign the second-phase column once the challenge is known.
+                        if second_phase {
+                            region.assign_advice(|| "rlc", config.rlc, 0, || self.a * c)?;
+                        }
+                        Ok(())
+                    },
+                )
+            }
+        }
+
+        let prover = MockProver::run(
+            K,
+            &RlcCircuit {
+                a: Value::known(Fp::from(3)),
+            },
+            vec![],
+        )
+        .unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+    }
+
+    #[test]
+    fn witness_failure_reports_region() {
+        const K: u32 = 4;
+
+        struct FailingCircuit<P: FloorPlanner>(PhantomData<P>);
+
+        impl<P: FloorPlanner> Circuit<Fp> for FailingCircuit<P> {
+            type Config = Column<Advice>;
+            type FloorPlanner = P;
+            #[cfg(feature = "circuit-params")]
+            type Params = ();
+
+            fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+                meta.advice_column()
+            }
+
+            fn without_witnesses(&self) -> Self {
+                Self(PhantomData)
+            }
+
+            fn synthesize(
+                &self,
+                config: Self::Config,
+                mut layouter: impl Layouter<Fp>,
+            ) -> Result<(), Error> {
+                layouter.assign_region(
+                    || "inversions",
+                    |mut region| {
+                        for offset in 0..4 {
+                            // The inversion fails at offset 2.
+                            region.try_assign_advice(
+                                || "inverse",
+                                config,
+                                offset,
+                                || {
+                                    Option::<Fp>::from(
+                                        (Fp::from(offset as u64) - Fp::from(2)).invert(),
+                                    )
+                                    .map(Value::known)
+                                    .ok_or(Error::Synthesis)
+                                },
+                            )?;
+                        }
+                        Ok(())
+                    },
+                )
+            }
+        }
+
+        fn check<P: FloorPlanner>() {
+            let err = MockProver::run(K, &FailingCircuit::<P>(PhantomData), vec![]).unwrap_err();
+            assert!(matches!(
+                &err,
+                Error::WitnessFailure { region, offset: 2, error, .. }
+                    if region == "inversions" && matches!(**error, Error::Synthesis)
+            ));
+            assert_eq!(
+                err.to_string(),
+                "Failed to compute the witness for \"inverse\" at offset 2 in region \"inversions\": General synthesis error"
+            );
+        }
+
+        check::<SimpleFloorPlanner>();
+        check::<V1>();
+    }
+
+    #[test]
+    fn enable_selector_range_matches_loop() {
+        const K: u32 = 5;
+
+        #[derive(Clone)]
+        struct RangeConfig {
+            a: Column<Advice>,
+            s: Selector,
+        }
+
+        struct RangeCircuit<P: FloorPlanner> {
+            use_range: bool,
+            _marker: PhantomData<P>,
+        }
+
+        impl<P: FloorPlanner> Circuit<Fp> for RangeCircuit<P> {
+            type Config = RangeConfig;
+            type FloorPlanner = P;
+            #[cfg(feature = "circuit-params")]
+            type Params = ();
+
+            fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+                let a = meta.advice_column();
+                let s = meta.selector();
+
+                meta.create_gate("a is one", |cells| {
+                    let a = cells.query_advice(a, Rotation::cur());
+                    let s = cells.query_selector(s);
+
+                    vec![s * (a - Expression::Constant(Fp::one()))]
+                });
+
+                RangeConfig { a, s }
+            }
+
+            fn without_witnesses(&self) -> Self {
+                Self {
+                    use_range: self.use_range,
+                    _marker: PhantomData,
+                }
+            }
+
+            fn synthesize(
+                &self,
+                config: Self::Config,
+                mut layouter: impl Layouter<Fp>,
+            ) -> Result<(), Error> {
+                layouter.assign_region(
+                    || "range",
+                    |mut region| {
+                        for offset in 0..4 {
+                            region.assign_advice(
+                                || "a",
+                                config.a,
+                                offset,
+                                || Value::known(Fp::one()),
+                            )?;
+                        }
+                        // The selector range extends past the assigned advice cells, so
+                        // the region shape must be taken from the range.
+                        if self.use_range {
+                            config.s.enable_range(&mut region, 1..8)?;
+                        } else {
+                            for offset in 1..8 {
+                                config.s.enable(&mut region, offset)?;
+                            }
+                        }
+                        for offset in 4..8 {
+                            region.assign_advice(
+                                || "a",
+                                config.a,
+                                offset,
+                                || Value::known(Fp::one()),
+                            )?;
+                        }
+                        Ok(())
+                    },
+                )?;
+
+                layouter.assign_region(
+                    || "single",
+                    |mut region| {
+                        config.s.enable(&mut region, 0)?;
+                        region.assign_advice(|| "a", config.a, 0, || Value::known(Fp::one()))?;
+                        Ok(())
+                    },
+                )
+            }
+        }
+
+        fn check<P: FloorPlanner>() {
+            let looped = RangeCircuit::<P> {
+                use_range: false,
+                _marker: PhantomData,
+            };
+            let ranged = RangeCircuit::<P> {
+                use_range: true,
+                _marker: PhantomData,
+            };
+
+            let looped_prover = MockProver::run(K, &looped, vec![]).unwrap();
+            let ranged_prover = MockProver::run(K, &ranged, vec![]).unwrap();
+            assert_eq!(ranged_prover.verify(), Ok(()));
+            assert_eq!(looped_prover.selectors, ranged_prover.selectors);
+            assert_eq!(looped_prover.fixed, ranged_prover.fixed);
+            assert_eq!(looped_prover.regions.len(), ranged_prover.regions.len());
+            for (l, r) in looped_prover.regions.iter().zip(&ranged_prover.regions) {
+                assert_eq!(l.rows, r.rows);
+                assert_eq!(l.enabled_selectors, r.enabled_selectors);
+            }
+
+            let (looped_compiled, _, _) = compile_circuit(K, &looped, false).unwrap();
+            let (ranged_compiled, _, _) = compile_circuit(K, &ranged, false).unwrap();
+            assert_eq!(
+                looped_compiled.preprocessing.fixed,
+                ranged_compiled.preprocessing.fixed
+            );
+        }
+
+        check::<SimpleFloorPlanner>();
+        check::<V1>();
+    }
+
+    #[test]
+    fn region_scoped_annotations() {
+        const K: u32 = 4;
+
+        #[derive(Clone)]
+        struct AnnotatedConfig {
+            a: Column<Advice>,
+            b: Column<Advice>,
+            q: Selector,
+        }
+
+        struct AnnotatedCircuit {}
+
+        impl Circuit<Fp> for AnnotatedCircuit {
+            type Config = AnnotatedConfig;
+            type FloorPlanner = SimpleFloorPlanner;
+            #[cfg(feature = "circuit-params")]
+            type Params = ();
+
+            fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+                let a = meta.advice_column();
+                let b = meta.advice_column();
+                let q = meta.selector();
+
+                // Circuit-wide annotation, used when a region doesn't annotate `b`.
+                meta.annotate_lookup_any_column(b, || "global b");
+
+                meta.create_gate("a equals b", |cells| {
+                    let a = cells.query_advice(a, Rotation::cur());
+                    let b = cells.query_advice(b, Rotation::cur());
+                    let q = cells.query_selector(q);
+
+                    vec![q * (a - b)]
+                });
+
+                AnnotatedConfig { a, b, q }
+            }
+
+            fn without_witnesses(&self) -> Self {
+                Self {}
+            }
+
+            fn synthesize(
+                &self,
+                config: Self::Config,
+                mut layouter: impl Layouter<Fp>,
+            ) -> Result<(), Error> {
+                for (name, annotation, a, b) in [("first", "lhs", 1, 2), ("second", "rhs", 3, 4)] {
+                    layouter.assign_region(
+                        || name,
+                        |mut region| {
+                            region.name_column(|| annotation, config.a);
+                            config.q.enable(&mut region, 0)?;
+                            region.assign_advice(
+                                || "a",
+                                config.a,
+                                0,
+                                || Value::known(Fp::from(a)),
+                            )?;
+                            region.assign_advice(
+                                || "b",
+                                config.b,
+                                0,
+                                || Value::known(Fp::from(b)),
+                            )?;
+                            Ok(())
+                        },
+                    )?;
+                }
+                Ok(())
+            }
+        }
+
+        let prover = MockProver::run(K, &AnnotatedCircuit {}, vec![]).unwrap();
+        let failures = prover
+            .verify()
+            .unwrap_err()
+            .iter()
+            .map(|failure| failure.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(failures.len(), 2);
+
+        assert!(failures[0].contains("Region 0 ('first')"));
+        assert!(failures[0].contains("Column('Advice', 0 - lhs)@0"));
+        assert!(failures[0].contains("Column('Advice', 1 - global b)@0"));
+
+        assert!(failures[1].contains("Region 1 ('second')"));
+        assert!(failures[1].contains("Column('Advice', 0 - rhs)@0"));
+        assert!(failures[1].contains("Column('Advice', 1 - global b)@0"));
+    }
+
+    #[test]
+    fn copy_advice_from_region() {
+        const K: u32 = 5;
+
+        #[derive(Clone)]
+        struct CopyConfig {
+            a: Column<Advice>,
+            b: Column<Advice>,
+        }
+
+        struct CopyCircuit<P: FloorPlanner> {
+            values: Vec<Value<Fp>>,
+            _marker: PhantomData<P>,
+        }
+
+        impl<P: FloorPlanner> Circuit<Fp> for CopyCircuit<P> {
+            type Config = CopyConfig;
+            type FloorPlanner = P;
+            #[cfg(feature = "circuit-params")]
+            type Params = ();
+
+            fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+                let a = meta.advice_column();
+                let b = meta.advice_column();
+                meta.enable_equality(a);
+                meta.enable_equality(b);
+                CopyConfig { a, b }
+            }
+
+            fn without_witnesses(&self) -> Self {
+                Self {
+                    values: vec![Value::unknown(); self.values.len()],
+                    _marker: PhantomData,
+                }
+            }
+
+            fn synthesize(
+                &self,
+                config: Self::Config,
+                mut layouter: impl Layouter<Fp>,
+            ) -> Result<(), Error> {
+                let cells = layouter.assign_region(
+                    || "source",
+                    |mut region| {
+                        self.values
+                            .iter()
+                            .enumerate()
+                            .map(|(offset, value)| {
+                                region.assign_advice(|| "value", config.a, offset, || *value)
+                            })
+                            .collect::<Result<Vec<_>, _>>()
+                    },
+                )?;
+
+                let copies = layouter.assign_region(
+                    || "destination",
+                    |mut region| region.copy_advice_from(|| "copy", &cells, config.b, 1),
+                )?;
+
+                assert_eq!(copies.len(), cells.len());
+                for (copy, cell) in copies.iter().zip(cells.iter()) {
+                    copy.value()
+                        .zip(cell.value())
+                        .assert_if_known(|(copy, cell)| copy == cell);
+                }
+                Ok(())
+            }
+        }
+
+        fn check<P: FloorPlanner>() {
+            let values = [3, 5, 7].map(Fp::from);
+            let circuit = CopyCircuit::<P> {
+                values: values.iter().copied().map(Value::known).collect(),
+                _marker: PhantomData,
+            };
+            let prover = MockProver::run(K, &circuit, vec![]).unwrap();
+            assert_eq!(prover.verify(), Ok(()));
+
+            // Every copy is constrained to its original, and carries the same value.
+            let copies = &prover.permutation().copies;
+            assert_eq!(copies.len(), values.len());
+            let mut copied = Vec::new();
+            for (copy, original) in copies {
+                assert_eq!(copy.column.index, 1);
+                assert_eq!(original.column.index, 0);
+                let copy_value = prover.advice()[copy.column.index][copy.row];
+                let original_value = prover.advice()[original.column.index][original.row];
+                assert_eq!(copy_value, original_value);
+                copied.push(copy_value);
+            }
+            assert_eq!(copied, values.map(super::CellValue::Assigned).to_vec());
+        }
+
+        check::<SimpleFloorPlanner>();
+        check::<V1>();
+    }
+
+    #[test]
+    fn assign_advice_from_expression() {
+        const K: u32 = 4;
+
+        #[derive(Clone)]
+        struct MulAddConfig {
+            a: Column<Advice>,
+            b: Column<Advice>,
+            c: Column<Advice>,
+            s: Selector,
+            c_expr: Expression<Fp>,
+        }
+
+        struct MulAddCircuit {
+            values: Vec<(Value<Fp>, Value<Fp>)>,
+        }
+
+        impl Circuit<Fp> for MulAddCircuit {
+            type Config = MulAddConfig;
+            type FloorPlanner = SimpleFloorPlanner;
+            #[cfg(feature = "circuit-params")]
+            type Params = ();
+
+            fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+                let a = meta.advice_column();
+                let b = meta.advice_column();
+                let c = meta.advice_column();
+                let s = meta.selector();
+
+                // The expression that `c` is constrained to, also used to compute `c`.
+                let c_expr = a.cur() * b.cur() + Expression::Constant(Fp::from(3));
+                meta.create_gate("mul add", |meta| {
+                    let s = meta.query_selector(s);
+                    let c = meta.query_advice(c, Rotation::cur());
+                    vec![s * (c_expr.clone() - c)]
+                });
+
+                MulAddConfig { a, b, c, s, c_expr }
+            }
+
+            fn without_witnesses(&self) -> Self {
+                Self {
+                    values: vec![(Value::unknown(), Value::unknown()); self.values.len()],
+                }
+            }
+
+            fn synthesize(
+                &self,
+                config: Self::Config,
+                mut layouter: impl Layouter<Fp>,
+            ) -> Result<(), Error> {
+                layouter.assign_region(
+                    || "mul add",
+                    |mut region| {
+                        for (offset, (a, b)) in self.values.iter().enumerate() {
+                            config.s.enable(&mut region, offset)?;
+                            let a = region.assign_advice(|| "a", config.a, offset, || *a)?;
+                            let b = region.assign_advice(|| "b", config.b, offset, || *b)?;
+                            let bindings = |column: Column<Any>, rotation: Rotation| {
+                                assert_eq!(rotation, Rotation::cur());
+                                if column == Column::<Any>::from(config.a) {
+                                    a.value().copied()
+                                } else {
+                                    assert_eq!(column, Column::<Any>::from(config.b));
+                                    b.value().copied()
+                                }
+                            };
+                            let c = region.assign_advice_from_expression(
+                                || "c",
+                                config.c,
+                                offset,
+                                &config.c_expr,
+                                &bindings,
+                            )?;
+
+                            // Unknown inputs make the assigned value unknown.
+                            let inputs = a.value().zip(b.value());
+                            assert_eq!(c.value().assign().is_ok(), inputs.assign().is_ok());
+                            c.value()
+                                .zip(inputs)
+                                .assert_if_known(|(c, (a, b))| **c == **a * **b + Fp::from(3));
+                        }
+                        Ok(())
+                    },
+                )
+            }
+        }
+
+        let circuit = MulAddCircuit {
+            values: [(2, 5), (0, 7), (9, 9)]
+                .map(|(a, b)| (Value::known(Fp::from(a)), Value::known(Fp::from(b))))
+                .to_vec(),
+        };
+        let prover = MockProver::run(K, &circuit, vec![]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+
+        // The keygen synthesizes the circuit without witnesses.
+        compile_circuit(K, &circuit.without_witnesses(), false).unwrap();
+    }
+
+    #[test]
+    fn constrain_equal_checked_compares_known_values() {
+        const K: u32 = 4;
+
+        #[derive(Clone)]
+        struct CopyConfig {
+            a: Column<Advice>,
+            b: Column<Advice>,
+        }
+
+        struct CopyCircuit {
+            a: Value<Fp>,
+            b: Value<Fp>,
+        }
+
+        impl Circuit<Fp> for CopyCircuit {
+            type Config = CopyConfig;
+            type FloorPlanner = SimpleFloorPlanner;
+            #[cfg(feature = "circuit-params")]
+            type Params = ();
+
+            fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+                let a = meta.advice_column();
+                let b = meta.advice_column();
+                meta.enable_equality(a);
+                meta.enable_equality(b);
+                CopyConfig { a, b }
+            }
+
+            fn without_witnesses(&self) -> Self {
+                Self {
+                    a: Value::unknown(),
+                    b: Value::unknown(),
+                }
+            }
+
+            fn synthesize(
+                &self,
+                config: Self::Config,
+                mut layouter: impl Layouter<Fp>,
+            ) -> Result<(), Error> {
+                let a = layouter.assign_region(
+                    || "a",
+                    |mut region| region.assign_advice(|| "a", config.a, 0, || self.a),
+                )?;
+                layouter.assign_region(
+                    || "b",
+                    |mut region| {
+                        let b = region.assign_advice(|| "b", config.b, 1, || self.b)?;
+                        region.constrain_equal_checked(&a, &b)
+                    },
+                )
+            }
+        }
+
+        let circuit = |a: u64, b: u64| CopyCircuit {
+            a: Value::known(Fp::from(a)),
+            b: Value::known(Fp::from(b)),
+        };
+
+        let prover = MockProver::run(K, &circuit(3, 3), vec![]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+        assert_eq!(prover.permutation().copies.len(), 1);
+
+        let err = MockProver::run(K, &circuit(3, 5), vec![]).unwrap_err();
+        match err.root() {
+            Error::CopyValueMismatch {
+                left,
+                right,
+                left_value,
+                right_value,
+            } => {
+                assert_eq!((*left.region_index, left.row_offset), (0, 0));
+                assert_eq!((*right.region_index, right.row_offset), (1, 1));
+                assert_eq!(left_value, &format!("{:?}", Fp::from(3)));
+                assert_eq!(right_value, &format!("{:?}", Fp::from(5)));
+            }
+            err => panic!("unexpected error {err:?}"),
+        }
+        assert!(err.to_string().contains("different values"));
+
+        // Without witnesses, the copy is emitted and nothing is compared.
+        let (compiled, _, _) =
+            compile_circuit(K, &circuit(3, 5).without_witnesses(), false).unwrap();
+        assert_eq!(compiled.preprocessing.permutation.copies.len(), 1);
+    }
+
+    #[test]
+    fn enabled_rows_outside_usable_rows() {
+        const K: u32 = 4;
+
+        #[derive(Clone)]
+        struct EdgeConfig {
+            a: Column<Advice>,
+            s: Selector,
+        }
+
+        struct EdgeCircuit {
+            selector_row: usize,
+            advice_row: usize,
+        }
+
+        impl Circuit<Fp> for EdgeCircuit {
+            type Config = EdgeConfig;
+            type FloorPlanner = SimpleFloorPlanner;
+            #[cfg(feature = "circuit-params")]
+            type Params = ();
+
+            fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+                let a = meta.advice_column();
+                let s = meta.selector();
+                meta.create_gate("a is zero", |cells| {
+                    let s = cells.query_selector(s);
+                    let a = cells.query_advice(a, Rotation::cur());
+                    vec![s * a]
+                });
+                EdgeConfig { a, s }
+            }
+
+            fn without_witnesses(&self) -> Self {
+                Self {
+                    selector_row: self.selector_row,
+                    advice_row: self.advice_row,
+                }
+            }
+
+            fn synthesize(
+                &self,
+                config: Self::Config,
+                mut layouter: impl Layouter<Fp>,
+            ) -> Result<(), Error> {
+                layouter.assign_region(
+                    || "edge",
+                    |mut region| {
+                        config.s.enable(&mut region, self.selector_row)?;
+                        region.assign_advice(
+                            || "a",
+                            config.a,
+                            self.advice_row,
+                            || Value::known(Fp::zero()),
+                        )?;
+                        Ok(())
+                    },
+                )
+            }
+        }
+
+        let mut cs = ConstraintSystem::<Fp>::default();
+        EdgeCircuit::configure(&mut cs);
+        let usable_rows = cs.usable_rows(K);
+        let last = usable_rows.end - 1;
+
+        let circuit = EdgeCircuit {
+            selector_row: last,
+            advice_row: last,
+        };
+        let prover = MockProver::run(K, &circuit, vec![]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+
+        // A selector enabled in the blinding rows is reported by the mock prover, as it
+        // is rejected by the real prover.
+        let circuit = EdgeCircuit {
+            selector_row: usable_rows.end,
+            advice_row: last,
+        };
+        let err = MockProver::run(K, &circuit, vec![]).unwrap_err();
+        match err.root() {
+            Error::SelectorOutsideUsableRows {
+                row,
+                usable_rows: rows,
+                ..
+            } => assert_eq!((*row, rows), (usable_rows.end, &usable_rows)),
+            err => panic!("unexpected error {err:?}"),
+        }
+        assert!(err.to_string().contains("blinding factors"));
+        assert!(compile_circuit(K, &circuit, false).is_err());
+
+        let circuit = EdgeCircuit {
+            selector_row: last,
+            advice_row: usable_rows.end,
+        };
+        let err = MockProver::run(K, &circuit, vec![]).unwrap_err();
+        match err.root() {
+            Error::AdviceOutsideUsableRows {
+                column,
+                row,
+                usable_rows: rows,
+            } => {
+                assert_eq!(column.index(), 0);
+                assert_eq!((*row, rows), (usable_rows.end, &usable_rows));
+            }
+            err => panic!("unexpected error {err:?}"),
+        }
+    }
+
+    #[test]
+    fn assign_advice_column_matches_loop() {
+        use crate::circuit::WitnessCalculator;
+        use halo2_common::circuit::layouter::SyncDeps;
+        use halo2_common::plonk::{Assigned, Assignment};
+        use std::collections::HashMap;
+
+        const K: u32 = 5;
+
+        /// A floor planner that writes a fixed sequence of values into the first advice
+        /// column, either cell by cell or as a single column slice.
+        struct ColumnPlanner<const BATCH: bool>;
+
+        impl<const BATCH: bool> FloorPlanner for ColumnPlanner<BATCH> {
+            fn synthesize<F: Field, CS: Assignment<F> + SyncDeps, C: Circuit<F>>(
+                cs: &mut CS,
+                _: &C,
+                _: C::Config,
+                _: Vec<Column<Fixed>>,
+            ) -> Result<(), Error> {
+                let column = Column::new(0, Advice::default());
+                let mut value = F::ONE;
+                let values: Vec<Value<Assigned<F>>> = (0..8)
+                    .map(|_| {
+                        value = value.double() + F::ONE;
+                        Value::known(value.into())
+                    })
+                    .collect();
+
+                if BATCH {
+                    cs.assign_advice_column(column, 2, &values)
+                } else {
+                    for (row, value) in (2..).zip(values.iter()) {
+                        cs.assign_advice(|| "", column, row, || *value)?;
+                    }
+                    Ok(())
+                }
+            }
+        }
+
+        struct ColumnCircuit<P: FloorPlanner>(PhantomData<P>);
+
+        impl<P: FloorPlanner> Circuit<Fp> for ColumnCircuit<P> {
+            type Config = Column<Advice>;
+            type FloorPlanner = P;
+            #[cfg(feature = "circuit-params")]
+            type Params = ();
+
+            fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+                meta.advice_column()
+            }
+
+            fn without_witnesses(&self) -> Self {
+                Self(PhantomData)
+            }
+
+            fn synthesize(&self, _: Self::Config, _: impl Layouter<Fp>) -> Result<(), Error> {
+                unreachable!("ColumnPlanner does not synthesize the circuit")
+            }
+        }
+
+        let looped = ColumnCircuit::<ColumnPlanner<false>>(PhantomData);
+        let batched = ColumnCircuit::<ColumnPlanner<true>>(PhantomData);
+
+        // MockProver
+        let looped_prover = MockProver::run(K, &looped, vec![]).unwrap();
+        let batched_prover = MockProver::run(K, &batched, vec![]).unwrap();
+        assert_eq!(looped_prover.advice(), batched_prover.advice());
+
+        // Witness calculation
+        fn witness<P: FloorPlanner>(circuit: &ColumnCircuit<P>) -> Vec<Option<Vec<Fp>>> {
+            let (_, config, cs) = compile_circuit(K, circuit, false).unwrap();
+            let mut witness_calc = WitnessCalculator::new(K, circuit, &config, &cs, &[]);
+            witness_calc.calc(0, &HashMap::new()).unwrap()
+        }
+        assert_eq!(witness(&looped), witness(&batched));
+    }
+
+    #[test]
+    fn assignment_hooks() {
+        use halo2_common::plonk::AssignmentHooks;
+
+        const K: u32 = 5;
+
+        #[derive(Default)]
+        struct CountingHooks {
+            phases: Vec<u8>,
+            regions_started: Vec<String>,
+            regions_finished: Vec<(String, usize)>,
+            cells: usize,
+        }
+
+        impl AssignmentHooks for CountingHooks {
+            fn phase_started(&mut self, phase: u8) {
+                self.phases.push(phase);
+            }
+
+            fn region_started(&mut self, name: &str) {
+                self.regions_started.push(name.to_string());
+            }
+
+            fn region_finished(&mut self, name: &str, rows: usize) {
+                self.regions_finished.push((name.to_string(), rows));
+            }
+
+            fn cells_assigned(&mut self, n: usize) {
+                self.cells += n;
+            }
+        }
+
+        #[derive(Clone)]
+        struct AddConfig {
+            a: Column<Advice>,
+            s: Selector,
+        }
+
+        struct AddCircuit;
+
+        impl Circuit<Fp> for AddCircuit {
+            type Config = AddConfig;
+            type FloorPlanner = SimpleFloorPlanner;
+            #[cfg(feature = "circuit-params")]
+            type Params = ();
+
+            fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+                let a = meta.advice_column();
+                let s = meta.selector();
+
+                meta.create_gate("add", |cells| {
+                    let lhs = cells.query_advice(a, Rotation::cur());
+                    let rhs = cells.query_advice(a, Rotation::next());
+                    let out = cells.query_advice(a, Rotation(2));
+                    let s = cells.query_selector(s);
+                    vec![s * (lhs + rhs - out)]
+                });
+
+                AddConfig { a, s }
+            }
+
+            fn without_witnesses(&self) -> Self {
+                Self
+            }
+
+            fn synthesize(
+                &self,
+                config: Self::Config,
+                mut layouter: impl Layouter<Fp>,
+            ) -> Result<(), Error> {
+                for name in ["first", "second"] {
+                    layouter.assign_region(
+                        || name,
+                        |mut region| {
+                            config.s.enable(&mut region, 0)?;
+                            for (offset, value) in [1, 2, 3].into_iter().enumerate() {
+                                region.assign_advice(
+                                    || "value",
+                                    config.a,
+                                    offset,
+                                    || Value::known(Fp::from(value)),
+                                )?;
+                            }
+                            Ok(())
+                        },
+                    )?;
+                }
+                Ok(())
+            }
+        }
+
+        let mut hooks = CountingHooks::default();
+        let prover = MockProver::run_with_hooks(K, &AddCircuit, vec![], &mut hooks).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+
+        assert_eq!(hooks.phases, vec![0]);
+        assert_eq!(hooks.regions_started, vec!["first", "second"]);
+        assert_eq!(
+            hooks.regions_finished,
+            vec![("first".to_string(), 3), ("second".to_string(), 3)]
+        );
+        assert_eq!(hooks.cells, 6);
+
+        #[cfg(feature = "tracing")]
+        {
+            let mut hooks = super::LoggingHooks::new();
+            MockProver::run_with_hooks(K, &AddCircuit, vec![], &mut hooks).unwrap();
+            assert_eq!(hooks.regions(), 2);
+            assert_eq!(hooks.cells(), 6);
+        }
+    }
+
+    #[test]
+    fn fill_advice_from_offset() {
+        use super::CellValue;
+        use halo2_common::plonk::Assignment;
+
+        const K: u32 = 5;
+
+        #[derive(Clone)]
+        struct TableConfig {
+            table: Column<Advice>,
+            q: Selector,
+        }
+
+        struct TableCircuit<P: FloorPlanner>(PhantomData<P>);
+
+        impl<P: FloorPlanner> Circuit<Fp> for TableCircuit<P> {
+            type Config = TableConfig;
+            type FloorPlanner = P;
+            #[cfg(feature = "circuit-params")]
+            type Params = ();
+
+            fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+                let table = meta.advice_column();
+                let q = meta.selector();
+
+                // The content of the table is increasing; the padding is unconstrained.
+                meta.create_gate("increasing", |cells| {
+                    let cur = cells.query_advice(table, Rotation::cur());
+                    let next = cells.query_advice(table, Rotation::next());
+                    let q = cells.query_selector(q);
+                    vec![q * (next - cur - Expression::Constant(Fp::ONE))]
+                });
+
+                TableConfig { table, q }
+            }
+
+            fn without_witnesses(&self) -> Self {
+                Self(PhantomData)
+            }
+
+            fn synthesize(
+                &self,
+                config: Self::Config,
+                mut layouter: impl Layouter<Fp>,
+            ) -> Result<(), Error> {
+                layouter.assign_region(
+                    || "table",
+                    |mut region| {
+                        config.q.enable_range(&mut region, 0..3)?;
+                        for offset in 0..4 {
+                            region.assign_advice(
+                                || "entry",
+                                config.table,
+                                offset,
+                                || Value::known(Fp::from(offset as u64 + 1)),
+                            )?;
+                        }
+                        region.fill_advice_from_offset(config.table, 4, Value::known(Fp::ZERO))
+                    },
+                )
+            }
+        }
+
+        fn check<P: FloorPlanner>() {
+            let prover = MockProver::run(K, &TableCircuit::<P>(PhantomData), vec![]).unwrap();
+            assert_eq!(prover.verify(), Ok(()));
+
+            let usable_rows = prover.usable_rows().end;
+            let table = &prover.advice()[0];
+            for (row, cell) in table.iter().enumerate() {
+                let expected = if row < 4 {
+                    CellValue::Assigned(Fp::from(row as u64 + 1))
+                } else if row < usable_rows {
+                    CellValue::Assigned(Fp::ZERO)
+                } else {
+                    // The blinding rows are not clobbered.
+                    CellValue::Poison(row)
+                };
+                assert_eq!(*cell, expected);
+            }
+
+            // Only the first row of the padding is part of the region.
+            let region = &prover.regions()[0];
+            assert_eq!(region.rows(), Some((0, 4)));
+            assert_eq!(region.cells().len(), 5);
+        }
+
+        check::<SimpleFloorPlanner>();
+        check::<V1>();
+
+        // Filling from the end of the usable rows assigns nothing, and beyond is an error.
+        let mut prover =
+            MockProver::run(K, &TableCircuit::<SimpleFloorPlanner>(PhantomData), vec![]).unwrap();
+        let table = Column::new(0, Advice::default());
+        let end = prover.usable_rows().end;
+        let one = Value::known(Fp::ONE.into());
+        prover.fill_advice_from_row(table, end, one).unwrap();
+        assert!(prover.fill_advice_from_row(table, end + 1, one).is_err());
+        assert_eq!(prover.verify(), Ok(()));
+    }
+
+    #[test]
+    fn fill_advice_keeps_other_regions() {
+        use super::CellValue;
+
+        const K: u32 = 5;
+
+        #[derive(Clone)]
+        struct TableConfig {
+            table: Column<Advice>,
+            q: Selector,
+        }
+
+        struct TableCircuit;
+
+        impl Circuit<Fp> for TableCircuit {
+            type Config = TableConfig;
+            type FloorPlanner = V1;
+            #[cfg(feature = "circuit-params")]
+            type Params = ();
+
+            fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+                let table = meta.advice_column();
+                let q = meta.selector();
+
+                meta.create_gate("increasing", |cells| {
+                    let cur = cells.query_advice(table, Rotation::cur());
+                    let next = cells.query_advice(table, Rotation::next());
+                    let q = cells.query_selector(q);
+                    vec![q * (next - cur - Expression::Constant(Fp::ONE))]
+                });
+
+                TableConfig { table, q }
+            }
+
+            fn without_witnesses(&self) -> Self {
+                Self
+            }
+
+            fn synthesize(
+                &self,
+                config: Self::Config,
+                mut layouter: impl Layouter<Fp>,
+            ) -> Result<(), Error> {
+                // This region is synthesized first, but V1 places it after the bigger table,
+                // within the rows filled by the padding.
+                layouter.assign_region(
+                    || "values",
+                    |mut region| {
+                        for (offset, value) in [7, 9].into_iter().enumerate() {
+                            region.assign_advice(
+                                || "value",
+                                config.table,
+                                offset,
+                                || Value::known(Fp::from(value)),
+                            )?;
+                        }
+                        Ok(())
+                    },
+                )?;
+
+                layouter.assign_region(
+                    || "table",
+                    |mut region| {
+                        config.q.enable_range(&mut region, 0..3)?;
+                        for offset in 0..4 {
+                            region.assign_advice(
+                                || "entry",
+                                config.table,
+                                offset,
+                                || Value::known(Fp::from(offset as u64 + 1)),
+                            )?;
+                        }
+                        region.fill_advice_from_offset(config.table, 4, Value::known(Fp::ZERO))
+                    },
+                )
+            }
+        }
+
+        let prover = MockProver::run(K, &TableCircuit, vec![]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+
+        assert_eq!(prover.regions()[0].rows(), Some((5, 6)));
+        assert_eq!(prover.regions()[1].rows(), Some((0, 4)));
+
+        let usable_rows = prover.usable_rows().end;
+        for (row, cell) in prover.advice()[0].iter().enumerate() {
+            let expected = match row {
+                0..=3 => CellValue::Assigned(Fp::from(row as u64 + 1)),
+                5 => CellValue::Assigned(Fp::from(7)),
+                6 => CellValue::Assigned(Fp::from(9)),
+                _ if row < usable_rows => CellValue::Assigned(Fp::ZERO),
+                _ => CellValue::Poison(row),
+            };
+            assert_eq!(*cell, expected);
+        }
+    }
+
+    #[test]
+    fn region_out_of_rows_reports_region() {
+        const K: u32 = 4;
+
+        struct TallCircuit;
+
+        impl Circuit<Fp> for TallCircuit {
+            type Config = (Column<Advice>, Column<Advice>);
+            type FloorPlanner = V1;
+            #[cfg(feature = "circuit-params")]
+            type Params = ();
+
+            fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+                (meta.advice_column(), meta.advice_column())
+            }
+
+            fn without_witnesses(&self) -> Self {
+                Self
+            }
+
+            fn synthesize(
+                &self,
+                (a, b): Self::Config,
+                mut layouter: impl Layouter<Fp>,
+            ) -> Result<(), Error> {
+                let mut assign_rows = |name: &'static str, column, rows| {
+                    layouter.assign_region(
+                        || name,
+                        |mut region| {
+                            for offset in 0..rows {
+                                region.assign_advice(
+                                    || "value",
+                                    column,
+                                    offset,
+                                    || Value::known(Fp::ONE),
+                                )?;
+                            }
+                            Ok(())
+                        },
+                    )
+                };
+                assign_rows("short", a, 2)?;
+                // 2^K rows are not enough for this region, once blinding rows are reserved.
+                assign_rows("tall", b, 1 << K)
+            }
+        }
+
+        // V1 fails before assigning any cell of the region, instead of failing on the
+        // first cell that falls in the blinding rows.
+        let err = MockProver::run(K, &TallCircuit, vec![]).unwrap_err();
+        assert!(matches!(
+            &err,
+            Error::RegionOutOfRows { region, rows, usable_rows }
+                if region == "tall" && *rows == (0..1 << K) && usable_rows.end < 1 << K
+        ));
+        assert!(err
+            .to_string()
+            .starts_with("Region \"tall\" is planned at rows 0..16"));
+    }
+
+    #[test]
+    fn verbose_report_pinpoints_summand() {
+        const K: u32 = 4;
+
+        #[derive(Clone)]
+        struct BoolConfig {
+            a: Column<Advice>,
+            b: Column<Advice>,
+            s: Selector,
+        }
+
+        struct BoolCircuit;
+
+        impl Circuit<Fp> for BoolCircuit {
+            type Config = BoolConfig;
+            type FloorPlanner = SimpleFloorPlanner;
+            #[cfg(feature = "circuit-params")]
+            type Params = ();
+
+            fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+                let a = meta.advice_column();
+                let b = meta.advice_column();
+                let s = meta.selector();
+
+                // Both `a` and `b` are boolean, checked by a single constraint.
+                meta.create_gate("booleans", |meta| {
+                    let one = Expression::Constant(Fp::ONE);
+                    let a = meta.query_advice(a, Rotation::cur());
+                    let b = meta.query_advice(b, Rotation::cur());
+                    let s = meta.query_selector(s);
+                    vec![s * ((one.clone() - a.clone()) * a + (one - b.clone()) * b)]
+                });
+
+                BoolConfig { a, b, s }
+            }
+
+            fn without_witnesses(&self) -> Self {
+                Self
+            }
+
+            fn synthesize(
+                &self,
+                config: Self::Config,
+                mut layouter: impl Layouter<Fp>,
+            ) -> Result<(), Error> {
+                layouter.assign_region(
+                    || "booleans",
+                    |mut region| {
+                        config.s.enable(&mut region, 0)?;
+                        region.assign_advice(|| "a", config.a, 0, || Value::known(Fp::ONE))?;
+                        // The injected bad term: `b` is not boolean.
+                        region.assign_advice(|| "b", config.b, 0, || Value::known(Fp::from(2)))?;
+                        Ok(())
+                    },
+                )
+            }
+        }
+
+        let prover = MockProver::run(K, &BoolCircuit, vec![]).unwrap();
+        let failures = prover.verify().unwrap_err();
+        assert_eq!(failures.len(), 1);
+
+        let report = failures[0].verbose_report(&prover);
+        // The default output is unchanged.
+        assert!(report.starts_with(&failures[0].to_string()));
+
+        let summands: Vec<_> = report
+            .lines()
+            .skip_while(|line| !line.starts_with("Summands at row 0:"))
+            .skip(1)
+            .collect();
+        assert_eq!(summands.len(), 2);
+        assert!(summands[0].ends_with(" = 0"), "{report}");
+        assert!(
+            summands[1].ends_with(&format!(" = {}", super::util::format_value(-Fp::from(2)))),
+            "{report}"
+        );
+    }
+
+    #[test]
+    fn run_with_challenges() {
+        const K: u32 = 4;
+
+        #[derive(Clone)]
+        struct RlcConfig {
+            a: Column<Advice>,
+            rlc: Column<Advice>,
+            c: Challenge,
+            s: Selector,
+        }
+
+        struct RlcCircuit;
+
+        impl Circuit<Fp> for RlcCircuit {
+            type Config = RlcConfig;
+            type FloorPlanner = SimpleFloorPlanner;
+            #[cfg(feature = "circuit-params")]
+            type Params = ();
+
+            fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+                let a = meta.advice_column();
+                let rlc = meta.advice_column_in(SecondPhase);
+                let c = meta.challenge_usable_after(FirstPhase);
+                let s = meta.selector();
+
+                meta.create_gate("rlc", |cells| {
+                    let a = cells.query_advice(a, Rotation::cur());
+                    let rlc = cells.query_advice(rlc, Rotation::cur());
+                    let c = cells.query_challenge(c);
+                    let s = cells.query_selector(s);
+
+                    vec![s * (rlc - a * c)]
+                });
+
+                RlcConfig { a, rlc, c, s }
+            }
+
+            fn without_witnesses(&self) -> Self {
+                Self
+            }
+
+            fn synthesize(
+                &self,
+                config: Self::Config,
+                mut layouter: impl Layouter<Fp>,
+            ) -> Result<(), Error> {
+                let c = layouter.get_challenge(config.c);
+
+                layouter.assign_region(
+                    || "rlc",
+                    |mut region| {
+                        config.s.enable(&mut region, 0)?;
+                        let a = Value::known(Fp::from(3));
+                        region.assign_advice(|| "a", config.a, 0, || a)?;
+                        // A bug that only shows up for a specific challenge value.
+                        let rlc = c.map(|c| {
+                            if c == Fp::from(42) {
+                                Fp::ZERO
+                            } else {
+                                Fp::from(3) * c
+                            }
+                        });
+                        region.assign_advice(|| "rlc", config.rlc, 0, || rlc)
+                    },
+                )?;
+                Ok(())
+            }
+        }
+
+        // The internally derived challenge does not trigger the bug, and can be captured
+        // to reproduce the run.
+        let prover = MockProver::run(K, &RlcCircuit, vec![]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+        let challenges = prover.challenges().to_vec();
+        assert_eq!(challenges.len(), 1);
+        let prover =
+            MockProver::run_with_challenges(K, &RlcCircuit, vec![], challenges.clone()).unwrap();
+        assert_eq!(prover.challenges(), &challenges[..]);
+        assert_eq!(prover.verify(), Ok(()));
+
+        // The challenge sampled by a failing real prover reproduces the failure.
+        let prover =
+            MockProver::run_with_challenges(K, &RlcCircuit, vec![], vec![Fp::from(42)]).unwrap();
+        assert!(matches!(
+            prover.verify().unwrap_err()[..],
+            [VerifyFailure::ConstraintNotSatisfied { .. }]
+        ));
+
+        // The number of challenges must match the circuit.
+        assert!(matches!(
+            MockProver::run_with_challenges(K, &RlcCircuit, vec![], vec![]),
+            Err(Error::Other(_))
+        ));
+    }
+
+    #[test]
+    fn verify_subsets_match_full_verify() {
+        use halo2_common::circuit::{AssignedCell, Region};
+
+        const K: u32 = 5;
+
+        #[derive(Clone)]
+        struct ArithConfig {
+            a: Column<Advice>,
+            b: Column<Advice>,
+            c: Column<Advice>,
+            s_mul: Selector,
+            s_sub: Selector,
+            s_range: Selector,
+            t: TableColumn,
+        }
+
+        struct ArithCircuit;
+
+        impl Circuit<Fp> for ArithCircuit {
+            type Config = ArithConfig;
+            type FloorPlanner = SimpleFloorPlanner;
+            #[cfg(feature = "circuit-params")]
+            type Params = ();
+
+            fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+                let a = meta.advice_column();
+                let b = meta.advice_column();
+                let c = meta.advice_column();
+                let s_mul = meta.selector();
+                let s_sub = meta.selector();
+                let s_range = meta.complex_selector();
+                let t = meta.lookup_table_column();
+                meta.enable_equality(a);
+                meta.enable_equality(b);
+
+                meta.create_gate("mul", |meta| {
+                    let a = meta.query_advice(a, Rotation::cur());
+                    let b = meta.query_advice(b, Rotation::cur());
+                    let c = meta.query_advice(c, Rotation::cur());
+                    let s = meta.query_selector(s_mul);
+                    vec![s * (a * b - c)]
+                });
+                meta.create_gate("sub", |meta| {
+                    let a = meta.query_advice(a, Rotation::cur());
+                    let b = meta.query_advice(b, Rotation::cur());
+                    let c = meta.query_advice(c, Rotation::cur());
+                    let s = meta.query_selector(s_sub);
+                    vec![s * (a - b - c)]
+                });
+                meta.lookup("range", |meta| {
+                    let a = meta.query_advice(a, Rotation::cur());
+                    let s = meta.query_selector(s_range);
+                    vec![(s * a, t)]
+                });
+
+                ArithConfig {
+                    a,
+                    b,
+                    c,
+                    s_mul,
+                    s_sub,
+                    s_range,
+                    t,
+                }
+            }
+
+            fn without_witnesses(&self) -> Self {
+                Self
+            }
+
+            fn synthesize(
+                &self,
+                config: Self::Config,
+                mut layouter: impl Layouter<Fp>,
+            ) -> Result<(), Error> {
+                layouter.assign_table(
+                    || "range",
+                    |mut table| {
+                        for value in 0..4 {
+                            table.assign_cell(
+                                || "value",
+                                config.t,
+                                value,
+                                || Value::known(Fp::from(value as u64)),
+                            )?;
+                        }
+                        Ok(())
+                    },
+                )?;
+
+                fn assign_row(
+                    region: &mut Region<'_, Fp>,
+                    config: &ArithConfig,
+                    values: [u64; 3],
+                ) -> Result<Vec<AssignedCell<Fp, Fp>>, Error> {
+                    [config.a, config.b, config.c]
+                        .into_iter()
+                        .zip(values)
+                        .map(|(column, value)| {
+                            region.assign_advice(
+                                || "value",
+                                column,
+                                0,
+                                || Value::known(Fp::from(value)),
+                            )
+                        })
+                        .collect()
+                }
+
+                // BUG: 2 * 3 != 7.
+                let mul = layouter.assign_region(
+                    || "mul",
+                    |mut region| {
+                        config.s_mul.enable(&mut region, 0)?;
+                        assign_row(&mut region, &config, [2, 3, 7])
+                    },
+                )?;
+                // BUG: 5 - 1 != 1.
+                layouter.assign_region(
+                    || "sub",
+                    |mut region| {
+                        config.s_sub.enable(&mut region, 0)?;
+                        assign_row(&mut region, &config, [5, 1, 1])
+                    },
+                )?;
+                // BUG: 9 is out of range.
+                layouter.assign_region(
+                    || "range",
+                    |mut region| {
+                        config.s_range.enable(&mut region, 0)?;
+                        assign_row(&mut region, &config, [9, 0, 0])
+                    },
+                )?;
+                // BUG: the copy of 2 is 3.
+                layouter.assign_region(
+                    || "copy",
+                    |mut region| {
+                        let cells = assign_row(&mut region, &config, [0, 3, 0])?;
+                        region.constrain_equal(mul[0].cell(), cells[1].cell())
+                    },
+                )
+            }
+        }
+
+        let prover = MockProver::run(K, &ArithCircuit, vec![]).unwrap();
+        let failures = prover.verify().unwrap_err();
+        assert_eq!(failures.len(), 4);
+
+        // Each subset returns exactly the failures of the full run for its constraints.
+        let assert_subset = |subset: Result<(), Vec<VerifyFailure>>,
+                             keep: &dyn Fn(&VerifyFailure) -> bool| {
+            let subset = subset.unwrap_err();
+            let expected: Vec<_> = failures.iter().filter(|f| keep(f)).collect();
+            assert_eq!(expected.len(), 1);
+            assert_eq!(subset.iter().collect::<Vec<_>>(), expected);
+        };
+        let is_gate = |name: &'static str| {
+            move |failure: &VerifyFailure| {
+                matches!(failure, VerifyFailure::ConstraintNotSatisfied { constraint, .. }
+                    if constraint.gate.name == name)
+            }
+        };
+
+        assert_subset(prover.verify_gates(|name| name == "mul"), &is_gate("mul"));
+        assert_subset(prover.verify_gate_indices(&[1]), &is_gate("sub"));
+        assert_subset(prover.verify_lookups(|name| name == "range"), &|f| {
+            matches!(f, VerifyFailure::Lookup { .. })
+        });
+        assert_subset(prover.verify_permutation_only(), &|f| {
+            matches!(f, VerifyFailure::Permutation { .. })
+        });
+
+        // Filters that select nothing check nothing.
+        assert_eq!(prover.verify_gates(|_| false), Ok(()));
+        assert_eq!(prover.verify_gate_indices(&[]), Ok(()));
+        assert_eq!(prover.verify_lookups(|name| name == "bits"), Ok(()));
+        // Selecting all the gates returns the gate failures in the order of the full run.
+        let gates = prover.verify_gate_indices(&[0, 1]).unwrap_err();
+        assert_eq!(
+            gates.iter().collect::<Vec<_>>(),
+            failures.iter().take(2).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn verify_at_rows_with_lookups_reports_rows() {
+        const K: u32 = 5;
+
+        #[derive(Clone)]
+        struct DoubleConfig {
+            a: Column<Advice>,
+            b: Column<Advice>,
+            c: Column<Advice>,
+            s: Selector,
+            s_range: Selector,
+            t: TableColumn,
+        }
+
+        struct DoubleCircuit;
+
+        impl Circuit<Fp> for DoubleCircuit {
+            type Config = DoubleConfig;
+            type FloorPlanner = SimpleFloorPlanner;
+            #[cfg(feature = "circuit-params")]
+            type Params = ();
+
+            fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+                let a = meta.advice_column();
+                let b = meta.advice_column();
+                let c = meta.advice_column();
+                let s = meta.selector();
+                let s_range = meta.complex_selector();
+                let t = meta.lookup_table_column();
+                meta.enable_equality(a);
+
+                meta.create_gate("double", |meta| {
+                    let a = meta.query_advice(a, Rotation::cur());
+                    let b = meta.query_advice(b, Rotation::cur());
+                    let s = meta.query_selector(s);
+                    vec![s * (a * Fp::from(2) - b)]
+                });
+                meta.lookup("range", |meta| {
+                    let c = meta.query_advice(c, Rotation::cur());
+                    let s = meta.query_selector(s_range);
+                    vec![(s * c, t)]
+                });
+
+                DoubleConfig {
+                    a,
+                    b,
+                    c,
+                    s,
+                    s_range,
+                    t,
+                }
+            }
+
+            fn without_witnesses(&self) -> Self {
+                Self
+            }
+
+            fn synthesize(
+                &self,
+                config: Self::Config,
+                mut layouter: impl Layouter<Fp>,
+            ) -> Result<(), Error> {
+                layouter.assign_table(
+                    || "range",
+                    |mut table| {
+                        for value in 0..4 {
+                            table.assign_cell(
+                                || "value",
+                                config.t,
+                                value,
+                                // The table is stored in reverse order.
+                                || Value::known(Fp::from(3 - value as u64)),
+                            )?;
+                        }
+                        Ok(())
+                    },
+                )?;
+
+                layouter.assign_region(
+                    || "values",
+                    |mut region| {
+                        let mut a_cells = vec![];
+                        for row in 0..8 {
+                            config.s.enable(&mut region, row)?;
+                            config.s_range.enable(&mut region, row)?;
+                            let a = row as u64;
+                            // BUG: rows 1 and 6 are not doubled.
+                            let b = if row == 1 || row == 6 {
+                                2 * a + 1
+                            } else {
+                                2 * a
+                            };
+                            // BUG: rows 2 and 5 are out of range.
+                            let c = if row == 2 || row == 5 { 7 } else { a % 4 };
+                            for (column, value) in [(config.b, b), (config.c, c)] {
+                                region.assign_advice(
+                                    || "value",
+                                    column,
+                                    row,
+                                    || Value::known(Fp::from(value)),
+                                )?;
+                            }
+                            a_cells.push(region.assign_advice(
+                                || "a",
+                                config.a,
+                                row,
+                                || Value::known(Fp::from(a)),
+                            )?);
+                        }
+                        // BUG: rows 3 and 7 are copied to cells with other values.
+                        region.constrain_equal(a_cells[3].cell(), a_cells[4].cell())?;
+                        region.constrain_equal(a_cells[7].cell(), a_cells[0].cell())
+                    },
+                )
+            }
+        }
+
+        let row = |failure: &VerifyFailure| match failure {
+            VerifyFailure::ConstraintNotSatisfied { location, .. }
+            | VerifyFailure::Lookup { location, .. }
+            | VerifyFailure::Permutation { location, .. } => match location {
+                FailureLocation::InRegion { offset, .. } => *offset,
+                FailureLocation::OutsideRegion { row } => *row,
+            },
+            _ => panic!("unexpected failure {failure:?}"),
+        };
+
+        let prover = MockProver::run(K, &DoubleCircuit, vec![]).unwrap();
+        let failures = prover.verify().unwrap_err();
+        let mut rows: Vec<_> = failures.iter().map(row).collect();
+        rows.sort_unstable();
+        assert_eq!(rows, vec![1, 2, 3, 5, 6, 7]);
+
+        // Only the failures within the rows are returned, as in the full run.
+        let restricted = prover.verify_at_rows_with_lookups(0..4).unwrap_err();
+        assert_eq!(
+            restricted.iter().collect::<Vec<_>>(),
+            failures
+                .iter()
+                .filter(|failure| row(failure) < 4)
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(restricted.len(), 3);
+
+        // The lookup table is built from all the usable rows, even those outside the range.
+        // Here, the input at row 1 is in the table at row 2.
+        let restricted = prover.verify_at_rows_with_lookups(1..2).unwrap_err();
+        assert_eq!(restricted.iter().map(row).collect::<Vec<_>>(), vec![1]);
+        assert_eq!(prover.verify_at_rows_with_lookups(8..16), Ok(()));
+
+        // The rows compose with the gate filter.
+        let restricted = prover
+            .verify_gates_at_rows_with_lookups(4..8, |name| name != "double")
+            .unwrap_err();
+        let mut rows: Vec<_> = restricted.iter().map(row).collect();
+        rows.sort_unstable();
+        assert_eq!(rows, vec![5, 7]);
+    }
+
+    #[test]
+    fn parallel_verify_matches_serial() {
+        use halo2_common::multicore::ThreadPoolBuilder;
+
+        const K: u32 = 6;
+        const ROWS: usize = 24;
+
+        #[derive(Clone)]
+        struct HeavyConfig {
+            a: Column<Advice>,
+            b: Column<Advice>,
+            c: Column<Advice>,
+            s: Selector,
+            s_lookup: Selector,
+            t: TableColumn,
+        }
+
+        struct HeavyCircuit;
+
+        impl Circuit<Fp> for HeavyCircuit {
+            type Config = HeavyConfig;
+            type FloorPlanner = SimpleFloorPlanner;
+            #[cfg(feature = "circuit-params")]
+            type Params = ();
+
+            fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+                let a = meta.advice_column();
+                let b = meta.advice_column();
+                let c = meta.advice_column();
+                let s = meta.selector();
+                let s_lookup = meta.complex_selector();
+                let t = meta.lookup_table_column();
+
+                meta.create_gate("double", |meta| {
+                    let a = meta.query_advice(a, Rotation::cur());
+                    let b = meta.query_advice(b, Rotation::cur());
+                    let s = meta.query_selector(s);
+                    vec![s * (a * Fp::from(2) - b)]
+                });
+                meta.create_gate("square", |meta| {
+                    let a = meta.query_advice(a, Rotation::cur());
+                    let b = meta.query_advice(b, Rotation::cur());
+                    let s = meta.query_selector(s);
+                    vec![s * (a.clone() * a - b)]
+                });
+                meta.lookup("small", |meta| {
+                    let a = meta.query_advice(a, Rotation::cur());
+                    let s = meta.query_selector(s_lookup);
+                    vec![(s * a, t)]
+                });
+                meta.lookup("small b", |meta| {
+                    let b = meta.query_advice(b, Rotation::cur());
+                    let s = meta.query_selector(s_lookup);
+                    vec![(s * b, t)]
+                });
+                meta.shuffle("permuted", |meta| {
+                    let s = meta.query_selector(s_lookup);
+                    let a = meta.query_advice(a, Rotation::cur());
+                    let c = meta.query_advice(c, Rotation::cur());
+                    vec![(s.clone() * a, s * c)]
+                });
+
+                HeavyConfig {
+                    a,
+                    b,
+                    c,
+                    s,
+                    s_lookup,
+                    t,
+                }
+            }
+
+            fn without_witnesses(&self) -> Self {
+                Self
+            }
+
+            fn synthesize(
+                &self,
+                config: Self::Config,
+                mut layouter: impl Layouter<Fp>,
+            ) -> Result<(), Error> {
+                layouter.assign_table(
+                    || "small",
+                    |mut table| {
+                        for value in 0..16 {
+                            table.assign_cell(
+                                || "value",
+                                config.t,
+                                value,
+                                || Value::known(Fp::from(value as u64)),
+                            )?;
+                        }
+                        Ok(())
+                    },
+                )?;
+
+                layouter.assign_region(
+                    || "values",
+                    |mut region| {
+                        for row in 0..ROWS {
+                            config.s.enable(&mut region, row)?;
+                            config.s_lookup.enable(&mut region, row)?;
+                            let a = row as u64;
+                            // Every row fails one of the gates, except rows 0 and 2 which
+                            // satisfy both.
+                            let b = if row % 3 == 0 { a * a } else { 2 * a };
+                            // The values of `c` are those of `a` in reverse order, except
+                            // for one.
+                            let c = if row == 5 {
+                                100
+                            } else {
+                                (ROWS - 1 - row) as u64
+                            };
+                            for (column, value) in [(config.a, a), (config.b, b), (config.c, c)] {
+                                region.assign_advice(
+                                    || "value",
+                                    column,
+                                    row,
+                                    || Value::known(Fp::from(value)),
+                                )?;
+                            }
+                        }
+                        Ok(())
+                    },
+                )
+            }
+        }
+
+        let prover = MockProver::run(K, &HeavyCircuit, vec![]).unwrap();
+        let parallel = prover.verify().unwrap_err();
+        let serial = ThreadPoolBuilder::new()
+            .num_threads(1)
+            .build()
+            .unwrap()
+            .install(|| prover.verify())
+            .unwrap_err();
+        assert_eq!(parallel, serial);
+
+        // Within each kind, the failures are ordered by row, then constraint.
+        let keys = |kind: &dyn Fn(&VerifyFailure) -> Option<usize>| {
+            parallel
+                .iter()
+                .filter_map(|failure| {
+                    let index = kind(failure)?;
+                    let row = match failure {
+                        VerifyFailure::ConstraintNotSatisfied { location, .. }
+                        | VerifyFailure::Lookup { location, .. }
+                        | VerifyFailure::Shuffle { location, .. } => match location {
+                            FailureLocation::InRegion { offset, .. } => *offset,
+                            FailureLocation::OutsideRegion { row } => *row,
+                        },
+                        _ => unreachable!(),
+                    };
+                    Some((row, index))
+                })
+                .collect::<Vec<_>>()
+        };
+        let gates = keys(&|failure| match failure {
+            VerifyFailure::ConstraintNotSatisfied { constraint, .. } => Some(constraint.gate.index),
+            _ => None,
+        });
+        let lookups = keys(&|failure| match failure {
+            VerifyFailure::Lookup { lookup_index, .. } => Some(*lookup_index),
+            _ => None,
+        });
+        let shuffles = keys(&|failure| match failure {
+            VerifyFailure::Shuffle { shuffle_index, .. } => Some(*shuffle_index),
+            _ => None,
+        });
+        for keys in [&gates, &lookups, &shuffles] {
+            assert!(!keys.is_empty());
+            assert!(keys.windows(2).all(|w| w[0] < w[1]));
+        }
+        // Rows 0 and 2 satisfy both gates, and the other rows fail exactly one.
+        assert_eq!(gates.len(), ROWS - 2);
+        // `a` is out of the table from row 16, and `b` at row 6 and from row 8.
+        assert_eq!(lookups.len(), (ROWS - 16) + 1 + (ROWS - 8));
+    }
+
+    #[test]
+    fn lookup_report_nearest_table_rows() {
+        const K: u32 = 5;
+
+        #[derive(Clone)]
+        struct SquareConfig {
+            a: Column<Advice>,
+            b: Column<Advice>,
+            q: Selector,
+            table: (TableColumn, TableColumn),
+        }
+
+        struct SquareCircuit;
+
+        impl Circuit<Fp> for SquareCircuit {
+            type Config = SquareConfig;
+            type FloorPlanner = SimpleFloorPlanner;
+            #[cfg(feature = "circuit-params")]
+            type Params = ();
+
+            fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+                let a = meta.advice_column();
+                let b = meta.advice_column();
+                let q = meta.complex_selector();
+                let table = (meta.lookup_table_column(), meta.lookup_table_column());
+
+                meta.lookup("square", |cells| {
+                    let a = cells.query_advice(a, Rotation::cur());
+                    let b = cells.query_advice(b, Rotation::cur());
+                    let q = cells.query_selector(q);
+                    vec![(q.clone() * a, table.0), (q * b, table.1)]
+                });
+
+                SquareConfig { a, b, q, table }
+            }
+
+            fn without_witnesses(&self) -> Self {
+                Self
+            }
+
+            fn synthesize(
+                &self,
+                config: Self::Config,
+                mut layouter: impl Layouter<Fp>,
+            ) -> Result<(), Error> {
+                layouter.assign_table(
+                    || "squares",
+                    |mut table| {
+                        for i in 0..8 {
+                            table.assign_cell(
+                                || "x",
+                                config.table.0,
+                                i,
+                                || Value::known(Fp::from(i as u64)),
+                            )?;
+                            table.assign_cell(
+                                || "x^2",
+                                config.table.1,
+                                i,
+                                || Value::known(Fp::from((i * i) as u64)),
+                            )?;
+                        }
+                        Ok(())
+                    },
+                )?;
+
+                layouter.assign_region(
+                    || "out of range",
+                    |mut region| {
+                        config.q.enable(&mut region, 0)?;
+                        region.assign_advice(|| "a", config.a, 0, || Value::known(Fp::from(10)))?;
+                        region.assign_advice(
+                            || "b",
+                            config.b,
+                            0,
+                            || Value::known(Fp::from(100)),
+                        )?;
+                        Ok(())
+                    },
+                )
+            }
+        }
+
+        let prover = MockProver::run(K, &SquareCircuit, vec![]).unwrap();
+        let failures = prover.verify().unwrap_err();
+        assert_eq!(failures.len(), 1);
+
+        // The table rows are ordered by the distance of their first component to 10.
+        assert_eq!(
+            failures[0].lookup_report(&prover, 3).unwrap(),
+            "  Lookup 'square' input at row 0:\n    \
+             (0xa, 0x64)\n  \
+             Gated by:\n    \
+             Column('Fixed', 2 - )@0 = 1\n  \
+             Table has 8 distinct rows, nearest:\n    \
+             (0x7, 0x31)\n    \
+             (0x6, 0x24)\n    \
+             (0x5, 0x19)\n"
+        );
+        assert_eq!(
+            failures[0]
+                .lookup_report(&prover, 1)
+                .unwrap()
+                .lines()
+                .last(),
+            Some("    (0x7, 0x31)")
+        );
+    }
+
+    #[test]
+    fn verify_to_json_snapshot() {
+        const K: u32 = 4;
+
+        #[derive(Clone)]
+        struct OneConfig {
+            a: Column<Advice>,
+            s: Selector,
+        }
+
+        struct OneCircuit;
+
+        impl Circuit<Fp> for OneCircuit {
+            type Config = OneConfig;
+            type FloorPlanner = SimpleFloorPlanner;
+            #[cfg(feature = "circuit-params")]
+            type Params = ();
+
+            fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+                let a = meta.advice_column();
+                let s = meta.selector();
+
+                meta.create_gate("one", |meta| {
+                    let a = meta.query_advice(a, Rotation::cur());
+                    let s = meta.query_selector(s);
+                    vec![("a = 1", s * (a - Expression::Constant(Fp::ONE)))]
+                });
+
+                OneConfig { a, s }
+            }
+
+            fn without_witnesses(&self) -> Self {
+                Self
+            }
+
+            fn synthesize(
+                &self,
+                config: Self::Config,
+                mut layouter: impl Layouter<Fp>,
+            ) -> Result<(), Error> {
+                layouter.assign_region(
+                    || "first",
+                    |mut region| {
+                        config.s.enable(&mut region, 0)?;
+                        region.assign_advice(|| "a", config.a, 0, || Value::known(Fp::ONE))?;
+                        Ok(())
+                    },
+                )?;
+                layouter.assign_region(
+                    || "second",
+                    |mut region| {
+                        region.name_column(|| "a", config.a);
+                        config.s.enable(&mut region, 0)?;
+                        region.assign_advice(|| "a", config.a, 0, || Value::known(Fp::from(2)))?;
+                        Ok(())
+                    },
+                )
+            }
+        }
+
+        let prover = MockProver::run(K, &OneCircuit, vec![]).unwrap();
+        let failures = prover.verify().unwrap_err();
+        assert_eq!(failures.len(), 1);
+
+        // Changes to this schema break the tools that consume it.
+        assert_eq!(
+            prover.verify_to_json(),
+            r#"[{"kind":"constraint_not_satisfied","message":"Constraint 0 ('a = 1') in gate 0 ('one') is not satisfied in Region 1 ('second') at offset 0\n- Column('Advice', 0 - a)@0 = 0x2","gate":{"index":0,"name":"one"},"constraint":{"index":0,"name":"a = 1"},"lookup":null,"shuffle":null,"region":{"index":1,"name":"second"},"gate_offset":null,"offset":0,"row":1,"column":null,"cells":[{"column":{"type":"advice","index":0,"annotation":"a"},"rotation":0,"name":"","value":"0x2"}]}]"#
+        );
+
+        // Without the prover, the absolute row of a failure in a region is unknown.
+        let json: serde_json::Value = serde_json::from_str(&failures[0].to_json()).unwrap();
+        assert_eq!(json["kind"], "constraint_not_satisfied");
+        assert_eq!(json["row"], serde_json::Value::Null);
+        assert_eq!(json["offset"], 0);
+    }
 }
@@ -0,0 +1,402 @@
+//! Developer tools for attributing the cost of proving a circuit to its regions.
+
+use std::fmt;
+
+use halo2_common::plonk::{circuit::Column, ConstraintSystem, Expression};
+use halo2_middleware::circuit::Any;
+use halo2_middleware::ff::Field;
+
+use super::render::{LayoutCell, LayoutColumn, LayoutJson, RegionPlacement};
+
+/// The weight of an assigned advice cell in [`RegionCost::score`].
+const ADVICE_CELL_WEIGHT: f64 = 1.0;
+/// The weight of an assigned fixed cell in [`RegionCost::score`].
+const FIXED_CELL_WEIGHT: f64 = 0.25;
+/// The weight of an enabled selector in [`RegionCost::score`].
+const SELECTOR_WEIGHT: f64 = 0.25;
+/// The weight of an equality constraint in [`RegionCost::score`].
+const COPY_WEIGHT: f64 = 2.0;
+/// The weight of a row on which a lookup is active in [`RegionCost::score`].
+const LOOKUP_ROW_WEIGHT: f64 = 4.0;
+
+/// The cells of a column assigned by a region.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ColumnShare {
+    /// The column.
+    pub column: LayoutColumn,
+    /// The number of cells of the column assigned by the region.
+    pub cells: usize,
+    /// The share of the usable rows of the column assigned by the region, between 0
+    /// and 1.
+    pub share: f64,
+}
+
+/// The estimated contribution of a region to the cost of proving a circuit.
+///
+/// This is returned by [`attribute_costs`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct RegionCost {
+    /// The index of the region in [`LayoutJson::regions`].
+    pub index: usize,
+    /// The name of the region.
+    pub name: String,
+    /// The number of rows that the region takes up.
+    pub rows: usize,
+    /// The number of assigned advice cells.
+    pub advice_cells: usize,
+    /// The number of assigned fixed cells.
+    pub fixed_cells: usize,
+    /// The number of enabled selectors.
+    pub selectors: usize,
+    /// The cells assigned in each advice, fixed and instance column, ordered by column.
+    pub columns: Vec<ColumnShare>,
+    /// The number of equality constraints with at least one cell in the region.
+    pub copies: usize,
+    /// The number of lookups whose inputs are active in the region.
+    pub lookups: usize,
+    /// The number of rows on which lookups are active in the region, summed over the
+    /// lookups.
+    pub lookup_rows: usize,
+    /// The weighted cost score of the region.
+    pub score: f64,
+}
+
+/// Formats a list of [`RegionCost`]s as a table, with one region per line.
+pub struct RegionCostTable<'a>(pub &'a [RegionCost]);
+
+impl fmt::Display for RegionCostTable<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{:<24} {:>6} {:>7} {:>7} {:>9} {:>7} {:>7} {:>10}",
+            "region", "rows", "advice", "fixed", "selectors", "copies", "lookups", "score"
+        )?;
+        for cost in self.0 {
+            writeln!(
+                f,
+                "{:<24} {:>6} {:>7} {:>7} {:>9} {:>7} {:>7} {:>10.2}",
+                format!("{} ({})", cost.name, cost.index),
+                cost.rows,
+                cost.advice_cells,
+                cost.fixed_cells,
+                cost.selectors,
+                cost.copies,
+                cost.lookups,
+                cost.score
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Collects the selectors and fixed columns queried by `expression`, which gate the
+/// rows on which it is non-zero.
+fn collect_gating<F: Field>(
+    expression: &Expression<F>,
+    selectors: &mut Vec<usize>,
+    fixed: &mut Vec<usize>,
+) {
+    match expression {
+        Expression::Selector(selector) => selectors.push(selector.index()),
+        Expression::Fixed(query) => fixed.push(query.column_index),
+        Expression::Negated(a) | Expression::Scaled(a, _) => collect_gating(a, selectors, fixed),
+        Expression::Sum(a, b) | Expression::Product(a, b) => {
+            collect_gating(a, selectors, fixed);
+            collect_gating(b, selectors, fixed);
+        }
+        Expression::Constant(_)
+        | Expression::Advice(_)
+        | Expression::Instance(_)
+        | Expression::Challenge(_) => (),
+    }
+}
+
+/// Estimates the contribution of each region of `floor_plan` to the cost of proving the
+/// circuit, and returns them sorted by descending score.
+///
+/// `cs` must be the constraint system returned by `Circuit::configure`, before selectors
+/// are compressed, and `floor_plan` the layout returned by [`render_layout`] for the
+/// same circuit.
+///
+/// The estimate is meant for ranking regions (and so the chips that assign them) for
+/// optimization effort, not for predicting proving time. For each region, it counts:
+/// - the advice, fixed and instance cells it assigns, and their share of the usable rows
+///   of each column,
+/// - the selectors it enables,
+/// - the equality constraints with at least one cell in the region (a constraint
+///   between two regions counts for both), and
+/// - the lookups whose inputs are active in the region. A lookup is active on the rows
+///   of the region on which it enables a selector or assigns a fixed column that the
+///   lookup inputs query, or on all the rows of the region if the inputs query no
+///   selector or fixed column.
+///
+/// The score is the weighted sum `1 * advice cells + 0.25 * fixed cells + 0.25 *
+/// selectors + 2 * copies + 4 * lookup rows`. Advice cells dominate as the prover commits
+/// to them, copies and lookups add to the permutation and lookup arguments, while fixed
+/// cells and selectors only add to the evaluation of the constraints. Lookup tables are
+/// not regions, so they are not attributed. Regions with equal scores are ordered by
+/// index, so the result is deterministic.
+///
+/// [`render_layout`]: crate::dev::render_layout
+pub fn attribute_costs<F: Field>(
+    cs: &ConstraintSystem<F>,
+    floor_plan: &LayoutJson,
+) -> Vec<RegionCost> {
+    let lane = |column: LayoutColumn| floor_plan.columns.iter().position(|c| *c == column);
+    let lookup_gating: Vec<Vec<usize>> = cs
+        .lookups
+        .iter()
+        .map(|lookup| {
+            let (mut selectors, mut fixed) = (vec![], vec![]);
+            for expression in &lookup.input_expressions {
+                collect_gating(expression, &mut selectors, &mut fixed);
+            }
+            selectors
+                .into_iter()
+                .map(LayoutColumn::Selector)
+                .chain(
+                    fixed
+                        .into_iter()
+                        .map(|index| LayoutColumn::Column(Column::new(index, Any::Fixed))),
+                )
+                .filter_map(lane)
+                .collect()
+        })
+        .collect();
+    let contains = |placement: &RegionPlacement, cell: &LayoutCell| {
+        placement
+            .columns
+            .iter()
+            .any(|extent| extent.column == cell.column && extent.rows.contains(&cell.row))
+    };
+
+    let mut costs: Vec<_> = floor_plan
+        .regions
+        .iter()
+        .enumerate()
+        .map(|(index, placement)| {
+            let (mut advice_cells, mut fixed_cells, mut selectors) = (0, 0, 0);
+            let mut columns = vec![];
+            for extent in &placement.columns {
+                let column = floor_plan.columns[extent.column];
+                match column {
+                    LayoutColumn::Column(c) => {
+                        match c.column_type() {
+                            Any::Advice(_) => advice_cells += extent.cells,
+                            Any::Fixed => fixed_cells += extent.cells,
+                            Any::Instance => (),
+                        }
+                        columns.push(ColumnShare {
+                            column,
+                            cells: extent.cells,
+                            share: extent.cells as f64 / floor_plan.usable_rows as f64,
+                        });
+                    }
+                    LayoutColumn::Selector(_) => selectors += extent.cells,
+                }
+            }
+
+            let copies = floor_plan
+                .copies
+                .iter()
+                .filter(|(from, to)| contains(placement, from) || contains(placement, to))
+                .count();
+
+            let active_rows: Vec<usize> = lookup_gating
+                .iter()
+                .map(|gating| {
+                    if gating.is_empty() {
+                        placement.rows
+                    } else {
+                        placement
+                            .columns
+                            .iter()
+                            .filter(|extent| gating.contains(&extent.column))
+                            .map(|extent| extent.cells)
+                            .max()
+                            .unwrap_or(0)
+                    }
+                })
+                .filter(|rows| *rows > 0)
+                .collect();
+            let lookups = active_rows.len();
+            let lookup_rows = active_rows.iter().sum();
+
+            let score = ADVICE_CELL_WEIGHT * advice_cells as f64
+                + FIXED_CELL_WEIGHT * fixed_cells as f64
+                + SELECTOR_WEIGHT * selectors as f64
+                + COPY_WEIGHT * copies as f64
+                + LOOKUP_ROW_WEIGHT * lookup_rows as f64;
+
+            RegionCost {
+                index,
+                name: placement.name.clone(),
+                rows: placement.rows,
+                advice_cells,
+                fixed_cells,
+                selectors,
+                columns,
+                copies,
+                lookups,
+                lookup_rows,
+                score,
+            }
+        })
+        .collect();
+
+    costs.sort_by(|a, b| b.score.total_cmp(&a.score).then(a.index.cmp(&b.index)));
+    costs
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_middleware::poly::Rotation;
+    use halo2curves::pasta::Fp;
+
+    use super::*;
+    use crate::circuit::{Layouter, SimpleFloorPlanner, Value};
+    use crate::dev::render_layout;
+    use halo2_common::plonk::{Circuit, Error, Selector, TableColumn};
+    use halo2_middleware::circuit::Advice;
+
+    #[derive(Clone)]
+    struct TwoChipConfig {
+        a: Column<Advice>,
+        b: Column<Advice>,
+        s_add: Selector,
+        s_range: Selector,
+        table: TableColumn,
+    }
+
+    struct TwoChipCircuit;
+
+    impl Circuit<Fp> for TwoChipCircuit {
+        type Config = TwoChipConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+        #[cfg(feature = "circuit-params")]
+        type Params = ();
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let a = meta.advice_column();
+            let b = meta.advice_column();
+            let s_add = meta.selector();
+            let s_range = meta.complex_selector();
+            let table = meta.lookup_table_column();
+            meta.enable_equality(a);
+            meta.enable_equality(b);
+
+            meta.create_gate("double", |meta| {
+                let a = meta.query_advice(a, Rotation::cur());
+                let b = meta.query_advice(b, Rotation::cur());
+                let s_add = meta.query_selector(s_add);
+                vec![s_add * (a.clone() + a - b)]
+            });
+
+            meta.lookup("range", |meta| {
+                let a = meta.query_advice(a, Rotation::cur());
+                let s_range = meta.query_selector(s_range);
+                vec![(s_range * a, table)]
+            });
+
+            TwoChipConfig {
+                a,
+                b,
+                s_add,
+                s_range,
+                table,
+            }
+        }
+
+        fn without_witnesses(&self) -> Self {
+            Self
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            layouter.assign_table(
+                || "range",
+                |mut table| {
+                    for i in 0..16 {
+                        table.assign_cell(
+                            || "value",
+                            config.table,
+                            i,
+                            || Value::known(Fp::from(i as u64)),
+                        )?;
+                    }
+                    Ok(())
+                },
+            )?;
+
+            // The light chip doubles a single value.
+            layouter.assign_region(
+                || "light",
+                |mut region| {
+                    config.s_add.enable(&mut region, 0)?;
+                    region.assign_advice(|| "a", config.a, 0, || Value::known(Fp::ONE))?;
+                    region.assign_advice(|| "b", config.b, 0, || Value::known(Fp::from(2)))?;
+                    Ok(())
+                },
+            )?;
+
+            // The heavy chip range checks 8 values, and copies each of them.
+            layouter.assign_region(
+                || "heavy",
+                |mut region| {
+                    for row in 0..8 {
+                        config.s_range.enable(&mut region, row)?;
+                        let value = Value::known(Fp::from(row as u64));
+                        let a = region.assign_advice(|| "a", config.a, row, || value)?;
+                        a.copy_advice(|| "b", &mut region, config.b, row)?;
+                    }
+                    Ok(())
+                },
+            )
+        }
+    }
+
+    #[test]
+    fn heavy_chip_ranks_first() {
+        const K: u32 = 6;
+
+        let mut cs = ConstraintSystem::default();
+        TwoChipCircuit::configure(&mut cs);
+        let floor_plan = render_layout(K, &TwoChipCircuit).unwrap();
+        let costs = attribute_costs(&cs, &floor_plan);
+
+        let names: Vec<_> = costs.iter().map(|cost| cost.name.as_str()).collect();
+        assert_eq!(names, vec!["heavy", "light"]);
+
+        let heavy = &costs[0];
+        assert_eq!(heavy.index, 1);
+        assert_eq!(heavy.rows, 8);
+        assert_eq!(heavy.advice_cells, 16);
+        assert_eq!(heavy.selectors, 8);
+        assert_eq!(heavy.copies, 8);
+        assert_eq!((heavy.lookups, heavy.lookup_rows), (1, 8));
+        assert_eq!(heavy.columns.len(), 2);
+        assert_eq!(heavy.columns[0].cells, 8);
+        assert_eq!(heavy.columns[0].share, 8.0 / floor_plan.usable_rows as f64);
+
+        let light = &costs[1];
+        assert_eq!(light.advice_cells, 2);
+        assert_eq!(light.selectors, 1);
+        assert_eq!(light.copies, 0);
+        assert_eq!(light.lookups, 0);
+        assert_eq!(light.score, 2.25);
+
+        // The analysis is deterministic.
+        assert_eq!(attribute_costs(&cs, &floor_plan), costs);
+
+        let table = RegionCostTable(&costs).to_string();
+        let lines: Vec<_> = table.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("region "));
+        assert!(lines[1].starts_with("heavy (1) "));
+        assert!(lines[2].starts_with("light (0) "));
+        assert!(lines[2].ends_with(" 2.25"));
+    }
+}
@@ -39,6 +39,8 @@ pub struct CircuitCost<G: PrimeGroup, ConcreteCircuit: Circuit<G::Scalar>> {
     lookups: usize,
     /// Number of columns in the global permutation.
     permutation_cols: usize,
+    /// Number of sets of columns that the global permutation is split into.
+    permutation_chunks: usize,
     /// Number of distinct sets of points in the multiopening argument.
     point_sets: usize,
     /// Maximum rows used over all columns
@@ -186,7 +188,7 @@ impl<F: Field> Assignment<F> for Layout {
         if let Some(cell) = self.selectors[selector.0].get_mut(row) {
             *cell = true;
         } else {
-            return Err(Error::not_enough_rows_available(self.k));
+            return Err(Error::not_enough_rows_available_at(self.k, row));
         }
 
         self.update((*selector).into(), row);
@@ -251,10 +253,24 @@ impl<F: Field> Assignment<F> for Layout {
         Ok(())
     }
 
+    fn fill_advice_from_row(
+        &mut self,
+        _: Column<Advice>,
+        _: usize,
+        _: Value<Assigned<F>>,
+    ) -> Result<(), Error> {
+        Ok(())
+    }
+
     fn get_challenge(&self, _: Challenge) -> Value<F> {
         Value::unknown()
     }
 
+    fn current_phase(&self) -> u8 {
+        // The circuit is synthesized once, so all phases must be included.
+        u8::MAX
+    }
+
     fn push_namespace<NR, N>(&mut self, _: N)
     where
         NR: Into<String>,
@@ -338,6 +354,7 @@ impl<G: PrimeGroup, ConcreteCircuit: Circuit<G::Scalar>> CircuitCost<G, Concrete
             fixed_queries: cs.fixed_queries.len(),
             lookups: cs.lookups.len(),
             permutation_cols,
+            permutation_chunks: cs.permutation_chunks().len(),
             point_sets: point_sets.len(),
             max_rows: layout.total_rows,
             max_advice_rows: layout.total_advice_rows,
@@ -352,9 +369,10 @@ impl<G: PrimeGroup, ConcreteCircuit: Circuit<G::Scalar>> CircuitCost<G, Concrete
         }
     }
 
-    fn permutation_chunks(&self) -> usize {
-        let chunk_size = self.max_deg - 2;
-        (self.permutation_cols + chunk_size - 1) / chunk_size
+    /// Returns the number of sets of columns that the global permutation is split into,
+    /// which is the number of permutation product commitments per instance.
+    pub fn permutation_chunks(&self) -> usize {
+        self.permutation_chunks
     }
 
     /// Returns the marginal proof size per instance of this circuit.
@@ -1,18 +1,18 @@
 //! The cost estimator takes high-level parameters for a circuit design, and estimates the
 //! verification cost, as well as resulting proof size.
 
-use std::collections::HashSet;
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::{iter, num::ParseIntError, str::FromStr};
 
-use halo2_common::plonk::circuit::Circuit;
-use halo2_middleware::ff::{Field, FromUniformBytes};
+use halo2_common::plonk::{circuit::Circuit, ConstraintSystem};
+use halo2_middleware::ff::{Field, FromUniformBytes, PrimeField};
 use serde::Deserialize;
 use serde_derive::Serialize;
 
 use super::MockProver;
 
 /// Supported commitment schemes
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Deserialize, Serialize)]
 pub enum CommitmentScheme {
     /// Inner Product Argument commitment scheme
     IPA,
@@ -321,3 +321,213 @@ pub fn from_circuit_to_cost_model_options<F: Ord + Field + FromUniformBytes<64>,
         k,
     }
 }
+
+/// The estimated cost of creating a proof for a constraint system, as returned by
+/// [`estimate`].
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct CostEstimate {
+    /// Power-of-2 bound on the number of rows in the circuit.
+    pub k: u32,
+    /// The commitment scheme of the proof.
+    pub scheme: CommitmentScheme,
+    /// Maximum degree of the constraint system.
+    pub degree: usize,
+    /// Number of advice column commitments, written in the proof.
+    pub advice_commitments: usize,
+    /// Number of fixed column commitments, held in the verifying key.
+    pub fixed_commitments: usize,
+    /// Number of instance column commitments, computed by the verifier.
+    pub instance_commitments: usize,
+    /// Number of lookup product polynomials (one per lookup argument).
+    pub lookup_products: usize,
+    /// Number of shuffle product polynomials (one per shuffle argument).
+    pub shuffle_products: usize,
+    /// Number of permutation product polynomials (one per chunk of columns).
+    pub permutation_products: usize,
+    /// Number of pieces of the quotient polynomial (`degree - 1`).
+    pub quotient_pieces: usize,
+    /// Total number of commitments in the proof.
+    pub proof_commitments: usize,
+    /// Total number of evaluations in the proof.
+    pub proof_evaluations: usize,
+    /// Estimated size of the proof, in bytes.
+    pub proof_bytes: usize,
+    /// Rough estimate of the peak memory used by the prover, in bytes.
+    pub prover_memory_bytes: usize,
+}
+
+impl std::fmt::Display for CostEstimate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "k: {}", self.k)?;
+        writeln!(f, "scheme: {:?}", self.scheme)?;
+        writeln!(f, "degree: {}", self.degree)?;
+        writeln!(f, "advice commitments: {}", self.advice_commitments)?;
+        writeln!(f, "fixed commitments: {}", self.fixed_commitments)?;
+        writeln!(f, "instance commitments: {}", self.instance_commitments)?;
+        writeln!(f, "lookup products: {}", self.lookup_products)?;
+        writeln!(f, "shuffle products: {}", self.shuffle_products)?;
+        writeln!(f, "permutation products: {}", self.permutation_products)?;
+        writeln!(f, "quotient pieces: {}", self.quotient_pieces)?;
+        writeln!(f, "proof commitments: {}", self.proof_commitments)?;
+        writeln!(f, "proof evaluations: {}", self.proof_evaluations)?;
+        writeln!(f, "proof bytes: {}", self.proof_bytes)?;
+        write!(f, "prover memory bytes: {}", self.prover_memory_bytes)
+    }
+}
+
+/// Estimates the cost of creating a proof of a single circuit instance for `cs`, with
+/// parameter `k` and the given commitment scheme.
+///
+/// `cs` must be the constraint system that the proof is created for, i.e. after selector
+/// compression (such as [`MockProver::cs`]). The counts are derived the way the prover
+/// derives them: the number of pieces of the quotient polynomial and the size of the
+/// permutation chunks come from [`ConstraintSystem::degree`], the evaluations from the
+/// queries of the constraint system, and the last rows of the permutation products from
+/// [`ConstraintSystem::blinding_factors`].
+///
+/// The proof size assumes that compressed curve points have the size of a scalar, which
+/// holds for the usual curves (such as BN254 and the Pasta curves). The prover memory is a
+/// rough estimate, which assumes that each polynomial is held in both Lagrange and
+/// coefficient form, along with the evaluations of the quotient polynomial over the
+/// extended domain.
+pub fn estimate<F: PrimeField>(
+    cs: &ConstraintSystem<F>,
+    k: u32,
+    scheme: CommitmentScheme,
+) -> CostEstimate {
+    let n = 1usize << k;
+    let scalar_bytes = F::Repr::default().as_ref().len();
+    let degree = cs.degree();
+    let quotient_pieces = degree - 1;
+
+    let permutation_columns = cs.permutation.get_columns().len();
+    let permutation_products = cs.permutation_chunks().len();
+    let lookups = cs.lookups.len();
+    let shuffles = cs.shuffles.len();
+    let instance_queries = match scheme {
+        // Only IPA opens the instance columns in the proof.
+        CommitmentScheme::IPA => cs.instance_queries.len(),
+        CommitmentScheme::KZGGWC | CommitmentScheme::KZGSHPLONK => 0,
+    };
+
+    // PLONK:
+    // - 1 commitment per advice column
+    // - 2 permuted commitments and 1 product commitment per lookup
+    // - 1 product commitment per shuffle
+    // - 1 product commitment per permutation chunk
+    // Vanishing argument:
+    // - 1 random polynomial commitment and 1 commitment per quotient piece
+    let commitments =
+        cs.num_advice_columns + 3 * lookups + shuffles + permutation_products + 1 + quotient_pieces;
+
+    // - 1 eval per instance (IPA only), advice and fixed query
+    // - 1 eval per permutation column
+    // - 2 evals per permutation chunk, and 1 more for each chunk but the last
+    // - 5 evals per lookup
+    // - 2 evals per shuffle
+    // - 1 eval of the random polynomial
+    let evaluations = instance_queries
+        + cs.advice_queries.len()
+        + cs.fixed_queries.len()
+        + permutation_columns
+        + if permutation_products == 0 {
+            0
+        } else {
+            3 * permutation_products - 1
+        }
+        + 5 * lookups
+        + 2 * shuffles
+        + 1;
+
+    // The sets of points at which each polynomial is opened.
+    let mut column_points = HashMap::<(u8, usize), BTreeSet<i32>>::new();
+    for (column, rotation) in cs.advice_queries.iter() {
+        column_points
+            .entry((0, column.index()))
+            .or_default()
+            .insert(rotation.0);
+    }
+    for (column, rotation) in cs.fixed_queries.iter() {
+        column_points
+            .entry((1, column.index()))
+            .or_default()
+            .insert(rotation.0);
+    }
+    if instance_queries > 0 {
+        for (column, rotation) in cs.instance_queries.iter() {
+            column_points
+                .entry((2, column.index()))
+                .or_default()
+                .insert(rotation.0);
+        }
+    }
+    let last_rotation = -((cs.blinding_factors() + 1) as i32);
+    let opened_sets: Vec<BTreeSet<i32>> = column_points
+        .into_values()
+        // Permutation: the sigma polynomials, and the product polynomials, of which all
+        // but the last are also opened at the last usable row.
+        .chain((0..permutation_columns).map(|_| BTreeSet::from([0])))
+        .chain((0..permutation_products).map(|chunk| {
+            if chunk + 1 < permutation_products {
+                BTreeSet::from([0, 1, last_rotation])
+            } else {
+                BTreeSet::from([0, 1])
+            }
+        }))
+        // Lookups: the product, permuted input and permuted table polynomials.
+        .chain((0..lookups).flat_map(|_| {
+            [
+                BTreeSet::from([0, 1]),
+                BTreeSet::from([-1, 0]),
+                BTreeSet::from([0]),
+            ]
+        }))
+        // Shuffles: the product polynomial.
+        .chain((0..shuffles).map(|_| BTreeSet::from([0, 1])))
+        // Vanishing argument: the quotient and random polynomials.
+        .chain(Some(BTreeSet::from([0])))
+        .collect();
+    let points: BTreeSet<i32> = opened_sets.iter().flatten().copied().collect();
+    let point_sets: HashSet<&BTreeSet<i32>> = opened_sets.iter().collect();
+
+    // Multiopening argument and polynomial commitment scheme.
+    let (commitments, evaluations) = match scheme {
+        // - 1 witness commitment per point
+        CommitmentScheme::KZGGWC => (commitments + points.len(), evaluations),
+        // - 2 commitments
+        CommitmentScheme::KZGSHPLONK => (commitments + 2, evaluations),
+        // - 1 commitment and 1 eval per set of points in the multiopening argument
+        // - 1 commitment, 2 commitments per round and 2 scalars in the inner product
+        //   argument
+        CommitmentScheme::IPA => (
+            commitments + 1 + 1 + 2 * k as usize,
+            evaluations + point_sets.len() + 2,
+        ),
+    };
+
+    let polynomials = cs.num_advice_columns
+        + cs.num_fixed_columns
+        + cs.num_instance_columns
+        + permutation_columns
+        + 3 * lookups
+        + shuffles
+        + permutation_products;
+    let extended_factor = quotient_pieces.next_power_of_two();
+
+    CostEstimate {
+        k,
+        scheme,
+        degree,
+        advice_commitments: cs.num_advice_columns,
+        fixed_commitments: cs.num_fixed_columns,
+        instance_commitments: cs.num_instance_columns,
+        lookup_products: lookups,
+        shuffle_products: shuffles,
+        permutation_products,
+        quotient_pieces,
+        proof_commitments: commitments,
+        proof_evaluations: evaluations,
+        proof_bytes: (commitments + evaluations) * scalar_bytes,
+        prover_memory_bytes: (2 * polynomials + extended_factor) * n * scalar_bytes,
+    }
+}
@@ -0,0 +1,548 @@
+//! Developer tools for comparing the witnesses of two circuits.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt::{self, Write};
+
+use halo2_middleware::circuit::{Any, ColumnMid};
+use halo2_middleware::ff::Field;
+
+use super::metadata::{self, DebugColumn};
+use super::render::json_string;
+use super::util::format_value;
+use super::{CellValue, InstanceValue, MockProver};
+
+/// The maximum number of differing cells that are displayed per column.
+const MAX_DISPLAYED_CELLS: usize = 8;
+
+/// A column compared by [`MockProver::diff`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum DiffColumn {
+    /// An instance, advice or fixed column.
+    Column(metadata::Column),
+    /// The selector with the given index.
+    Selector(usize),
+}
+
+/// A cell whose value differs between two circuits.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CellDiff {
+    /// The column of the cell.
+    pub column: DiffColumn,
+    /// The absolute row of the cell.
+    pub row: usize,
+    /// The value of the cell in the old circuit.
+    pub old: String,
+    /// The value of the cell in the new circuit.
+    pub new: String,
+}
+
+/// The number of differing cells in a column.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ColumnDiff {
+    /// The column.
+    pub column: DiffColumn,
+    /// The annotation of the column, if any.
+    pub annotation: Option<String>,
+    /// The number of cells of the column that differ.
+    pub cells: usize,
+}
+
+/// A copy constraint between two cells, as `(column, row)` pairs.
+pub type CopyConstraint = ((metadata::Column, usize), (metadata::Column, usize));
+
+/// The differences between the witnesses of two circuits, as returned by
+/// [`MockProver::diff`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WitnessDiff {
+    /// Power-of-2 bound on the number of rows of both circuits.
+    pub k: u32,
+    /// The differing cells, ordered by column and row.
+    pub cells: Vec<CellDiff>,
+    /// The number of differing cells per column, for the columns with differing cells.
+    pub columns: Vec<ColumnDiff>,
+    /// The columns that only exist in the old circuit.
+    pub only_in_old: Vec<DiffColumn>,
+    /// The columns that only exist in the new circuit.
+    pub only_in_new: Vec<DiffColumn>,
+    /// The copy constraints that only exist in the old circuit.
+    pub removed_copies: Vec<CopyConstraint>,
+    /// The copy constraints that only exist in the new circuit.
+    pub added_copies: Vec<CopyConstraint>,
+    annotations: HashMap<metadata::Column, String>,
+}
+
+impl WitnessDiff {
+    /// Returns `true` if the witnesses of both circuits are identical.
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+            && self.only_in_old.is_empty()
+            && self.only_in_new.is_empty()
+            && self.removed_copies.is_empty()
+            && self.added_copies.is_empty()
+    }
+
+    fn column_name(&self, column: DiffColumn) -> String {
+        match column {
+            DiffColumn::Column(column) => {
+                DebugColumn::from((column, Some(&self.annotations))).to_string()
+            }
+            DiffColumn::Selector(index) => format!("Selector({index})"),
+        }
+    }
+
+    /// Returns the differences as JSON, including all the differing cells.
+    pub fn to_json(&self) -> String {
+        let column = |column: DiffColumn| match column {
+            DiffColumn::Column(column) => format!(
+                r#"{{"type":"{}","index":{},"annotation":{}}}"#,
+                match column.column_type {
+                    Any::Advice(_) => "advice",
+                    Any::Fixed => "fixed",
+                    Any::Instance => "instance",
+                },
+                column.index,
+                self.annotations
+                    .get(&column)
+                    .map_or("null".to_string(), |a| json_string(a)),
+            ),
+            DiffColumn::Selector(index) => {
+                format!(r#"{{"type":"selector","index":{index},"annotation":null}}"#)
+            }
+        };
+        let copy = |((column_a, row_a), (column_b, row_b)): &CopyConstraint| {
+            format!(
+                r#"{{"from":{{"column":{},"row":{}}},"to":{{"column":{},"row":{}}}}}"#,
+                column(DiffColumn::Column(*column_a)),
+                row_a,
+                column(DiffColumn::Column(*column_b)),
+                row_b
+            )
+        };
+        let list = |items: Vec<String>| format!("[{}]", items.join(","));
+
+        let mut out = String::new();
+        write!(
+            out,
+            r#"{{"k":{},"columns":{},"cells":{},"only_in_old":{},"only_in_new":{},"removed_copies":{},"added_copies":{}}}"#,
+            self.k,
+            list(
+                self.columns
+                    .iter()
+                    .map(|c| format!(r#"{{"column":{},"cells":{}}}"#, column(c.column), c.cells))
+                    .collect()
+            ),
+            list(
+                self.cells
+                    .iter()
+                    .map(|c| format!(
+                        r#"{{"column":{},"row":{},"old":{},"new":{}}}"#,
+                        column(c.column),
+                        c.row,
+                        json_string(&c.old),
+                        json_string(&c.new)
+                    ))
+                    .collect()
+            ),
+            list(self.only_in_old.iter().map(|c| column(*c)).collect()),
+            list(self.only_in_new.iter().map(|c| column(*c)).collect()),
+            list(self.removed_copies.iter().map(copy).collect()),
+            list(self.added_copies.iter().map(copy).collect()),
+        )
+        .unwrap();
+        out
+    }
+}
+
+impl fmt::Display for WitnessDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return writeln!(f, "The witnesses are identical");
+        }
+
+        writeln!(
+            f,
+            "{} differing cells in {} columns",
+            self.cells.len(),
+            self.columns.len()
+        )?;
+        for column in &self.columns {
+            writeln!(
+                f,
+                "  {}: {} differing cells",
+                self.column_name(column.column),
+                column.cells
+            )?;
+            let cells = self.cells.iter().filter(|c| c.column == column.column);
+            for cell in cells.take(MAX_DISPLAYED_CELLS) {
+                writeln!(f, "    row {}: {} -> {}", cell.row, cell.old, cell.new)?;
+            }
+            if column.cells > MAX_DISPLAYED_CELLS {
+                writeln!(f, "    ... and {} more", column.cells - MAX_DISPLAYED_CELLS)?;
+            }
+        }
+
+        for column in &self.only_in_old {
+            writeln!(f, "  {} only in old circuit", self.column_name(*column))?;
+        }
+        for column in &self.only_in_new {
+            writeln!(f, "  {} only in new circuit", self.column_name(*column))?;
+        }
+
+        for (copies, change) in [
+            (&self.removed_copies, "removed"),
+            (&self.added_copies, "added"),
+        ] {
+            if copies.is_empty() {
+                continue;
+            }
+            writeln!(f, "{} copy constraints {}", copies.len(), change)?;
+            for ((column_a, row_a), (column_b, row_b)) in copies.iter().take(MAX_DISPLAYED_CELLS) {
+                writeln!(
+                    f,
+                    "    {}@{} == {}@{}",
+                    self.column_name(DiffColumn::Column(*column_a)),
+                    row_a,
+                    self.column_name(DiffColumn::Column(*column_b)),
+                    row_b
+                )?;
+            }
+            if copies.len() > MAX_DISPLAYED_CELLS {
+                writeln!(f, "    ... and {} more", copies.len() - MAX_DISPLAYED_CELLS)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn cell_value<F: Field>(value: &CellValue<F>) -> String {
+    match value {
+        CellValue::Unassigned => "unassigned".to_string(),
+        CellValue::Assigned(value) => format_value(*value),
+        CellValue::Poison(_) => "poison".to_string(),
+    }
+}
+
+fn instance_value<F: Field>(value: &InstanceValue<F>) -> String {
+    match value {
+        InstanceValue::Assigned(value) => format_value(*value),
+        InstanceValue::Padding => "padding".to_string(),
+    }
+}
+
+fn column_mid(column: ColumnMid) -> metadata::Column {
+    metadata::Column {
+        column_type: column.column_type,
+        index: column.index,
+    }
+}
+
+impl<F: Field> MockProver<F> {
+    /// Compares the witness of this circuit (the old one) with that of `other` (the new
+    /// one), cell by cell.
+    ///
+    /// The instance, advice and fixed columns (including those that selectors were
+    /// compressed into), the selectors and the copy constraints are compared. Columns
+    /// that only exist in one of the circuits are reported as such, rather than cell by
+    /// cell.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the circuits have a different `k`.
+    pub fn diff(&self, other: &MockProver<F>) -> WitnessDiff {
+        assert_eq!(
+            self.k, other.k,
+            "circuits must have the same k to be compared"
+        );
+
+        let mut cells = vec![];
+        let mut only_in_old = vec![];
+        let mut only_in_new = vec![];
+        let mut compare = |column: DiffColumn,
+                           old: Option<Vec<String>>,
+                           new: Option<Vec<String>>| match (old, new) {
+            (Some(old), Some(new)) => cells.extend(
+                old.into_iter()
+                    .zip(new)
+                    .enumerate()
+                    .filter(|(_, (old, new))| old != new)
+                    .map(|(row, (old, new))| CellDiff {
+                        column,
+                        row,
+                        old,
+                        new,
+                    }),
+            ),
+            (Some(_), None) => only_in_old.push(column),
+            (None, Some(_)) => only_in_new.push(column),
+            (None, None) => (),
+        };
+
+        macro_rules! compare_columns {
+            ($field:ident, $column_type:expr, $value:expr) => {
+                for index in 0..self.$field.len().max(other.$field.len()) {
+                    let values = |prover: &MockProver<F>| {
+                        prover
+                            .$field
+                            .get(index)
+                            .map(|column| column.iter().map($value).collect::<Vec<_>>())
+                    };
+                    let column = metadata::Column {
+                        column_type: $column_type(index),
+                        index,
+                    };
+                    compare(DiffColumn::Column(column), values(self), values(other));
+                }
+            };
+        }
+        compare_columns!(instance, |_| Any::Instance, instance_value);
+        compare_columns!(
+            advice,
+            |index| {
+                let prover = if index < self.advice.len() {
+                    self
+                } else {
+                    other
+                };
+                Any::Advice(halo2_middleware::circuit::Advice::new(
+                    prover.cs.advice_column_phase[index].0,
+                ))
+            },
+            cell_value
+        );
+        compare_columns!(fixed, |_| Any::Fixed, cell_value);
+        for index in 0..self.selectors.len().max(other.selectors.len()) {
+            let values = |prover: &MockProver<F>| {
+                prover.selectors.get(index).map(|selector| {
+                    selector
+                        .iter()
+                        .map(|enabled| enabled.to_string())
+                        .collect::<Vec<_>>()
+                })
+            };
+            compare(DiffColumn::Selector(index), values(self), values(other));
+        }
+
+        cells.sort_by_key(|cell| (cell.column, cell.row));
+        let mut columns = BTreeMap::<DiffColumn, usize>::new();
+        for cell in &cells {
+            *columns.entry(cell.column).or_default() += 1;
+        }
+
+        let copies = |prover: &MockProver<F>| -> BTreeSet<CopyConstraint> {
+            prover
+                .permutation
+                .copies
+                .iter()
+                .map(|(a, b)| {
+                    let a = (column_mid(a.column), a.row);
+                    let b = (column_mid(b.column), b.row);
+                    (a.min(b), a.max(b))
+                })
+                .collect()
+        };
+        let (old_copies, new_copies) = (copies(self), copies(other));
+
+        let mut annotations = self.cs.general_column_annotations.clone();
+        for (column, annotation) in &other.cs.general_column_annotations {
+            annotations.insert(*column, annotation.clone());
+        }
+
+        WitnessDiff {
+            k: self.k,
+            columns: columns
+                .into_iter()
+                .map(|(column, cells)| ColumnDiff {
+                    column,
+                    annotation: match column {
+                        DiffColumn::Column(column) => annotations.get(&column).cloned(),
+                        DiffColumn::Selector(_) => None,
+                    },
+                    cells,
+                })
+                .collect(),
+            cells,
+            only_in_old,
+            only_in_new,
+            removed_copies: old_copies.difference(&new_copies).copied().collect(),
+            added_copies: new_copies.difference(&old_copies).copied().collect(),
+            annotations,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_middleware::poly::Rotation;
+    use halo2curves::pasta::Fp;
+
+    use super::*;
+    use crate::circuit::{Layouter, SimpleFloorPlanner, Value};
+    use halo2_common::plonk::{circuit::Column, Circuit, ConstraintSystem, Error, Selector};
+    use halo2_middleware::circuit::Advice;
+
+    #[test]
+    fn perturbed_value() {
+        const K: u32 = 5;
+
+        #[derive(Clone)]
+        struct TestConfig {
+            a: Column<Advice>,
+            b: Column<Advice>,
+            s: Selector,
+        }
+
+        struct TestCircuit {
+            values: Vec<u64>,
+        }
+
+        impl Circuit<Fp> for TestCircuit {
+            type Config = TestConfig;
+            type FloorPlanner = SimpleFloorPlanner;
+            #[cfg(feature = "circuit-params")]
+            type Params = ();
+
+            fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+                let a = meta.advice_column();
+                let b = meta.advice_column();
+                let s = meta.selector();
+                meta.enable_equality(a);
+                meta.annotate_lookup_any_column(a, || "a");
+
+                meta.create_gate("double", |meta| {
+                    let a = meta.query_advice(a, Rotation::cur());
+                    let b = meta.query_advice(b, Rotation::cur());
+                    let s = meta.query_selector(s);
+                    vec![s * (a.clone() + a - b)]
+                });
+
+                TestConfig { a, b, s }
+            }
+
+            fn without_witnesses(&self) -> Self {
+                Self {
+                    values: self.values.clone(),
+                }
+            }
+
+            fn synthesize(
+                &self,
+                config: Self::Config,
+                mut layouter: impl Layouter<Fp>,
+            ) -> Result<(), Error> {
+                layouter.assign_region(
+                    || "double",
+                    |mut region| {
+                        for (offset, value) in self.values.iter().enumerate() {
+                            config.s.enable(&mut region, offset)?;
+                            let value = Fp::from(*value);
+                            region.assign_advice(
+                                || "a",
+                                config.a,
+                                offset,
+                                || Value::known(value),
+                            )?;
+                            region.assign_advice(
+                                || "b",
+                                config.b,
+                                offset,
+                                || Value::known(value.double()),
+                            )?;
+                        }
+                        Ok(())
+                    },
+                )
+            }
+        }
+
+        let old = MockProver::run(
+            K,
+            &TestCircuit {
+                values: vec![1, 2, 3],
+            },
+            vec![],
+        )
+        .unwrap();
+        assert!(old.diff(&old).is_empty());
+        assert_eq!(old.diff(&old).to_string(), "The witnesses are identical\n");
+
+        let new = MockProver::run(
+            K,
+            &TestCircuit {
+                values: vec![1, 5, 3],
+            },
+            vec![],
+        )
+        .unwrap();
+        let diff = old.diff(&new);
+        let advice = |index| {
+            DiffColumn::Column(metadata::Column {
+                column_type: Any::Advice(Advice::new(0)),
+                index,
+            })
+        };
+        assert_eq!(
+            diff.cells,
+            vec![
+                CellDiff {
+                    column: advice(0),
+                    row: 1,
+                    old: "0x2".to_string(),
+                    new: "0x5".to_string(),
+                },
+                CellDiff {
+                    column: advice(1),
+                    row: 1,
+                    old: "0x4".to_string(),
+                    new: "0xa".to_string(),
+                },
+            ]
+        );
+        assert_eq!(diff.columns.len(), 2);
+        assert_eq!(diff.columns[0].annotation.as_deref(), Some("a"));
+        assert!(diff.only_in_old.is_empty() && diff.only_in_new.is_empty());
+        assert_eq!(
+            diff.to_string(),
+            "2 differing cells in 2 columns\n  \
+             Column('Advice', 0 - a): 1 differing cells\n    row 1: 0x2 -> 0x5\n  \
+             Column('Advice', 1 - ): 1 differing cells\n    row 1: 0x4 -> 0xa\n"
+        );
+
+        let json: serde_json::Value = serde_json::from_str(&diff.to_json()).unwrap();
+        assert_eq!(json["cells"][0]["row"], 1);
+        assert_eq!(json["cells"][0]["column"]["annotation"], "a");
+        assert_eq!(json["cells"][1]["new"], "0xa");
+
+        // A longer witness enables the selector on more rows.
+        let longer = MockProver::run(
+            K,
+            &TestCircuit {
+                values: vec![1, 2, 3, 4],
+            },
+            vec![],
+        )
+        .unwrap();
+        let diff = old.diff(&longer);
+        assert_eq!(
+            diff.cells
+                .iter()
+                .map(|c| (c.column, c.row))
+                .collect::<Vec<_>>(),
+            vec![
+                (advice(0), 3),
+                (advice(1), 3),
+                // The fixed column that the selector is compressed into.
+                (
+                    DiffColumn::Column(metadata::Column {
+                        column_type: Any::Fixed,
+                        index: 0
+                    }),
+                    3
+                ),
+                (DiffColumn::Selector(0), 3),
+            ]
+        );
+        assert_eq!(diff.cells[0].old, "unassigned");
+        assert_eq!(diff.cells[3].new, "true");
+    }
+}
@@ -1,5 +1,5 @@
-use std::collections::{BTreeMap, HashSet};
-use std::fmt::{self, Debug};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fmt::{self, Debug, Write};
 
 use group::ff::Field;
 
@@ -16,6 +16,7 @@ use halo2_common::plonk::{circuit::Column, ConstraintSystem, Expression, Gate};
 use halo2_middleware::circuit::Any;
 
 mod emitter;
+mod json;
 
 /// The location within the circuit at which a particular [`VerifyFailure`] occurred.
 #[derive(Debug, PartialEq, Eq, Clone)]
@@ -89,12 +90,21 @@ impl FailureLocation {
             })
             .collect();
 
-        Self::find(regions, failure_row, failure_columns)
+        Self::find(
+            regions,
+            &cs.general_column_annotations,
+            failure_row,
+            failure_columns,
+        )
     }
 
     /// Figures out whether the given row and columns overlap an assigned region.
+    ///
+    /// The annotations of the returned region prefer those given within the region, and
+    /// fall back to `general_annotations` for the other columns.
     pub(super) fn find(
         regions: &[Region],
+        general_annotations: &HashMap<metadata::Column, String>,
         failure_row: usize,
         failure_columns: HashSet<Column<Any>>,
     ) -> Self {
@@ -115,7 +125,7 @@ impl FailureLocation {
                 }
             })
             .map(|(r_i, r)| FailureLocation::InRegion {
-                region: (r_i, r.name.clone(), r.annotations.clone()).into(),
+                region: r.metadata(r_i, Some(r.scoped_annotations(general_annotations))),
                 offset: failure_row - r.rows.unwrap().0,
             })
             .unwrap_or_else(|| FailureLocation::OutsideRegion { row: failure_row })
@@ -438,24 +448,12 @@ fn render_cell_not_assigned<F: Field>(
 /// ```
 fn render_constraint_not_satisfied<F: Field>(
     gates: &[Gate<F>],
+    challenge_annotations: &HashMap<usize, String>,
     constraint: &metadata::Constraint,
     location: &FailureLocation,
     cell_values: &[(metadata::VirtualCell, String)],
 ) {
-    // Collect the necessary rendering information:
-    // - The columns involved in this constraint.
-    // - How many cells are in each column.
-    // - The grid of cell values, indexed by rotation.
-    let mut columns = BTreeMap::<metadata::Column, usize>::default();
-    let mut layout = BTreeMap::<i32, BTreeMap<metadata::Column, _>>::default();
-    for (i, (cell, _)) in cell_values.iter().enumerate() {
-        *columns.entry(cell.column).or_default() += 1;
-        layout
-            .entry(cell.rotation)
-            .or_default()
-            .entry(cell.column)
-            .or_insert(format!("x{i}"));
-    }
+    let (columns, layout) = constraint_cell_layout(cell_values);
 
     eprintln!("error: constraint not satisfied");
     emitter::render_cell_layout("  ", location, &columns, &layout, |_, rotation| {
@@ -471,7 +469,8 @@ fn render_constraint_not_satisfied<F: Field>(
         "    {} = 0",
         emitter::expression_to_string(
             &gates[constraint.gate.index].polynomials()[constraint.index],
-            &layout
+            &layout,
+            challenge_annotations,
         )
     );
 
@@ -483,6 +482,117 @@ fn render_constraint_not_satisfied<F: Field>(
     }
 }
 
+/// Collects the information necessary to render the cells used by a constraint:
+/// - The columns involved in this constraint, and how many cells are in each column.
+/// - The grid of local variables, indexed by rotation.
+#[allow(clippy::type_complexity)]
+fn constraint_cell_layout(
+    cell_values: &[(metadata::VirtualCell, String)],
+) -> (
+    BTreeMap<metadata::Column, usize>,
+    BTreeMap<i32, BTreeMap<metadata::Column, String>>,
+) {
+    let mut columns = BTreeMap::<metadata::Column, usize>::default();
+    let mut layout = BTreeMap::<i32, BTreeMap<metadata::Column, _>>::default();
+    for (i, (cell, _)) in cell_values.iter().enumerate() {
+        *columns.entry(cell.column).or_default() += 1;
+        layout
+            .entry(cell.rotation)
+            .or_default()
+            .entry(cell.column)
+            .or_insert(format!("x{i}"));
+    }
+    (columns, layout)
+}
+
+/// Splits `expr` into its top-level summands, i.e. at its outermost `Sum` nodes.
+///
+/// Negations, scalings and multiplications by a fixed column (which is how selectors are
+/// applied once they have been converted to fixed columns) are distributed over the
+/// summands, so that `s * (a - b)` is split into `s * a` and `-(s * b)`.
+fn summands<F: Field>(expr: &Expression<F>) -> Vec<Expression<F>> {
+    match expr {
+        Expression::Sum(a, b) => {
+            let mut summands_a = summands(a);
+            summands_a.extend(summands(b));
+            summands_a
+        }
+        Expression::Negated(a) => summands(a).into_iter().map(|a| -a).collect(),
+        Expression::Scaled(a, f) => summands(a).into_iter().map(|a| a * *f).collect(),
+        Expression::Product(a, b) => match (a.as_ref(), b.as_ref()) {
+            (Expression::Fixed(_), b) => summands(b)
+                .into_iter()
+                .map(|b| a.as_ref().clone() * b)
+                .collect(),
+            (a, Expression::Fixed(_)) => summands(a)
+                .into_iter()
+                .map(|a| a * b.as_ref().clone())
+                .collect(),
+            _ => vec![expr.clone()],
+        },
+        _ => vec![expr.clone()],
+    }
+}
+
+/// Renders the top-level summands of the constraint of a
+/// `VerifyFailure::ConstraintNotSatisfied`, with their values at the failing row.
+///
+/// ```text
+/// Summands at row 0:
+/// - S0 * (1 - x0) * x0 = 0
+/// - S0 * (1 - x1) * x1 = -0x2
+/// ```
+///
+/// Values that cannot be computed (because they depend on unusable rows) are rendered as
+/// `?`.
+fn render_summands<F: Field>(
+    prover: &MockProver<F>,
+    constraint: &metadata::Constraint,
+    location: &FailureLocation,
+    cell_values: &[(metadata::VirtualCell, String)],
+) -> String {
+    let n = prover.n as i32;
+    let row = match location {
+        FailureLocation::InRegion { region, offset } => {
+            prover.regions[region.index].rows.unwrap().0 + offset
+        }
+        FailureLocation::OutsideRegion { row } => *row,
+    };
+    let (_, layout) = constraint_cell_layout(cell_values);
+    let poly = &prover.cs.gates[constraint.gate.index].polynomials()[constraint.index];
+
+    let mut report = format!("Summands at row {row}:\n");
+    for summand in summands(poly) {
+        let value = summand.evaluate_lazy(
+            &|scalar| Value::Real(scalar),
+            &|_| panic!("virtual selectors are removed during optimization"),
+            &util::load(n, row as i32 + n, &prover.cs.fixed_queries, &prover.fixed),
+            &util::load(n, row as i32 + n, &prover.cs.advice_queries, &prover.advice),
+            &util::load_instance(
+                n,
+                row as i32 + n,
+                &prover.cs.instance_queries,
+                &prover.instance,
+            ),
+            &|challenge| Value::Real(prover.challenges[challenge.index()]),
+            &|a| -a,
+            &|a, b| a + b,
+            &|a, b| a * b,
+            &|a, scalar| a * scalar,
+            &Value::Real(F::ZERO),
+        );
+        report.push_str(&format!(
+            "- {} = {}\n",
+            emitter::expression_to_string(&summand, &layout, &prover.cs.challenge_annotations),
+            match value {
+                Value::Real(v) => util::format_value(v),
+                Value::Poison => "?".to_string(),
+            }
+        ));
+    }
+    report
+}
+
 /// Renders `VerifyFailure::Lookup`.
 ///
 /// ```text
@@ -559,7 +669,12 @@ fn render_lookup<F: Field>(
                         .unwrap_or_else(|| format!("I{}", query.column_index()))
                 )
             },
-            &|challenge| format! {"C{}", challenge.index()},
+            &|challenge| {
+                prover.cs.challenge_annotation(challenge).map_or_else(
+                    || format!("C{}", challenge.index()),
+                    |name| format!("{name:?}"),
+                )
+            },
             &|query| format! {"-{query}"},
             &|a, b| format! {"{a} + {b}"},
             &|a, b| format! {"{a} * {b}"},
@@ -650,7 +765,7 @@ fn render_lookup<F: Field>(
         eprintln!(
             "    L{} = {}",
             i,
-            emitter::expression_to_string(input, &layout)
+            emitter::expression_to_string(input, &layout, &prover.cs.challenge_annotations)
         );
         eprintln!("    ^");
 
@@ -669,6 +784,116 @@ fn render_lookup<F: Field>(
     }
 }
 
+/// The number of nearest table rows shown when a lookup failure is emitted.
+const NEAREST_TABLE_ROWS: usize = 3;
+
+fn render_lookup_values<F: Field + Ord>(
+    prover: &MockProver<F>,
+    name: &str,
+    lookup_index: usize,
+    location: &FailureLocation,
+    nearest: usize,
+) -> String {
+    let lookup = &prover.cs.lookups[lookup_index];
+    let row = match location {
+        FailureLocation::InRegion { region, offset } => {
+            prover.regions[region.index].rows.unwrap().0 + offset
+        }
+        FailureLocation::OutsideRegion { row } => *row,
+    };
+
+    let format_value = |value: &Value<F>| match value {
+        Value::Real(v) => util::format_value(*v),
+        Value::Poison => "poison".to_string(),
+    };
+    let format_tuple = |values: &[Value<F>]| {
+        let values: Vec<_> = values.iter().map(format_value).collect();
+        format!("({})", values.join(", "))
+    };
+
+    let input: Vec<_> = lookup
+        .input_expressions
+        .iter()
+        .map(|expr| prover.evaluate_at(expr, row))
+        .collect();
+
+    // The fixed columns queried by the inputs, which include the compressed selectors.
+    let mut gates = BTreeMap::<(usize, i32), Value<F>>::new();
+    for expr in &lookup.input_expressions {
+        let queries = expr.evaluate(
+            &|_| vec![],
+            &|_| panic!("virtual selectors are removed during optimization"),
+            &|query| vec![query],
+            &|_| vec![],
+            &|_| vec![],
+            &|_| vec![],
+            &|a| a,
+            &|mut a, mut b| {
+                a.append(&mut b);
+                a
+            },
+            &|mut a, mut b| {
+                a.append(&mut b);
+                a
+            },
+            &|a, _| a,
+        );
+        for query in queries {
+            gates.insert(
+                (query.column_index, query.rotation.0),
+                prover.evaluate_at(&Expression::Fixed(query), row),
+            );
+        }
+    }
+
+    let table: BTreeSet<Vec<Value<F>>> = prover
+        .usable_rows
+        .clone()
+        .map(|table_row| {
+            lookup
+                .table_expressions
+                .iter()
+                .map(|expr| prover.evaluate_at(expr, table_row))
+                .collect()
+        })
+        .collect();
+
+    // The distance between two field elements, in either direction.
+    let distance = |a: &Value<F>, b: &Value<F>| match (a, b) {
+        (Value::Real(a), Value::Real(b)) => Some((*a - b).min(*b - a)),
+        _ => None,
+    };
+    let mut nearest_rows: Vec<_> = table.iter().collect();
+    nearest_rows.sort_by_key(|table_row| {
+        // Rows that cannot be compared are sorted last.
+        let d = distance(&input[0], &table_row[0]);
+        (d.is_none(), d)
+    });
+
+    let mut out = String::new();
+    writeln!(out, "  Lookup '{name}' input at row {row}:").unwrap();
+    writeln!(out, "    {}", format_tuple(&input)).unwrap();
+    if gates.is_empty() {
+        writeln!(out, "  The input is not gated by any fixed column").unwrap();
+    } else {
+        writeln!(out, "  Gated by:").unwrap();
+        for ((column_index, rotation), value) in &gates {
+            writeln!(
+                out,
+                "    {}@{rotation} = {}",
+                location.get_debug_column(metadata::Column::from((Any::Fixed, *column_index))),
+                format_value(value)
+            )
+            .unwrap();
+        }
+    }
+    writeln!(out, "  Table has {} distinct rows, nearest:", table.len()).unwrap();
+    for table_row in nearest_rows.into_iter().take(nearest) {
+        writeln!(out, "    {}", format_tuple(table_row)).unwrap();
+    }
+    out
+}
+
 fn render_shuffle<F: Field>(
     prover: &MockProver<F>,
     name: &str,
@@ -725,7 +950,12 @@ fn render_shuffle<F: Field>(
                         .unwrap_or_else(|| format!("I{}", query.column_index()))
                 )
             },
-            &|challenge| format! {"C{}", challenge.index()},
+            &|challenge| {
+                prover.cs.challenge_annotation(challenge).map_or_else(
+                    || format!("C{}", challenge.index()),
+                    |name| format!("{name:?}"),
+                )
+            },
             &|query| format! {"-{query}"},
             &|a, b| format! {"{a} + {b}"},
             &|a, b| format! {"{a} * {b}"},
@@ -815,7 +1045,7 @@ fn render_shuffle<F: Field>(
         eprintln!(
             "    Sh{} = {}",
             i,
-            emitter::expression_to_string(input, &layout)
+            emitter::expression_to_string(input, &layout, &prover.cs.challenge_annotations)
         );
         eprintln!("    ^");
 
@@ -835,8 +1065,60 @@ fn render_shuffle<F: Field>(
 }
 
 impl VerifyFailure {
+    /// Returns a report of this failure that is more detailed than its `Display` output.
+    ///
+    /// For `VerifyFailure::ConstraintNotSatisfied`, the failing constraint is split into
+    /// its top-level summands, and the value of each summand at the failing row is
+    /// reported, in order to pinpoint the terms that break the constraint. The cells used
+    /// by the constraint are named `x0`, `x1`, ... in the order of the cell values listed
+    /// above them. Other failures are reported as by their `Display` implementation.
+    pub fn verbose_report<F: Field>(&self, prover: &MockProver<F>) -> String {
+        match self {
+            Self::ConstraintNotSatisfied {
+                constraint,
+                location,
+                cell_values,
+            } => format!(
+                "{self}{}",
+                render_summands(prover, constraint, location, cell_values)
+            ),
+            _ => self.to_string(),
+        }
+    }
+
+    /// Returns a report of the lookup input of a `VerifyFailure::Lookup` at its failing
+    /// row, or `None` for other failures.
+    ///
+    /// The report contains:
+    /// - the values of the input expressions,
+    /// - the values of the fixed columns (including those that selectors were compressed
+    ///   into) queried by the input expressions, which usually gate the lookup,
+    /// - the number of distinct rows in the table, and
+    /// - the `nearest` table rows whose first component is closest to the first input
+    ///   value.
+    pub fn lookup_report<F: Field + Ord>(
+        &self,
+        prover: &MockProver<F>,
+        nearest: usize,
+    ) -> Option<String> {
+        match self {
+            Self::Lookup {
+                name,
+                lookup_index,
+                location,
+            } => Some(render_lookup_values(
+                prover,
+                name,
+                *lookup_index,
+                location,
+                nearest,
+            )),
+            _ => None,
+        }
+    }
+
     /// Emits this failure in pretty-printed format to stderr.
-    pub(super) fn emit<F: Field>(&self, prover: &MockProver<F>) {
+    pub(super) fn emit<F: Field + Ord>(&self, prover: &MockProver<F>) {
         match self {
             Self::CellNotAssigned {
                 gate,
@@ -856,14 +1138,25 @@ impl VerifyFailure {
                 constraint,
                 location,
                 cell_values,
-            } => {
-                render_constraint_not_satisfied(&prover.cs.gates, constraint, location, cell_values)
-            }
+            } => render_constraint_not_satisfied(
+                &prover.cs.gates,
+                &prover.cs.challenge_annotations,
+                constraint,
+                location,
+                cell_values,
+            ),
             Self::Lookup {
                 name,
                 lookup_index,
                 location,
-            } => render_lookup(prover, name, *lookup_index, location),
+            } => {
+                render_lookup(prover, name, *lookup_index, location);
+                eprintln!();
+                eprint!(
+                    "{}",
+                    render_lookup_values(prover, name, *lookup_index, location, NEAREST_TABLE_ROWS)
+                );
+            }
             Self::Shuffle {
                 name,
                 shuffle_index,
@@ -1,4 +1,4 @@
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashMap};
 use std::iter;
 
 use group::ff::Field;
@@ -140,6 +140,7 @@ pub(super) fn render_cell_layout(
 pub(super) fn expression_to_string<F: Field>(
     expr: &Expression<F>,
     layout: &BTreeMap<i32, BTreeMap<metadata::Column, String>>,
+    challenge_annotations: &HashMap<usize, String>,
 ) -> String {
     expr.evaluate(
         &util::format_value,
@@ -183,7 +184,12 @@ pub(super) fn expression_to_string<F: Field>(
                 .unwrap()
                 .clone()
         },
-        &|challenge| format!("C{}({})", challenge.index(), challenge.phase()),
+        &|challenge| {
+            challenge_annotations
+                .get(&challenge.index())
+                .cloned()
+                .unwrap_or_else(|| format!("C{}({})", challenge.index(), challenge.phase()))
+        },
         &|a| {
             if a.contains(' ') {
                 format!("-({a})")
@@ -0,0 +1,250 @@
+use std::fmt::Write;
+
+use super::{FailureLocation, VerifyFailure};
+use crate::dev::{metadata, render::json_string, MockProver, Region};
+use halo2_middleware::circuit::Any;
+use halo2_middleware::ff::FromUniformBytes;
+
+fn json_column(column: metadata::Column, annotation: Option<String>) -> String {
+    format!(
+        r#"{{"type":"{}","index":{},"annotation":{}}}"#,
+        match column.column_type {
+            Any::Advice(_) => "advice",
+            Any::Fixed => "fixed",
+            Any::Instance => "instance",
+        },
+        column.index,
+        annotation.map_or("null".to_string(), |a| json_string(&a)),
+    )
+}
+
+fn json_region(region: &metadata::Region) -> String {
+    format!(
+        r#"{{"index":{},"name":{}}}"#,
+        region.index,
+        json_string(&region.name)
+    )
+}
+
+fn json_named(index: usize, name: &str) -> String {
+    format!(r#"{{"index":{},"name":{}}}"#, index, json_string(name))
+}
+
+fn or_null(value: Option<String>) -> String {
+    value.unwrap_or_else(|| "null".to_string())
+}
+
+/// The fields of a failure in the JSON schema. Fields that do not apply to a failure are
+/// `null`, so that every failure has the same keys.
+#[derive(Default)]
+struct Fields {
+    gate: Option<String>,
+    constraint: Option<String>,
+    lookup: Option<String>,
+    shuffle: Option<String>,
+    region: Option<String>,
+    gate_offset: Option<usize>,
+    offset: Option<isize>,
+    row: Option<usize>,
+    column: Option<String>,
+    cells: Vec<String>,
+}
+
+impl Fields {
+    /// Sets the region, offset and row of `location`, where `start` is the first row of
+    /// the region (if known).
+    fn located(mut self, location: &FailureLocation, start: Option<usize>) -> Self {
+        match location {
+            FailureLocation::InRegion { region, offset } => {
+                self.region = Some(json_region(region));
+                self.offset = Some(*offset as isize);
+                self.row = start.map(|start| start + offset);
+            }
+            FailureLocation::OutsideRegion { row } => self.row = Some(*row),
+        }
+        self
+    }
+}
+
+impl VerifyFailure {
+    /// Returns the kind of this failure, as used in the `"kind"` field of
+    /// [`VerifyFailure::to_json`].
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::CellNotAssigned { .. } => "cell_not_assigned",
+            Self::InstanceCellNotAssigned { .. } => "instance_cell_not_assigned",
+            Self::ConstraintNotSatisfied { .. } => "constraint_not_satisfied",
+            Self::ConstraintPoisoned { .. } => "constraint_poisoned",
+            Self::Lookup { .. } => "lookup",
+            Self::Shuffle { .. } => "shuffle",
+            Self::Permutation { .. } => "permutation",
+        }
+    }
+
+    /// Returns this failure as a JSON object, for annotating CI runs.
+    ///
+    /// Every failure has the same keys:
+    /// - `kind`: one of the values returned by [`VerifyFailure::kind`].
+    /// - `message`: the `Display` form of the failure.
+    /// - `gate` and `constraint`: `{"index", "name"}` of the failing gate and constraint.
+    /// - `lookup` and `shuffle`: `{"index", "name"}` of the failing argument.
+    /// - `region`: `{"index", "name"}` of the region containing the failure.
+    /// - `gate_offset`: the offset within the region at which the gate is active.
+    /// - `offset`: the offset of the failure within the region.
+    /// - `row`: the absolute row of the failure.
+    /// - `column`: `{"type", "index", "annotation"}` of the failing column.
+    /// - `cells`: the `{"column", "rotation", "name", "value"}` of the cells queried by a
+    ///   failing constraint.
+    ///
+    /// Keys that do not apply to a failure are `null` (or an empty list for `cells`). The
+    /// absolute row of a failure within a region is only known to the [`MockProver`], so
+    /// it is `null` here; use [`MockProver::verify_to_json`] to include it.
+    pub fn to_json(&self) -> String {
+        self.to_json_with(&[])
+    }
+
+    /// Returns this failure as a JSON object, taking the rows of the failing regions from
+    /// `regions`.
+    pub(crate) fn to_json_with(&self, regions: &[Region]) -> String {
+        let start = |region: &metadata::Region| {
+            regions
+                .get(region.index)
+                .and_then(|r| r.rows)
+                .map(|(start, _)| start)
+        };
+        let location_start = |location: &FailureLocation| match location {
+            FailureLocation::InRegion { region, .. } => start(region),
+            FailureLocation::OutsideRegion { .. } => None,
+        };
+        let annotation = |location: &FailureLocation, column: metadata::Column| match location {
+            FailureLocation::InRegion { region, .. } => region.get_column_annotation(column),
+            FailureLocation::OutsideRegion { .. } => None,
+        };
+
+        let fields = match self {
+            Self::CellNotAssigned {
+                gate,
+                region,
+                gate_offset,
+                column,
+                offset,
+            } => Fields {
+                gate: Some(json_named(gate.index, &gate.name)),
+                region: Some(json_region(region)),
+                gate_offset: Some(*gate_offset),
+                offset: Some(*offset),
+                row: start(region).and_then(|start| usize::try_from(start as isize + offset).ok()),
+                column: Some(json_column(
+                    (*column).into(),
+                    region.get_column_annotation((*column).into()),
+                )),
+                ..Default::default()
+            },
+            Self::InstanceCellNotAssigned {
+                gate,
+                region,
+                gate_offset,
+                column,
+                row,
+            } => {
+                let column = metadata::Column::from((Any::Instance, column.index()));
+                Fields {
+                    gate: Some(json_named(gate.index, &gate.name)),
+                    region: Some(json_region(region)),
+                    gate_offset: Some(*gate_offset),
+                    row: Some(*row),
+                    column: Some(json_column(column, region.get_column_annotation(column))),
+                    ..Default::default()
+                }
+            }
+            Self::ConstraintNotSatisfied {
+                constraint,
+                location,
+                cell_values,
+            } => Fields {
+                gate: Some(json_named(constraint.gate.index, &constraint.gate.name)),
+                constraint: Some(json_named(constraint.index, &constraint.name)),
+                cells: cell_values
+                    .iter()
+                    .map(|(cell, value)| {
+                        format!(
+                            r#"{{"column":{},"rotation":{},"name":{},"value":{}}}"#,
+                            json_column(cell.column, annotation(location, cell.column)),
+                            cell.rotation,
+                            json_string(&cell.name),
+                            json_string(value)
+                        )
+                    })
+                    .collect(),
+                ..Default::default()
+            }
+            .located(location, location_start(location)),
+            Self::ConstraintPoisoned { constraint } => Fields {
+                gate: Some(json_named(constraint.gate.index, &constraint.gate.name)),
+                constraint: Some(json_named(constraint.index, &constraint.name)),
+                ..Default::default()
+            },
+            Self::Lookup {
+                name,
+                lookup_index,
+                location,
+            } => Fields {
+                lookup: Some(json_named(*lookup_index, name)),
+                ..Default::default()
+            }
+            .located(location, location_start(location)),
+            Self::Shuffle {
+                name,
+                shuffle_index,
+                location,
+            } => Fields {
+                shuffle: Some(json_named(*shuffle_index, name)),
+                ..Default::default()
+            }
+            .located(location, location_start(location)),
+            Self::Permutation { column, location } => Fields {
+                column: Some(json_column(*column, annotation(location, *column))),
+                ..Default::default()
+            }
+            .located(location, location_start(location)),
+        };
+
+        let mut out = String::new();
+        write!(
+            out,
+            r#"{{"kind":"{}","message":{},"gate":{},"constraint":{},"lookup":{},"shuffle":{},"region":{},"gate_offset":{},"offset":{},"row":{},"column":{},"cells":[{}]}}"#,
+            self.kind(),
+            json_string(self.to_string().trim_end()),
+            or_null(fields.gate),
+            or_null(fields.constraint),
+            or_null(fields.lookup),
+            or_null(fields.shuffle),
+            or_null(fields.region),
+            or_null(fields.gate_offset.map(|o| o.to_string())),
+            or_null(fields.offset.map(|o| o.to_string())),
+            or_null(fields.row.map(|r| r.to_string())),
+            or_null(fields.column),
+            fields.cells.join(","),
+        )
+        .unwrap();
+        out
+    }
+}
+
+impl<F: FromUniformBytes<64> + Ord> MockProver<F> {
+    /// Runs [`MockProver::verify`] and returns the failures as a JSON list, in the schema
+    /// of [`VerifyFailure::to_json`], with the absolute rows of failures within regions.
+    ///
+    /// The list is empty if the circuit is satisfied.
+    pub fn verify_to_json(&self) -> String {
+        let failures = self.verify().err().unwrap_or_default();
+        format!(
+            "[{}]",
+            failures
+                .iter()
+                .map(|failure| failure.to_json_with(&self.regions))
+                .collect::<Vec<_>>()
+                .join(",")
+        )
+    }
+}
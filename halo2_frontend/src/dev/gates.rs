@@ -1,12 +1,13 @@
 use std::{
-    collections::BTreeSet,
+    collections::{BTreeMap, BTreeSet},
     fmt::{self, Write},
 };
 
-use halo2_middleware::ff::PrimeField;
+use halo2_middleware::circuit::{Advice, Any};
+use halo2_middleware::ff::{Field, PrimeField};
 
 use crate::dev::util;
-use halo2_common::plonk::{sealed::SealedPhase, Circuit, ConstraintSystem, FirstPhase};
+use halo2_common::plonk::{sealed::SealedPhase, Circuit, ConstraintSystem, Expression, FirstPhase};
 
 #[derive(Debug)]
 struct Constraint {
@@ -21,6 +22,194 @@ struct Gate {
     constraints: Vec<Constraint>,
 }
 
+/// The columns queried by some expressions, with their rotations, and the selectors used
+/// by the expressions.
+#[derive(Debug, Default)]
+pub(super) struct Queries {
+    /// The rotations at which each column is queried, indexed by column label (see
+    /// [`column_label`]).
+    pub(super) columns: BTreeMap<String, BTreeSet<i32>>,
+    pub(super) challenges: BTreeSet<String>,
+    /// Whether each selector is simple, indexed by selector index.
+    pub(super) selectors: BTreeMap<usize, bool>,
+}
+
+/// Returns the label of a column, e.g. `A0` for the first advice column, or `A1(1)` for
+/// an advice column in the second phase.
+pub(super) fn column_label(column_type: Any, index: usize) -> String {
+    match column_type {
+        Any::Advice(Advice { phase: 0 }) => format!("A{index}"),
+        Any::Advice(Advice { phase }) => format!("A{index}({phase})"),
+        Any::Fixed => format!("F{index}"),
+        Any::Instance => format!("I{index}"),
+    }
+}
+
+impl Queries {
+    pub(super) fn add<F: Field>(&mut self, expression: &Expression<F>) {
+        match expression {
+            Expression::Constant(_) => (),
+            Expression::Selector(selector) => {
+                self.selectors
+                    .insert(selector.index(), selector.is_simple());
+            }
+            Expression::Fixed(query) => self.add_column(
+                column_label(Any::Fixed, query.column_index),
+                query.rotation.0,
+            ),
+            Expression::Advice(query) => self.add_column(
+                column_label(Any::Advice(Advice::new(query.phase())), query.column_index),
+                query.rotation.0,
+            ),
+            Expression::Instance(query) => self.add_column(
+                column_label(Any::Instance, query.column_index),
+                query.rotation.0,
+            ),
+            Expression::Challenge(challenge) => {
+                self.challenges
+                    .insert(format!("C{}({})", challenge.index(), challenge.phase()));
+            }
+            Expression::Negated(a) | Expression::Scaled(a, _) => self.add(a),
+            Expression::Sum(a, b) | Expression::Product(a, b) => {
+                self.add(a);
+                self.add(b);
+            }
+        }
+    }
+
+    fn add_column(&mut self, column: String, rotation: i32) {
+        self.columns.entry(column).or_default().insert(rotation);
+    }
+
+    /// Returns the largest difference between two rotations at which a column is queried.
+    fn rotation_spread(&self) -> i32 {
+        self.columns
+            .values()
+            .map(|rotations| rotations.last().unwrap() - rotations.first().unwrap())
+            .max()
+            .unwrap_or(0)
+    }
+
+    fn queries(&self) -> String {
+        self.columns
+            .iter()
+            .flat_map(|(column, rotations)| {
+                rotations
+                    .iter()
+                    .map(move |rotation| format!("{column}@{rotation}"))
+            })
+            .chain(self.challenges.iter().cloned())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    fn selectors(&self) -> String {
+        self.selectors
+            .iter()
+            .map(|(index, simple)| {
+                format!(
+                    "S{} ({})",
+                    index,
+                    if *simple { "simple" } else { "complex" }
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+/// A row of the table produced by [`CircuitGates::table`] and [`CircuitGates::to_csv`].
+#[derive(Debug)]
+struct Row {
+    kind: &'static str,
+    name: String,
+    constraint: String,
+    degree: usize,
+    selectors: String,
+    queries: String,
+    spread: i32,
+}
+
+impl Row {
+    const HEADER: [&'static str; 7] = [
+        "kind",
+        "name",
+        "constraint",
+        "degree",
+        "selector",
+        "queries",
+        "spread",
+    ];
+
+    fn new<'a, F: Field>(
+        kind: &'static str,
+        name: &str,
+        constraint: String,
+        expressions: impl IntoIterator<Item = &'a Expression<F>>,
+        spread: Option<i32>,
+    ) -> Self {
+        let mut queries = Queries::default();
+        let mut degree = 0;
+        for expression in expressions {
+            queries.add(expression);
+            degree = degree.max(expression.degree());
+        }
+        Row {
+            kind,
+            name: name.to_string(),
+            constraint,
+            degree,
+            selectors: queries.selectors(),
+            queries: queries.queries(),
+            spread: spread.unwrap_or_else(|| queries.rotation_spread()),
+        }
+    }
+
+    fn cells(&self) -> [String; 7] {
+        [
+            self.kind.to_string(),
+            self.name.clone(),
+            self.constraint.clone(),
+            self.degree.to_string(),
+            self.selectors.clone(),
+            self.queries.clone(),
+            self.spread.to_string(),
+        ]
+    }
+}
+
+/// The aligned table returned by [`CircuitGates::table`].
+struct Table<'a>(&'a [Row]);
+
+impl fmt::Display for Table<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let rows: Vec<_> = self.0.iter().map(Row::cells).collect();
+        let mut widths = Row::HEADER.map(str::len);
+        for row in &rows {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell.chars().count());
+            }
+        }
+
+        let line = |cells: &[String]| {
+            cells
+                .iter()
+                .zip(widths)
+                .map(|(cell, width)| format!("{cell:<width$}"))
+                .collect::<Vec<_>>()
+                .join(" | ")
+                .trim_end()
+                .to_string()
+        };
+        writeln!(f, "{}", line(&Row::HEADER.map(String::from)))?;
+        writeln!(f, "{}", widths.map(|width| "-".repeat(width)).join("-+-"))?;
+        for row in &rows {
+            writeln!(f, "{}", line(row))?;
+        }
+        Ok(())
+    }
+}
+
 /// A struct for collecting and displaying the gates within a circuit.
 ///
 /// # Examples
@@ -98,6 +287,7 @@ struct Gate {
 #[derive(Debug)]
 pub struct CircuitGates {
     gates: Vec<Gate>,
+    rows: Vec<Row>,
     total_negations: usize,
     total_additions: usize,
     total_multiplications: usize,
@@ -219,6 +409,61 @@ impl CircuitGates {
             })
             .collect();
 
+        let mut rows = vec![];
+        for gate in &cs.gates {
+            // The rotation spread of a gate is shared by all of its constraints.
+            let mut gate_queries = Queries::default();
+            for polynomial in gate.polynomials() {
+                gate_queries.add(polynomial);
+            }
+            let spread = gate_queries.rotation_spread();
+            for (i, polynomial) in gate.polynomials().iter().enumerate() {
+                let constraint = match gate.constraint_name(i) {
+                    "" => i.to_string(),
+                    name => name.to_string(),
+                };
+                rows.push(Row::new(
+                    "gate",
+                    gate.name(),
+                    constraint,
+                    Some(polynomial),
+                    Some(spread),
+                ));
+            }
+        }
+        for lookup in &cs.lookups {
+            rows.push(Row::new(
+                "lookup",
+                lookup.name(),
+                "input".to_string(),
+                lookup.input_expressions(),
+                None,
+            ));
+            rows.push(Row::new(
+                "lookup",
+                lookup.name(),
+                "table".to_string(),
+                lookup.table_expressions(),
+                None,
+            ));
+        }
+        for shuffle in &cs.shuffles {
+            rows.push(Row::new(
+                "shuffle",
+                shuffle.name(),
+                "input".to_string(),
+                shuffle.input_expressions(),
+                None,
+            ));
+            rows.push(Row::new(
+                "shuffle",
+                shuffle.name(),
+                "shuffle".to_string(),
+                shuffle.shuffle_expressions(),
+                None,
+            ));
+        }
+
         let (total_negations, total_additions, total_multiplications) = cs
             .gates
             .iter()
@@ -244,12 +489,45 @@ impl CircuitGates {
 
         CircuitGates {
             gates,
+            rows,
             total_negations,
             total_additions,
             total_multiplications,
         }
     }
 
+    /// Returns an aligned table listing, for every gate constraint and for both sides of
+    /// every lookup and shuffle argument:
+    /// - its degree;
+    /// - the selectors it uses, and whether they are simple or complex;
+    /// - the columns it queries, with their rotations;
+    /// - the rotation spread, i.e. the largest difference between two rotations at which
+    ///   a column is queried. For gates, this is computed over all of their constraints.
+    ///
+    /// The rotation spread determines the size of the extended domain that is required to
+    /// evaluate the constraints.
+    pub fn table(&self) -> impl fmt::Display + '_ {
+        Table(&self.rows)
+    }
+
+    /// Exports the rows of [`CircuitGates::table`] as CSV.
+    pub fn to_csv(&self) -> String {
+        let escape = |cell: String| {
+            if cell.contains(|c| matches!(c, ',' | '"' | '\n')) {
+                format!("\"{}\"", cell.replace('"', "\"\""))
+            } else {
+                cell
+            }
+        };
+
+        let mut ret = String::new();
+        writeln!(ret, "{}", Row::HEADER.join(",")).unwrap();
+        for row in &self.rows {
+            writeln!(ret, "{}", row.cells().map(escape).join(",")).unwrap();
+        }
+        ret
+    }
+
     /// Prints the queries in this circuit to a CSV grid.
     pub fn queries_to_csv(&self) -> String {
         let mut queries = BTreeSet::new();
@@ -312,3 +590,78 @@ impl fmt::Display for CircuitGates {
         writeln!(f, "Total multiplications: {}", self.total_multiplications)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use halo2_middleware::poly::Rotation;
+    use halo2curves::pasta::Fp;
+
+    use super::*;
+    use crate::circuit::{Layouter, SimpleFloorPlanner};
+    use halo2_common::plonk::Error;
+
+    struct MyCircuit;
+
+    impl Circuit<Fp> for MyCircuit {
+        type Config = ();
+        type FloorPlanner = SimpleFloorPlanner;
+        #[cfg(feature = "circuit-params")]
+        type Params = ();
+
+        fn without_witnesses(&self) -> Self {
+            Self
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) {
+            let a = meta.advice_column();
+            let b = meta.advice_column();
+            let s = meta.selector();
+            let q = meta.complex_selector();
+            let table = meta.lookup_table_column();
+
+            meta.create_gate("add", |meta| {
+                let prev = meta.query_advice(a, Rotation::prev());
+                let b = meta.query_advice(b, Rotation::cur());
+                let next = meta.query_advice(a, Rotation::next());
+                let s = meta.query_selector(s);
+                Some(("sum", s * (prev + b - next)))
+            });
+
+            meta.lookup("range", |meta| {
+                let a = meta.query_advice(a, Rotation::cur());
+                let q = meta.query_selector(q);
+                vec![(q * a, table)]
+            });
+        }
+
+        fn synthesize(&self, _: (), _: impl Layouter<Fp>) -> Result<(), Error> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn gates_table() {
+        #[cfg(feature = "circuit-params")]
+        let gates = CircuitGates::collect::<Fp, MyCircuit>(());
+        #[cfg(not(feature = "circuit-params"))]
+        let gates = CircuitGates::collect::<Fp, MyCircuit>();
+
+        assert_eq!(
+            gates.table().to_string(),
+            r#####"kind   | name  | constraint | degree | selector     | queries         | spread
+-------+-------+------------+--------+--------------+-----------------+-------
+gate   | add   | sum        | 2      | S0 (simple)  | A0@-1 A0@1 A1@0 | 2
+lookup | range | input      | 2      | S1 (complex) | A0@0            | 0
+lookup | range | table      | 1      |              | F0@0            | 0
+"#####,
+        );
+        assert_eq!(
+            gates.to_csv(),
+            r#####"kind,name,constraint,degree,selector,queries,spread
+gate,add,sum,2,S0 (simple),A0@-1 A0@1 A1@0,2
+lookup,range,input,2,S1 (complex),A0@0,0
+lookup,range,table,1,,F0@0,0
+"#####,
+        );
+    }
+}
@@ -1,11 +1,15 @@
+use std::fmt::Write;
+
 use halo2_common::plonk::{
     circuit::{Circuit, Column},
-    Assigned, Assignment, Challenge, ConstraintSystem, Error, FloorPlanner, Selector,
+    Assigned, Assignment, Challenge, ConstraintSystem, Error, Expression, FloorPlanner, Selector,
 };
 use halo2_middleware::circuit::{Advice, Any, Fixed, Instance};
 use halo2_middleware::ff::Field;
+use halo2_middleware::metadata;
 use tabbycat::{AttrList, Edge, GraphBuilder, GraphType, Identity, StmtList};
 
+use super::gates::{column_label, Queries};
 use crate::circuit::Value;
 
 pub mod layout;
@@ -67,6 +71,164 @@ pub fn circuit_dot_graph<F: Field, ConcreteCircuit: Circuit<F>>(
         .to_string()
 }
 
+/// Builds a dot graph string representing the structure of the given circuit's
+/// constraint system.
+///
+/// The nodes of the graph are:
+/// - the columns, as boxes colored by column type (and by phase for advice columns), and
+///   labeled with their annotations when they have one;
+/// - the selectors, as diamonds;
+/// - the gates, as ellipses;
+/// - the lookup and shuffle arguments, as hexagons.
+///
+/// Each gate, lookup and shuffle has an edge to every column it queries, labeled with the
+/// rotations at which the column is queried, and each selector has an edge to every gate,
+/// lookup and shuffle that uses it.
+///
+/// Only the circuit's configuration is used; the circuit is not synthesized. The output
+/// is deterministic, so that it can be committed and diffed.
+pub fn circuit_dot_graph_detailed<F: Field, ConcreteCircuit: Circuit<F>>(
+    #[allow(unused_variables)] circuit: &ConcreteCircuit,
+) -> String {
+    let mut cs = ConstraintSystem::default();
+    #[cfg(feature = "circuit-params")]
+    let _ = ConcreteCircuit::configure_with_params(&mut cs, circuit.params());
+    #[cfg(not(feature = "circuit-params"))]
+    let _ = ConcreteCircuit::configure(&mut cs);
+
+    let escape = |s: &str| s.replace('\\', "\\\\").replace('"', "\\\"");
+
+    // The nodes using the columns and selectors, with the queries of their expressions.
+    let mut users = vec![];
+    for (i, gate) in cs.gates.iter().enumerate() {
+        users.push((
+            format!("gate{i}"),
+            gate.name().to_string(),
+            "ellipse",
+            queries(gate.polynomials()),
+        ));
+    }
+    for (i, lookup) in cs.lookups.iter().enumerate() {
+        users.push((
+            format!("lookup{i}"),
+            format!("lookup: {}", lookup.name()),
+            "hexagon",
+            queries(
+                lookup
+                    .input_expressions()
+                    .iter()
+                    .chain(lookup.table_expressions()),
+            ),
+        ));
+    }
+    for (i, shuffle) in cs.shuffles.iter().enumerate() {
+        users.push((
+            format!("shuffle{i}"),
+            format!("shuffle: {}", shuffle.name()),
+            "hexagon",
+            queries(
+                shuffle
+                    .input_expressions()
+                    .iter()
+                    .chain(shuffle.shuffle_expressions()),
+            ),
+        ));
+    }
+
+    let mut dot = String::new();
+    writeln!(dot, "digraph circuit {{").unwrap();
+
+    // Columns.
+    let columns = (0..cs.num_advice_columns)
+        .map(|index| {
+            (
+                Any::Advice(Advice::new(cs.advice_column_phase[index].0)),
+                index,
+            )
+        })
+        .chain((0..cs.num_fixed_columns).map(|index| (Any::Fixed, index)))
+        .chain((0..cs.num_instance_columns).map(|index| (Any::Instance, index)));
+    for (column_type, index) in columns {
+        let id = column_label(column_type, index);
+        let label = cs
+            .general_column_annotations
+            .get(&metadata::Column::from((column_type, index)))
+            .cloned()
+            .unwrap_or_else(|| id.clone());
+        let color = match column_type {
+            Any::Advice(Advice { phase }) => {
+                const PHASE_COLORS: [&str; 3] = ["lightblue", "lightgreen", "khaki"];
+                PHASE_COLORS[phase as usize % PHASE_COLORS.len()]
+            }
+            Any::Fixed => "lightgray",
+            Any::Instance => "salmon",
+        };
+        writeln!(
+            dot,
+            "  \"{id}\" [label=\"{}\", shape=box, style=filled, fillcolor={color}];",
+            escape(&label)
+        )
+        .unwrap();
+    }
+
+    // Selectors. Whether a selector is simple is only known from its queries.
+    let mut selectors = vec![None; cs.num_selectors];
+    for (_, _, _, queries) in &users {
+        for (index, simple) in &queries.selectors {
+            selectors[*index] = Some(*simple);
+        }
+    }
+    for (index, simple) in selectors.into_iter().enumerate() {
+        let kind = match simple {
+            Some(true) => " (simple)",
+            Some(false) => " (complex)",
+            None => "",
+        };
+        writeln!(
+            dot,
+            "  \"S{index}\" [label=\"S{index}{kind}\", shape=diamond];"
+        )
+        .unwrap();
+    }
+
+    // Gates, lookups and shuffles.
+    for (id, label, shape, _) in &users {
+        writeln!(
+            dot,
+            "  \"{id}\" [label=\"{}\", shape={shape}];",
+            escape(label)
+        )
+        .unwrap();
+    }
+
+    // Edges.
+    for (id, _, _, queries) in &users {
+        for index in queries.selectors.keys() {
+            writeln!(dot, "  \"S{index}\" -> \"{id}\";").unwrap();
+        }
+        for (column, rotations) in &queries.columns {
+            let rotations: Vec<_> = rotations.iter().map(|r| r.to_string()).collect();
+            writeln!(
+                dot,
+                "  \"{id}\" -> \"{column}\" [label=\"{}\"];",
+                rotations.join(", ")
+            )
+            .unwrap();
+        }
+    }
+
+    writeln!(dot, "}}").unwrap();
+    dot
+}
+
+fn queries<'a, F: Field + 'a>(expressions: impl IntoIterator<Item = &'a Expression<F>>) -> Queries {
+    let mut queries = Queries::default();
+    for expression in expressions {
+        queries.add(expression);
+    }
+    queries
+}
+
 #[derive(Default)]
 struct Graph {
     /// Graph nodes in the namespace, structured as `(name, gadget_name)`.
@@ -167,10 +329,24 @@ impl<F: Field> Assignment<F> for Graph {
         Ok(())
     }
 
+    fn fill_advice_from_row(
+        &mut self,
+        _: Column<Advice>,
+        _: usize,
+        _: Value<Assigned<F>>,
+    ) -> Result<(), Error> {
+        Ok(())
+    }
+
     fn get_challenge(&self, _: Challenge) -> Value<F> {
         Value::unknown()
     }
 
+    fn current_phase(&self) -> u8 {
+        // The circuit is synthesized once, so all phases must be included.
+        u8::MAX
+    }
+
     fn push_namespace<NR, N>(&mut self, name_fn: N)
     where
         NR: Into<String>,
@@ -201,3 +377,78 @@ impl<F: Field> Assignment<F> for Graph {
         self.current_namespace.pop();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use halo2_middleware::poly::Rotation;
+    use halo2curves::pasta::Fp;
+
+    use super::*;
+    use crate::circuit::{Layouter, SimpleFloorPlanner};
+
+    struct MyCircuit;
+
+    impl Circuit<Fp> for MyCircuit {
+        type Config = ();
+        type FloorPlanner = SimpleFloorPlanner;
+        #[cfg(feature = "circuit-params")]
+        type Params = ();
+
+        fn without_witnesses(&self) -> Self {
+            Self
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) {
+            let a = meta.advice_column();
+            let b = meta.advice_column();
+            let _ = meta.instance_column();
+            let s = meta.selector();
+            let q = meta.complex_selector();
+            let table = meta.lookup_table_column();
+            meta.annotate_lookup_any_column(a, || "a");
+            meta.annotate_lookup_column(table, || "range table");
+
+            meta.create_gate("add", |meta| {
+                let prev = meta.query_advice(a, Rotation::prev());
+                let b = meta.query_advice(b, Rotation::cur());
+                let next = meta.query_advice(a, Rotation::next());
+                let s = meta.query_selector(s);
+                Some(s * (prev + b - next))
+            });
+
+            meta.lookup("range", |meta| {
+                let a = meta.query_advice(a, Rotation::cur());
+                let q = meta.query_selector(q);
+                vec![(q * a, table)]
+            });
+        }
+
+        fn synthesize(&self, _: (), _: impl Layouter<Fp>) -> Result<(), Error> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn detailed_dot_graph() {
+        assert_eq!(
+            circuit_dot_graph_detailed(&MyCircuit),
+            r#####"digraph circuit {
+  "A0" [label="a", shape=box, style=filled, fillcolor=lightblue];
+  "A1" [label="A1", shape=box, style=filled, fillcolor=lightblue];
+  "F0" [label="range table", shape=box, style=filled, fillcolor=lightgray];
+  "I0" [label="I0", shape=box, style=filled, fillcolor=salmon];
+  "S0" [label="S0 (simple)", shape=diamond];
+  "S1" [label="S1 (complex)", shape=diamond];
+  "gate0" [label="add", shape=ellipse];
+  "lookup0" [label="lookup: range", shape=hexagon];
+  "S0" -> "gate0";
+  "gate0" -> "A0" [label="-1, 1"];
+  "gate0" -> "A1" [label="0"];
+  "S1" -> "lookup0";
+  "lookup0" -> "A0" [label="0"];
+  "lookup0" -> "F0" [label="0"];
+}
+"#####
+        );
+    }
+}
@@ -0,0 +1,62 @@
+use tracing::{debug, info};
+
+use halo2_common::plonk::AssignmentHooks;
+
+/// [`AssignmentHooks`] that report the progress of synthesis with [`tracing`] events.
+///
+/// The start of each phase is reported at the `info` level, and each finished region at
+/// the `debug` level, along with the number of rows it spans and of cells assigned in it.
+#[derive(Debug, Default)]
+pub struct LoggingHooks {
+    regions: usize,
+    cells: usize,
+    region_cells: usize,
+}
+
+impl LoggingHooks {
+    /// Creates a new `LoggingHooks`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of regions finished in the current phase.
+    pub fn regions(&self) -> usize {
+        self.regions
+    }
+
+    /// Returns the number of cells assigned in the current phase.
+    pub fn cells(&self) -> usize {
+        self.cells
+    }
+}
+
+impl AssignmentHooks for LoggingHooks {
+    fn phase_started(&mut self, phase: u8) {
+        info!(target: "synthesis", phase = phase, "Started phase");
+        self.regions = 0;
+        self.cells = 0;
+    }
+
+    fn region_started(&mut self, name: &str) {
+        debug!(target: "synthesis", name = name, "Entered region");
+        self.region_cells = 0;
+    }
+
+    fn region_finished(&mut self, name: &str, rows: usize) {
+        self.regions += 1;
+        debug!(
+            target: "synthesis",
+            name = name,
+            rows = rows,
+            cells = self.region_cells,
+            total_regions = self.regions,
+            total_cells = self.cells,
+            "Exited region"
+        );
+    }
+
+    fn cells_assigned(&mut self, n: usize) {
+        self.cells += n;
+        self.region_cells += n;
+    }
+}
@@ -0,0 +1,590 @@
+//! Developer tools for finding common soundness issues in a constraint system.
+
+use std::fmt;
+
+use halo2_common::plonk::{
+    circuit::{Challenge, Column},
+    ConstraintSystem, Expression,
+};
+use halo2_middleware::circuit::{Fixed, Instance};
+use halo2_middleware::ff::Field;
+use halo2_middleware::poly::Rotation;
+
+/// A potential soundness issue in a constraint system, found by [`lint`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LintWarning {
+    /// A table expression of a lookup queries a fixed column that was not allocated with
+    /// [`ConstraintSystem::lookup_table_column`].
+    ///
+    /// Such a column is not filled with a default value by `Layouter::assign_table`, so
+    /// its unassigned rows are zero, which makes zero a valid table entry.
+    UnmanagedTableColumn {
+        /// The name of the lookup.
+        lookup: String,
+        /// The fixed column queried by the table expressions.
+        column: Column<Fixed>,
+    },
+    /// The input expressions of a lookup do not query any selector or fixed column, so
+    /// the lookup is enforced on every row, including those outside of the regions that
+    /// use it.
+    UngatedLookupInput {
+        /// The name of the lookup.
+        lookup: String,
+    },
+    /// A shuffle has a different number of input and shuffle expressions.
+    ShuffleLengthMismatch {
+        /// The name of the shuffle.
+        shuffle: String,
+        /// The number of input expressions.
+        inputs: usize,
+        /// The number of shuffle expressions.
+        shuffles: usize,
+    },
+    /// An instance column is queried at a non-zero rotation, so the constraint depends on
+    /// the position of the public inputs relative to the rows it is enforced on.
+    RotatedInstanceQuery {
+        /// The name of the gate, lookup or shuffle that queries the column.
+        name: String,
+        /// The instance column.
+        column: Column<Instance>,
+        /// The rotation of the query.
+        rotation: Rotation,
+    },
+    /// A gate constraint uses a challenge, but only queries advice columns of the phase
+    /// after which the challenge is squeezed, or of earlier phases.
+    ///
+    /// The values of these columns are assigned before the challenge is known, so
+    /// synthesis sees `Value::unknown()` for it and can't satisfy the constraint, unless
+    /// the constraint holds whatever the challenge.
+    EarlyChallenge {
+        /// The name of the gate.
+        gate: String,
+        /// The index of the constraint in the gate.
+        constraint: usize,
+        /// The challenge.
+        challenge: Challenge,
+        /// The latest phase of the advice columns queried by the constraint.
+        advice_phase: u8,
+    },
+    /// A gate queries advice columns of more than one phase, and was not allowed with
+    /// [`ConstraintSystem::allow_phase_mix`].
+    ///
+    /// This is only reported for the gates created after
+    /// [`ConstraintSystem::set_strict_phases`], see [`ConstraintSystem::validate`].
+    PhaseMix {
+        /// The name of the gate.
+        gate: String,
+        /// The phases of the advice columns queried by the gate.
+        advice_phases: Vec<u8>,
+        /// The phases of the challenges used by the gate.
+        challenge_phases: Vec<u8>,
+    },
+}
+
+impl LintWarning {
+    /// Returns a stable identifier of the kind of this warning.
+    pub fn id(&self) -> &'static str {
+        match self {
+            Self::UnmanagedTableColumn { .. } => "unmanaged-table-column",
+            Self::UngatedLookupInput { .. } => "ungated-lookup-input",
+            Self::ShuffleLengthMismatch { .. } => "shuffle-length-mismatch",
+            Self::RotatedInstanceQuery { .. } => "rotated-instance-query",
+            Self::EarlyChallenge { .. } => "early-challenge",
+            Self::PhaseMix { .. } => "phase-mix",
+        }
+    }
+
+    /// Returns the name of the gate, lookup or shuffle this warning is about.
+    pub fn name(&self) -> &str {
+        match self {
+            Self::UnmanagedTableColumn { lookup, .. } | Self::UngatedLookupInput { lookup } => {
+                lookup
+            }
+            Self::ShuffleLengthMismatch { shuffle, .. } => shuffle,
+            Self::RotatedInstanceQuery { name, .. } => name,
+            Self::EarlyChallenge { gate, .. } | Self::PhaseMix { gate, .. } => gate,
+        }
+    }
+}
+
+/// Warnings that [`lint_with`] does not report.
+#[derive(Clone, Debug, Default)]
+pub struct LintAllowlist {
+    allowed: Vec<(String, String)>,
+}
+
+impl LintAllowlist {
+    /// Creates an empty allowlist.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Allows the warnings with the identifier `id` (see [`LintWarning::id`]) about the
+    /// gate, lookup or shuffle named `name`.
+    pub fn allow(mut self, id: impl Into<String>, name: impl Into<String>) -> Self {
+        self.allowed.push((id.into(), name.into()));
+        self
+    }
+
+    fn allows(&self, warning: &LintWarning) -> bool {
+        self.allowed
+            .iter()
+            .any(|(id, name)| id == warning.id() && name == warning.name())
+    }
+}
+
+impl fmt::Display for LintWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnmanagedTableColumn { lookup, column } => write!(
+                f,
+                "Lookup '{lookup}' has a table that queries fixed column {}, which is not a \
+                 TableColumn: its unassigned rows are zero, which is then a valid table entry",
+                column.index()
+            )?,
+            Self::UngatedLookupInput { lookup } => write!(
+                f,
+                "Lookup '{lookup}' has inputs that are not gated by any selector or fixed \
+                 column, so it is enforced on every row"
+            )?,
+            Self::ShuffleLengthMismatch {
+                shuffle,
+                inputs,
+                shuffles,
+            } => write!(
+                f,
+                "Shuffle '{shuffle}' has {inputs} input expressions but {shuffles} shuffle \
+                 expressions"
+            )?,
+            Self::RotatedInstanceQuery {
+                name,
+                column,
+                rotation,
+            } => write!(
+                f,
+                "'{name}' queries instance column {} at rotation {}, which depends on the \
+                 position of the public inputs",
+                column.index(),
+                rotation.0
+            )?,
+            Self::EarlyChallenge {
+                gate,
+                constraint,
+                challenge,
+                advice_phase,
+            } => write!(
+                f,
+                "Constraint {constraint} of gate '{gate}' uses challenge {}, which is squeezed \
+                 after phase {}, but only queries advice columns up to phase {advice_phase}, \
+                 whose values are assigned before the challenge is known",
+                challenge.index(),
+                challenge.phase()
+            )?,
+            Self::PhaseMix {
+                gate,
+                advice_phases,
+                challenge_phases,
+            } => write!(
+                f,
+                "Gate '{gate}' queries advice columns of phases {advice_phases:?} and uses \
+                 challenges of phases {challenge_phases:?}"
+            )?,
+        }
+        write!(f, " [lint: {}]", self.id())
+    }
+}
+
+/// The columns queried by an expression.
+#[derive(Default)]
+struct Queries {
+    selectors: bool,
+    fixed: Vec<(usize, Rotation)>,
+    instance: Vec<(usize, Rotation)>,
+    advice_phases: Vec<u8>,
+    challenges: Vec<Challenge>,
+}
+
+impl Queries {
+    fn of<'a, F: Field>(expressions: impl IntoIterator<Item = &'a Expression<F>>) -> Self {
+        let mut queries = Queries::default();
+        for expression in expressions {
+            queries.collect(expression);
+        }
+        queries
+    }
+
+    fn collect<F: Field>(&mut self, expression: &Expression<F>) {
+        match expression {
+            Expression::Selector(_) => self.selectors = true,
+            Expression::Fixed(query) => self.fixed.push((query.column_index, query.rotation)),
+            Expression::Instance(query) => self.instance.push((query.column_index, query.rotation)),
+            Expression::Negated(a) | Expression::Scaled(a, _) => self.collect(a),
+            Expression::Sum(a, b) | Expression::Product(a, b) => {
+                self.collect(a);
+                self.collect(b);
+            }
+            Expression::Advice(query) => self.advice_phases.push(query.phase()),
+            Expression::Challenge(challenge) => self.challenges.push(*challenge),
+            Expression::Constant(_) => (),
+        }
+    }
+}
+
+/// Checks `cs` for common soundness issues.
+///
+/// This reports:
+/// - lookup tables that query fixed columns not allocated as `TableColumn`s,
+/// - lookups whose inputs are not gated by any selector or fixed column,
+/// - shuffles with a different number of input and shuffle expressions,
+/// - instance columns queried at non-zero rotations,
+/// - gate constraints that use a challenge but no advice column of a later phase, and
+/// - in strict mode (see [`ConstraintSystem::set_strict_phases`]), gates that query advice
+///   columns of more than one phase without being allowed with
+///   [`ConstraintSystem::allow_phase_mix`].
+///
+/// The warnings are heuristics, and some of them may be intended, but a circuit without
+/// warnings can be checked with `assert!(lint(&cs).is_empty())`. `cs` can be built with
+/// `Circuit::configure`, or taken from [`MockProver::cs`]. Intended warnings can be
+/// allowed with [`lint_with`].
+///
+/// [`MockProver::cs`]: crate::dev::MockProver::cs
+pub fn lint<F: Field>(cs: &ConstraintSystem<F>) -> Vec<LintWarning> {
+    lint_with(cs, &LintAllowlist::default())
+}
+
+/// Checks `cs` for common soundness issues like [`lint`], except those in `allowlist`.
+pub fn lint_with<F: Field>(
+    cs: &ConstraintSystem<F>,
+    allowlist: &LintAllowlist,
+) -> Vec<LintWarning> {
+    let mut warnings = vec![];
+
+    for lookup in &cs.lookups {
+        let table = Queries::of(&lookup.table_expressions);
+        let mut columns: Vec<_> = table.fixed.iter().map(|(index, _)| *index).collect();
+        columns.sort_unstable();
+        columns.dedup();
+        for index in columns {
+            let managed = cs.table_columns.iter().any(|c| c.index() == index)
+                // Selectors that were compressed into fixed columns.
+                || cs.selector_map.iter().any(|c| c.index() == index);
+            if !managed {
+                warnings.push(LintWarning::UnmanagedTableColumn {
+                    lookup: lookup.name.clone(),
+                    column: Column::new(index, Fixed),
+                });
+            }
+        }
+
+        let input = Queries::of(&lookup.input_expressions);
+        if !input.selectors && input.fixed.is_empty() {
+            warnings.push(LintWarning::UngatedLookupInput {
+                lookup: lookup.name.clone(),
+            });
+        }
+    }
+
+    for shuffle in &cs.shuffles {
+        if shuffle.input_expressions.len() != shuffle.shuffle_expressions.len() {
+            warnings.push(LintWarning::ShuffleLengthMismatch {
+                shuffle: shuffle.name.clone(),
+                inputs: shuffle.input_expressions.len(),
+                shuffles: shuffle.shuffle_expressions.len(),
+            });
+        }
+    }
+
+    let named_expressions = cs
+        .gates
+        .iter()
+        .map(|gate| (gate.name(), Queries::of(gate.polynomials())))
+        .chain(cs.lookups.iter().map(|lookup| {
+            (
+                lookup.name.as_str(),
+                Queries::of(
+                    lookup
+                        .input_expressions
+                        .iter()
+                        .chain(&lookup.table_expressions),
+                ),
+            )
+        }))
+        .chain(cs.shuffles.iter().map(|shuffle| {
+            (
+                shuffle.name.as_str(),
+                Queries::of(
+                    shuffle
+                        .input_expressions
+                        .iter()
+                        .chain(&shuffle.shuffle_expressions),
+                ),
+            )
+        }));
+    for (name, queries) in named_expressions {
+        let mut instance = queries.instance;
+        instance.sort_unstable();
+        instance.dedup();
+        for (index, rotation) in instance {
+            if rotation.0 != 0 {
+                warnings.push(LintWarning::RotatedInstanceQuery {
+                    name: name.to_string(),
+                    column: Column::new(index, Instance),
+                    rotation,
+                });
+            }
+        }
+    }
+
+    for gate in &cs.gates {
+        for (constraint, poly) in gate.polynomials().iter().enumerate() {
+            let queries = Queries::of([poly]);
+            let Some(advice_phase) = queries.advice_phases.iter().copied().max() else {
+                continue;
+            };
+            let mut challenges = queries.challenges;
+            challenges.sort_unstable_by_key(|challenge| challenge.index());
+            challenges.dedup();
+            for challenge in challenges {
+                if advice_phase <= challenge.phase() {
+                    warnings.push(LintWarning::EarlyChallenge {
+                        gate: gate.name().to_string(),
+                        constraint,
+                        challenge,
+                        advice_phase,
+                    });
+                }
+            }
+        }
+    }
+
+    for mix in cs.unintended_phase_mixes() {
+        warnings.push(LintWarning::PhaseMix {
+            gate: mix.gate.clone(),
+            advice_phases: mix.advice_phases.clone(),
+            challenge_phases: mix.challenge_phases.clone(),
+        });
+    }
+
+    warnings.retain(|warning| !allowlist.allows(warning));
+    warnings
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2curves::pasta::Fp;
+
+    use super::*;
+    use halo2_common::plonk::{shuffle, FirstPhase, SecondPhase};
+
+    #[test]
+    fn clean_constraint_system() {
+        let mut cs = ConstraintSystem::<Fp>::default();
+        let a = cs.advice_column();
+        let q = cs.complex_selector();
+        let table = cs.lookup_table_column();
+        cs.lookup("range", |meta| {
+            let a = meta.query_advice(a, Rotation::cur());
+            let q = meta.query_selector(q);
+            vec![(q * a, table)]
+        });
+
+        assert!(lint(&cs).is_empty());
+    }
+
+    #[test]
+    fn unmanaged_table_column() {
+        let mut cs = ConstraintSystem::<Fp>::default();
+        let a = cs.advice_column();
+        let q = cs.complex_selector();
+        let table = cs.fixed_column();
+        cs.lookup_any("range", |meta| {
+            let a = meta.query_advice(a, Rotation::cur());
+            let q = meta.query_selector(q);
+            let table = meta.query_fixed(table, Rotation::cur());
+            vec![(q * a, table)]
+        });
+
+        let warnings = lint(&cs);
+        assert_eq!(
+            warnings,
+            vec![LintWarning::UnmanagedTableColumn {
+                lookup: "range".to_string(),
+                column: table,
+            }]
+        );
+        assert_eq!(
+            warnings[0].to_string(),
+            "Lookup 'range' has a table that queries fixed column 0, which is not a \
+             TableColumn: its unassigned rows are zero, which is then a valid table entry \
+             [lint: unmanaged-table-column]"
+        );
+    }
+
+    #[test]
+    fn ungated_lookup_input() {
+        let mut cs = ConstraintSystem::<Fp>::default();
+        let a = cs.advice_column();
+        let table = cs.lookup_table_column();
+        cs.lookup("range", |meta| {
+            let a = meta.query_advice(a, Rotation::cur());
+            vec![(a, table)]
+        });
+
+        assert_eq!(
+            lint(&cs),
+            vec![LintWarning::UngatedLookupInput {
+                lookup: "range".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn shuffle_length_mismatch() {
+        let mut cs = ConstraintSystem::<Fp>::default();
+        let a = cs.advice_column();
+        let b = cs.advice_column();
+        let q = cs.complex_selector();
+        cs.shuffle("permuted", |meta| {
+            let a = meta.query_advice(a, Rotation::cur());
+            let b = meta.query_advice(b, Rotation::cur());
+            let q = meta.query_selector(q);
+            vec![(q * a, b)]
+        });
+        assert!(lint(&cs).is_empty());
+
+        // A shuffle argument that was not built with `ConstraintSystem::shuffle`.
+        let argument = cs.shuffles[0].clone();
+        cs.shuffles.push(shuffle::Argument {
+            name: "truncated".to_string(),
+            input_expressions: argument.input_expressions,
+            shuffle_expressions: vec![],
+        });
+        assert_eq!(
+            lint(&cs),
+            vec![LintWarning::ShuffleLengthMismatch {
+                shuffle: "truncated".to_string(),
+                inputs: 1,
+                shuffles: 0,
+            }]
+        );
+    }
+
+    #[test]
+    fn rotated_instance_query() {
+        let mut cs = ConstraintSystem::<Fp>::default();
+        let a = cs.advice_column();
+        let instance = cs.instance_column();
+        let s = cs.selector();
+        cs.create_gate("previous input", |meta| {
+            let a = meta.query_advice(a, Rotation::cur());
+            let current = meta.query_instance(instance, Rotation::cur());
+            let previous = meta.query_instance(instance, Rotation::prev());
+            let s = meta.query_selector(s);
+            vec![s * (a - current - previous)]
+        });
+
+        assert_eq!(
+            lint(&cs),
+            vec![LintWarning::RotatedInstanceQuery {
+                name: "previous input".to_string(),
+                column: instance,
+                rotation: Rotation::prev(),
+            }]
+        );
+    }
+
+    #[test]
+    fn early_challenge() {
+        let mut cs = ConstraintSystem::<Fp>::default();
+        let a = cs.advice_column_in(FirstPhase);
+        let b = cs.advice_column_in(FirstPhase);
+        let acc = cs.advice_column_in(SecondPhase);
+        let r = cs.challenge_usable_after(FirstPhase);
+        let s = cs.selector();
+        // A random linear combination of `a` into `acc`, which is assigned after `r` is known.
+        cs.create_gate("accumulate", |meta| {
+            let s = meta.query_selector(s);
+            let a = meta.query_advice(a, Rotation::cur());
+            let acc_prev = meta.query_advice(acc, Rotation::prev());
+            let acc = meta.query_advice(acc, Rotation::cur());
+            let r = meta.query_challenge(r);
+            vec![s * (acc - (acc_prev * r + a))]
+        });
+        assert!(lint(&cs).is_empty());
+
+        // `b` is assigned before `r` is known.
+        cs.create_gate("scale", |meta| {
+            let s = meta.query_selector(s);
+            let a = meta.query_advice(a, Rotation::cur());
+            let b = meta.query_advice(b, Rotation::cur());
+            let r = meta.query_challenge(r);
+            vec![s.clone() * (a.clone() - b.clone()), s * (b - a * r)]
+        });
+        let warnings = lint(&cs);
+        assert_eq!(
+            warnings,
+            vec![LintWarning::EarlyChallenge {
+                gate: "scale".to_string(),
+                constraint: 1,
+                challenge: r,
+                advice_phase: 0,
+            }]
+        );
+        assert_eq!(
+            warnings[0].to_string(),
+            "Constraint 1 of gate 'scale' uses challenge 0, which is squeezed after phase 0, \
+             but only queries advice columns up to phase 0, whose values are assigned before \
+             the challenge is known [lint: early-challenge]"
+        );
+
+        let allowlist = LintAllowlist::new().allow("early-challenge", "scale");
+        assert!(lint_with(&cs, &allowlist).is_empty());
+        let allowlist = LintAllowlist::new().allow("early-challenge", "accumulate");
+        assert_eq!(lint_with(&cs, &allowlist), warnings);
+    }
+
+    #[test]
+    fn phase_mix() {
+        let build = |strict: bool| {
+            let mut cs = ConstraintSystem::<Fp>::default();
+            cs.set_strict_phases(strict);
+            let a = cs.advice_column_in(FirstPhase);
+            let acc = cs.advice_column_in(SecondPhase);
+            let r = cs.challenge_usable_after(FirstPhase);
+            let s = cs.selector();
+            cs.create_gate("accumulate", |meta| {
+                let s = meta.query_selector(s);
+                let a = meta.query_advice(a, Rotation::cur());
+                let acc_prev = meta.query_advice(acc, Rotation::prev());
+                let acc = meta.query_advice(acc, Rotation::cur());
+                let r = meta.query_challenge(r);
+                vec![s * (acc - (acc_prev * r + a))]
+            });
+            cs
+        };
+
+        // Gates mixing phases are only reported in strict mode.
+        assert!(lint(&build(false)).is_empty());
+
+        let mut cs = build(true);
+        let warnings = lint(&cs);
+        assert_eq!(
+            warnings,
+            vec![LintWarning::PhaseMix {
+                gate: "accumulate".to_string(),
+                advice_phases: vec![0, 1],
+                challenge_phases: vec![0],
+            }]
+        );
+        assert_eq!(
+            warnings[0].to_string(),
+            "Gate 'accumulate' queries advice columns of phases [0, 1] and uses challenges of \
+             phases [0] [lint: phase-mix]"
+        );
+
+        let allowlist = LintAllowlist::new().allow("phase-mix", "accumulate");
+        assert!(lint_with(&cs, &allowlist).is_empty());
+        cs.allow_phase_mix("accumulate");
+        assert!(lint(&cs).is_empty());
+    }
+}
@@ -0,0 +1,385 @@
+//! Developer tools for measuring the number of rows used by a circuit.
+
+use std::{cmp, collections::HashMap, ops::Range};
+
+use halo2_middleware::ff::Field;
+
+use halo2_common::{
+    circuit::Value,
+    plonk::{
+        circuit::{Challenge, Column},
+        Assigned, Assignment, Circuit, ConstraintSystem, Error, FloorPlanner, Selector,
+    },
+};
+use halo2_middleware::circuit::{Advice, Any, Fixed, Instance};
+
+/// The rows used by a circuit, as measured by [`CircuitMeasure::run`].
+#[derive(Clone, Debug)]
+pub struct MeasureReport {
+    /// The number of rows used in each column, i.e. one more than the largest row that is
+    /// assigned or constrained in the column.
+    pub columns: HashMap<Column<Any>, usize>,
+    /// The rows between the first and the last row at which each selector is enabled.
+    pub selectors: HashMap<Selector, Range<usize>>,
+    /// The number of equality constraints.
+    pub copies: usize,
+    /// The length of each lookup table column.
+    pub tables: HashMap<Column<Fixed>, usize>,
+    /// The number of rows used by the circuit, over all columns and selectors.
+    pub rows: usize,
+    /// The minimum number of rows of the circuit's constraint system
+    /// (see [`ConstraintSystem::minimum_rows`]).
+    pub minimum_rows: usize,
+    /// The smallest `k` such that `rows + minimum_rows <= 2^k`.
+    pub k: u32,
+}
+
+/// Measures the rows used by a circuit, without requiring `k` up front.
+///
+/// The circuit is synthesized once, with its own floor planner, against an assignment
+/// that discards all values and only records which rows are used. This is much cheaper
+/// than running keygen or [`MockProver`](super::MockProver), which allocate every column
+/// in full.
+#[derive(Debug, Default)]
+pub struct CircuitMeasure {
+    columns: HashMap<Column<Any>, usize>,
+    selectors: HashMap<Selector, Range<usize>>,
+    copies: usize,
+    tables: HashMap<Column<Fixed>, usize>,
+}
+
+impl CircuitMeasure {
+    /// Configures and synthesizes `circuit`, and reports the rows it uses.
+    pub fn run<F: Field, ConcreteCircuit: Circuit<F>>(
+        circuit: &ConcreteCircuit,
+    ) -> Result<MeasureReport, Error> {
+        let mut cs = ConstraintSystem::default();
+        #[cfg(feature = "circuit-params")]
+        let config = ConcreteCircuit::configure_with_params(&mut cs, circuit.params());
+        #[cfg(not(feature = "circuit-params"))]
+        let config = ConcreteCircuit::configure(&mut cs);
+
+        let mut measure = CircuitMeasure::default();
+        ConcreteCircuit::FloorPlanner::synthesize(
+            &mut measure,
+            circuit,
+            config,
+            cs.constants.clone(),
+        )?;
+
+        let rows = measure
+            .columns
+            .values()
+            .copied()
+            .chain(measure.selectors.values().map(|rows| rows.end))
+            .max()
+            .unwrap_or(0);
+        let minimum_rows = cs.minimum_rows();
+        let k = (rows + minimum_rows).next_power_of_two().trailing_zeros();
+
+        Ok(MeasureReport {
+            columns: measure.columns,
+            selectors: measure.selectors,
+            copies: measure.copies,
+            tables: measure.tables,
+            rows,
+            minimum_rows,
+            k,
+        })
+    }
+
+    fn update(&mut self, column: Column<Any>, row: usize) {
+        let rows = self.columns.entry(column).or_default();
+        *rows = cmp::max(*rows, row + 1);
+    }
+
+    fn update_selector(&mut self, selector: &Selector, rows: Range<usize>) {
+        self.selectors
+            .entry(*selector)
+            .and_modify(|extent| {
+                *extent = cmp::min(extent.start, rows.start)..cmp::max(extent.end, rows.end)
+            })
+            .or_insert(rows);
+    }
+}
+
+impl<F: Field> Assignment<F> for CircuitMeasure {
+    fn enter_region<NR, N>(&mut self, _: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+        // Do nothing; we don't care about regions in this context.
+    }
+
+    fn annotate_column<A, AR>(&mut self, _: A, _: Column<Any>)
+    where
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+    }
+
+    fn exit_region(&mut self) {
+        // Do nothing; we don't care about regions in this context.
+    }
+
+    fn enable_selector<A, AR>(&mut self, _: A, selector: &Selector, row: usize) -> Result<(), Error>
+    where
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.update_selector(selector, row..row + 1);
+        Ok(())
+    }
+
+    fn enable_selector_range<A, AR>(
+        &mut self,
+        _: A,
+        selector: &Selector,
+        rows: Range<usize>,
+    ) -> Result<(), Error>
+    where
+        A: Fn() -> AR,
+        AR: Into<String>,
+    {
+        if !rows.is_empty() {
+            self.update_selector(selector, rows);
+        }
+        Ok(())
+    }
+
+    fn query_instance(&self, _: Column<Instance>, _: usize) -> Result<Value<F>, Error> {
+        Ok(Value::unknown())
+    }
+
+    fn assign_advice<V, VR, A, AR>(
+        &mut self,
+        _: A,
+        column: Column<Advice>,
+        row: usize,
+        _: V,
+    ) -> Result<(), Error>
+    where
+        V: FnOnce() -> Value<VR>,
+        VR: Into<Assigned<F>>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.update(column.into(), row);
+        Ok(())
+    }
+
+    fn try_assign_advice<V, VR, A, AR>(
+        &mut self,
+        _: A,
+        column: Column<Advice>,
+        row: usize,
+        _: V,
+    ) -> Result<(), Error>
+    where
+        V: FnOnce() -> Result<Value<VR>, Error>,
+        VR: Into<Assigned<F>>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.update(column.into(), row);
+        Ok(())
+    }
+
+    fn assign_advice_column(
+        &mut self,
+        column: Column<Advice>,
+        start_row: usize,
+        values: &[Value<Assigned<F>>],
+    ) -> Result<(), Error> {
+        if !values.is_empty() {
+            self.update(column.into(), start_row + values.len() - 1);
+        }
+        Ok(())
+    }
+
+    fn assign_fixed<V, VR, A, AR>(
+        &mut self,
+        _: A,
+        column: Column<Fixed>,
+        row: usize,
+        _: V,
+    ) -> Result<(), Error>
+    where
+        V: FnOnce() -> Value<VR>,
+        VR: Into<Assigned<F>>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.update(column.into(), row);
+        Ok(())
+    }
+
+    fn copy(
+        &mut self,
+        left_column: Column<Any>,
+        left_row: usize,
+        right_column: Column<Any>,
+        right_row: usize,
+    ) -> Result<(), Error> {
+        self.update(left_column, left_row);
+        self.update(right_column, right_row);
+        self.copies += 1;
+        Ok(())
+    }
+
+    fn fill_from_row(
+        &mut self,
+        column: Column<Fixed>,
+        row: usize,
+        _: Value<Assigned<F>>,
+    ) -> Result<(), Error> {
+        // Lookup tables are padded from their first unused row.
+        self.tables.insert(column, row);
+        Ok(())
+    }
+
+    fn fill_advice_from_row(
+        &mut self,
+        column: Column<Advice>,
+        from_row: usize,
+        _: Value<Assigned<F>>,
+    ) -> Result<(), Error> {
+        // Padding extends to the last usable row, whatever `k` is; only its first row
+        // is required.
+        self.update(column.into(), from_row);
+        Ok(())
+    }
+
+    fn get_challenge(&self, _: Challenge) -> Value<F> {
+        Value::unknown()
+    }
+
+    fn current_phase(&self) -> u8 {
+        // The circuit is synthesized once, so all phases must be included.
+        u8::MAX
+    }
+
+    fn push_namespace<NR, N>(&mut self, _: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+        // Do nothing; we don't care about namespaces in this context.
+    }
+
+    fn pop_namespace(&mut self, _: Option<String>) {
+        // Do nothing; we don't care about namespaces in this context.
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::panic;
+
+    use halo2curves::pasta::Fp;
+
+    use super::*;
+    use crate::circuit::{Layouter, SimpleFloorPlanner};
+    use crate::dev::MockProver;
+    use halo2_common::plonk::TableColumn;
+    use halo2_middleware::poly::Rotation;
+
+    #[test]
+    fn minimum_k_matches_mock_prover() {
+        const ROWS: usize = 20;
+
+        #[derive(Clone)]
+        struct MyConfig {
+            a: Column<Advice>,
+            s: Selector,
+            table: TableColumn,
+        }
+
+        struct MyCircuit;
+
+        impl Circuit<Fp> for MyCircuit {
+            type Config = MyConfig;
+            type FloorPlanner = SimpleFloorPlanner;
+            #[cfg(feature = "circuit-params")]
+            type Params = ();
+
+            fn without_witnesses(&self) -> Self {
+                Self
+            }
+
+            fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+                let a = meta.advice_column();
+                let s = meta.complex_selector();
+                let table = meta.lookup_table_column();
+                meta.enable_equality(a);
+
+                meta.lookup("a in table", |cells| {
+                    let a = cells.query_advice(a, Rotation::cur());
+                    let s = cells.query_selector(s);
+                    vec![(s * a, table)]
+                });
+
+                MyConfig { a, s, table }
+            }
+
+            fn synthesize(
+                &self,
+                config: Self::Config,
+                mut layouter: impl Layouter<Fp>,
+            ) -> Result<(), Error> {
+                layouter.assign_table(
+                    || "table",
+                    |mut table| {
+                        for i in 0..4 {
+                            table.assign_cell(
+                                || "entry",
+                                config.table,
+                                i,
+                                || Value::known(Fp::from(i as u64)),
+                            )?;
+                        }
+                        Ok(())
+                    },
+                )?;
+
+                layouter.assign_region(
+                    || "values",
+                    |mut region| {
+                        config.s.enable_range(&mut region, 0..ROWS)?;
+                        let cells = (0..ROWS)
+                            .map(|offset| {
+                                region.assign_advice(
+                                    || "a",
+                                    config.a,
+                                    offset,
+                                    || Value::known(Fp::ONE),
+                                )
+                            })
+                            .collect::<Result<Vec<_>, _>>()?;
+                        region.constrain_equal(cells[0].cell(), cells[ROWS - 1].cell())
+                    },
+                )
+            }
+        }
+
+        let report = CircuitMeasure::run(&MyCircuit).unwrap();
+        assert_eq!(report.rows, ROWS);
+        assert_eq!(report.copies, 1);
+        assert_eq!(report.tables.values().copied().collect::<Vec<_>>(), vec![4]);
+        assert_eq!(
+            report.selectors.values().cloned().collect::<Vec<_>>(),
+            vec![0..ROWS]
+        );
+        assert!(ROWS + report.minimum_rows <= 1 << report.k);
+        assert!(ROWS + report.minimum_rows > 1 << (report.k - 1));
+
+        // The circuit fits in `2^k` rows...
+        let prover = MockProver::run(report.k, &MyCircuit, vec![]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+
+        // ...but not in `2^(k - 1)` rows.
+        let result = panic::catch_unwind(|| MockProver::run(report.k - 1, &MyCircuit, vec![]));
+        assert!(!matches!(result, Ok(Ok(_))));
+    }
+}
@@ -48,7 +48,7 @@ impl fmt::Display for DebugColumn {
 /// within a custom gate.
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
 pub struct VirtualCell {
-    name: String,
+    pub(super) name: String,
     pub(super) column: Column,
     pub(super) rotation: i32,
 }
@@ -197,11 +197,24 @@ pub struct Region {
     /// The name of the region. This is specified by the region creator (such as a chip
     /// implementation), and is not enforced to be unique.
     pub(super) name: String,
+    /// The namespaces that were active when the region was entered, outermost first.
+    pub(super) namespace: Vec<String>,
     /// A reference to the annotations of the Columns that exist within this `Region`.
     pub(super) column_annotations: Option<HashMap<ColumnMetadata, String>>,
 }
 
 impl Region {
+    /// Returns the name of the region, prefixed by the namespaces it was entered in and
+    /// separated by `/`, e.g. `sha256/message_schedule/decompose`.
+    pub fn path(&self) -> String {
+        self.namespace
+            .iter()
+            .map(String::as_str)
+            .chain(std::iter::once(self.name.as_str()))
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+
     /// Fetch the annotation of a `Column` within a `Region` providing it's associated metadata.
     ///
     /// This function will return `None` if:
@@ -224,13 +237,13 @@ impl Eq for Region {}
 
 impl Debug for Region {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "Region {} ('{}')", self.index, self.name)
+        write!(f, "Region {} ('{}')", self.index, self.path())
     }
 }
 
 impl fmt::Display for Region {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "Region {} ('{}')", self.index, self.name.as_str())
+        write!(f, "Region {} ('{}')", self.index, self.path())
     }
 }
 
@@ -239,6 +252,7 @@ impl From<(usize, String)> for Region {
         Region {
             index,
             name,
+            namespace: vec![],
             column_annotations: None,
         }
     }
@@ -249,6 +263,7 @@ impl From<(usize, &str)> for Region {
         Region {
             index,
             name: name.to_owned(),
+            namespace: vec![],
             column_annotations: None,
         }
     }
@@ -259,6 +274,7 @@ impl From<(usize, String, HashMap<ColumnMetadata, String>)> for Region {
         Region {
             index,
             name,
+            namespace: vec![],
             column_annotations: Some(annotations),
         }
     }
@@ -269,6 +285,7 @@ impl From<(usize, &str, HashMap<ColumnMetadata, String>)> for Region {
         Region {
             index,
             name: name.to_owned(),
+            namespace: vec![],
             column_annotations: Some(annotations),
         }
     }
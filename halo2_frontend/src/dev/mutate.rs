@@ -0,0 +1,231 @@
+//! Mutation of the assignment collected by a [`MockProver`], for negative testing.
+//!
+//! These functions edit the assignment in place, bypassing the region bookkeeping of
+//! synthesis, so that a test can check that a circuit rejects a tampered witness without
+//! writing a second, malicious circuit. They are meant for testing only.
+
+use halo2_common::plonk::{circuit::Column, Selector};
+use halo2_middleware::circuit::{Advice, Instance};
+use halo2_middleware::ff::Field;
+
+use super::{CellValue, InstanceValue, MockProver};
+
+impl<F: Field> MockProver<F> {
+    /// Sets the advice cell of `column` at the absolute `row` to `value`.
+    ///
+    /// This is for testing only: the cell does not need to have been assigned, and is
+    /// not recorded in any region.
+    pub fn modify_advice(&mut self, column: Column<Advice>, row: usize, value: F) {
+        assert!(row < self.n as usize, "row={}, n={}", row, self.n);
+        self.advice[column.index()][row] = CellValue::Assigned(value);
+    }
+
+    /// Sets the instance cell of `column` at the absolute `row` to `value`.
+    ///
+    /// This is for testing only.
+    pub fn modify_instance(&mut self, column: Column<Instance>, row: usize, value: F) {
+        assert!(row < self.n as usize, "row={}, n={}", row, self.n);
+        self.instance[column.index()][row] = InstanceValue::Assigned(value);
+    }
+
+    /// Removes the equality constraint at `index` in the list of copies of
+    /// [`MockProver::permutation`], shifting the following copies down by one.
+    ///
+    /// This is for testing only.
+    pub fn disable_copy(&mut self, index: usize) {
+        assert!(
+            index < self.permutation.copies.len(),
+            "index={}, copies={}",
+            index,
+            self.permutation.copies.len()
+        );
+        self.permutation.copies.remove(index);
+    }
+
+    /// Enables `selector` at the absolute `row`, outside of any region.
+    ///
+    /// The selectors are compressed again, so the fixed columns and the constraint system
+    /// of the prover are those that the modified selector assignment would produce. This
+    /// is for testing only.
+    pub fn enable_selector_at(&mut self, selector: Selector, row: usize) {
+        assert!(
+            self.usable_rows.contains(&row),
+            "row={} not in usable_rows={:?}, k={}",
+            row,
+            self.usable_rows,
+            self.k,
+        );
+        self.selectors[selector.index()][row] = true;
+        self.compress_selectors();
+    }
+
+    /// Returns the advice cells that were assigned a known value in the usable rows,
+    /// ordered by column, then by row.
+    pub fn assigned_advice_cells(&self) -> Vec<(Column<Advice>, usize)> {
+        self.advice
+            .iter()
+            .enumerate()
+            .flat_map(|(index, column)| {
+                let column_type = Advice::new(self.cs.advice_column_phase[index].0);
+                self.usable_rows
+                    .clone()
+                    .filter(move |row| matches!(column[*row], CellValue::Assigned(_)))
+                    .map(move |row| (Column::new(index, column_type), row))
+            })
+            .collect()
+    }
+
+    /// Adds one to the value of an assigned advice cell, and returns the cell.
+    ///
+    /// The cell is the one at `seed` (modulo the number of cells) in
+    /// [`MockProver::assigned_advice_cells`], so that it can be picked by a property
+    /// test, e.g. from `any::<usize>()`, and shrunk. This is for testing only.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no advice cell was assigned.
+    pub fn flip_advice_cell(&mut self, seed: usize) -> (Column<Advice>, usize) {
+        let cells = self.assigned_advice_cells();
+        assert!(!cells.is_empty(), "no advice cell was assigned");
+        let (column, row) = cells[seed % cells.len()];
+        if let CellValue::Assigned(value) = self.advice[column.index()][row] {
+            self.modify_advice(column, row, value + F::ONE);
+        }
+        (column, row)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_middleware::poly::Rotation;
+    use halo2curves::pasta::Fp;
+    use proptest::prelude::*;
+
+    use super::*;
+    use crate::circuit::{Layouter, SimpleFloorPlanner, Value};
+    use crate::dev::{FailureLocation, VerifyFailure};
+    use halo2_common::plonk::{Circuit, ConstraintSystem, Error, Expression};
+
+    const K: u32 = 5;
+    const STEPS: usize = 8;
+
+    #[derive(Clone)]
+    struct CounterConfig {
+        a: Column<Advice>,
+        instance: Column<Instance>,
+        s: Selector,
+        s_unused: Selector,
+    }
+
+    /// Counts from 0 to `STEPS - 1`, and exposes the last value as a public input.
+    struct CounterCircuit;
+
+    impl Circuit<Fp> for CounterCircuit {
+        type Config = CounterConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+        #[cfg(feature = "circuit-params")]
+        type Params = ();
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let a = meta.advice_column();
+            let instance = meta.instance_column();
+            let s = meta.selector();
+            let s_unused = meta.selector();
+            meta.enable_equality(a);
+            meta.enable_equality(instance);
+
+            meta.create_gate("increment", |meta| {
+                let cur = meta.query_advice(a, Rotation::cur());
+                let next = meta.query_advice(a, Rotation::next());
+                let s = meta.query_selector(s);
+                vec![s * (cur + Expression::Constant(Fp::ONE) - next)]
+            });
+            meta.create_gate("zero", |meta| {
+                let a = meta.query_advice(a, Rotation::cur());
+                let s_unused = meta.query_selector(s_unused);
+                vec![s_unused * a]
+            });
+
+            CounterConfig {
+                a,
+                instance,
+                s,
+                s_unused,
+            }
+        }
+
+        fn without_witnesses(&self) -> Self {
+            Self
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            let last = layouter.assign_region(
+                || "count",
+                |mut region| {
+                    let mut cell = None;
+                    for row in 0..STEPS {
+                        if row + 1 < STEPS {
+                            config.s.enable(&mut region, row)?;
+                        }
+                        let value = Value::known(Fp::from(row as u64));
+                        cell = Some(region.assign_advice(|| "a", config.a, row, || value)?);
+                    }
+                    Ok(cell.unwrap())
+                },
+            )?;
+            layouter.constrain_instance(last.cell(), config.instance, 0)
+        }
+    }
+
+    fn prover() -> MockProver<Fp> {
+        let instance = vec![vec![Fp::from(STEPS as u64 - 1)]];
+        let prover = MockProver::run(K, &CounterCircuit, instance).unwrap();
+        prover.assert_satisfied();
+        prover
+    }
+
+    #[test]
+    fn mutations() {
+        let config = CounterCircuit::configure(&mut ConstraintSystem::default());
+
+        let mut tampered = prover();
+        tampered.modify_instance(config.instance, 0, Fp::from(100));
+        assert!(matches!(
+            &tampered.verify().unwrap_err()[..],
+            [VerifyFailure::Permutation { .. }]
+        ));
+        // Without the copy to the public input, the tampered instance is not checked.
+        assert_eq!(tampered.permutation().copies.len(), 1);
+        tampered.disable_copy(0);
+        assert_eq!(tampered.verify(), Ok(()));
+
+        // Enabling a selector outside of a region recompresses the selectors.
+        let mut tampered = prover();
+        tampered.enable_selector_at(config.s_unused, 3);
+        assert!(matches!(
+            &tampered.verify().unwrap_err()[..],
+            [VerifyFailure::ConstraintNotSatisfied {
+                location: FailureLocation::InRegion { offset: 3, .. },
+                ..
+            }]
+        ));
+        tampered.modify_advice(config.a, 3, Fp::ZERO);
+        assert_eq!(tampered.verify().unwrap_err().len(), 2);
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(32))]
+
+        #[test]
+        fn single_cell_flip_is_rejected(seed in any::<usize>()) {
+            let mut tampered = prover();
+            prop_assert_eq!(tampered.assigned_advice_cells().len(), STEPS);
+            tampered.flip_advice_cell(seed);
+            prop_assert!(tampered.verify().is_err());
+        }
+    }
+}
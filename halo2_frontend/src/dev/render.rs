@@ -0,0 +1,717 @@
+//! Developer tools for exporting the placement of regions, without graphics dependencies.
+
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::fmt::Write;
+use std::ops::Range;
+
+use halo2_common::{
+    circuit::layouter::RegionColumn,
+    plonk::{circuit::Column, Circuit, ConstraintSystem, Error, FloorPlanner},
+};
+use halo2_middleware::circuit::{Advice, Any};
+use halo2_middleware::ff::Field;
+
+use super::cost::{Layout, LayoutRegion};
+use super::gates::column_label;
+
+/// Width of a column lane in the SVG rendering, in pixels.
+const LANE_WIDTH: usize = 48;
+/// Height of a row in the SVG rendering, in pixels.
+const ROW_HEIGHT: usize = 12;
+/// Height of the column headers in the SVG rendering, in pixels.
+const HEADER_HEIGHT: usize = 24;
+
+/// A column of the circuit layout.
+///
+/// Selectors are rendered as their own columns, as they are before selector
+/// compression.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LayoutColumn {
+    /// A concrete column.
+    Column(Column<Any>),
+    /// The selector with the given index.
+    Selector(usize),
+}
+
+impl LayoutColumn {
+    fn label(&self) -> String {
+        match self {
+            LayoutColumn::Column(column) => column_label(*column.column_type(), column.index()),
+            LayoutColumn::Selector(index) => format!("S{index}"),
+        }
+    }
+
+    fn type_name(&self) -> &'static str {
+        match self {
+            LayoutColumn::Column(column) => match column.column_type() {
+                Any::Advice(_) => "advice",
+                Any::Fixed => "fixed",
+                Any::Instance => "instance",
+            },
+            LayoutColumn::Selector(_) => "selector",
+        }
+    }
+
+    fn fill(&self) -> &'static str {
+        match self {
+            LayoutColumn::Column(column) => match column.column_type() {
+                Any::Advice(_) => "#f4cccc",
+                Any::Fixed => "#cfe2f3",
+                Any::Instance => "#ffffff",
+            },
+            LayoutColumn::Selector(_) => "#e1eaf2",
+        }
+    }
+}
+
+/// The rows of a column that a region assigns to.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ColumnExtent {
+    /// The index of the column in [`LayoutJson::columns`].
+    pub column: usize,
+    /// The rows between the first and the last assigned cell of the column.
+    pub rows: Range<usize>,
+    /// The number of cells assigned in the column, which is at most the length of
+    /// `rows`.
+    pub cells: usize,
+}
+
+/// The placement of a region (or a table) in the circuit layout.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RegionPlacement {
+    /// The name of the region. Not required to be unique.
+    pub name: String,
+    /// The row that the region starts at.
+    pub start: usize,
+    /// The number of rows that the region takes up.
+    pub rows: usize,
+    /// The rows assigned in each column of the region, ordered by column.
+    pub columns: Vec<ColumnExtent>,
+}
+
+/// A cell of the circuit layout.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LayoutCell {
+    /// The index of the column in [`LayoutJson::columns`].
+    pub column: usize,
+    /// The row of the cell.
+    pub row: usize,
+}
+
+/// The placement of the regions of a circuit, as computed by its floor planner.
+///
+/// This is returned by [`render_layout`], and can be exported with
+/// [`LayoutJson::to_json`] and [`LayoutJson::to_svg`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LayoutJson {
+    /// Power-of-2 bound on the number of rows in the circuit.
+    pub k: u32,
+    /// The number of rows used by the layout, i.e. the first row after which no cell
+    /// is assigned.
+    pub rows: usize,
+    /// The number of rows that can be assigned, before the blinding rows.
+    pub usable_rows: usize,
+    /// The columns of the layout: instance columns first, then advice, fixed and
+    /// selector columns.
+    pub columns: Vec<LayoutColumn>,
+    /// The regions of the circuit, in the order they were assigned. Regions in which no
+    /// cell is assigned are omitted.
+    pub regions: Vec<RegionPlacement>,
+    /// The lookup tables of the circuit, in the order they were assigned.
+    pub tables: Vec<RegionPlacement>,
+    /// The cells of the constants columns assigned by the floor planner.
+    pub constants: Vec<LayoutCell>,
+    /// The pairs of cells between which there is an equality constraint.
+    pub copies: Vec<(LayoutCell, LayoutCell)>,
+}
+
+/// Runs the floor planner of `circuit` and returns the resulting placement of its
+/// regions, tables, constants and equality constraints.
+///
+/// A table is recognized as a region in which only fixed columns that are used in a
+/// lookup table are assigned.
+///
+/// Returns the error of the floor planner if the circuit cannot be synthesized.
+///
+/// # Examples
+///
+/// ```ignore
+/// use halo2_proofs::dev::render_layout;
+///
+/// let circuit = MyCircuit::default();
+/// let k = 5; // Suitable size for MyCircuit
+/// let layout = render_layout(k, &circuit).unwrap();
+/// std::fs::write("layout.json", layout.to_json()).unwrap();
+/// std::fs::write("layout.svg", layout.to_svg()).unwrap();
+/// ```
+pub fn render_layout<F: Field, ConcreteCircuit: Circuit<F>>(
+    k: u32,
+    circuit: &ConcreteCircuit,
+) -> Result<LayoutJson, Error> {
+    let n = 1 << k;
+    // Collect the layout details.
+    let mut cs = ConstraintSystem::default();
+    #[cfg(feature = "circuit-params")]
+    let config = ConcreteCircuit::configure_with_params(&mut cs, circuit.params());
+    #[cfg(not(feature = "circuit-params"))]
+    let config = ConcreteCircuit::configure(&mut cs);
+    let mut layout = Layout::new(k, n, cs.num_selectors);
+    ConcreteCircuit::FloorPlanner::synthesize(&mut layout, circuit, config, cs.constants.clone())?;
+
+    let columns: Vec<_> = (0..cs.num_instance_columns)
+        .map(|index| Column::new(index, Any::Instance))
+        .chain((0..cs.num_advice_columns).map(|index| {
+            Column::new(
+                index,
+                Any::Advice(Advice::new(cs.advice_column_phase[index].0)),
+            )
+        }))
+        .chain((0..cs.num_fixed_columns).map(|index| Column::new(index, Any::Fixed)))
+        .map(LayoutColumn::Column)
+        .chain((0..cs.num_selectors).map(LayoutColumn::Selector))
+        .collect();
+    let lane = |column: RegionColumn| match column {
+        RegionColumn::Column(column) => {
+            column.index()
+                + match column.column_type() {
+                    Any::Instance => 0,
+                    Any::Advice(_) => cs.num_instance_columns,
+                    Any::Fixed => cs.num_instance_columns + cs.num_advice_columns,
+                }
+        }
+        RegionColumn::Selector(selector) => {
+            cs.num_instance_columns + cs.num_advice_columns + cs.num_fixed_columns + selector.0
+        }
+    };
+    let cell = |column: Column<Any>, row: usize| LayoutCell {
+        column: lane(column.into()),
+        row,
+    };
+
+    // Collect the fixed columns that lookup tables are assigned to.
+    let table_columns: HashSet<usize> = cs
+        .lookups
+        .iter()
+        .flat_map(|lookup| &lookup.table_expressions)
+        .flat_map(|expression| {
+            expression.evaluate(
+                &|_| vec![],
+                &|_| vec![],
+                &|query| vec![query.column_index],
+                &|_| vec![],
+                &|_| vec![],
+                &|_| vec![],
+                &|a| a,
+                &|mut a, b| {
+                    a.extend(b);
+                    a
+                },
+                &|mut a, b| {
+                    a.extend(b);
+                    a
+                },
+                &|a, _| a,
+            )
+        })
+        .collect();
+    let is_table = |region: &LayoutRegion| {
+        region.columns.iter().all(|column| match column {
+            RegionColumn::Column(column) => {
+                *column.column_type() == Any::Fixed && table_columns.contains(&column.index())
+            }
+            RegionColumn::Selector(_) => false,
+        })
+    };
+
+    let mut regions = vec![];
+    let mut tables = vec![];
+    for region in &layout.regions {
+        let Some(start) = region.offset else {
+            continue;
+        };
+        let mut extents = BTreeMap::<usize, (Range<usize>, BTreeSet<usize>)>::new();
+        for (column, row) in &region.cells {
+            let (rows, cells) = extents
+                .entry(lane(*column))
+                .or_insert((*row..row + 1, BTreeSet::new()));
+            rows.start = rows.start.min(*row);
+            rows.end = rows.end.max(row + 1);
+            cells.insert(*row);
+        }
+        let placement = RegionPlacement {
+            name: region.name.clone(),
+            start,
+            rows: region.rows,
+            columns: extents
+                .into_iter()
+                .map(|(column, (rows, cells))| ColumnExtent {
+                    column,
+                    rows,
+                    cells: cells.len(),
+                })
+                .collect(),
+        };
+        if is_table(region) {
+            tables.push(placement);
+        } else {
+            regions.push(placement);
+        }
+    }
+
+    let constants = layout
+        .loose_cells
+        .iter()
+        .filter_map(|(column, row)| match column {
+            RegionColumn::Column(column)
+                if *column.column_type() == Any::Fixed
+                    && cs.constants.iter().any(|c| c.index() == column.index()) =>
+            {
+                Some(cell(*column, *row))
+            }
+            _ => None,
+        })
+        .collect();
+
+    let copies = layout
+        .equality
+        .iter()
+        .map(|(l_col, l_row, r_col, r_row)| (cell(*l_col, *l_row), cell(*r_col, *r_row)))
+        .collect();
+
+    Ok(LayoutJson {
+        k,
+        rows: layout.total_rows,
+        usable_rows: n - (cs.blinding_factors() + 1),
+        columns,
+        regions,
+        tables,
+        constants,
+        copies,
+    })
+}
+
+impl LayoutJson {
+    /// Returns the layout as a JSON document.
+    ///
+    /// The document is an object with the fields of [`LayoutJson`]. Columns are objects
+    /// with a `type` (`"instance"`, `"advice"`, `"fixed"` or `"selector"`), an `index`
+    /// and a `label`, as well as a `phase` for advice columns. Regions and tables are
+    /// objects with a `name`, a `start`, a number of `rows` and per-column `extents`
+    /// (`{"column", "start", "end", "cells"}`, where `end` is exclusive). Cells are objects
+    /// `{"column", "row"}`, where `column` is an index into `columns`. Copies are
+    /// objects `{"from", "to"}` of cells.
+    pub fn to_json(&self) -> String {
+        let mut out = String::new();
+        let list = |out: &mut String, items: Vec<String>| {
+            out.push('[');
+            out.push_str(&items.join(","));
+            out.push(']');
+        };
+        let cell =
+            |cell: &LayoutCell| format!(r#"{{"column":{},"row":{}}}"#, cell.column, cell.row);
+        let placement = |placement: &RegionPlacement| {
+            let extents: Vec<_> = placement
+                .columns
+                .iter()
+                .map(|extent| {
+                    format!(
+                        r#"{{"column":{},"start":{},"end":{},"cells":{}}}"#,
+                        extent.column, extent.rows.start, extent.rows.end, extent.cells
+                    )
+                })
+                .collect();
+            format!(
+                r#"{{"name":{},"start":{},"rows":{},"extents":[{}]}}"#,
+                json_string(&placement.name),
+                placement.start,
+                placement.rows,
+                extents.join(",")
+            )
+        };
+
+        write!(
+            out,
+            r#"{{"k":{},"rows":{},"usable_rows":{},"columns":"#,
+            self.k, self.rows, self.usable_rows
+        )
+        .unwrap();
+        list(
+            &mut out,
+            self.columns
+                .iter()
+                .map(|column| {
+                    let (index, phase) = match column {
+                        LayoutColumn::Column(column) => match column.column_type() {
+                            Any::Advice(advice) => {
+                                (column.index(), format!(r#","phase":{}"#, advice.phase))
+                            }
+                            _ => (column.index(), String::new()),
+                        },
+                        LayoutColumn::Selector(index) => (*index, String::new()),
+                    };
+                    format!(
+                        r#"{{"type":"{}","index":{}{},"label":{}}}"#,
+                        column.type_name(),
+                        index,
+                        phase,
+                        json_string(&column.label())
+                    )
+                })
+                .collect(),
+        );
+        out.push_str(r#","regions":"#);
+        list(&mut out, self.regions.iter().map(placement).collect());
+        out.push_str(r#","tables":"#);
+        list(&mut out, self.tables.iter().map(placement).collect());
+        out.push_str(r#","constants":"#);
+        list(&mut out, self.constants.iter().map(cell).collect());
+        out.push_str(r#","copies":"#);
+        list(
+            &mut out,
+            self.copies
+                .iter()
+                .map(|(from, to)| format!(r#"{{"from":{},"to":{}}}"#, cell(from), cell(to)))
+                .collect(),
+        );
+        out.push('}');
+        out
+    }
+
+    /// Returns the layout as a standalone SVG image.
+    ///
+    /// Columns are drawn as vertical lanes and rows grow downwards. Regions are drawn as
+    /// labeled rectangles over the rows they assign in each of their columns, tables
+    /// likewise in a different color, constants as small squares and equality
+    /// constraints as thin lines between the centers of their cells.
+    pub fn to_svg(&self) -> String {
+        let width = self.columns.len().max(1) * LANE_WIDTH;
+        let height = HEADER_HEIGHT + self.rows.max(1) * ROW_HEIGHT;
+        let x = |column: usize| column * LANE_WIDTH;
+        let y = |row: usize| HEADER_HEIGHT + row * ROW_HEIGHT;
+
+        let mut out = String::new();
+        writeln!(
+            out,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" font-family="sans-serif" font-size="10">"#
+        )
+        .unwrap();
+
+        // Column lanes, with their labels as headers.
+        for (index, column) in self.columns.iter().enumerate() {
+            writeln!(
+                out,
+                r##"<rect x="{}" y="{}" width="{}" height="{}" fill="{}" stroke="#999999"/>"##,
+                x(index),
+                HEADER_HEIGHT,
+                LANE_WIDTH,
+                height - HEADER_HEIGHT,
+                column.fill()
+            )
+            .unwrap();
+            writeln!(
+                out,
+                r#"<text x="{}" y="{}" text-anchor="middle">{}</text>"#,
+                x(index) + LANE_WIDTH / 2,
+                HEADER_HEIGHT - 8,
+                xml_escape(&column.label())
+            )
+            .unwrap();
+        }
+
+        // Regions and tables.
+        for (placements, fill) in [(&self.regions, "#b6d7a8"), (&self.tables, "#ffe599")] {
+            for placement in placements {
+                for extent in &placement.columns {
+                    writeln!(
+                        out,
+                        r##"<rect x="{}" y="{}" width="{}" height="{}" fill="{}" stroke="#000000"/>"##,
+                        x(extent.column),
+                        y(extent.rows.start),
+                        LANE_WIDTH,
+                        extent.rows.len() * ROW_HEIGHT,
+                        fill
+                    )
+                    .unwrap();
+                }
+                if let Some(extent) = placement.columns.first() {
+                    writeln!(
+                        out,
+                        r#"<text x="{}" y="{}">{}</text>"#,
+                        x(extent.column) + 2,
+                        y(extent.rows.start) + ROW_HEIGHT - 2,
+                        xml_escape(&placement.name)
+                    )
+                    .unwrap();
+                }
+            }
+        }
+
+        // Constants.
+        for cell in &self.constants {
+            writeln!(
+                out,
+                r##"<rect x="{}" y="{}" width="{}" height="{}" fill="#cc0000"/>"##,
+                x(cell.column) + LANE_WIDTH / 2 - ROW_HEIGHT / 4,
+                y(cell.row) + ROW_HEIGHT / 4,
+                ROW_HEIGHT / 2,
+                ROW_HEIGHT / 2
+            )
+            .unwrap();
+        }
+
+        // Equality constraints.
+        for (from, to) in &self.copies {
+            writeln!(
+                out,
+                r##"<line x1="{}" y1="{}" x2="{}" y2="{}" stroke="#cc0000" stroke-width="0.5"/>"##,
+                x(from.column) + LANE_WIDTH / 2,
+                y(from.row) + ROW_HEIGHT / 2,
+                x(to.column) + LANE_WIDTH / 2,
+                y(to.row) + ROW_HEIGHT / 2
+            )
+            .unwrap();
+        }
+
+        out.push_str("</svg>\n");
+        out
+    }
+}
+
+pub(super) fn json_string(s: &str) -> String {
+    let mut out = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => write!(out, "\\u{:04x}", c as u32).unwrap(),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_middleware::poly::Rotation;
+    use halo2curves::pasta::Fp;
+    use serde_json::Value as Json;
+
+    use super::*;
+    use crate::circuit::{floor_planner::V1, Layouter, Value};
+    use halo2_common::plonk::{Error, Selector, TableColumn};
+
+    #[derive(Clone)]
+    struct LookupConfig {
+        a: Column<Advice>,
+        b: Column<Advice>,
+        s: Selector,
+        t: TableColumn,
+    }
+
+    struct LookupCircuit;
+
+    impl Circuit<Fp> for LookupCircuit {
+        type Config = LookupConfig;
+        type FloorPlanner = V1;
+        #[cfg(feature = "circuit-params")]
+        type Params = ();
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let a = meta.advice_column();
+            let b = meta.advice_column();
+            let c = meta.fixed_column();
+            let s = meta.complex_selector();
+            let t = meta.lookup_table_column();
+            meta.enable_equality(a);
+            meta.enable_equality(b);
+            meta.enable_constant(c);
+
+            meta.lookup("bit", |meta| {
+                let s = meta.query_selector(s);
+                let a = meta.query_advice(a, Rotation::cur());
+                vec![(s * a, t)]
+            });
+
+            LookupConfig { a, b, s, t }
+        }
+
+        fn without_witnesses(&self) -> Self {
+            Self
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            layouter.assign_table(
+                || "bits",
+                |mut table| {
+                    for offset in 0..2 {
+                        table.assign_cell(
+                            || "bit",
+                            config.t,
+                            offset,
+                            || Value::known(Fp::from(offset as u64)),
+                        )?;
+                    }
+                    Ok(())
+                },
+            )?;
+
+            let bit = layouter.assign_region(
+                || "bits \"in\"",
+                |mut region| {
+                    let mut bit = None;
+                    for offset in 0..2 {
+                        config.s.enable(&mut region, offset)?;
+                        bit = Some(region.assign_advice(
+                            || "a",
+                            config.a,
+                            offset,
+                            || Value::known(Fp::from(offset as u64)),
+                        )?);
+                    }
+                    Ok(bit.unwrap())
+                },
+            )?;
+
+            layouter.assign_region(
+                || "copies",
+                |mut region| {
+                    bit.copy_advice(|| "b", &mut region, config.b, 0)?;
+                    region.assign_advice_from_constant(|| "one", config.b, 1, Fp::ONE)?;
+                    region.assign_advice(|| "b", config.b, 2, || Value::known(Fp::ZERO))?;
+                    Ok(())
+                },
+            )
+        }
+    }
+
+    #[test]
+    fn layout_json() {
+        const K: u32 = 4;
+        let layout = render_layout(K, &LookupCircuit).unwrap();
+
+        // Lanes: A0, A1, F0 (constants), F1 (table), S0.
+        assert_eq!(layout.columns.len(), 5);
+        assert_eq!(
+            layout.regions,
+            vec![
+                RegionPlacement {
+                    name: "bits \"in\"".to_string(),
+                    start: 0,
+                    rows: 2,
+                    columns: vec![
+                        ColumnExtent {
+                            column: 0,
+                            rows: 0..2,
+                            cells: 2,
+                        },
+                        ColumnExtent {
+                            column: 4,
+                            rows: 0..2,
+                            cells: 2,
+                        },
+                    ],
+                },
+                RegionPlacement {
+                    name: "copies".to_string(),
+                    start: 0,
+                    rows: 3,
+                    columns: vec![ColumnExtent {
+                        column: 1,
+                        rows: 0..3,
+                        cells: 3,
+                    }],
+                },
+            ]
+        );
+        assert_eq!(
+            layout.tables,
+            vec![RegionPlacement {
+                name: "bits".to_string(),
+                start: 0,
+                rows: 2,
+                columns: vec![ColumnExtent {
+                    column: 3,
+                    rows: 0..2,
+                    cells: 2,
+                }],
+            }]
+        );
+        assert_eq!(layout.constants, vec![LayoutCell { column: 2, row: 0 }]);
+        assert_eq!(
+            layout.copies,
+            vec![
+                (
+                    LayoutCell { column: 1, row: 0 },
+                    LayoutCell { column: 0, row: 1 }
+                ),
+                (
+                    LayoutCell { column: 2, row: 0 },
+                    LayoutCell { column: 1, row: 1 }
+                ),
+            ]
+        );
+
+        // The V1 planner places both regions at row 0, so its first unassigned row is the
+        // end of the longest region. The constant is placed within those rows.
+        let first_unassigned_row = layout
+            .regions
+            .iter()
+            .map(|region| region.start + region.rows)
+            .max()
+            .unwrap();
+        assert_eq!(first_unassigned_row, 3);
+        assert_eq!(layout.rows, first_unassigned_row);
+
+        // Check the schema of the JSON document.
+        let json: Json = serde_json::from_str(&layout.to_json()).unwrap();
+        assert_eq!(json["k"], K);
+        assert_eq!(json["rows"], 3);
+        assert_eq!(json["usable_rows"], 10);
+        let columns = json["columns"].as_array().unwrap();
+        let types: Vec<_> = columns
+            .iter()
+            .map(|column| column["type"].as_str().unwrap())
+            .collect();
+        assert_eq!(
+            types,
+            ["advice", "advice", "fixed", "fixed", "selector"].to_vec()
+        );
+        assert_eq!(columns[1]["label"], "A1");
+        assert_eq!(columns[1]["phase"], 0);
+        assert!(columns[2].get("phase").is_none());
+        let region = &json["regions"][0];
+        assert_eq!(region["name"], "bits \"in\"");
+        assert_eq!(region["start"], 0);
+        assert_eq!(region["rows"], 2);
+        assert_eq!(region["extents"][1]["column"], 4);
+        assert_eq!(region["extents"][1]["start"], 0);
+        assert_eq!(region["extents"][1]["end"], 2);
+        assert_eq!(region["extents"][1]["cells"], 2);
+        assert_eq!(json["tables"][0]["name"], "bits");
+        assert_eq!(json["constants"][0]["column"], 2);
+        assert_eq!(json["constants"][0]["row"], 0);
+        assert_eq!(json["copies"][1]["from"]["column"], 2);
+        assert_eq!(json["copies"][1]["to"]["row"], 1);
+
+        let svg = layout.to_svg();
+        assert!(svg.starts_with("<svg "));
+        assert!(svg.contains(">bits &quot;in&quot;</text>"));
+        assert_eq!(svg.matches("<line ").count(), 2);
+        assert!(svg.ends_with("</svg>\n"));
+    }
+}
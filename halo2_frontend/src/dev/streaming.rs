@@ -0,0 +1,1258 @@
+//! A low-memory mode of [`MockProver`], which checks a circuit in chunks of rows.
+//!
+//! Instead of storing the whole assignment, the circuit is synthesized once per chunk,
+//! and only the cells of the rows that the constraints of the chunk can query (the
+//! chunk, extended by the largest rotations) are kept. The failures are the same as the
+//! ones returned by [`MockProver::verify`].
+
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::ops::Range;
+
+use halo2_common::{
+    circuit,
+    plonk::{
+        circuit::{Challenge, Column},
+        permutation,
+        sealed::{self, SealedPhase},
+        AdviceQuery, Assigned, Assignment, Circuit, ConstraintSystem, Error, Expression,
+        FirstPhase, FixedQuery, FloorPlanner, InstanceQuery, Phase, Selector,
+    },
+};
+use halo2_middleware::circuit::{Advice, Any, Cell, Fixed, Instance};
+use halo2_middleware::ff::{Field, FromUniformBytes};
+
+use super::{
+    metadata, util, CellValue, FailureLocation, InstanceValue, MockProver, Region, Value,
+    VerifyFailure,
+};
+
+/// The rows of the circuit that are kept in memory while a chunk is checked.
+#[derive(Clone, Debug)]
+struct Window {
+    n: usize,
+    /// The first row of the window, which wraps around the end of the domain.
+    start: usize,
+    len: usize,
+}
+
+impl Window {
+    /// Returns the window covering the rows of `chunk`, and the `before` rows preceding
+    /// and `after` rows following it.
+    fn new(n: usize, chunk: &Range<usize>, before: usize, after: usize) -> Self {
+        Window {
+            n,
+            start: (chunk.start + n - before.min(n)) % n,
+            len: (chunk.len() + before + after).min(n),
+        }
+    }
+
+    /// Returns the position of `row` in the window, if it is in the window.
+    fn index(&self, row: usize) -> Option<usize> {
+        let index = (row + self.n - self.start) % self.n;
+        (index < self.len).then_some(index)
+    }
+}
+
+/// The data shared by every synthesis of a streaming run.
+struct Streaming<F: Field> {
+    k: u32,
+    n: usize,
+    usable_rows: Range<usize>,
+    /// The constraint system returned by `Circuit::configure`.
+    cs: ConstraintSystem<F>,
+    /// The constraint system in which the constraints are evaluated, where selector `s`
+    /// is replaced by fixed column `cs.num_fixed_columns + s`.
+    eval_cs: ConstraintSystem<F>,
+    instance: Vec<Vec<F>>,
+    challenges: Vec<F>,
+    /// The largest negative and positive rotations of the queries.
+    before: usize,
+    after: usize,
+    /// The table of each lookup, where lookups with the same table expressions share a
+    /// table.
+    lookup_tables: Vec<usize>,
+    num_tables: usize,
+}
+
+impl<F: Field> Streaming<F> {
+    /// Synthesizes `circuit`, keeping the cells of the window of `chunk`. The regions and
+    /// copies are only recorded if `record` is set.
+    fn synthesize<C: Circuit<F>>(
+        &self,
+        circuit: &C,
+        config: &C::Config,
+        chunk: Range<usize>,
+        record: bool,
+    ) -> Result<StreamingAssignment<'_, F>, Error> {
+        let window = Window::new(self.n, &chunk, self.before, self.after);
+        let num_fixed = self.cs.num_fixed_columns;
+        let mut generated_columns = self
+            .cs
+            .boundary_columns
+            .map_or(vec![], |(l_first, l_last)| {
+                vec![l_first.index(), l_last.index()]
+            });
+        generated_columns.extend(
+            self.cs
+                .preprocessed_fixed_columns
+                .iter()
+                .map(|preprocessed| preprocessed.column.index()),
+        );
+        let mut fixed: Vec<_> = (0..self.eval_cs.num_fixed_columns)
+            .map(|column| {
+                // Selector, boundary and preprocessed columns are set on every row.
+                let value = if column < num_fixed && !generated_columns.contains(&column) {
+                    CellValue::Unassigned
+                } else {
+                    CellValue::Assigned(F::ZERO)
+                };
+                vec![value; window.len]
+            })
+            .collect();
+        for (column, row) in self.cs.boundary_cells(&self.usable_rows) {
+            if let Some(index) = window.index(row) {
+                fixed[column.index()][index] = CellValue::Assigned(F::ONE);
+            }
+        }
+        for (column, row, value) in self.cs.preprocessed_fixed_cells(&self.usable_rows) {
+            if let Some(index) = window.index(row) {
+                fixed[column.index()][index] = CellValue::Assigned(value);
+            }
+        }
+        let advice_column = (0..window.len)
+            .map(|index| {
+                // Poison unusable rows.
+                let row = (window.start + index) % self.n;
+                if self.usable_rows.contains(&row) {
+                    CellValue::Unassigned
+                } else {
+                    CellValue::Poison(row)
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let mut assignment = StreamingAssignment {
+            streaming: self,
+            record,
+            regions: vec![],
+            permutation: permutation::Assembly::new(self.n, &self.cs.permutation),
+            current_region: None,
+            regions_entered: 0,
+            current_phase: FirstPhase.to_sealed(),
+            namespace: vec![],
+            window,
+            chunk,
+            fixed,
+            advice: vec![advice_column; self.cs.num_advice_columns],
+            region_cells: HashMap::default(),
+            enabled_selectors: BTreeMap::default(),
+        };
+        for current_phase in self.cs.phases() {
+            assignment.current_phase = current_phase;
+            assignment.regions_entered = 0;
+            C::FloorPlanner::synthesize(
+                &mut assignment,
+                circuit,
+                config.clone(),
+                self.cs.constants.clone(),
+            )?;
+        }
+        Ok(assignment)
+    }
+
+    fn rotate(&self, row: usize, rotation: i32) -> usize {
+        (row as i64 + rotation as i64).rem_euclid(self.n as i64) as usize
+    }
+
+    fn instance_value(&self, column: usize, row: usize) -> InstanceValue<F> {
+        match self.instance[column].get(row) {
+            Some(value) => InstanceValue::Assigned(*value),
+            None => InstanceValue::Padding,
+        }
+    }
+}
+
+/// An [`Assignment`] that keeps the cells of a window of rows.
+struct StreamingAssignment<'a, F: Field> {
+    streaming: &'a Streaming<F>,
+    /// Whether the regions and copies are recorded, which is only needed once.
+    record: bool,
+    /// The regions, without their cells and enabled selectors.
+    regions: Vec<Region>,
+    permutation: permutation::Assembly,
+    /// The index of the region being assigned to, in every phase.
+    current_region: Option<usize>,
+    /// The number of regions entered so far in the current phase.
+    regions_entered: usize,
+    current_phase: sealed::Phase,
+    /// The namespaces pushed during synthesis, outermost first.
+    namespace: Vec<String>,
+
+    window: Window,
+    chunk: Range<usize>,
+    // The cells of the window, arranged as [column][index in the window]. The fixed
+    // columns are followed by a column for each selector.
+    fixed: Vec<Vec<CellValue<F>>>,
+    advice: Vec<Vec<CellValue<F>>>,
+    /// The cells of the window assigned in each region, by region index.
+    region_cells: HashMap<usize, HashSet<(Column<Any>, usize)>>,
+    /// The rows of the chunk at which each region enabled selectors, by region index.
+    enabled_selectors: BTreeMap<usize, HashMap<Selector, Vec<usize>>>,
+}
+
+impl<F: Field> StreamingAssignment<'_, F> {
+    fn in_phase<P: Phase>(&self, phase: P) -> bool {
+        self.current_phase == phase.to_sealed()
+    }
+
+    /// Returns the position of `row` in the window, if it is in the window.
+    fn window_index(&self, row: usize) -> Option<usize> {
+        assert!(row < self.streaming.n, "bounds failure");
+        self.window.index(row)
+    }
+
+    /// Records an assignment to the cell at `(column, row)` in the region at `index`.
+    fn track_cell(&mut self, index: usize, column: Column<Any>, row: usize) {
+        if self.record {
+            if let Some(region) = self.regions.get_mut(index) {
+                region.update_extent(column, row);
+            }
+        }
+        if self.window_index(row).is_some() {
+            self.region_cells
+                .entry(index)
+                .or_default()
+                .insert((column, row));
+        }
+    }
+
+    /// Records an assignment to the advice cell at `(column, row)` in the current region.
+    fn track_advice_cell(&mut self, column: Column<Advice>, row: usize) -> Result<(), Error> {
+        let streaming = self.streaming;
+        if self.in_phase(FirstPhase) {
+            if !streaming.usable_rows.contains(&row) {
+                return Err(Error::AdviceOutsideUsableRows {
+                    column,
+                    row,
+                    usable_rows: streaming.usable_rows.clone(),
+                });
+            }
+        } else if !column.in_phase(self.current_phase) {
+            return Ok(());
+        }
+
+        if let Some(index) = self.current_region {
+            self.track_cell(index, column.into(), row);
+        }
+
+        Ok(())
+    }
+
+    fn fixed(&self, column: usize, row: usize) -> CellValue<F> {
+        self.fixed[column][self.window.index(row).expect("row is in the window")]
+    }
+
+    fn advice(&self, column: usize, row: usize) -> CellValue<F> {
+        self.advice[column][self.window.index(row).expect("row is in the window")]
+    }
+
+    fn load_fixed(&self, row: usize) -> impl Fn(FixedQuery) -> Value<F> + '_ {
+        move |query| {
+            let row = self.streaming.rotate(row, query.rotation.0);
+            self.fixed(query.column_index, row).into()
+        }
+    }
+
+    fn load_advice(&self, row: usize) -> impl Fn(AdviceQuery) -> Value<F> + '_ {
+        move |query| {
+            let row = self.streaming.rotate(row, query.rotation.0);
+            self.advice(query.column_index, row).into()
+        }
+    }
+
+    fn load_instance(&self, row: usize) -> impl Fn(InstanceQuery) -> Value<F> + '_ {
+        move |query| {
+            let row = self.streaming.rotate(row, query.rotation.0);
+            Value::Real(
+                self.streaming
+                    .instance_value(query.column_index, row)
+                    .value(),
+            )
+        }
+    }
+
+    /// Evaluates `expression` at `row`, which must be in the chunk.
+    fn evaluate(&self, expression: &Expression<F>, row: usize) -> Value<F> {
+        expression.evaluate_lazy(
+            &|scalar| Value::Real(scalar),
+            &|_| panic!("virtual selectors are removed during optimization"),
+            &self.load_fixed(row),
+            &self.load_advice(row),
+            &self.load_instance(row),
+            &|challenge| Value::Real(self.streaming.challenges[challenge.index()]),
+            &|a| -a,
+            &|a, b| a + b,
+            &|a, b| a * b,
+            &|a, scalar| a * scalar,
+            &Value::Real(F::ZERO),
+        )
+    }
+
+    /// The usable rows of the chunk.
+    fn usable_chunk(&self) -> Range<usize> {
+        let usable_rows = &self.streaming.usable_rows;
+        self.chunk.start.max(usable_rows.start)..self.chunk.end.min(usable_rows.end)
+    }
+
+    /// Checks that within each region, all cells used in the gates enabled in the chunk
+    /// have been assigned to.
+    fn selector_failures(&self, regions: &[Region]) -> Vec<VerifyFailure> {
+        let streaming = self.streaming;
+        let cs = &streaming.eval_cs;
+        let mut failures = vec![];
+        for (r_i, selectors) in &self.enabled_selectors {
+            let r = &regions[*r_i];
+            for (selector, at) in selectors {
+                let gates = cs
+                    .gates
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, gate)| gate.queried_selectors().contains(selector));
+                for (gate_index, gate) in gates {
+                    for selector_row in at {
+                        for cell in gate.queried_cells() {
+                            // Determine where this cell should have been assigned.
+                            let cell_row = streaming.rotate(*selector_row, cell.rotation.0);
+                            match cell.column.column_type() {
+                                Any::Instance => {
+                                    if let InstanceValue::Padding =
+                                        streaming.instance_value(cell.column.index(), cell_row)
+                                    {
+                                        failures.push(VerifyFailure::InstanceCellNotAssigned {
+                                            gate: (gate_index, gate.name()).into(),
+                                            region: r.metadata(*r_i, None),
+                                            gate_offset: *selector_row,
+                                            column: cell.column.try_into().unwrap(),
+                                            row: cell_row,
+                                        });
+                                    }
+                                }
+                                _ => {
+                                    let assigned =
+                                        self.region_cells.get(r_i).map_or(false, |cells| {
+                                            cells.contains(&(cell.column, cell_row))
+                                        });
+                                    if !assigned {
+                                        failures.push(VerifyFailure::CellNotAssigned {
+                                            gate: (gate_index, gate.name()).into(),
+                                            region: r.metadata(
+                                                *r_i,
+                                                Some(r.scoped_annotations(
+                                                    &cs.general_column_annotations,
+                                                )),
+                                            ),
+                                            gate_offset: *selector_row,
+                                            column: cell.column,
+                                            offset: cell_row as isize - r.rows.unwrap().0 as isize,
+                                        });
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        failures
+    }
+
+    /// Checks that all gates are satisfied on the rows of the chunk.
+    fn gate_failures(&self, regions: &[Region]) -> Vec<VerifyFailure> {
+        let cs = &self.streaming.eval_cs;
+        let mut failures = vec![];
+        for row in self.chunk.clone() {
+            for (gate_index, gate) in cs.gates.iter().enumerate() {
+                for (poly_index, poly) in gate.polynomials().iter().enumerate() {
+                    let constraint = || -> metadata::Constraint {
+                        (
+                            (gate_index, gate.name()).into(),
+                            poly_index,
+                            gate.constraint_name(poly_index),
+                        )
+                            .into()
+                    };
+                    match self.evaluate(poly, row) {
+                        Value::Real(x) if x.is_zero_vartime() => {}
+                        Value::Real(_) => failures.push(VerifyFailure::ConstraintNotSatisfied {
+                            constraint: constraint(),
+                            location: FailureLocation::find_expressions(
+                                cs,
+                                regions,
+                                row,
+                                Some(poly).into_iter(),
+                            ),
+                            cell_values: util::cell_values(
+                                gate,
+                                poly,
+                                self.load_fixed(row),
+                                self.load_advice(row),
+                                self.load_instance(row),
+                            ),
+                        }),
+                        Value::Poison => failures.push(VerifyFailure::ConstraintPoisoned {
+                            constraint: constraint(),
+                        }),
+                    }
+                }
+            }
+        }
+        failures
+    }
+
+    /// Records the values of the copied cells that are in the chunk.
+    fn record_copies(&self, copies: &[(Cell, Cell)], values: &mut [[Option<CellValue<F>>; 2]]) {
+        let original = |cell: &Cell| match cell.column.column_type {
+            Any::Advice(_) => self.advice(cell.column.index, cell.row),
+            Any::Fixed => self.fixed(cell.column.index, cell.row),
+            Any::Instance => CellValue::Assigned(
+                self.streaming
+                    .instance_value(cell.column.index, cell.row)
+                    .value(),
+            ),
+        };
+        for ((cell_a, cell_b), values) in copies.iter().zip(values.iter_mut()) {
+            for (cell, value) in [cell_a, cell_b].into_iter().zip(values.iter_mut()) {
+                if self.chunk.contains(&cell.row) {
+                    *value = Some(original(cell));
+                }
+            }
+        }
+    }
+}
+
+impl<F: Field + Ord> StreamingAssignment<'_, F> {
+    /// Adds the rows of the lookup tables in the chunk to `tables`.
+    fn insert_table_rows(&self, tables: &mut [BTreeSet<Vec<Value<F>>>]) {
+        let cs = &self.streaming.eval_cs;
+        let mut inserted = vec![false; tables.len()];
+        for (lookup, table) in cs.lookups.iter().zip(&self.streaming.lookup_tables) {
+            if std::mem::replace(&mut inserted[*table], true) {
+                continue;
+            }
+            // In the real prover, the lookup expressions are never enforced on unusable
+            // rows, due to the (1 - (l_last(X) + l_blind(X))) term.
+            for row in self.usable_chunk() {
+                tables[*table].insert(
+                    lookup
+                        .table_expressions
+                        .iter()
+                        .map(|expression| self.evaluate(expression, row))
+                        .collect(),
+                );
+            }
+        }
+    }
+
+    /// Checks that the lookup inputs of the chunk are in their tables, which must
+    /// contain the rows of the whole circuit.
+    fn lookup_failures(
+        &self,
+        regions: &[Region],
+        tables: &[BTreeSet<Vec<Value<F>>>],
+    ) -> Vec<VerifyFailure> {
+        let cs = &self.streaming.eval_cs;
+        let mut failures = vec![];
+        for row in self.usable_chunk() {
+            for (lookup_index, lookup) in cs.lookups.iter().enumerate() {
+                let input: Vec<_> = lookup
+                    .input_expressions
+                    .iter()
+                    .map(|expression| self.evaluate(expression, row))
+                    .collect();
+                if !tables[self.streaming.lookup_tables[lookup_index]].contains(&input) {
+                    failures.push(VerifyFailure::Lookup {
+                        name: lookup.name.clone(),
+                        lookup_index,
+                        location: FailureLocation::find_expressions(
+                            cs,
+                            regions,
+                            row,
+                            lookup.input_expressions.iter(),
+                        ),
+                    });
+                }
+            }
+        }
+        failures
+    }
+
+    /// Appends the shuffle and input rows of the chunk to `shuffles`, by shuffle.
+    #[allow(clippy::type_complexity)]
+    fn push_shuffle_rows(
+        &self,
+        shuffles: &mut [(Vec<Vec<Value<F>>>, Vec<(Vec<Value<F>>, usize)>)],
+    ) {
+        let cs = &self.streaming.eval_cs;
+        for (shuffle, (shuffle_rows, input_rows)) in cs.shuffles.iter().zip(shuffles.iter_mut()) {
+            for row in self.usable_chunk() {
+                let evaluate = |expressions: &[Expression<F>]| {
+                    expressions
+                        .iter()
+                        .map(|expression| self.evaluate(expression, row))
+                        .collect::<Vec<_>>()
+                };
+                shuffle_rows.push(evaluate(&shuffle.shuffle_expressions));
+                input_rows.push((evaluate(&shuffle.input_expressions), row));
+            }
+        }
+    }
+}
+
+impl<F: Field> Assignment<F> for StreamingAssignment<'_, F> {
+    fn enter_region<NR, N>(&mut self, name: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+        // Regions are entered in the same order in every phase and synthesis, so they
+        // are identified by their index.
+        if self.in_phase(FirstPhase) {
+            assert!(self.current_region.is_none());
+            if self.record {
+                self.regions.push(Region {
+                    name: name().into(),
+                    namespace: self.namespace.clone(),
+                    columns: HashSet::default(),
+                    rows: None,
+                    annotations: HashMap::default(),
+                    enabled_selectors: HashMap::default(),
+                    cells: HashMap::default(),
+                });
+            }
+        }
+        self.current_region = Some(self.regions_entered);
+        self.regions_entered += 1;
+    }
+
+    fn exit_region(&mut self) {
+        self.current_region = None;
+    }
+
+    fn annotate_column<A, AR>(&mut self, annotation: A, column: Column<Any>)
+    where
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        if !self.in_phase(FirstPhase) || !self.record {
+            return;
+        }
+
+        if let Some(region) = self
+            .current_region
+            .and_then(|index| self.regions.get_mut(index))
+        {
+            region
+                .annotations
+                .insert(column.into(), annotation().into());
+        }
+    }
+
+    fn enable_selector<A, AR>(&mut self, _: A, selector: &Selector, row: usize) -> Result<(), Error>
+    where
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        if !self.in_phase(FirstPhase) {
+            return Ok(());
+        }
+
+        let streaming = self.streaming;
+        if !streaming.usable_rows.contains(&row) {
+            return Err(Error::SelectorOutsideUsableRows {
+                selector: *selector,
+                row,
+                usable_rows: streaming.usable_rows.clone(),
+            });
+        }
+
+        // We require that all selectors are enabled inside some region (i.e. no floating
+        // selectors).
+        let region = self.current_region.unwrap();
+        if self.chunk.contains(&row) {
+            self.enabled_selectors
+                .entry(region)
+                .or_default()
+                .entry(*selector)
+                .or_default()
+                .push(row);
+        }
+        if let Some(index) = self.window.index(row) {
+            self.fixed[streaming.cs.num_fixed_columns + selector.index()][index] =
+                CellValue::Assigned(F::ONE);
+        }
+
+        Ok(())
+    }
+
+    fn query_instance(
+        &self,
+        column: Column<Instance>,
+        row: usize,
+    ) -> Result<circuit::Value<F>, Error> {
+        let streaming = self.streaming;
+        assert!(
+            streaming.usable_rows.contains(&row),
+            "row={}, usable_rows={:?}, k={}",
+            row,
+            streaming.usable_rows,
+            streaming.k,
+        );
+
+        Ok(streaming
+            .instance
+            .get(column.index())
+            .map(|column| circuit::Value::known(column.get(row).copied().unwrap_or(F::ZERO)))
+            .expect("bound failure"))
+    }
+
+    fn assign_advice<V, VR, A, AR>(
+        &mut self,
+        _: A,
+        column: Column<Advice>,
+        row: usize,
+        to: V,
+    ) -> Result<(), Error>
+    where
+        V: FnOnce() -> circuit::Value<VR>,
+        VR: Into<Assigned<F>>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.track_advice_cell(column, row)?;
+
+        match to().into_field().evaluate().assign() {
+            Ok(to) => {
+                if let Some(index) = self.window_index(row) {
+                    self.advice[column.index()][index] = CellValue::Assigned(to);
+                }
+            }
+            Err(err) => {
+                // Propagate `assign` error if the column is in current phase.
+                if column.in_phase(self.current_phase) {
+                    return Err(err);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn assign_fixed<V, VR, A, AR>(
+        &mut self,
+        _: A,
+        column: Column<Fixed>,
+        row: usize,
+        to: V,
+    ) -> Result<(), Error>
+    where
+        V: FnOnce() -> circuit::Value<VR>,
+        VR: Into<Assigned<F>>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        if !self.in_phase(FirstPhase) {
+            return Ok(());
+        }
+
+        let streaming = self.streaming;
+        assert!(
+            streaming.usable_rows.contains(&row),
+            "row={}, usable_rows={:?}, k={}",
+            row,
+            streaming.usable_rows,
+            streaming.k,
+        );
+        if streaming.cs.is_preprocessed_fixed(column) {
+            return Err(Error::PreprocessedFixedAssigned(column));
+        }
+
+        if let Some(index) = self.current_region {
+            self.track_cell(index, column.into(), row);
+        }
+
+        let value = CellValue::Assigned(to().into_field().evaluate().assign()?);
+        if let Some(index) = self.window_index(row) {
+            self.fixed[column.index()][index] = value;
+        }
+
+        Ok(())
+    }
+
+    fn copy(
+        &mut self,
+        left_column: Column<Any>,
+        left_row: usize,
+        right_column: Column<Any>,
+        right_row: usize,
+    ) -> Result<(), Error> {
+        if !self.in_phase(FirstPhase) {
+            return Ok(());
+        }
+
+        let streaming = self.streaming;
+        assert!(
+            streaming.usable_rows.contains(&left_row) && streaming.usable_rows.contains(&right_row),
+            "left_row={}, right_row={}, usable_rows={:?}, k={}",
+            left_row,
+            right_row,
+            streaming.usable_rows,
+            streaming.k,
+        );
+
+        self.permutation
+            .copy(left_column, left_row, right_column, right_row)?;
+        // The copies are checked in every synthesis, but only kept once.
+        if !self.record {
+            self.permutation.copies.pop();
+        }
+
+        Ok(())
+    }
+
+    fn fill_from_row(
+        &mut self,
+        col: Column<Fixed>,
+        from_row: usize,
+        to: circuit::Value<Assigned<F>>,
+    ) -> Result<(), Error> {
+        if !self.in_phase(FirstPhase) {
+            return Ok(());
+        }
+
+        let usable_rows = self.streaming.usable_rows.clone();
+        assert!(
+            usable_rows.contains(&from_row),
+            "row={}, usable_rows={:?}, k={}",
+            from_row,
+            usable_rows,
+            self.streaming.k,
+        );
+
+        for row in usable_rows.skip(from_row) {
+            self.assign_fixed(|| "", col, row, || to)?;
+        }
+
+        Ok(())
+    }
+
+    fn fill_advice_from_row(
+        &mut self,
+        column: Column<Advice>,
+        from_row: usize,
+        to: circuit::Value<Assigned<F>>,
+    ) -> Result<(), Error> {
+        let usable_rows = self.streaming.usable_rows.clone();
+        if from_row > usable_rows.end {
+            return Err(Error::AdviceOutsideUsableRows {
+                column,
+                row: from_row,
+                usable_rows,
+            });
+        }
+        if from_row == usable_rows.end {
+            return Ok(());
+        }
+
+        // As in `MockProver`, only the first row belongs to the region.
+        self.track_advice_cell(column, from_row)?;
+
+        match to.evaluate().assign() {
+            Ok(to) => {
+                for row in from_row..usable_rows.end {
+                    if let Some(index) = self.window.index(row) {
+                        let cell = &mut self.advice[column.index()][index];
+                        if *cell == CellValue::Unassigned {
+                            *cell = CellValue::Assigned(to);
+                        }
+                    }
+                }
+            }
+            Err(err) => {
+                // Propagate `assign` error if the column is in current phase.
+                if column.in_phase(self.current_phase) {
+                    return Err(err);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn get_challenge(&self, challenge: Challenge) -> circuit::Value<F> {
+        if self.current_phase.0 <= challenge.phase() {
+            return circuit::Value::unknown();
+        }
+
+        circuit::Value::known(self.streaming.challenges[challenge.index()])
+    }
+
+    fn current_phase(&self) -> u8 {
+        self.current_phase.0
+    }
+
+    fn usable_rows(&self) -> Option<Range<usize>> {
+        Some(self.streaming.usable_rows.clone())
+    }
+
+    fn push_namespace<NR, N>(&mut self, name_fn: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+        self.namespace.push(name_fn().into());
+    }
+
+    fn pop_namespace(&mut self, _: Option<String>) {
+        self.namespace.pop();
+    }
+}
+
+impl<F: FromUniformBytes<64> + Ord> MockProver<F> {
+    /// Checks the circuit like [`MockProver::run`] followed by [`MockProver::verify`],
+    /// without keeping the whole assignment in memory.
+    ///
+    /// The rows are checked in chunks of `chunk_rows` rows, and the circuit is
+    /// synthesized again for each chunk, keeping only the cells that the constraints on
+    /// the chunk query. The lookup tables are collected in a first pass over the chunks,
+    /// and the lookup inputs are checked in a second one. The memory used is thus
+    /// proportional to the number of columns times the size of a chunk, plus the rows of
+    /// the lookup tables, the copies and the regions (without their cells). Shuffles
+    /// are the exception: they are checked by sorting all their rows, as in
+    /// [`MockProver::verify`].
+    ///
+    /// The failures are the same as the ones returned by [`MockProver::verify`], in an
+    /// order that may differ. The circuit must assign the same cells on every synthesis.
+    ///
+    /// Returns an error if synthesis fails.
+    pub fn run_streaming<ConcreteCircuit: Circuit<F>>(
+        k: u32,
+        circuit: &ConcreteCircuit,
+        instance: Vec<Vec<F>>,
+        chunk_rows: usize,
+    ) -> Result<Result<(), Vec<VerifyFailure>>, Error> {
+        assert!(chunk_rows > 0, "chunk_rows must be positive");
+        let n = 1 << k;
+
+        let mut cs = ConstraintSystem::default();
+        #[cfg(feature = "circuit-params")]
+        let config = ConcreteCircuit::configure_with_params(&mut cs, circuit.params());
+        #[cfg(not(feature = "circuit-params"))]
+        let config = ConcreteCircuit::configure(&mut cs);
+
+        assert!(
+            n >= cs.minimum_rows(),
+            "n={}, minimum_rows={}, k={}",
+            n,
+            cs.minimum_rows(),
+            k,
+        );
+
+        assert_eq!(instance.len(), cs.num_instance_columns);
+        for instance in &instance {
+            assert!(
+                instance.len() <= n - (cs.blinding_factors() + 1),
+                "instance.len={}, n={}, cs.blinding_factors={}",
+                instance.len(),
+                n,
+                cs.blinding_factors()
+            );
+        }
+
+        // The selectors are replaced by fixed columns as they are, which evaluates the
+        // constraints to the same values as the compressed selectors of `MockProver::run`.
+        let (eval_cs, _) = cs
+            .clone()
+            .directly_convert_selectors_to_fixed(vec![vec![]; cs.num_selectors]);
+        let rotations = eval_cs
+            .fixed_queries
+            .iter()
+            .map(|(_, rotation)| rotation.0)
+            .chain(
+                eval_cs
+                    .advice_queries
+                    .iter()
+                    .map(|(_, rotation)| rotation.0),
+            )
+            .chain(
+                eval_cs
+                    .instance_queries
+                    .iter()
+                    .map(|(_, rotation)| rotation.0),
+            );
+        let (before, after) = rotations.fold((0, 0), |(before, after), rotation| {
+            (
+                before.max((-rotation).max(0) as usize),
+                after.max(rotation.max(0) as usize),
+            )
+        });
+
+        let mut table_identifiers = vec![];
+        let lookup_tables = eval_cs
+            .lookups
+            .iter()
+            .map(|lookup| {
+                let identifier = lookup
+                    .table_expressions
+                    .iter()
+                    .map(Expression::identifier)
+                    .collect::<Vec<_>>();
+                match table_identifiers
+                    .iter()
+                    .position(|other| *other == identifier)
+                {
+                    Some(table) => table,
+                    None => {
+                        table_identifiers.push(identifier);
+                        table_identifiers.len() - 1
+                    }
+                }
+            })
+            .collect();
+
+        let streaming = Streaming {
+            k,
+            n,
+            usable_rows: cs.usable_rows(k),
+            challenges: Self::derive_challenges(cs.num_challenges),
+            cs,
+            eval_cs,
+            instance,
+            before,
+            after,
+            lookup_tables,
+            num_tables: table_identifiers.len(),
+        };
+        let chunks: Vec<_> = (0..n)
+            .step_by(chunk_rows)
+            .map(|start| start..(start + chunk_rows).min(n))
+            .collect();
+
+        // The first pass checks the gates and copies, and collects the lookup tables and
+        // the shuffled rows.
+        let mut regions = vec![];
+        let mut copies = vec![];
+        let mut copy_values = vec![];
+        let mut tables = vec![BTreeSet::new(); streaming.num_tables];
+        let mut shuffles = vec![(vec![], vec![]); streaming.eval_cs.shuffles.len()];
+        let mut selector_errors = vec![];
+        let mut gate_errors = vec![];
+        for (i, chunk) in chunks.iter().enumerate() {
+            let mut assignment = streaming.synthesize(circuit, &config, chunk.clone(), i == 0)?;
+            if i == 0 {
+                // The whole circuit has been synthesized, so the regions are complete.
+                regions = std::mem::take(&mut assignment.regions);
+                copies = std::mem::take(&mut assignment.permutation.copies);
+                copy_values = vec![[None; 2]; copies.len()];
+            }
+            selector_errors.extend(assignment.selector_failures(&regions));
+            gate_errors.extend(assignment.gate_failures(&regions));
+            assignment.record_copies(&copies, &mut copy_values);
+            assignment.insert_table_rows(&mut tables);
+            assignment.push_shuffle_rows(&mut shuffles);
+        }
+
+        // The second pass checks the lookup inputs against the complete tables.
+        let mut lookup_errors = vec![];
+        if !streaming.eval_cs.lookups.is_empty() {
+            for chunk in &chunks {
+                let assignment = streaming.synthesize(circuit, &config, chunk.clone(), false)?;
+                lookup_errors.extend(assignment.lookup_failures(&regions, &tables));
+            }
+        }
+
+        let cs = &streaming.eval_cs;
+        let perm_errors = copies
+            .iter()
+            .zip(copy_values)
+            .filter(|(_, [original, permuted])| original != permuted)
+            .map(|((cell_a, _), _)| VerifyFailure::Permutation {
+                column: cell_a.column.into(),
+                location: FailureLocation::find(
+                    &regions,
+                    &cs.general_column_annotations,
+                    cell_a.row,
+                    [Column::<Any>::from(cell_a.column)].into_iter().collect(),
+                ),
+            });
+
+        let mut shuffle_errors = vec![];
+        for (shuffle_index, (shuffle, (mut shuffle_rows, mut input_rows))) in
+            cs.shuffles.iter().zip(shuffles).enumerate()
+        {
+            shuffle_rows.sort();
+            input_rows.sort();
+            for ((input_value, row), shuffle_value) in input_rows.iter().zip(&shuffle_rows) {
+                if input_value != shuffle_value {
+                    shuffle_errors.push((
+                        *row,
+                        VerifyFailure::Shuffle {
+                            name: shuffle.name.clone(),
+                            shuffle_index,
+                            location: FailureLocation::find_expressions(
+                                cs,
+                                &regions,
+                                *row,
+                                shuffle.input_expressions.iter(),
+                            ),
+                        },
+                    ));
+                }
+            }
+        }
+        // Order the failures by row, then shuffle.
+        shuffle_errors.sort_by_key(|(row, _)| *row);
+
+        let mut errors: Vec<_> = selector_errors
+            .into_iter()
+            .chain(gate_errors)
+            .chain(lookup_errors)
+            .chain(perm_errors)
+            .chain(shuffle_errors.into_iter().map(|(_, error)| error))
+            .collect();
+        if errors.is_empty() {
+            return Ok(Ok(()));
+        }
+
+        // Remove any duplicate `ConstraintPoisoned` errors, as in `MockProver::verify`.
+        let mut poisoned = vec![];
+        errors.retain(|error| match error {
+            VerifyFailure::ConstraintPoisoned { constraint } => {
+                if poisoned.contains(constraint) {
+                    false
+                } else {
+                    poisoned.push(constraint.clone());
+                    true
+                }
+            }
+            _ => true,
+        });
+        Ok(Err(errors))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_middleware::poly::Rotation;
+    use halo2curves::pasta::Fp;
+
+    use super::*;
+    use crate::circuit::{Layouter, SimpleFloorPlanner, Value};
+    use halo2_common::plonk::TableColumn;
+
+    const K: u32 = 8;
+    const BLOCKS: usize = 4;
+    const BLOCK_ROWS: usize = 40;
+
+    #[derive(Clone)]
+    struct BlockConfig {
+        a: Column<Advice>,
+        b: Column<Advice>,
+        d: Column<Advice>,
+        e: Column<Advice>,
+        c: Column<Fixed>,
+        instance: Column<Instance>,
+        s_step: Selector,
+        s_square: Selector,
+        s_lookup: Selector,
+        t: TableColumn,
+    }
+
+    /// Counts over several regions chained by copies, with a lookup and a shuffle. If
+    /// `bad` is set, the assignment fails constraints of every kind.
+    struct BlockCircuit {
+        bad: bool,
+    }
+
+    impl Circuit<Fp> for BlockCircuit {
+        type Config = BlockConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+        #[cfg(feature = "circuit-params")]
+        type Params = ();
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let a = meta.advice_column();
+            let b = meta.advice_column();
+            let d = meta.advice_column();
+            let e = meta.advice_column();
+            let c = meta.fixed_column();
+            let instance = meta.instance_column();
+            let s_step = meta.selector();
+            let s_square = meta.selector();
+            let s_lookup = meta.complex_selector();
+            let t = meta.lookup_table_column();
+            meta.enable_equality(a);
+            meta.enable_equality(instance);
+
+            meta.create_gate("step", |meta| {
+                let prev = meta.query_advice(a, Rotation::prev());
+                let cur = meta.query_advice(a, Rotation::cur());
+                let c = meta.query_fixed(c, Rotation::prev());
+                let s = meta.query_selector(s_step);
+                vec![s * (prev + c - cur)]
+            });
+            meta.create_gate("square", |meta| {
+                let a = meta.query_advice(a, Rotation::cur());
+                let b = meta.query_advice(b, Rotation::cur());
+                let s = meta.query_selector(s_square);
+                vec![s * (a.clone() * a - b)]
+            });
+            meta.lookup("small", |meta| {
+                let d = meta.query_advice(d, Rotation::cur());
+                let s = meta.query_selector(s_lookup);
+                vec![(s * d, t)]
+            });
+            meta.shuffle("reversed", |meta| {
+                let d = meta.query_advice(d, Rotation::cur());
+                let e = meta.query_advice(e, Rotation::cur());
+                let s = meta.query_selector(s_lookup);
+                vec![(s.clone() * d, s * e)]
+            });
+
+            BlockConfig {
+                a,
+                b,
+                d,
+                e,
+                c,
+                instance,
+                s_step,
+                s_square,
+                s_lookup,
+                t,
+            }
+        }
+
+        fn without_witnesses(&self) -> Self {
+            Self { bad: self.bad }
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            layouter.assign_table(
+                || "small",
+                |mut table| {
+                    for value in 0..64 {
+                        table.assign_cell(
+                            || "value",
+                            config.t,
+                            value,
+                            || Value::known(Fp::from(value as u64)),
+                        )?;
+                    }
+                    Ok(())
+                },
+            )?;
+
+            let mut carry = None;
+            for block in 0..BLOCKS {
+                let last = layouter.assign_region(
+                    || "block",
+                    |mut region| {
+                        let value = |offset: usize| (block * BLOCK_ROWS + offset) as u64;
+                        let known = |value: u64| Value::known(Fp::from(value));
+
+                        let mut last = match &carry {
+                            Some(cell) => cell.copy_advice(|| "a", &mut region, config.a, 0)?,
+                            None => region.assign_advice(|| "a", config.a, 0, || known(0))?,
+                        };
+                        for offset in 1..=BLOCK_ROWS {
+                            config.s_step.enable(&mut region, offset)?;
+                            let a = if self.bad && block == 1 && offset == 5 {
+                                value(offset) + 1
+                            } else {
+                                value(offset)
+                            };
+                            last = region.assign_advice(|| "a", config.a, offset, || known(a))?;
+                        }
+
+                        for offset in 0..BLOCK_ROWS {
+                            config.s_square.enable(&mut region, offset)?;
+                            config.s_lookup.enable(&mut region, offset)?;
+                            region.assign_fixed(|| "c", config.c, offset, || known(1))?;
+                            if !(self.bad && block == 2 && offset == 7) {
+                                let b = value(offset) * value(offset);
+                                region.assign_advice(|| "b", config.b, offset, || known(b))?;
+                            }
+                            let d = if self.bad && block == 3 && offset == 3 {
+                                100
+                            } else {
+                                value(offset) % 64
+                            };
+                            let e = value(BLOCK_ROWS - 1 - offset) % 64;
+                            region.assign_advice(|| "d", config.d, offset, || known(d))?;
+                            region.assign_advice(|| "e", config.e, offset, || known(e))?;
+                        }
+                        Ok(last)
+                    },
+                )?;
+                carry = Some(last);
+            }
+
+            layouter.constrain_instance(carry.unwrap().cell(), config.instance, 0)
+        }
+    }
+
+    /// Checks that `MockProver::run_streaming` returns the failures of
+    /// `MockProver::verify` for various chunk sizes, and returns them.
+    fn streaming_failures(circuit: &BlockCircuit, instance: u64) -> Vec<VerifyFailure> {
+        let instance = vec![vec![Fp::from(instance)]];
+        let expected = MockProver::run(K, circuit, instance.clone())
+            .unwrap()
+            .verify()
+            .err()
+            .unwrap_or_default();
+        for chunk_rows in [1, 5, 37, 1 << K] {
+            let actual = MockProver::run_streaming(K, circuit, instance.clone(), chunk_rows)
+                .unwrap()
+                .err()
+                .unwrap_or_default();
+            // The failures are the same, in any order.
+            assert_eq!(actual.len(), expected.len(), "chunk_rows={chunk_rows}");
+            for failure in &expected {
+                assert!(
+                    actual.contains(failure),
+                    "chunk_rows={chunk_rows}, missing failure: {failure}"
+                );
+            }
+        }
+        expected
+    }
+
+    #[test]
+    fn streaming_satisfied() {
+        let failures =
+            streaming_failures(&BlockCircuit { bad: false }, (BLOCKS * BLOCK_ROWS) as u64);
+        assert_eq!(failures, vec![]);
+    }
+
+    #[test]
+    fn streaming_matches_in_memory() {
+        let failures = streaming_failures(&BlockCircuit { bad: true }, 0);
+        let mut kinds: Vec<_> = failures.iter().map(VerifyFailure::kind).collect();
+        kinds.sort_unstable();
+        kinds.dedup();
+        assert_eq!(
+            kinds,
+            vec![
+                "cell_not_assigned",
+                "constraint_not_satisfied",
+                "lookup",
+                "permutation",
+                "shuffle",
+            ]
+        );
+    }
+}
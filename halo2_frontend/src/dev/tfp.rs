@@ -1,11 +1,11 @@
-use std::{fmt, marker::PhantomData};
+use std::{fmt, marker::PhantomData, ops::Range};
 
 use halo2_middleware::ff::Field;
-use tracing::{debug, debug_span, span::EnteredSpan};
+use tracing::{debug, debug_span, Span};
 
 use halo2_common::circuit::{
     layouter::{RegionLayouter, SyncDeps},
-    AssignedCell, Cell, Layouter, Region, Table, Value,
+    AssignedCell, Cell, Layouter, Region, RegionIndex, Table, Value,
 };
 use halo2_common::plonk::{
     circuit::{Challenge, Column},
@@ -19,6 +19,23 @@ use halo2_middleware::circuit::{Advice, Any, Fixed, Instance};
 /// what is happening during a particular run of keygen or proving. This can be useful for
 /// identifying unexpected non-determinism or changes to a circuit.
 ///
+/// The inner floor planner (such as [`floor_planner::V1`] or [`SimpleFloorPlanner`]) is
+/// given an [`Assignment`] that passes every call through unchanged, and that emits:
+/// - a `phase` span around the synthesis of each phase,
+/// - a `ns` span for each namespace, between `push_namespace` and `pop_namespace`,
+/// - a `region` span for each region (including tables), between `enter_region` and
+///   `exit_region`, and
+/// - a `region` event when a region is exited, with the number of cells assigned in it.
+///
+/// As the spans are only emitted for the calls that reach the [`Assignment`], the
+/// measurement pass of [`floor_planner::V1`] is not included. The spans are compatible
+/// with flamegraph layers such as `tracing_flame::FlameLayer`.
+///
+/// This type is only available with the `tracing` feature.
+///
+/// [`floor_planner::V1`]: crate::circuit::floor_planner::V1
+/// [`SimpleFloorPlanner`]: crate::circuit::SimpleFloorPlanner
+///
 /// # No stability guarantees
 ///
 /// The `tracing` output is intended for use during circuit development. It should not be
@@ -93,6 +110,7 @@ impl<P: FloorPlanner> FloorPlanner for TracingFloorPlanner<P> {
         config: C::Config,
         constants: Vec<Column<Fixed>>,
     ) -> Result<(), Error> {
+        let _span = debug_span!("phase", phase = cs.current_phase()).entered();
         P::synthesize(
             &mut TracingAssignment::new(cs),
             &TracingCircuit::borrowed(circuit),
@@ -150,7 +168,6 @@ impl<'c, F: Field, C: Circuit<F>> Circuit<F> for TracingCircuit<'c, F, C> {
 /// A helper type that augments a [`Layouter`] with [`tracing`] spans and events.
 struct TracingLayouter<F: Field, L: Layouter<F>> {
     layouter: L,
-    namespace_spans: Vec<EnteredSpan>,
     _phantom: PhantomData<F>,
 }
 
@@ -158,7 +175,6 @@ impl<F: Field, L: Layouter<F>> TracingLayouter<F, L> {
     fn new(layouter: L) -> Self {
         Self {
             layouter,
-            namespace_spans: vec![],
             _phantom: PhantomData,
         }
     }
@@ -173,7 +189,7 @@ impl<F: Field, L: Layouter<F>> Layouter<F> for TracingLayouter<F, L> {
         N: Fn() -> NR,
         NR: Into<String>,
     {
-        let _span = debug_span!("region", name = name().into()).entered();
+        // We enter region spans in TracingAssignment.
         self.layouter.assign_region(name, |region| {
             let mut region = TracingRegion(region);
             let region: &mut dyn RegionLayouter<F> = &mut region;
@@ -181,6 +197,24 @@ impl<F: Field, L: Layouter<F>> Layouter<F> for TracingLayouter<F, L> {
         })
     }
 
+    fn assign_region_indexed<A, AR, N, NR>(
+        &mut self,
+        name: N,
+        mut assignment: A,
+    ) -> Result<(RegionIndex, AR), Error>
+    where
+        A: FnMut(Region<'_, F>) -> Result<AR, Error>,
+        N: Fn() -> NR,
+        NR: Into<String>,
+    {
+        // We enter region spans in TracingAssignment.
+        self.layouter.assign_region_indexed(name, |region| {
+            let mut region = TracingRegion(region);
+            let region: &mut dyn RegionLayouter<F> = &mut region;
+            assignment(region.into())
+        })
+    }
+
     fn assign_table<A, N, NR>(&mut self, name: N, assignment: A) -> Result<(), Error>
     where
         A: FnMut(Table<'_, F>) -> Result<(), Error>,
@@ -200,8 +234,16 @@ impl<F: Field, L: Layouter<F>> Layouter<F> for TracingLayouter<F, L> {
         self.layouter.constrain_instance(cell, column, row)
     }
 
-    fn get_challenge(&self, _: Challenge) -> Value<F> {
-        Value::unknown()
+    fn next_instance_slot(&mut self, column: Column<Instance>) -> Result<usize, Error> {
+        self.layouter.next_instance_slot(column)
+    }
+
+    fn get_challenge(&self, challenge: Challenge) -> Value<F> {
+        self.layouter.get_challenge(challenge)
+    }
+
+    fn current_phase(&self) -> u8 {
+        self.layouter.current_phase()
     }
 
     fn get_root(&mut self) -> &mut Self::Root {
@@ -213,14 +255,12 @@ impl<F: Field, L: Layouter<F>> Layouter<F> for TracingLayouter<F, L> {
         NR: Into<String>,
         N: FnOnce() -> NR,
     {
-        let name = name_fn().into();
-        self.namespace_spans.push(debug_span!("ns", name).entered());
-        self.layouter.push_namespace(|| name);
+        // We enter namespace spans in TracingAssignment.
+        self.layouter.push_namespace(name_fn);
     }
 
     fn pop_namespace(&mut self, gadget_name: Option<String>) {
         self.layouter.pop_namespace(gadget_name);
-        self.namespace_spans.pop();
     }
 }
 
@@ -252,11 +292,28 @@ impl<'r, F: Field> RegionLayouter<F> for TracingRegion<'r, F> {
         self.0.enable_selector(annotation, selector, offset)
     }
 
+    fn enable_selector_range<'v>(
+        &'v mut self,
+        annotation: &'v (dyn Fn() -> String + 'v),
+        selector: &Selector,
+        offsets: Range<usize>,
+    ) -> Result<(), Error> {
+        let _guard = debug_span!(
+            "enable_selector_range",
+            name = annotation(),
+            offsets = ?offsets
+        )
+        .entered();
+        debug!(target: "layouter", "Entered");
+        self.0.enable_selector_range(annotation, selector, offsets)
+    }
+
     fn name_column<'v>(
         &'v mut self,
-        _: &'v (dyn std::ops::Fn() -> std::string::String + 'v),
-        _: Column<Any>,
+        annotation: &'v (dyn std::ops::Fn() -> std::string::String + 'v),
+        column: Column<Any>,
     ) {
+        self.0.name_column(annotation, column);
     }
 
     fn assign_advice<'v>(
@@ -275,6 +332,26 @@ impl<'r, F: Field> RegionLayouter<F> for TracingRegion<'r, F> {
             .map(debug_value_and_return_cell)
     }
 
+    fn try_assign_advice<'v>(
+        &'v mut self,
+        annotation: &'v (dyn Fn() -> String + 'v),
+        column: Column<Advice>,
+        offset: usize,
+        to: &'v mut (dyn FnMut() -> Result<Value<Assigned<F>>, Error> + 'v),
+    ) -> Result<Cell, Error> {
+        let _guard = debug_span!(
+            "try_assign_advice",
+            name = annotation(),
+            column = ?column,
+            offset = offset
+        )
+        .entered();
+        debug!(target: "layouter", "Entered");
+        self.0
+            .try_assign_advice(annotation, column, offset, to)
+            .map(debug_value_and_return_cell)
+    }
+
     fn assign_advice_from_constant<'v>(
         &'v mut self,
         annotation: &'v (dyn Fn() -> String + 'v),
@@ -344,6 +421,16 @@ impl<'r, F: Field> RegionLayouter<F> for TracingRegion<'r, F> {
             .map(debug_value_and_return_cell)
     }
 
+    fn fill_advice_from_offset(
+        &mut self,
+        column: Column<Advice>,
+        offset: usize,
+        to: Value<Assigned<F>>,
+    ) -> Result<(), Error> {
+        debug!(target: "fill_advice_from_offset", column = ?column, offset = offset);
+        self.0.fill_advice_from_offset(column, offset, to)
+    }
+
     fn constrain_constant(&mut self, cell: Cell, constant: Assigned<F>) -> Result<(), Error> {
         debug!(target: "constrain_constant", cell = ?cell, constant = ?constant);
         self.0.constrain_constant(cell, constant)
@@ -355,10 +442,40 @@ impl<'r, F: Field> RegionLayouter<F> for TracingRegion<'r, F> {
     }
 }
 
+/// A span that is entered until it is dropped.
+///
+/// Unlike [`tracing::span::EnteredSpan`], this is `Send` and `Sync`, so that it can be held
+/// by an [`Assignment`] when the `thread-safe-region` feature is enabled.
+struct HeldSpan(Span);
+
+impl HeldSpan {
+    fn enter(span: Span) -> Self {
+        span.with_subscriber(|(id, dispatch)| dispatch.enter(id));
+        Self(span)
+    }
+}
+
+impl Drop for HeldSpan {
+    fn drop(&mut self) {
+        self.0.with_subscriber(|(id, dispatch)| dispatch.exit(id));
+    }
+}
+
+/// The region being assigned by a [`TracingAssignment`].
+struct TracingRegionState {
+    name: String,
+    advice_cells: usize,
+    fixed_cells: usize,
+    selectors: usize,
+    copies: usize,
+    _span: HeldSpan,
+}
+
 /// A helper type that augments an [`Assignment`] with [`tracing`] spans and events.
 struct TracingAssignment<'cs, F: Field, CS: Assignment<F>> {
     cs: &'cs mut CS,
-    in_region: bool,
+    region: Option<TracingRegionState>,
+    namespace_spans: Vec<HeldSpan>,
     _phantom: PhantomData<F>,
 }
 
@@ -366,10 +483,21 @@ impl<'cs, F: Field, CS: Assignment<F>> TracingAssignment<'cs, F, CS> {
     fn new(cs: &'cs mut CS) -> Self {
         Self {
             cs,
-            in_region: false,
+            region: None,
+            namespace_spans: vec![],
             _phantom: PhantomData,
         }
     }
+
+    fn in_region(&self) -> bool {
+        self.region.is_some()
+    }
+
+    fn count(&mut self, f: impl FnOnce(&mut TracingRegionState)) {
+        if let Some(region) = self.region.as_mut() {
+            f(region);
+        }
+    }
 }
 
 impl<'cs, F: Field, CS: Assignment<F>> Assignment<F> for TracingAssignment<'cs, F, CS> {
@@ -378,20 +506,38 @@ impl<'cs, F: Field, CS: Assignment<F>> Assignment<F> for TracingAssignment<'cs,
         NR: Into<String>,
         N: FnOnce() -> NR,
     {
-        self.in_region = true;
-        self.cs.enter_region(name_fn);
-    }
-
-    fn annotate_column<A, AR>(&mut self, _: A, _: Column<Any>)
+        let name = name_fn().into();
+        self.region = Some(TracingRegionState {
+            _span: HeldSpan::enter(debug_span!("region", name = name.as_str())),
+            name: name.clone(),
+            advice_cells: 0,
+            fixed_cells: 0,
+            selectors: 0,
+            copies: 0,
+        });
+        self.cs.enter_region(|| name);
+    }
+
+    fn annotate_column<A, AR>(&mut self, annotation: A, column: Column<Any>)
     where
         A: FnOnce() -> AR,
         AR: Into<String>,
     {
+        self.cs.annotate_column(annotation, column)
     }
 
     fn exit_region(&mut self) {
         self.cs.exit_region();
-        self.in_region = false;
+        if let Some(region) = self.region.take() {
+            debug!(
+                target: "region",
+                name = region.name,
+                advice_cells = region.advice_cells,
+                fixed_cells = region.fixed_cells,
+                selectors = region.selectors,
+                copies = region.copies,
+            );
+        }
     }
 
     fn enable_selector<A, AR>(
@@ -405,14 +551,36 @@ impl<'cs, F: Field, CS: Assignment<F>> Assignment<F> for TracingAssignment<'cs,
         AR: Into<String>,
     {
         let annotation = annotation().into();
-        if self.in_region {
+        if self.in_region() {
             debug!(target: "position", row = row);
         } else {
             debug!(target: "enable_selector", name = annotation, row = row);
         }
+        self.count(|region| region.selectors += 1);
         self.cs.enable_selector(|| annotation, selector, row)
     }
 
+    fn enable_selector_range<A, AR>(
+        &mut self,
+        annotation: A,
+        selector: &Selector,
+        rows: Range<usize>,
+    ) -> Result<(), Error>
+    where
+        A: Fn() -> AR,
+        AR: Into<String>,
+    {
+        let annotation = annotation().into();
+        if self.in_region() {
+            debug!(target: "position", rows = ?rows);
+        } else {
+            debug!(target: "enable_selector_range", name = annotation, rows = ?rows);
+        }
+        self.count(|region| region.selectors += rows.len());
+        self.cs
+            .enable_selector_range(|| annotation.clone(), selector, rows)
+    }
+
     fn query_instance(&self, column: Column<Instance>, row: usize) -> Result<Value<F>, Error> {
         let _guard = debug_span!("positioned").entered();
         debug!(target: "query_instance", column = ?column, row = row);
@@ -433,14 +601,54 @@ impl<'cs, F: Field, CS: Assignment<F>> Assignment<F> for TracingAssignment<'cs,
         AR: Into<String>,
     {
         let annotation = annotation().into();
-        if self.in_region {
+        if self.in_region() {
             debug!(target: "position", row = row);
         } else {
             debug!(target: "assign_advice", name = annotation, column = ?column, row = row);
         }
+        self.count(|region| region.advice_cells += 1);
         self.cs.assign_advice(|| annotation, column, row, to)
     }
 
+    fn try_assign_advice<V, VR, A, AR>(
+        &mut self,
+        annotation: A,
+        column: Column<Advice>,
+        row: usize,
+        to: V,
+    ) -> Result<(), Error>
+    where
+        V: FnOnce() -> Result<Value<VR>, Error>,
+        VR: Into<Assigned<F>>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        let annotation = annotation().into();
+        if self.in_region() {
+            debug!(target: "position", row = row);
+        } else {
+            debug!(target: "try_assign_advice", name = annotation, column = ?column, row = row);
+        }
+        self.count(|region| region.advice_cells += 1);
+        self.cs.try_assign_advice(|| annotation, column, row, to)
+    }
+
+    fn assign_advice_column(
+        &mut self,
+        column: Column<Advice>,
+        start_row: usize,
+        values: &[Value<Assigned<F>>],
+    ) -> Result<(), Error> {
+        let rows = start_row..start_row + values.len();
+        if self.in_region() {
+            debug!(target: "position", rows = ?rows);
+        } else {
+            debug!(target: "assign_advice_column", column = ?column, rows = ?rows);
+        }
+        self.count(|region| region.advice_cells += values.len());
+        self.cs.assign_advice_column(column, start_row, values)
+    }
+
     fn assign_fixed<V, VR, A, AR>(
         &mut self,
         annotation: A,
@@ -455,11 +663,12 @@ impl<'cs, F: Field, CS: Assignment<F>> Assignment<F> for TracingAssignment<'cs,
         AR: Into<String>,
     {
         let annotation = annotation().into();
-        if self.in_region {
+        if self.in_region() {
             debug!(target: "position", row = row);
         } else {
             debug!(target: "assign_fixed", name = annotation, column = ?column, row = row);
         }
+        self.count(|region| region.fixed_cells += 1);
         self.cs.assign_fixed(|| annotation, column, row, to)
     }
 
@@ -478,6 +687,7 @@ impl<'cs, F: Field, CS: Assignment<F>> Assignment<F> for TracingAssignment<'cs,
             right_column = ?right_column,
             right_row = right_row,
         );
+        self.count(|region| region.copies += 1);
         self.cs.copy(left_column, left_row, right_column, right_row)
     }
 
@@ -492,8 +702,27 @@ impl<'cs, F: Field, CS: Assignment<F>> Assignment<F> for TracingAssignment<'cs,
         self.cs.fill_from_row(column, row, to)
     }
 
-    fn get_challenge(&self, _: Challenge) -> Value<F> {
-        Value::unknown()
+    fn fill_advice_from_row(
+        &mut self,
+        column: Column<Advice>,
+        from_row: usize,
+        to: Value<Assigned<F>>,
+    ) -> Result<(), Error> {
+        let _guard = debug_span!("positioned").entered();
+        debug!(target: "fill_advice_from_row", column = ?column, row = from_row);
+        self.cs.fill_advice_from_row(column, from_row, to)
+    }
+
+    fn get_challenge(&self, challenge: Challenge) -> Value<F> {
+        self.cs.get_challenge(challenge)
+    }
+
+    fn current_phase(&self) -> u8 {
+        self.cs.current_phase()
+    }
+
+    fn usable_rows(&self) -> Option<Range<usize>> {
+        self.cs.usable_rows()
     }
 
     fn push_namespace<NR, N>(&mut self, name_fn: N)
@@ -501,12 +730,146 @@ impl<'cs, F: Field, CS: Assignment<F>> Assignment<F> for TracingAssignment<'cs,
         NR: Into<String>,
         N: FnOnce() -> NR,
     {
-        // We enter namespace spans in TracingLayouter.
-        self.cs.push_namespace(name_fn)
+        let name = name_fn().into();
+        self.namespace_spans
+            .push(HeldSpan::enter(debug_span!("ns", name = name.as_str())));
+        self.cs.push_namespace(|| name)
     }
 
     fn pop_namespace(&mut self, gadget_name: Option<String>) {
         self.cs.pop_namespace(gadget_name);
-        // We exit namespace spans in TracingLayouter.
+        self.namespace_spans.pop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2curves::pasta::Fp;
+
+    use super::*;
+    use crate::circuit::{floor_planner::V1, SimpleFloorPlanner};
+    use crate::dev::MockProver;
+    use halo2_common::plonk::{FirstPhase, SecondPhase};
+    use halo2_middleware::poly::Rotation;
+
+    #[derive(Clone)]
+    struct TestConfig {
+        a: Column<Advice>,
+        b: Column<Advice>,
+        c: Column<Fixed>,
+        q: Selector,
+        challenge: Challenge,
+    }
+
+    struct TestCircuit<P>(PhantomData<P>);
+
+    impl<P: FloorPlanner> Circuit<Fp> for TestCircuit<P> {
+        type Config = TestConfig;
+        type FloorPlanner = P;
+        #[cfg(feature = "circuit-params")]
+        type Params = ();
+
+        fn without_witnesses(&self) -> Self {
+            Self(PhantomData)
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> TestConfig {
+            let a = meta.advice_column_in(FirstPhase);
+            let b = meta.advice_column_in(SecondPhase);
+            let c = meta.fixed_column();
+            let q = meta.selector();
+            let challenge = meta.challenge_usable_after(FirstPhase);
+            meta.enable_equality(a);
+
+            meta.create_gate("b = (a + c) * challenge", |meta| {
+                let a = meta.query_advice(a, Rotation::cur());
+                let b = meta.query_advice(b, Rotation::cur());
+                let c = meta.query_fixed(c, Rotation::cur());
+                let challenge = meta.query_challenge(challenge);
+                let q = meta.query_selector(q);
+                vec![q * ((a + c) * challenge - b)]
+            });
+
+            TestConfig {
+                a,
+                b,
+                c,
+                q,
+                challenge,
+            }
+        }
+
+        fn synthesize(
+            &self,
+            config: TestConfig,
+            mut layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            let challenge = layouter.get_challenge(config.challenge);
+            let first = layouter.namespace(|| "gadget").assign_region(
+                || "first",
+                |mut region| {
+                    region.name_column(|| "a", config.a);
+                    let mut first = None;
+                    for offset in 0..3 {
+                        config.q.enable(&mut region, offset)?;
+                        let a = Value::known(Fp::from(offset as u64 + 1));
+                        let c = Fp::from(10);
+                        let cell = region.assign_advice(|| "a", config.a, offset, || a)?;
+                        region.assign_fixed(|| "c", config.c, offset, || Value::known(c))?;
+                        region.assign_advice(
+                            || "b",
+                            config.b,
+                            offset,
+                            || (a + Value::known(c)) * challenge,
+                        )?;
+                        first.get_or_insert(cell);
+                    }
+                    Ok(first.unwrap())
+                },
+            )?;
+
+            layouter.assign_region(
+                || "second",
+                |mut region| {
+                    first.copy_advice(|| "a", &mut region, config.a, 0)?;
+                    Ok(())
+                },
+            )
+        }
+    }
+
+    fn assert_pass_through<P: FloorPlanner>() {
+        const K: u32 = 5;
+
+        let plain = MockProver::run(K, &TestCircuit::<P>(PhantomData), vec![]).unwrap();
+        let traced = MockProver::run(
+            K,
+            &TestCircuit::<TracingFloorPlanner<P>>(PhantomData),
+            vec![],
+        )
+        .unwrap();
+        assert_eq!(plain.verify(), Ok(()));
+        assert_eq!(traced.verify(), Ok(()));
+
+        let diff = plain.diff(&traced);
+        assert!(diff.is_empty(), "{diff}");
+
+        assert_eq!(plain.regions.len(), traced.regions.len());
+        for (plain, traced) in plain.regions.iter().zip(&traced.regions) {
+            assert_eq!(plain.name, traced.name);
+            assert_eq!(plain.rows, traced.rows);
+            assert_eq!(plain.annotations, traced.annotations);
+            assert_eq!(plain.enabled_selectors, traced.enabled_selectors);
+        }
+    }
+
+    #[test]
+    fn pass_through_simple_floor_planner() {
+        assert_pass_through::<SimpleFloorPlanner>();
+    }
+
+    #[test]
+    fn pass_through_v1() {
+        assert_pass_through::<V1>();
     }
 }
@@ -0,0 +1,348 @@
+//! Developer tools for finding assigned cells that no constraint depends on.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use halo2_common::plonk::{circuit::Column, Expression};
+use halo2_middleware::circuit::{Advice, Any};
+use halo2_middleware::ff::Field;
+use halo2_middleware::poly::Rotation;
+
+use super::{CellValue, FailureLocation, MockProver};
+
+/// An advice cell that is assigned, but is not constrained by any gate, lookup, shuffle
+/// or equality constraint. Its value can be changed freely without the proof failing.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UnconstrainedCell {
+    /// The column of the cell.
+    pub column: Column<Advice>,
+    /// The location of the cell.
+    pub location: FailureLocation,
+}
+
+impl fmt::Display for UnconstrainedCell {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Cell in column {} {} is assigned but not constrained",
+            self.location
+                .get_debug_column(Column::<Any>::from(self.column).into()),
+            self.location,
+        )
+    }
+}
+
+/// Unconstrained cells that are expected, and should not be reported by
+/// [`MockProver::analyze_unconstrained_with`].
+///
+/// Some cells are intentionally left free, for example padding cells.
+#[derive(Clone, Debug, Default)]
+pub struct UnconstrainedAllowlist {
+    columns: HashSet<Column<Advice>>,
+    regions: HashMap<String, usize>,
+}
+
+impl UnconstrainedAllowlist {
+    /// Creates an empty allowlist.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Allows all the cells of `column` to be unconstrained.
+    pub fn allow_column(mut self, column: Column<Advice>) -> Self {
+        self.columns.insert(column);
+        self
+    }
+
+    /// Allows up to `max_cells` cells to be unconstrained in each region named `name`.
+    ///
+    /// If a region has more unconstrained cells than that, all of them are reported.
+    pub fn allow_region(mut self, name: impl Into<String>, max_cells: usize) -> Self {
+        self.regions.insert(name.into(), max_cells);
+        self
+    }
+}
+
+impl<F: Field> MockProver<F> {
+    /// Returns the advice cells that are assigned, but that are not constrained by any
+    /// gate, lookup, shuffle or equality constraint.
+    ///
+    /// See [`MockProver::analyze_unconstrained_with`].
+    pub fn analyze_unconstrained(&self) -> Vec<UnconstrainedCell> {
+        self.analyze_unconstrained_with(&UnconstrainedAllowlist::default())
+    }
+
+    /// Returns the advice cells that are assigned, but that are not constrained by any
+    /// gate, lookup, shuffle or equality constraint, except those in `allowlist`.
+    ///
+    /// A cell is constrained by a gate if a polynomial of the gate queries it at a row
+    /// where the terms containing the query are not disabled by a zero fixed value (such
+    /// as a disabled selector). The same holds for the expressions of lookups and
+    /// shuffles. A cell is constrained by an equality constraint if it is copied to or
+    /// from another cell.
+    ///
+    /// This analysis only finds cells that no constraint depends on; it does not check
+    /// that the constraints that depend on a cell determine its value.
+    pub fn analyze_unconstrained_with(
+        &self,
+        allowlist: &UnconstrainedAllowlist,
+    ) -> Vec<UnconstrainedCell> {
+        let n = self.n as usize;
+        let mut constrained = vec![vec![false; n]; self.advice.len()];
+
+        for row in self.usable_rows.clone() {
+            let expressions = self
+                .cs
+                .gates
+                .iter()
+                .flat_map(|gate| gate.polynomials())
+                .chain(self.cs.lookups.iter().flat_map(|lookup| {
+                    lookup
+                        .input_expressions
+                        .iter()
+                        .chain(&lookup.table_expressions)
+                }))
+                .chain(self.cs.shuffles.iter().flat_map(|shuffle| {
+                    shuffle
+                        .input_expressions
+                        .iter()
+                        .chain(&shuffle.shuffle_expressions)
+                }));
+            for expression in expressions {
+                self.mark_queried_cells(expression, row, &mut constrained);
+            }
+        }
+
+        for (cell_a, cell_b) in &self.permutation.copies {
+            for cell in [cell_a, cell_b] {
+                if let Any::Advice(_) = cell.column.column_type {
+                    constrained[cell.column.index][cell.row] = true;
+                }
+            }
+        }
+
+        let mut cells = vec![];
+        for (column_index, values) in self.advice.iter().enumerate() {
+            let column = Column::new(
+                column_index,
+                Advice::new(self.cs.advice_column_phase[column_index].0),
+            );
+            if allowlist.columns.contains(&column) {
+                continue;
+            }
+            for row in self.usable_rows.clone() {
+                if matches!(values[row], CellValue::Assigned(_)) && !constrained[column_index][row]
+                {
+                    cells.push(UnconstrainedCell {
+                        column,
+                        location: FailureLocation::find(
+                            &self.regions,
+                            &self.cs.general_column_annotations,
+                            row,
+                            Some(column.into()).into_iter().collect(),
+                        ),
+                    });
+                }
+            }
+        }
+
+        // Drop the cells of allowed regions that are within their threshold.
+        let mut region_counts = HashMap::<usize, usize>::new();
+        for cell in &cells {
+            if let FailureLocation::InRegion { region, .. } = &cell.location {
+                *region_counts.entry(region.index).or_default() += 1;
+            }
+        }
+        cells.retain(|cell| match &cell.location {
+            FailureLocation::InRegion { region, .. } => allowlist
+                .regions
+                .get(&region.name)
+                .map_or(true, |max_cells| region_counts[&region.index] > *max_cells),
+            FailureLocation::OutsideRegion { .. } => true,
+        });
+
+        cells
+    }
+
+    fn rotated_row(&self, row: usize, rotation: Rotation) -> usize {
+        let n = self.n as i32;
+        ((row as i32 + n + rotation.0) % n) as usize
+    }
+
+    /// Returns `true` if `expression` evaluates to zero at `row` because of its fixed
+    /// values, regardless of the values of the other cells it queries.
+    pub(super) fn vanishes(&self, expression: &Expression<F>, row: usize) -> bool {
+        match expression {
+            Expression::Constant(constant) => constant.is_zero_vartime(),
+            Expression::Selector(_) => {
+                panic!("virtual selectors are removed during optimization")
+            }
+            Expression::Fixed(query) => {
+                match self.fixed[query.column_index][self.rotated_row(row, query.rotation)] {
+                    CellValue::Unassigned => true,
+                    CellValue::Assigned(value) => value.is_zero_vartime(),
+                    CellValue::Poison(_) => false,
+                }
+            }
+            Expression::Advice(_) | Expression::Instance(_) | Expression::Challenge(_) => false,
+            Expression::Negated(a) => self.vanishes(a, row),
+            Expression::Sum(a, b) => self.vanishes(a, row) && self.vanishes(b, row),
+            Expression::Product(a, b) => self.vanishes(a, row) || self.vanishes(b, row),
+            Expression::Scaled(a, scalar) => scalar.is_zero_vartime() || self.vanishes(a, row),
+        }
+    }
+
+    /// Marks the advice cells queried by the terms of `expression` that do not vanish at
+    /// `row`.
+    fn mark_queried_cells(
+        &self,
+        expression: &Expression<F>,
+        row: usize,
+        constrained: &mut [Vec<bool>],
+    ) {
+        if self.vanishes(expression, row) {
+            return;
+        }
+        match expression {
+            Expression::Advice(query) => {
+                constrained[query.column_index][self.rotated_row(row, query.rotation)] = true;
+            }
+            Expression::Negated(a) | Expression::Scaled(a, _) => {
+                self.mark_queried_cells(a, row, constrained)
+            }
+            Expression::Sum(a, b) | Expression::Product(a, b) => {
+                self.mark_queried_cells(a, row, constrained);
+                self.mark_queried_cells(b, row, constrained);
+            }
+            _ => (),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2curves::pasta::Fp;
+
+    use super::*;
+    use crate::circuit::{Layouter, SimpleFloorPlanner, Value};
+    use halo2_common::plonk::{Circuit, ConstraintSystem, Error, Selector};
+
+    #[test]
+    fn forgotten_constraint() {
+        const K: u32 = 5;
+
+        #[derive(Clone)]
+        struct MulConfig {
+            a: Column<Advice>,
+            b: Column<Advice>,
+            c: Column<Advice>,
+            s: Selector,
+        }
+
+        struct MulCircuit;
+
+        impl Circuit<Fp> for MulCircuit {
+            type Config = MulConfig;
+            type FloorPlanner = SimpleFloorPlanner;
+            #[cfg(feature = "circuit-params")]
+            type Params = ();
+
+            fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+                let a = meta.advice_column();
+                let b = meta.advice_column();
+                let c = meta.advice_column();
+                let s = meta.selector();
+                meta.enable_equality(c);
+
+                meta.create_gate("mul", |meta| {
+                    let a = meta.query_advice(a, Rotation::cur());
+                    let b = meta.query_advice(b, Rotation::cur());
+                    let c = meta.query_advice(c, Rotation::cur());
+                    let s = meta.query_selector(s);
+                    vec![s * (a * b - c)]
+                });
+
+                MulConfig { a, b, c, s }
+            }
+
+            fn without_witnesses(&self) -> Self {
+                Self
+            }
+
+            fn synthesize(
+                &self,
+                config: Self::Config,
+                mut layouter: impl Layouter<Fp>,
+            ) -> Result<(), Error> {
+                let product = layouter.assign_region(
+                    || "mul",
+                    |mut region| {
+                        let mut product = None;
+                        for offset in 0..2 {
+                            // BUG: the selector is only enabled on the first row.
+                            if offset == 0 {
+                                config.s.enable(&mut region, offset)?;
+                            }
+                            let one = || Value::known(Fp::ONE);
+                            region.assign_advice(|| "a", config.a, offset, one)?;
+                            region.assign_advice(|| "b", config.b, offset, one)?;
+                            product = Some(region.assign_advice(|| "c", config.c, offset, one)?);
+                        }
+                        Ok(product.unwrap())
+                    },
+                )?;
+
+                layouter.assign_region(
+                    || "padding",
+                    |mut region| {
+                        // The output of the second multiplication is copied, but its
+                        // inputs are free.
+                        product.copy_advice(|| "c", &mut region, config.c, 0)?;
+                        region.assign_advice(|| "pad", config.a, 0, || Value::known(Fp::ZERO))?;
+                        Ok(())
+                    },
+                )
+            }
+        }
+
+        let prover = MockProver::run(K, &MulCircuit, vec![]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+
+        let mul_cells = |cells: &[UnconstrainedCell]| {
+            cells
+                .iter()
+                .filter_map(|cell| match &cell.location {
+                    FailureLocation::InRegion { region, offset } if region.name == "mul" => {
+                        Some((cell.column.index(), *offset))
+                    }
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+        };
+
+        // The inputs of the second multiplication, and the padding cell, are free.
+        let cells = prover.analyze_unconstrained();
+        assert_eq!(mul_cells(&cells), vec![(0, 1), (1, 1)]);
+        assert_eq!(cells.len(), 3);
+        assert_eq!(
+            cells[0].to_string(),
+            "Cell in column Column('Advice', 0 - ) in Region 0 ('mul') at offset 1 is assigned but not constrained"
+        );
+
+        // The padding cell can be allowed.
+        let allowlist = UnconstrainedAllowlist::new().allow_region("padding", 1);
+        let cells = prover.analyze_unconstrained_with(&allowlist);
+        assert_eq!(mul_cells(&cells), vec![(0, 1), (1, 1)]);
+        assert_eq!(cells.len(), 2);
+
+        // The threshold of an allowed region must cover all of its free cells.
+        let allowlist = UnconstrainedAllowlist::new().allow_region("mul", 1);
+        assert_eq!(prover.analyze_unconstrained_with(&allowlist).len(), 3);
+
+        // Columns can be allowed as a whole.
+        let allowlist = UnconstrainedAllowlist::new()
+            .allow_column(Column::new(0, Advice::new(0)))
+            .allow_column(Column::new(1, Advice::new(0)));
+        assert!(prover.analyze_unconstrained_with(&allowlist).is_empty());
+    }
+}
@@ -0,0 +1,185 @@
+//! Developer tools for finding gates and selectors that are never enabled.
+
+use std::collections::HashSet;
+
+use halo2_common::plonk::Selector;
+use halo2_middleware::ff::Field;
+
+use super::MockProver;
+
+/// Gates and selectors that are intentionally never enabled, and should not be reported
+/// by [`MockProver::unused_gates_with`] and [`MockProver::unused_selectors_with`].
+#[derive(Clone, Debug, Default)]
+pub struct UnusedAllowlist {
+    gates: HashSet<String>,
+    selectors: HashSet<usize>,
+}
+
+impl UnusedAllowlist {
+    /// Creates an empty allowlist.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Allows the gate named `name` to be disabled on all rows.
+    pub fn allow_gate(mut self, name: impl Into<String>) -> Self {
+        self.gates.insert(name.into());
+        self
+    }
+
+    /// Allows `selector` to never be enabled.
+    pub fn allow_selector(mut self, selector: Selector) -> Self {
+        self.selectors.insert(selector.index());
+        self
+    }
+}
+
+impl<F: Field> MockProver<F> {
+    /// Returns the names of the gates that are disabled on all usable rows.
+    ///
+    /// See [`MockProver::unused_gates_with`].
+    pub fn unused_gates(&self) -> Vec<String> {
+        self.unused_gates_with(&UnusedAllowlist::default())
+    }
+
+    /// Returns the names of the gates that are disabled on all usable rows, except those
+    /// in `allowlist`.
+    ///
+    /// A gate is disabled at a row if all of its polynomials evaluate to zero there
+    /// because of their fixed values, such as when the simple selector that controls the
+    /// gate is not enabled, or the fixed column (or complex selector) that the gate is
+    /// multiplied by is zero. Such a gate still adds to the degree of the circuit and to
+    /// the cost of evaluating the quotient polynomial, and usually indicates a missing
+    /// call to [`Selector::enable`].
+    pub fn unused_gates_with(&self, allowlist: &UnusedAllowlist) -> Vec<String> {
+        self.cs
+            .gates
+            .iter()
+            .filter(|gate| !allowlist.gates.contains(gate.name()))
+            .filter(|gate| {
+                self.usable_rows.clone().all(|row| {
+                    gate.polynomials()
+                        .iter()
+                        .all(|poly| self.vanishes(poly, row))
+                })
+            })
+            .map(|gate| gate.name().to_string())
+            .collect()
+    }
+
+    /// Returns the indices of the selectors allocated in `configure` that are never
+    /// enabled.
+    ///
+    /// See [`MockProver::unused_selectors_with`].
+    pub fn unused_selectors(&self) -> Vec<usize> {
+        self.unused_selectors_with(&UnusedAllowlist::default())
+    }
+
+    /// Returns the indices of the selectors allocated in `configure` that are never
+    /// enabled, except those in `allowlist`.
+    ///
+    /// The indices are those returned by [`Selector::index`].
+    pub fn unused_selectors_with(&self, allowlist: &UnusedAllowlist) -> Vec<usize> {
+        self.selectors
+            .iter()
+            .enumerate()
+            .filter(|(index, enabled)| {
+                !allowlist.selectors.contains(index) && !enabled.iter().any(|enabled| *enabled)
+            })
+            .map(|(index, _)| index)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_middleware::poly::Rotation;
+    use halo2curves::pasta::Fp;
+
+    use super::*;
+    use crate::circuit::{Layouter, SimpleFloorPlanner, Value};
+    use halo2_common::plonk::{circuit::Column, Circuit, ConstraintSystem, Error};
+    use halo2_middleware::circuit::Advice;
+
+    #[test]
+    fn never_enabled_gate() {
+        const K: u32 = 5;
+
+        #[derive(Clone)]
+        struct TestConfig {
+            a: Column<Advice>,
+            b: Column<Advice>,
+            s_add: Selector,
+            s_mul: Selector,
+        }
+
+        struct TestCircuit;
+
+        impl Circuit<Fp> for TestCircuit {
+            type Config = TestConfig;
+            type FloorPlanner = SimpleFloorPlanner;
+            #[cfg(feature = "circuit-params")]
+            type Params = ();
+
+            fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+                let a = meta.advice_column();
+                let b = meta.advice_column();
+                let s_add = meta.selector();
+                let s_mul = meta.selector();
+                // This selector is allocated, but not used anywhere.
+                let _s_unused = meta.complex_selector();
+
+                meta.create_gate("add", |meta| {
+                    let a = meta.query_advice(a, Rotation::cur());
+                    let b = meta.query_advice(b, Rotation::cur());
+                    let s_add = meta.query_selector(s_add);
+                    vec![s_add * (a - b)]
+                });
+
+                // BUG: the selector of this gate is never enabled.
+                meta.create_gate("mul", |meta| {
+                    let a = meta.query_advice(a, Rotation::cur());
+                    let b = meta.query_advice(b, Rotation::cur());
+                    let s_mul = meta.query_selector(s_mul);
+                    vec![s_mul * (a * b - b)]
+                });
+
+                TestConfig { a, b, s_add, s_mul }
+            }
+
+            fn without_witnesses(&self) -> Self {
+                Self
+            }
+
+            fn synthesize(
+                &self,
+                config: Self::Config,
+                mut layouter: impl Layouter<Fp>,
+            ) -> Result<(), Error> {
+                layouter.assign_region(
+                    || "add",
+                    |mut region| {
+                        config.s_add.enable(&mut region, 0)?;
+                        let one = || Value::known(Fp::ONE);
+                        region.assign_advice(|| "a", config.a, 0, one)?;
+                        region.assign_advice(|| "b", config.b, 0, one)?;
+                        Ok(())
+                    },
+                )
+            }
+        }
+
+        let prover = MockProver::run(K, &TestCircuit, vec![]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+
+        assert_eq!(prover.unused_gates(), vec!["mul".to_string()]);
+        assert_eq!(prover.unused_selectors(), vec![1, 2]);
+
+        let config = TestCircuit::configure(&mut ConstraintSystem::default());
+        let allowlist = UnusedAllowlist::new()
+            .allow_gate("mul")
+            .allow_selector(config.s_mul);
+        assert!(prover.unused_gates_with(&allowlist).is_empty());
+        assert_eq!(prover.unused_selectors_with(&allowlist), vec![2]);
+    }
+}
@@ -0,0 +1,218 @@
+//! Read access to the values assigned by a circuit, by region.
+
+use std::ops::Range;
+
+use halo2_common::plonk::circuit::Column;
+use halo2_middleware::circuit::{Advice, Any};
+use halo2_middleware::ff::Field;
+
+use super::{CellValue, MockProver, Region};
+
+/// A view of the cells assigned in a region of a [`MockProver`].
+///
+/// This is returned by [`MockProver::region`], [`MockProver::region_at`] and
+/// [`MockProver::regions_named`].
+#[derive(Clone, Copy, Debug)]
+pub struct RegionView<'a, F: Field> {
+    prover: &'a MockProver<F>,
+    index: usize,
+    region: &'a Region,
+}
+
+impl<'a, F: Field> RegionView<'a, F> {
+    /// Returns the index of the region, in the order in which regions were assigned.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Returns the name of the region.
+    pub fn name(&self) -> &'a str {
+        &self.region.name
+    }
+
+    /// Returns the absolute rows of the region, from its first to its last assigned row,
+    /// or `None` if no cell was assigned in the region.
+    pub fn rows(&self) -> Option<Range<usize>> {
+        self.region.rows.map(|(start, end)| start..end + 1)
+    }
+
+    /// Returns the columns in which the region assigned cells, ordered by type and index.
+    pub fn columns(&self) -> Vec<Column<Any>> {
+        let mut columns: Vec<_> = self.region.columns.iter().copied().collect();
+        columns.sort_by_key(|column| (*column.column_type(), column.index()));
+        columns
+    }
+
+    /// Returns the value of the cell of `column` at `offset` (relative to the first row
+    /// of the region), or `None` if the region did not assign this cell or its value is
+    /// unknown.
+    pub fn cell(&self, column: impl Into<Column<Any>>, offset: usize) -> Option<F> {
+        let column = column.into();
+        let row = self.region.rows?.0 + offset;
+        if !self.region.cells.contains_key(&(column, row)) {
+            return None;
+        }
+        let value = match column.column_type() {
+            Any::Advice(_) => self.prover.advice[column.index()][row],
+            Any::Fixed => self.prover.fixed[column.index()][row],
+            Any::Instance => return None,
+        };
+        match value {
+            CellValue::Assigned(value) => Some(value),
+            CellValue::Unassigned | CellValue::Poison(_) => None,
+        }
+    }
+}
+
+impl<F: Field> MockProver<F> {
+    /// Returns the first region named `name`.
+    ///
+    /// Region names are not required to be unique; see [`MockProver::regions_named`] and
+    /// [`MockProver::region_at`].
+    pub fn region(&self, name: &str) -> Option<RegionView<'_, F>> {
+        self.regions_named(name).next()
+    }
+
+    /// Returns the region at `index`, in the order in which regions were assigned.
+    pub fn region_at(&self, index: usize) -> Option<RegionView<'_, F>> {
+        self.regions.get(index).map(|region| RegionView {
+            prover: self,
+            index,
+            region,
+        })
+    }
+
+    /// Returns the regions named `name`, in the order in which they were assigned.
+    pub fn regions_named<'a>(&'a self, name: &'a str) -> impl Iterator<Item = RegionView<'a, F>> {
+        self.regions
+            .iter()
+            .enumerate()
+            .filter(move |(_, region)| region.name == name)
+            .map(move |(index, region)| RegionView {
+                prover: self,
+                index,
+                region,
+            })
+    }
+
+    /// Returns the value assigned to the advice cell of `column` at the absolute `row`,
+    /// or `None` if the cell is unassigned or out of bounds.
+    pub fn advice_value(&self, column: Column<Advice>, row: usize) -> Option<F> {
+        match self.advice.get(column.index())?.get(row)? {
+            CellValue::Assigned(value) => Some(*value),
+            CellValue::Unassigned | CellValue::Poison(_) => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_middleware::poly::Rotation;
+    use halo2curves::pasta::Fp;
+
+    use super::*;
+    use crate::circuit::{Layouter, SimpleFloorPlanner, Value};
+    use halo2_common::plonk::{Circuit, ConstraintSystem, Error, Selector};
+    use halo2_middleware::circuit::Fixed;
+
+    #[derive(Clone)]
+    struct RoundConfig {
+        a: Column<Advice>,
+        c: Column<Fixed>,
+        s: Selector,
+    }
+
+    struct RoundCircuit;
+
+    impl Circuit<Fp> for RoundCircuit {
+        type Config = RoundConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+        #[cfg(feature = "circuit-params")]
+        type Params = ();
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let a = meta.advice_column();
+            let c = meta.fixed_column();
+            let s = meta.selector();
+
+            meta.create_gate("add constant", |meta| {
+                let cur = meta.query_advice(a, Rotation::cur());
+                let next = meta.query_advice(a, Rotation::next());
+                let c = meta.query_fixed(c, Rotation::cur());
+                let s = meta.query_selector(s);
+                vec![s * (cur + c - next)]
+            });
+
+            RoundConfig { a, c, s }
+        }
+
+        fn without_witnesses(&self) -> Self {
+            Self
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            let mut value = Fp::ONE;
+            for round in 0..3 {
+                layouter.assign_region(
+                    || "round",
+                    |mut region| {
+                        config.s.enable(&mut region, 0)?;
+                        let constant = Fp::from(round + 2);
+                        region.assign_advice(|| "in", config.a, 0, || Value::known(value))?;
+                        region.assign_fixed(|| "c", config.c, 0, || Value::known(constant))?;
+                        region.assign_advice(
+                            || "out",
+                            config.a,
+                            1,
+                            || Value::known(value + constant),
+                        )?;
+                        Ok(())
+                    },
+                )?;
+                value += Fp::from(round + 2);
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn region_cell_values() {
+        let prover = MockProver::run(5, &RoundCircuit, vec![]).unwrap();
+        prover.assert_satisfied();
+        let config = RoundCircuit::configure(&mut ConstraintSystem::default());
+
+        // Region names repeat, and `region` returns the first one.
+        let rounds: Vec<_> = prover.regions_named("round").collect();
+        assert_eq!(rounds.len(), 3);
+        assert!(prover.regions_named("missing").next().is_none());
+        assert!(prover.region("missing").is_none());
+        assert_eq!(prover.region("round").unwrap().index(), 0);
+
+        // 1 + 2 = 3, 3 + 3 = 6, 6 + 4 = 10
+        let outputs: Vec<_> = rounds
+            .iter()
+            .map(|round| round.cell(config.a, 1).unwrap())
+            .collect();
+        assert_eq!(outputs, vec![Fp::from(3), Fp::from(6), Fp::from(10)]);
+
+        let last = prover.region_at(2).unwrap();
+        assert_eq!(last.name(), "round");
+        assert_eq!(last.cell(config.c, 0), Some(Fp::from(4)));
+        // The fixed column is only assigned at offset 0.
+        assert_eq!(last.cell(config.c, 1), None);
+        assert!(prover.region_at(3).is_none());
+
+        let rows = last.rows().unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(
+            last.columns(),
+            vec![Column::<Any>::from(config.a), config.c.into()]
+        );
+        assert_eq!(prover.advice_value(config.a, rows.start), Some(Fp::from(6)));
+        assert_eq!(prover.advice_value(config.a, rows.end), None);
+    }
+}
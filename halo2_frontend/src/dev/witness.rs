@@ -0,0 +1,610 @@
+//! Export and import of the assignment collected by a [`MockProver`], so that a witness can
+//! be verified again, e.g. on another machine, without synthesizing the circuit.
+
+use std::collections::{HashMap, HashSet};
+use std::io;
+
+use halo2_common::plonk::{
+    circuit::Column, permutation, sealed::SealedPhase, Circuit, ConstraintSystem, FirstPhase,
+    Selector,
+};
+use halo2_middleware::circuit::{Advice, Any};
+use halo2_middleware::ff::FromUniformBytes;
+
+use super::metadata::Column as ColumnMetadata;
+use super::{CellValue, InstanceValue, MockProver, Region};
+
+/// Version byte that is checked when reading a witness with [`MockProver::read_witness`].
+const VERSION: u8 = 0x01;
+
+/// Tag of an unassigned fixed or advice cell, and of an instance padding cell.
+const UNASSIGNED: u8 = 0;
+/// Tag of an assigned cell, followed by its value.
+const ASSIGNED: u8 = 1;
+/// Tag of a poisoned advice cell, followed by its row.
+const POISON: u8 = 2;
+
+impl<F: FromUniformBytes<64> + Ord> MockProver<F> {
+    /// Writes the assignment collected by this prover to `writer`, so that it can be
+    /// verified again with [`MockProver::read_witness`].
+    ///
+    /// # Format
+    ///
+    /// Integers are little-endian `u32`s unless stated otherwise, and field elements are
+    /// their canonical representation, as returned by `PrimeField::to_repr`. The witness is
+    /// made of, in order:
+    /// - a version byte, and `k` as one byte;
+    /// - the number of fixed columns, not counting the columns that the selectors are
+    ///   compressed into, of advice columns, of instance columns, of selectors and of
+    ///   challenges;
+    /// - the fixed columns, then the advice columns, each as its `2^k` cells in row order.
+    ///   A cell is the byte `0` if it is unassigned, the byte `1` followed by its value if
+    ///   it is assigned, or the byte `2` followed by its row if it is poisoned, so that an
+    ///   unassigned cell is not read back as zero;
+    /// - the instance columns, each as its `2^k` cells in row order. A cell is the byte `0`
+    ///   if it is padding, or the byte `1` followed by its value;
+    /// - the selectors, each as `2^k` bytes, `1` on the rows where it is enabled and `0`
+    ///   elsewhere;
+    /// - the values of the challenges;
+    /// - the number of copy constraints, followed by the two cells of each constraint, as
+    ///   a column and a row;
+    /// - the number of regions, followed by the regions in the order they were entered.
+    ///   A region is its name; its namespaces, as their number followed by the namespaces;
+    ///   the byte `0` if it has no rows, or the byte `1` followed by its first and last
+    ///   rows; its columns; its enabled selectors, as their number followed by, for each
+    ///   selector, its index, the byte `1` if it is simple or `0` otherwise, and the rows
+    ///   where it is enabled; its column annotations, as their number followed by a column
+    ///   and an annotation each; and its assigned cells, as their number followed by a
+    ///   column, a row and the number of assignments each.
+    ///
+    /// A string is its length followed by its UTF-8 bytes, a list of columns or of rows is
+    /// its length followed by its elements, and a column is the byte `0` followed by its
+    /// phase as one byte for an advice column, `1` for a fixed column or `2` for an
+    /// instance column, followed by its index. The sets of a region are written in
+    /// ascending order, so that a witness is always written identically.
+    pub fn write_witness<W: io::Write>(&self, writer: &mut W) -> io::Result<()> {
+        let num_fixed_columns = self.configured_cs.num_fixed_columns;
+
+        writer.write_all(&[VERSION])?;
+        // k value fits in 1 byte
+        writer.write_all(&[self.k as u8])?;
+        for count in [
+            num_fixed_columns,
+            self.advice.len(),
+            self.instance.len(),
+            self.selectors.len(),
+            self.challenges.len(),
+        ] {
+            write_u32(writer, count)?;
+        }
+
+        for column in self.fixed[..num_fixed_columns].iter().chain(&self.advice) {
+            for cell in column {
+                match cell {
+                    CellValue::Unassigned => writer.write_all(&[UNASSIGNED])?,
+                    CellValue::Assigned(value) => {
+                        writer.write_all(&[ASSIGNED])?;
+                        write_field(writer, value)?;
+                    }
+                    CellValue::Poison(row) => {
+                        writer.write_all(&[POISON])?;
+                        write_u32(writer, *row)?;
+                    }
+                }
+            }
+        }
+        for column in &self.instance {
+            for cell in column {
+                match cell {
+                    InstanceValue::Padding => writer.write_all(&[UNASSIGNED])?,
+                    InstanceValue::Assigned(value) => {
+                        writer.write_all(&[ASSIGNED])?;
+                        write_field(writer, value)?;
+                    }
+                }
+            }
+        }
+        for selector in &self.selectors {
+            let enabled = selector.iter().map(|enabled| *enabled as u8);
+            writer.write_all(&enabled.collect::<Vec<_>>())?;
+        }
+        for challenge in &self.challenges {
+            write_field(writer, challenge)?;
+        }
+
+        write_u32(writer, self.permutation.copies.len())?;
+        for (left, right) in &self.permutation.copies {
+            for cell in [left, right] {
+                write_column(writer, cell.column.into())?;
+                write_u32(writer, cell.row)?;
+            }
+        }
+
+        write_u32(writer, self.regions.len())?;
+        for region in &self.regions {
+            write_region(writer, region)?;
+        }
+
+        Ok(())
+    }
+
+    /// Reads a witness written by [`MockProver::write_witness`], and returns a prover
+    /// holding it, whose [`MockProver::verify`] returns the same result as the prover
+    /// that wrote it.
+    ///
+    /// `circuit` is only configured, to recover the constraint system of the witness; it
+    /// is not synthesized, so it can be the result of [`Circuit::without_witnesses`].
+    /// Returns an error of kind [`io::ErrorKind::InvalidData`] if the witness does not
+    /// match the constraint system of `circuit`.
+    pub fn read_witness<ConcreteCircuit: Circuit<F>, R: io::Read>(
+        reader: &mut R,
+        #[allow(unused_variables)] circuit: &ConcreteCircuit,
+    ) -> io::Result<Self> {
+        if read_u8(reader)? != VERSION {
+            return Err(invalid_data("unexpected version byte".to_string()));
+        }
+        let k = u32::from(read_u8(reader)?);
+        if k > F::S {
+            return Err(invalid_data(format!(
+                "k = {} is larger than the two-adicity of the field",
+                k
+            )));
+        }
+        let n = 1usize << k;
+
+        let mut cs = ConstraintSystem::default();
+        #[cfg(feature = "circuit-params")]
+        let _ = ConcreteCircuit::configure_with_params(&mut cs, circuit.params());
+        #[cfg(not(feature = "circuit-params"))]
+        let _ = ConcreteCircuit::configure(&mut cs);
+
+        if n < cs.minimum_rows() {
+            return Err(invalid_data(format!(
+                "n={}, minimum_rows={}, k={}",
+                n,
+                cs.minimum_rows(),
+                k
+            )));
+        }
+        for (what, expected) in [
+            ("fixed columns", cs.num_fixed_columns),
+            ("advice columns", cs.num_advice_columns),
+            ("instance columns", cs.num_instance_columns),
+            ("selectors", cs.num_selectors),
+            ("challenges", cs.num_challenges),
+        ] {
+            let count = read_u32(reader)?;
+            if count != expected {
+                return Err(invalid_data(format!(
+                    "the witness has {} {}, but the circuit has {}",
+                    count, what, expected
+                )));
+            }
+        }
+
+        let read_cell_column = |reader: &mut R| -> io::Result<Vec<CellValue<F>>> {
+            (0..n)
+                .map(|_| match read_u8(reader)? {
+                    UNASSIGNED => Ok(CellValue::Unassigned),
+                    ASSIGNED => read_field(reader).map(CellValue::Assigned),
+                    POISON => read_u32(reader).map(CellValue::Poison),
+                    _ => Err(invalid_data("unexpected cell tag".to_string())),
+                })
+                .collect::<io::Result<Vec<_>>>()
+        };
+        let fixed = (0..cs.num_fixed_columns)
+            .map(|_| read_cell_column(reader))
+            .collect::<io::Result<Vec<_>>>()?;
+        let advice = (0..cs.num_advice_columns)
+            .map(|_| read_cell_column(reader))
+            .collect::<io::Result<Vec<_>>>()?;
+        let instance = (0..cs.num_instance_columns)
+            .map(|_| {
+                (0..n)
+                    .map(|_| match read_u8(reader)? {
+                        UNASSIGNED => Ok(InstanceValue::Padding),
+                        ASSIGNED => read_field(reader).map(InstanceValue::Assigned),
+                        _ => Err(invalid_data("unexpected instance cell tag".to_string())),
+                    })
+                    .collect::<io::Result<Vec<_>>>()
+            })
+            .collect::<io::Result<Vec<_>>>()?;
+        let selectors = (0..cs.num_selectors)
+            .map(|_| {
+                let mut enabled = vec![0u8; n];
+                reader.read_exact(&mut enabled)?;
+                enabled
+                    .into_iter()
+                    .map(|enabled| match enabled {
+                        0 => Ok(false),
+                        1 => Ok(true),
+                        _ => Err(invalid_data("unexpected selector byte".to_string())),
+                    })
+                    .collect::<io::Result<Vec<_>>>()
+            })
+            .collect::<io::Result<Vec<_>>>()?;
+        let challenges = (0..cs.num_challenges)
+            .map(|_| read_field(reader))
+            .collect::<io::Result<Vec<_>>>()?;
+
+        let mut permutation = permutation::Assembly::new(n, &cs.permutation);
+        for _ in 0..read_u32(reader)? {
+            let left = read_column(reader)?;
+            let left_row = read_u32(reader)?;
+            let right = read_column(reader)?;
+            let right_row = read_u32(reader)?;
+            permutation
+                .copy(left, left_row, right, right_row)
+                .map_err(|err| invalid_data(err.to_string()))?;
+        }
+
+        let regions = (0..read_u32(reader)?)
+            .map(|_| read_region(reader))
+            .collect::<io::Result<Vec<_>>>()?;
+
+        let mut prover = MockProver {
+            k,
+            n: n as u32,
+            configured_cs: cs.clone(),
+            usable_rows: cs.usable_rows(k),
+            cs,
+            regions,
+            current_region: None,
+            revisited_region: None,
+            regions_revisited: 0,
+            fixed,
+            advice,
+            instance,
+            selectors,
+            challenges,
+            permutation,
+            current_phase: FirstPhase.to_sealed(),
+            namespace: vec![],
+        };
+        prover.compress_selectors();
+
+        Ok(prover)
+    }
+}
+
+fn write_region<W: io::Write>(writer: &mut W, region: &Region) -> io::Result<()> {
+    write_string(writer, &region.name)?;
+    write_u32(writer, region.namespace.len())?;
+    for namespace in &region.namespace {
+        write_string(writer, namespace)?;
+    }
+    match region.rows {
+        Some((start, end)) => {
+            writer.write_all(&[1])?;
+            write_u32(writer, start)?;
+            write_u32(writer, end)?;
+        }
+        None => writer.write_all(&[0])?,
+    }
+
+    let mut columns = region.columns.iter().copied().collect::<Vec<_>>();
+    columns.sort();
+    write_u32(writer, columns.len())?;
+    for column in columns {
+        write_column(writer, column)?;
+    }
+
+    let mut selectors = region.enabled_selectors.iter().collect::<Vec<_>>();
+    selectors.sort_by_key(|(selector, _)| selector.index());
+    write_u32(writer, selectors.len())?;
+    for (selector, rows) in selectors {
+        write_u32(writer, selector.index())?;
+        writer.write_all(&[selector.is_simple() as u8])?;
+        write_u32(writer, rows.len())?;
+        for row in rows {
+            write_u32(writer, *row)?;
+        }
+    }
+
+    let mut annotations = region.annotations.iter().collect::<Vec<_>>();
+    annotations.sort();
+    write_u32(writer, annotations.len())?;
+    for (column, annotation) in annotations {
+        write_column(writer, Column::new(column.index, column.column_type))?;
+        write_string(writer, annotation)?;
+    }
+
+    let mut cells = region.cells.iter().collect::<Vec<_>>();
+    cells.sort();
+    write_u32(writer, cells.len())?;
+    for ((column, row), assignments) in cells {
+        write_column(writer, *column)?;
+        write_u32(writer, *row)?;
+        write_u32(writer, *assignments)?;
+    }
+
+    Ok(())
+}
+
+fn read_region<R: io::Read>(reader: &mut R) -> io::Result<Region> {
+    let name = read_string(reader)?;
+    let namespace = (0..read_u32(reader)?)
+        .map(|_| read_string(reader))
+        .collect::<io::Result<Vec<_>>>()?;
+    let rows = match read_u8(reader)? {
+        0 => None,
+        1 => Some((read_u32(reader)?, read_u32(reader)?)),
+        _ => return Err(invalid_data("unexpected region rows tag".to_string())),
+    };
+    let columns = (0..read_u32(reader)?)
+        .map(|_| read_column(reader))
+        .collect::<io::Result<HashSet<_>>>()?;
+    let enabled_selectors = (0..read_u32(reader)?)
+        .map(|_| {
+            let index = read_u32(reader)?;
+            let is_simple = match read_u8(reader)? {
+                0 => false,
+                1 => true,
+                _ => return Err(invalid_data("unexpected selector kind".to_string())),
+            };
+            let rows = (0..read_u32(reader)?)
+                .map(|_| read_u32(reader))
+                .collect::<io::Result<Vec<_>>>()?;
+            Ok((Selector::from_parts(index, is_simple), rows))
+        })
+        .collect::<io::Result<HashMap<_, _>>>()?;
+    let annotations = (0..read_u32(reader)?)
+        .map(|_| {
+            let column = ColumnMetadata::from(read_column(reader)?);
+            Ok::<_, io::Error>((column, read_string(reader)?))
+        })
+        .collect::<io::Result<HashMap<_, _>>>()?;
+    let cells = (0..read_u32(reader)?)
+        .map(|_| {
+            let cell = (read_column(reader)?, read_u32(reader)?);
+            Ok::<_, io::Error>((cell, read_u32(reader)?))
+        })
+        .collect::<io::Result<HashMap<_, _>>>()?;
+
+    Ok(Region {
+        name,
+        namespace,
+        columns,
+        rows,
+        enabled_selectors,
+        annotations,
+        cells,
+    })
+}
+
+fn write_column<W: io::Write>(writer: &mut W, column: Column<Any>) -> io::Result<()> {
+    match column.column_type() {
+        Any::Advice(advice) => writer.write_all(&[0, advice.phase()])?,
+        Any::Fixed => writer.write_all(&[1])?,
+        Any::Instance => writer.write_all(&[2])?,
+    }
+    write_u32(writer, column.index())
+}
+
+fn read_column<R: io::Read>(reader: &mut R) -> io::Result<Column<Any>> {
+    let column_type = match read_u8(reader)? {
+        0 => Any::Advice(Advice::new(read_u8(reader)?)),
+        1 => Any::Fixed,
+        2 => Any::Instance,
+        _ => return Err(invalid_data("unexpected column kind".to_string())),
+    };
+    Ok(Column::new(read_u32(reader)? as usize, column_type))
+}
+
+fn write_field<F: FromUniformBytes<64>, W: io::Write>(writer: &mut W, value: &F) -> io::Result<()> {
+    writer.write_all(value.to_repr().as_ref())
+}
+
+fn read_field<F: FromUniformBytes<64>, R: io::Read>(reader: &mut R) -> io::Result<F> {
+    let mut repr = F::Repr::default();
+    reader.read_exact(repr.as_mut())?;
+    Option::from(F::from_repr(repr))
+        .ok_or_else(|| invalid_data("non-canonical field element".to_string()))
+}
+
+fn write_string<W: io::Write>(writer: &mut W, string: &str) -> io::Result<()> {
+    write_u32(writer, string.len())?;
+    writer.write_all(string.as_bytes())
+}
+
+fn read_string<R: io::Read>(reader: &mut R) -> io::Result<String> {
+    let mut bytes = vec![0u8; read_u32(reader)?];
+    reader.read_exact(&mut bytes)?;
+    String::from_utf8(bytes).map_err(|err| invalid_data(err.to_string()))
+}
+
+fn write_u32<W: io::Write>(writer: &mut W, value: usize) -> io::Result<()> {
+    writer.write_all(&(value as u32).to_le_bytes())
+}
+
+fn read_u32<R: io::Read>(reader: &mut R) -> io::Result<usize> {
+    let mut bytes = [0u8; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes) as usize)
+}
+
+fn read_u8<R: io::Read>(reader: &mut R) -> io::Result<u8> {
+    let mut byte = [0u8; 1];
+    reader.read_exact(&mut byte)?;
+    Ok(byte[0])
+}
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_middleware::circuit::{Fixed, Instance};
+    use halo2_middleware::ff::Field;
+    use halo2_middleware::poly::Rotation;
+    use halo2curves::pasta::Fp;
+
+    use super::*;
+    use crate::circuit::{Layouter, SimpleFloorPlanner, Value};
+    use halo2_common::plonk::Error;
+
+    const K: u32 = 4;
+
+    #[derive(Clone)]
+    struct ScaleConfig {
+        a: Column<Advice>,
+        b: Column<Advice>,
+        f: Column<Fixed>,
+        instance: Column<Instance>,
+        s: Selector,
+    }
+
+    /// Scales `a` by `f` into `b`, and exposes the last `b` as a public input. When `fail`
+    /// is set, `b` is wrong on row 1 and `a` is not assigned on row 2.
+    struct ScaleCircuit {
+        fail: bool,
+    }
+
+    impl Circuit<Fp> for ScaleCircuit {
+        type Config = ScaleConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+        #[cfg(feature = "circuit-params")]
+        type Params = ();
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let a = meta.advice_column();
+            let b = meta.advice_column();
+            let f = meta.fixed_column();
+            let instance = meta.instance_column();
+            let s = meta.selector();
+            meta.enable_equality(b);
+            meta.enable_equality(instance);
+
+            meta.create_gate("scale", |meta| {
+                let a = meta.query_advice(a, Rotation::cur());
+                let b = meta.query_advice(b, Rotation::cur());
+                let f = meta.query_fixed(f, Rotation::cur());
+                let s = meta.query_selector(s);
+                vec![s * (a * f - b)]
+            });
+
+            ScaleConfig {
+                a,
+                b,
+                f,
+                instance,
+                s,
+            }
+        }
+
+        fn without_witnesses(&self) -> Self {
+            Self { fail: false }
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            let last = layouter.namespace(|| "scaling").assign_region(
+                || "scale",
+                |mut region| {
+                    region.name_column(|| "input", config.a);
+                    let mut cell = None;
+                    for row in 0..4 {
+                        config.s.enable(&mut region, row)?;
+                        let a = Fp::from(row as u64);
+                        let f = Fp::from(3);
+                        if !(self.fail && row == 2) {
+                            region.assign_advice(|| "a", config.a, row, || Value::known(a))?;
+                        }
+                        region.assign_fixed(|| "f", config.f, row, || Value::known(f))?;
+                        let b = if self.fail && row == 1 { a } else { a * f };
+                        cell = Some(region.assign_advice(
+                            || "b",
+                            config.b,
+                            row,
+                            || Value::known(b),
+                        )?);
+                    }
+                    Ok(cell.unwrap())
+                },
+            )?;
+            layouter.constrain_instance(last.cell(), config.instance, 0)
+        }
+    }
+
+    fn reload(prover: &MockProver<Fp>) -> MockProver<Fp> {
+        let mut bytes = vec![];
+        prover.write_witness(&mut bytes).unwrap();
+        let reloaded =
+            MockProver::read_witness(&mut &bytes[..], &ScaleCircuit { fail: false }).unwrap();
+
+        // The reloaded witness is written identically.
+        let mut reloaded_bytes = vec![];
+        reloaded.write_witness(&mut reloaded_bytes).unwrap();
+        assert_eq!(reloaded_bytes, bytes);
+
+        reloaded
+    }
+
+    #[test]
+    fn witness_round_trip() {
+        let config = ScaleCircuit::configure(&mut ConstraintSystem::default());
+        let instance = vec![vec![Fp::from(9)]];
+
+        let prover = MockProver::run(K, &ScaleCircuit { fail: false }, instance.clone()).unwrap();
+        let reloaded = reload(&prover);
+        assert_eq!(reloaded.verify(), Ok(()));
+        assert_eq!(
+            reloaded.advice_values(config.a),
+            prover.advice_values(config.a)
+        );
+        assert_eq!(
+            reloaded.fixed_values(config.f),
+            prover.fixed_values(config.f)
+        );
+        assert_eq!(reloaded.permutation().copies.len(), 1);
+        assert_eq!(reloaded.regions().len(), prover.regions().len());
+        for (reloaded, region) in reloaded.regions().iter().zip(prover.regions()) {
+            assert_eq!(reloaded.name(), region.name());
+            assert_eq!(reloaded.enabled_selectors(), region.enabled_selectors());
+            assert_eq!(reloaded.annotations(), region.annotations());
+            assert_eq!(reloaded.cells(), region.cells());
+        }
+
+        // The failures are the same, and are reported in the same regions.
+        let prover = MockProver::run(K, &ScaleCircuit { fail: true }, instance).unwrap();
+        let reloaded = reload(&prover);
+        assert!(prover.verify().is_err());
+        assert_eq!(reloaded.verify(), prover.verify());
+
+        // Unassigned cells are not read back as zero, and the blinding rows stay poisoned.
+        let a = reloaded.advice_values(config.a);
+        assert_eq!(a[0], CellValue::Assigned(Fp::ZERO));
+        assert_eq!(a[2], CellValue::Unassigned);
+        assert_eq!(a[(1 << K) - 1], CellValue::Poison((1 << K) - 1));
+    }
+
+    #[test]
+    fn read_witness_rejects_invalid_data() {
+        let prover =
+            MockProver::run(K, &ScaleCircuit { fail: false }, vec![vec![Fp::from(9)]]).unwrap();
+        let mut bytes = vec![];
+        prover.write_witness(&mut bytes).unwrap();
+        let read = |bytes: &[u8]| {
+            MockProver::<Fp>::read_witness(&mut &bytes[..], &ScaleCircuit { fail: false })
+                .map(|_| ())
+                .map_err(|err| err.kind())
+        };
+        assert_eq!(read(&bytes), Ok(()));
+
+        // Truncated witnesses, unknown versions and witnesses of other circuits are rejected.
+        assert_eq!(
+            read(&bytes[..bytes.len() - 1]),
+            Err(io::ErrorKind::UnexpectedEof)
+        );
+        let mut other = bytes.clone();
+        other[0] = VERSION + 1;
+        assert_eq!(read(&other), Err(io::ErrorKind::InvalidData));
+        let mut other = bytes;
+        other[2] += 1;
+        assert_eq!(read(&other), Err(io::ErrorKind::InvalidData));
+    }
+}
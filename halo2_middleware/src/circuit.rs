@@ -3,6 +3,8 @@ use crate::{lookup, metadata, permutation, shuffle};
 use core::cmp::max;
 use ff::Field;
 use std::collections::HashMap;
+use std::iter::{Product, Sum};
+use std::ops::{Add, Mul, Neg, Sub};
 
 /// Query of fixed column at a certain relative location
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
@@ -76,21 +78,192 @@ pub enum ExpressionMid<F> {
 }
 
 impl<F: Field> ExpressionMid<F> {
-    /// Compute the degree of this polynomial
-    pub fn degree(&self) -> usize {
-        use ExpressionMid::*;
+    /// Evaluate the polynomial using the provided closures to perform the
+    /// operations.
+    #[allow(clippy::too_many_arguments)]
+    pub fn evaluate<T>(
+        &self,
+        constant: &impl Fn(F) -> T,
+        fixed_column: &impl Fn(FixedQueryMid) -> T,
+        advice_column: &impl Fn(AdviceQueryMid) -> T,
+        instance_column: &impl Fn(InstanceQueryMid) -> T,
+        challenge: &impl Fn(ChallengeMid) -> T,
+        negated: &impl Fn(T) -> T,
+        sum: &impl Fn(T, T) -> T,
+        product: &impl Fn(T, T) -> T,
+        scaled: &impl Fn(T, F) -> T,
+    ) -> T {
+        let evaluate = |expr: &ExpressionMid<F>| {
+            expr.evaluate(
+                constant,
+                fixed_column,
+                advice_column,
+                instance_column,
+                challenge,
+                negated,
+                sum,
+                product,
+                scaled,
+            )
+        };
         match self {
-            Constant(_) => 0,
-            Fixed(_) => 1,
-            Advice(_) => 1,
-            Instance(_) => 1,
-            Challenge(_) => 0,
-            Negated(poly) => poly.degree(),
-            Sum(a, b) => max(a.degree(), b.degree()),
-            Product(a, b) => a.degree() + b.degree(),
-            Scaled(poly, _) => poly.degree(),
+            ExpressionMid::Constant(scalar) => constant(*scalar),
+            ExpressionMid::Fixed(query) => fixed_column(*query),
+            ExpressionMid::Advice(query) => advice_column(*query),
+            ExpressionMid::Instance(query) => instance_column(*query),
+            ExpressionMid::Challenge(value) => challenge(*value),
+            ExpressionMid::Negated(a) => negated(evaluate(a)),
+            ExpressionMid::Sum(a, b) => sum(evaluate(a), evaluate(b)),
+            ExpressionMid::Product(a, b) => product(evaluate(a), evaluate(b)),
+            ExpressionMid::Scaled(a, f) => scaled(evaluate(a), *f),
         }
     }
+
+    fn write_identifier<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        match self {
+            ExpressionMid::Constant(scalar) => write!(writer, "{scalar:?}"),
+            ExpressionMid::Fixed(query) => {
+                write!(
+                    writer,
+                    "fixed[{}][{}]",
+                    query.column_index, query.rotation.0
+                )
+            }
+            ExpressionMid::Advice(query) => {
+                write!(
+                    writer,
+                    "advice[{}][{}]",
+                    query.column_index, query.rotation.0
+                )
+            }
+            ExpressionMid::Instance(query) => {
+                write!(
+                    writer,
+                    "instance[{}][{}]",
+                    query.column_index, query.rotation.0
+                )
+            }
+            ExpressionMid::Challenge(challenge) => {
+                write!(writer, "challenge[{}]", challenge.index())
+            }
+            ExpressionMid::Negated(a) => {
+                writer.write_all(b"(-")?;
+                a.write_identifier(writer)?;
+                writer.write_all(b")")
+            }
+            ExpressionMid::Sum(a, b) => {
+                writer.write_all(b"(")?;
+                a.write_identifier(writer)?;
+                writer.write_all(b"+")?;
+                b.write_identifier(writer)?;
+                writer.write_all(b")")
+            }
+            ExpressionMid::Product(a, b) => {
+                writer.write_all(b"(")?;
+                a.write_identifier(writer)?;
+                writer.write_all(b"*")?;
+                b.write_identifier(writer)?;
+                writer.write_all(b")")
+            }
+            ExpressionMid::Scaled(a, f) => {
+                a.write_identifier(writer)?;
+                write!(writer, "*{f:?}")
+            }
+        }
+    }
+
+    /// Identifier for this expression. Expressions with identical identifiers
+    /// do the same calculation (but the expressions don't need to be exactly equal
+    /// in how they are composed e.g. `1 + 2` and `2 + 1` can have the same identifier).
+    pub fn identifier(&self) -> String {
+        let mut cursor = std::io::Cursor::new(Vec::new());
+        self.write_identifier(&mut cursor).unwrap();
+        String::from_utf8(cursor.into_inner()).unwrap()
+    }
+
+    /// Compute the degree of this polynomial
+    pub fn degree(&self) -> usize {
+        self.evaluate(
+            &|_| 0,
+            &|_| 1,
+            &|_| 1,
+            &|_| 1,
+            &|_| 0,
+            &|a| a,
+            &|a, b| max(a, b),
+            &|a, b| a + b,
+            &|a, _| a,
+        )
+    }
+
+    /// Approximate the computational complexity of this expression.
+    pub fn complexity(&self) -> usize {
+        self.evaluate(
+            &|_| 0,
+            &|_| 1,
+            &|_| 1,
+            &|_| 1,
+            &|_| 0,
+            &|a| a + 5,
+            &|a, b| a + b + 15,
+            &|a, b| a + b + 30,
+            &|a, _| a + 30,
+        )
+    }
+
+    /// Square this expression.
+    pub fn square(self) -> Self {
+        self.clone() * self
+    }
+}
+
+impl<F: Field> Neg for ExpressionMid<F> {
+    type Output = ExpressionMid<F>;
+    fn neg(self) -> Self::Output {
+        ExpressionMid::Negated(Box::new(self))
+    }
+}
+
+impl<F: Field> Add for ExpressionMid<F> {
+    type Output = ExpressionMid<F>;
+    fn add(self, rhs: ExpressionMid<F>) -> ExpressionMid<F> {
+        ExpressionMid::Sum(Box::new(self), Box::new(rhs))
+    }
+}
+
+impl<F: Field> Sub for ExpressionMid<F> {
+    type Output = ExpressionMid<F>;
+    fn sub(self, rhs: ExpressionMid<F>) -> ExpressionMid<F> {
+        ExpressionMid::Sum(Box::new(self), Box::new(-rhs))
+    }
+}
+
+impl<F: Field> Mul for ExpressionMid<F> {
+    type Output = ExpressionMid<F>;
+    fn mul(self, rhs: ExpressionMid<F>) -> ExpressionMid<F> {
+        ExpressionMid::Product(Box::new(self), Box::new(rhs))
+    }
+}
+
+impl<F: Field> Mul<F> for ExpressionMid<F> {
+    type Output = ExpressionMid<F>;
+    fn mul(self, rhs: F) -> ExpressionMid<F> {
+        ExpressionMid::Scaled(Box::new(self), rhs)
+    }
+}
+
+impl<F: Field> Sum<Self> for ExpressionMid<F> {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.reduce(|acc, x| acc + x)
+            .unwrap_or(ExpressionMid::Constant(F::ZERO))
+    }
+}
+
+impl<F: Field> Product<Self> for ExpressionMid<F> {
+    fn product<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.reduce(|acc, x| acc * x)
+            .unwrap_or(ExpressionMid::Constant(F::ONE))
+    }
 }
 
 /// A Gate contains a single polynomial identity with a name as metadata.
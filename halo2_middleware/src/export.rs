@@ -0,0 +1,3 @@
+//! Exporters of compiled circuits to the representations used by other tooling.
+
+pub mod ccs;
@@ -0,0 +1,336 @@
+//! Export of compiled circuits to a customizable constraint system (CCS), as described in
+//! [the CCS paper](https://eprint.iacr.org/2023/552).
+//!
+//! A CCS is satisfied by an assignment `z` when
+//! `Σ_i c_i · ∘_{j ∈ S_i} (M_j · z) = 0`, where `∘` is the entry-wise product.
+//!
+//! # Assignment layout
+//!
+//! The assignment is `1 ‖ fixed ‖ instance ‖ advice`: the constant one, followed by the cells
+//! of the fixed, instance and advice columns. Each block of cells is row-major, so that the
+//! cell at `row` of the column `column` of a block with `c` columns is at
+//! `offset + row * c + column`. [`assignment`] lays out the columns of a circuit this way.
+//!
+//! # Lowering
+//!
+//! - Each polynomial of the gates must vanish on every one of the `2^k` rows, with rotations
+//!   wrapping around as in the proof system. It is expanded into monomials, and the `i`-th
+//!   monomial is the product of the matrices of the `i`-th multiset, one per variable. The
+//!   coefficient of the monomial is folded into its first matrix, and the matrices of the
+//!   missing variables of monomials of lower degree select the constant one.
+//! - The fixed cells are constrained to their preprocessed values, and the copy constraints
+//!   to equalities, both with a single linear matrix.
+//! - Lookups, shuffles and challenges have no lowering, and are reported as an
+//!   [`ExportError`].
+
+use crate::circuit::{Any, Cell, ConstraintSystemV2Backend, ExpressionMid, PreprocessingV2};
+use ff::Field;
+use std::fmt;
+
+/// A sparse matrix, as a list of `(row, column, value)` entries.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SparseMatrix<F> {
+    pub entries: Vec<(usize, usize, F)>,
+}
+
+/// A customizable constraint system.
+#[derive(Clone, Debug)]
+pub struct Ccs<F> {
+    /// The number of constraints, `m`, which is the number of rows of the matrices.
+    pub num_constraints: usize,
+    /// The length of the assignment, `n`, which is the number of columns of the matrices.
+    pub num_variables: usize,
+    /// The number of public entries at the start of the assignment: the constant one, and
+    /// the fixed and instance cells.
+    pub num_public: usize,
+    /// The maximum number of matrices in a multiset, `d`.
+    pub degree: usize,
+    /// The matrices `M_j`.
+    pub matrices: Vec<SparseMatrix<F>>,
+    /// The multisets `S_i`, of indices of matrices.
+    pub multisets: Vec<Vec<usize>>,
+    /// The constants `c_i` of the multisets.
+    pub constants: Vec<F>,
+}
+
+/// A part of a circuit that can't be exported to a CCS.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExportError {
+    /// The circuit has lookup arguments.
+    Lookups,
+    /// The circuit has shuffle arguments.
+    Shuffles,
+    /// A gate queries a challenge, whose value is only known while proving.
+    Challenges,
+}
+
+impl fmt::Display for ExportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExportError::Lookups => write!(f, "Lookup arguments can't be exported to a CCS"),
+            ExportError::Shuffles => write!(f, "Shuffle arguments can't be exported to a CCS"),
+            ExportError::Challenges => write!(f, "Challenges can't be exported to a CCS"),
+        }
+    }
+}
+
+impl std::error::Error for ExportError {}
+
+impl<F: Field> Ccs<F> {
+    /// Returns `Σ_i c_i · ∘_{j ∈ S_i} (M_j · z)`, which is zero on each constraint that `z`
+    /// satisfies.
+    pub fn evaluate(&self, z: &[F]) -> Vec<F> {
+        assert_eq!(z.len(), self.num_variables);
+        let products: Vec<Vec<F>> = self
+            .matrices
+            .iter()
+            .map(|matrix| {
+                let mut product = vec![F::ZERO; self.num_constraints];
+                for (row, column, value) in matrix.entries.iter() {
+                    product[*row] += *value * z[*column];
+                }
+                product
+            })
+            .collect();
+
+        let mut values = vec![F::ZERO; self.num_constraints];
+        for (multiset, constant) in self.multisets.iter().zip(self.constants.iter()) {
+            for (row, value) in values.iter_mut().enumerate() {
+                *value += multiset
+                    .iter()
+                    .fold(*constant, |acc, matrix| acc * products[*matrix][row]);
+            }
+        }
+        values
+    }
+
+    /// Returns whether `z` satisfies every constraint.
+    pub fn is_satisfied(&self, z: &[F]) -> bool {
+        self.evaluate(z).iter().all(|value| value.is_zero_vartime())
+    }
+}
+
+/// A column queried at a rotation.
+#[derive(Clone, Copy, Debug)]
+enum Variable {
+    Fixed(usize, i32),
+    Instance(usize, i32),
+    Advice(usize, i32),
+}
+
+impl Variable {
+    fn new(column_type: Any, column: usize, rotation: i32) -> Self {
+        match column_type {
+            Any::Fixed => Variable::Fixed(column, rotation),
+            Any::Instance => Variable::Instance(column, rotation),
+            Any::Advice(_) => Variable::Advice(column, rotation),
+        }
+    }
+}
+
+/// A coefficient times a product of variables.
+type Monomial<F> = (F, Vec<Variable>);
+
+fn monomials<F: Field>(expression: &ExpressionMid<F>) -> Result<Vec<Monomial<F>>, ExportError> {
+    Ok(match expression {
+        ExpressionMid::Constant(constant) => vec![(*constant, vec![])],
+        ExpressionMid::Fixed(query) => vec![(
+            F::ONE,
+            vec![Variable::Fixed(query.column_index, query.rotation.0)],
+        )],
+        ExpressionMid::Instance(query) => vec![(
+            F::ONE,
+            vec![Variable::Instance(query.column_index, query.rotation.0)],
+        )],
+        ExpressionMid::Advice(query) => vec![(
+            F::ONE,
+            vec![Variable::Advice(query.column_index, query.rotation.0)],
+        )],
+        ExpressionMid::Challenge(_) => return Err(ExportError::Challenges),
+        ExpressionMid::Negated(a) => monomials(a)?
+            .into_iter()
+            .map(|(coefficient, variables)| (-coefficient, variables))
+            .collect(),
+        ExpressionMid::Sum(a, b) => {
+            let mut monomials_a = monomials(a)?;
+            monomials_a.extend(monomials(b)?);
+            monomials_a
+        }
+        ExpressionMid::Product(a, b) => {
+            let monomials_b = monomials(b)?;
+            monomials(a)?
+                .iter()
+                .flat_map(|(coefficient_a, variables_a)| {
+                    monomials_b.iter().map(move |(coefficient_b, variables_b)| {
+                        let variables = variables_a.iter().chain(variables_b).copied().collect();
+                        (*coefficient_a * coefficient_b, variables)
+                    })
+                })
+                .collect()
+        }
+        ExpressionMid::Scaled(a, scalar) => monomials(a)?
+            .into_iter()
+            .map(|(coefficient, variables)| (coefficient * scalar, variables))
+            .collect(),
+    })
+}
+
+/// The position of the cells in the assignment.
+struct Layout {
+    n: usize,
+    fixed: usize,
+    instance: usize,
+    advice: usize,
+}
+
+impl Layout {
+    fn len(&self) -> usize {
+        1 + self.n * (self.fixed + self.instance + self.advice)
+    }
+
+    fn index(&self, variable: Variable, row: usize) -> usize {
+        let row = |rotation: i32| (row as i64 + rotation as i64).rem_euclid(self.n as i64) as usize;
+        let (offset, columns, column, row) = match variable {
+            Variable::Fixed(column, rotation) => (1, self.fixed, column, row(rotation)),
+            Variable::Instance(column, rotation) => (
+                1 + self.n * self.fixed,
+                self.instance,
+                column,
+                row(rotation),
+            ),
+            Variable::Advice(column, rotation) => (
+                1 + self.n * (self.fixed + self.instance),
+                self.advice,
+                column,
+                row(rotation),
+            ),
+        };
+        offset + row * columns + column
+    }
+}
+
+/// Lays out the cells of a circuit as the assignment of its CCS, described in the
+/// [module documentation](self). Columns shorter than `2^k` rows, such as instance columns,
+/// are padded with zeros.
+pub fn assignment<F: Field>(
+    k: u32,
+    fixed: &[Vec<F>],
+    instance: &[Vec<F>],
+    advice: &[Vec<F>],
+) -> Vec<F> {
+    let n = 1 << k;
+    let mut z = vec![F::ONE];
+    for columns in [fixed, instance, advice] {
+        for row in 0..n {
+            z.extend(
+                columns
+                    .iter()
+                    .map(|column| column.get(row).copied().unwrap_or(F::ZERO)),
+            );
+        }
+    }
+    z
+}
+
+/// Exports the constraints of a circuit with `2^k` rows to a CCS.
+pub fn export<F: Field>(
+    cs: &ConstraintSystemV2Backend<F>,
+    preprocessing: &PreprocessingV2<F>,
+    k: u32,
+) -> Result<Ccs<F>, ExportError> {
+    if !cs.lookups.is_empty() {
+        return Err(ExportError::Lookups);
+    }
+    if !cs.shuffles.is_empty() {
+        return Err(ExportError::Shuffles);
+    }
+    let gates = cs
+        .gates
+        .iter()
+        .map(|gate| monomials(gate.polynomial()))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let layout = Layout {
+        n: 1 << k,
+        fixed: cs.num_fixed_columns,
+        instance: cs.num_instance_columns,
+        advice: cs.num_advice_columns,
+    };
+    let degree = gates
+        .iter()
+        .flatten()
+        .map(|(_, variables)| variables.len())
+        .max()
+        .unwrap_or(0)
+        .max(1);
+    let num_terms = gates.iter().map(Vec::len).max().unwrap_or(0).max(1);
+    let mut matrices = vec![SparseMatrix::default(); num_terms * degree];
+
+    // Adds a constraint made of terms given by their factors, each a linear combination of
+    // the assignment.
+    let mut num_constraints = 0;
+    let mut constrain = |terms: Vec<Vec<Vec<(usize, F)>>>| {
+        let row = num_constraints;
+        num_constraints += 1;
+        for (term, factors) in terms.into_iter().enumerate() {
+            let factors = factors
+                .into_iter()
+                .chain(std::iter::repeat(vec![(0, F::ONE)]));
+            for (factor, entries) in factors.take(degree).enumerate() {
+                matrices[term * degree + factor].entries.extend(
+                    entries
+                        .into_iter()
+                        .map(|(column, value)| (row, column, value)),
+                );
+            }
+        }
+    };
+
+    for monomials in gates.iter() {
+        for row in 0..layout.n {
+            constrain(
+                monomials
+                    .iter()
+                    .map(|(coefficient, variables)| match variables.split_first() {
+                        None => vec![vec![(0, *coefficient)]],
+                        Some((first, rest)) => {
+                            std::iter::once(vec![(layout.index(*first, row), *coefficient)])
+                                .chain(
+                                    rest.iter().map(|variable| {
+                                        vec![(layout.index(*variable, row), F::ONE)]
+                                    }),
+                                )
+                                .collect()
+                        }
+                    })
+                    .collect(),
+            );
+        }
+    }
+    for (column, values) in preprocessing.fixed.iter().enumerate() {
+        for (row, value) in values.iter().enumerate() {
+            let cell = layout.index(Variable::Fixed(column, 0), row);
+            constrain(vec![vec![vec![(cell, F::ONE), (0, -*value)]]]);
+        }
+    }
+    for (left, right) in preprocessing.permutation.copies.iter() {
+        let cell = |cell: &Cell| {
+            let variable = Variable::new(cell.column.column_type, cell.column.index, 0);
+            layout.index(variable, cell.row)
+        };
+        let (left, right) = (cell(left), cell(right));
+        constrain(vec![vec![vec![(left, F::ONE), (right, -F::ONE)]]]);
+    }
+
+    Ok(Ccs {
+        num_constraints,
+        num_variables: layout.len(),
+        num_public: 1 + layout.n * (layout.fixed + layout.instance),
+        degree,
+        matrices,
+        multisets: (0..num_terms)
+            .map(|term| (term * degree..(term + 1) * degree).collect())
+            .collect(),
+        constants: vec![F::ONE; num_terms],
+    })
+}
@@ -0,0 +1,505 @@
+//! Import of circuits described in JSON, to build a [`CompiledCircuitV2`] without going
+//! through the frontend.
+//!
+//! The description uses the interchange format of constraint systems written by
+//! `ConstraintSystem::to_interchange` in `halo2_common`, which documents the fields
+//! `version`, `counts`, `columns`, `challenges`, `gates`, `lookups`, `shuffles` and
+//! `permutation`, and the prefix notation of expressions. Constraint systems with selectors
+//! are not supported: they must be converted to fixed columns first. The description adds:
+//!
+//! - `k`, the circuit has `2^k` rows;
+//! - `fixed`, the values of each fixed column, in the hexadecimal encoding of field elements
+//!   of the interchange format, and padded with zeros to `2^k` rows;
+//! - `copies`, the copy constraints, as pairs of cells such as
+//!   `[{ "kind": "advice", "index": 0, "row": 0 }, { "kind": "instance", "index": 0, "row": 0 }]`,
+//!   between columns of the permutation.
+//!
+//! ```json
+//! {
+//!   "version": 1,
+//!   "k": 4,
+//!   "counts": { "fixed": 1, "advice": 1, "instance": 1, "selectors": 0, "challenges": 0 },
+//!   "columns": [
+//!     { "kind": "fixed", "index": 0 },
+//!     { "kind": "advice", "index": 0, "phase": 0 },
+//!     { "kind": "instance", "index": 0 }
+//!   ],
+//!   "challenges": [],
+//!   "gates": [{ "name": "square", "polynomial": ["*", ["fixed", 0, 0], ["+", ["advice", 0, 1], ["-", ["*", ["advice", 0, 0], ["advice", 0, 0]]]]] }],
+//!   "lookups": [],
+//!   "shuffles": [],
+//!   "permutation": [{ "kind": "advice", "index": 0 }, { "kind": "instance", "index": 0 }],
+//!   "fixed": [["0x01", "0x01", "0x01"]],
+//!   "copies": [[{ "kind": "advice", "index": 0, "row": 0 }, { "kind": "instance", "index": 0, "row": 0 }]]
+//! }
+//! ```
+//!
+//! Fields that are not described are ignored. The indices of columns and challenges, the
+//! phases, the rows and the field elements are validated, and every error points at the
+//! offending value with its JSON path, such as `$.gates[0].polynomial[2][1]`.
+
+use crate::circuit::{
+    AdviceQueryMid, Any, Cell, ChallengeMid, ColumnMid, CompiledCircuitV2,
+    ConstraintSystemV2Backend, ExpressionMid, FixedQueryMid, GateV2Backend, InstanceQueryMid,
+    PreprocessingV2,
+};
+use crate::poly::Rotation;
+use crate::{lookup, metadata, permutation, shuffle};
+use ff::PrimeField;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt;
+
+/// The version of the interchange format that can be imported.
+pub const IMPORT_VERSION: u64 = 1;
+
+/// An invalid value in the description of a circuit.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ImportError {
+    /// The JSON path of the value, such as `$.gates[0].polynomial`.
+    pub path: String,
+    /// What is wrong with the value.
+    pub message: String,
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Invalid circuit description at {}: {}",
+            self.path, self.message
+        )
+    }
+}
+
+impl std::error::Error for ImportError {}
+
+/// A JSON value, with its path in the description.
+struct Node<'a> {
+    value: &'a Value,
+    path: String,
+}
+
+impl<'a> Node<'a> {
+    fn error<T>(&self, message: impl Into<String>) -> Result<T, ImportError> {
+        Err(ImportError {
+            path: self.path.clone(),
+            message: message.into(),
+        })
+    }
+
+    fn optional(&self, name: &str) -> Result<Option<Node<'a>>, ImportError> {
+        match self.value {
+            Value::Object(object) => Ok(object.get(name).map(|value| Node {
+                value,
+                path: format!("{}.{name}", self.path),
+            })),
+            _ => self.error("expected an object"),
+        }
+    }
+
+    fn field(&self, name: &str) -> Result<Node<'a>, ImportError> {
+        match self.optional(name)? {
+            Some(node) => Ok(node),
+            None => self.error(format!("missing field `{name}`")),
+        }
+    }
+
+    fn array(&self) -> Result<Vec<Node<'a>>, ImportError> {
+        match self.value {
+            Value::Array(array) => Ok(array
+                .iter()
+                .enumerate()
+                .map(|(i, value)| Node {
+                    value,
+                    path: format!("{}[{i}]", self.path),
+                })
+                .collect()),
+            _ => self.error("expected an array"),
+        }
+    }
+
+    /// The elements of an array, which may be absent.
+    fn optional_array(&self, name: &str) -> Result<Vec<Node<'a>>, ImportError> {
+        self.optional(name)?.map_or(Ok(vec![]), |node| node.array())
+    }
+
+    fn str(&self) -> Result<&'a str, ImportError> {
+        match self.value.as_str() {
+            Some(value) => Ok(value),
+            None => self.error("expected a string"),
+        }
+    }
+
+    fn bool(&self) -> Result<bool, ImportError> {
+        match self.value.as_bool() {
+            Some(value) => Ok(value),
+            None => self.error("expected a boolean"),
+        }
+    }
+
+    fn u64(&self) -> Result<u64, ImportError> {
+        match self.value.as_u64() {
+            Some(value) => Ok(value),
+            None => self.error("expected a non-negative integer"),
+        }
+    }
+
+    fn usize(&self) -> Result<usize, ImportError> {
+        let value = self.u64()?;
+        usize::try_from(value).or_else(|_| self.error(format!("{value} is too large")))
+    }
+
+    fn u8(&self) -> Result<u8, ImportError> {
+        let value = self.u64()?;
+        u8::try_from(value).or_else(|_| self.error(format!("{value} is too large")))
+    }
+
+    fn i32(&self) -> Result<i32, ImportError> {
+        match self
+            .value
+            .as_i64()
+            .and_then(|value| i32::try_from(value).ok())
+        {
+            Some(value) => Ok(value),
+            None => self.error("expected a 32-bit integer"),
+        }
+    }
+
+    /// An index below `count`, of something described by `what`.
+    fn index(&self, count: usize, what: &str) -> Result<usize, ImportError> {
+        let index = self.usize()?;
+        if index >= count {
+            return self.error(format!("{what} {index} doesn't exist, there are {count}"));
+        }
+        Ok(index)
+    }
+
+    /// A field element, as the `0x`-prefixed big-endian hexadecimal encoding of its
+    /// little-endian canonical representation.
+    fn field_element<F: PrimeField>(&self) -> Result<F, ImportError> {
+        let hex = self.str()?;
+        let invalid = || self.error(format!("{hex:?} is not a hexadecimal field element"));
+        let Some(digits) = hex.strip_prefix("0x").map(str::as_bytes) else {
+            return invalid();
+        };
+        let mut repr = F::Repr::default();
+        let bytes = repr.as_mut();
+        if digits.is_empty()
+            || digits.len() > 2 * bytes.len()
+            || !digits.iter().all(u8::is_ascii_hexdigit)
+        {
+            return invalid();
+        }
+        for (byte, chunk) in bytes.iter_mut().zip(digits.rchunks(2)) {
+            // The digits are ASCII hexadecimal digits.
+            *byte = u8::from_str_radix(std::str::from_utf8(chunk).unwrap(), 16).unwrap();
+        }
+        match Option::from(F::from_repr(repr)) {
+            Some(value) => Ok(value),
+            None => self.error(format!("{hex} is not below the modulus")),
+        }
+    }
+}
+
+/// The columns and challenges of the circuit, to validate the references to them.
+struct Shape {
+    fixed: usize,
+    instance: usize,
+    advice_phase: Vec<u8>,
+    challenge_phase: Vec<u8>,
+}
+
+impl Shape {
+    fn column_type(&self, node: &Node, index: usize) -> Result<Any, ImportError> {
+        Ok(match node.str()? {
+            "fixed" => Any::Fixed,
+            "instance" => Any::Instance,
+            "advice" => Any::advice_in(self.advice_phase.get(index).copied().unwrap_or(0)),
+            kind => return node.error(format!("unknown column kind {kind:?}")),
+        })
+    }
+
+    fn count(&self, column_type: Any) -> usize {
+        match column_type {
+            Any::Fixed => self.fixed,
+            Any::Instance => self.instance,
+            Any::Advice(_) => self.advice_phase.len(),
+        }
+    }
+
+    /// A `{ "kind", "index" }` reference to a column.
+    fn column(&self, node: &Node) -> Result<ColumnMid, ImportError> {
+        let index_node = node.field("index")?;
+        let index = index_node.usize()?;
+        let column_type = self.column_type(&node.field("kind")?, index)?;
+        let index = index_node.index(self.count(column_type), "column")?;
+        Ok(ColumnMid { index, column_type })
+    }
+
+    fn expression<F: PrimeField>(&self, node: &Node) -> Result<ExpressionMid<F>, ImportError> {
+        let operands = node.array()?;
+        let Some((op, operands)) = operands.split_first() else {
+            return node.error("expected an expression");
+        };
+        let op_name = op.str()?;
+        let arity = match op_name {
+            "constant" | "selector" | "challenge" | "-" => 1,
+            "fixed" | "instance" | "advice" | "+" | "*" | "scale" => 2,
+            _ => return op.error(format!("unknown operation {op_name:?}")),
+        };
+        if operands.len() != arity {
+            return node.error(format!(
+                "expected {arity} operands for {op_name:?}, found {}",
+                operands.len()
+            ));
+        }
+        let boxed = |node: &Node| self.expression(node).map(Box::new);
+        Ok(match op_name {
+            "constant" => ExpressionMid::Constant(operands[0].field_element()?),
+            "selector" => {
+                return node.error("selectors are not supported, convert them to fixed columns")
+            }
+            "fixed" => ExpressionMid::Fixed(FixedQueryMid {
+                column_index: operands[0].index(self.fixed, "fixed column")?,
+                rotation: Rotation(operands[1].i32()?),
+            }),
+            "instance" => ExpressionMid::Instance(InstanceQueryMid {
+                column_index: operands[0].index(self.instance, "instance column")?,
+                rotation: Rotation(operands[1].i32()?),
+            }),
+            "advice" => {
+                let column_index = operands[0].index(self.advice_phase.len(), "advice column")?;
+                ExpressionMid::Advice(AdviceQueryMid {
+                    column_index,
+                    rotation: Rotation(operands[1].i32()?),
+                    phase: self.advice_phase[column_index],
+                })
+            }
+            "challenge" => {
+                let index = operands[0].index(self.challenge_phase.len(), "challenge")?;
+                ExpressionMid::Challenge(ChallengeMid {
+                    index,
+                    phase: self.challenge_phase[index],
+                })
+            }
+            "-" => ExpressionMid::Negated(boxed(&operands[0])?),
+            "+" => ExpressionMid::Sum(boxed(&operands[0])?, boxed(&operands[1])?),
+            "*" => ExpressionMid::Product(boxed(&operands[0])?, boxed(&operands[1])?),
+            "scale" => ExpressionMid::Scaled(boxed(&operands[0])?, operands[1].field_element()?),
+            _ => unreachable!(),
+        })
+    }
+
+    fn expressions<F: PrimeField>(
+        &self,
+        node: &Node,
+    ) -> Result<Vec<ExpressionMid<F>>, ImportError> {
+        node.array()?
+            .iter()
+            .map(|node| self.expression(node))
+            .collect()
+    }
+}
+
+/// Builds a circuit from its JSON description, documented in the [module](self).
+pub fn from_json<F: PrimeField>(bytes: &[u8]) -> Result<CompiledCircuitV2<F>, ImportError> {
+    let value: Value = serde_json::from_slice(bytes).map_err(|error| ImportError {
+        path: "$".to_string(),
+        message: error.to_string(),
+    })?;
+    let root = Node {
+        value: &value,
+        path: "$".to_string(),
+    };
+
+    let version = root.field("version")?;
+    if version.u64()? != IMPORT_VERSION {
+        return version.error(format!("only version {IMPORT_VERSION} is supported"));
+    }
+    let k = root.field("k")?;
+    let n = match k.u64()? {
+        k @ 0..=31 => 1usize << k,
+        _ => return k.error("expected at most 31"),
+    };
+
+    let counts = root.field("counts")?;
+    if let Some(selectors) = counts.optional("selectors")? {
+        if selectors.usize()? != 0 {
+            return selectors.error("selectors are not supported, convert them to fixed columns");
+        }
+    }
+    let mut shape = Shape {
+        fixed: counts.field("fixed")?.usize()?,
+        instance: counts.field("instance")?.usize()?,
+        advice_phase: vec![0; counts.field("advice")?.usize()?],
+        challenge_phase: vec![0; counts.field("challenges")?.usize()?],
+    };
+
+    // The phases of the advice columns are needed to read the other columns.
+    let columns = root.field("columns")?.array()?;
+    for column in columns.iter() {
+        if column.field("kind")?.str()? == "advice" {
+            let index = column
+                .field("index")?
+                .index(shape.advice_phase.len(), "advice column")?;
+            if let Some(phase) = column.optional("phase")? {
+                shape.advice_phase[index] = phase.u8()?;
+            }
+        }
+    }
+    let mut unblinded_advice_columns = vec![];
+    let mut general_column_annotations = HashMap::new();
+    for column in columns.iter() {
+        let ColumnMid { index, column_type } = shape.column(column)?;
+        if let Some(unblinded) = column.optional("unblinded")? {
+            if unblinded.bool()? && matches!(column_type, Any::Advice(_)) {
+                unblinded_advice_columns.push(index);
+            }
+        }
+        if let Some(annotation) = column.optional("annotation")? {
+            general_column_annotations.insert(
+                metadata::Column { column_type, index },
+                annotation.str()?.to_string(),
+            );
+        }
+    }
+    let max_phase = shape.advice_phase.iter().copied().max().unwrap_or(0);
+    for phase in 0..max_phase {
+        if !shape.advice_phase.contains(&phase) {
+            return root.field("columns")?.error(format!(
+                "no advice column is in phase {phase}, before phase {max_phase}"
+            ));
+        }
+    }
+    for challenge in root.field("challenges")?.array()? {
+        let index = challenge
+            .field("index")?
+            .index(shape.challenge_phase.len(), "challenge")?;
+        let phase = challenge.field("phase")?;
+        shape.challenge_phase[index] = phase.u8()?;
+        if !shape.advice_phase.contains(&shape.challenge_phase[index]) {
+            return phase.error(format!(
+                "no advice column is in phase {}",
+                shape.challenge_phase[index]
+            ));
+        }
+    }
+
+    let gates = root
+        .field("gates")?
+        .array()?
+        .iter()
+        .map(|gate| {
+            Ok(GateV2Backend {
+                name: gate.field("name")?.str()?.to_string(),
+                poly: shape.expression(&gate.field("polynomial")?)?,
+            })
+        })
+        .collect::<Result<_, ImportError>>()?;
+    let lookups = root
+        .optional_array("lookups")?
+        .iter()
+        .map(|lookup| {
+            let tables = lookup.field("tables")?;
+            let argument = lookup::ArgumentV2 {
+                name: lookup.field("name")?.str()?.to_string(),
+                input_expressions: shape.expressions(&lookup.field("inputs")?)?,
+                table_expressions: shape.expressions(&tables)?,
+            };
+            if argument.input_expressions.len() != argument.table_expressions.len() {
+                return tables.error("expected as many tables as inputs");
+            }
+            Ok(argument)
+        })
+        .collect::<Result<_, ImportError>>()?;
+    let shuffles = root
+        .optional_array("shuffles")?
+        .iter()
+        .map(|shuffle| {
+            let shuffles = shuffle.field("shuffles")?;
+            let argument = shuffle::ArgumentV2 {
+                name: shuffle.field("name")?.str()?.to_string(),
+                input_expressions: shape.expressions(&shuffle.field("inputs")?)?,
+                shuffle_expressions: shape.expressions(&shuffles)?,
+            };
+            if argument.input_expressions.len() != argument.shuffle_expressions.len() {
+                return shuffles.error("expected as many shuffles as inputs");
+            }
+            Ok(argument)
+        })
+        .collect::<Result<_, ImportError>>()?;
+    let permutation_columns = root
+        .optional_array("permutation")?
+        .iter()
+        .map(|column| shape.column(column))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let fixed_values = root.optional_array("fixed")?;
+    if fixed_values.len() != shape.fixed {
+        return root.field("fixed")?.error(format!(
+            "expected the values of {} fixed columns, found {}",
+            shape.fixed,
+            fixed_values.len()
+        ));
+    }
+    let fixed = fixed_values
+        .iter()
+        .map(|column| {
+            let values = column.array()?;
+            if values.len() > n {
+                return column.error(format!("expected at most {n} values"));
+            }
+            let mut column = values
+                .iter()
+                .map(|value| value.field_element())
+                .collect::<Result<Vec<F>, _>>()?;
+            column.resize(n, F::ZERO);
+            Ok(column)
+        })
+        .collect::<Result<_, ImportError>>()?;
+    let copies = root
+        .optional_array("copies")?
+        .iter()
+        .map(|copy| {
+            let cells = copy.array()?;
+            let [left, right] = &cells[..] else {
+                return copy.error("expected a pair of cells");
+            };
+            let cell = |node: &Node| {
+                let column = shape.column(node)?;
+                if !permutation_columns.contains(&column) {
+                    return node.error("the column is not in the permutation");
+                }
+                Ok(Cell {
+                    column,
+                    row: node.field("row")?.index(n, "row")?,
+                })
+            };
+            Ok((cell(left)?, cell(right)?))
+        })
+        .collect::<Result<_, ImportError>>()?;
+
+    Ok(CompiledCircuitV2 {
+        preprocessing: PreprocessingV2 {
+            permutation: permutation::AssemblyMid { copies },
+            fixed,
+        },
+        cs: ConstraintSystemV2Backend {
+            num_fixed_columns: shape.fixed,
+            num_advice_columns: shape.advice_phase.len(),
+            num_instance_columns: shape.instance,
+            num_challenges: shape.challenge_phase.len(),
+            unblinded_advice_columns,
+            advice_column_phase: shape.advice_phase,
+            challenge_phase: shape.challenge_phase,
+            gates,
+            permutation: permutation::ArgumentV2 {
+                columns: permutation_columns,
+            },
+            lookups,
+            shuffles,
+            general_column_annotations,
+        },
+    })
+}
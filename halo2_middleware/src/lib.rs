@@ -1,8 +1,12 @@
 pub mod circuit;
+pub mod export;
+#[cfg(feature = "import")]
+pub mod import;
 pub mod lookup;
 pub mod metadata;
 pub mod permutation;
 pub mod poly;
 pub mod shuffle;
+pub mod validate;
 
 pub use ff;
@@ -11,3 +11,141 @@ pub struct ArgumentV2 {
     /// A sequence of columns involved in the argument.
     pub columns: Vec<ColumnMid>,
 }
+
+/// Summary statistics of the cycles of a permutation.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CycleStats {
+    /// The number of cycles with more than one cell.
+    pub cycles: usize,
+    /// The number of cells of the largest cycle, or 0 if there are no copies.
+    pub largest_cycle: usize,
+    /// The number of copy constraints, including the redundant ones.
+    pub copies: usize,
+}
+
+/// The cycles of the permutation that the copy constraints induce over the cells of the
+/// columns of a permutation argument, which are the equality classes of the cells.
+///
+/// Cells are `(column, row)` pairs, and the cells that are not copied are left out.
+#[derive(Clone, Debug)]
+pub struct CopyCycles {
+    columns: Vec<ColumnMid>,
+    n: usize,
+    /// The representative of the cycle of each cell, with cell `(column, row)` at
+    /// `column * n + row`. It is the first cell of the cycle in this order.
+    roots: Vec<usize>,
+    /// The cells of the cycles with more than one cell, grouped by cycle, and ordered by
+    /// their index.
+    cells: Vec<usize>,
+    copies: usize,
+}
+
+impl CopyCycles {
+    /// Computes the cycles of the copies of `assembly` between the columns of `argument`, in
+    /// a circuit with `n` rows.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a copy involves a column that is not in `argument`, or a row that is not
+    /// below `n`.
+    pub fn new(n: usize, argument: &ArgumentV2, assembly: &AssemblyMid) -> Self {
+        let columns = argument.columns.clone();
+        let index = |cell: &Cell| {
+            let column = columns
+                .iter()
+                .position(|column| *column == cell.column)
+                .expect("copied column should be in the permutation");
+            assert!(cell.row < n, "copied row should be below {n}");
+            column * n + cell.row
+        };
+
+        // A disjoint-set forest, whose roots are the first cell of their set.
+        let mut roots: Vec<usize> = (0..columns.len() * n).collect();
+        fn find(roots: &mut [usize], mut cell: usize) -> usize {
+            while roots[cell] != cell {
+                roots[cell] = roots[roots[cell]];
+                cell = roots[cell];
+            }
+            cell
+        }
+        for (left, right) in assembly.copies.iter() {
+            let left = find(&mut roots, index(left));
+            let right = find(&mut roots, index(right));
+            roots[left.max(right)] = left.min(right);
+        }
+        for cell in 0..roots.len() {
+            roots[cell] = find(&mut roots, cell);
+        }
+
+        let mut sizes = vec![0usize; roots.len()];
+        for root in roots.iter() {
+            sizes[*root] += 1;
+        }
+        let mut cells: Vec<usize> = (0..roots.len())
+            .filter(|cell| sizes[roots[*cell]] > 1)
+            .collect();
+        cells.sort_by_key(|cell| roots[*cell]);
+
+        CopyCycles {
+            columns,
+            n,
+            roots,
+            cells,
+            copies: assembly.copies.len(),
+        }
+    }
+
+    fn cell(&self, index: usize) -> (ColumnMid, usize) {
+        (self.columns[index / self.n], index % self.n)
+    }
+
+    fn index(&self, (column, row): (ColumnMid, usize)) -> Option<usize> {
+        let position = self.columns.iter().position(|c| *c == column)?;
+        (row < self.n).then_some(position * self.n + row)
+    }
+
+    /// Returns an iterator over the cycles with more than one cell, ordered by their first
+    /// cell, which only allocates the cycle being returned.
+    pub fn cycles(&self) -> impl Iterator<Item = Vec<(ColumnMid, usize)>> + '_ {
+        let mut cells = self.cells.as_slice();
+        std::iter::from_fn(move || {
+            let root = self.roots[*cells.first()?];
+            let len = cells
+                .iter()
+                .position(|cell| self.roots[*cell] != root)
+                .unwrap_or(cells.len());
+            let (cycle, rest) = cells.split_at(len);
+            cells = rest;
+            Some(cycle.iter().map(|cell| self.cell(*cell)).collect())
+        })
+    }
+
+    /// Returns the cycles with more than one cell, which are the equality classes of the
+    /// copied cells.
+    pub fn equality_classes(&self) -> Vec<Vec<(ColumnMid, usize)>> {
+        self.cycles().collect()
+    }
+
+    /// Returns the summary statistics of the cycles.
+    pub fn stats(&self) -> CycleStats {
+        let mut stats = CycleStats {
+            copies: self.copies,
+            ..CycleStats::default()
+        };
+        for cycle in self.cycles() {
+            stats.cycles += 1;
+            stats.largest_cycle = stats.largest_cycle.max(cycle.len());
+        }
+        stats
+    }
+
+    /// Returns whether the cells `a` and `b` are in the same cycle, that is whether they are
+    /// constrained to be equal. Cells outside of the permutation are only equal to
+    /// themselves.
+    pub fn contains(&self, a: (ColumnMid, usize), b: (ColumnMid, usize)) -> bool {
+        match (self.index(a), self.index(b)) {
+            (Some(a), Some(b)) => self.roots[a] == self.roots[b],
+            _ => a == b,
+        }
+    }
+}
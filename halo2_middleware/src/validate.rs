@@ -0,0 +1,415 @@
+//! Checks of the invariants of [`ConstraintSystemV2Backend`] and [`CompiledCircuitV2`].
+//!
+//! The frontend upholds these invariants, but circuits that are built directly from the
+//! middleware structs may not, and would otherwise fail with a panic during keygen or
+//! proving.
+
+use crate::circuit::{Any, ColumnMid, CompiledCircuitV2, ConstraintSystemV2Backend, ExpressionMid};
+use crate::metadata;
+use ff::Field;
+use std::fmt;
+
+/// An argument of a constraint system that holds expressions.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Location {
+    /// The gate with this index and name.
+    Gate { index: usize, name: String },
+    /// The lookup argument with this index and name.
+    Lookup { index: usize, name: String },
+    /// The shuffle argument with this index and name.
+    Shuffle { index: usize, name: String },
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Location::Gate { index, name } => write!(f, "gate {index} ({name:?})"),
+            Location::Lookup { index, name } => write!(f, "lookup {index} ({name:?})"),
+            Location::Shuffle { index, name } => write!(f, "shuffle {index} ({name:?})"),
+        }
+    }
+}
+
+/// A broken invariant of a constraint system or compiled circuit.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MidError {
+    /// There are not as many phases as advice columns, or as challenges.
+    PhaseCount {
+        what: &'static str,
+        expected: usize,
+        found: usize,
+    },
+    /// No advice column is in `phase`, although some are in a later phase.
+    MissingAdvicePhase { phase: u8 },
+    /// A challenge is squeezed after a phase that has no advice columns.
+    ChallengePhase { challenge: usize, phase: u8 },
+    /// An unblinded advice column doesn't exist.
+    UnblindedColumn { index: usize },
+    /// The column at `position` in the permutation doesn't exist.
+    PermutationColumn { position: usize, column: ColumnMid },
+    /// An expression queries a column that doesn't exist.
+    QueryColumn {
+        location: Location,
+        column: ColumnMid,
+    },
+    /// An expression queries an advice column in another phase than the column's.
+    QueryPhase {
+        location: Location,
+        column: usize,
+        phase: u8,
+        expected: u8,
+    },
+    /// An expression uses a challenge that doesn't exist.
+    QueryChallenge { location: Location, index: usize },
+    /// An expression uses a challenge in another phase than the one it is squeezed in.
+    QueryChallengePhase {
+        location: Location,
+        index: usize,
+        phase: u8,
+        expected: u8,
+    },
+    /// A lookup or shuffle argument has not as many input expressions as table expressions.
+    ArgumentLength {
+        location: Location,
+        inputs: usize,
+        tables: usize,
+    },
+    /// There are not as many preprocessed fixed columns as fixed columns.
+    FixedColumnCount { expected: usize, found: usize },
+    /// A preprocessed fixed column doesn't have one value per row.
+    FixedColumnLength {
+        index: usize,
+        name: Option<String>,
+        expected: usize,
+        found: usize,
+    },
+    /// The copy constraint at `copy` involves a cell of a column that doesn't exist, or
+    /// outside of the rows of the circuit.
+    CopyCell {
+        copy: usize,
+        column: ColumnMid,
+        row: usize,
+    },
+}
+
+impl fmt::Display for MidError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let column = |column: &ColumnMid| metadata::Column::from(*column);
+        match self {
+            MidError::PhaseCount {
+                what,
+                expected,
+                found,
+            } => write!(f, "expected {expected} phases of {what}, got {found}"),
+            MidError::MissingAdvicePhase { phase } => {
+                write!(f, "no advice column is in phase {phase}, but a later phase has some")
+            }
+            MidError::ChallengePhase { challenge, phase } => write!(
+                f,
+                "challenge {challenge} is squeezed after phase {phase}, which has no advice columns"
+            ),
+            MidError::UnblindedColumn { index } => {
+                write!(f, "unblinded advice column {index} doesn't exist")
+            }
+            MidError::PermutationColumn { position, column: c } => write!(
+                f,
+                "{} at position {position} of the permutation doesn't exist",
+                column(c)
+            ),
+            MidError::QueryColumn {
+                location,
+                column: c,
+            } => write!(f, "{location} queries {}, which doesn't exist", column(c)),
+            MidError::QueryPhase {
+                location,
+                column,
+                phase,
+                expected,
+            } => write!(
+                f,
+                "{location} queries advice column {column} in phase {phase}, but it is in phase {expected}"
+            ),
+            MidError::QueryChallenge { location, index } => {
+                write!(f, "{location} uses challenge {index}, which doesn't exist")
+            }
+            MidError::QueryChallengePhase {
+                location,
+                index,
+                phase,
+                expected,
+            } => write!(
+                f,
+                "{location} uses challenge {index} in phase {phase}, but it is squeezed in phase {expected}"
+            ),
+            MidError::ArgumentLength {
+                location,
+                inputs,
+                tables,
+            } => write!(
+                f,
+                "{location} has {inputs} input expressions and {tables} table expressions"
+            ),
+            MidError::FixedColumnCount { expected, found } => {
+                write!(f, "expected {expected} fixed columns, got {found}")
+            }
+            MidError::FixedColumnLength {
+                index,
+                name,
+                expected,
+                found,
+            } => {
+                write!(f, "expected fixed column {index}")?;
+                if let Some(name) = name {
+                    write!(f, " ({name:?})")?;
+                }
+                write!(f, " to have length {expected}, got {found}")
+            }
+            MidError::CopyCell {
+                copy,
+                column: c,
+                row,
+            } => write!(
+                f,
+                "copy constraint {copy} involves {} at row {row}, which doesn't exist",
+                column(c)
+            ),
+        }
+    }
+}
+
+impl std::error::Error for MidError {}
+
+impl<F: Field> ConstraintSystemV2Backend<F> {
+    /// Checks that the columns, challenges and phases referenced by the constraint system
+    /// exist and are consistent, and returns every broken invariant.
+    pub fn validate(&self) -> Result<(), Vec<MidError>> {
+        let mut errors = vec![];
+        for (what, expected, found) in [
+            (
+                "advice columns",
+                self.num_advice_columns,
+                self.advice_column_phase.len(),
+            ),
+            (
+                "challenges",
+                self.num_challenges,
+                self.challenge_phase.len(),
+            ),
+        ] {
+            if expected != found {
+                errors.push(MidError::PhaseCount {
+                    what,
+                    expected,
+                    found,
+                });
+            }
+        }
+
+        let max_phase = self.advice_column_phase.iter().copied().max().unwrap_or(0);
+        for phase in 0..max_phase {
+            if !self.advice_column_phase.contains(&phase) {
+                errors.push(MidError::MissingAdvicePhase { phase });
+            }
+        }
+        for (challenge, phase) in self.challenge_phase.iter().enumerate() {
+            if !self.advice_column_phase.contains(phase) {
+                errors.push(MidError::ChallengePhase {
+                    challenge,
+                    phase: *phase,
+                });
+            }
+        }
+        for index in self.unblinded_advice_columns.iter() {
+            if *index >= self.num_advice_columns {
+                errors.push(MidError::UnblindedColumn { index: *index });
+            }
+        }
+        for (position, column) in self.permutation.columns.iter().enumerate() {
+            if !self.column_exists(column) {
+                errors.push(MidError::PermutationColumn {
+                    position,
+                    column: *column,
+                });
+            }
+        }
+
+        for (index, gate) in self.gates.iter().enumerate() {
+            let location = Location::Gate {
+                index,
+                name: gate.name.clone(),
+            };
+            self.validate_expression(&location, &gate.poly, &mut errors);
+        }
+        for (index, lookup) in self.lookups.iter().enumerate() {
+            let location = Location::Lookup {
+                index,
+                name: lookup.name.clone(),
+            };
+            self.validate_argument(
+                location,
+                &lookup.input_expressions,
+                &lookup.table_expressions,
+                &mut errors,
+            );
+        }
+        for (index, shuffle) in self.shuffles.iter().enumerate() {
+            let location = Location::Shuffle {
+                index,
+                name: shuffle.name.clone(),
+            };
+            self.validate_argument(
+                location,
+                &shuffle.input_expressions,
+                &shuffle.shuffle_expressions,
+                &mut errors,
+            );
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    fn column_exists(&self, column: &ColumnMid) -> bool {
+        let count = match column.column_type {
+            Any::Fixed => self.num_fixed_columns,
+            Any::Instance => self.num_instance_columns,
+            Any::Advice(_) => self.num_advice_columns,
+        };
+        column.index < count
+    }
+
+    fn validate_argument(
+        &self,
+        location: Location,
+        inputs: &[ExpressionMid<F>],
+        tables: &[ExpressionMid<F>],
+        errors: &mut Vec<MidError>,
+    ) {
+        for expr in inputs.iter().chain(tables.iter()) {
+            self.validate_expression(&location, expr, errors);
+        }
+        if inputs.len() != tables.len() {
+            errors.push(MidError::ArgumentLength {
+                location,
+                inputs: inputs.len(),
+                tables: tables.len(),
+            });
+        }
+    }
+
+    fn validate_expression(
+        &self,
+        location: &Location,
+        expr: &ExpressionMid<F>,
+        errors: &mut Vec<MidError>,
+    ) {
+        let mut query = |index: usize, column_type: Any| {
+            let column = ColumnMid { index, column_type };
+            if !self.column_exists(&column) {
+                errors.push(MidError::QueryColumn {
+                    location: location.clone(),
+                    column,
+                });
+            }
+        };
+        match expr {
+            ExpressionMid::Constant(_) => {}
+            ExpressionMid::Fixed(q) => query(q.column_index, Any::Fixed),
+            ExpressionMid::Instance(q) => query(q.column_index, Any::Instance),
+            ExpressionMid::Advice(q) => {
+                query(q.column_index, Any::advice_in(q.phase));
+                if let Some(expected) = self.advice_column_phase.get(q.column_index) {
+                    if q.phase != *expected {
+                        errors.push(MidError::QueryPhase {
+                            location: location.clone(),
+                            column: q.column_index,
+                            phase: q.phase,
+                            expected: *expected,
+                        });
+                    }
+                }
+            }
+            ExpressionMid::Challenge(c) => {
+                if c.index >= self.num_challenges {
+                    errors.push(MidError::QueryChallenge {
+                        location: location.clone(),
+                        index: c.index,
+                    });
+                } else if let Some(expected) = self.challenge_phase.get(c.index) {
+                    if c.phase != *expected {
+                        errors.push(MidError::QueryChallengePhase {
+                            location: location.clone(),
+                            index: c.index,
+                            phase: c.phase,
+                            expected: *expected,
+                        });
+                    }
+                }
+            }
+            ExpressionMid::Negated(a) | ExpressionMid::Scaled(a, _) => {
+                self.validate_expression(location, a, errors)
+            }
+            ExpressionMid::Sum(a, b) | ExpressionMid::Product(a, b) => {
+                self.validate_expression(location, a, errors);
+                self.validate_expression(location, b, errors);
+            }
+        }
+    }
+}
+
+impl<F: Field> CompiledCircuitV2<F> {
+    /// Checks the constraint system with [`ConstraintSystemV2Backend::validate`], and that
+    /// the preprocessed fixed columns and the copy constraints fit in a circuit with `2^k`
+    /// rows. Returns every broken invariant.
+    pub fn validate(&self, k: u32) -> Result<(), Vec<MidError>> {
+        let mut errors = self.cs.validate().err().unwrap_or_default();
+        let n = 1usize << k;
+
+        let fixed = &self.preprocessing.fixed;
+        if fixed.len() != self.cs.num_fixed_columns {
+            errors.push(MidError::FixedColumnCount {
+                expected: self.cs.num_fixed_columns,
+                found: fixed.len(),
+            });
+        }
+        for (index, column) in fixed.iter().enumerate() {
+            if column.len() != n {
+                let name = self
+                    .cs
+                    .general_column_annotations
+                    .get(&metadata::Column {
+                        column_type: Any::Fixed,
+                        index,
+                    })
+                    .cloned();
+                errors.push(MidError::FixedColumnLength {
+                    index,
+                    name,
+                    expected: n,
+                    found: column.len(),
+                });
+            }
+        }
+
+        for (copy, (left, right)) in self.preprocessing.permutation.copies.iter().enumerate() {
+            for cell in [left, right] {
+                if !self.cs.column_exists(&cell.column) || cell.row >= n {
+                    errors.push(MidError::CopyCell {
+                        copy,
+                        column: cell.column,
+                        row: cell.row,
+                    });
+                }
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
@@ -0,0 +1,132 @@
+#[macro_use]
+extern crate criterion;
+
+use ff::Field;
+use halo2_common::circuit::layouter::SyncDeps;
+use halo2_common::plonk::Assignment;
+use halo2_frontend::circuit::{compile_circuit, WitnessCalculator};
+use halo2_proofs::circuit::{Layouter, Value};
+use halo2_proofs::plonk::*;
+use halo2curves::pasta::pallas;
+
+use std::collections::HashMap;
+use std::marker::PhantomData;
+
+use criterion::{BenchmarkId, Criterion};
+
+const K: u32 = 21;
+const CELLS: usize = 1 << 20;
+
+/// A floor planner that fills the first advice column with `CELLS` values, either
+/// cell by cell or with a single `Assignment::assign_advice_column` call.
+struct ColumnPlanner<const BATCH: bool>;
+
+impl<const BATCH: bool> FloorPlanner for ColumnPlanner<BATCH> {
+    fn synthesize<F: Field, CS: Assignment<F> + SyncDeps, C: Circuit<F>>(
+        cs: &mut CS,
+        _: &C,
+        _: C::Config,
+        _: Vec<Column<Fixed>>,
+    ) -> Result<(), Error> {
+        let column = Column::new(0, Advice::default());
+        let mut value = F::ONE;
+        let values: Vec<Value<Assigned<F>>> = (0..CELLS)
+            .map(|_| {
+                value = value.double() + F::ONE;
+                Value::known(value.into())
+            })
+            .collect();
+
+        if BATCH {
+            cs.assign_advice_column(column, 0, &values)
+        } else {
+            for (row, value) in values.iter().enumerate() {
+                cs.assign_advice(|| "", column, row, || *value)?;
+            }
+            Ok(())
+        }
+    }
+}
+
+/// A floor planner that pads the first advice column with a default value up to its last
+/// usable row, either cell by cell or with a single `Assignment::fill_advice_from_row`
+/// call.
+struct FillPlanner<const FILL: bool>;
+
+impl<const FILL: bool> FloorPlanner for FillPlanner<FILL> {
+    fn synthesize<F: Field, CS: Assignment<F> + SyncDeps, C: Circuit<F>>(
+        cs: &mut CS,
+        _: &C,
+        _: C::Config,
+        _: Vec<Column<Fixed>>,
+    ) -> Result<(), Error> {
+        let column = Column::new(0, Advice::default());
+        let filler = Value::known(Assigned::Zero);
+
+        if FILL {
+            cs.fill_advice_from_row(column, 0, filler)
+        } else {
+            for row in 0.. {
+                match cs.assign_advice(|| "", column, row, || filler) {
+                    Ok(()) => (),
+                    Err(Error::NotEnoughRowsAvailable { .. }) => break,
+                    Err(err) => return Err(err),
+                }
+            }
+            Ok(())
+        }
+    }
+}
+
+struct MyCircuit<F: Field, P: FloorPlanner>(PhantomData<(F, P)>);
+
+impl<F: Field, P: FloorPlanner> Circuit<F> for MyCircuit<F, P> {
+    type Config = Column<Advice>;
+    type FloorPlanner = P;
+    #[cfg(feature = "circuit-params")]
+    type Params = ();
+
+    fn without_witnesses(&self) -> Self {
+        Self(PhantomData)
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        meta.advice_column()
+    }
+
+    fn synthesize(&self, _: Self::Config, _: impl Layouter<F>) -> Result<(), Error> {
+        unreachable!("ColumnPlanner does not synthesize the circuit")
+    }
+}
+
+fn witness<P: FloorPlanner>() {
+    let circuit = MyCircuit::<pallas::Base, P>(PhantomData);
+    let (_, config, cs) = compile_circuit(K, &circuit, false).unwrap();
+    let mut witness_calc = WitnessCalculator::new(K, &circuit, &config, &cs, &[]);
+    witness_calc.calc(0, &HashMap::new()).unwrap();
+}
+
+fn criterion_benchmark(c: &mut Criterion) {
+    let mut group = c.benchmark_group("advice-column");
+    group.sample_size(10);
+    group.bench_function(BenchmarkId::from_parameter("loop"), |b| {
+        b.iter(witness::<ColumnPlanner<false>>);
+    });
+    group.bench_function(BenchmarkId::from_parameter("column"), |b| {
+        b.iter(witness::<ColumnPlanner<true>>);
+    });
+    group.finish();
+
+    let mut group = c.benchmark_group("advice-fill");
+    group.sample_size(10);
+    group.bench_function(BenchmarkId::from_parameter("loop"), |b| {
+        b.iter(witness::<FillPlanner<false>>);
+    });
+    group.bench_function(BenchmarkId::from_parameter("fill"), |b| {
+        b.iter(witness::<FillPlanner<true>>);
+    });
+    group.finish();
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
@@ -0,0 +1,40 @@
+#[macro_use]
+extern crate criterion;
+
+use ff::Field;
+use halo2_proofs::plonk::Assigned;
+use halo2curves::pasta::Fp;
+use rand_core::OsRng;
+
+use criterion::{black_box, Criterion};
+
+const NUM_VALUES: usize = 1 << 20;
+
+fn criterion_benchmark(c: &mut Criterion) {
+    // A mix of trivial values and rationals, as produced by a typical circuit.
+    let values: Vec<_> = (0..NUM_VALUES)
+        .map(|i| match i % 4 {
+            0 => Assigned::Zero,
+            1 => Assigned::Trivial(Fp::random(OsRng)),
+            _ => Assigned::Rational(Fp::random(OsRng), Fp::random(OsRng)),
+        })
+        .collect();
+
+    let mut group = c.benchmark_group("assigned-evaluation");
+    group.sample_size(10);
+    group.bench_function("evaluate", |b| {
+        b.iter(|| {
+            black_box(&values)
+                .iter()
+                .map(|value| value.evaluate())
+                .collect::<Vec<_>>()
+        })
+    });
+    group.bench_function("batch_evaluate", |b| {
+        b.iter(|| Assigned::batch_evaluate(black_box(&values)))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
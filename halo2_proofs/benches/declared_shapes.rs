@@ -0,0 +1,94 @@
+#[macro_use]
+extern crate criterion;
+
+use ff::PrimeField;
+use halo2_middleware::circuit::Any;
+use halo2_proofs::circuit::floor_planner::{V1SinglePass, V1};
+use halo2_proofs::circuit::layouter::{RegionColumn, RegionShape};
+use halo2_proofs::circuit::{Layouter, Value};
+use halo2_proofs::dev::MockProver;
+use halo2_proofs::plonk::*;
+use halo2curves::pasta::pallas;
+
+use std::marker::PhantomData;
+
+use criterion::{BenchmarkId, Criterion};
+
+const K: u32 = 14;
+const ROWS: usize = 10_000;
+/// The number of squarings used to compute each witness value.
+const ROUNDS: usize = 100;
+
+fn criterion_benchmark(c: &mut Criterion) {
+    struct MyCircuit<F: PrimeField, P> {
+        _marker: PhantomData<(F, P)>,
+    }
+
+    impl<F: PrimeField, P> Clone for MyCircuit<F, P> {
+        fn clone(&self) -> Self {
+            MyCircuit {
+                _marker: PhantomData,
+            }
+        }
+    }
+
+    impl<F: PrimeField, P: FloorPlanner> Circuit<F> for MyCircuit<F, P> {
+        type Config = Column<Advice>;
+        type FloorPlanner = P;
+        #[cfg(feature = "circuit-params")]
+        type Params = ();
+
+        fn without_witnesses(&self) -> Self {
+            self.clone()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Column<Advice> {
+            meta.advice_column()
+        }
+
+        fn region_shapes(&self, config: &Column<Advice>) -> Option<Vec<RegionShape>> {
+            let column = RegionColumn::from(Column::<Any>::from(*config));
+            Some(vec![RegionShape::declare(0.into(), [column], ROWS)])
+        }
+
+        fn synthesize(
+            &self,
+            config: Column<Advice>,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            layouter.assign_region(
+                || "witness",
+                |mut region| {
+                    // Gadgets commonly compute their witness eagerly, so that the work is
+                    // repeated by every synthesis pass.
+                    let mut value = F::from(2);
+                    for offset in 0..ROWS {
+                        for _ in 0..ROUNDS {
+                            value = value.square() + F::ONE;
+                        }
+                        region.assign_advice(|| "value", config, offset, || Value::known(value))?;
+                    }
+                    Ok(())
+                },
+            )
+        }
+    }
+
+    fn prover<P: FloorPlanner>() {
+        let circuit = MyCircuit::<pallas::Base, P> {
+            _marker: PhantomData,
+        };
+        MockProver::run(K, &circuit, vec![]).unwrap();
+    }
+
+    let mut group = c.benchmark_group("declared-shapes");
+    group.sample_size(10);
+    group.bench_function(BenchmarkId::from_parameter("v1"), |b| b.iter(prover::<V1>));
+    group.bench_function(BenchmarkId::from_parameter("v1-single-pass"), |b| {
+        b.iter(prover::<V1SinglePass>)
+    });
+    group.finish();
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
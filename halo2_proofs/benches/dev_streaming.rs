@@ -0,0 +1,191 @@
+//! Compares the peak heap usage and the running time of `MockProver::run` followed by
+//! `MockProver::verify` with those of `MockProver::run_streaming`.
+//!
+//! Run with `cargo bench --bench dev_streaming`.
+
+use ff::{Field, PrimeField};
+use halo2_proofs::circuit::{Layouter, SimpleFloorPlanner, Value};
+use halo2_proofs::dev::MockProver;
+use halo2_proofs::plonk::*;
+use halo2_proofs::poly::Rotation;
+use halo2curves::pasta::pallas;
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::marker::PhantomData;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+
+/// An allocator that tracks the peak number of allocated bytes.
+struct PeakAlloc {
+    current: AtomicUsize,
+    peak: AtomicUsize,
+}
+
+impl PeakAlloc {
+    /// Resets the peak to the current number of allocated bytes.
+    fn reset(&self) {
+        self.peak
+            .store(self.current.load(Ordering::SeqCst), Ordering::SeqCst);
+    }
+
+    /// Returns the peak number of bytes allocated since the last reset, on top of those
+    /// allocated at the reset.
+    fn peak_since(&self, base: usize) -> usize {
+        self.peak.load(Ordering::SeqCst) - base
+    }
+}
+
+unsafe impl GlobalAlloc for PeakAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            let current = self.current.fetch_add(layout.size(), Ordering::SeqCst) + layout.size();
+            self.peak.fetch_max(current, Ordering::SeqCst);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        self.current.fetch_sub(layout.size(), Ordering::SeqCst);
+    }
+}
+
+#[global_allocator]
+static ALLOC: PeakAlloc = PeakAlloc {
+    current: AtomicUsize::new(0),
+    peak: AtomicUsize::new(0),
+};
+
+/// A circuit with several gates and a lookup enabled on every row.
+#[derive(Clone, Default)]
+struct WideCircuit<F: Field> {
+    rows: usize,
+    _marker: PhantomData<F>,
+}
+
+#[derive(Clone)]
+struct WideConfig {
+    a: Column<Advice>,
+    b: Column<Advice>,
+    c: Column<Advice>,
+    s: Selector,
+    s_lookup: Selector,
+    table: TableColumn,
+}
+
+impl<F: PrimeField> Circuit<F> for WideCircuit<F> {
+    type Config = WideConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+    #[cfg(feature = "circuit-params")]
+    type Params = ();
+
+    fn without_witnesses(&self) -> Self {
+        self.clone()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> WideConfig {
+        let a = meta.advice_column();
+        let b = meta.advice_column();
+        let c = meta.advice_column();
+        let s = meta.selector();
+        let s_lookup = meta.complex_selector();
+        let table = meta.lookup_table_column();
+
+        meta.create_gate("increment", |meta| {
+            let s = meta.query_selector(s);
+            let a_cur = meta.query_advice(a, Rotation::cur());
+            let a_next = meta.query_advice(a, Rotation::next());
+            vec![s * (a_next - a_cur - Expression::Constant(F::ONE))]
+        });
+        meta.create_gate("product", |meta| {
+            let s = meta.query_selector(s);
+            let a = meta.query_advice(a, Rotation::cur());
+            let b = meta.query_advice(b, Rotation::cur());
+            let c = meta.query_advice(c, Rotation::cur());
+            vec![s * (a * b - c)]
+        });
+        meta.lookup("low byte", |meta| {
+            let s = meta.query_selector(s_lookup);
+            let b = meta.query_advice(b, Rotation::cur());
+            vec![(s * b, table)]
+        });
+
+        WideConfig {
+            a,
+            b,
+            c,
+            s,
+            s_lookup,
+            table,
+        }
+    }
+
+    fn synthesize(&self, config: WideConfig, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        layouter.assign_table(
+            || "8-bit table",
+            |mut table| {
+                for row in 0u64..(1 << 8) {
+                    table.assign_cell(
+                        || format!("row {row}"),
+                        config.table,
+                        row as usize,
+                        || Value::known(F::from(row)),
+                    )?;
+                }
+                Ok(())
+            },
+        )?;
+
+        layouter.assign_region(
+            || "counter",
+            |mut region| {
+                for offset in 0..self.rows {
+                    if offset + 1 < self.rows {
+                        config.s.enable(&mut region, offset)?;
+                    }
+                    config.s_lookup.enable(&mut region, offset)?;
+                    let a = F::from(offset as u64);
+                    let b = F::from(offset as u64 % 256);
+                    region.assign_advice(|| "a", config.a, offset, || Value::known(a))?;
+                    region.assign_advice(|| "b", config.b, offset, || Value::known(b))?;
+                    region.assign_advice(|| "c", config.c, offset, || Value::known(a * b))?;
+                }
+                Ok(())
+            },
+        )
+    }
+}
+
+/// Runs `f`, and returns the peak heap usage in bytes and the running time.
+fn measure(f: impl FnOnce()) -> (usize, f64) {
+    ALLOC.reset();
+    let base = ALLOC.current.load(Ordering::SeqCst);
+    let start = Instant::now();
+    f();
+    (ALLOC.peak_since(base), start.elapsed().as_secs_f64())
+}
+
+fn main() {
+    const CHUNK_ROWS: usize = 1 << 10;
+
+    println!("k,mode,peak_heap_bytes,seconds");
+    for k in 14..=17 {
+        let circuit = WideCircuit::<pallas::Base> {
+            rows: (1 << k) - 10,
+            _marker: PhantomData,
+        };
+
+        let (peak, seconds) = measure(|| {
+            let prover = MockProver::run(k, &circuit, vec![]).unwrap();
+            assert_eq!(prover.verify(), Ok(()));
+        });
+        println!("{k},in-memory,{peak},{seconds:.3}");
+
+        let (peak, seconds) = measure(|| {
+            let result = MockProver::run_streaming(k, &circuit, vec![], CHUNK_ROWS).unwrap();
+            assert_eq!(result, Ok(()));
+        });
+        println!("{k},streaming,{peak},{seconds:.3}");
+    }
+}
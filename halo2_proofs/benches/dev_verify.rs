@@ -0,0 +1,144 @@
+#[macro_use]
+extern crate criterion;
+
+use ff::{Field, PrimeField};
+use halo2_proofs::circuit::{Layouter, SimpleFloorPlanner, Value};
+use halo2_proofs::dev::MockProver;
+use halo2_proofs::plonk::*;
+use halo2_proofs::poly::Rotation;
+use halo2curves::pasta::pallas;
+
+use std::marker::PhantomData;
+
+use criterion::{BenchmarkId, Criterion};
+
+fn criterion_benchmark(c: &mut Criterion) {
+    /// A circuit with several gates and a lookup enabled on almost every row.
+    #[derive(Clone, Default)]
+    struct HeavyCircuit<F: Field> {
+        rows: usize,
+        _marker: PhantomData<F>,
+    }
+
+    #[derive(Clone)]
+    struct HeavyConfig {
+        a: Column<Advice>,
+        b: Column<Advice>,
+        c: Column<Advice>,
+        s: Selector,
+        s_lookup: Selector,
+        table: TableColumn,
+    }
+
+    impl<F: PrimeField> Circuit<F> for HeavyCircuit<F> {
+        type Config = HeavyConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+        #[cfg(feature = "circuit-params")]
+        type Params = ();
+
+        fn without_witnesses(&self) -> Self {
+            self.clone()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> HeavyConfig {
+            let a = meta.advice_column();
+            let b = meta.advice_column();
+            let c = meta.advice_column();
+            let s = meta.selector();
+            let s_lookup = meta.complex_selector();
+            let table = meta.lookup_table_column();
+
+            meta.create_gate("fibonacci", |meta| {
+                let s = meta.query_selector(s);
+                let a_cur = meta.query_advice(a, Rotation::cur());
+                let a_next = meta.query_advice(a, Rotation::next());
+                let b_cur = meta.query_advice(b, Rotation::cur());
+                let b_next = meta.query_advice(b, Rotation::next());
+                vec![
+                    s.clone() * (a_next - b_cur.clone()),
+                    s * (b_next - a_cur - b_cur),
+                ]
+            });
+            meta.create_gate("product", |meta| {
+                let s = meta.query_selector(s);
+                let a = meta.query_advice(a, Rotation::cur());
+                let b = meta.query_advice(b, Rotation::cur());
+                let c = meta.query_advice(c, Rotation::cur());
+                vec![s * (a * b - c)]
+            });
+            meta.lookup("low byte", |meta| {
+                let s = meta.query_selector(s_lookup);
+                let a = meta.query_advice(a, Rotation::cur());
+                vec![(s * a, table)]
+            });
+
+            HeavyConfig {
+                a,
+                b,
+                c,
+                s,
+                s_lookup,
+                table,
+            }
+        }
+
+        fn synthesize(
+            &self,
+            config: HeavyConfig,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            layouter.assign_table(
+                || "8-bit table",
+                |mut table| {
+                    for row in 0u64..(1 << 8) {
+                        table.assign_cell(
+                            || format!("row {row}"),
+                            config.table,
+                            row as usize,
+                            || Value::known(F::from(row)),
+                        )?;
+                    }
+                    Ok(())
+                },
+            )?;
+
+            layouter.assign_region(
+                || "fibonacci",
+                |mut region| {
+                    let (mut a, mut b) = (F::ONE, F::ONE);
+                    for offset in 0..self.rows {
+                        if offset + 1 < self.rows {
+                            config.s.enable(&mut region, offset)?;
+                        }
+                        // Only the first values of the sequence fit in the table.
+                        if offset < 12 {
+                            config.s_lookup.enable(&mut region, offset)?;
+                        }
+                        region.assign_advice(|| "a", config.a, offset, || Value::known(a))?;
+                        region.assign_advice(|| "b", config.b, offset, || Value::known(b))?;
+                        region.assign_advice(|| "c", config.c, offset, || Value::known(a * b))?;
+                        (a, b) = (b, a + b);
+                    }
+                    Ok(())
+                },
+            )
+        }
+    }
+
+    let mut group = c.benchmark_group("dev-verify");
+    group.sample_size(10);
+    for k in 14..=17 {
+        let circuit = HeavyCircuit::<pallas::Base> {
+            rows: (1 << k) - 10,
+            _marker: PhantomData,
+        };
+        let prover = MockProver::run(k, &circuit, vec![]).unwrap();
+        group.bench_with_input(BenchmarkId::from_parameter(k), &prover, |b, prover| {
+            b.iter(|| assert_eq!(prover.verify(), Ok(())));
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
@@ -0,0 +1,104 @@
+#[macro_use]
+extern crate criterion;
+
+use halo2_proofs::circuit::{Layouter, SimpleFloorPlanner, Value};
+use halo2_proofs::plonk::*;
+use halo2_proofs::poly::commitment::ParamsProver;
+use halo2_proofs::poly::kzg::commitment::{KZGCommitmentScheme, ParamsKZG};
+use halo2_proofs::poly::kzg::multiopen::ProverSHPLONK;
+use halo2_proofs::poly::Rotation;
+use halo2_proofs::transcript::{Blake2bWrite, Challenge255, TranscriptWriterBuffer};
+use halo2curves::bn256::{Bn256, Fr, G1Affine};
+use rand_core::OsRng;
+
+use criterion::measurement::WallTime;
+use criterion::{BenchmarkGroup, BenchmarkId, Criterion};
+
+const K: u32 = 8;
+/// The number of terms of the polynomial of each gate.
+const TERMS: usize = 8;
+
+fn criterion_benchmark(c: &mut Criterion) {
+    /// Repeats the same gate `COPIES` times, as macro-generated configurations do. The
+    /// evaluator computes the polynomial of the gate once per row, and reuses it for every
+    /// copy.
+    #[derive(Clone, Default)]
+    struct DuplicatedCircuit<const COPIES: usize>;
+
+    impl<const COPIES: usize> Circuit<Fr> for DuplicatedCircuit<COPIES> {
+        type Config = (Column<Advice>, Selector);
+        type FloorPlanner = SimpleFloorPlanner;
+        #[cfg(feature = "circuit-params")]
+        type Params = ();
+
+        fn without_witnesses(&self) -> Self {
+            Self
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+            let a = meta.advice_column();
+            let s = meta.selector();
+            for _ in 0..COPIES {
+                meta.create_gate("power sum", |meta| {
+                    let s = meta.query_selector(s);
+                    let a = meta.query_advice(a, Rotation::cur());
+                    let sum = (1..TERMS).fold(a.clone(), |sum, i| {
+                        sum * a.clone() + Expression::Constant(Fr::from(i as u64))
+                    });
+                    vec![s * sum]
+                });
+            }
+            (a, s)
+        }
+
+        fn synthesize(
+            &self,
+            (a, _): Self::Config,
+            mut layouter: impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            layouter.assign_region(
+                || "unused",
+                |mut region| {
+                    region.assign_advice(|| "a", a, 0, || Value::known(Fr::from(0)))?;
+                    Ok(())
+                },
+            )
+        }
+    }
+
+    /// Benches the proof of the circuit with `COPIES` copies of the gate, whose quotient
+    /// polynomial is evaluated with the intermediates of the gate shared by every copy.
+    fn prover<const COPIES: usize>(
+        group: &mut BenchmarkGroup<'_, WallTime>,
+        params: &ParamsKZG<Bn256>,
+    ) {
+        let vk = keygen_vk(params, &DuplicatedCircuit::<COPIES>).unwrap();
+        let pk = keygen_pk::<G1Affine, _, _>(params, vk, &DuplicatedCircuit::<COPIES>).unwrap();
+        group.bench_function(BenchmarkId::new("prover", COPIES), |b| {
+            b.iter(|| {
+                let mut transcript = Blake2bWrite::<_, _, Challenge255<_>>::init(vec![]);
+                create_proof::<KZGCommitmentScheme<Bn256>, ProverSHPLONK<_>, _, _, _, _>(
+                    params,
+                    &pk,
+                    &[DuplicatedCircuit::<COPIES>],
+                    &[&[]],
+                    OsRng,
+                    &mut transcript,
+                )
+                .expect("proof generation should not fail");
+                transcript.finalize()
+            })
+        });
+    }
+
+    let params = ParamsKZG::<Bn256>::new(K);
+    let mut group = c.benchmark_group("duplicated-gates");
+    group.sample_size(10);
+    prover::<1>(&mut group, &params);
+    prover::<16>(&mut group, &params);
+    prover::<64>(&mut group, &params);
+    group.finish();
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
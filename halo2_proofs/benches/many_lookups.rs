@@ -0,0 +1,148 @@
+#[macro_use]
+extern crate criterion;
+
+use halo2_proofs::circuit::{Layouter, SimpleFloorPlanner, Value};
+use halo2_proofs::plonk::*;
+use halo2_proofs::poly::commitment::ParamsProver;
+use halo2_proofs::poly::kzg::commitment::{KZGCommitmentScheme, ParamsKZG};
+use halo2_proofs::poly::kzg::multiopen::ProverSHPLONK;
+use halo2_proofs::poly::Rotation;
+use halo2_proofs::transcript::{Blake2bWrite, Challenge255, TranscriptWriterBuffer};
+use halo2curves::bn256::{Bn256, Fr, G1Affine};
+use rand_core::OsRng;
+
+use criterion::{BenchmarkId, Criterion};
+
+const LOOKUPS: usize = 14;
+const SHUFFLES: usize = 4;
+
+#[derive(Clone)]
+struct LookupsConfig {
+    table: Column<Fixed>,
+    inputs: Vec<Column<Advice>>,
+    shuffled: Vec<Column<Advice>>,
+}
+
+/// Looks up each of its `LOOKUPS` columns of `rows` cells in a table of bytes, and shuffles
+/// the first `SHUFFLES` of them into other columns.
+#[derive(Clone, Default)]
+struct LookupsCircuit {
+    rows: usize,
+}
+
+impl Circuit<Fr> for LookupsCircuit {
+    type Config = LookupsConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+    #[cfg(feature = "circuit-params")]
+    type Params = ();
+
+    fn without_witnesses(&self) -> Self {
+        self.clone()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fr>) -> LookupsConfig {
+        let table = meta.fixed_column();
+        let inputs: Vec<_> = (0..LOOKUPS).map(|_| meta.advice_column()).collect();
+        let shuffled: Vec<_> = (0..SHUFFLES).map(|_| meta.advice_column()).collect();
+
+        for input in inputs.iter() {
+            meta.lookup_any("byte", |meta| {
+                let input = meta.query_advice(*input, Rotation::cur());
+                let table = meta.query_fixed(table, Rotation::cur());
+                vec![(input, table)]
+            });
+        }
+        for (input, shuffled) in inputs.iter().zip(shuffled.iter()) {
+            meta.shuffle("reversed", |meta| {
+                let input = meta.query_advice(*input, Rotation::cur());
+                let shuffled = meta.query_advice(*shuffled, Rotation::cur());
+                vec![(input, shuffled)]
+            });
+        }
+
+        LookupsConfig {
+            table,
+            inputs,
+            shuffled,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: LookupsConfig,
+        mut layouter: impl Layouter<Fr>,
+    ) -> Result<(), Error> {
+        layouter.assign_region(
+            || "lookups",
+            |mut region| {
+                let value = |column: usize, row: usize| {
+                    Value::known(Fr::from(((column + row) % 256) as u64))
+                };
+                for row in 0..self.rows {
+                    region.assign_fixed(|| "table", config.table, row, || value(0, row))?;
+                    for (column, input) in config.inputs.iter().enumerate() {
+                        region.assign_advice(|| "input", *input, row, || value(column, row))?;
+                    }
+                    for (column, shuffled) in config.shuffled.iter().enumerate() {
+                        region.assign_advice(
+                            || "shuffled",
+                            *shuffled,
+                            row,
+                            || value(column, self.rows - 1 - row),
+                        )?;
+                    }
+                }
+                Ok(())
+            },
+        )
+    }
+}
+
+fn prove(
+    params: &ParamsKZG<Bn256>,
+    pk: &ProvingKey<G1Affine>,
+    circuit: &LookupsCircuit,
+    num_threads: Option<usize>,
+) {
+    let mut transcript = Blake2bWrite::<_, _, Challenge255<_>>::init(vec![]);
+    create_proof_with_options::<KZGCommitmentScheme<Bn256>, ProverSHPLONK<_>, _, _, _, _>(
+        params,
+        pk,
+        &[circuit.clone()],
+        &[&[]],
+        OsRng,
+        &mut transcript,
+        &ProverOptions {
+            num_threads,
+            ..Default::default()
+        },
+    )
+    .expect("proof generation should not fail");
+    transcript.finalize();
+}
+
+/// Compares the latency of a proof of a circuit with 14 lookups and 4 shuffles on one
+/// thread and on all of them.
+fn criterion_benchmark(c: &mut Criterion) {
+    let mut group = c.benchmark_group("many_lookups");
+    group.sample_size(10);
+    for k in [10, 12] {
+        let params = ParamsKZG::<Bn256>::new(k);
+        let circuit = LookupsCircuit {
+            rows: (1 << k) - 10,
+        };
+        let vk = keygen_vk(&params, &circuit).expect("keygen_vk should not fail");
+        let pk = keygen_pk(&params, vk, &circuit).expect("keygen_pk should not fail");
+
+        group.bench_with_input(BenchmarkId::new("serial", k), &k, |b, _| {
+            b.iter(|| prove(&params, &pk, &circuit, Some(1)))
+        });
+        group.bench_with_input(BenchmarkId::new("parallel", k), &k, |b, _| {
+            b.iter(|| prove(&params, &pk, &circuit, None))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
@@ -0,0 +1,142 @@
+#[macro_use]
+extern crate criterion;
+
+use halo2_proofs::circuit::{Layouter, SimpleFloorPlanner, Value};
+use halo2_proofs::plonk::*;
+use halo2_proofs::poly::commitment::ParamsProver;
+use halo2_proofs::poly::kzg::commitment::{KZGCommitmentScheme, ParamsKZG};
+use halo2_proofs::poly::kzg::multiopen::ProverSHPLONK;
+use halo2_proofs::poly::Rotation;
+use halo2_proofs::transcript::{Blake2bWrite, Challenge255, TranscriptWriterBuffer};
+use halo2curves::bn256::{Bn256, Fr, G1Affine};
+use rand_core::OsRng;
+
+use criterion::{BatchSize, BenchmarkId, Criterion};
+
+#[derive(Clone)]
+struct RangeConfig {
+    a: Column<Advice>,
+    b: Column<Advice>,
+    table: Column<Fixed>,
+}
+
+/// Checks that `rows` cells are bytes, and copies each of them to another column.
+#[derive(Clone, Default)]
+struct RangeCircuit {
+    rows: usize,
+}
+
+impl Circuit<Fr> for RangeCircuit {
+    type Config = RangeConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+    #[cfg(feature = "circuit-params")]
+    type Params = ();
+
+    fn without_witnesses(&self) -> Self {
+        self.clone()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fr>) -> RangeConfig {
+        let a = meta.advice_column();
+        let b = meta.advice_column();
+        let table = meta.fixed_column();
+        meta.enable_equality(a);
+        meta.enable_equality(b);
+
+        meta.lookup_any("byte", |meta| {
+            let a = meta.query_advice(a, Rotation::cur());
+            let table = meta.query_fixed(table, Rotation::cur());
+            vec![(a, table)]
+        });
+
+        RangeConfig { a, b, table }
+    }
+
+    fn synthesize(
+        &self,
+        config: RangeConfig,
+        mut layouter: impl Layouter<Fr>,
+    ) -> Result<(), Error> {
+        layouter.assign_region(
+            || "bytes",
+            |mut region| {
+                for row in 0..self.rows {
+                    let value = Value::known(Fr::from((row % 256) as u64));
+                    region.assign_fixed(|| "table", config.table, row, || value)?;
+                    let a = region.assign_advice(|| "a", config.a, row, || value)?;
+                    let b = region.assign_advice(|| "b", config.b, row, || value)?;
+                    region.constrain_equal(a.cell(), b.cell())?;
+                }
+                Ok(())
+            },
+        )
+    }
+}
+
+const INSTANCES: usize = 4;
+
+fn prove(
+    params: &ParamsKZG<Bn256>,
+    pk: &ProvingKey<G1Affine>,
+    inputs: Vec<ProverInput<'_, Fr, RangeCircuit>>,
+) {
+    let instances = vec![&[][..]; INSTANCES];
+    let mut transcript = Blake2bWrite::<_, _, Challenge255<_>>::init(vec![]);
+    create_proof_with_inputs::<KZGCommitmentScheme<Bn256>, ProverSHPLONK<_>, _, _, _, _>(
+        params,
+        pk,
+        inputs,
+        &instances,
+        OsRng,
+        &mut transcript,
+    )
+    .expect("proof generation should not fail");
+    transcript.finalize();
+}
+
+/// Compares the latency of a proof of 4 instances of a circuit, with their witnesses
+/// synthesized in parallel during proof creation, and generated ahead of it.
+fn criterion_benchmark(c: &mut Criterion) {
+    let mut group = c.benchmark_group("multi_instance");
+    group.sample_size(10);
+    for k in [10, 12] {
+        let params = ParamsKZG::<Bn256>::new(k);
+        let circuit = RangeCircuit {
+            rows: (1 << k) - 10,
+        };
+        let vk = keygen_vk(&params, &circuit).expect("keygen_vk should not fail");
+        let pk = keygen_pk(&params, vk, &circuit).expect("keygen_pk should not fail");
+
+        group.bench_with_input(BenchmarkId::new("synthesized", k), &k, |b, _| {
+            b.iter(|| {
+                prove(
+                    &params,
+                    &pk,
+                    (0..INSTANCES)
+                        .map(|_| ProverInput::Circuit(&circuit))
+                        .collect(),
+                )
+            })
+        });
+
+        let witness =
+            generate_witness(k, &circuit, &[]).expect("witness generation should not fail");
+        group.bench_with_input(BenchmarkId::new("witnesses", k), &k, |b, _| {
+            b.iter_batched(
+                || vec![witness.clone(); INSTANCES],
+                |witnesses| {
+                    prove(
+                        &params,
+                        &pk,
+                        witnesses.into_iter().map(ProverInput::Witness).collect(),
+                    )
+                },
+                BatchSize::LargeInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
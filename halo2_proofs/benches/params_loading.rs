@@ -0,0 +1,63 @@
+#[macro_use]
+extern crate criterion;
+
+use halo2_proofs::poly::commitment::ParamsProver;
+use halo2_proofs::poly::kzg::commitment::ParamsKZG;
+use halo2_proofs::SerdeFormat;
+use halo2curves::bn256::Bn256;
+
+use criterion::{BenchmarkId, Criterion};
+
+/// Returns the serialization of dummy params for `2^k` rows in `format`, whose bases are
+/// all the same point, to avoid the cost of a setup.
+fn dummy_params(k: u32, format: SerdeFormat) -> Vec<u8> {
+    let write = |k| {
+        let mut bytes = vec![];
+        ParamsKZG::<Bn256>::new(k)
+            .write_custom(&mut bytes, format)
+            .expect("writing to a vector should not fail");
+        bytes
+    };
+    // Params for 2 more rows hold 2 more bases in both `g` and `g_lagrange`.
+    let small = write(1);
+    let point_len = (write(2).len() - small.len()) / 4;
+    let point = &small[4..4 + point_len];
+    let g2 = &small[4 + 4 * point_len..];
+
+    let mut bytes = k.to_le_bytes().to_vec();
+    for _ in 0..2 << k {
+        bytes.extend_from_slice(point);
+    }
+    bytes.extend_from_slice(g2);
+    bytes
+}
+
+/// Compares the time to load params through `io::Read` and from a slice of bytes, such as
+/// a memory-mapped file.
+fn criterion_benchmark(c: &mut Criterion) {
+    let mut group = c.benchmark_group("params_loading");
+    group.sample_size(10);
+    let k = 18;
+    for (name, format) in [
+        ("processed", SerdeFormat::Processed),
+        ("raw", SerdeFormat::RawBytes),
+    ] {
+        let bytes = dummy_params(k, format);
+
+        group.bench_with_input(BenchmarkId::new("read", name), &bytes, |b, bytes| {
+            b.iter(|| {
+                ParamsKZG::<Bn256>::read_custom(&mut &bytes[..], format)
+                    .expect("params should be read")
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("from_bytes", name), &bytes, |b, bytes| {
+            b.iter(|| {
+                ParamsKZG::<Bn256>::from_bytes_custom(bytes, format).expect("params should be read")
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
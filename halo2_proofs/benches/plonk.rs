@@ -194,7 +194,7 @@ fn criterion_benchmark(c: &mut Criterion) {
         }
 
         fn configure(meta: &mut ConstraintSystem<F>) -> PlonkConfig {
-            meta.set_minimum_degree(5);
+            meta.set_minimum_degree(5).unwrap();
 
             let a = meta.advice_column();
             let b = meta.advice_column();
@@ -0,0 +1,116 @@
+#[macro_use]
+extern crate criterion;
+
+use halo2_proofs::circuit::{Layouter, SimpleFloorPlanner, Value};
+use halo2_proofs::plonk::*;
+use halo2_proofs::poly::commitment::ParamsProver;
+use halo2_proofs::poly::kzg::commitment::{KZGCommitmentScheme, ParamsKZG};
+use halo2_proofs::poly::kzg::multiopen::ProverSHPLONK;
+use halo2_proofs::poly::Rotation;
+use halo2_proofs::transcript::{Blake2bWrite, Challenge255, TranscriptWriterBuffer};
+use halo2curves::bn256::{Bn256, Fr, G1Affine};
+use rand_core::OsRng;
+
+use criterion::{BenchmarkId, Criterion};
+
+#[derive(Clone)]
+struct RangeConfig {
+    a: Column<Advice>,
+    b: Column<Advice>,
+    table: Column<Fixed>,
+}
+
+/// Checks that `rows` cells are bytes, and copies each of them to another column.
+#[derive(Clone, Default)]
+struct RangeCircuit {
+    rows: usize,
+}
+
+impl Circuit<Fr> for RangeCircuit {
+    type Config = RangeConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+    #[cfg(feature = "circuit-params")]
+    type Params = ();
+
+    fn without_witnesses(&self) -> Self {
+        self.clone()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fr>) -> RangeConfig {
+        let a = meta.advice_column();
+        let b = meta.advice_column();
+        let table = meta.fixed_column();
+        meta.enable_equality(a);
+        meta.enable_equality(b);
+
+        meta.lookup_any("byte", |meta| {
+            let a = meta.query_advice(a, Rotation::cur());
+            let table = meta.query_fixed(table, Rotation::cur());
+            vec![(a, table)]
+        });
+
+        RangeConfig { a, b, table }
+    }
+
+    fn synthesize(
+        &self,
+        config: RangeConfig,
+        mut layouter: impl Layouter<Fr>,
+    ) -> Result<(), Error> {
+        layouter.assign_region(
+            || "bytes",
+            |mut region| {
+                for row in 0..self.rows {
+                    let value = Value::known(Fr::from((row % 256) as u64));
+                    region.assign_fixed(|| "table", config.table, row, || value)?;
+                    let a = region.assign_advice(|| "a", config.a, row, || value)?;
+                    let b = region.assign_advice(|| "b", config.b, row, || value)?;
+                    region.constrain_equal(a.cell(), b.cell())?;
+                }
+                Ok(())
+            },
+        )
+    }
+}
+
+fn prove(params: &ParamsKZG<Bn256>, pk: &ProvingKey<G1Affine>, circuit: &RangeCircuit) {
+    let mut transcript = Blake2bWrite::<_, _, Challenge255<_>>::init(vec![]);
+    create_proof::<KZGCommitmentScheme<Bn256>, ProverSHPLONK<_>, _, _, _, _>(
+        params,
+        pk,
+        &[circuit.clone()],
+        &[&[]],
+        OsRng,
+        &mut transcript,
+    )
+    .expect("proof generation should not fail");
+    transcript.finalize();
+}
+
+/// Compares the latency of the proofs created after the first one, with and without the
+/// [`ProverCache`].
+fn criterion_benchmark(c: &mut Criterion) {
+    let mut group = c.benchmark_group("prover_cache");
+    group.sample_size(10);
+    for k in [10, 12] {
+        let params = ParamsKZG::<Bn256>::new(k);
+        let circuit = RangeCircuit {
+            rows: (1 << k) - 10,
+        };
+        let vk = keygen_vk(&params, &circuit).expect("keygen_vk should not fail");
+        let mut pk = keygen_pk(&params, vk, &circuit).expect("keygen_pk should not fail");
+
+        group.bench_with_input(BenchmarkId::new("cold", k), &k, |b, _| {
+            b.iter(|| prove(&params, &pk, &circuit))
+        });
+
+        pk.warm_cache();
+        group.bench_with_input(BenchmarkId::new("warm", k), &k, |b, _| {
+            b.iter(|| prove(&params, &pk, &circuit))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
@@ -0,0 +1,107 @@
+#[macro_use]
+extern crate criterion;
+
+use ff::{Field, PrimeField};
+use halo2_proofs::circuit::{Layouter, SimpleFloorPlanner, Value};
+use halo2_proofs::dev::MockProver;
+use halo2_proofs::plonk::*;
+use halo2_proofs::poly::Rotation;
+use halo2curves::pasta::pallas;
+
+use std::marker::PhantomData;
+
+use criterion::{BenchmarkId, Criterion};
+
+const K: u32 = 17;
+const ROWS: usize = 100_000;
+
+fn criterion_benchmark(c: &mut Criterion) {
+    #[derive(Clone, Default)]
+    struct MyCircuit<F: Field> {
+        use_range: bool,
+        _marker: PhantomData<F>,
+    }
+
+    #[derive(Clone)]
+    struct MyConfig {
+        selector: Selector,
+        advice: Column<Advice>,
+    }
+
+    impl<F: PrimeField> Circuit<F> for MyCircuit<F> {
+        type Config = MyConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+        #[cfg(feature = "circuit-params")]
+        type Params = ();
+
+        fn without_witnesses(&self) -> Self {
+            self.clone()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> MyConfig {
+            let config = MyConfig {
+                selector: meta.selector(),
+                advice: meta.advice_column(),
+            };
+
+            meta.create_gate("zero", |meta| {
+                let selector = meta.query_selector(config.selector);
+                let advice = meta.query_advice(config.advice, Rotation::cur());
+                vec![selector * advice]
+            });
+
+            config
+        }
+
+        fn synthesize(
+            &self,
+            config: MyConfig,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            layouter.assign_region(
+                || "enable selectors",
+                |mut region| {
+                    if self.use_range {
+                        config.selector.enable_range(&mut region, 0..ROWS)?;
+                    } else {
+                        for offset in 0..ROWS {
+                            config.selector.enable(&mut region, offset)?;
+                        }
+                    }
+                    region.assign_advice(
+                        || "last row",
+                        config.advice,
+                        ROWS - 1,
+                        || Value::known(F::ZERO),
+                    )
+                },
+            )?;
+
+            Ok(())
+        }
+    }
+
+    fn prover(use_range: bool) {
+        let circuit = MyCircuit::<pallas::Base> {
+            use_range,
+            _marker: PhantomData,
+        };
+        MockProver::run(K, &circuit, vec![]).unwrap();
+    }
+
+    let mut group = c.benchmark_group("selector-range");
+    group.sample_size(10);
+    for (name, use_range) in [("loop", false), ("range", true)] {
+        group.bench_with_input(
+            BenchmarkId::from_parameter(name),
+            &use_range,
+            |b, &use_range| {
+                b.iter(|| prover(use_range));
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
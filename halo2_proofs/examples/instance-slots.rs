@@ -0,0 +1,150 @@
+//! Gadgets exposing public inputs without knowing their absolute instance rows.
+//!
+//! Each gadget claims the next free rows of the instance column with
+//! `Layouter::constrain_instance_next`. Rows are handed out in synthesis order, so the
+//! verifier-side instance vector lists the exposed values in the order in which the
+//! gadgets are called by the circuit.
+
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    arithmetic::Field,
+    circuit::{floor_planner::V1, Layouter, SimpleFloorPlanner, Value},
+    plonk::{Advice, Circuit, Column, ConstraintSystem, Error, FloorPlanner, Instance, Selector},
+    poly::Rotation,
+};
+
+/// A gadget that doubles its input, and exposes both the input and the output as
+/// public inputs.
+#[derive(Clone, Debug)]
+struct DoubleConfig {
+    advice: Column<Advice>,
+    instance: Column<Instance>,
+    s_double: Selector,
+}
+
+impl DoubleConfig {
+    fn configure<F: Field>(
+        meta: &mut ConstraintSystem<F>,
+        advice: Column<Advice>,
+        instance: Column<Instance>,
+    ) -> Self {
+        meta.enable_equality(advice);
+        meta.enable_equality(instance);
+        let s_double = meta.selector();
+
+        // | advice | s_double |
+        // |--------|----------|
+        // | input  | s_double |
+        // | output |          |
+        meta.create_gate("double", |meta| {
+            let input = meta.query_advice(advice, Rotation::cur());
+            let output = meta.query_advice(advice, Rotation::next());
+            let s_double = meta.query_selector(s_double);
+
+            vec![s_double * (input.clone() + input - output)]
+        });
+
+        DoubleConfig {
+            advice,
+            instance,
+            s_double,
+        }
+    }
+
+    /// Doubles `input`, and exposes the input and the output, in that order, on the
+    /// next two free rows of the instance column.
+    fn double_and_expose<F: Field>(
+        &self,
+        mut layouter: impl Layouter<F>,
+        input: Value<F>,
+    ) -> Result<(), Error> {
+        let (input, output) = layouter.assign_region(
+            || "double",
+            |mut region| {
+                self.s_double.enable(&mut region, 0)?;
+                let input = region.assign_advice(|| "input", self.advice, 0, || input)?;
+                let output = region.assign_advice(
+                    || "output",
+                    self.advice,
+                    1,
+                    || input.value().map(|input| input.double()),
+                )?;
+                Ok((input, output))
+            },
+        )?;
+
+        layouter.constrain_instance_next(input.cell(), self.instance)?;
+        layouter.constrain_instance_next(output.cell(), self.instance)?;
+        Ok(())
+    }
+}
+
+struct MyCircuit<F: Field, P: FloorPlanner> {
+    a: Value<F>,
+    b: Value<F>,
+    _marker: PhantomData<P>,
+}
+
+impl<F: Field, P: FloorPlanner> Circuit<F> for MyCircuit<F, P> {
+    type Config = DoubleConfig;
+    type FloorPlanner = P;
+    #[cfg(feature = "circuit-params")]
+    type Params = ();
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            a: Value::unknown(),
+            b: Value::unknown(),
+            _marker: PhantomData,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let advice = meta.advice_column();
+        let instance = meta.instance_column();
+        DoubleConfig::configure(meta, advice, instance)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        // The first gadget claims instance rows 0 and 1, the second one rows 2 and 3.
+        config.double_and_expose(layouter.namespace(|| "double a"), self.a)?;
+        config.double_and_expose(layouter.namespace(|| "double b"), self.b)
+    }
+}
+
+fn check<P: FloorPlanner>() {
+    use halo2_proofs::dev::MockProver;
+    use halo2curves::pasta::Fp;
+
+    let k = 4;
+    let a = Fp::from(3);
+    let b = Fp::from(5);
+    let circuit = MyCircuit::<Fp, P> {
+        a: Value::known(a),
+        b: Value::known(b),
+        _marker: PhantomData,
+    };
+
+    // The instance vector lists the values exposed by each gadget, in the order in
+    // which the gadgets are called: `[a, 2a, b, 2b]`.
+    let public_inputs = vec![a, a.double(), b, b.double()];
+    let prover = MockProver::run(k, &circuit, vec![public_inputs]).unwrap();
+    assert_eq!(prover.verify(), Ok(()));
+
+    // Swapping the outputs of the two gadgets makes the proof fail.
+    let public_inputs = vec![b, b.double(), a, a.double()];
+    let prover = MockProver::run(k, &circuit, vec![public_inputs]).unwrap();
+    assert!(prover.verify().is_err());
+}
+
+fn main() {
+    // Both floor planners hand out the same instance rows. `V1` synthesizes the circuit
+    // twice, and each pass starts from the first row of the instance column.
+    check::<SimpleFloorPlanner>();
+    check::<V1>();
+}
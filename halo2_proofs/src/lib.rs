@@ -16,6 +16,7 @@ pub mod plonk;
 /// Traits and structs for implementing circuit components.
 pub mod circuit {
     pub use halo2_common::circuit::floor_planner;
+    pub use halo2_common::circuit::layouter;
     pub use halo2_common::circuit::{
         AssignedCell, Cell, Chip, Layouter, Region, SimpleFloorPlanner, Value,
     };
@@ -29,13 +30,30 @@ pub mod arithmetic {
 }
 /// Tools for developing circuits.
 pub mod dev {
-    pub use halo2_frontend::dev::{metadata, FailureLocation, MockProver, VerifyFailure};
+    pub use halo2_backend::dev::{
+        extract_advice_commitments, proof_structure, ProofSection, ProofStructure,
+    };
+    pub use halo2_common::dev::{
+        assert_circuit_unchanged, pinned_circuit, pinned_diff, PinnedDiff, PinnedPolynomial,
+    };
+    pub use halo2_frontend::dev::{
+        attribute_costs, lint, lint_with, metadata, render_layout, CellDiff, CircuitMeasure,
+        ColumnDiff, ColumnExtent, ColumnShare, CopyConstraint, DiffColumn, FailureLocation,
+        LayoutCell, LayoutColumn, LayoutJson, LintAllowlist, LintWarning, MeasureReport,
+        MockProver, RegionCost, RegionCostTable, RegionPlacement, RegionView,
+        UnconstrainedAllowlist, UnconstrainedCell, UnusedAllowlist, VerifyFailure, WitnessDiff,
+    };
+
+    pub use halo2_middleware::permutation::{CopyCycles, CycleStats};
 
     #[cfg(feature = "cost-estimator")]
     pub use halo2_frontend::dev::cost_model;
 
+    #[cfg(feature = "tracing")]
+    pub use halo2_frontend::dev::{LoggingHooks, TracingFloorPlanner};
+
     #[cfg(feature = "dev-graph")]
-    pub use halo2_frontend::dev::{circuit_dot_graph, CircuitLayout};
+    pub use halo2_frontend::dev::{circuit_dot_graph, circuit_dot_graph_detailed, CircuitLayout};
 }
 /// Contains utilities for performing arithmetic over univariate polynomials in
 /// various forms, including computing commitments to them and provably opening
@@ -43,14 +61,16 @@ pub mod dev {
 pub mod poly {
     pub use halo2_backend::poly::VerificationStrategy;
     pub use halo2_backend::poly::{commitment, ipa, kzg};
+    pub use halo2_backend::poly::{CpuEngine, PolynomialEngine};
     pub use halo2_middleware::poly::Rotation;
 }
 /// This module contains utilities and traits for dealing with Fiat-Shamir
 /// transcripts.
 pub mod transcript {
     pub use halo2_common::transcript::{
-        Blake2bRead, Blake2bWrite, Challenge255, EncodedChallenge, TranscriptReadBuffer,
-        TranscriptWriterBuffer,
+        base_to_limbs, Blake2bRead, Blake2bWrite, Challenge255, EncodedChallenge,
+        PoseidonChallenge, PoseidonRead, PoseidonSpec, PoseidonSponge, PoseidonWrite, StreamWriter,
+        TranscriptReadBuffer, TranscriptWriterBuffer,
     };
 }
 mod helpers {
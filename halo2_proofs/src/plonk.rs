@@ -7,19 +7,50 @@
 
 mod keygen;
 mod prover;
+pub mod proof {
+    //! Helpers to tie a serialized proof to the verifying key it was created for.
+    pub use halo2_backend::plonk::proof::{read_with_header, write_with_header};
+}
 mod verifier {
-    pub use halo2_backend::plonk::verifier::verify_proof;
+    pub use halo2_backend::plonk::verifier::{
+        verify_proof, verify_proof_with_instance_commitments, verify_proofs_batch,
+        verify_proofs_batch_gwc, verify_proofs_batch_shplonk, BatchVerifierGWC, BatchVerifierKZG,
+        BatchVerifierSHPLONK,
+    };
 }
 
-pub use keygen::{keygen_pk, keygen_vk};
+pub use keygen::{
+    keygen_pk, keygen_pk_v2, keygen_vk, keygen_vk_v2, keygen_vk_v2_with_options,
+    keygen_vk_with_options,
+};
 
-pub use prover::create_proof;
-pub use verifier::verify_proof;
+pub use prover::{
+    create_proof, create_proof_from_witness, create_proof_from_witness_phases,
+    create_proof_streamed, create_proof_with_engine, create_proof_with_inputs,
+    create_proof_with_mode, create_proof_with_options, ProverInput, ProverOptions, ProverState,
+    ProvingMode,
+};
 
-pub use halo2_backend::plonk::{ProvingKey, VerifyingKey};
+pub use halo2_frontend::circuit::{generate_witness, ChallengeValues, Witness};
+pub use verifier::{
+    verify_proof, verify_proof_with_instance_commitments, verify_proofs_batch,
+    verify_proofs_batch_gwc, verify_proofs_batch_shplonk, BatchVerifierGWC, BatchVerifierKZG,
+    BatchVerifierSHPLONK,
+};
+
+pub use halo2_backend::plonk::{
+    evaluate_constraints, ArgumentValues, CircuitOptions, LookupValues, PermutationAssembly,
+    ProverCache, ProverMemoryEstimate, ProvingKey, VerifyingKey, VkLayout, VK_LAYOUT_VERSION,
+};
 pub use halo2_common::plonk::{
     circuit::{Challenge, Column},
-    Assigned, Circuit, ConstraintSystem, Error, Expression, FirstPhase, SecondPhase, Selector,
-    TableColumn, ThirdPhase,
+    Assigned, AssignmentContext, AssignmentHooks, Circuit, CombinationReport, ConstraintSystem,
+    Constraints, ConstraintsBuilder, Error, Expression, ExpressionDisplay, FirstPhase,
+    FloorPlanner, PhaseId, PhaseMix, PreprocessedFixedColumn, SecondPhase, Selector,
+    SelectorCompressionReport, SelectorReport, TableColumn, ThirdPhase, VerificationError,
+    VerificationStage,
 };
 pub use halo2_middleware::circuit::{Advice, Fixed, Instance};
+
+#[cfg(feature = "serde")]
+pub use halo2_common::plonk::circuit::interchange;
@@ -1,7 +1,5 @@
-use halo2_backend::plonk::{
-    keygen::{keygen_pk_v2, keygen_vk_v2},
-    ProvingKey, VerifyingKey,
-};
+pub use halo2_backend::plonk::keygen::{keygen_pk_v2, keygen_vk_v2, keygen_vk_v2_with_options};
+use halo2_backend::plonk::{CircuitOptions, ProvingKey, VerifyingKey};
 use halo2_backend::{arithmetic::CurveAffine, poly::commitment::Params};
 use halo2_common::plonk::{circuit::Circuit, Error};
 use halo2_frontend::circuit::compile_circuit;
@@ -30,6 +28,30 @@ pub fn keygen_vk_custom<'params, C, P, ConcreteCircuit>(
     circuit: &ConcreteCircuit,
     compress_selectors: bool,
 ) -> Result<VerifyingKey<C>, Error>
+where
+    C: CurveAffine,
+    P: Params<'params, C>,
+    ConcreteCircuit: Circuit<C::Scalar>,
+    C::Scalar: FromUniformBytes<64>,
+{
+    keygen_vk_with_options(
+        params,
+        circuit,
+        compress_selectors,
+        &CircuitOptions::default(),
+    )
+}
+
+/// Generate a `VerifyingKey` from an instance of `Circuit`, with the given
+/// [`CircuitOptions`].
+///
+/// The selector compression optimization is turned on only if `compress_selectors` is `true`.
+pub fn keygen_vk_with_options<'params, C, P, ConcreteCircuit>(
+    params: &P,
+    circuit: &ConcreteCircuit,
+    compress_selectors: bool,
+    options: &CircuitOptions,
+) -> Result<VerifyingKey<C>, Error>
 where
     C: CurveAffine,
     P: Params<'params, C>,
@@ -37,7 +59,7 @@ where
     C::Scalar: FromUniformBytes<64>,
 {
     let (compiled_circuit, _, _) = compile_circuit(params.k(), circuit, compress_selectors)?;
-    let mut vk = keygen_vk_v2(params, &compiled_circuit)?;
+    let mut vk = keygen_vk_v2_with_options(params, &compiled_circuit, options)?;
     vk.compress_selectors = compress_selectors;
     Ok(vk)
 }
@@ -1,11 +1,29 @@
-use crate::poly::commitment::{CommitmentScheme, Params, Prover};
-use halo2_backend::plonk::{prover::ProverV2, ProvingKey};
+use crate::poly::commitment::{CommitmentScheme, Params, ParamsProver, Prover, Verifier};
+use halo2_backend::plonk::{prover::ProverV2, verifier::verify_proof, ProvingKey};
+use halo2_backend::poly::{CpuEngine, PolynomialEngine, VerificationStrategy};
+use halo2_common::multicore::{IntoParallelRefMutIterator, ParallelIterator, ThreadPoolBuilder};
 use halo2_common::plonk::{circuit::Circuit, Error};
-use halo2_common::transcript::{EncodedChallenge, TranscriptWrite};
-use halo2_frontend::circuit::{compile_circuit, WitnessCalculator};
-use halo2_middleware::ff::{FromUniformBytes, WithSmallOrderMulGroup};
-use rand_core::RngCore;
+use halo2_common::transcript::{
+    EncodedChallenge, StreamWriter, TranscriptReadBuffer, TranscriptWrite, TranscriptWriterBuffer,
+};
+use halo2_frontend::circuit::{compile_circuit, ChallengeValues, Witness, WitnessCalculator};
+use halo2_middleware::ff::{Field, FromUniformBytes, WithSmallOrderMulGroup};
+use rand_chacha::ChaCha20Rng;
+use rand_core::{RngCore, SeedableRng};
 use std::collections::HashMap;
+use std::io::{Cursor, Write};
+
+/// How [`create_proof_with_mode`] chooses the blinding factors of a proof.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProvingMode {
+    /// The blinding factors are sampled from a ChaCha20 RNG seeded with the given seed, so
+    /// that the same circuits, instances and seed always give byte-identical proofs.
+    Deterministic([u8; 32]),
+    /// Every blinding factor is one, so that proofs can be compared with other
+    /// implementations. These proofs are not zero-knowledge.
+    #[cfg(feature = "unit-blinds")]
+    UnitBlinds,
+}
 
 /// This creates a proof for the provided `circuit` when given the public
 /// parameters `params` and the proving key [`ProvingKey`] that was
@@ -30,25 +48,649 @@ pub fn create_proof<
 where
     Scheme::Scalar: WithSmallOrderMulGroup<3> + FromUniformBytes<64>,
 {
-    if circuits.len() != instances.len() {
+    prove_circuits::<_, P, _, _, _, _, _>(
+        params,
+        pk,
+        synthesized(circuits),
+        synthesize_sequentially,
+        instances,
+        rng,
+        transcript,
+        &CpuEngine,
+        false,
+        None,
+    )
+}
+
+/// This creates a proof like [`create_proof`], with every commitment and FFT done by
+/// `engine`, for example to offload them to a GPU.
+///
+/// The proof is the same as with [`create_proof`] if `engine` computes the same results as
+/// the [`CpuEngine`].
+pub fn create_proof_with_engine<
+    'params,
+    Scheme: CommitmentScheme,
+    P: Prover<'params, Scheme>,
+    E: EncodedChallenge<Scheme::Curve>,
+    R: RngCore,
+    T: TranscriptWrite<Scheme::Curve, E>,
+    ConcreteCircuit: Circuit<Scheme::Scalar>,
+>(
+    params: &'params Scheme::ParamsProver,
+    pk: &ProvingKey<Scheme::Curve>,
+    circuits: &[ConcreteCircuit],
+    instances: &[&[&[Scheme::Scalar]]],
+    rng: R,
+    transcript: &mut T,
+    engine: &dyn PolynomialEngine<Scheme::Curve>,
+) -> Result<(), Error>
+where
+    Scheme::Scalar: WithSmallOrderMulGroup<3> + FromUniformBytes<64>,
+{
+    prove_circuits::<_, P, _, _, _, _, _>(
+        params,
+        pk,
+        synthesized(circuits),
+        synthesize_sequentially,
+        instances,
+        rng,
+        transcript,
+        engine,
+        false,
+        None,
+    )
+}
+
+/// Options of [`create_proof_with_options`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ProverOptions {
+    /// The number of threads used to create the proof, or `None` to use the global thread
+    /// pool, like [`create_proof`].
+    pub num_threads: Option<usize>,
+    /// The most advice and instance columns held over the extended domain at once to
+    /// compute the quotient polynomial, or `None` to hold those of every circuit.
+    ///
+    /// The circuits are evaluated as many at a time as fit. Below the columns of a single
+    /// circuit, each circuit is evaluated over one of the `2^(extended_k - k)` parts of the
+    /// extended domain at a time, which holds a part of each column only, at the cost of one
+    /// FFT per column and part; the bound can't go lower than that. The proof doesn't depend
+    /// on it. See [`ProvingKey::estimated_prover_memory`] to choose it.
+    pub max_extended_columns_in_memory: Option<usize>,
+}
+
+/// This creates a proof like [`create_proof`], with the parallelism bounded by `options`.
+///
+/// With `num_threads`, the calling thread becomes one of the threads of a new thread pool
+/// of that size, in which the whole proving call runs: the synthesis of the witnesses, the
+/// FFTs, the MSMs and the evaluation of the quotient polynomial. Since the proof is
+/// created on the calling thread, the circuits don't need to be `Sync`. Building the pool
+/// has a cost; to reuse a pool across proofs, call [`create_proof`] from within
+/// `pool.install`. The proof doesn't depend on the number of threads.
+///
+/// There is no `multicore` feature flag: halo2 always uses rayon, so `num_threads: Some(1)`
+/// is the way to prove on a single thread.
+///
+/// With `num_threads`, this returns an [`Error::Other`] if the calling thread already
+/// belongs to a rayon thread pool, for example within `pool.install`.
+#[allow(clippy::too_many_arguments)]
+pub fn create_proof_with_options<
+    'params,
+    Scheme: CommitmentScheme,
+    P: Prover<'params, Scheme>,
+    E: EncodedChallenge<Scheme::Curve>,
+    R: RngCore,
+    T: TranscriptWrite<Scheme::Curve, E>,
+    ConcreteCircuit: Circuit<Scheme::Scalar>,
+>(
+    params: &'params Scheme::ParamsProver,
+    pk: &ProvingKey<Scheme::Curve>,
+    circuits: &[ConcreteCircuit],
+    instances: &[&[&[Scheme::Scalar]]],
+    rng: R,
+    transcript: &mut T,
+    options: &ProverOptions,
+) -> Result<(), Error>
+where
+    Scheme::Scalar: WithSmallOrderMulGroup<3> + FromUniformBytes<64>,
+{
+    // The pool only lasts for this call, after which the calling thread leaves it.
+    let _pool = options
+        .num_threads
+        .map(|num_threads| {
+            ThreadPoolBuilder::new()
+                .num_threads(num_threads)
+                .use_current_thread()
+                .build()
+                .map_err(|e| Error::Other(format!("cannot build a thread pool: {e}")))
+        })
+        .transpose()?;
+    prove_circuits::<_, P, _, _, _, _, _>(
+        params,
+        pk,
+        synthesized(circuits),
+        synthesize_sequentially,
+        instances,
+        rng,
+        transcript,
+        &CpuEngine,
+        false,
+        options.max_extended_columns_in_memory,
+    )
+}
+
+/// This creates a proof like [`create_proof`], with the blinding factors chosen according
+/// to `mode` instead of sampled from a caller's RNG, and returns the proof bytes.
+///
+/// The proof is verified once with `V` and a `Strategy` which checks it on its own, such
+/// as a `SingleStrategy`, read back with the transcript `TR` matching `T`. If it doesn't
+/// verify, the verification error is returned instead of the proof.
+#[allow(clippy::type_complexity)]
+pub fn create_proof_with_mode<
+    'params,
+    Scheme: CommitmentScheme,
+    P: Prover<'params, Scheme>,
+    V: Verifier<'params, Scheme>,
+    E: EncodedChallenge<Scheme::Curve>,
+    T: TranscriptWriterBuffer<Vec<u8>, Scheme::Curve, E>,
+    TR: TranscriptReadBuffer<Cursor<Vec<u8>>, Scheme::Curve, E>,
+    Strategy: VerificationStrategy<'params, Scheme, V, Output = ()>,
+    ConcreteCircuit: Circuit<Scheme::Scalar>,
+>(
+    params: &'params Scheme::ParamsProver,
+    pk: &ProvingKey<Scheme::Curve>,
+    circuits: &[ConcreteCircuit],
+    instances: &[&[&[Scheme::Scalar]]],
+    mode: ProvingMode,
+) -> Result<Vec<u8>, Error>
+where
+    Scheme::Scalar: WithSmallOrderMulGroup<3> + FromUniformBytes<64>,
+{
+    let (seed, unit_blinds) = match mode {
+        ProvingMode::Deterministic(seed) => (seed, false),
+        #[cfg(feature = "unit-blinds")]
+        ProvingMode::UnitBlinds => ([0; 32], true),
+    };
+
+    let mut transcript = T::init(vec![]);
+    prove_circuits::<_, P, _, _, _, _, _>(
+        params,
+        pk,
+        synthesized(circuits),
+        synthesize_sequentially,
+        instances,
+        ChaCha20Rng::from_seed(seed),
+        &mut transcript,
+        &CpuEngine,
+        unit_blinds,
+        None,
+    )?;
+    let proof = transcript.finalize();
+
+    let verifier_params = params.verifier_params();
+    let mut transcript = TR::init(Cursor::new(proof.clone()));
+    verify_proof::<_, V, _, _, _>(
+        verifier_params,
+        pk.get_vk(),
+        Strategy::new(verifier_params),
+        instances,
+        &mut transcript,
+    )?;
+    Ok(proof)
+}
+
+/// This creates a proof like [`create_proof`], streaming it to `writer` instead of
+/// buffering it in memory, and returns the number of bytes written.
+///
+/// `writer` is flushed after every point and scalar of the proof. If writing fails, the
+/// error is returned as [`Error::Transcript`], and `writer` holds a truncated proof.
+pub fn create_proof_streamed<
+    'params,
+    Scheme: CommitmentScheme,
+    P: Prover<'params, Scheme>,
+    E: EncodedChallenge<Scheme::Curve>,
+    R: RngCore,
+    W: Write,
+    T: TranscriptWriterBuffer<StreamWriter<W>, Scheme::Curve, E>,
+    ConcreteCircuit: Circuit<Scheme::Scalar>,
+>(
+    params: &'params Scheme::ParamsProver,
+    pk: &ProvingKey<Scheme::Curve>,
+    circuits: &[ConcreteCircuit],
+    instances: &[&[&[Scheme::Scalar]]],
+    rng: R,
+    writer: W,
+) -> Result<usize, Error>
+where
+    Scheme::Scalar: WithSmallOrderMulGroup<3> + FromUniformBytes<64>,
+{
+    let mut transcript = T::init(StreamWriter::new(writer));
+    prove_circuits::<_, P, _, _, _, _, _>(
+        params,
+        pk,
+        synthesized(circuits),
+        synthesize_sequentially,
+        instances,
+        rng,
+        &mut transcript,
+        &CpuEngine,
+        false,
+        None,
+    )?;
+    Ok(transcript.finalize().bytes_written())
+}
+
+/// The source of the advice columns of one of the circuits proven by
+/// [`create_proof_with_inputs`].
+#[derive(Debug)]
+pub enum ProverInput<'a, F: Field, ConcreteCircuit: Circuit<F>> {
+    /// The circuit is synthesized during proof creation, like in [`create_proof`].
+    Circuit(&'a ConcreteCircuit),
+    /// The advice columns were generated ahead of proof creation with
+    /// [`generate_witness`](halo2_frontend::circuit::generate_witness), like in
+    /// [`create_proof_from_witness`].
+    Witness(Witness<F>),
+}
+
+/// This creates a proof for several instances of the same circuit, like [`create_proof`],
+/// with the advice columns of each one either synthesized from the circuit or supplied as
+/// a [`Witness`] generated ahead of proof creation.
+///
+/// The witnesses of the circuits are synthesized in parallel, which requires the circuit
+/// and its configuration to be `Sync`; the other functions creating proofs from circuits
+/// synthesize them one after the other. Supplying some of them lets a pipeline generate
+/// the witnesses of the next proof while this one is being created.
+///
+/// # Randomness
+///
+/// The proof is the same, for the same `rng`, whichever inputs are circuits or witnesses,
+/// and in whichever order the witnesses are synthesized: the witnesses don't consume `rng`.
+/// Only the backend prover does, in this order:
+/// - in each phase, for each instance in the order of `inputs`, the blinding rows of its
+///   blinded advice columns of that phase, column after column, and then one blind for
+///   the commitment to each of these columns;
+/// - after the last phase, the lookup, permutation and shuffle arguments, for each
+///   instance in the same order, and then the vanishing argument and the multi-open
+///   argument.
+pub fn create_proof_with_inputs<
+    'params,
+    Scheme: CommitmentScheme,
+    P: Prover<'params, Scheme>,
+    E: EncodedChallenge<Scheme::Curve>,
+    R: RngCore,
+    T: TranscriptWrite<Scheme::Curve, E>,
+    ConcreteCircuit: Circuit<Scheme::Scalar> + Sync,
+>(
+    params: &'params Scheme::ParamsProver,
+    pk: &ProvingKey<Scheme::Curve>,
+    inputs: Vec<ProverInput<'_, Scheme::Scalar, ConcreteCircuit>>,
+    instances: &[&[&[Scheme::Scalar]]],
+    rng: R,
+    transcript: &mut T,
+) -> Result<(), Error>
+where
+    Scheme::Scalar: WithSmallOrderMulGroup<3> + FromUniformBytes<64>,
+    ConcreteCircuit::Config: Sync,
+{
+    prove_circuits::<_, P, _, _, _, _, _>(
+        params,
+        pk,
+        inputs,
+        synthesize_in_parallel,
+        instances,
+        rng,
+        transcript,
+        &CpuEngine,
+        false,
+        None,
+    )
+}
+
+fn synthesized<F: Field, ConcreteCircuit: Circuit<F>>(
+    circuits: &[ConcreteCircuit],
+) -> Vec<ProverInput<'_, F, ConcreteCircuit>> {
+    circuits.iter().map(ProverInput::Circuit).collect()
+}
+
+/// Where the advice columns of each phase of a circuit come from during proof creation.
+enum AdviceSource<'a, F: Field, ConcreteCircuit: Circuit<F>> {
+    Synthesized(WitnessCalculator<'a, F, ConcreteCircuit>),
+    Supplied(std::vec::IntoIter<Vec<Option<Vec<F>>>>),
+}
+
+impl<'a, F: Field, ConcreteCircuit: Circuit<F>> AdviceSource<'a, F, ConcreteCircuit> {
+    fn advice(
+        &mut self,
+        phase: u8,
+        challenges: &HashMap<usize, F>,
+    ) -> Result<Vec<Option<Vec<F>>>, Error> {
+        match self {
+            AdviceSource::Synthesized(witness_calc) => witness_calc.calc(phase, challenges),
+            AdviceSource::Supplied(advice) => advice
+                .next()
+                .ok_or_else(|| Error::Other(format!("witness is missing phase {}", phase))),
+        }
+    }
+}
+
+/// Returns the advice columns of `phase` of each circuit, synthesized one circuit after
+/// the other.
+fn synthesize_sequentially<F: Field, ConcreteCircuit: Circuit<F>>(
+    sources: &mut [AdviceSource<'_, F, ConcreteCircuit>],
+    phase: u8,
+    challenges: &HashMap<usize, F>,
+) -> Result<Vec<Vec<Option<Vec<F>>>>, Error> {
+    sources
+        .iter_mut()
+        .map(|source| source.advice(phase, challenges))
+        .collect()
+}
+
+/// Returns the advice columns of `phase` of each circuit, synthesized in parallel.
+fn synthesize_in_parallel<F: Field, ConcreteCircuit: Circuit<F> + Sync>(
+    sources: &mut [AdviceSource<'_, F, ConcreteCircuit>],
+    phase: u8,
+    challenges: &HashMap<usize, F>,
+) -> Result<Vec<Vec<Option<Vec<F>>>>, Error>
+where
+    ConcreteCircuit::Config: Sync,
+{
+    sources
+        .par_iter_mut()
+        .map(|source| source.advice(phase, challenges))
+        .collect()
+}
+
+/// Creates a proof like [`create_proof_with_engine`], with the advice columns of each phase
+/// synthesized by `synthesize`, all the blinding factors set to one if `unit_blinds` is
+/// `true`, which requires the `unit-blinds` feature, and at most `max_extended_columns`
+/// columns held over the extended domain at once, see
+/// [`ProverOptions::max_extended_columns_in_memory`].
+#[allow(clippy::too_many_arguments)]
+fn prove_circuits<
+    'params,
+    Scheme: CommitmentScheme,
+    P: Prover<'params, Scheme>,
+    E: EncodedChallenge<Scheme::Curve>,
+    R: RngCore,
+    T: TranscriptWrite<Scheme::Curve, E>,
+    ConcreteCircuit: Circuit<Scheme::Scalar>,
+    S: Fn(
+        &mut [AdviceSource<'_, Scheme::Scalar, ConcreteCircuit>],
+        u8,
+        &HashMap<usize, Scheme::Scalar>,
+    ) -> Result<Vec<Vec<Option<Vec<Scheme::Scalar>>>>, Error>,
+>(
+    params: &'params Scheme::ParamsProver,
+    pk: &ProvingKey<Scheme::Curve>,
+    inputs: Vec<ProverInput<'_, Scheme::Scalar, ConcreteCircuit>>,
+    synthesize: S,
+    instances: &[&[&[Scheme::Scalar]]],
+    rng: R,
+    transcript: &mut T,
+    engine: &dyn PolynomialEngine<Scheme::Curve>,
+    unit_blinds: bool,
+    max_extended_columns: Option<usize>,
+) -> Result<(), Error>
+where
+    Scheme::Scalar: WithSmallOrderMulGroup<3> + FromUniformBytes<64>,
+{
+    if inputs.len() != instances.len() {
         return Err(Error::InvalidInstances);
     }
-    let (_, config, cs) =
-        compile_circuit(params.k(), &circuits[0], pk.get_vk().compress_selectors)?;
-    let mut witness_calcs: Vec<_> = circuits
+    // All the circuits share the configuration of the first one.
+    let compiled = inputs
         .iter()
-        .enumerate()
-        .map(|(i, circuit)| WitnessCalculator::new(params.k(), circuit, &config, &cs, instances[i]))
-        .collect();
-    let mut prover = ProverV2::<Scheme, P, _, _, _>::new(params, pk, instances, rng, transcript)?;
+        .find_map(|input| match input {
+            ProverInput::Circuit(circuit) => Some(*circuit),
+            ProverInput::Witness(_) => None,
+        })
+        .map(|circuit| compile_circuit(params.k(), circuit, pk.get_vk().compress_selectors))
+        .transpose()?;
+    let mut sources = inputs
+        .into_iter()
+        .zip(instances.iter())
+        .map(|(input, instances)| match input {
+            ProverInput::Circuit(circuit) => {
+                let (_, config, cs) = compiled.as_ref().expect("a circuit was compiled");
+                Ok(AdviceSource::Synthesized(WitnessCalculator::new(
+                    params.k(),
+                    circuit,
+                    config,
+                    cs,
+                    instances,
+                )))
+            }
+            ProverInput::Witness(witness) => {
+                if witness.k() != params.k() {
+                    return Err(Error::Other(
+                        "witness was generated for a different k".to_string(),
+                    ));
+                }
+                Ok(AdviceSource::Supplied(witness.into_advice().into_iter()))
+            }
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut prover = ProverV2::<Scheme, P, _, _, _>::new_with_engine(
+        params, pk, instances, rng, transcript, engine,
+    )?;
+    #[cfg(feature = "unit-blinds")]
+    if unit_blinds {
+        prover = prover.with_unit_blinds();
+    }
+    #[cfg(not(feature = "unit-blinds"))]
+    assert!(
+        !unit_blinds,
+        "unit blinds require the `unit-blinds` feature"
+    );
+    if let Some(max) = max_extended_columns {
+        prover = prover.with_max_extended_columns(max);
+    }
     let mut challenges = HashMap::new();
     let phases = prover.phases.clone();
     for phase in &phases {
-        let mut witnesses = Vec::with_capacity(circuits.len());
-        for witness_calc in witness_calcs.iter_mut() {
-            witnesses.push(witness_calc.calc(phase.0, &challenges)?);
+        let witnesses = synthesize(sources.as_mut_slice(), phase.0, &challenges)?;
+        challenges = prover.commit_phase(phase.0, witnesses)?;
+    }
+    prover.create_proof()
+}
+
+/// A proof in creation, which the caller drives phase by phase, so that the advice columns
+/// of a phase can be computed however and whenever the caller likes, after observing the
+/// challenges squeezed after the previous phases.
+///
+/// The proof is the same as with [`create_proof`] for the same advice columns and `rng`.
+/// Each phase's advice columns can be computed with
+/// [`WitnessCalculator::next_phase`](halo2_frontend::circuit::WitnessCalculator::next_phase).
+///
+/// ```ignore
+/// let mut state = ProverState::<Scheme, P, _, _, _>::new(&params, &pk, instances, rng, &mut transcript)?;
+/// let mut challenges = ChallengeValues::default();
+/// for phase in state.phases() {
+///     let advice = witness_calculators
+///         .iter_mut()
+///         .map(|calculator| calculator.next_phase(&challenges))
+///         .collect::<Result<_, _>>()?;
+///     challenges = state.commit_phase(phase, advice)?;
+/// }
+/// state.finalize()?;
+/// let proof = transcript.finalize();
+/// ```
+#[derive(Debug)]
+pub struct ProverState<
+    'a,
+    'params,
+    Scheme: CommitmentScheme,
+    P: Prover<'params, Scheme>,
+    E: EncodedChallenge<Scheme::Curve>,
+    R: RngCore,
+    T: TranscriptWrite<Scheme::Curve, E>,
+> {
+    prover: ProverV2<'a, 'params, Scheme, P, E, R, T>,
+    committed_phases: usize,
+}
+
+impl<
+        'a,
+        'params,
+        Scheme: CommitmentScheme,
+        P: Prover<'params, Scheme>,
+        E: EncodedChallenge<Scheme::Curve>,
+        R: RngCore,
+        T: TranscriptWrite<Scheme::Curve, E>,
+    > ProverState<'a, 'params, Scheme, P, E, R, T>
+where
+    Scheme::Scalar: WithSmallOrderMulGroup<3> + FromUniformBytes<64>,
+{
+    /// Starts a proof of one circuit per entry of `instances`, writing it to `transcript`.
+    pub fn new(
+        params: &'params Scheme::ParamsProver,
+        pk: &'a ProvingKey<Scheme::Curve>,
+        instances: &[&[&[Scheme::Scalar]]],
+        rng: R,
+        transcript: &'a mut T,
+    ) -> Result<Self, Error> {
+        Ok(ProverState {
+            prover: ProverV2::new(params, pk, instances, rng, transcript)?,
+            committed_phases: 0,
+        })
+    }
+
+    /// Returns the phases to commit, in order.
+    pub fn phases(&self) -> Vec<u8> {
+        self.prover.phases.iter().map(|phase| phase.0).collect()
+    }
+
+    /// Commits the advice columns of `phase`, one vector of columns per circuit, in which
+    /// the columns of the other phases are `None`, and returns the values of the
+    /// challenges squeezed so far.
+    ///
+    /// The phases must be committed in the order of [`ProverState::phases`].
+    pub fn commit_phase(
+        &mut self,
+        phase: u8,
+        advice: Vec<Vec<Option<Vec<Scheme::Scalar>>>>,
+    ) -> Result<ChallengeValues<Scheme::Scalar>, Error> {
+        let challenges = self.prover.commit_phase(phase, advice)?;
+        self.committed_phases += 1;
+        Ok(challenges.into())
+    }
+
+    /// Writes the rest of the proof to the transcript, once every phase is committed.
+    pub fn finalize(self) -> Result<(), Error> {
+        if let Some(phase) = self.prover.phases.get(self.committed_phases) {
+            return Err(Error::Other(format!("phase {} was not committed", phase.0)));
         }
-        challenges = prover.commit_phase(phase.0, witnesses).unwrap();
+        self.prover.create_proof()
+    }
+}
+
+/// This creates a proof from the [`Witness`]es that were generated previously with
+/// [`generate_witness`](halo2_frontend::circuit::generate_witness), possibly on another
+/// machine, without synthesizing the circuit. Otherwise, this behaves like
+/// [`create_proof`].
+///
+/// The witnesses must hold the advice columns of every phase; for circuits with several
+/// phases, use [`create_proof_from_witness_phases`].
+pub fn create_proof_from_witness<
+    'params,
+    Scheme: CommitmentScheme,
+    P: Prover<'params, Scheme>,
+    E: EncodedChallenge<Scheme::Curve>,
+    R: RngCore,
+    T: TranscriptWrite<Scheme::Curve, E>,
+>(
+    params: &'params Scheme::ParamsProver,
+    pk: &ProvingKey<Scheme::Curve>,
+    witnesses: Vec<Witness<Scheme::Scalar>>,
+    instances: &[&[&[Scheme::Scalar]]],
+    rng: R,
+    transcript: &mut T,
+) -> Result<(), Error>
+where
+    Scheme::Scalar: WithSmallOrderMulGroup<3> + FromUniformBytes<64>,
+{
+    create_proof_from_witness_phases::<_, P, _, _, _, _>(
+        params,
+        pk,
+        witnesses,
+        |phase, _| Err(Error::Other(format!("witness is missing phase {}", phase))),
+        instances,
+        rng,
+        transcript,
+    )
+}
+
+/// This creates a proof like [`create_proof_from_witness`], for circuits with advice
+/// columns in several phases.
+///
+/// The phases held by the `witnesses` are committed first. The advice columns of each
+/// following phase are then returned by `next_phase`, one vector of columns per circuit
+/// (in which the columns of the other phases are `None`), given the phase and the
+/// challenges squeezed after the previous phases. It can, for example, send the
+/// challenges to the machine that generated the witnesses, and return the result of
+/// [`WitnessCalculator::next_phase`] there.
+pub fn create_proof_from_witness_phases<
+    'params,
+    Scheme: CommitmentScheme,
+    P: Prover<'params, Scheme>,
+    E: EncodedChallenge<Scheme::Curve>,
+    R: RngCore,
+    T: TranscriptWrite<Scheme::Curve, E>,
+    W: FnMut(
+        u8,
+        &ChallengeValues<Scheme::Scalar>,
+    ) -> Result<Vec<Vec<Option<Vec<Scheme::Scalar>>>>, Error>,
+>(
+    params: &'params Scheme::ParamsProver,
+    pk: &ProvingKey<Scheme::Curve>,
+    witnesses: Vec<Witness<Scheme::Scalar>>,
+    mut next_phase: W,
+    instances: &[&[&[Scheme::Scalar]]],
+    rng: R,
+    transcript: &mut T,
+) -> Result<(), Error>
+where
+    Scheme::Scalar: WithSmallOrderMulGroup<3> + FromUniformBytes<64>,
+{
+    if witnesses.len() != instances.len() {
+        return Err(Error::InvalidInstances);
+    }
+    if witnesses.iter().any(|witness| witness.k() != params.k()) {
+        return Err(Error::Other(
+            "witness was generated for a different k".to_string(),
+        ));
+    }
+    let mut prover = ProverV2::<Scheme, P, _, _, _>::new(params, pk, instances, rng, transcript)?;
+    let phases = prover.phases.clone();
+    let mut witnesses: Vec<_> = witnesses
+        .into_iter()
+        .map(|witness| witness.into_advice().into_iter())
+        .collect();
+    let mut challenges = ChallengeValues::default();
+    for phase in &phases {
+        let advice = witnesses
+            .iter_mut()
+            .map(|witness| witness.next())
+            .collect::<Option<Vec<_>>>();
+        let advice = match advice {
+            Some(advice) => advice,
+            None if witnesses
+                .iter()
+                .all(|witness| witness.as_slice().is_empty()) =>
+            {
+                let advice = next_phase(phase.0, &challenges)?;
+                if advice.len() != instances.len() {
+                    return Err(Error::InvalidInstances);
+                }
+                advice
+            }
+            None => {
+                return Err(Error::Other(
+                    "witnesses hold different numbers of phases".to_string(),
+                ))
+            }
+        };
+        challenges = prover.commit_phase(phase.0, advice)?.into();
     }
     prover.create_proof()
 }
@@ -119,3 +761,272 @@ fn test_create_proof() {
     )
     .expect("proof generation should not fail");
 }
+
+#[test]
+fn test_create_proof_from_witness() {
+    use crate::{
+        circuit::{Layouter, SimpleFloorPlanner, Value},
+        plonk::{
+            generate_witness, keygen_pk, keygen_vk, verify_proof, Advice, Column, ConstraintSystem,
+            Instance, Selector,
+        },
+        poly::{
+            commitment::ParamsProver,
+            kzg::{
+                commitment::{KZGCommitmentScheme, ParamsKZG},
+                multiopen::{ProverSHPLONK, VerifierSHPLONK},
+                strategy::SingleStrategy,
+            },
+            Rotation,
+        },
+        transcript::{
+            Blake2bRead, Blake2bWrite, Challenge255, TranscriptReadBuffer, TranscriptWriterBuffer,
+        },
+        SerdeFormat,
+    };
+    use halo2curves::bn256::{Bn256, Fr, G1Affine};
+    use rand_core::OsRng;
+
+    #[derive(Clone, Copy)]
+    struct MyCircuit(Value<Fr>);
+
+    impl Circuit<Fr> for MyCircuit {
+        type Config = (Column<Advice>, Column<Instance>, Selector);
+        type FloorPlanner = SimpleFloorPlanner;
+        #[cfg(feature = "circuit-params")]
+        type Params = ();
+
+        fn without_witnesses(&self) -> Self {
+            MyCircuit(Value::unknown())
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+            let advice = meta.advice_column();
+            let instance = meta.instance_column();
+            meta.enable_equality(advice);
+            meta.enable_equality(instance);
+            let s = meta.selector();
+            // | advice | s |
+            // |--------|---|
+            // | x      | 1 |
+            // | x^2    |   |
+            meta.create_gate("square", |meta| {
+                let x = meta.query_advice(advice, Rotation::cur());
+                let y = meta.query_advice(advice, Rotation::next());
+                let s = meta.query_selector(s);
+                vec![s * (x.clone() * x - y)]
+            });
+            (advice, instance, s)
+        }
+
+        fn synthesize(
+            &self,
+            (advice, instance, s): Self::Config,
+            mut layouter: impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let y = layouter.assign_region(
+                || "square",
+                |mut region| {
+                    s.enable(&mut region, 0)?;
+                    region.assign_advice(|| "x", advice, 0, || self.0)?;
+                    region.assign_advice(|| "x^2", advice, 1, || self.0.map(|x| x.square()))
+                },
+            )?;
+            layouter.constrain_instance(y.cell(), instance, 0)
+        }
+    }
+
+    let k = 4;
+    let params: ParamsKZG<Bn256> = ParamsKZG::setup(k, OsRng);
+    let empty_circuit = MyCircuit(Value::unknown());
+    let vk = keygen_vk(&params, &empty_circuit).expect("keygen_vk should not fail");
+    let pk = keygen_pk(&params, vk, &empty_circuit).expect("keygen_pk should not fail");
+
+    // Generate the witness, and move it to the prover as bytes.
+    let circuit = MyCircuit(Value::known(Fr::from(3)));
+    let instance = [Fr::from(9)];
+    let witness = generate_witness(k, &circuit, &[&instance]).expect("witness generation");
+    let bytes = witness.to_bytes(SerdeFormat::RawBytes);
+    let witness = Witness::from_bytes(&bytes, SerdeFormat::RawBytes).unwrap();
+
+    // A witness generated for another `k` is rejected.
+    let other_witness = generate_witness(k + 1, &circuit, &[&instance]).unwrap();
+    let mut transcript = Blake2bWrite::<_, G1Affine, Challenge255<_>>::init(vec![]);
+    let proof = create_proof_from_witness::<KZGCommitmentScheme<_>, ProverSHPLONK<_>, _, _, _>(
+        &params,
+        &pk,
+        vec![other_witness],
+        &[&[&instance]],
+        OsRng,
+        &mut transcript,
+    );
+    assert!(matches!(proof.unwrap_err(), Error::Other(_)));
+
+    let mut transcript = Blake2bWrite::<_, G1Affine, Challenge255<_>>::init(vec![]);
+    create_proof_from_witness::<KZGCommitmentScheme<_>, ProverSHPLONK<_>, _, _, _>(
+        &params,
+        &pk,
+        vec![witness],
+        &[&[&instance]],
+        OsRng,
+        &mut transcript,
+    )
+    .expect("proof generation should not fail");
+    let proof = transcript.finalize();
+
+    let mut transcript = Blake2bRead::<_, G1Affine, Challenge255<_>>::init(proof.as_slice());
+    let strategy = SingleStrategy::new(params.verifier_params());
+    verify_proof::<KZGCommitmentScheme<Bn256>, VerifierSHPLONK<'_, Bn256>, _, _, _>(
+        &params,
+        pk.get_vk(),
+        strategy,
+        &[&[&instance]],
+        &mut transcript,
+    )
+    .expect("proof verification should not fail");
+}
+
+#[test]
+fn test_create_proof_from_witness_phases() {
+    use crate::{
+        circuit::{Layouter, SimpleFloorPlanner, Value},
+        plonk::{
+            generate_witness, keygen_pk, keygen_vk, verify_proof, Advice, Challenge, Column,
+            ConstraintSystem, FirstPhase, SecondPhase, Selector,
+        },
+        poly::{
+            commitment::ParamsProver,
+            kzg::{
+                commitment::{KZGCommitmentScheme, ParamsKZG},
+                multiopen::{ProverSHPLONK, VerifierSHPLONK},
+                strategy::SingleStrategy,
+            },
+            Rotation,
+        },
+        transcript::{
+            Blake2bRead, Blake2bWrite, Challenge255, TranscriptReadBuffer, TranscriptWriterBuffer,
+        },
+        SerdeFormat,
+    };
+    use halo2curves::bn256::{Bn256, Fr, G1Affine};
+    use rand_core::OsRng;
+
+    #[derive(Clone, Copy)]
+    struct TwoPhaseConfig {
+        a: Column<Advice>,
+        b: Column<Advice>,
+        s: Selector,
+        c: Challenge,
+    }
+
+    /// Checks that `b = a * c`, where `b` is in the second phase.
+    #[derive(Clone, Copy)]
+    struct TwoPhaseCircuit(Value<Fr>);
+
+    impl Circuit<Fr> for TwoPhaseCircuit {
+        type Config = TwoPhaseConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+        #[cfg(feature = "circuit-params")]
+        type Params = ();
+
+        fn without_witnesses(&self) -> Self {
+            TwoPhaseCircuit(Value::unknown())
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+            let a = meta.advice_column_in(FirstPhase);
+            let c = meta.challenge_usable_after(FirstPhase);
+            let b = meta.advice_column_in(SecondPhase);
+            let s = meta.selector();
+            meta.create_gate("mul challenge", |meta| {
+                let a = meta.query_advice(a, Rotation::cur());
+                let b = meta.query_advice(b, Rotation::cur());
+                let c = meta.query_challenge(c);
+                let s = meta.query_selector(s);
+                vec![s * (a * c - b)]
+            });
+            TwoPhaseConfig { a, b, s, c }
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let c = layouter.get_challenge(config.c);
+            layouter.assign_region(
+                || "mul challenge",
+                |mut region| {
+                    config.s.enable(&mut region, 0)?;
+                    region.assign_advice(|| "a", config.a, 0, || self.0)?;
+                    region.assign_advice(|| "b", config.b, 0, || self.0 * c)?;
+                    Ok(())
+                },
+            )
+        }
+    }
+
+    let k = 4;
+    let params: ParamsKZG<Bn256> = ParamsKZG::setup(k, OsRng);
+    let empty_circuit = TwoPhaseCircuit(Value::unknown());
+    let vk = keygen_vk(&params, &empty_circuit).expect("keygen_vk should not fail");
+    let pk = keygen_pk(&params, vk, &empty_circuit).expect("keygen_pk should not fail");
+
+    // The witness machine keeps a calculator for the second phase, and sends the witness
+    // of the first phase to the prover as bytes.
+    let circuit = TwoPhaseCircuit(Value::known(Fr::from(3)));
+    let mut cs = ConstraintSystem::default();
+    let config = TwoPhaseCircuit::configure(&mut cs);
+    let instances: [&[Fr]; 0] = [];
+    let mut calculator = WitnessCalculator::new(k, &circuit, &config, &cs, &instances);
+    let witness = Witness::new(
+        k,
+        vec![calculator.next_phase(&ChallengeValues::default()).unwrap()],
+    );
+    assert_eq!(
+        generate_witness(k, &circuit, &instances).expect("witness generation"),
+        witness
+    );
+    let bytes = witness.to_bytes(SerdeFormat::RawBytes);
+    let witness = Witness::from_bytes(&bytes, SerdeFormat::RawBytes).unwrap();
+
+    // The witness alone is missing the second phase.
+    let mut transcript = Blake2bWrite::<_, G1Affine, Challenge255<_>>::init(vec![]);
+    let proof = create_proof_from_witness::<KZGCommitmentScheme<_>, ProverSHPLONK<_>, _, _, _>(
+        &params,
+        &pk,
+        vec![witness.clone()],
+        &[&[]],
+        OsRng,
+        &mut transcript,
+    );
+    assert!(matches!(proof.unwrap_err(), Error::Other(_)));
+
+    let mut transcript = Blake2bWrite::<_, G1Affine, Challenge255<_>>::init(vec![]);
+    create_proof_from_witness_phases::<KZGCommitmentScheme<_>, ProverSHPLONK<_>, _, _, _, _>(
+        &params,
+        &pk,
+        vec![witness],
+        |phase, challenges| {
+            assert_eq!(phase, 1);
+            assert!(challenges.get(config.c).is_some());
+            Ok(vec![calculator.next_phase(challenges)?])
+        },
+        &[&[]],
+        OsRng,
+        &mut transcript,
+    )
+    .expect("proof generation should not fail");
+    let proof = transcript.finalize();
+
+    let mut transcript = Blake2bRead::<_, G1Affine, Challenge255<_>>::init(proof.as_slice());
+    let strategy = SingleStrategy::new(params.verifier_params());
+    verify_proof::<KZGCommitmentScheme<Bn256>, VerifierSHPLONK<'_, Bn256>, _, _, _>(
+        &params,
+        pk.get_vk(),
+        strategy,
+        &[&[]],
+        &mut transcript,
+    )
+    .expect("proof verification should not fail");
+}
@@ -0,0 +1,110 @@
+mod common;
+
+use assert_matches::assert_matches;
+use common::{setup, MulCircuit};
+use halo2_proofs::circuit::Value;
+use halo2_proofs::plonk::{
+    create_proof, verify_proofs_batch_gwc, verify_proofs_batch_shplonk, BatchVerifierSHPLONK,
+    Error, ProvingKey,
+};
+use halo2_proofs::poly::commitment::Prover;
+use halo2_proofs::poly::kzg::commitment::{KZGCommitmentScheme, ParamsKZG};
+use halo2_proofs::poly::kzg::multiopen::{ProverGWC, ProverSHPLONK};
+use halo2_proofs::transcript::{Blake2bWrite, Challenge255, TranscriptWriterBuffer};
+use halo2curves::bn256::{Bn256, Fr, G1Affine};
+use rand_core::OsRng;
+
+const K: u32 = 4;
+
+type Proof = (Vec<Vec<Vec<Fr>>>, Vec<u8>);
+
+/// Returns a proof that `a * b = c`, which is only valid if it is true.
+fn prove<'params, P: Prover<'params, KZGCommitmentScheme<Bn256>>>(
+    params: &'params ParamsKZG<Bn256>,
+    pk: &ProvingKey<G1Affine>,
+    a: u64,
+    b: u64,
+    c: u64,
+) -> Proof {
+    let circuit = MulCircuit {
+        a: Value::known(Fr::from(a)),
+        b: Value::known(Fr::from(b)),
+    };
+    let instance = vec![Fr::from(c)];
+    let mut transcript = Blake2bWrite::<_, _, Challenge255<_>>::init(vec![]);
+    create_proof::<KZGCommitmentScheme<Bn256>, P, _, _, _, _>(
+        params,
+        pk,
+        &[circuit],
+        &[&[&instance]],
+        OsRng,
+        &mut transcript,
+    )
+    .expect("prover should not fail");
+    (vec![vec![instance]], transcript.finalize())
+}
+
+#[test]
+fn batch_verify_gwc() {
+    let (params, pk) = setup(K);
+    let vk = pk.get_vk();
+    let mut proofs: Vec<_> = (1..5)
+        .map(|a| prove::<ProverGWC<_>>(&params, &pk, a, 3, a * 3))
+        .collect();
+
+    assert_matches!(verify_proofs_batch_gwc(&params, vk, &[], OsRng), Ok(()));
+    assert_matches!(verify_proofs_batch_gwc(&params, vk, &proofs, OsRng), Ok(()));
+
+    proofs[2] = prove::<ProverGWC<_>>(&params, &pk, 2, 3, 7);
+    assert_matches!(
+        verify_proofs_batch_gwc(&params, vk, &proofs, OsRng),
+        Err(Error::BatchVerificationFailed { invalid: None })
+    );
+}
+
+#[test]
+fn batch_verify_shplonk() {
+    let (params, pk) = setup(K);
+    let vk = pk.get_vk();
+    let mut proofs: Vec<_> = (1..5)
+        .map(|a| prove::<ProverSHPLONK<_>>(&params, &pk, a, 3, a * 3))
+        .collect();
+
+    assert_matches!(verify_proofs_batch_shplonk(&params, vk, &[], OsRng), Ok(()));
+    assert_matches!(
+        verify_proofs_batch_shplonk(&params, vk, &proofs, OsRng),
+        Ok(())
+    );
+
+    proofs[1] = prove::<ProverSHPLONK<_>>(&params, &pk, 2, 3, 7);
+    assert_matches!(
+        verify_proofs_batch_shplonk(&params, vk, &proofs, OsRng),
+        Err(Error::BatchVerificationFailed { invalid: None })
+    );
+}
+
+#[test]
+fn batch_verifier_bisection() {
+    let (params, pk) = setup(K);
+    let vk = pk.get_vk();
+
+    let batch = BatchVerifierSHPLONK::new(&params, vk, OsRng).with_bisection();
+    assert!(batch.is_empty());
+    assert_matches!(batch.finalize(), Ok(()));
+
+    let mut batch = BatchVerifierSHPLONK::new(&params, vk, OsRng).with_bisection();
+    for a in 0..7 {
+        // Proofs 2 and 5 are invalid, and proof 6 cannot be read.
+        let (instances, proof) = match a {
+            2 | 5 => prove::<ProverSHPLONK<_>>(&params, &pk, a, 3, a * 3 + 1),
+            _ => prove::<ProverSHPLONK<_>>(&params, &pk, a, 3, a * 3),
+        };
+        let proof = if a == 6 { &proof[..10] } else { &proof[..] };
+        batch.add_proof(&instances, proof);
+    }
+    assert_eq!(batch.len(), 7);
+    assert_matches!(
+        batch.finalize(),
+        Err(Error::BatchVerificationFailed { invalid: Some(invalid) }) if invalid == vec![2, 5, 6]
+    );
+}
@@ -0,0 +1,191 @@
+//! A running sum whose first and last rows are constrained with `l_first` and `l_last`,
+//! checked at two values of `k`.
+
+use halo2_proofs::circuit::{Layouter, SimpleFloorPlanner, Value};
+use halo2_proofs::dev::MockProver;
+use halo2_proofs::plonk::{
+    create_proof, keygen_pk, keygen_vk, verify_proof, Advice, Circuit, Column, ConstraintSystem,
+    Error, Expression, Instance, Selector,
+};
+use halo2_proofs::poly::commitment::ParamsProver;
+use halo2_proofs::poly::kzg::commitment::{KZGCommitmentScheme, ParamsKZG};
+use halo2_proofs::poly::kzg::multiopen::{ProverSHPLONK, VerifierSHPLONK};
+use halo2_proofs::poly::kzg::strategy::SingleStrategy;
+use halo2_proofs::poly::Rotation;
+use halo2_proofs::transcript::{
+    Blake2bRead, Blake2bWrite, Challenge255, TranscriptReadBuffer, TranscriptWriterBuffer,
+};
+use halo2curves::bn256::{Bn256, Fr};
+use rand_core::OsRng;
+
+#[derive(Clone)]
+struct RunningSumConfig {
+    value: Column<Advice>,
+    acc: Column<Advice>,
+    total: Column<Advice>,
+    expected: Column<Instance>,
+    step: Selector,
+}
+
+/// Sums `values` over every usable row, padded with zeros, and exposes the sum, which is
+/// only known to be complete on the last usable row.
+#[derive(Clone, Default)]
+struct RunningSumCircuit {
+    k: u32,
+    values: Vec<u64>,
+}
+
+impl Circuit<Fr> for RunningSumCircuit {
+    type Config = RunningSumConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+    #[cfg(feature = "circuit-params")]
+    type Params = ();
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            k: self.k,
+            values: vec![],
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fr>) -> RunningSumConfig {
+        let value = meta.advice_column();
+        let acc = meta.advice_column();
+        let total = meta.advice_column();
+        let expected = meta.instance_column();
+        let step = meta.selector();
+        meta.enable_equality(total);
+        meta.enable_equality(expected);
+
+        let (l_first, l_last) = meta.boundary_fixed_columns();
+        meta.create_gate("running sum", |meta| {
+            let value = meta.query_advice(value, Rotation::cur());
+            let acc_prev = meta.query_advice(acc, Rotation::prev());
+            let acc = meta.query_advice(acc, Rotation::cur());
+            let total = meta.query_advice(total, Rotation::cur());
+            let step = meta.query_selector(step);
+            vec![
+                l_first * (acc.clone() - value.clone()),
+                step * (acc.clone() - acc_prev - value),
+                l_last * (acc - total),
+            ]
+        });
+
+        RunningSumConfig {
+            value,
+            acc,
+            total,
+            expected,
+            step,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: RunningSumConfig,
+        mut layouter: impl Layouter<Fr>,
+    ) -> Result<(), Error> {
+        let mut cs = ConstraintSystem::default();
+        Self::configure(&mut cs);
+        let rows = cs.usable_rows(self.k);
+
+        let total = layouter.assign_region(
+            || "running sum",
+            |mut region| {
+                let mut acc = Value::known(Fr::from(0));
+                let mut total = None;
+                for row in rows.clone() {
+                    let value = if self.values.is_empty() {
+                        Value::unknown()
+                    } else {
+                        Value::known(Fr::from(self.values.get(row).copied().unwrap_or(0)))
+                    };
+                    acc = acc + value;
+                    if row > rows.start {
+                        config.step.enable(&mut region, row)?;
+                    }
+                    region.assign_advice(|| "value", config.value, row, || value)?;
+                    region.assign_advice(|| "acc", config.acc, row, || acc)?;
+                    total = Some(region.assign_advice(|| "total", config.total, row, || acc)?);
+                }
+                Ok(total.unwrap())
+            },
+        )?;
+        layouter.constrain_instance(total.cell(), config.expected, 0)
+    }
+}
+
+fn expression_is_fixed(expr: &Expression<Fr>) -> bool {
+    matches!(expr, Expression::Fixed(_))
+}
+
+#[test]
+fn boundary_columns_are_allocated_once() {
+    let mut cs = ConstraintSystem::<Fr>::default();
+    let (l_first, l_last) = cs.boundary_fixed_columns();
+    assert!(expression_is_fixed(&l_first) && expression_is_fixed(&l_last));
+    assert_eq!(cs.boundary_fixed_columns(), (l_first, l_last));
+    assert_eq!(cs.num_fixed_columns(), 2);
+
+    let (first, last) = cs.boundary_columns.unwrap();
+    let rows = cs.usable_rows(4);
+    assert_eq!(
+        cs.boundary_cells(&rows),
+        [(first, rows.start), (last, rows.end - 1)]
+    );
+}
+
+#[test]
+fn running_sum_with_mock_prover() {
+    let values = vec![1, 2, 3, 4];
+    for k in [4, 6] {
+        let circuit = RunningSumCircuit {
+            k,
+            values: values.clone(),
+        };
+        let prover = MockProver::run(k, &circuit, vec![vec![Fr::from(10)]]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+
+        let prover = MockProver::run(k, &circuit, vec![vec![Fr::from(11)]]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+}
+
+#[test]
+fn running_sum_with_real_prover() {
+    for k in [4, 6] {
+        let params = ParamsKZG::<Bn256>::new(k);
+        let empty = RunningSumCircuit { k, values: vec![] };
+        let vk = keygen_vk(&params, &empty).unwrap();
+        let pk = keygen_pk(&params, vk, &empty).unwrap();
+
+        let circuit = RunningSumCircuit {
+            k,
+            values: vec![1, 2, 3, 4],
+        };
+        let mut transcript = Blake2bWrite::<_, _, Challenge255<_>>::init(vec![]);
+        create_proof::<KZGCommitmentScheme<Bn256>, ProverSHPLONK<_>, _, _, _, _>(
+            &params,
+            &pk,
+            &[circuit],
+            &[&[&[Fr::from(10)]]],
+            OsRng,
+            &mut transcript,
+        )
+        .unwrap();
+        let proof = transcript.finalize();
+
+        for (expected, valid) in [(10, true), (11, false)] {
+            let mut transcript = Blake2bRead::<_, _, Challenge255<_>>::init(&proof[..]);
+            let strategy = SingleStrategy::new(&params);
+            let verified = verify_proof::<_, VerifierSHPLONK<_>, _, _, _>(
+                &params,
+                pk.get_vk(),
+                strategy,
+                &[&[&[Fr::from(expected)]]],
+                &mut transcript,
+            );
+            assert_eq!(verified.is_ok(), valid);
+        }
+    }
+}
@@ -0,0 +1,177 @@
+use halo2_frontend::circuit::compile_circuit;
+use halo2_middleware::circuit::{ChallengeMid, CompiledCircuitV2, ExpressionMid};
+use halo2_middleware::export::ccs::{assignment, export, ExportError};
+use halo2_middleware::lookup;
+use halo2_proofs::circuit::{Layouter, SimpleFloorPlanner, Value};
+use halo2_proofs::dev::MockProver;
+use halo2_proofs::plonk::{
+    generate_witness, Advice, Circuit, Column, ConstraintSystem, Error, Instance, Selector,
+};
+use halo2_proofs::poly::Rotation;
+use halo2curves::bn256::Fr;
+use halo2curves::ff::Field;
+
+const K: u32 = 4;
+const ROWS: usize = 8;
+
+#[derive(Clone)]
+struct FibonacciConfig {
+    a: Column<Advice>,
+    b: Column<Advice>,
+    instance: Column<Instance>,
+    s: Selector,
+}
+
+/// Computes the Fibonacci sequence from the first two public inputs down two columns, and
+/// exposes its last term as the third public input.
+#[derive(Clone, Default)]
+struct FibonacciCircuit;
+
+impl Circuit<Fr> for FibonacciCircuit {
+    type Config = FibonacciConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+    #[cfg(feature = "circuit-params")]
+    type Params = ();
+
+    fn without_witnesses(&self) -> Self {
+        Self
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fr>) -> FibonacciConfig {
+        let a = meta.advice_column();
+        let b = meta.advice_column();
+        let instance = meta.instance_column();
+        let s = meta.selector();
+        meta.enable_equality(a);
+        meta.enable_equality(b);
+        meta.enable_equality(instance);
+
+        meta.create_gate("fibonacci", |meta| {
+            let s = meta.query_selector(s);
+            let a_cur = meta.query_advice(a, Rotation::cur());
+            let b_cur = meta.query_advice(b, Rotation::cur());
+            let a_next = meta.query_advice(a, Rotation::next());
+            let b_next = meta.query_advice(b, Rotation::next());
+            vec![
+                s.clone() * (a_next - b_cur.clone()),
+                s * (b_next - a_cur - b_cur),
+            ]
+        });
+
+        FibonacciConfig { a, b, instance, s }
+    }
+
+    fn synthesize(
+        &self,
+        config: FibonacciConfig,
+        mut layouter: impl Layouter<Fr>,
+    ) -> Result<(), Error> {
+        let (first, second, last) = layouter.assign_region(
+            || "fibonacci",
+            |mut region| {
+                let (mut a, mut b) = (Fr::ONE, Fr::ONE);
+                let mut cells = vec![];
+                for row in 0..ROWS {
+                    if row + 1 < ROWS {
+                        config.s.enable(&mut region, row)?;
+                    }
+                    let a_cell = region.assign_advice(|| "a", config.a, row, || Value::known(a))?;
+                    let b_cell = region.assign_advice(|| "b", config.b, row, || Value::known(b))?;
+                    cells.push((a_cell, b_cell));
+                    (a, b) = (b, a + b);
+                }
+                let (first, second) = cells[0].clone();
+                Ok((first, second, cells[ROWS - 1].1.clone()))
+            },
+        )?;
+        layouter.constrain_instance(first.cell(), config.instance, 0)?;
+        layouter.constrain_instance(second.cell(), config.instance, 1)?;
+        layouter.constrain_instance(last.cell(), config.instance, 2)
+    }
+}
+
+fn instance() -> Vec<Fr> {
+    // The two first terms, and the ninth term in `b` on the last row.
+    vec![Fr::ONE, Fr::ONE, Fr::from(34)]
+}
+
+fn compile() -> CompiledCircuitV2<Fr> {
+    let (compiled, _, _) =
+        compile_circuit(K, &FibonacciCircuit, true).expect("circuit should compile");
+    compiled
+}
+
+fn advice(instance: &[Fr]) -> Vec<Vec<Fr>> {
+    generate_witness(K, &FibonacciCircuit, &[instance])
+        .expect("witness should be generated")
+        .into_advice()
+        .remove(0)
+        .into_iter()
+        .flatten()
+        .collect()
+}
+
+#[test]
+fn ccs_is_satisfied_by_a_valid_witness() {
+    let instance = instance();
+    MockProver::run(K, &FibonacciCircuit, vec![instance.clone()])
+        .unwrap()
+        .assert_satisfied();
+
+    let compiled = compile();
+    let ccs = export(&compiled.cs, &compiled.preprocessing, K).unwrap();
+    let fixed = &compiled.preprocessing.fixed;
+    let z = assignment(K, fixed, &[instance.clone()], &advice(&instance));
+    assert_eq!(z.len(), ccs.num_variables);
+    assert_eq!(ccs.num_public, 1 + (1 << K) * (fixed.len() + 1));
+    assert_eq!(ccs.degree, 2);
+    assert!(ccs.is_satisfied(&z));
+}
+
+#[test]
+fn ccs_is_violated_by_an_invalid_witness() {
+    let instance = instance();
+    let compiled = compile();
+    let ccs = export(&compiled.cs, &compiled.preprocessing, K).unwrap();
+    let fixed = &compiled.preprocessing.fixed;
+
+    // A gate fails.
+    let mut wrong_advice = advice(&instance);
+    wrong_advice[1][3] += Fr::ONE;
+    let z = assignment(K, fixed, &[instance.clone()], &wrong_advice);
+    assert!(!ccs.is_satisfied(&z));
+
+    // A copy to the instance fails.
+    let mut wrong_instance = instance.clone();
+    wrong_instance[2] += Fr::ONE;
+    let z = assignment(K, fixed, &[wrong_instance], &advice(&instance));
+    assert!(!ccs.is_satisfied(&z));
+
+    // A fixed cell differs from its preprocessed value.
+    let mut z = assignment(K, fixed, &[instance.clone()], &advice(&instance));
+    z[1] += Fr::ONE;
+    assert!(!ccs.is_satisfied(&z));
+}
+
+#[test]
+fn ccs_export_reports_unsupported_arguments() {
+    let compiled = compile();
+
+    let mut cs = compiled.cs.clone();
+    cs.lookups.push(lookup::ArgumentV2 {
+        name: "lookup".to_string(),
+        input_expressions: vec![],
+        table_expressions: vec![],
+    });
+    assert_eq!(
+        export(&cs, &compiled.preprocessing, K).unwrap_err(),
+        ExportError::Lookups
+    );
+
+    let mut cs = compiled.cs.clone();
+    cs.gates[0].poly = ExpressionMid::Challenge(ChallengeMid { index: 0, phase: 0 });
+    assert_eq!(
+        export(&cs, &compiled.preprocessing, K).unwrap_err(),
+        ExportError::Challenges
+    );
+}
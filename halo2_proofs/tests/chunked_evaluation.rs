@@ -0,0 +1,262 @@
+#[cfg(feature = "heap-profiling")]
+#[global_allocator]
+static ALLOC: dhat::Alloc = dhat::Alloc;
+
+use halo2_proofs::circuit::{Layouter, SimpleFloorPlanner, Value};
+use halo2_proofs::plonk::{
+    create_proof_with_options, keygen_pk, keygen_vk, verify_proof, Advice, Circuit, Column,
+    ConstraintSystem, Error, Fixed, Instance, ProverOptions, ProvingKey, Selector,
+};
+use halo2_proofs::poly::commitment::ParamsProver;
+use halo2_proofs::poly::kzg::commitment::{KZGCommitmentScheme, ParamsKZG};
+use halo2_proofs::poly::kzg::multiopen::{ProverSHPLONK, VerifierSHPLONK};
+use halo2_proofs::poly::kzg::strategy::SingleStrategy;
+use halo2_proofs::poly::Rotation;
+use halo2_proofs::transcript::{
+    Blake2bRead, Blake2bWrite, Challenge255, TranscriptReadBuffer, TranscriptWriterBuffer,
+};
+use halo2curves::bn256::{Bn256, Fr, G1Affine};
+use rand_chacha::ChaCha20Rng;
+use rand_core::SeedableRng;
+
+const K: u32 = 8;
+const COLUMNS: usize = 16;
+const ROWS: usize = 40;
+const OFFSETS: [u64; 2] = [0, 3];
+
+#[derive(Clone)]
+struct CountConfig {
+    columns: Vec<Column<Advice>>,
+    shuffled: Column<Advice>,
+    table: Column<Fixed>,
+    instance: Column<Instance>,
+    s: Selector,
+}
+
+/// Counts from `offset` down the first of `COLUMNS` columns, and from each column to the
+/// next one across them, with every kind of argument: a lookup of the first column, a
+/// shuffle of the second one, and a copy of the first cell to the instance.
+#[derive(Clone, Default)]
+struct CountCircuit {
+    offset: u64,
+}
+
+impl Circuit<Fr> for CountCircuit {
+    type Config = CountConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+    #[cfg(feature = "circuit-params")]
+    type Params = ();
+
+    fn without_witnesses(&self) -> Self {
+        self.clone()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fr>) -> CountConfig {
+        let columns: Vec<_> = (0..COLUMNS).map(|_| meta.advice_column()).collect();
+        let shuffled = meta.advice_column();
+        let table = meta.fixed_column();
+        let instance = meta.instance_column();
+        let s = meta.selector();
+        meta.enable_equality(columns[0]);
+        meta.enable_equality(instance);
+
+        meta.create_gate("count down", |meta| {
+            let cur = meta.query_advice(columns[0], Rotation::cur());
+            let next = meta.query_advice(columns[0], Rotation::next());
+            let s = meta.query_selector(s);
+            vec![s * (next - cur - Fr::from(1))]
+        });
+        meta.create_gate("count across", |meta| {
+            let s = meta.query_selector(s);
+            (1..COLUMNS)
+                .map(|i| {
+                    let prev = meta.query_advice(columns[i - 1], Rotation::cur());
+                    let cur = meta.query_advice(columns[i], Rotation::cur());
+                    s.clone() * (cur - prev - Fr::from(1))
+                })
+                .collect::<Vec<_>>()
+        });
+        meta.lookup_any("table", |meta| {
+            let input = meta.query_advice(columns[0], Rotation::cur());
+            let table = meta.query_fixed(table, Rotation::cur());
+            vec![(input, table)]
+        });
+        meta.shuffle("reversed", |meta| {
+            let input = meta.query_advice(columns[1], Rotation::cur());
+            let shuffled = meta.query_advice(shuffled, Rotation::cur());
+            vec![(input, shuffled)]
+        });
+
+        CountConfig {
+            columns,
+            shuffled,
+            table,
+            instance,
+            s,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: CountConfig,
+        mut layouter: impl Layouter<Fr>,
+    ) -> Result<(), Error> {
+        let first = layouter.assign_region(
+            || "count",
+            |mut region| {
+                let value = |column: usize, row: usize| {
+                    Value::known(Fr::from(self.offset + (column + row) as u64))
+                };
+                for row in 0..ROWS + OFFSETS[1] as usize {
+                    let table = Value::known(Fr::from(row as u64));
+                    region.assign_fixed(|| "table", config.table, row, || table)?;
+                }
+                let mut first = None;
+                for row in 0..ROWS {
+                    if row + 1 < ROWS {
+                        config.s.enable(&mut region, row)?;
+                    }
+                    for (column, advice) in config.columns.iter().enumerate() {
+                        let cell = region.assign_advice(
+                            || "count",
+                            *advice,
+                            row,
+                            || value(column, row),
+                        )?;
+                        first.get_or_insert(cell);
+                    }
+                    region.assign_advice(
+                        || "shuffled",
+                        config.shuffled,
+                        row,
+                        || value(1, ROWS - 1 - row),
+                    )?;
+                }
+                Ok(first.expect("a cell was assigned"))
+            },
+        )?;
+        layouter.constrain_instance(first.cell(), config.instance, 0)
+    }
+}
+
+fn setup() -> (ParamsKZG<Bn256>, ProvingKey<G1Affine>) {
+    let params = ParamsKZG::<Bn256>::new(K);
+    let vk = keygen_vk(&params, &CountCircuit::default()).expect("keygen_vk should not fail");
+    let pk = keygen_pk(&params, vk, &CountCircuit::default()).expect("keygen_pk should not fail");
+    (params, pk)
+}
+
+fn instances() -> Vec<[Fr; 1]> {
+    OFFSETS.iter().map(|offset| [Fr::from(*offset)]).collect()
+}
+
+fn prove(
+    params: &ParamsKZG<Bn256>,
+    pk: &ProvingKey<G1Affine>,
+    max_extended_columns_in_memory: Option<usize>,
+) -> Vec<u8> {
+    let circuits = OFFSETS.map(|offset| CountCircuit { offset });
+    let instances = instances();
+    let instances: Vec<[&[Fr]; 1]> = instances.iter().map(|instance| [&instance[..]]).collect();
+    let instances: Vec<&[&[Fr]]> = instances.iter().map(|instance| &instance[..]).collect();
+    let mut transcript = Blake2bWrite::<_, _, Challenge255<_>>::init(vec![]);
+    create_proof_with_options::<KZGCommitmentScheme<Bn256>, ProverSHPLONK<_>, _, _, _, _>(
+        params,
+        pk,
+        &circuits,
+        &instances,
+        ChaCha20Rng::seed_from_u64(0),
+        &mut transcript,
+        &ProverOptions {
+            max_extended_columns_in_memory,
+            ..Default::default()
+        },
+    )
+    .expect("prover should not fail");
+    transcript.finalize()
+}
+
+#[test]
+fn chunked_evaluation_gives_the_same_proof() {
+    let (params, pk) = setup();
+    let proof = prove(&params, &pk, None);
+    // Both circuits at once, one circuit at a time, and one circuit over one part at a time.
+    for max in [2 * (COLUMNS + 2), COLUMNS + 2, COLUMNS, 0] {
+        assert!(prove(&params, &pk, Some(max)) == proof, "max {max}");
+    }
+
+    let instances = instances();
+    let instances: Vec<[&[Fr]; 1]> = instances.iter().map(|instance| [&instance[..]]).collect();
+    let instances: Vec<&[&[Fr]]> = instances.iter().map(|instance| &instance[..]).collect();
+    let mut transcript = Blake2bRead::<_, _, Challenge255<_>>::init(&proof[..]);
+    verify_proof::<_, VerifierSHPLONK<_>, _, _, _>(
+        &params,
+        pk.get_vk(),
+        SingleStrategy::new(&params),
+        &instances,
+        &mut transcript,
+    )
+    .expect("proof should verify");
+}
+
+#[test]
+fn estimated_memory_accounts_for_the_parts() {
+    let (_, pk) = setup();
+    let estimate = pk.estimated_prover_memory(K);
+    assert_eq!(estimate.extended_columns, COLUMNS + 2);
+    assert!(estimate.parts > 1);
+
+    // Without a bound, or with a bound on both circuits, the columns of both circuits are
+    // extended at once; with a bound on a single circuit, those of one circuit; and below,
+    // those of a single circuit over a single part.
+    let circuits = OFFSETS.len();
+    let column = estimate.extended_column;
+    let columns = estimate.extended_columns * column;
+    let base = estimate.total(circuits, None) - circuits * columns;
+    for (max, live) in [
+        (None, circuits * columns),
+        (
+            Some(circuits * estimate.extended_columns),
+            circuits * columns,
+        ),
+        (Some(2 * estimate.extended_columns - 1), columns),
+        (Some(estimate.extended_columns), columns),
+        (
+            Some(estimate.extended_columns - 1),
+            columns / estimate.parts,
+        ),
+        (Some(0), columns / estimate.parts),
+    ] {
+        assert_eq!(estimate.total(circuits, max), base + live, "max {max:?}");
+    }
+
+    // The estimate of a circuit twice as large is twice as large.
+    let larger = pk.estimated_prover_memory(K + 1);
+    assert_eq!(
+        larger.total(circuits, Some(0)),
+        2 * estimate.total(circuits, Some(0))
+    );
+}
+
+#[cfg(feature = "heap-profiling")]
+#[test]
+fn chunked_evaluation_lowers_peak_memory() {
+    let (params, pk) = setup();
+    let estimate = pk.estimated_prover_memory(K);
+    let extended_columns = OFFSETS.len() * estimate.extended_columns * estimate.extended_column;
+
+    let _profiler = dhat::Profiler::builder().testing().build();
+    let start = dhat::HeapStats::get().curr_bytes;
+    let chunked = prove(&params, &pk, Some(0));
+    let chunked_peak = dhat::HeapStats::get().max_bytes - start;
+    let full = prove(&params, &pk, None);
+    let full_peak = dhat::HeapStats::get().max_bytes - start;
+    assert!(chunked == full);
+
+    // Without a bound, every advice and instance column of both circuits is held over the
+    // extended domain at once, on top of the peak of the proof in parts.
+    assert!(
+        full_peak >= chunked_peak + extended_columns / 2,
+        "the peak memory went from {full_peak} bytes to {chunked_peak} bytes only"
+    );
+}
@@ -0,0 +1,316 @@
+//! Checks that malformed compiled circuits are reported by their validation, and rejected by
+//! the V2 keygen.
+
+use std::collections::HashMap;
+
+use assert_matches::assert_matches;
+use halo2_middleware::circuit::{
+    AdviceQueryMid, Any, Cell, ChallengeMid, ColumnMid, CompiledCircuitV2,
+    ConstraintSystemV2Backend, ExpressionMid, FixedQueryMid, GateV2Backend, InstanceQueryMid,
+    PreprocessingV2,
+};
+use halo2_middleware::metadata;
+use halo2_middleware::permutation::{ArgumentV2, AssemblyMid};
+use halo2_middleware::validate::{Location, MidError};
+use halo2_middleware::{lookup, shuffle};
+use halo2_proofs::plonk::{keygen_vk_v2, Error};
+use halo2_proofs::poly::commitment::ParamsProver;
+use halo2_proofs::poly::kzg::commitment::ParamsKZG;
+use halo2_proofs::poly::Rotation;
+use halo2curves::bn256::{Bn256, Fr, G1Affine};
+use halo2curves::ff::Field;
+
+const K: u32 = 4;
+const N: usize = 1 << K;
+
+fn fixed(column_index: usize) -> ExpressionMid<Fr> {
+    ExpressionMid::Fixed(FixedQueryMid {
+        column_index,
+        rotation: Rotation::cur(),
+    })
+}
+
+fn advice(column_index: usize, phase: u8) -> ExpressionMid<Fr> {
+    ExpressionMid::Advice(AdviceQueryMid {
+        column_index,
+        rotation: Rotation::cur(),
+        phase,
+    })
+}
+
+fn challenge(index: usize, phase: u8) -> ExpressionMid<Fr> {
+    ExpressionMid::Challenge(ChallengeMid { index, phase })
+}
+
+fn column(column_type: Any, index: usize) -> ColumnMid {
+    ColumnMid { index, column_type }
+}
+
+/// Returns a circuit with a fixed column `q`, an advice column `a` in the first phase, an
+/// advice column `b` in the second phase and a challenge `r` squeezed after the first phase,
+/// that enforces `q * (b - a * r) = 0`, looks `a` up in `q`, shuffles `a` into itself, and
+/// copies `a` at row 0 to the instance.
+fn compiled_circuit() -> CompiledCircuitV2<Fr> {
+    let a = column(Any::advice(), 0);
+    let instance = column(Any::Instance, 0);
+    let cs = ConstraintSystemV2Backend {
+        num_fixed_columns: 1,
+        num_advice_columns: 2,
+        num_instance_columns: 1,
+        num_challenges: 1,
+        unblinded_advice_columns: vec![],
+        advice_column_phase: vec![0, 1],
+        challenge_phase: vec![0],
+        gates: vec![GateV2Backend {
+            name: "mix".to_string(),
+            poly: fixed(0) * (advice(1, 1) - advice(0, 0) * challenge(0, 0)),
+        }],
+        permutation: ArgumentV2 {
+            columns: vec![a, instance],
+        },
+        lookups: vec![lookup::ArgumentV2 {
+            name: "range".to_string(),
+            input_expressions: vec![advice(0, 0)],
+            table_expressions: vec![fixed(0)],
+        }],
+        shuffles: vec![shuffle::ArgumentV2 {
+            name: "sorted".to_string(),
+            input_expressions: vec![advice(0, 0)],
+            shuffle_expressions: vec![advice(0, 0)],
+        }],
+        general_column_annotations: HashMap::from([(
+            metadata::Column {
+                column_type: Any::Fixed,
+                index: 0,
+            },
+            "q".to_string(),
+        )]),
+    };
+    let preprocessing = PreprocessingV2 {
+        permutation: AssemblyMid {
+            copies: vec![(
+                Cell { column: a, row: 0 },
+                Cell {
+                    column: instance,
+                    row: 0,
+                },
+            )],
+        },
+        fixed: vec![vec![Fr::ONE; N]],
+    };
+    CompiledCircuitV2 { preprocessing, cs }
+}
+
+fn errors(circuit: &CompiledCircuitV2<Fr>) -> Vec<MidError> {
+    circuit.validate(K).unwrap_err()
+}
+
+fn gate() -> Location {
+    Location::Gate {
+        index: 0,
+        name: "mix".to_string(),
+    }
+}
+
+#[test]
+fn valid_circuit_passes_validation() {
+    let circuit = compiled_circuit();
+    assert_eq!(circuit.cs.validate(), Ok(()));
+    assert_eq!(circuit.validate(K), Ok(()));
+
+    let params = ParamsKZG::<Bn256>::new(K);
+    assert_matches!(keygen_vk_v2::<G1Affine, _>(&params, &circuit), Ok(_));
+}
+
+#[test]
+fn validation_reports_malformed_constraint_systems() {
+    let mut circuit = compiled_circuit();
+    circuit.cs.gates[0].poly = fixed(1);
+    assert_eq!(
+        errors(&circuit),
+        [MidError::QueryColumn {
+            location: gate(),
+            column: column(Any::Fixed, 1),
+        }]
+    );
+
+    let mut circuit = compiled_circuit();
+    circuit.cs.lookups[0].table_expressions = vec![advice(2, 0), fixed(0)];
+    let location = Location::Lookup {
+        index: 0,
+        name: "range".to_string(),
+    };
+    assert_eq!(
+        errors(&circuit),
+        [
+            MidError::QueryColumn {
+                location: location.clone(),
+                column: column(Any::advice(), 2),
+            },
+            MidError::ArgumentLength {
+                location,
+                inputs: 1,
+                tables: 2,
+            },
+        ]
+    );
+
+    let mut circuit = compiled_circuit();
+    circuit.cs.shuffles[0].shuffle_expressions = vec![advice(1, 0)];
+    assert_eq!(
+        errors(&circuit),
+        [MidError::QueryPhase {
+            location: Location::Shuffle {
+                index: 0,
+                name: "sorted".to_string(),
+            },
+            column: 1,
+            phase: 0,
+            expected: 1,
+        }]
+    );
+
+    let mut circuit = compiled_circuit();
+    circuit.cs.gates[0].poly = ExpressionMid::Instance(InstanceQueryMid {
+        column_index: 0,
+        rotation: Rotation::cur(),
+    }) * challenge(1, 0);
+    assert_eq!(
+        errors(&circuit),
+        [MidError::QueryChallenge {
+            location: gate(),
+            index: 1,
+        }]
+    );
+
+    // The challenge is squeezed after the first phase, but is used as if it was squeezed
+    // after the second one.
+    let mut circuit = compiled_circuit();
+    circuit.cs.gates[0].poly = challenge(0, 1);
+    assert_eq!(
+        errors(&circuit),
+        [MidError::QueryChallengePhase {
+            location: gate(),
+            index: 0,
+            phase: 1,
+            expected: 0,
+        }]
+    );
+
+    // No advice column is committed in the third phase.
+    let mut circuit = compiled_circuit();
+    circuit.cs.challenge_phase = vec![2];
+    circuit.cs.gates[0].poly = challenge(0, 2);
+    assert_eq!(
+        errors(&circuit),
+        [MidError::ChallengePhase {
+            challenge: 0,
+            phase: 2,
+        }]
+    );
+
+    let mut circuit = compiled_circuit();
+    circuit.cs.advice_column_phase = vec![0, 2];
+    circuit.cs.gates[0].poly = advice(1, 2);
+    assert_eq!(
+        errors(&circuit),
+        [MidError::MissingAdvicePhase { phase: 1 }]
+    );
+
+    let mut circuit = compiled_circuit();
+    circuit.cs.num_challenges = 2;
+    assert_eq!(
+        errors(&circuit),
+        [MidError::PhaseCount {
+            what: "challenges",
+            expected: 2,
+            found: 1,
+        }]
+    );
+
+    let mut circuit = compiled_circuit();
+    circuit.cs.unblinded_advice_columns = vec![2];
+    assert_eq!(errors(&circuit), [MidError::UnblindedColumn { index: 2 }]);
+
+    let mut circuit = compiled_circuit();
+    circuit
+        .cs
+        .permutation
+        .columns
+        .push(column(Any::Instance, 1));
+    assert_eq!(
+        errors(&circuit),
+        [MidError::PermutationColumn {
+            position: 2,
+            column: column(Any::Instance, 1),
+        }]
+    );
+}
+
+#[test]
+fn validation_reports_malformed_preprocessing() {
+    let mut circuit = compiled_circuit();
+    circuit.preprocessing.fixed[0].pop();
+    assert_eq!(
+        errors(&circuit),
+        [MidError::FixedColumnLength {
+            index: 0,
+            name: Some("q".to_string()),
+            expected: N,
+            found: N - 1,
+        }]
+    );
+
+    let mut circuit = compiled_circuit();
+    circuit.preprocessing.fixed.push(vec![Fr::ZERO; N]);
+    assert_eq!(
+        errors(&circuit),
+        [MidError::FixedColumnCount {
+            expected: 1,
+            found: 2,
+        }]
+    );
+
+    let mut circuit = compiled_circuit();
+    circuit.preprocessing.permutation.copies[0].1.row = N;
+    assert_eq!(
+        errors(&circuit),
+        [MidError::CopyCell {
+            copy: 0,
+            column: column(Any::Instance, 0),
+            row: N,
+        }]
+    );
+
+    let mut circuit = compiled_circuit();
+    circuit.preprocessing.permutation.copies[0].0.column = column(Any::advice(), 3);
+    assert_eq!(
+        errors(&circuit),
+        [MidError::CopyCell {
+            copy: 0,
+            column: column(Any::advice(), 3),
+            row: 0,
+        }]
+    );
+}
+
+#[test]
+fn keygen_rejects_invalid_circuits() {
+    let params = ParamsKZG::<Bn256>::new(K);
+    let mut circuit = compiled_circuit();
+    circuit.cs.gates[0].poly = advice(5, 0);
+    circuit.preprocessing.permutation.copies[0].1.row = N;
+
+    let Err(Error::Other(message)) = keygen_vk_v2::<G1Affine, _>(&params, &circuit) else {
+        panic!("keygen should reject the circuit");
+    };
+    assert_eq!(
+        message,
+        errors(&circuit)
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join("; ")
+    );
+    assert!(message.contains("gate 0 (\"mix\")"));
+}
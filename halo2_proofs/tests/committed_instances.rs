@@ -0,0 +1,215 @@
+mod common;
+
+use assert_matches::assert_matches;
+use common::MulCircuit;
+use group::Curve;
+use halo2_proofs::circuit::Value;
+use halo2_proofs::plonk::proof::{read_with_header, write_with_header};
+use halo2_proofs::plonk::{
+    create_proof, keygen_pk, keygen_vk, keygen_vk_with_options, verify_proof,
+    verify_proof_with_instance_commitments, CircuitOptions, Error, ProvingKey, VerificationError,
+    VerifyingKey,
+};
+use halo2_proofs::poly::commitment::{Blind, Params, ParamsProver};
+use halo2_proofs::poly::kzg::commitment::{KZGCommitmentScheme, ParamsKZG};
+use halo2_proofs::poly::kzg::multiopen::{ProverSHPLONK, VerifierSHPLONK};
+use halo2_proofs::poly::kzg::strategy::SingleStrategy;
+use halo2_proofs::transcript::{
+    Blake2bRead, Blake2bWrite, Challenge255, TranscriptReadBuffer, TranscriptWriterBuffer,
+};
+use halo2_proofs::SerdeFormat;
+use halo2curves::bn256::{Bn256, Fr, G1Affine};
+use rand_core::OsRng;
+
+const K: u32 = 4;
+/// The size of a point and of a scalar in a proof.
+const POINT: usize = 32;
+const SCALAR: usize = 32;
+
+fn setup(commit_instances: bool) -> (ParamsKZG<Bn256>, ProvingKey<G1Affine>) {
+    let params = ParamsKZG::<Bn256>::new(K);
+    let options = CircuitOptions { commit_instances };
+    let vk = keygen_vk_with_options(&params, &MulCircuit::default(), true, &options)
+        .expect("keygen_vk should not fail");
+    let pk = keygen_pk(&params, vk, &MulCircuit::default()).expect("keygen_pk should not fail");
+    (params, pk)
+}
+
+/// Returns a raw proof that `2 * 3 = 6`.
+fn prove(params: &ParamsKZG<Bn256>, pk: &ProvingKey<G1Affine>) -> Vec<u8> {
+    let circuit = MulCircuit {
+        a: Value::known(Fr::from(2)),
+        b: Value::known(Fr::from(3)),
+    };
+    let mut transcript = Blake2bWrite::<_, _, Challenge255<_>>::init(vec![]);
+    create_proof::<KZGCommitmentScheme<Bn256>, ProverSHPLONK<_>, _, _, _, _>(
+        params,
+        pk,
+        &[circuit],
+        &[&[&[Fr::from(6)]]],
+        OsRng,
+        &mut transcript,
+    )
+    .expect("prover should not fail");
+    transcript.finalize()
+}
+
+fn verify(
+    params: &ParamsKZG<Bn256>,
+    vk: &VerifyingKey<G1Affine>,
+    instance: Fr,
+    proof: &[u8],
+) -> Result<(), VerificationError> {
+    let mut transcript = Blake2bRead::<_, _, Challenge255<_>>::init(proof);
+    let strategy = SingleStrategy::new(params);
+    verify_proof::<_, VerifierSHPLONK<_>, _, _, _>(
+        params,
+        vk,
+        strategy,
+        &[&[&[instance]]],
+        &mut transcript,
+    )
+}
+
+#[test]
+fn prove_and_verify_in_both_modes() {
+    let mut lens = vec![];
+    for commit_instances in [false, true] {
+        let (params, pk) = setup(commit_instances);
+        let vk = pk.get_vk();
+        assert_eq!(vk.commit_instances(), commit_instances);
+
+        let proof = prove(&params, &pk);
+        verify(&params, vk, Fr::from(6), &proof).expect("proof should verify");
+        assert_matches!(
+            verify(&params, vk, Fr::from(7), &proof),
+            Err(VerificationError::FinalCheck)
+        );
+        lens.push(proof.len());
+    }
+    // The marker, the commitment to the instance column and its evaluation are in the
+    // proof when it is committed.
+    assert_eq!(lens[1], lens[0] + 2 * POINT + SCALAR);
+}
+
+/// Returns the commitment to an instance column holding `instance`, as computed by the
+/// party which publishes the instances.
+fn commit_instance(
+    params: &ParamsKZG<Bn256>,
+    vk: &VerifyingKey<G1Affine>,
+    instance: Fr,
+) -> G1Affine {
+    let mut values = vec![Fr::zero(); params.n() as usize];
+    values[0] = instance;
+    let poly = vk.get_domain().lagrange_from_vec(values);
+    params.commit_lagrange(&poly, Blind::default()).to_affine()
+}
+
+fn verify_with_commitment(
+    params: &ParamsKZG<Bn256>,
+    vk: &VerifyingKey<G1Affine>,
+    commitment: G1Affine,
+    proof: &[u8],
+) -> Result<(), VerificationError> {
+    let mut transcript = Blake2bRead::<_, _, Challenge255<_>>::init(proof);
+    let strategy = SingleStrategy::new(params);
+    verify_proof_with_instance_commitments::<_, VerifierSHPLONK<_>, _, _, _>(
+        params,
+        vk,
+        strategy,
+        &[&[commitment]],
+        &mut transcript,
+    )
+}
+
+#[test]
+fn verify_without_instance_values() {
+    let (params, pk) = setup(true);
+    let vk = pk.get_vk();
+    let proof = prove(&params, &pk);
+
+    let commitment = commit_instance(&params, vk, Fr::from(6));
+    verify_with_commitment(&params, vk, commitment, &proof).expect("proof should verify");
+
+    // The proof was created for other instances.
+    let other = commit_instance(&params, vk, Fr::from(7));
+    assert_matches!(
+        verify_with_commitment(&params, vk, other, &proof),
+        Err(VerificationError::FinalCheck)
+    );
+
+    // The commitments are only accepted by keys which commit to the instance columns.
+    let (params, pk) = setup(false);
+    let proof = prove(&params, &pk);
+    let commitment = commit_instance(&params, pk.get_vk(), Fr::from(6));
+    assert_matches!(
+        verify_with_commitment(&params, pk.get_vk(), commitment, &proof),
+        Err(VerificationError::InvalidInstances)
+    );
+}
+
+#[test]
+fn mode_is_part_of_the_verifying_key() {
+    let (params, evaluated) = setup(false);
+    let (_, committed) = setup(true);
+    let (evaluated, committed) = (evaluated.get_vk(), committed.get_vk());
+
+    // The default mode keeps the fingerprint of `keygen_vk`.
+    let default = keygen_vk(&params, &MulCircuit::default()).unwrap();
+    assert!(!default.commit_instances());
+    assert_eq!(default.transcript_repr(), evaluated.transcript_repr());
+    assert_ne!(committed.transcript_repr(), evaluated.transcript_repr());
+
+    for format in [SerdeFormat::Processed, SerdeFormat::RawBytes] {
+        let bytes = committed.to_bytes(format);
+        let read = VerifyingKey::<G1Affine>::from_bytes::<MulCircuit>(
+            &bytes,
+            format,
+            #[cfg(feature = "circuit-params")]
+            (),
+        )
+        .unwrap();
+        assert!(read.commit_instances());
+        assert_eq!(read.transcript_repr(), committed.transcript_repr());
+
+        let bytes = committed.to_bytes_self_describing(format);
+        let read = VerifyingKey::<G1Affine>::from_bytes_self_describing(&bytes).unwrap();
+        assert!(read.commit_instances());
+        assert_eq!(read.transcript_repr(), committed.transcript_repr());
+    }
+}
+
+#[test]
+fn mode_mismatch() {
+    let (params, evaluated) = setup(false);
+    let (_, committed) = setup(true);
+
+    for (prover, verifier) in [(&committed, &evaluated), (&evaluated, &committed)] {
+        let proof = prove(&params, prover);
+        let expected_commit_instances = verifier.get_vk().commit_instances();
+        assert_matches!(
+            verify(&params, verifier.get_vk(), Fr::from(6), &proof),
+            Err(VerificationError::InstanceModeMismatch {
+                expected_commit_instances: e,
+            }) if e == expected_commit_instances
+        );
+
+        let mut bytes = vec![];
+        write_with_header(prover.get_vk(), &proof, &mut bytes).unwrap();
+        let err = read_with_header(verifier.get_vk(), &mut &bytes[..]).unwrap_err();
+        assert_matches!(
+            err,
+            Error::InstanceModeMismatch {
+                expected_commit_instances: e,
+            } if e == expected_commit_instances
+        );
+    }
+
+    let err = Error::InstanceModeMismatch {
+        expected_commit_instances: false,
+    };
+    assert_eq!(
+        err.to_string(),
+        "Proof was created with committed instance columns, but the verifying key expects evaluated instance columns"
+    );
+}
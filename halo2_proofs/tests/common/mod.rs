@@ -0,0 +1,78 @@
+//! The circuit shared by the integration tests of proof creation and verification.
+
+// Each integration test uses its own subset of these helpers.
+#![allow(dead_code)]
+
+use ff::PrimeField;
+use halo2_proofs::circuit::{Layouter, SimpleFloorPlanner, Value};
+use halo2_proofs::plonk::{
+    keygen_pk, keygen_vk, Advice, Circuit, Column, ConstraintSystem, Error, Instance, ProvingKey,
+    Selector,
+};
+use halo2_proofs::poly::commitment::ParamsProver;
+use halo2_proofs::poly::kzg::commitment::ParamsKZG;
+use halo2_proofs::poly::Rotation;
+use halo2curves::bn256::{Bn256, Fr, G1Affine};
+
+#[derive(Clone)]
+pub struct MulConfig {
+    a: Column<Advice>,
+    b: Column<Advice>,
+    c: Column<Instance>,
+    s: Selector,
+}
+
+/// Proves the knowledge of `a` and `b` such that `a * b` is the public input.
+#[derive(Clone, Default)]
+pub struct MulCircuit<F: PrimeField = Fr> {
+    pub a: Value<F>,
+    pub b: Value<F>,
+}
+
+impl<F: PrimeField> Circuit<F> for MulCircuit<F> {
+    type Config = MulConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+    #[cfg(feature = "circuit-params")]
+    type Params = ();
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> MulConfig {
+        let a = meta.advice_column();
+        let b = meta.advice_column();
+        let c = meta.instance_column();
+        let s = meta.selector();
+
+        meta.create_gate("mul", |meta| {
+            let a = meta.query_advice(a, Rotation::cur());
+            let b = meta.query_advice(b, Rotation::cur());
+            let c = meta.query_instance(c, Rotation::cur());
+            let s = meta.query_selector(s);
+            vec![s * (a * b - c)]
+        });
+
+        MulConfig { a, b, c, s }
+    }
+
+    fn synthesize(&self, config: MulConfig, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        layouter.assign_region(
+            || "mul",
+            |mut region| {
+                config.s.enable(&mut region, 0)?;
+                region.assign_advice(|| "a", config.a, 0, || self.a)?;
+                region.assign_advice(|| "b", config.b, 0, || self.b)?;
+                Ok(())
+            },
+        )
+    }
+}
+
+/// Returns KZG parameters for `k`, and the proving key of [`MulCircuit`] for them.
+pub fn setup(k: u32) -> (ParamsKZG<Bn256>, ProvingKey<G1Affine>) {
+    let params = ParamsKZG::<Bn256>::new(k);
+    let vk = keygen_vk(&params, &MulCircuit::default()).expect("keygen_vk should not fail");
+    let pk = keygen_pk(&params, vk, &MulCircuit::default()).expect("keygen_pk should not fail");
+    (params, pk)
+}
@@ -0,0 +1,190 @@
+//! Proves a circuit that is built directly from the middleware structs, without a `Circuit`
+//! implementation.
+
+use std::collections::HashMap;
+
+use assert_matches::assert_matches;
+use halo2_backend::plonk::{prover::ProverV2Single, verifier::verify_proof_single};
+use halo2_middleware::circuit::{
+    AdviceQueryMid, Any, Cell, ColumnMid, CompiledCircuitV2, ConstraintSystemV2Backend,
+    ExpressionMid, FixedQueryMid, GateV2Backend, PreprocessingV2,
+};
+use halo2_middleware::permutation::{ArgumentV2, AssemblyMid};
+use halo2_proofs::plonk::{keygen_pk_v2, keygen_vk_v2, Error};
+use halo2_proofs::poly::commitment::ParamsProver;
+use halo2_proofs::poly::kzg::commitment::{KZGCommitmentScheme, ParamsKZG};
+use halo2_proofs::poly::kzg::multiopen::{ProverSHPLONK, VerifierSHPLONK};
+use halo2_proofs::poly::kzg::strategy::SingleStrategy;
+use halo2_proofs::poly::Rotation;
+use halo2_proofs::transcript::{
+    Blake2bRead, Blake2bWrite, Challenge255, TranscriptReadBuffer, TranscriptWriterBuffer,
+};
+use halo2curves::bn256::{Bn256, Fr, G1Affine};
+use rand_core::OsRng;
+
+const K: u32 = 5;
+const N: usize = 1 << K;
+
+/// The rows at which `a * b = c` is enforced.
+const ROWS: usize = 3;
+
+fn advice(column_index: usize) -> ExpressionMid<Fr> {
+    ExpressionMid::Advice(AdviceQueryMid {
+        column_index,
+        rotation: Rotation::cur(),
+        phase: 0,
+    })
+}
+
+/// Returns a circuit with advice columns `a`, `b` and `c`, and a fixed column `q`, that
+/// enforces `q * (a * b - c) = 0`, with `c` at row 0 copied to the instance.
+fn compiled_circuit() -> CompiledCircuitV2<Fr> {
+    let q = ExpressionMid::Fixed(FixedQueryMid {
+        column_index: 0,
+        rotation: Rotation::cur(),
+    });
+    let poly = ExpressionMid::Product(
+        Box::new(q),
+        Box::new(ExpressionMid::Sum(
+            Box::new(ExpressionMid::Product(
+                Box::new(advice(0)),
+                Box::new(advice(1)),
+            )),
+            Box::new(ExpressionMid::Negated(Box::new(advice(2)))),
+        )),
+    );
+
+    let c = ColumnMid {
+        index: 2,
+        column_type: Any::advice(),
+    };
+    let instance = ColumnMid {
+        index: 0,
+        column_type: Any::Instance,
+    };
+
+    let cs = ConstraintSystemV2Backend {
+        num_fixed_columns: 1,
+        num_advice_columns: 3,
+        num_instance_columns: 1,
+        num_challenges: 0,
+        unblinded_advice_columns: vec![],
+        advice_column_phase: vec![0; 3],
+        challenge_phase: vec![],
+        gates: vec![GateV2Backend {
+            name: "mul".to_string(),
+            poly,
+        }],
+        permutation: ArgumentV2 {
+            columns: vec![c, instance],
+        },
+        lookups: vec![],
+        shuffles: vec![],
+        general_column_annotations: HashMap::new(),
+    };
+
+    let mut q = vec![Fr::zero(); N];
+    q[..ROWS].fill(Fr::one());
+    let preprocessing = PreprocessingV2 {
+        permutation: AssemblyMid {
+            copies: vec![(
+                Cell { column: c, row: 0 },
+                Cell {
+                    column: instance,
+                    row: 0,
+                },
+            )],
+        },
+        fixed: vec![q],
+    };
+
+    CompiledCircuitV2 { preprocessing, cs }
+}
+
+/// Returns the witness of the advice columns, with `a = [2, 3, 4]` and `b = [5, 6, 7]`.
+fn witness() -> Vec<Option<Vec<Fr>>> {
+    let column = |values: [u64; ROWS]| {
+        let mut column = vec![Fr::zero(); N];
+        for (cell, value) in column.iter_mut().zip(values) {
+            *cell = Fr::from(value);
+        }
+        Some(column)
+    };
+    vec![column([2, 3, 4]), column([5, 6, 7]), column([10, 18, 28])]
+}
+
+fn prove_and_verify(instance: u64) -> Result<(), Error> {
+    let params = ParamsKZG::<Bn256>::new(K);
+    let circuit = compiled_circuit();
+    let vk = keygen_vk_v2(&params, &circuit).expect("keygen_vk should not fail");
+    let pk = keygen_pk_v2(&params, vk.clone(), &circuit).expect("keygen_pk should not fail");
+
+    let instance = [Fr::from(instance)];
+    let instances: &[&[Fr]] = &[&instance];
+    let mut transcript = Blake2bWrite::<_, G1Affine, Challenge255<_>>::init(vec![]);
+    let mut prover =
+        ProverV2Single::<KZGCommitmentScheme<Bn256>, ProverSHPLONK<'_, Bn256>, _, _, _>::new(
+            &params,
+            &pk,
+            instances,
+            OsRng,
+            &mut transcript,
+        )
+        .unwrap();
+    prover.commit_phase(0, witness()).unwrap();
+    prover.create_proof().unwrap();
+    let proof = transcript.finalize();
+
+    let mut transcript = Blake2bRead::<_, G1Affine, Challenge255<_>>::init(proof.as_slice());
+    let strategy = SingleStrategy::new(&params);
+    verify_proof_single::<KZGCommitmentScheme<Bn256>, VerifierSHPLONK<'_, Bn256>, _, _, _>(
+        &params,
+        &vk,
+        strategy,
+        instances,
+        &mut transcript,
+    )
+    .map(|_| ())
+}
+
+#[test]
+fn compiled_circuit_prove_and_verify() {
+    assert_matches!(prove_and_verify(10), Ok(()));
+    assert_matches!(prove_and_verify(11), Err(_));
+}
+
+#[test]
+fn compiled_circuit_invariants() {
+    let params = ParamsKZG::<Bn256>::new(K);
+
+    let mut circuit = compiled_circuit();
+    circuit.preprocessing.fixed[0].pop();
+    assert_matches!(
+        keygen_vk_v2::<G1Affine, _>(&params, &circuit),
+        Err(Error::Other(_))
+    );
+
+    let mut circuit = compiled_circuit();
+    circuit.preprocessing.fixed.clear();
+    assert_matches!(
+        keygen_vk_v2::<G1Affine, _>(&params, &circuit),
+        Err(Error::Other(_))
+    );
+
+    let mut circuit = compiled_circuit();
+    circuit.preprocessing.permutation.copies[0].1.row = N - 1;
+    assert_matches!(
+        keygen_vk_v2::<G1Affine, _>(&params, &circuit),
+        Err(Error::Other(_))
+    );
+
+    let mut circuit = compiled_circuit();
+    circuit.preprocessing.permutation.copies[0].1.column = ColumnMid {
+        index: 0,
+        column_type: Any::advice(),
+    };
+    assert_matches!(
+        keygen_vk_v2::<G1Affine, _>(&params, &circuit),
+        Err(Error::ColumnNotInPermutation(_))
+    );
+}
@@ -0,0 +1,271 @@
+use halo2_middleware::circuit::Any;
+use halo2_proofs::circuit::{Layouter, SimpleFloorPlanner, Value};
+use halo2_proofs::plonk::{
+    evaluate_constraints, generate_witness, keygen_pk, keygen_vk, Advice, ArgumentValues, Circuit,
+    Column, ConstraintSystem, Error, Fixed, Instance, LookupValues, ProvingKey, Selector,
+};
+use halo2_proofs::poly::commitment::ParamsProver;
+use halo2_proofs::poly::kzg::commitment::ParamsKZG;
+use halo2_proofs::poly::Rotation;
+use halo2curves::bn256::{Bn256, Fr, G1Affine};
+use halo2curves::ff::{Field, PrimeField};
+use rand_chacha::ChaCha20Rng;
+use rand_core::SeedableRng;
+
+const K: u32 = 4;
+const ROWS: u64 = 6;
+
+#[derive(Clone)]
+struct CountConfig {
+    a: Column<Advice>,
+    b: Column<Advice>,
+    table: Column<Fixed>,
+    instance: Column<Instance>,
+    s: Selector,
+}
+
+/// Counts from the public input in `a`, which is looked up in a table holding the same
+/// values, and shuffled into `b`.
+#[derive(Clone, Default)]
+struct CountCircuit;
+
+impl Circuit<Fr> for CountCircuit {
+    type Config = CountConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+    #[cfg(feature = "circuit-params")]
+    type Params = ();
+
+    fn without_witnesses(&self) -> Self {
+        Self
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fr>) -> CountConfig {
+        let a = meta.advice_column();
+        let b = meta.advice_column();
+        let table = meta.fixed_column();
+        let instance = meta.instance_column();
+        let s = meta.selector();
+        meta.enable_equality(a);
+        meta.enable_equality(instance);
+
+        meta.create_gate("count", |meta| {
+            let cur = meta.query_advice(a, Rotation::cur());
+            let next = meta.query_advice(a, Rotation::next());
+            let s = meta.query_selector(s);
+            vec![s * (next - cur - Fr::ONE)]
+        });
+        meta.lookup_any("table", |meta| {
+            let a = meta.query_advice(a, Rotation::cur());
+            let table = meta.query_fixed(table, Rotation::cur());
+            vec![(a, table)]
+        });
+        meta.shuffle("reversed", |meta| {
+            let a = meta.query_advice(a, Rotation::cur());
+            let b = meta.query_advice(b, Rotation::cur());
+            vec![(a, b)]
+        });
+
+        CountConfig {
+            a,
+            b,
+            table,
+            instance,
+            s,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: CountConfig,
+        mut layouter: impl Layouter<Fr>,
+    ) -> Result<(), Error> {
+        let first = layouter.assign_region(
+            || "count",
+            |mut region| {
+                let mut first = None;
+                for row in 0..ROWS {
+                    let offset = row as usize;
+                    if row + 1 < ROWS {
+                        config.s.enable(&mut region, offset)?;
+                    }
+                    let count = Value::known(Fr::from(row + 1));
+                    let reversed = Value::known(Fr::from(ROWS - row));
+                    let cell = region.assign_advice(|| "a", config.a, offset, || count)?;
+                    first.get_or_insert(cell);
+                    region.assign_advice(|| "b", config.b, offset, || reversed)?;
+                    region.assign_fixed(|| "table", config.table, offset, || reversed)?;
+                }
+                Ok(first.expect("a cell was assigned"))
+            },
+        )?;
+        layouter.constrain_instance(first.cell(), config.instance, 0)
+    }
+}
+
+struct Columns {
+    fixed: Vec<Vec<Fr>>,
+    advice: Vec<Vec<Fr>>,
+    instance: Vec<Vec<Fr>>,
+}
+
+fn setup() -> (ProvingKey<G1Affine>, Columns) {
+    let params = ParamsKZG::<Bn256>::new(K);
+    let vk = keygen_vk(&params, &CountCircuit).expect("keygen_vk should not fail");
+    let pk = keygen_pk(&params, vk, &CountCircuit).expect("keygen_pk should not fail");
+
+    let mut instance = vec![Fr::ZERO; 1 << K];
+    instance[0] = Fr::ONE;
+    let witness =
+        generate_witness(K, &CountCircuit, &[&instance[..1]]).expect("witness should be generated");
+    let advice = witness
+        .into_advice()
+        .remove(0)
+        .into_iter()
+        .flatten()
+        .collect();
+    let columns = Columns {
+        fixed: pk.fixed_values().iter().map(|poly| poly.to_vec()).collect(),
+        advice,
+        instance: vec![instance],
+    };
+    (pk, columns)
+}
+
+/// Computes the columns of the arguments of the circuit as the prover does, with the
+/// blinding rows left at zero. The lookup input and table hold the same values, so they
+/// are their own permutations.
+fn argument_values(
+    pk: &ProvingKey<G1Affine>,
+    columns: &Columns,
+    beta: Fr,
+    gamma: Fr,
+) -> ArgumentValues<Fr> {
+    let cs = pk.get_vk().cs();
+    let n = 1 << K;
+    let last_row = n - cs.blinding_factors() - 1;
+    let omega = pk.get_vk().get_domain().get_omega();
+    let (a, b, table) = (&columns.advice[0], &columns.advice[1], &columns.fixed[0]);
+
+    // The grand product of `numerator / denominator` over the usable rows, from `start`.
+    let product = |start: Fr, ratio: &dyn Fn(usize) -> (Fr, Fr)| {
+        let mut product = vec![Fr::ZERO; n];
+        product[0] = start;
+        for row in 0..last_row {
+            let (numerator, denominator) = ratio(row);
+            product[row + 1] = product[row] * numerator * denominator.invert().unwrap();
+        }
+        product
+    };
+
+    let permutation_columns = cs.permutation().get_columns();
+    let permutation_sigmas: Vec<Vec<Fr>> = pk
+        .permutation_values()
+        .iter()
+        .map(|poly| poly.to_vec())
+        .collect();
+    let values: Vec<&Vec<Fr>> = permutation_columns
+        .iter()
+        .map(|column| match column.column_type() {
+            Any::Advice(_) => &columns.advice[column.index()],
+            Any::Fixed => &columns.fixed[column.index()],
+            Any::Instance => &columns.instance[column.index()],
+        })
+        .collect();
+    let mut permutation_products: Vec<Vec<Fr>> = vec![];
+    let mut delta_start = Fr::ONE;
+    for (values, sigmas) in values
+        .chunks(cs.degree() - 2)
+        .zip(permutation_sigmas.chunks(cs.degree() - 2))
+    {
+        let start = permutation_products
+            .last()
+            .map_or(Fr::ONE, |product| product[last_row]);
+        permutation_products.push(product(start, &|row| {
+            let mut delta = delta_start;
+            let (mut numerator, mut denominator) = (Fr::ONE, Fr::ONE);
+            for (values, sigma) in values.iter().zip(sigmas) {
+                let x = omega.pow_vartime([row as u64]);
+                numerator *= values[row] + beta * delta * x + gamma;
+                denominator *= values[row] + beta * sigma[row] + gamma;
+                delta *= Fr::DELTA;
+            }
+            (numerator, denominator)
+        }));
+        delta_start *= Fr::DELTA.pow_vartime([values.len() as u64]);
+    }
+
+    ArgumentValues {
+        theta: Fr::from(7),
+        beta,
+        gamma,
+        permutation_sigmas,
+        permutation_products,
+        lookups: vec![LookupValues {
+            product: product(Fr::ONE, &|row| (table[row] + gamma, a[row] + gamma)),
+            permuted_input: a.clone(),
+            permuted_table: a.clone(),
+        }],
+        shuffle_products: vec![product(Fr::ONE, &|row| (a[row] + gamma, b[row] + gamma))],
+    }
+}
+
+fn evaluate(
+    pk: &ProvingKey<G1Affine>,
+    columns: &Columns,
+    arguments: &ArgumentValues<Fr>,
+    rows: std::ops::Range<usize>,
+) -> Vec<Fr> {
+    evaluate_constraints::<G1Affine>(
+        pk.get_vk().cs(),
+        &columns.fixed,
+        &columns.advice,
+        &columns.instance,
+        &[],
+        arguments,
+        Fr::from(11),
+        rows,
+    )
+}
+
+#[test]
+fn constraints_vanish_over_the_rows_of_a_valid_witness() {
+    let (pk, columns) = setup();
+    let mut rng = ChaCha20Rng::seed_from_u64(0);
+    let arguments = argument_values(&pk, &columns, Fr::random(&mut rng), Fr::random(&mut rng));
+
+    // The numerator of h(X) vanishes over the rows, which is what makes it divisible by
+    // the vanishing polynomial.
+    let evaluations = evaluate(&pk, &columns, &arguments, 0..1 << K);
+    assert!(evaluations
+        .iter()
+        .all(|evaluation| bool::from(evaluation.is_zero())));
+    assert_eq!(
+        evaluate(&pk, &columns, &arguments, 3..7),
+        &evaluations[3..7]
+    );
+}
+
+#[test]
+fn constraints_catch_an_invalid_witness() {
+    let (pk, mut columns) = setup();
+    let mut rng = ChaCha20Rng::seed_from_u64(0);
+    let arguments = argument_values(&pk, &columns, Fr::random(&mut rng), Fr::random(&mut rng));
+
+    // The count gate now fails at rows 1 and 2, and the arguments at row 2.
+    columns.advice[0][2] += Fr::ONE;
+    let failing: Vec<usize> = evaluate(&pk, &columns, &arguments, 0..1 << K)
+        .iter()
+        .enumerate()
+        .filter(|(_, evaluation)| !bool::from(evaluation.is_zero()))
+        .map(|(row, _)| row)
+        .collect();
+    assert_eq!(failing, [1, 2]);
+
+    // So does a copy that doesn't match the instance.
+    let (pk, mut columns) = setup();
+    let arguments = argument_values(&pk, &columns, Fr::random(&mut rng), Fr::random(&mut rng));
+    columns.instance[0][0] = Fr::from(2);
+    assert!(evaluate(&pk, &columns, &arguments, 0..1 << K)
+        .iter()
+        .any(|evaluation| !bool::from(evaluation.is_zero())));
+}
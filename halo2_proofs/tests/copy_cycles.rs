@@ -0,0 +1,162 @@
+use halo2_frontend::circuit::compile_circuit;
+use halo2_middleware::circuit::{Any, ColumnMid};
+use halo2_proofs::circuit::{Layouter, SimpleFloorPlanner, Value};
+use halo2_proofs::dev::{CycleStats, MockProver};
+use halo2_proofs::plonk::{
+    Advice, Circuit, Column, ConstraintSystem, Error, Instance, PermutationAssembly,
+};
+use halo2curves::bn256::Fr;
+
+const K: u32 = 4;
+
+#[derive(Clone)]
+struct CopyConfig {
+    a: Column<Advice>,
+    b: Column<Advice>,
+    instance: Column<Instance>,
+}
+
+/// Assigns `a` on rows 0 to 3 and `b` on rows 0 and 1, copies `a[0] = b[0] = a[2]` (once
+/// redundantly) and `a[1] = b[1]`, and exposes `a[3]` as the public input.
+#[derive(Clone, Default)]
+struct CopyCircuit;
+
+impl Circuit<Fr> for CopyCircuit {
+    type Config = CopyConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+    #[cfg(feature = "circuit-params")]
+    type Params = ();
+
+    fn without_witnesses(&self) -> Self {
+        Self
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fr>) -> CopyConfig {
+        let a = meta.advice_column();
+        let b = meta.advice_column();
+        let instance = meta.instance_column();
+        meta.enable_equality(a);
+        meta.enable_equality(b);
+        meta.enable_equality(instance);
+        CopyConfig { a, b, instance }
+    }
+
+    fn synthesize(&self, config: CopyConfig, mut layouter: impl Layouter<Fr>) -> Result<(), Error> {
+        let last = layouter.assign_region(
+            || "copies",
+            |mut region| {
+                let value = |v: u64| Value::known(Fr::from(v));
+                let a = (0..4)
+                    .map(|row| {
+                        let v = if row % 2 == 0 { 1 } else { row as u64 };
+                        region.assign_advice(|| "a", config.a, row, || value(v))
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                let b0 = region.assign_advice(|| "b", config.b, 0, || value(1))?;
+                let b1 = region.assign_advice(|| "b", config.b, 1, || value(1))?;
+                region.constrain_equal(a[0].cell(), b0.cell())?;
+                region.constrain_equal(b0.cell(), a[2].cell())?;
+                region.constrain_equal(a[2].cell(), a[0].cell())?;
+                region.constrain_equal(a[1].cell(), b1.cell())?;
+                Ok(a[3].clone())
+            },
+        )?;
+        layouter.constrain_instance(last.cell(), config.instance, 0)
+    }
+}
+
+fn a(row: usize) -> (ColumnMid, usize) {
+    (
+        ColumnMid {
+            index: 0,
+            column_type: Any::advice(),
+        },
+        row,
+    )
+}
+
+fn b(row: usize) -> (ColumnMid, usize) {
+    (
+        ColumnMid {
+            index: 1,
+            column_type: Any::advice(),
+        },
+        row,
+    )
+}
+
+fn instance(row: usize) -> (ColumnMid, usize) {
+    (
+        ColumnMid {
+            index: 0,
+            column_type: Any::Instance,
+        },
+        row,
+    )
+}
+
+/// Sorts the cells of each class, and the classes, to compare them regardless of order.
+fn normalize(mut classes: Vec<Vec<(ColumnMid, usize)>>) -> Vec<Vec<(ColumnMid, usize)>> {
+    let key = |(column, row): &(ColumnMid, usize)| (column.column_type, column.index, *row);
+    for class in classes.iter_mut() {
+        class.sort_by_key(key);
+    }
+    classes.sort_by_key(|class| key(&class[0]));
+    classes
+}
+
+fn expected_classes() -> Vec<Vec<(ColumnMid, usize)>> {
+    normalize(vec![
+        vec![a(0), a(2), b(0)],
+        vec![a(1), b(1)],
+        vec![a(3), instance(0)],
+    ])
+}
+
+const EXPECTED_STATS: CycleStats = CycleStats {
+    cycles: 3,
+    largest_cycle: 3,
+    copies: 5,
+};
+
+#[test]
+fn mock_prover_exposes_copy_cycles() {
+    let prover = MockProver::run(K, &CopyCircuit, vec![vec![Fr::from(3)]]).unwrap();
+    prover.assert_satisfied();
+
+    let cycles = prover.copy_cycles();
+    assert_eq!(normalize(cycles.equality_classes()), expected_classes());
+    assert_eq!(cycles.cycles().count(), 3);
+    assert_eq!(cycles.stats(), EXPECTED_STATS);
+
+    assert!(cycles.contains(a(0), a(2)));
+    assert!(cycles.contains(b(0), a(0)));
+    assert!(cycles.contains(a(3), instance(0)));
+    assert!(!cycles.contains(a(0), a(1)));
+    assert!(!cycles.contains(b(1), instance(0)));
+    // Cells that are not copied, or not in the permutation, are only equal to themselves.
+    assert!(cycles.contains(b(5), b(5)));
+    assert!(!cycles.contains(b(5), b(6)));
+    assert!(!cycles.contains(instance(1 << K), instance(0)));
+}
+
+#[test]
+fn permutation_assembly_exposes_copy_cycles() {
+    let (compiled, _, _) = compile_circuit(K, &CopyCircuit, false).unwrap();
+    let assembly = PermutationAssembly::new_from_assembly_mid(
+        1 << K,
+        &compiled.cs.permutation,
+        &compiled.preprocessing.permutation,
+    )
+    .unwrap();
+
+    assert_eq!(normalize(assembly.equality_classes()), expected_classes());
+    assert_eq!(assembly.cycles().count(), 3);
+    assert_eq!(assembly.cycle_stats(), EXPECTED_STATS);
+
+    assert!(assembly.contains(a(2), b(0)));
+    assert!(assembly.contains(instance(0), a(3)));
+    assert!(!assembly.contains(a(1), a(3)));
+    assert!(assembly.contains(a(7), a(7)));
+    assert!(!assembly.contains(a(7), b(7)));
+}
@@ -0,0 +1,171 @@
+//! Copy constraints between advice columns of different phases are supported, as the
+//! permutation argument is committed after every phase, and can be rejected with
+//! `MockProver::check_copy_phases`.
+
+use assert_matches::assert_matches;
+use halo2_frontend::circuit::compile_circuit;
+use halo2_middleware::circuit::{Any, ColumnMid};
+use halo2_proofs::circuit::{Layouter, SimpleFloorPlanner, Value};
+use halo2_proofs::dev::MockProver;
+use halo2_proofs::plonk::{
+    create_proof, keygen_pk, keygen_vk, keygen_vk_v2, verify_proof, Advice, Circuit, Column,
+    ConstraintSystem, Error, FirstPhase, Instance, SecondPhase,
+};
+use halo2_proofs::poly::commitment::ParamsProver;
+use halo2_proofs::poly::kzg::commitment::{KZGCommitmentScheme, ParamsKZG};
+use halo2_proofs::poly::kzg::multiopen::{ProverSHPLONK, VerifierSHPLONK};
+use halo2_proofs::poly::kzg::strategy::SingleStrategy;
+use halo2_proofs::transcript::{
+    Blake2bRead, Blake2bWrite, Challenge255, TranscriptReadBuffer, TranscriptWriterBuffer,
+};
+use halo2curves::bn256::{Bn256, Fr, G1Affine};
+use halo2curves::ff::Field;
+use rand_core::OsRng;
+
+const K: u32 = 4;
+
+#[derive(Clone)]
+struct CopyConfig {
+    a: Column<Advice>,
+    b: Column<Advice>,
+    c: Column<Advice>,
+    instance: Column<Instance>,
+}
+
+/// Copies `a[0] = 1` to `b[0]`, with `a` in the first phase, and `b` in the first phase too
+/// if `SAME_PHASE`, or in the second phase otherwise. `c`, in the second phase, is copied to
+/// the instance.
+#[derive(Clone, Default)]
+struct CopyCircuit<const SAME_PHASE: bool> {
+    b: Value<Fr>,
+}
+
+impl<const SAME_PHASE: bool> Circuit<Fr> for CopyCircuit<SAME_PHASE> {
+    type Config = CopyConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+    #[cfg(feature = "circuit-params")]
+    type Params = ();
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fr>) -> CopyConfig {
+        let a = meta.advice_column_in(FirstPhase);
+        let b = if SAME_PHASE {
+            meta.advice_column_in(FirstPhase)
+        } else {
+            meta.advice_column_in(SecondPhase)
+        };
+        let c = meta.advice_column_in(SecondPhase);
+        let instance = meta.instance_column();
+        meta.enable_equality(a);
+        meta.enable_equality(b);
+        meta.enable_equality(c);
+        meta.enable_equality(instance);
+        CopyConfig { a, b, c, instance }
+    }
+
+    fn synthesize(&self, config: CopyConfig, mut layouter: impl Layouter<Fr>) -> Result<(), Error> {
+        let c = layouter.assign_region(
+            || "copy",
+            |mut region| {
+                let a = region.assign_advice(|| "a", config.a, 0, || Value::known(Fr::ONE))?;
+                let b = region.assign_advice(|| "b", config.b, 0, || self.b)?;
+                region.constrain_equal(a.cell(), b.cell())?;
+                region.assign_advice(|| "c", config.c, 0, || Value::known(Fr::ONE))
+            },
+        )?;
+        layouter.constrain_instance(c.cell(), config.instance, 0)
+    }
+}
+
+#[test]
+fn copies_across_phases_are_supported() {
+    let circuit = CopyCircuit::<false> {
+        b: Value::known(Fr::ONE),
+    };
+    let prover = MockProver::run(K, &circuit, vec![vec![Fr::ONE]]).unwrap();
+    prover.assert_satisfied();
+
+    let params = ParamsKZG::<Bn256>::new(K);
+    let vk = keygen_vk(&params, &circuit).expect("keygen_vk should not fail");
+    let pk = keygen_pk(&params, vk, &circuit).expect("keygen_pk should not fail");
+    let mut transcript = Blake2bWrite::<_, _, Challenge255<_>>::init(vec![]);
+    create_proof::<KZGCommitmentScheme<Bn256>, ProverSHPLONK<_>, _, _, _, _>(
+        &params,
+        &pk,
+        &[circuit],
+        &[&[&[Fr::ONE]]],
+        OsRng,
+        &mut transcript,
+    )
+    .expect("prover should not fail");
+    let proof = transcript.finalize();
+
+    let mut transcript = Blake2bRead::<_, _, Challenge255<_>>::init(&proof[..]);
+    verify_proof::<_, VerifierSHPLONK<_>, _, _, _>(
+        &params,
+        pk.get_vk(),
+        SingleStrategy::new(&params),
+        &[&[&[Fr::ONE]]],
+        &mut transcript,
+    )
+    .expect("proof should verify");
+}
+
+#[test]
+fn copies_across_phases_are_checked() {
+    // The cell of the second phase doesn't hold the value of the first one.
+    let circuit = CopyCircuit::<false> {
+        b: Value::known(Fr::from(2)),
+    };
+    let prover = MockProver::run(K, &circuit, vec![vec![Fr::ONE]]).unwrap();
+    assert!(prover.verify().is_err());
+}
+
+#[test]
+fn copies_across_phases_can_be_rejected() {
+    let prover = MockProver::run(
+        K,
+        &CopyCircuit::<true> {
+            b: Value::known(Fr::ONE),
+        },
+        vec![vec![Fr::ONE]],
+    )
+    .unwrap();
+    assert_matches!(prover.check_copy_phases(), Ok(()));
+
+    let prover = MockProver::run(
+        K,
+        &CopyCircuit::<false> {
+            b: Value::known(Fr::ONE),
+        },
+        vec![vec![Fr::ONE]],
+    )
+    .unwrap();
+    let Err(Error::CopyPhaseMismatch(left, right)) = prover.check_copy_phases() else {
+        panic!("the copy across phases should be rejected");
+    };
+    assert_eq!(*left.column_type(), Any::advice_in(0));
+    assert_eq!(*right.column_type(), Any::advice_in(1));
+    let message = Error::CopyPhaseMismatch(left, right).to_string();
+    assert!(message.contains("in phase 0"));
+    assert!(message.contains("in phase 1"));
+}
+
+#[test]
+fn compiled_copies_across_phases_are_supported() {
+    // Compiles the circuit without the copy across phases, and then adds it.
+    let circuit = CopyCircuit::<true> {
+        b: Value::known(Fr::ONE),
+    };
+    let (mut compiled, _, _) = compile_circuit(K, &circuit, false).unwrap();
+    compiled.preprocessing.permutation.copies[0].1.column = ColumnMid {
+        index: 2,
+        column_type: Any::advice_in(1),
+    };
+
+    let params = ParamsKZG::<Bn256>::new(K);
+    keygen_vk_v2::<G1Affine, _>(&params, &compiled).expect("keygen_vk_v2 should not fail");
+}
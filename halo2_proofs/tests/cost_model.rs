@@ -0,0 +1,175 @@
+#![cfg(feature = "cost-estimator")]
+
+use halo2_proofs::circuit::{Layouter, SimpleFloorPlanner, Value};
+use halo2_proofs::dev::cost_model::{
+    estimate, CommitmentScheme as CommitmentSchemeKind, CostEstimate,
+};
+use halo2_proofs::dev::MockProver;
+use halo2_proofs::plonk::{
+    create_proof, keygen_pk, keygen_vk, Advice, Circuit, Column, ConstraintSystem, Error, Instance,
+    Selector, TableColumn,
+};
+use halo2_proofs::poly::commitment::{CommitmentScheme, ParamsProver, Prover};
+use halo2_proofs::poly::Rotation;
+use halo2_proofs::transcript::{Blake2bWrite, Challenge255, TranscriptWriterBuffer};
+use halo2curves::bn256::{Bn256, Fr};
+use halo2curves::pasta::{EqAffine, Fp};
+use rand_core::OsRng;
+
+const K: u32 = 6;
+
+#[derive(Clone)]
+struct TestConfig {
+    a: Column<Advice>,
+    b: Column<Advice>,
+    c: Column<Advice>,
+    instance: Column<Instance>,
+    s_mul: Selector,
+    table: TableColumn,
+}
+
+#[derive(Clone, Default)]
+struct TestCircuit;
+
+impl<F: ff::PrimeField> Circuit<F> for TestCircuit {
+    type Config = TestConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+    #[cfg(feature = "circuit-params")]
+    type Params = ();
+
+    fn without_witnesses(&self) -> Self {
+        Self
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> TestConfig {
+        let a = meta.advice_column();
+        let b = meta.advice_column();
+        let c = meta.advice_column();
+        let instance = meta.instance_column();
+        let s_mul = meta.selector();
+        let table = meta.lookup_table_column();
+
+        meta.enable_equality(a);
+        meta.enable_equality(c);
+        meta.enable_equality(instance);
+
+        meta.create_gate("mul", |meta| {
+            let a = meta.query_advice(a, Rotation::cur());
+            let b = meta.query_advice(b, Rotation::cur());
+            let c = meta.query_advice(c, Rotation::next());
+            let s_mul = meta.query_selector(s_mul);
+            vec![s_mul * (a * b - c)]
+        });
+
+        meta.lookup("small b", |meta| {
+            let b = meta.query_advice(b, Rotation::cur());
+            vec![(b, table)]
+        });
+
+        TestConfig {
+            a,
+            b,
+            c,
+            instance,
+            s_mul,
+            table,
+        }
+    }
+
+    fn synthesize(&self, config: TestConfig, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        layouter.assign_table(
+            || "table",
+            |mut table| {
+                for i in 0..8 {
+                    table.assign_cell(
+                        || "value",
+                        config.table,
+                        i,
+                        || Value::known(F::from(i as u64)),
+                    )?;
+                }
+                Ok(())
+            },
+        )?;
+
+        let product = layouter.assign_region(
+            || "mul",
+            |mut region| {
+                config.s_mul.enable(&mut region, 0)?;
+                region.assign_advice_from_instance(|| "a", config.instance, 0, config.a, 0)?;
+                region.assign_advice(|| "b", config.b, 0, || Value::known(F::from(3)))?;
+                region.assign_advice(|| "b", config.b, 1, || Value::known(F::ZERO))?;
+                region.assign_advice(|| "c", config.c, 1, || Value::known(F::from(6)))
+            },
+        )?;
+
+        layouter.constrain_instance(product.cell(), config.instance, 1)
+    }
+}
+
+fn proof_size<'params, Scheme, P>(params: &'params Scheme::ParamsProver) -> usize
+where
+    Scheme: CommitmentScheme,
+    Scheme::Scalar: Ord + ff::WithSmallOrderMulGroup<3> + ff::FromUniformBytes<64>,
+    P: Prover<'params, Scheme>,
+{
+    let instance = [Scheme::Scalar::from(2), Scheme::Scalar::from(6)];
+    let vk = keygen_vk(params, &TestCircuit).unwrap();
+    let pk = keygen_pk(params, vk, &TestCircuit).unwrap();
+
+    let mut transcript = Blake2bWrite::<_, _, Challenge255<_>>::init(vec![]);
+    create_proof::<Scheme, P, _, _, _, _>(
+        params,
+        &pk,
+        &[TestCircuit],
+        &[&[&instance]],
+        OsRng,
+        &mut transcript,
+    )
+    .unwrap();
+    transcript.finalize().len()
+}
+
+fn assert_close(estimated: usize, actual: usize) {
+    // Allow for a 5% difference between the estimate and the actual proof size.
+    let tolerance = actual / 20;
+    assert!(
+        estimated.abs_diff(actual) <= tolerance,
+        "estimated {estimated} bytes, actual proof is {actual} bytes"
+    );
+}
+
+#[test]
+fn estimate_matches_proof_size() {
+    use halo2_proofs::poly::ipa::commitment::{IPACommitmentScheme, ParamsIPA};
+    use halo2_proofs::poly::ipa::multiopen::ProverIPA;
+    use halo2_proofs::poly::kzg::commitment::{KZGCommitmentScheme, ParamsKZG};
+    use halo2_proofs::poly::kzg::multiopen::{ProverGWC, ProverSHPLONK};
+
+    let instance = vec![vec![Fr::from(2), Fr::from(6)]];
+    let prover = MockProver::run(K, &TestCircuit, instance).unwrap();
+    assert_eq!(prover.verify(), Ok(()));
+
+    let params = ParamsKZG::<Bn256>::new(K);
+    let actual = proof_size::<KZGCommitmentScheme<_>, ProverGWC<_>>(&params);
+    let estimated = estimate(prover.cs(), K, CommitmentSchemeKind::KZGGWC);
+    assert_close(estimated.proof_bytes, actual);
+
+    let actual = proof_size::<KZGCommitmentScheme<_>, ProverSHPLONK<_>>(&params);
+    let estimated = estimate(prover.cs(), K, CommitmentSchemeKind::KZGSHPLONK);
+    assert_close(estimated.proof_bytes, actual);
+
+    let instance = vec![vec![Fp::from(2), Fp::from(6)]];
+    let prover = MockProver::run(K, &TestCircuit, instance).unwrap();
+    let params = ParamsIPA::<EqAffine>::new(K);
+    let actual = proof_size::<IPACommitmentScheme<_>, ProverIPA<_>>(&params);
+    let estimated = estimate(prover.cs(), K, CommitmentSchemeKind::IPA);
+    assert_close(estimated.proof_bytes, actual);
+
+    // The estimate round-trips through serde.
+    let json = serde_json::to_string(&estimated).unwrap();
+    assert_eq!(
+        serde_json::from_str::<CostEstimate>(&json).ok(),
+        Some(estimated)
+    );
+}
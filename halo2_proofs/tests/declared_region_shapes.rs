@@ -0,0 +1,212 @@
+//! Circuits that declare the shapes of their regions are synthesized once by the
+//! `V1SinglePass` floor planner, and laid out as `V1` lays them out after measuring them.
+
+use std::marker::PhantomData;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use assert_matches::assert_matches;
+use halo2_middleware::circuit::Any;
+use halo2_proofs::circuit::floor_planner::{V1SinglePass, V1};
+use halo2_proofs::circuit::layouter::{RegionColumn, RegionShape};
+use halo2_proofs::circuit::{Layouter, Value};
+use halo2_proofs::dev::MockProver;
+use halo2_proofs::plonk::{
+    Advice, Circuit, Column, ConstraintSystem, Error, Fixed, FloorPlanner, Selector,
+};
+use halo2_proofs::poly::Rotation;
+use halo2curves::pasta::Fp;
+
+const K: u32 = 5;
+
+#[derive(Clone)]
+struct ShapedConfig {
+    a: Column<Advice>,
+    b: Column<Advice>,
+    f: Column<Fixed>,
+    s: Selector,
+}
+
+/// How a circuit departs from the shapes it declares.
+#[derive(Clone, Copy, Debug)]
+enum Mismatch {
+    None,
+    /// Assigns a row beyond the declared rows of the `add` region.
+    ExtraRow,
+    /// Assigns a column that is not in the declared shape of the `inputs` region.
+    ExtraColumn,
+    /// Assigns a region whose shape is not declared.
+    ExtraRegion,
+}
+
+/// Enforces `b = a + f` over the three rows of the `add` region, then assigns two rows of
+/// the `inputs` region.
+struct ShapedCircuit<P> {
+    mismatch: Mismatch,
+    /// Counts the calls to `synthesize`, shared with the circuits without witnesses.
+    synthesized: Arc<AtomicUsize>,
+    _marker: PhantomData<P>,
+}
+
+// Derived `Clone` would require `P: Clone`.
+impl<P> Clone for ShapedCircuit<P> {
+    fn clone(&self) -> Self {
+        ShapedCircuit {
+            mismatch: self.mismatch,
+            synthesized: self.synthesized.clone(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<P: FloorPlanner> Circuit<Fp> for ShapedCircuit<P> {
+    type Config = ShapedConfig;
+    type FloorPlanner = P;
+    #[cfg(feature = "circuit-params")]
+    type Params = ();
+
+    fn without_witnesses(&self) -> Self {
+        self.clone()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> ShapedConfig {
+        let a = meta.advice_column();
+        let b = meta.advice_column();
+        let f = meta.fixed_column();
+        let s = meta.selector();
+        meta.create_gate("add", |meta| {
+            let s = meta.query_selector(s);
+            let a = meta.query_advice(a, Rotation::cur());
+            let b = meta.query_advice(b, Rotation::cur());
+            let f = meta.query_fixed(f, Rotation::cur());
+            vec![s * (b - a - f)]
+        });
+        ShapedConfig { a, b, f, s }
+    }
+
+    fn region_shapes(&self, config: &ShapedConfig) -> Option<Vec<RegionShape>> {
+        let column = |column: Column<Any>| RegionColumn::from(column);
+        Some(vec![
+            RegionShape::declare(
+                0.into(),
+                [
+                    column(config.a.into()),
+                    column(config.b.into()),
+                    column(config.f.into()),
+                    config.s.into(),
+                ],
+                3,
+            ),
+            RegionShape::declare(1.into(), [column(config.a.into())], 2),
+        ])
+    }
+
+    fn synthesize(
+        &self,
+        config: ShapedConfig,
+        mut layouter: impl Layouter<Fp>,
+    ) -> Result<(), Error> {
+        self.synthesized.fetch_add(1, Ordering::SeqCst);
+        layouter.assign_region(
+            || "add",
+            |mut region| {
+                for row in 0..3 {
+                    config.s.enable(&mut region, row)?;
+                    let a = Fp::from(row as u64);
+                    let f = Fp::from(10);
+                    region.assign_advice(|| "a", config.a, row, || Value::known(a))?;
+                    region.assign_fixed(|| "f", config.f, row, || Value::known(f))?;
+                    region.assign_advice(|| "b", config.b, row, || Value::known(a + f))?;
+                }
+                if let Mismatch::ExtraRow = self.mismatch {
+                    region.assign_advice(|| "a", config.a, 3, || Value::known(Fp::from(1)))?;
+                }
+                Ok(())
+            },
+        )?;
+        layouter.assign_region(
+            || "inputs",
+            |mut region| {
+                for row in 0..2 {
+                    region.assign_advice(|| "a", config.a, row, || Value::known(Fp::from(1)))?;
+                }
+                if let Mismatch::ExtraColumn = self.mismatch {
+                    region.assign_advice(|| "b", config.b, 0, || Value::known(Fp::from(1)))?;
+                }
+                Ok(())
+            },
+        )?;
+        if let Mismatch::ExtraRegion = self.mismatch {
+            layouter.assign_region(
+                || "extra",
+                |mut region| {
+                    region.assign_advice(|| "a", config.a, 0, || Value::known(Fp::from(1)))?;
+                    Ok(())
+                },
+            )?;
+        }
+        Ok(())
+    }
+}
+
+fn run<P: FloorPlanner>(mismatch: Mismatch) -> (Result<MockProver<Fp>, Error>, usize) {
+    let circuit = ShapedCircuit::<P> {
+        mismatch,
+        synthesized: Arc::default(),
+        _marker: PhantomData,
+    };
+    let prover = MockProver::run(K, &circuit, vec![]);
+    (prover, circuit.synthesized.load(Ordering::SeqCst))
+}
+
+#[test]
+fn declared_shapes_skip_measurement() {
+    let (v1, v1_passes) = run::<V1>(Mismatch::None);
+    let (single, single_passes) = run::<V1SinglePass>(Mismatch::None);
+    let (v1, single) = (v1.unwrap(), single.unwrap());
+    assert_eq!(v1_passes, 2);
+    assert_eq!(single_passes, 1);
+
+    single.assert_satisfied();
+    assert_eq!(single.advice(), v1.advice());
+    assert_eq!(single.fixed(), v1.fixed());
+    assert_eq!(single.selectors(), v1.selectors());
+}
+
+#[test]
+fn assignments_outside_declared_shapes_are_reported() {
+    let (error, _) = run::<V1SinglePass>(Mismatch::ExtraRow);
+    let error = error.unwrap_err();
+    assert_matches!(
+        &error,
+        Error::RegionShapeExceeded {
+            region,
+            column: RegionColumn::Column(column),
+            offset: 3,
+            row_count: 3,
+        } if region == "add" && *column == Column::new(0, Any::advice())
+    );
+    assert!(error.to_string().ends_with("the declared shape has 3 rows"));
+
+    let (error, _) = run::<V1SinglePass>(Mismatch::ExtraColumn);
+    let error = error.unwrap_err();
+    assert_matches!(
+        &error,
+        Error::RegionShapeExceeded {
+            region,
+            column: RegionColumn::Column(column),
+            offset: 0,
+            row_count: 2,
+        } if region == "inputs" && *column == Column::new(1, Any::advice())
+    );
+    assert!(error
+        .to_string()
+        .ends_with("the declared shape does not include the column"));
+
+    let (error, _) = run::<V1SinglePass>(Mismatch::ExtraRegion);
+    assert_matches!(error.unwrap_err(), Error::Other(message) if message.contains("\"extra\""));
+
+    // Circuits planned by measurement are not checked against their declared shapes.
+    let (prover, _) = run::<V1>(Mismatch::ExtraRow);
+    assert!(prover.is_ok());
+}
@@ -0,0 +1,272 @@
+mod common;
+
+use common::{setup, MulCircuit};
+use ff::Field;
+use halo2_proofs::circuit::Value;
+use halo2_proofs::plonk::{
+    create_proof, create_proof_with_mode, keygen_pk, keygen_vk, verify_proof, Error, ProvingKey,
+    ProvingMode,
+};
+use halo2_proofs::poly::kzg::commitment::{KZGCommitmentScheme, ParamsKZG};
+use halo2_proofs::poly::kzg::multiopen::{ProverSHPLONK, VerifierSHPLONK};
+use halo2_proofs::poly::kzg::strategy::SingleStrategy;
+use halo2_proofs::transcript::{
+    Blake2bRead, Blake2bWrite, Challenge255, TranscriptReadBuffer, TranscriptWriterBuffer,
+};
+use halo2curves::bn256::{Bn256, Fr, G1Affine};
+use rand_chacha::ChaCha20Rng;
+use rand_core::{RngCore, SeedableRng};
+use std::sync::{Arc, Mutex};
+
+const K: u32 = 4;
+
+/// The BLAKE2b hash of the proof of `2 * 3 = 6` with [`golden_setup`] and
+/// `ProvingMode::Deterministic([1; 32])`. Any change of the proof format, the transcript or
+/// the prover's randomness changes it.
+const GOLDEN_PROOF_HASH: &str = "";
+
+/// Returns KZG parameters for `k` generated from a fixed seed, and the proving key of
+/// [`MulCircuit`] for them, so that proofs are reproducible across runs.
+fn golden_setup(k: u32) -> (ParamsKZG<Bn256>, ProvingKey<G1Affine>) {
+    let params = ParamsKZG::<Bn256>::setup(k, ChaCha20Rng::seed_from_u64(0));
+    let vk = keygen_vk(&params, &MulCircuit::default()).expect("keygen_vk should not fail");
+    let pk = keygen_pk(&params, vk, &MulCircuit::default()).expect("keygen_pk should not fail");
+    (params, pk)
+}
+
+/// Returns the circuit and the instance column proving the knowledge of `a` and `b`.
+fn statement(a: u64, b: u64) -> (MulCircuit, Vec<Fr>) {
+    let circuit = MulCircuit {
+        a: Value::known(Fr::from(a)),
+        b: Value::known(Fr::from(b)),
+    };
+    (circuit, vec![Fr::from(a * b)])
+}
+
+fn try_prove_with_mode(
+    params: &ParamsKZG<Bn256>,
+    pk: &ProvingKey<G1Affine>,
+    circuit: MulCircuit,
+    instance: &[Fr],
+    mode: ProvingMode,
+) -> Result<Vec<u8>, Error> {
+    create_proof_with_mode::<
+        KZGCommitmentScheme<Bn256>,
+        ProverSHPLONK<_>,
+        VerifierSHPLONK<_>,
+        _,
+        Blake2bWrite<_, _, Challenge255<_>>,
+        Blake2bRead<_, _, Challenge255<_>>,
+        SingleStrategy<_>,
+        _,
+    >(params, pk, &[circuit], &[&[instance]], mode)
+}
+
+fn prove_with_mode(
+    params: &ParamsKZG<Bn256>,
+    pk: &ProvingKey<G1Affine>,
+    mode: ProvingMode,
+) -> Vec<u8> {
+    let (circuit, instance) = statement(2, 3);
+    try_prove_with_mode(params, pk, circuit, &instance, mode).expect("prover should not fail")
+}
+
+fn verify(params: &ParamsKZG<Bn256>, pk: &ProvingKey<G1Affine>, proof: &[u8]) {
+    let mut transcript = Blake2bRead::<_, _, Challenge255<_>>::init(proof);
+    let strategy = SingleStrategy::new(params);
+    verify_proof::<_, VerifierSHPLONK<_>, _, _, _>(
+        params,
+        pk.get_vk(),
+        strategy,
+        &[&[&[Fr::from(6)]]],
+        &mut transcript,
+    )
+    .expect("proof should verify");
+}
+
+/// An RNG which records the number of bytes of each call.
+#[derive(Clone)]
+struct CountingRng {
+    rng: ChaCha20Rng,
+    calls: Arc<Mutex<Vec<usize>>>,
+}
+
+impl CountingRng {
+    fn new(seed: u64) -> Self {
+        CountingRng {
+            rng: ChaCha20Rng::seed_from_u64(seed),
+            calls: Arc::default(),
+        }
+    }
+
+    fn calls(&self) -> Vec<usize> {
+        self.calls.lock().unwrap().clone()
+    }
+
+    fn record(&self, len: usize) {
+        self.calls.lock().unwrap().push(len);
+    }
+}
+
+impl RngCore for CountingRng {
+    fn next_u32(&mut self) -> u32 {
+        self.record(4);
+        self.rng.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.record(8);
+        self.rng.next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.record(dest.len());
+        self.rng.fill_bytes(dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand_core::Error> {
+        self.record(dest.len());
+        self.rng.try_fill_bytes(dest)
+    }
+}
+
+/// Returns the calls made to the RNG when proving `a * b = a * b`.
+fn rng_calls(params: &ParamsKZG<Bn256>, pk: &ProvingKey<G1Affine>, a: u64, b: u64) -> Vec<usize> {
+    let (circuit, instance) = statement(a, b);
+    let rng = CountingRng::new(0);
+    let mut transcript = Blake2bWrite::<_, _, Challenge255<_>>::init(vec![]);
+    create_proof::<KZGCommitmentScheme<Bn256>, ProverSHPLONK<_>, _, _, _, _>(
+        params,
+        pk,
+        &[circuit],
+        &[&[&instance]],
+        rng.clone(),
+        &mut transcript,
+    )
+    .expect("prover should not fail");
+    rng.calls()
+}
+
+fn in_pool<T: Send>(num_threads: usize, f: impl FnOnce() -> T + Send) -> T {
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(num_threads)
+        .build()
+        .unwrap()
+        .install(f)
+}
+
+#[test]
+fn rng_consumption_order() {
+    let (params, pk) = setup(K);
+    let vk = pk.get_vk();
+    let m = vk.cs().blinding_factors();
+    let q = vk.get_domain().get_quotient_poly_degree();
+
+    // The calls made by a single `Field::random`.
+    let draw = {
+        let rng = CountingRng::new(0);
+        Fr::random(rng.clone());
+        rng.calls()
+    };
+
+    // Both advice columns are blinded, and there are no lookups, shuffles or permutation.
+    let advice_draws = 2 * (m + 1) + 2;
+    let seeds = ((1usize << K) + 1023) / 1024;
+    let mut expected = vec![];
+    for _ in 0..advice_draws {
+        expected.extend_from_slice(&draw);
+    }
+    expected.extend(std::iter::repeat(32).take(seeds));
+    for _ in 0..1 + q {
+        expected.extend_from_slice(&draw);
+    }
+
+    let calls = rng_calls(&params, &pk, 2, 3);
+    assert_eq!(calls, expected);
+
+    // The consumption depends neither on the witness nor on the number of threads.
+    assert_eq!(rng_calls(&params, &pk, 5, 7), expected);
+    assert_eq!(in_pool(1, || rng_calls(&params, &pk, 2, 3)), expected);
+    assert_eq!(in_pool(4, || rng_calls(&params, &pk, 2, 3)), expected);
+}
+
+#[test]
+fn deterministic_proofs() {
+    let (params, pk) = setup(K);
+
+    let proof = prove_with_mode(&params, &pk, ProvingMode::Deterministic([1; 32]));
+    verify(&params, &pk, &proof);
+
+    // The same seed gives the same proof, whatever the number of threads.
+    for num_threads in [1, 4] {
+        let other = in_pool(num_threads, || {
+            prove_with_mode(&params, &pk, ProvingMode::Deterministic([1; 32]))
+        });
+        assert_eq!(other, proof);
+    }
+
+    // A different seed gives a different proof.
+    let other = prove_with_mode(&params, &pk, ProvingMode::Deterministic([2; 32]));
+    assert_ne!(other, proof);
+    verify(&params, &pk, &other);
+}
+
+#[test]
+fn golden_proof() {
+    let (params, pk) = golden_setup(K);
+
+    let proof = prove_with_mode(&params, &pk, ProvingMode::Deterministic([1; 32]));
+    verify(&params, &pk, &proof);
+
+    let hash = blake2b_simd::blake2b(&proof).to_hex();
+    assert_eq!(
+        hash.as_str(),
+        GOLDEN_PROOF_HASH,
+        "the proof changed; if this is intended, update GOLDEN_PROOF_HASH"
+    );
+
+    // The proof doesn't depend on the number of threads either.
+    for num_threads in [1, 4] {
+        let other = in_pool(num_threads, || {
+            prove_with_mode(&params, &pk, ProvingMode::Deterministic([1; 32]))
+        });
+        assert_eq!(blake2b_simd::blake2b(&other).to_hex(), hash);
+    }
+}
+
+#[test]
+fn deterministic_proof_of_false_statement() {
+    let (params, pk) = setup(K);
+
+    // The proof of `2 * 3 = 7` doesn't verify, so no proof is returned.
+    let (circuit, _) = statement(2, 3);
+    let proof = try_prove_with_mode(
+        &params,
+        &pk,
+        circuit,
+        &[Fr::from(7)],
+        ProvingMode::Deterministic([1; 32]),
+    );
+    assert!(matches!(proof, Err(Error::ConstraintSystemFailure)));
+}
+
+#[cfg(feature = "unit-blinds")]
+#[test]
+fn unit_blinds_proofs() {
+    let (params, pk) = setup(K);
+
+    let proof = prove_with_mode(&params, &pk, ProvingMode::UnitBlinds);
+    verify(&params, &pk, &proof);
+    assert_ne!(
+        proof,
+        prove_with_mode(&params, &pk, ProvingMode::Deterministic([0; 32]))
+    );
+
+    // The proof only depends on the statement, even if proving with different threads.
+    for num_threads in [1, 4] {
+        let other = in_pool(num_threads, || {
+            prove_with_mode(&params, &pk, ProvingMode::UnitBlinds)
+        });
+        assert_eq!(other, proof);
+    }
+}
@@ -0,0 +1,150 @@
+//! Assignments that fail within a region report the region, its namespaces, the column and
+//! the offset of the assignment.
+
+use assert_matches::assert_matches;
+use halo2_middleware::circuit::Any;
+use halo2_proofs::circuit::{floor_planner::V1, Layouter, SimpleFloorPlanner, Value};
+use halo2_proofs::dev::MockProver;
+use halo2_proofs::plonk::{
+    keygen_vk, Advice, Circuit, Column, ConstraintSystem, Error, Fixed, FloorPlanner,
+};
+use halo2_proofs::poly::commitment::ParamsProver;
+use halo2_proofs::poly::kzg::commitment::ParamsKZG;
+use halo2curves::bn256::{Bn256, Fr, G1Affine};
+use halo2curves::ff::Field;
+use std::marker::PhantomData;
+
+const K: u32 = 4;
+
+/// Assigns a fixed cell beyond the usable rows of the circuit, in the `overflow` region
+/// within the `gadget` namespace.
+#[derive(Clone, Default)]
+struct OverflowCircuit;
+
+impl Circuit<Fr> for OverflowCircuit {
+    type Config = Column<Fixed>;
+    type FloorPlanner = SimpleFloorPlanner;
+    #[cfg(feature = "circuit-params")]
+    type Params = ();
+
+    fn without_witnesses(&self) -> Self {
+        Self
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fr>) -> Column<Fixed> {
+        meta.fixed_column()
+    }
+
+    fn synthesize(
+        &self,
+        config: Column<Fixed>,
+        mut layouter: impl Layouter<Fr>,
+    ) -> Result<(), Error> {
+        layouter.namespace(|| "gadget").assign_region(
+            || "overflow",
+            |mut region| {
+                region.name_column(|| "acc", config);
+                region.assign_fixed(|| "acc", config, 20, || Value::known(Fr::ONE))?;
+                Ok(())
+            },
+        )
+    }
+}
+
+/// Copies between advice columns that are not in the permutation, in the `copy` region
+/// within the `outer/inner` namespaces, with the floor planner `P`.
+struct CopyCircuit<P>(PhantomData<P>);
+
+impl<P: FloorPlanner> Circuit<Fr> for CopyCircuit<P> {
+    type Config = [Column<Advice>; 2];
+    type FloorPlanner = P;
+    #[cfg(feature = "circuit-params")]
+    type Params = ();
+
+    fn without_witnesses(&self) -> Self {
+        Self(PhantomData)
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fr>) -> [Column<Advice>; 2] {
+        [meta.advice_column(), meta.advice_column()]
+    }
+
+    fn synthesize(
+        &self,
+        [a, b]: [Column<Advice>; 2],
+        mut layouter: impl Layouter<Fr>,
+    ) -> Result<(), Error> {
+        let mut outer = layouter.namespace(|| "outer");
+        outer.namespace(|| "inner").assign_region(
+            || "copy",
+            |mut region| {
+                let a = region.assign_advice(|| "a", a, 1, || Value::known(Fr::ONE))?;
+                let b = region.assign_advice(|| "b", b, 1, || Value::known(Fr::ONE))?;
+                region.constrain_equal(a.cell(), b.cell())
+            },
+        )
+    }
+}
+
+#[test]
+fn out_of_rows_assignments_report_their_context() {
+    let params = ParamsKZG::<Bn256>::new(K);
+    let error = keygen_vk::<G1Affine, _, _>(&params, &OverflowCircuit).unwrap_err();
+    let Error::InRegion { context, error: root } = &error else {
+        panic!("expected the error to have the context of its region, got {error:?}");
+    };
+    assert_eq!(context.operation, "assign_fixed");
+    assert_eq!(context.region, "overflow");
+    assert_eq!(context.namespace, ["gadget"]);
+    assert_eq!(context.path(), "gadget/overflow");
+    assert_eq!(context.column, Some(Column::new(0, Any::Fixed)));
+    assert_eq!(context.column_annotation.as_deref(), Some("acc"));
+    assert_eq!(context.offset, Some(20));
+    assert_matches!(
+        **root,
+        Error::NotEnoughRowsAvailable {
+            current_k: K,
+            row: Some(20)
+        }
+    );
+
+    let message = error.to_string();
+    assert!(message.starts_with("Failed to assign_fixed at offset 20 of column"));
+    assert!(message.contains("(\"acc\") in region \"gadget/overflow\""));
+    assert!(message.contains("k = 4 is too small for the given circuit, which assigns row 20"));
+}
+
+#[test]
+fn failed_copies_report_their_context() {
+    let error = MockProver::run(K, &CopyCircuit::<V1>(PhantomData), vec![]).unwrap_err();
+    let Error::InRegion { context, .. } = &error else {
+        panic!("expected the error to have the context of its region, got {error:?}");
+    };
+    assert_eq!(context.operation, "constrain_equal");
+    assert_eq!(context.path(), "outer/inner/copy");
+    assert_eq!(context.column, Some(Column::new(0, Any::advice())));
+    assert_eq!(context.column_annotation, None);
+    assert_eq!(context.offset, Some(1));
+    assert_matches!(error.root(), Error::ColumnNotInPermutation(_));
+}
+
+/// Matches the errors of the copy within a region through [`Error::root`], as callers that
+/// matched them before they had the context of their region do.
+fn assert_root_errors<P: FloorPlanner>() {
+    let params = ParamsKZG::<Bn256>::new(K);
+    let circuit = CopyCircuit::<P>(PhantomData);
+    for error in [
+        keygen_vk::<G1Affine, _, _>(&params, &circuit).unwrap_err(),
+        MockProver::run(K, &circuit, vec![]).unwrap_err(),
+    ] {
+        assert_matches!(error, Error::InRegion { .. });
+        assert_matches!(error.root(), Error::ColumnNotInPermutation(_));
+        assert_matches!(error.into_root(), Error::ColumnNotInPermutation(_));
+    }
+}
+
+#[test]
+fn errors_match_through_their_root_with_both_floor_planners() {
+    assert_root_errors::<SimpleFloorPlanner>();
+    assert_root_errors::<V1>();
+}
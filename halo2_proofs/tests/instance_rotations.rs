@@ -0,0 +1,166 @@
+use halo2_proofs::circuit::{Layouter, SimpleFloorPlanner, Value};
+use halo2_proofs::dev::MockProver;
+use halo2_proofs::plonk::{
+    create_proof, keygen_pk, keygen_vk_with_options, verify_proof, Advice, Circuit, CircuitOptions,
+    Column, ConstraintSystem, Error, Instance, ProvingKey, Selector, VerificationError,
+};
+use halo2_proofs::poly::commitment::ParamsProver;
+use halo2_proofs::poly::kzg::commitment::{KZGCommitmentScheme, ParamsKZG};
+use halo2_proofs::poly::kzg::multiopen::{ProverSHPLONK, VerifierSHPLONK};
+use halo2_proofs::poly::kzg::strategy::SingleStrategy;
+use halo2_proofs::poly::Rotation;
+use halo2_proofs::transcript::{
+    Blake2bRead, Blake2bWrite, Challenge255, TranscriptReadBuffer, TranscriptWriterBuffer,
+};
+use halo2curves::bn256::{Bn256, Fr, G1Affine};
+use rand_core::OsRng;
+
+const K: u32 = 4;
+
+#[derive(Clone)]
+struct WindowConfig {
+    sum: Column<Advice>,
+    inputs: Column<Instance>,
+    s: Selector,
+}
+
+/// Proves sums over a sliding window of three public inputs, at rows 1 to 3. The window
+/// at row 3 reaches past the last public input, which reads as zero.
+#[derive(Clone, Default)]
+struct WindowCircuit {
+    sums: [Value<Fr>; 3],
+}
+
+impl WindowCircuit {
+    fn new(sums: [u64; 3]) -> Self {
+        Self {
+            sums: sums.map(|sum| Value::known(Fr::from(sum))),
+        }
+    }
+}
+
+impl Circuit<Fr> for WindowCircuit {
+    type Config = WindowConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+    #[cfg(feature = "circuit-params")]
+    type Params = ();
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fr>) -> WindowConfig {
+        let sum = meta.advice_column();
+        let inputs = meta.instance_column();
+        let s = meta.selector();
+
+        meta.create_gate("window", |meta| {
+            let sum = meta.query_advice(sum, Rotation::cur());
+            let prev = meta.query_instance(inputs, Rotation::prev());
+            let cur = meta.query_instance(inputs, Rotation::cur());
+            let next = meta.query_instance(inputs, Rotation::next());
+            let s = meta.query_selector(s);
+            vec![s * (sum - (prev + cur + next))]
+        });
+
+        WindowConfig { sum, inputs, s }
+    }
+
+    fn synthesize(
+        &self,
+        config: WindowConfig,
+        mut layouter: impl Layouter<Fr>,
+    ) -> Result<(), Error> {
+        layouter.assign_region(
+            || "window",
+            |mut region| {
+                for (offset, sum) in self.sums.iter().enumerate() {
+                    config.s.enable(&mut region, offset + 1)?;
+                    region.assign_advice(|| "sum", config.sum, offset + 1, || *sum)?;
+                }
+                Ok(())
+            },
+        )
+    }
+}
+
+const INPUTS: [u64; 4] = [1, 2, 3, 4];
+const SUMS: [u64; 3] = [6, 9, 7];
+
+fn inputs(values: [u64; 4]) -> Vec<Fr> {
+    values.into_iter().map(Fr::from).collect()
+}
+
+fn setup(commit_instances: bool) -> (ParamsKZG<Bn256>, ProvingKey<G1Affine>) {
+    let params = ParamsKZG::<Bn256>::new(K);
+    let options = CircuitOptions { commit_instances };
+    let vk = keygen_vk_with_options(&params, &WindowCircuit::default(), true, &options)
+        .expect("keygen_vk should not fail");
+    let pk = keygen_pk(&params, vk, &WindowCircuit::default()).expect("keygen_pk should not fail");
+    (params, pk)
+}
+
+fn prove(
+    params: &ParamsKZG<Bn256>,
+    pk: &ProvingKey<G1Affine>,
+    circuit: WindowCircuit,
+) -> Result<Vec<u8>, Error> {
+    let mut transcript = Blake2bWrite::<_, _, Challenge255<_>>::init(vec![]);
+    create_proof::<KZGCommitmentScheme<Bn256>, ProverSHPLONK<_>, _, _, _, _>(
+        params,
+        pk,
+        &[circuit],
+        &[&[&inputs(INPUTS)]],
+        OsRng,
+        &mut transcript,
+    )?;
+    Ok(transcript.finalize())
+}
+
+fn verify(
+    params: &ParamsKZG<Bn256>,
+    pk: &ProvingKey<G1Affine>,
+    instance: &[Fr],
+    proof: &[u8],
+) -> Result<(), VerificationError> {
+    let mut transcript = Blake2bRead::<_, _, Challenge255<_>>::init(proof);
+    let strategy = SingleStrategy::new(params);
+    verify_proof::<_, VerifierSHPLONK<_>, _, _, _>(
+        params,
+        pk.get_vk(),
+        strategy,
+        &[&[instance]],
+        &mut transcript,
+    )
+}
+
+#[test]
+fn rotated_instance_queries_prove_and_verify() {
+    for commit_instances in [false, true] {
+        let (params, pk) = setup(commit_instances);
+        let proof = prove(&params, &pk, WindowCircuit::new(SUMS)).expect("prover should not fail");
+        verify(&params, &pk, &inputs(INPUTS), &proof).expect("proof should verify");
+
+        // Changing any input in a window breaks the proof, including the inputs only
+        // reached through a rotation.
+        for changed in [[9, 2, 3, 4], [1, 2, 3, 9]] {
+            assert!(verify(&params, &pk, &inputs(changed), &proof).is_err());
+        }
+    }
+}
+
+#[test]
+fn rotated_instance_queries_mock_prover_parity() {
+    let prover = MockProver::run(K, &WindowCircuit::new(SUMS), vec![inputs(INPUTS)]).unwrap();
+    assert_eq!(prover.verify(), Ok(()));
+
+    // The window at row 3 must not see a fifth input.
+    let prover = MockProver::run(K, &WindowCircuit::new([6, 9, 12]), vec![inputs(INPUTS)]).unwrap();
+    assert!(prover.verify().is_err());
+
+    // Wrong sums are rejected by the real prover or verifier as well.
+    let (params, pk) = setup(false);
+    if let Ok(proof) = prove(&params, &pk, WindowCircuit::new([6, 9, 12])) {
+        assert!(verify(&params, &pk, &inputs(INPUTS), &proof).is_err());
+    }
+}
@@ -0,0 +1,160 @@
+#![cfg(feature = "serde")]
+
+use halo2_middleware::circuit::ConstraintSystemV2Backend;
+use halo2_proofs::plonk::interchange::{Interchange, InterchangeExpression};
+use halo2_proofs::plonk::{ConstraintSystem, Error, Expression, FirstPhase, SecondPhase};
+use halo2_proofs::poly::Rotation;
+use halo2curves::bn256::Fr;
+use halo2curves::ff::Field;
+
+const GOLDEN: &str = include_str!("data/interchange.json");
+
+/// Doubles `a` down its rows where `q` is set, looks it up in a table, shuffles it into `b`
+/// and mixes it with a challenge into `d`.
+fn configure(meta: &mut ConstraintSystem<Fr>) {
+    let q = meta.fixed_column();
+    let table = meta.fixed_column();
+    let constants = meta.fixed_column();
+    let a = meta.advice_column();
+    let b = meta.unblinded_advice_column();
+    let r = meta.challenge_usable_after(FirstPhase);
+    let d = meta.advice_column_in(SecondPhase);
+    let instance = meta.instance_column();
+    meta.annotate_lookup_any_column(q, || "q");
+    meta.annotate_lookup_any_column(table, || "table");
+    meta.annotate_lookup_any_column(a, || "a");
+    meta.enable_equality(a);
+    meta.enable_equality(instance);
+    meta.enable_constant(constants);
+
+    meta.create_gate("double", |meta| {
+        let q = meta.query_fixed(q, Rotation::cur());
+        let cur = meta.query_advice(a, Rotation::cur());
+        let next = meta.query_advice(a, Rotation::next());
+        vec![("next", q * (next - cur * Fr::from(2)))]
+    });
+    meta.create_gate("mix", |meta| {
+        let q = meta.query_fixed(q, Rotation::cur());
+        let a = meta.query_advice(a, Rotation::cur());
+        let b = meta.query_advice(b, Rotation::prev());
+        let d = meta.query_advice(d, Rotation::cur());
+        let r = meta.query_challenge(r);
+        vec![d - a * r, q * (b + Expression::Constant(Fr::ONE))]
+    });
+    meta.lookup_any("range", |meta| {
+        let a = meta.query_advice(a, Rotation::cur());
+        let table = meta.query_fixed(table, Rotation::cur());
+        vec![(a, table)]
+    });
+    meta.shuffle("sorted", |meta| {
+        let a = meta.query_advice(a, Rotation::cur());
+        let b = meta.query_advice(b, Rotation::cur());
+        vec![(a, b)]
+    });
+}
+
+fn constraint_system() -> ConstraintSystem<Fr> {
+    let mut meta = ConstraintSystem::default();
+    configure(&mut meta);
+    meta
+}
+
+#[test]
+fn interchange_matches_golden_file() {
+    let interchange = constraint_system().to_interchange();
+    let golden: serde_json::Value = serde_json::from_str(GOLDEN).unwrap();
+    assert_eq!(serde_json::to_value(&interchange).unwrap(), golden);
+
+    let json = serde_json::to_string(&interchange).unwrap();
+    assert_eq!(
+        serde_json::from_str::<Interchange>(&json).unwrap(),
+        interchange
+    );
+}
+
+#[test]
+fn interchange_round_trips_through_the_backend() {
+    let interchange: Interchange = serde_json::from_str(GOLDEN).unwrap();
+    let cs2: ConstraintSystemV2Backend<Fr> =
+        ConstraintSystem::from_interchange(&interchange).unwrap();
+    assert_eq!(cs2.advice_column_phase, [0, 0, 1]);
+    assert_eq!(cs2.unblinded_advice_columns, [1]);
+    assert_eq!(cs2.gates.len(), 3);
+
+    // The enabled constants are not part of a `ConstraintSystemV2Backend`.
+    let cs: ConstraintSystem<Fr> = cs2.into();
+    assert_eq!(
+        cs.to_interchange(),
+        Interchange {
+            constants: vec![],
+            ..interchange
+        }
+    );
+}
+
+#[test]
+fn interchange_ignores_unknown_fields() {
+    let mut json: serde_json::Value = serde_json::from_str(GOLDEN).unwrap();
+    json["origin"] = "a later version".into();
+    json["columns"][0]["degree"] = 3.into();
+    json["counts"]["lookups"] = 1.into();
+    let interchange: Interchange = serde_json::from_value(json).unwrap();
+    assert_eq!(interchange, constraint_system().to_interchange());
+}
+
+#[test]
+fn interchange_rejects_invalid_constraint_systems() {
+    let golden = || serde_json::from_str::<Interchange>(GOLDEN).unwrap();
+    let read = |interchange: &Interchange| ConstraintSystem::<Fr>::from_interchange(interchange);
+
+    // Selectors are written, but can't be read into a `ConstraintSystemV2Backend`.
+    let mut meta = ConstraintSystem::<Fr>::default();
+    let s = meta.selector();
+    let a = meta.advice_column();
+    meta.create_gate("selected", |meta| {
+        vec![meta.query_selector(s) * meta.query_advice(a, Rotation::cur())]
+    });
+    let interchange = meta.to_interchange();
+    assert_eq!(interchange.counts.selectors, 1);
+    assert!(matches!(
+        &interchange.gates[0].polynomial,
+        InterchangeExpression::Product(s, _) if **s == InterchangeExpression::Selector(0)
+    ));
+    assert!(matches!(read(&interchange), Err(Error::Other(_))));
+
+    let mut interchange = golden();
+    interchange.version += 1;
+    assert!(matches!(read(&interchange), Err(Error::Other(_))));
+
+    let mut interchange = golden();
+    interchange.gates[0].polynomial = InterchangeExpression::Advice {
+        column: 3,
+        rotation: 0,
+    };
+    assert!(matches!(read(&interchange), Err(Error::Other(_))));
+
+    // Only the first three phases are supported.
+    let mut interchange = golden();
+    let advice = interchange
+        .columns
+        .iter_mut()
+        .find(|column| column.phase.is_some())
+        .unwrap();
+    advice.phase = Some(3);
+    assert!(matches!(read(&interchange), Err(Error::Other(_))));
+
+    // Above the modulus.
+    let mut interchange = golden();
+    interchange.gates[0].polynomial =
+        InterchangeExpression::Constant(format!("0x{}", "f".repeat(64)));
+    assert!(matches!(read(&interchange), Err(Error::Other(_))));
+
+    // Leading zeros may be omitted.
+    let mut interchange = golden();
+    interchange.gates[0].polynomial = InterchangeExpression::Constant("0x2a".to_string());
+    let cs2 = read(&interchange).unwrap();
+    assert_eq!(
+        cs2.gates[0].poly,
+        halo2_middleware::circuit::ExpressionMid::Constant(Fr::from(42))
+    );
+}
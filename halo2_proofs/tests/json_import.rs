@@ -0,0 +1,182 @@
+#![cfg(feature = "import")]
+
+//! Proves a circuit that is imported from its JSON description.
+
+use assert_matches::assert_matches;
+use halo2_backend::plonk::{prover::ProverV2Single, verifier::verify_proof_single};
+use halo2_middleware::circuit::CompiledCircuitV2;
+use halo2_middleware::import::{from_json, ImportError};
+use halo2_proofs::plonk::{keygen_pk_v2, keygen_vk_v2, Error};
+use halo2_proofs::poly::commitment::ParamsProver;
+use halo2_proofs::poly::kzg::commitment::{KZGCommitmentScheme, ParamsKZG};
+use halo2_proofs::poly::kzg::multiopen::{ProverSHPLONK, VerifierSHPLONK};
+use halo2_proofs::poly::kzg::strategy::SingleStrategy;
+use halo2_proofs::transcript::{
+    Blake2bRead, Blake2bWrite, Challenge255, TranscriptReadBuffer, TranscriptWriterBuffer,
+};
+use halo2curves::bn256::{Bn256, Fr, G1Affine};
+use halo2curves::ff::Field;
+use rand_core::OsRng;
+
+const K: u32 = 5;
+const N: usize = 1 << K;
+
+/// Enforces `q * (a * b - c) = 0` on the first three rows, looks `a` up in the fixed column
+/// `t`, and copies `c` at row 0 to the instance.
+fn description() -> serde_json::Value {
+    serde_json::json!({
+        "version": 1,
+        "k": K,
+        "counts": { "fixed": 2, "advice": 3, "instance": 1, "selectors": 0, "challenges": 0 },
+        "columns": [
+            { "kind": "fixed", "index": 0, "annotation": "q" },
+            { "kind": "fixed", "index": 1, "annotation": "t" },
+            { "kind": "advice", "index": 0, "phase": 0 },
+            { "kind": "advice", "index": 1, "phase": 0 },
+            { "kind": "advice", "index": 2, "phase": 0 },
+            { "kind": "instance", "index": 0 }
+        ],
+        "challenges": [],
+        "gates": [{
+            "name": "mul",
+            "polynomial": ["*", ["fixed", 0, 0], ["+",
+                ["*", ["advice", 0, 0], ["advice", 1, 0]],
+                ["-", ["advice", 2, 0]]
+            ]]
+        }],
+        "lookups": [{
+            "name": "small",
+            "inputs": [["advice", 0, 0]],
+            "tables": [["fixed", 1, 0]]
+        }],
+        "shuffles": [],
+        "permutation": [{ "kind": "advice", "index": 2 }, { "kind": "instance", "index": 0 }],
+        "fixed": [
+            ["0x01", "0x01", "0x01"],
+            ["0x00", "0x01", "0x02", "0x03", "0x04"]
+        ],
+        "copies": [[
+            { "kind": "advice", "index": 2, "row": 0 },
+            { "kind": "instance", "index": 0, "row": 0 }
+        ]]
+    })
+}
+
+fn import(description: &serde_json::Value) -> Result<CompiledCircuitV2<Fr>, ImportError> {
+    from_json(description.to_string().as_bytes())
+}
+
+/// Returns the raw advice columns, with `a = [2, 3, 4]` and `b = [5, 6, 7]`.
+fn witness() -> Vec<Option<Vec<Fr>>> {
+    let column = |values: [u64; 3]| {
+        let mut column = vec![Fr::ZERO; N];
+        for (cell, value) in column.iter_mut().zip(values) {
+            *cell = Fr::from(value);
+        }
+        Some(column)
+    };
+    vec![column([2, 3, 4]), column([5, 6, 7]), column([10, 18, 28])]
+}
+
+fn prove_and_verify(circuit: &CompiledCircuitV2<Fr>, instance: u64) -> Result<(), Error> {
+    let params = ParamsKZG::<Bn256>::new(K);
+    let vk = keygen_vk_v2(&params, circuit).expect("keygen_vk should not fail");
+    let pk = keygen_pk_v2(&params, vk.clone(), circuit).expect("keygen_pk should not fail");
+
+    let instance = [Fr::from(instance)];
+    let instances: &[&[Fr]] = &[&instance];
+    let mut transcript = Blake2bWrite::<_, G1Affine, Challenge255<_>>::init(vec![]);
+    let mut prover =
+        ProverV2Single::<KZGCommitmentScheme<Bn256>, ProverSHPLONK<'_, Bn256>, _, _, _>::new(
+            &params,
+            &pk,
+            instances,
+            OsRng,
+            &mut transcript,
+        )?;
+    prover.commit_phase(0, witness())?;
+    prover.create_proof()?;
+    let proof = transcript.finalize();
+
+    let mut transcript = Blake2bRead::<_, G1Affine, Challenge255<_>>::init(proof.as_slice());
+    let strategy = SingleStrategy::new(&params);
+    verify_proof_single::<KZGCommitmentScheme<Bn256>, VerifierSHPLONK<'_, Bn256>, _, _, _>(
+        &params,
+        &vk,
+        strategy,
+        instances,
+        &mut transcript,
+    )
+    .map(|_| ())
+}
+
+#[test]
+fn json_import_prove_and_verify() {
+    let circuit = import(&description()).unwrap();
+    assert_eq!(circuit.cs.advice_column_phase, [0, 0, 0]);
+    assert_eq!(circuit.preprocessing.fixed[1].len(), N);
+    assert_eq!(circuit.preprocessing.fixed[1][4], Fr::from(4));
+    assert_eq!(circuit.preprocessing.fixed[1][5], Fr::ZERO);
+
+    assert_matches!(prove_and_verify(&circuit, 10), Ok(()));
+    assert_matches!(prove_and_verify(&circuit, 11), Err(_));
+}
+
+#[test]
+fn json_import_reports_the_path_of_errors() {
+    let error_at = |edit: fn(&mut serde_json::Value)| {
+        let mut description = description();
+        edit(&mut description);
+        import(&description).unwrap_err().path
+    };
+
+    assert_eq!(error_at(|d| d["version"] = 2.into()), "$.version");
+    assert_eq!(error_at(|d| d["k"] = 40.into()), "$.k");
+    assert_eq!(
+        error_at(|d| d["gates"][0]["polynomial"][2][1][1] = 3.into()),
+        "$.gates[0].polynomial[2][1][1]"
+    );
+    assert_eq!(
+        error_at(|d| d["gates"][0]["polynomial"][0] = "/".into()),
+        "$.gates[0].polynomial[0]"
+    );
+    assert_eq!(
+        error_at(|d| d["lookups"][0]["inputs"][0] = serde_json::json!(["selector", 0])),
+        "$.lookups[0].inputs[0]"
+    );
+    assert_eq!(
+        error_at(|d| d["lookups"][0]["tables"] = serde_json::json!([])),
+        "$.lookups[0].tables"
+    );
+    assert_eq!(
+        error_at(|d| d["columns"][2]["phase"] = 2.into()),
+        "$.columns"
+    );
+    assert_eq!(
+        error_at(|d| d["fixed"][1][0] = format!("0x{}", "f".repeat(64)).into()),
+        "$.fixed[1][0]"
+    );
+    assert_eq!(
+        error_at(|d| d["fixed"][0] = vec!["0x01"; N + 1].into()),
+        "$.fixed[0]"
+    );
+    assert_eq!(
+        error_at(|d| d["copies"][0][0]["row"] = N.into()),
+        "$.copies[0][0].row"
+    );
+    assert_eq!(
+        error_at(|d| d["copies"][0][1]["index"] = 1.into()),
+        "$.copies[0][1].index"
+    );
+    assert_eq!(
+        error_at(|d| d["copies"][0][0]["index"] = 0.into()),
+        "$.copies[0][0]"
+    );
+    assert_eq!(
+        error_at(|d| d["counts"]["selectors"] = 1.into()),
+        "$.counts.selectors"
+    );
+
+    let error = from_json::<Fr>(b"{").unwrap_err();
+    assert_eq!(error.path, "$");
+}
@@ -0,0 +1,300 @@
+use std::io;
+
+use assert_matches::assert_matches;
+use halo2_proofs::circuit::{Layouter, SimpleFloorPlanner, Value};
+use halo2_proofs::plonk::{
+    create_proof, keygen_pk, keygen_vk, verify_proof, Advice, Circuit, Column, ConstraintSystem,
+    Error, Fixed, Instance, ProvingKey, TableColumn, VerifyingKey,
+};
+use halo2_proofs::poly::commitment::ParamsProver;
+use halo2_proofs::poly::kzg::commitment::{KZGCommitmentScheme, ParamsKZG};
+use halo2_proofs::poly::kzg::multiopen::{ProverSHPLONK, VerifierSHPLONK};
+use halo2_proofs::poly::kzg::strategy::SingleStrategy;
+use halo2_proofs::poly::Rotation;
+use halo2_proofs::transcript::{
+    Blake2bRead, Blake2bWrite, Challenge255, TranscriptReadBuffer, TranscriptWriterBuffer,
+};
+use halo2_proofs::SerdeFormat;
+use halo2curves::bn256::{Bn256, Fr, G1Affine};
+use rand_core::OsRng;
+
+const K: u32 = 5;
+
+const FORMATS: [SerdeFormat; 3] = [
+    SerdeFormat::Processed,
+    SerdeFormat::RawBytes,
+    SerdeFormat::RawBytesUnchecked,
+];
+
+#[derive(Clone)]
+struct TestConfig {
+    a: Column<Advice>,
+    b: Column<Advice>,
+    q: Column<Fixed>,
+    table: TableColumn,
+}
+
+/// Proves that `b = a + instance`, where `a` is copied from the first row and lies in
+/// a small table.
+#[derive(Clone, Default)]
+struct TestCircuit {
+    a: Value<Fr>,
+}
+
+impl Circuit<Fr> for TestCircuit {
+    type Config = TestConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+    #[cfg(feature = "circuit-params")]
+    type Params = ();
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fr>) -> TestConfig {
+        let a = meta.advice_column();
+        let b = meta.advice_column();
+        let q = meta.fixed_column();
+        let instance: Column<Instance> = meta.instance_column();
+        let table = meta.lookup_table_column();
+        meta.enable_equality(a);
+        meta.annotate_lookup_column(table, || "table");
+
+        meta.create_gate("add", |meta| {
+            let q = meta.query_fixed(q, Rotation::cur());
+            let a = meta.query_advice(a, Rotation::prev());
+            let b = meta.query_advice(b, Rotation::cur());
+            let instance = meta.query_instance(instance, Rotation::cur());
+            vec![("b = a + instance", q * (b - a - instance))]
+        });
+        meta.lookup("a in table", |meta| {
+            let a = meta.query_advice(a, Rotation::cur());
+            vec![(a, table)]
+        });
+
+        TestConfig { a, b, q, table }
+    }
+
+    fn synthesize(&self, config: TestConfig, mut layouter: impl Layouter<Fr>) -> Result<(), Error> {
+        layouter.assign_table(
+            || "table",
+            |mut table| {
+                for i in 0..8 {
+                    table.assign_cell(
+                        || "",
+                        config.table,
+                        i,
+                        || Value::known(Fr::from(i as u64)),
+                    )?;
+                }
+                Ok(())
+            },
+        )?;
+        layouter.assign_region(
+            || "add",
+            |mut region| {
+                let a = region.assign_advice(|| "a", config.a, 0, || self.a)?;
+                a.copy_advice(|| "a", &mut region, config.a, 1)?;
+                region.assign_fixed(|| "q", config.q, 2, || Value::known(Fr::from(1)))?;
+                region.assign_advice(|| "b", config.b, 2, || self.a + Value::known(Fr::from(2)))?;
+                Ok(())
+            },
+        )
+    }
+}
+
+fn setup() -> (ParamsKZG<Bn256>, ProvingKey<G1Affine>) {
+    let params = ParamsKZG::<Bn256>::new(K);
+    let vk = keygen_vk(&params, &TestCircuit::default()).expect("keygen_vk should not fail");
+    let pk = keygen_pk(&params, vk, &TestCircuit::default()).expect("keygen_pk should not fail");
+    (params, pk)
+}
+
+/// Creates a proof with `pk` and checks it with `vk`.
+fn prove_and_verify(
+    params: &ParamsKZG<Bn256>,
+    pk: &ProvingKey<G1Affine>,
+    vk: &VerifyingKey<G1Affine>,
+) {
+    let circuit = TestCircuit {
+        a: Value::known(Fr::from(5)),
+    };
+    let instance = [Fr::from(0), Fr::from(0), Fr::from(2)];
+    let mut transcript = Blake2bWrite::<_, _, Challenge255<_>>::init(vec![]);
+    create_proof::<KZGCommitmentScheme<Bn256>, ProverSHPLONK<_>, _, _, _, _>(
+        params,
+        pk,
+        &[circuit],
+        &[&[&instance]],
+        OsRng,
+        &mut transcript,
+    )
+    .expect("prover should not fail");
+    let proof = transcript.finalize();
+
+    let mut transcript = Blake2bRead::<_, _, Challenge255<_>>::init(&proof[..]);
+    let strategy = SingleStrategy::new(params);
+    verify_proof::<_, VerifierSHPLONK<_>, _, _, _>(
+        params,
+        vk,
+        strategy,
+        &[&[&instance]],
+        &mut transcript,
+    )
+    .expect("proof should verify");
+}
+
+#[test]
+fn vk_self_describing_round_trip() {
+    let (_, pk) = setup();
+    let vk = pk.get_vk();
+
+    for format in FORMATS {
+        let bytes = vk.to_bytes_self_describing(format);
+        let read = VerifyingKey::<G1Affine>::from_bytes_self_describing(&bytes).unwrap();
+
+        assert_eq!(read.transcript_repr(), vk.transcript_repr());
+        assert_eq!(format!("{:?}", read.pinned()), format!("{:?}", vk.pinned()));
+        assert_eq!(read.compress_selectors, vk.compress_selectors);
+        assert_eq!(read.to_bytes_self_describing(format), bytes);
+        // The key can still be written in the legacy format.
+        assert_eq!(read.to_bytes(format), vk.to_bytes(format));
+    }
+}
+
+#[test]
+fn vk_legacy_round_trip() {
+    let (_, pk) = setup();
+    let vk = pk.get_vk();
+
+    for format in FORMATS {
+        let bytes = vk.to_bytes(format);
+        let read = VerifyingKey::<G1Affine>::from_bytes::<TestCircuit>(
+            &bytes,
+            format,
+            #[cfg(feature = "circuit-params")]
+            (),
+        )
+        .unwrap();
+        assert_eq!(read.to_bytes(format), bytes);
+        assert_eq!(read.fixed_commitments(), vk.fixed_commitments());
+    }
+}
+
+#[test]
+fn pk_self_describing_round_trip() {
+    let (params, pk) = setup();
+
+    for format in FORMATS {
+        let bytes = pk.to_bytes_self_describing(format);
+        let read = ProvingKey::<G1Affine>::from_bytes_self_describing(&bytes).unwrap();
+        assert_eq!(
+            read.get_vk().transcript_repr(),
+            pk.get_vk().transcript_repr()
+        );
+        assert_eq!(read.to_bytes(format), pk.to_bytes(format));
+
+        // Keys read in either format are interchangeable.
+        prove_and_verify(&params, &read, pk.get_vk());
+        prove_and_verify(&params, &pk, read.get_vk());
+    }
+}
+
+#[test]
+fn self_describing_corrupted_header() {
+    let (_, pk) = setup();
+    let bytes = pk.get_vk().to_bytes_self_describing(SerdeFormat::Processed);
+    let read = |bytes: &[u8]| VerifyingKey::<G1Affine>::from_bytes_self_describing(bytes);
+
+    // Bytes 0..4 are the magic bytes, 4 is the version, 5 is the `SerdeFormat`, 6 is `k`
+    // and 7 holds the flags, of which only the 2 lowest bits are used.
+    for (offset, byte) in [(0, b'X'), (4, 0xff), (5, 3), (6, 0xff), (7, 4)] {
+        let mut corrupted = bytes.clone();
+        corrupted[offset] = byte;
+        assert_matches!(read(&corrupted), Err(e) if e.kind() == io::ErrorKind::InvalidData);
+    }
+
+    // A key in the legacy format is not a self-describing key.
+    let legacy = pk.get_vk().to_bytes(SerdeFormat::Processed);
+    assert_matches!(read(&legacy), Err(e) if e.kind() == io::ErrorKind::InvalidData);
+
+    assert_matches!(
+        read(&bytes[..bytes.len() - 1]),
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof
+    );
+}
+
+#[test]
+fn pk_from_bytes_matches_read() {
+    let (params, pk) = setup();
+
+    for format in FORMATS {
+        let bytes = pk.to_bytes(format);
+        let read = ProvingKey::<G1Affine>::read::<_, TestCircuit>(
+            &mut &bytes[..],
+            format,
+            #[cfg(feature = "circuit-params")]
+            (),
+        )
+        .unwrap();
+        let from_bytes = ProvingKey::<G1Affine>::from_bytes::<TestCircuit>(
+            &bytes,
+            format,
+            #[cfg(feature = "circuit-params")]
+            (),
+        )
+        .unwrap();
+        assert_eq!(from_bytes.to_bytes(format), read.to_bytes(format));
+        assert_eq!(from_bytes.to_bytes(format), bytes);
+        prove_and_verify(&params, &from_bytes, pk.get_vk());
+
+        assert_matches!(
+            ProvingKey::<G1Affine>::from_bytes::<TestCircuit>(
+                &bytes[..bytes.len() - 1],
+                format,
+                #[cfg(feature = "circuit-params")]
+                (),
+            ),
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof
+        );
+    }
+}
+
+#[test]
+fn params_from_bytes_matches_read() {
+    let (params, _) = setup();
+
+    for format in FORMATS {
+        let mut bytes = vec![];
+        params.write_custom(&mut bytes, format).unwrap();
+        let read = ParamsKZG::<Bn256>::read_custom(&mut &bytes[..], format).unwrap();
+        let from_bytes = ParamsKZG::<Bn256>::from_bytes_custom(&bytes, format).unwrap();
+
+        let mut read_bytes = vec![];
+        read.write_custom(&mut read_bytes, format).unwrap();
+        let mut from_bytes_bytes = vec![];
+        from_bytes
+            .write_custom(&mut from_bytes_bytes, format)
+            .unwrap();
+        assert_eq!(from_bytes_bytes, read_bytes);
+        assert_eq!(from_bytes_bytes, bytes);
+
+        assert_matches!(
+            ParamsKZG::<Bn256>::from_bytes_custom(&bytes[..bytes.len() / 2], format),
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof
+        );
+    }
+
+    let mut bytes = vec![];
+    params
+        .write_custom(&mut bytes, SerdeFormat::RawBytes)
+        .unwrap();
+    let from_bytes = ParamsKZG::<Bn256>::from_bytes(&bytes).unwrap();
+    assert_eq!(from_bytes.g2(), params.g2());
+    assert_eq!(from_bytes.s_g2(), params.s_g2());
+
+    // The first base is corrupted: its coordinates no longer lie on the curve.
+    bytes[4] ^= 1;
+    assert!(ParamsKZG::<Bn256>::from_bytes(&bytes).is_err());
+    assert!(ParamsKZG::<Bn256>::from_bytes_custom(&bytes, SerdeFormat::RawBytesUnchecked).is_ok());
+}
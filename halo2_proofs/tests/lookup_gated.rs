@@ -0,0 +1,152 @@
+//! A simple selector that gates both a gate and a lookup, with `ConstraintSystem::lookup_gated`.
+
+use assert_matches::assert_matches;
+use halo2_proofs::circuit::{Layouter, SimpleFloorPlanner, Value};
+use halo2_proofs::dev::{MockProver, VerifyFailure};
+use halo2_proofs::plonk::{
+    create_proof, keygen_pk, keygen_vk_custom, verify_proof, Advice, Circuit, Column,
+    ConstraintSystem, Error, Selector, TableColumn,
+};
+use halo2_proofs::poly::commitment::ParamsProver;
+use halo2_proofs::poly::kzg::commitment::{KZGCommitmentScheme, ParamsKZG};
+use halo2_proofs::poly::kzg::multiopen::{ProverSHPLONK, VerifierSHPLONK};
+use halo2_proofs::poly::kzg::strategy::SingleStrategy;
+use halo2_proofs::poly::Rotation;
+use halo2_proofs::transcript::{
+    Blake2bRead, Blake2bWrite, Challenge255, TranscriptReadBuffer, TranscriptWriterBuffer,
+};
+use halo2curves::bn256::{Bn256, Fr, G1Affine};
+use rand_core::OsRng;
+
+const K: u32 = 5;
+
+#[derive(Clone)]
+struct GatedConfig {
+    a: Column<Advice>,
+    b: Column<Advice>,
+    q: Selector,
+    table: TableColumn,
+}
+
+/// Enforces `b = 2 * a` and looks `a` up in `[0, 8)` on the rows where `q` is enabled, which are
+/// the rows of `values`. The last row has `a = 100` and `q` disabled.
+#[derive(Clone, Default)]
+struct GatedCircuit {
+    values: [u64; 3],
+}
+
+impl Circuit<Fr> for GatedCircuit {
+    type Config = GatedConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+    #[cfg(feature = "circuit-params")]
+    type Params = ();
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fr>) -> GatedConfig {
+        let a = meta.advice_column();
+        let b = meta.advice_column();
+        let q = meta.selector();
+        let table = meta.lookup_table_column();
+        meta.create_gate("double", |meta| {
+            let q = meta.query_selector(q);
+            let a = meta.query_advice(a, Rotation::cur());
+            let b = meta.query_advice(b, Rotation::cur());
+            vec![q * (b - a * Fr::from(2))]
+        });
+        meta.lookup_gated("range", q, |meta| {
+            vec![(meta.query_advice(a, Rotation::cur()), table)]
+        });
+        GatedConfig { a, b, q, table }
+    }
+
+    fn synthesize(
+        &self,
+        config: GatedConfig,
+        mut layouter: impl Layouter<Fr>,
+    ) -> Result<(), Error> {
+        layouter.assign_table(
+            || "range",
+            |mut table| {
+                for value in 0..8 {
+                    table.assign_cell(
+                        || "value",
+                        config.table,
+                        value,
+                        || Value::known(Fr::from(value as u64)),
+                    )?;
+                }
+                Ok(())
+            },
+        )?;
+        layouter.assign_region(
+            || "values",
+            |mut region| {
+                let rows = self.values.iter().map(|value| (*value, true));
+                for (row, (value, enabled)) in rows.chain([(100, false)]).enumerate() {
+                    if enabled {
+                        config.q.enable(&mut region, row)?;
+                    }
+                    let value = Fr::from(value);
+                    region.assign_advice(|| "a", config.a, row, || Value::known(value))?;
+                    region.assign_advice(|| "b", config.b, row, || Value::known(value + value))?;
+                }
+                Ok(())
+            },
+        )
+    }
+}
+
+const VALID: GatedCircuit = GatedCircuit { values: [0, 3, 7] };
+
+#[test]
+fn mock_prover_enforces_gated_lookups() {
+    MockProver::run(K, &VALID, vec![])
+        .unwrap()
+        .assert_satisfied();
+
+    let circuit = GatedCircuit { values: [0, 8, 7] };
+    let failures = MockProver::run(K, &circuit, vec![])
+        .unwrap()
+        .verify()
+        .unwrap_err();
+    assert_matches!(
+        &failures[..],
+        [VerifyFailure::Lookup { name, .. }] if name == "range"
+    );
+}
+
+#[test]
+fn gated_lookups_prove_and_verify() {
+    let params = ParamsKZG::<Bn256>::new(K);
+    for compress_selectors in [true, false] {
+        let vk = keygen_vk_custom(&params, &VALID, compress_selectors).unwrap();
+        // The lookup input `q * a` has degree 2, as with a complex selector.
+        assert_eq!(vk.cs().degree(), 5);
+        let pk = keygen_pk(&params, vk, &VALID).unwrap();
+
+        let mut transcript = Blake2bWrite::<_, G1Affine, Challenge255<_>>::init(vec![]);
+        create_proof::<KZGCommitmentScheme<Bn256>, ProverSHPLONK<_>, _, _, _, _>(
+            &params,
+            &pk,
+            &[VALID],
+            &[&[]],
+            OsRng,
+            &mut transcript,
+        )
+        .expect("prover should not fail");
+        let proof = transcript.finalize();
+
+        let mut transcript = Blake2bRead::<_, G1Affine, Challenge255<_>>::init(&proof[..]);
+        verify_proof::<_, VerifierSHPLONK<_>, _, _, _>(
+            &params,
+            pk.get_vk(),
+            SingleStrategy::new(&params),
+            &[&[]],
+            &mut transcript,
+        )
+        .expect("proof should verify");
+    }
+}
@@ -0,0 +1,90 @@
+//! Failures in regions that are assigned within namespaces report the namespace path of the
+//! region.
+
+use halo2_proofs::circuit::{Layouter, SimpleFloorPlanner, Value};
+use halo2_proofs::dev::{FailureLocation, MockProver, VerifyFailure};
+use halo2_proofs::plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Selector};
+use halo2_proofs::poly::Rotation;
+use halo2curves::bn256::Fr;
+use halo2curves::ff::Field;
+
+const K: u32 = 4;
+
+#[derive(Clone)]
+struct ZeroConfig {
+    a: Column<Advice>,
+    q: Selector,
+}
+
+impl ZeroConfig {
+    fn assign(&self, mut layouter: impl Layouter<Fr>, value: Fr) -> Result<(), Error> {
+        layouter.assign_region(
+            || "decompose",
+            |mut region| {
+                self.q.enable(&mut region, 0)?;
+                region.assign_advice(|| "a", self.a, 0, || Value::known(value))?;
+                Ok(())
+            },
+        )
+    }
+}
+
+/// Enforces `q * a = 0`, in a satisfied region outside of any namespace, and in an unsatisfied
+/// region within the `sha256/message_schedule` namespace.
+#[derive(Clone, Default)]
+struct NamespacedCircuit;
+
+impl Circuit<Fr> for NamespacedCircuit {
+    type Config = ZeroConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+    #[cfg(feature = "circuit-params")]
+    type Params = ();
+
+    fn without_witnesses(&self) -> Self {
+        Self
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fr>) -> ZeroConfig {
+        let a = meta.advice_column();
+        let q = meta.selector();
+        meta.create_gate("zero", |meta| {
+            let a = meta.query_advice(a, Rotation::cur());
+            let q = meta.query_selector(q);
+            vec![q * a]
+        });
+        ZeroConfig { a, q }
+    }
+
+    fn synthesize(&self, config: ZeroConfig, mut layouter: impl Layouter<Fr>) -> Result<(), Error> {
+        config.assign(layouter.namespace(|| "padding"), Fr::ZERO)?;
+        {
+            let mut sha256 = layouter.namespace(|| "sha256");
+            config.assign(sha256.namespace(|| "message_schedule"), Fr::ONE)?;
+        }
+        config.assign(layouter.namespace(|| "tail"), Fr::ZERO)
+    }
+}
+
+#[test]
+fn failures_report_the_namespace_path() {
+    let prover = MockProver::run(K, &NamespacedCircuit, vec![]).unwrap();
+    let failures = prover.verify().unwrap_err();
+    assert_eq!(failures.len(), 1);
+
+    let VerifyFailure::ConstraintNotSatisfied {
+        location: FailureLocation::InRegion { region, offset },
+        ..
+    } = &failures[0]
+    else {
+        panic!("expected the gate to fail in a region, got {:?}", failures[0]);
+    };
+    assert_eq!(*offset, 0);
+    assert_eq!(region.path(), "sha256/message_schedule/decompose");
+    assert!(failures[0]
+        .to_string()
+        .contains("Region 1 ('sha256/message_schedule/decompose')"));
+
+    // The namespaces are popped when their layouter is dropped.
+    assert_eq!(prover.regions()[0].namespace(), ["padding"]);
+    assert_eq!(prover.regions()[2].namespace(), ["tail"]);
+}
@@ -0,0 +1,156 @@
+use halo2_proofs::circuit::{Layouter, SimpleFloorPlanner, Value};
+use halo2_proofs::plonk::{
+    create_proof_with_options, keygen_pk, keygen_vk, verify_proof, Advice, Circuit, Column,
+    ConstraintSystem, Error, Fixed, ProverOptions, ProvingKey,
+};
+use halo2_proofs::poly::commitment::ParamsProver;
+use halo2_proofs::poly::kzg::commitment::{KZGCommitmentScheme, ParamsKZG};
+use halo2_proofs::poly::kzg::multiopen::{ProverSHPLONK, VerifierSHPLONK};
+use halo2_proofs::poly::kzg::strategy::SingleStrategy;
+use halo2_proofs::poly::Rotation;
+use halo2_proofs::transcript::{
+    Blake2bRead, Blake2bWrite, Challenge255, TranscriptReadBuffer, TranscriptWriterBuffer,
+};
+use halo2curves::bn256::{Bn256, Fr, G1Affine};
+use rand_chacha::ChaCha20Rng;
+use rand_core::SeedableRng;
+
+const K: u32 = 6;
+const LOOKUPS: usize = 6;
+const SHUFFLES: usize = 3;
+const ROWS: usize = 40;
+
+#[derive(Clone)]
+struct ArgumentsConfig {
+    table: Column<Fixed>,
+    inputs: Vec<Column<Advice>>,
+    shuffled: Vec<Column<Advice>>,
+}
+
+/// Looks up each of its `LOOKUPS` input columns in a table of nibbles, and shuffles the first
+/// `SHUFFLES` of them into other columns.
+#[derive(Clone, Default)]
+struct ArgumentsCircuit {
+    offset: u64,
+}
+
+impl Circuit<Fr> for ArgumentsCircuit {
+    type Config = ArgumentsConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+    #[cfg(feature = "circuit-params")]
+    type Params = ();
+
+    fn without_witnesses(&self) -> Self {
+        self.clone()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fr>) -> ArgumentsConfig {
+        let table = meta.fixed_column();
+        let inputs: Vec<_> = (0..LOOKUPS).map(|_| meta.advice_column()).collect();
+        let shuffled: Vec<_> = (0..SHUFFLES).map(|_| meta.advice_column()).collect();
+
+        for (i, input) in inputs.iter().enumerate() {
+            meta.lookup_any(format!("nibble {i}"), |meta| {
+                let input = meta.query_advice(*input, Rotation::cur());
+                let table = meta.query_fixed(table, Rotation::cur());
+                vec![(input, table)]
+            });
+        }
+        for (i, (input, shuffled)) in inputs.iter().zip(shuffled.iter()).enumerate() {
+            meta.shuffle(format!("shuffle {i}"), |meta| {
+                let input = meta.query_advice(*input, Rotation::cur());
+                let shuffled = meta.query_advice(*shuffled, Rotation::cur());
+                vec![(input, shuffled)]
+            });
+        }
+
+        ArgumentsConfig {
+            table,
+            inputs,
+            shuffled,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: ArgumentsConfig,
+        mut layouter: impl Layouter<Fr>,
+    ) -> Result<(), Error> {
+        layouter.assign_region(
+            || "arguments",
+            |mut region| {
+                let value = |column: usize, row: usize| {
+                    Value::known(Fr::from((self.offset + (column * row) as u64) % 16))
+                };
+                for row in 0..ROWS {
+                    let nibble = Value::known(Fr::from((row % 16) as u64));
+                    region.assign_fixed(|| "table", config.table, row, || nibble)?;
+                    for (column, input) in config.inputs.iter().enumerate() {
+                        region.assign_advice(|| "input", *input, row, || value(column, row))?;
+                    }
+                    for (column, shuffled) in config.shuffled.iter().enumerate() {
+                        region.assign_advice(
+                            || "shuffled",
+                            *shuffled,
+                            row,
+                            || value(column, ROWS - 1 - row),
+                        )?;
+                    }
+                }
+                Ok(())
+            },
+        )
+    }
+}
+
+fn prove(
+    params: &ParamsKZG<Bn256>,
+    pk: &ProvingKey<G1Affine>,
+    num_threads: Option<usize>,
+) -> Vec<u8> {
+    let circuits = [
+        ArgumentsCircuit { offset: 0 },
+        ArgumentsCircuit { offset: 7 },
+    ];
+    let mut transcript = Blake2bWrite::<_, _, Challenge255<_>>::init(vec![]);
+    create_proof_with_options::<KZGCommitmentScheme<Bn256>, ProverSHPLONK<_>, _, _, _, _>(
+        params,
+        pk,
+        &circuits,
+        &[&[], &[]],
+        ChaCha20Rng::seed_from_u64(0),
+        &mut transcript,
+        &ProverOptions {
+            num_threads,
+            ..Default::default()
+        },
+    )
+    .expect("prover should not fail");
+    transcript.finalize()
+}
+
+#[test]
+fn parallel_arguments_match_the_serial_proof() {
+    let params = ParamsKZG::<Bn256>::new(K);
+    let vk = keygen_vk(&params, &ArgumentsCircuit::default()).expect("keygen_vk should not fail");
+    let pk =
+        keygen_pk(&params, vk, &ArgumentsCircuit::default()).expect("keygen_pk should not fail");
+
+    let serial = prove(&params, &pk, Some(1));
+    for num_threads in [Some(2), Some(5), None] {
+        assert!(
+            prove(&params, &pk, num_threads) == serial,
+            "the proof with {num_threads:?} threads differs from the serial one"
+        );
+    }
+
+    let mut transcript = Blake2bRead::<_, _, Challenge255<_>>::init(&serial[..]);
+    verify_proof::<_, VerifierSHPLONK<_>, _, _, _>(
+        &params,
+        pk.get_vk(),
+        SingleStrategy::new(&params),
+        &[&[], &[]],
+        &mut transcript,
+    )
+    .expect("proof should verify");
+}
@@ -0,0 +1,132 @@
+mod common;
+
+use common::MulCircuit;
+use halo2_proofs::circuit::Value;
+use halo2_proofs::plonk::{create_proof, keygen_pk, keygen_vk, verify_proof};
+use halo2_proofs::poly::commitment::Params;
+use halo2_proofs::poly::kzg::commitment::{KZGCommitmentScheme, ParamsKZG};
+use halo2_proofs::poly::kzg::multiopen::{ProverSHPLONK, VerifierSHPLONK};
+use halo2_proofs::poly::kzg::strategy::SingleStrategy;
+use halo2_proofs::transcript::{
+    Blake2bRead, Blake2bWrite, Challenge255, TranscriptReadBuffer, TranscriptWriterBuffer,
+};
+use halo2_proofs::SerdeFormat;
+use halo2curves::bn256::{Bn256, Fr};
+use rand_chacha::ChaCha20Rng;
+use rand_core::SeedableRng;
+use std::io;
+
+const K: u32 = 4;
+const K_LARGE: u32 = 6;
+
+/// Returns parameters for `k`, all drawn from the same secret.
+fn params(k: u32) -> ParamsKZG<Bn256> {
+    ParamsKZG::setup(k, ChaCha20Rng::seed_from_u64(0))
+}
+
+fn to_bytes(params: &ParamsKZG<Bn256>, format: SerdeFormat) -> Vec<u8> {
+    let mut bytes = vec![];
+    params.write_custom(&mut bytes, format).unwrap();
+    bytes
+}
+
+/// Returns a proof that `2 * 3 = 6`, after checking that it verifies.
+fn prove(params: &ParamsKZG<Bn256>) -> Vec<u8> {
+    let vk = keygen_vk(params, &MulCircuit::default()).expect("keygen_vk should not fail");
+    let pk = keygen_pk(params, vk, &MulCircuit::default()).expect("keygen_pk should not fail");
+    let circuit = MulCircuit {
+        a: Value::known(Fr::from(2)),
+        b: Value::known(Fr::from(3)),
+    };
+    let instances: &[&[&[Fr]]] = &[&[&[Fr::from(6)]]];
+
+    let mut transcript = Blake2bWrite::<_, _, Challenge255<_>>::init(vec![]);
+    create_proof::<KZGCommitmentScheme<Bn256>, ProverSHPLONK<_>, _, _, _, _>(
+        params,
+        &pk,
+        &[circuit],
+        instances,
+        ChaCha20Rng::seed_from_u64(1),
+        &mut transcript,
+    )
+    .expect("prover should not fail");
+    let proof = transcript.finalize();
+
+    let mut transcript = Blake2bRead::<_, _, Challenge255<_>>::init(&proof[..]);
+    let strategy = SingleStrategy::new(params);
+    verify_proof::<_, VerifierSHPLONK<_>, _, _, _>(
+        params,
+        pk.get_vk(),
+        strategy,
+        instances,
+        &mut transcript,
+    )
+    .expect("proof should verify");
+    proof
+}
+
+#[test]
+fn downsize_matches_native_params() {
+    let native = params(K);
+    let downsized = params(K_LARGE).downsized(K);
+
+    assert_eq!(downsized.k(), K);
+    assert_eq!(
+        to_bytes(&downsized, SerdeFormat::RawBytes),
+        to_bytes(&native, SerdeFormat::RawBytes)
+    );
+    assert_eq!(prove(&downsized), prove(&native));
+
+    // The trait method downsizes in place.
+    let mut in_place = params(K_LARGE);
+    in_place.downsize(K);
+    assert_eq!(
+        to_bytes(&in_place, SerdeFormat::RawBytes),
+        to_bytes(&native, SerdeFormat::RawBytes)
+    );
+}
+
+#[test]
+#[should_panic(expected = "cannot downsize parameters for k = 4 to the larger k = 6")]
+fn downsize_to_larger_k_panics() {
+    let _ = params(K).downsized(K_LARGE);
+}
+
+#[test]
+fn read_partial_matches_native_params() {
+    let large = params(K_LARGE);
+    for format in [
+        SerdeFormat::Processed,
+        SerdeFormat::RawBytes,
+        SerdeFormat::RawBytesUnchecked,
+    ] {
+        let bytes = to_bytes(&large, format);
+        for k in [K, K_LARGE] {
+            let partial = ParamsKZG::<Bn256>::read_partial_custom(&mut &bytes[..], k, format)
+                .expect("reading the parameters should not fail");
+            assert_eq!(to_bytes(&partial, format), to_bytes(&params(k), format));
+        }
+    }
+
+    let bytes = to_bytes(&large, SerdeFormat::RawBytes);
+    let partial = ParamsKZG::<Bn256>::read_partial(&mut &bytes[..], K).unwrap();
+    assert_eq!(prove(&partial), prove(&params(K)));
+}
+
+#[test]
+fn read_partial_rejects_larger_k() {
+    let bytes = to_bytes(&params(K), SerdeFormat::RawBytes);
+    let err = ParamsKZG::<Bn256>::read_partial(&mut &bytes[..], K_LARGE).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    assert_eq!(
+        err.to_string(),
+        "parameters for k = 6 requested, but the buffer only holds parameters for k = 4"
+    );
+}
+
+#[test]
+fn read_partial_rejects_truncated_params() {
+    let bytes = to_bytes(&params(K_LARGE), SerdeFormat::RawBytes);
+    let err = ParamsKZG::<Bn256>::read_partial(&mut &bytes[..bytes.len() / 2], K).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+}
@@ -423,6 +423,7 @@ fn plonk_api() {
                 keygen_vk(&much_too_small_params, &empty_circuit),
                 Err(Error::NotEnoughRowsAvailable {
                     current_k,
+                    ..
                 }) if current_k == 1
             );
 
@@ -433,6 +434,7 @@ fn plonk_api() {
                 keygen_vk(&slightly_too_small_params, &empty_circuit),
                 Err(Error::NotEnoughRowsAvailable {
                     current_k,
+                    ..
                 }) if current_k == K - 1
             );
         }};
@@ -0,0 +1,151 @@
+mod common;
+
+use common::MulCircuit;
+use ff::{FromUniformBytes, WithSmallOrderMulGroup};
+use halo2_proofs::circuit::Value;
+use halo2_proofs::plonk::{
+    create_proof, create_proof_with_engine, keygen_pk, keygen_vk, verify_proof, ProvingKey,
+};
+use halo2_proofs::poly::commitment::{CommitmentScheme, ParamsProver, Prover};
+use halo2_proofs::poly::ipa::commitment::{IPACommitmentScheme, ParamsIPA};
+use halo2_proofs::poly::ipa::multiopen::ProverIPA;
+use halo2_proofs::poly::kzg::commitment::{KZGCommitmentScheme, ParamsKZG};
+use halo2_proofs::poly::kzg::multiopen::{ProverSHPLONK, VerifierSHPLONK};
+use halo2_proofs::poly::kzg::strategy::SingleStrategy;
+use halo2_proofs::poly::{CpuEngine, PolynomialEngine};
+use halo2_proofs::transcript::{
+    Blake2bRead, Blake2bWrite, Challenge255, TranscriptReadBuffer, TranscriptWriterBuffer,
+};
+use halo2curves::bn256::{Bn256, Fr};
+use halo2curves::pasta::EqAffine;
+use halo2curves::CurveAffine;
+use rand_chacha::ChaCha20Rng;
+use rand_core::SeedableRng;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+const K: u32 = 4;
+
+/// An engine which counts its calls, and delegates them to the [`CpuEngine`].
+#[derive(Debug, Default)]
+struct CountingEngine {
+    msm: AtomicUsize,
+    fft: AtomicUsize,
+    ifft: AtomicUsize,
+}
+
+impl CountingEngine {
+    fn counts(&self) -> (usize, usize, usize) {
+        (
+            self.msm.load(Ordering::SeqCst),
+            self.fft.load(Ordering::SeqCst),
+            self.ifft.load(Ordering::SeqCst),
+        )
+    }
+}
+
+impl<C: CurveAffine> PolynomialEngine<C> for CountingEngine {
+    fn msm(&self, coeffs: &[C::Scalar], bases: &[C]) -> C::Curve {
+        self.msm.fetch_add(1, Ordering::SeqCst);
+        PolynomialEngine::<C>::msm(&CpuEngine, coeffs, bases)
+    }
+
+    fn fft(&self, values: &mut [C::Scalar], omega: C::Scalar, log_n: u32) {
+        self.fft.fetch_add(1, Ordering::SeqCst);
+        PolynomialEngine::<C>::fft(&CpuEngine, values, omega, log_n)
+    }
+
+    fn ifft(&self, values: &mut [C::Scalar], omega_inv: C::Scalar, log_n: u32, divisor: C::Scalar) {
+        self.ifft.fetch_add(1, Ordering::SeqCst);
+        PolynomialEngine::<C>::ifft(&CpuEngine, values, omega_inv, log_n, divisor)
+    }
+}
+
+/// Returns the proving key, and the proofs that `2 * 3 = 6` created with `engine` and
+/// with [`create_proof`], from the same RNG seed.
+fn prove<'params, Scheme: CommitmentScheme, P: Prover<'params, Scheme>>(
+    params: &'params Scheme::ParamsProver,
+    engine: &CountingEngine,
+) -> (ProvingKey<Scheme::Curve>, Vec<u8>, Vec<u8>)
+where
+    Scheme::Scalar: WithSmallOrderMulGroup<3> + FromUniformBytes<64>,
+{
+    let vk = keygen_vk(params, &MulCircuit::default()).expect("keygen_vk should not fail");
+    let pk = keygen_pk(params, vk, &MulCircuit::default()).expect("keygen_pk should not fail");
+    let circuit = MulCircuit {
+        a: Value::known(Scheme::Scalar::from(2)),
+        b: Value::known(Scheme::Scalar::from(3)),
+    };
+    let instances: &[&[&[Scheme::Scalar]]] = &[&[&[Scheme::Scalar::from(6)]]];
+
+    let mut transcript = Blake2bWrite::<_, _, Challenge255<_>>::init(vec![]);
+    create_proof_with_engine::<Scheme, P, _, _, _, _>(
+        params,
+        &pk,
+        &[circuit.clone()],
+        instances,
+        ChaCha20Rng::seed_from_u64(0),
+        &mut transcript,
+        engine,
+    )
+    .expect("prover should not fail");
+    let with_engine = transcript.finalize();
+
+    let mut transcript = Blake2bWrite::<_, _, Challenge255<_>>::init(vec![]);
+    create_proof::<Scheme, P, _, _, _, _>(
+        params,
+        &pk,
+        &[circuit],
+        instances,
+        ChaCha20Rng::seed_from_u64(0),
+        &mut transcript,
+    )
+    .expect("prover should not fail");
+    (pk, with_engine, transcript.finalize())
+}
+
+/// The FFTs of a proof of [`MulCircuit`], as `(fft, ifft)`:
+/// - the instance and the 2 advice columns are interpolated, and then extended to the
+///   coset of the extended domain to evaluate `h(X)`,
+/// - `h(X)` is interpolated from the extended domain.
+const FFTS: (usize, usize) = (3, 4);
+
+#[test]
+fn engine_kzg() {
+    let params = ParamsKZG::<Bn256>::new(K);
+    let engine = CountingEngine::default();
+    let (pk, proof, expected) =
+        prove::<KZGCommitmentScheme<Bn256>, ProverSHPLONK<_>>(&params, &engine);
+    assert_eq!(proof, expected);
+
+    // The 2 advice columns, the random polynomial and the pieces of `h(X)` of the
+    // vanishing argument, and the 2 commitments of SHPLONK. The instance is not committed.
+    let h_pieces = pk.get_vk().get_domain().get_quotient_poly_degree();
+    assert_eq!(engine.counts(), (2 + 1 + h_pieces + 2, FFTS.0, FFTS.1));
+
+    let mut transcript = Blake2bRead::<_, _, Challenge255<_>>::init(&proof[..]);
+    let strategy = SingleStrategy::new(&params);
+    verify_proof::<_, VerifierSHPLONK<_>, _, _, _>(
+        &params,
+        pk.get_vk(),
+        strategy,
+        &[&[&[Fr::from(6)]]],
+        &mut transcript,
+    )
+    .expect("proof should verify");
+}
+
+#[test]
+fn engine_ipa() {
+    let params = ParamsIPA::<EqAffine>::new(K);
+    let engine = CountingEngine::default();
+    let (pk, proof, expected) =
+        prove::<IPACommitmentScheme<EqAffine>, ProverIPA<_>>(&params, &engine);
+    assert_eq!(proof, expected);
+
+    // The instance and the 2 advice columns, the random polynomial and the pieces of
+    // `h(X)` of the vanishing argument, and for the multi-open argument: `Q'(X)`, the
+    // random polynomial of the inner product argument, and 4 per round of it.
+    let h_pieces = pk.get_vk().get_domain().get_quotient_poly_degree();
+    let msm = 3 + 1 + h_pieces + 2 + 4 * K as usize;
+    assert_eq!(engine.counts(), (msm, FFTS.0, FFTS.1));
+}
@@ -0,0 +1,147 @@
+mod common;
+
+use common::MulCircuit;
+use halo2_proofs::circuit::Value;
+use halo2_proofs::plonk::{
+    create_proof, keygen_pk, keygen_vk, verify_proof, ProvingKey, VerificationError,
+};
+use halo2_proofs::poly::commitment::ParamsProver;
+use halo2_proofs::poly::kzg::commitment::{KZGCommitmentScheme, ParamsKZG};
+use halo2_proofs::poly::kzg::multiopen::{ProverSHPLONK, VerifierSHPLONK};
+use halo2_proofs::poly::kzg::strategy::SingleStrategy;
+use halo2_proofs::transcript::{
+    base_to_limbs, Blake2bRead, Challenge255, PoseidonChallenge, PoseidonRead, PoseidonSpec,
+    PoseidonSponge, PoseidonWrite, TranscriptReadBuffer, TranscriptWriterBuffer,
+};
+use halo2curves::bn256::{Bn256, Fr, G1Affine};
+use halo2curves::ff::PrimeField;
+use halo2curves::CurveAffine;
+use rand_chacha::ChaCha20Rng;
+use rand_core::SeedableRng;
+
+/// Parses a big-endian hexadecimal scalar.
+fn fr(hex: &str) -> Fr {
+    let mut repr = <Fr as PrimeField>::Repr::default();
+    for (byte, chunk) in repr.as_mut().iter_mut().rev().zip(hex.as_bytes().chunks(2)) {
+        *byte = u8::from_str_radix(std::str::from_utf8(chunk).unwrap(), 16).unwrap();
+    }
+    Fr::from_repr(repr).unwrap()
+}
+
+fn setup() -> (ParamsKZG<Bn256>, ProvingKey<G1Affine>, Vec<u8>) {
+    let params = ParamsKZG::<Bn256>::new(4);
+    let vk = keygen_vk(&params, &MulCircuit::default()).expect("keygen_vk should not fail");
+    let pk = keygen_pk(&params, vk, &MulCircuit::default()).expect("keygen_pk should not fail");
+    let circuit = MulCircuit {
+        a: Value::known(Fr::from(2)),
+        b: Value::known(Fr::from(3)),
+    };
+    let mut transcript = PoseidonWrite::<_, _, PoseidonChallenge<_>, 3, 2>::init(vec![]);
+    create_proof::<KZGCommitmentScheme<Bn256>, ProverSHPLONK<_>, _, _, _, _>(
+        &params,
+        &pk,
+        &[circuit],
+        &[&[&[Fr::from(6)]]],
+        ChaCha20Rng::seed_from_u64(0),
+        &mut transcript,
+    )
+    .expect("prover should not fail");
+    (params, pk, transcript.finalize())
+}
+
+#[test]
+fn permutation_matches_circomlib() {
+    // poseidon([1, 2]) of circomlib.
+    let spec = PoseidonSpec::<Fr, 3>::new();
+    assert_eq!((spec.full_rounds(), spec.partial_rounds()), (8, 57));
+    assert_eq!(
+        spec.round_constants()[0][0],
+        fr("0ee9a592ba9a9518d05986d656f40c2114c4993c11bb29938d21d47304cd8e6e")
+    );
+    assert_eq!(
+        spec.mds()[0][0],
+        fr("109b7f411ba0e4c9b2b70caf5c36a7b194be7c11ad24378bfedb68592ba8118b")
+    );
+
+    let mut state = [Fr::from(0), Fr::from(1), Fr::from(2)];
+    spec.permute(&mut state);
+    assert_eq!(
+        state[0],
+        fr("115cc0f5e7d690413df64c6b9662e9cf2a3617f2743245519e19607a4417189a")
+    );
+}
+
+#[test]
+fn sponge_vectors() {
+    let mut sponge = PoseidonSponge::<Fr, 3, 2>::new(PoseidonSpec::new());
+    sponge.absorb(Fr::from(1));
+    sponge.absorb(Fr::from(2));
+    assert_eq!(
+        sponge.squeeze(),
+        fr("074deaf2e1953ac63a1fc256f41748506563bacf3968c25736c27b49a591d218")
+    );
+
+    let generator = G1Affine::generator().coordinates().unwrap();
+    let limbs: Vec<Fr> = base_to_limbs::<G1Affine>(generator.x())
+        .into_iter()
+        .chain(base_to_limbs::<G1Affine>(generator.y()))
+        .collect();
+    assert_eq!(limbs, [1, 0, 2, 0].map(Fr::from));
+    for limb in limbs {
+        sponge.absorb(limb);
+    }
+    assert_eq!(
+        sponge.squeeze(),
+        fr("22cee63b306abb217738217e2590b5476211791f84eb014fca0d60fa9afcfc47")
+    );
+    assert_eq!(
+        sponge.squeeze(),
+        fr("01c194de60786cf0ec4696f5c3fc6fd5c4bed343de37b0120abaf062239c3454")
+    );
+
+    sponge.absorb(Fr::from(3));
+    assert_eq!(
+        sponge.squeeze(),
+        fr("08819e3b4815999d59ca0d47263a9196a80f85d9e33d5afe2ff3b015d23cba4a")
+    );
+}
+
+#[test]
+fn proof_verifies_with_the_same_transcript_only() {
+    let (params, pk, proof) = setup();
+    let instances: &[&[&[Fr]]] = &[&[&[Fr::from(6)]]];
+
+    let mut transcript = PoseidonRead::<_, _, PoseidonChallenge<_>, 3, 2>::init(&proof[..]);
+    verify_proof::<_, VerifierSHPLONK<_>, _, _, _>(
+        &params,
+        pk.get_vk(),
+        SingleStrategy::new(&params),
+        instances,
+        &mut transcript,
+    )
+    .expect("proof should verify");
+
+    let mut transcript = PoseidonRead::<_, _, PoseidonChallenge<_>, 4, 3>::init(&proof[..]);
+    assert!(matches!(
+        verify_proof::<_, VerifierSHPLONK<_>, _, _, _>(
+            &params,
+            pk.get_vk(),
+            SingleStrategy::new(&params),
+            instances,
+            &mut transcript,
+        ),
+        Err(VerificationError::FinalCheck | VerificationError::Opening)
+    ));
+
+    let mut transcript = Blake2bRead::<_, _, Challenge255<_>>::init(&proof[..]);
+    assert!(matches!(
+        verify_proof::<_, VerifierSHPLONK<_>, _, _, _>(
+            &params,
+            pk.get_vk(),
+            SingleStrategy::new(&params),
+            instances,
+            &mut transcript,
+        ),
+        Err(VerificationError::FinalCheck | VerificationError::Opening)
+    ));
+}
@@ -0,0 +1,171 @@
+//! A range check against a row counter that is computed from the row index at keygen,
+//! instead of being assigned through the layouter.
+
+use assert_matches::assert_matches;
+use halo2_frontend::circuit::compile_circuit;
+use halo2_frontend::dev::CellValue;
+use halo2_proofs::circuit::{Layouter, SimpleFloorPlanner, Value};
+use halo2_proofs::dev::MockProver;
+use halo2_proofs::plonk::{
+    keygen_vk, Advice, Circuit, Column, ConstraintSystem, Error, Fixed, Selector,
+};
+use halo2_proofs::poly::commitment::ParamsProver;
+use halo2_proofs::poly::kzg::commitment::ParamsKZG;
+use halo2_proofs::poly::Rotation;
+use halo2curves::bn256::{Bn256, Fr, G1Affine};
+use halo2curves::ff::Field;
+
+const K: u32 = 4;
+
+#[derive(Clone)]
+struct RangeConfig {
+    value: Column<Advice>,
+    row_index: Column<Fixed>,
+    q_range: Selector,
+}
+
+/// Checks that each of `values` is below the number of usable rows, by looking it up in a
+/// row counter. If `assign_row_index` is set, the circuit also assigns a cell of the
+/// counter, which is rejected.
+#[derive(Clone, Default)]
+struct RangeCircuit {
+    values: Vec<u64>,
+    assign_row_index: bool,
+}
+
+impl Circuit<Fr> for RangeCircuit {
+    type Config = RangeConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+    #[cfg(feature = "circuit-params")]
+    type Params = ();
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            values: vec![],
+            assign_row_index: self.assign_row_index,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fr>) -> RangeConfig {
+        let value = meta.advice_column();
+        let q_range = meta.complex_selector();
+        let row_index = meta.preprocessed_fixed_column("row index", |row| Fr::from(row as u64));
+
+        meta.lookup_any("range", |meta| {
+            let value = meta.query_advice(value, Rotation::cur());
+            let q_range = meta.query_selector(q_range);
+            let row_index = meta.query_fixed(row_index, Rotation::cur());
+            vec![(q_range * value, row_index)]
+        });
+
+        RangeConfig {
+            value,
+            row_index,
+            q_range,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: RangeConfig,
+        mut layouter: impl Layouter<Fr>,
+    ) -> Result<(), Error> {
+        layouter.assign_region(
+            || "values",
+            |mut region| {
+                for (offset, value) in self.values.iter().enumerate() {
+                    config.q_range.enable(&mut region, offset)?;
+                    let value = Value::known(Fr::from(*value));
+                    region.assign_advice(|| "value", config.value, offset, || value)?;
+                }
+                if self.assign_row_index {
+                    region.assign_fixed(
+                        || "row index",
+                        config.row_index,
+                        0,
+                        || Value::known(Fr::ZERO),
+                    )?;
+                }
+                Ok(())
+            },
+        )
+    }
+}
+
+#[test]
+fn range_check_against_row_index() {
+    let usable_rows = {
+        let mut cs = ConstraintSystem::<Fr>::default();
+        RangeCircuit::configure(&mut cs);
+        cs.usable_rows(K)
+    };
+    let max = usable_rows.end as u64 - 1;
+
+    let circuit = RangeCircuit {
+        values: vec![0, 3, max],
+        assign_row_index: false,
+    };
+    let prover = MockProver::run(K, &circuit, vec![]).unwrap();
+    assert_eq!(prover.verify(), Ok(()));
+
+    let circuit = RangeCircuit {
+        values: vec![0, 3, max + 1],
+        assign_row_index: false,
+    };
+    let prover = MockProver::run(K, &circuit, vec![]).unwrap();
+    assert!(prover.verify().is_err());
+}
+
+#[test]
+fn keygen_and_mock_prover_agree() {
+    let circuit = RangeCircuit {
+        values: vec![1, 2],
+        assign_row_index: false,
+    };
+    let config = RangeCircuit::configure(&mut ConstraintSystem::default());
+    let (compiled, _, cs) = compile_circuit(K, &circuit, false).unwrap();
+    let prover = MockProver::run(K, &circuit, vec![]).unwrap();
+
+    let keygen = &compiled.preprocessing.fixed[config.row_index.index()];
+    let mock: Vec<_> = prover
+        .fixed_values(config.row_index)
+        .iter()
+        .map(|cell| match cell {
+            CellValue::Assigned(value) => *value,
+            cell => panic!("unexpected cell {cell:?}"),
+        })
+        .collect();
+    assert_eq!(keygen, &mock);
+
+    // The usable rows hold their index, and the other rows hold zero.
+    let usable_rows = cs.usable_rows(K);
+    for (row, value) in keygen.iter().enumerate() {
+        let expected = if usable_rows.contains(&row) {
+            Fr::from(row as u64)
+        } else {
+            Fr::ZERO
+        };
+        assert_eq!(*value, expected);
+    }
+}
+
+#[test]
+fn assigning_preprocessed_column_is_rejected() {
+    let circuit = RangeCircuit {
+        values: vec![1],
+        assign_row_index: true,
+    };
+    let config = RangeCircuit::configure(&mut ConstraintSystem::default());
+
+    let result = MockProver::run(K, &circuit, vec![]).map_err(Error::into_root);
+    assert_matches!(
+        result,
+        Err(Error::PreprocessedFixedAssigned(column)) if column == config.row_index
+    );
+
+    let params = ParamsKZG::<Bn256>::new(K);
+    assert_matches!(
+        keygen_vk::<G1Affine, _, _>(&params, &circuit).map_err(Error::into_root),
+        Err(Error::PreprocessedFixedAssigned(_))
+    );
+}
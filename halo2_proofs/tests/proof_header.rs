@@ -0,0 +1,93 @@
+mod common;
+
+use assert_matches::assert_matches;
+use common::{setup, MulCircuit};
+use halo2_proofs::circuit::Value;
+use halo2_proofs::plonk::proof::{read_with_header, write_with_header};
+use halo2_proofs::plonk::{create_proof, verify_proof, Error, ProvingKey};
+use halo2_proofs::poly::kzg::commitment::{KZGCommitmentScheme, ParamsKZG};
+use halo2_proofs::poly::kzg::multiopen::{ProverSHPLONK, VerifierSHPLONK};
+use halo2_proofs::poly::kzg::strategy::SingleStrategy;
+use halo2_proofs::transcript::{
+    Blake2bRead, Blake2bWrite, Challenge255, TranscriptReadBuffer, TranscriptWriterBuffer,
+};
+use halo2curves::bn256::{Bn256, Fr, G1Affine};
+use rand_core::OsRng;
+
+/// Returns a raw proof that `2 * 3 = 6`.
+fn prove(params: &ParamsKZG<Bn256>, pk: &ProvingKey<G1Affine>) -> Vec<u8> {
+    let circuit = MulCircuit {
+        a: Value::known(Fr::from(2)),
+        b: Value::known(Fr::from(3)),
+    };
+    let mut transcript = Blake2bWrite::<_, _, Challenge255<_>>::init(vec![]);
+    create_proof::<KZGCommitmentScheme<Bn256>, ProverSHPLONK<_>, _, _, _, _>(
+        params,
+        pk,
+        &[circuit],
+        &[&[&[Fr::from(6)]]],
+        OsRng,
+        &mut transcript,
+    )
+    .expect("prover should not fail");
+    transcript.finalize()
+}
+
+#[test]
+fn proof_header_round_trip() {
+    let (params, pk) = setup(4);
+    let vk = pk.get_vk();
+    let proof = prove(&params, &pk);
+
+    let mut bytes = vec![];
+    write_with_header(vk, &proof, &mut bytes).unwrap();
+    assert_eq!(&bytes[..4], &4u32.to_le_bytes());
+    assert!(bytes.ends_with(&proof));
+    assert_eq!(vk.transcript_repr_hex().len(), 64);
+
+    let read = read_with_header(vk, &mut &bytes[..]).unwrap();
+    assert_eq!(read, proof);
+
+    let mut transcript = Blake2bRead::<_, _, Challenge255<_>>::init(&read[..]);
+    let strategy = SingleStrategy::new(&params);
+    verify_proof::<_, VerifierSHPLONK<_>, _, _, _>(
+        &params,
+        vk,
+        strategy,
+        &[&[&[Fr::from(6)]]],
+        &mut transcript,
+    )
+    .expect("proof should verify");
+}
+
+#[test]
+fn proof_header_mismatch() {
+    let (params, pk) = setup(4);
+    let (_, other_pk) = setup(5);
+    let vk = pk.get_vk();
+    let other_vk = other_pk.get_vk();
+    assert_ne!(vk.transcript_repr_hex(), other_vk.transcript_repr_hex());
+
+    let mut bytes = vec![];
+    write_with_header(vk, &prove(&params, &pk), &mut bytes).unwrap();
+
+    let err = read_with_header(other_vk, &mut &bytes[..]).unwrap_err();
+    let (expected, found) = (other_vk.transcript_repr_hex(), vk.transcript_repr_hex());
+    assert_matches!(
+        &err,
+        Error::VerifyingKeyMismatch {
+            expected_k: 5,
+            expected: e,
+            found_k: 4,
+            found: f,
+        } if *e == expected && *f == found
+    );
+    assert!(err.to_string().contains(&expected));
+    assert!(err.to_string().contains(&found));
+
+    // A truncated header cannot be read.
+    assert_matches!(
+        read_with_header(vk, &mut &bytes[..10]),
+        Err(Error::Transcript(_))
+    );
+}
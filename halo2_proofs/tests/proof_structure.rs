@@ -0,0 +1,265 @@
+use ff::{FromUniformBytes, PrimeField, WithSmallOrderMulGroup};
+use halo2_proofs::circuit::{Layouter, SimpleFloorPlanner, Value};
+use halo2_proofs::dev::{proof_structure, ProofSection, ProofStructure};
+use halo2_proofs::plonk::{
+    create_proof, keygen_pk, keygen_vk, Advice, Circuit, Column, ConstraintSystem, Error, Fixed,
+    Instance, ProvingKey, Selector,
+};
+use halo2_proofs::poly::commitment::{CommitmentScheme, ParamsProver, Prover};
+use halo2_proofs::poly::ipa::commitment::{IPACommitmentScheme, ParamsIPA};
+use halo2_proofs::poly::ipa::multiopen::{ProverIPA, VerifierIPA};
+use halo2_proofs::poly::kzg::commitment::{KZGCommitmentScheme, ParamsKZG};
+use halo2_proofs::poly::kzg::multiopen::{ProverSHPLONK, VerifierSHPLONK};
+use halo2_proofs::poly::Rotation;
+use halo2_proofs::transcript::{Blake2bWrite, Challenge255, TranscriptWriterBuffer};
+use halo2curves::bn256::Bn256;
+use halo2curves::pasta::EqAffine;
+use rand_core::OsRng;
+
+const K: u32 = 5;
+
+#[derive(Clone)]
+struct SquareConfig {
+    a: Column<Advice>,
+    b: Column<Advice>,
+    table: Column<Fixed>,
+    instance: Column<Instance>,
+    s: Selector,
+}
+
+/// Proves the knowledge of the square `b` of the public input `a`, where `a` is less
+/// than 8.
+#[derive(Clone, Default)]
+struct SquareCircuit<F: PrimeField> {
+    b: Value<F>,
+}
+
+impl<F: PrimeField> Circuit<F> for SquareCircuit<F> {
+    type Config = SquareConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+    #[cfg(feature = "circuit-params")]
+    type Params = ();
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> SquareConfig {
+        let a = meta.advice_column();
+        let b = meta.advice_column();
+        let table = meta.fixed_column();
+        let instance = meta.instance_column();
+        let s = meta.complex_selector();
+        meta.enable_equality(a);
+        meta.enable_equality(instance);
+
+        meta.create_gate("square", |meta| {
+            let a = meta.query_advice(a, Rotation::cur());
+            let b = meta.query_advice(b, Rotation::cur());
+            let s = meta.query_selector(s);
+            vec![s * (a.clone() * a - b)]
+        });
+        meta.lookup_any("range", |meta| {
+            let a = meta.query_advice(a, Rotation::cur());
+            let s = meta.query_selector(s);
+            let table = meta.query_fixed(table, Rotation::cur());
+            vec![(s * a, table)]
+        });
+
+        SquareConfig {
+            a,
+            b,
+            table,
+            instance,
+            s,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: SquareConfig,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        layouter.assign_region(
+            || "table",
+            |mut region| {
+                for i in 0..8 {
+                    region.assign_fixed(
+                        || "table",
+                        config.table,
+                        i,
+                        || Value::known(F::from(i as u64)),
+                    )?;
+                }
+                Ok(())
+            },
+        )?;
+        layouter.assign_region(
+            || "square",
+            |mut region| {
+                config.s.enable(&mut region, 0)?;
+                region.assign_advice_from_instance(|| "a", config.instance, 0, config.a, 0)?;
+                region.assign_advice(|| "b", config.b, 0, || self.b)?;
+                Ok(())
+            },
+        )
+    }
+}
+
+/// Returns the proving key and a proof that `3 * 3 = 9`.
+fn prove<'params, Scheme: CommitmentScheme, P: Prover<'params, Scheme>>(
+    params: &'params Scheme::ParamsProver,
+) -> (ProvingKey<Scheme::Curve>, Vec<u8>)
+where
+    Scheme::Scalar: WithSmallOrderMulGroup<3> + FromUniformBytes<64>,
+{
+    let circuit = SquareCircuit {
+        b: Value::known(Scheme::Scalar::from(9)),
+    };
+    let vk = keygen_vk(params, &circuit).expect("keygen_vk should not fail");
+    let pk = keygen_pk(params, vk, &circuit).expect("keygen_pk should not fail");
+
+    let mut transcript = Blake2bWrite::<_, _, Challenge255<_>>::init(vec![]);
+    create_proof::<Scheme, P, _, _, _, _>(
+        params,
+        &pk,
+        &[circuit],
+        &[&[&[Scheme::Scalar::from(3)]]],
+        OsRng,
+        &mut transcript,
+    )
+    .expect("prover should not fail");
+    (pk, transcript.finalize())
+}
+
+/// Checks that the sections cover `range` in order.
+fn assert_covers(sections: &[ProofSection], range: std::ops::Range<usize>) {
+    let mut offset = range.start;
+    for section in sections {
+        assert_eq!(
+            section.range.start, offset,
+            "{} is not contiguous",
+            section.label
+        );
+        assert!(!section.is_empty());
+        if !section.children.is_empty() {
+            assert_covers(&section.children, section.range.clone());
+        }
+        offset = section.range.end;
+    }
+    assert_eq!(offset, range.end);
+}
+
+fn section<'a>(structure: &'a ProofStructure, label: &str) -> &'a ProofSection {
+    structure
+        .sections
+        .iter()
+        .find(|section| section.label == label)
+        .unwrap_or_else(|| panic!("missing section {label}"))
+}
+
+fn labels(sections: &[ProofSection]) -> Vec<&str> {
+    sections
+        .iter()
+        .map(|section| section.label.as_str())
+        .collect()
+}
+
+#[test]
+fn proof_structure_kzg() {
+    let params = ParamsKZG::<Bn256>::new(K);
+    let (pk, proof) = prove::<KZGCommitmentScheme<Bn256>, ProverSHPLONK<_>>(&params);
+    let structure =
+        proof_structure::<KZGCommitmentScheme<Bn256>, VerifierSHPLONK<_>>(pk.get_vk(), &proof);
+
+    assert_eq!(structure.error, None);
+    assert_eq!(structure.len, proof.len());
+    assert_covers(&structure.sections, 0..proof.len());
+    assert_eq!(
+        labels(&structure.sections),
+        [
+            "advice commitments",
+            "lookup permuted commitments",
+            "permutation product commitments",
+            "lookup product commitments",
+            "vanishing commitments",
+            "evaluations",
+            "multiopen argument",
+        ]
+    );
+    // Points are 32 bytes long.
+    assert_eq!(section(&structure, "lookup permuted commitments").len(), 64);
+    assert_eq!(section(&structure, "lookup product commitments").len(), 32);
+    // SHPLONK opens everything with 2 points.
+    assert_eq!(section(&structure, "multiopen argument").len(), 64);
+    // The instance is committed, not evaluated.
+    assert_eq!(
+        labels(&section(&structure, "evaluations").children),
+        [
+            "advice",
+            "fixed",
+            "vanishing random polynomial",
+            "permutation sigmas",
+            "permutation products",
+            "lookups",
+        ]
+    );
+
+    let table = structure.to_string();
+    assert!(table.contains("multiopen argument"));
+    assert!(table.contains("  phase 0"));
+    let total = table.lines().last().unwrap();
+    assert!(total.starts_with("total") && total.ends_with(&format!(" {}", proof.len())));
+}
+
+#[test]
+fn proof_structure_ipa() {
+    let params = ParamsIPA::<EqAffine>::new(K);
+    let (pk, proof) = prove::<IPACommitmentScheme<EqAffine>, ProverIPA<_>>(&params);
+    let structure =
+        proof_structure::<IPACommitmentScheme<EqAffine>, VerifierIPA<_>>(pk.get_vk(), &proof);
+
+    assert_eq!(structure.error, None);
+    assert_covers(&structure.sections, 0..proof.len());
+    // The instance is evaluated.
+    let evaluations = section(&structure, "evaluations");
+    assert_eq!(evaluations.children[0].label, "instance");
+    assert_eq!(evaluations.children[0].len(), 32);
+}
+
+#[test]
+fn proof_structure_invalid_proof() {
+    let params = ParamsKZG::<Bn256>::new(K);
+    let (pk, proof) = prove::<KZGCommitmentScheme<Bn256>, ProverSHPLONK<_>>(&params);
+    let vk = pk.get_vk();
+
+    // A truncated proof is parsed up to the truncated value.
+    let truncated = &proof[..100];
+    let structure =
+        proof_structure::<KZGCommitmentScheme<Bn256>, VerifierSHPLONK<_>>(vk, truncated);
+    assert_covers(&structure.sections, 0..100);
+    assert_eq!(
+        labels(&structure.sections),
+        [
+            "advice commitments",
+            "lookup permuted commitments",
+            "unparsed"
+        ]
+    );
+    assert_eq!(section(&structure, "unparsed").range, 96..100);
+    let error = structure.error.as_ref().unwrap();
+    assert!(error.starts_with("lookup permuted commitments: the proof ends at byte 100"));
+    assert!(structure.to_string().contains("parsing stopped"));
+
+    // An invalid point stops parsing at that point.
+    let mut invalid = proof.clone();
+    invalid[32..64].fill(0xff);
+    let structure = proof_structure::<KZGCommitmentScheme<Bn256>, VerifierSHPLONK<_>>(vk, &invalid);
+    assert_covers(&structure.sections, 0..proof.len());
+    assert_eq!(section(&structure, "advice commitments").range, 0..32);
+    assert_eq!(section(&structure, "unparsed").range, 32..proof.len());
+    assert_eq!(
+        structure.error.as_deref(),
+        Some("phase 0: invalid point encoding at byte 32")
+    );
+}
@@ -0,0 +1,156 @@
+use halo2_proofs::circuit::{Layouter, SimpleFloorPlanner, Value};
+use halo2_proofs::plonk::{
+    create_proof, keygen_pk, keygen_vk, verify_proof, Advice, Circuit, Column, ConstraintSystem,
+    Error, Fixed, Instance, ProvingKey, Selector,
+};
+use halo2_proofs::poly::commitment::ParamsProver;
+use halo2_proofs::poly::kzg::commitment::{KZGCommitmentScheme, ParamsKZG};
+use halo2_proofs::poly::kzg::multiopen::{ProverSHPLONK, VerifierSHPLONK};
+use halo2_proofs::poly::kzg::strategy::SingleStrategy;
+use halo2_proofs::poly::Rotation;
+use halo2_proofs::transcript::{
+    Blake2bRead, Blake2bWrite, Challenge255, TranscriptReadBuffer, TranscriptWriterBuffer,
+};
+use halo2curves::bn256::{Bn256, Fr, G1Affine};
+use rand_chacha::ChaCha20Rng;
+use rand_core::SeedableRng;
+
+const K: u32 = 5;
+
+#[derive(Clone)]
+struct CacheConfig {
+    a: Column<Advice>,
+    b: Column<Advice>,
+    table: Column<Fixed>,
+    instance: Column<Instance>,
+    s: Selector,
+}
+
+/// Proves that the public input `a` is less than 8, with a lookup in a fixed table, and
+/// that the private `b` is a shuffle of the first 4 rows of the table.
+#[derive(Clone, Default)]
+struct CacheCircuit {
+    b: [Value<Fr>; 4],
+}
+
+impl Circuit<Fr> for CacheCircuit {
+    type Config = CacheConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+    #[cfg(feature = "circuit-params")]
+    type Params = ();
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fr>) -> CacheConfig {
+        let a = meta.advice_column();
+        let b = meta.advice_column();
+        let table = meta.fixed_column();
+        let instance = meta.instance_column();
+        let s = meta.complex_selector();
+        meta.enable_equality(a);
+        meta.enable_equality(instance);
+
+        meta.lookup_any("range", |meta| {
+            let a = meta.query_advice(a, Rotation::cur());
+            let table = meta.query_fixed(table, Rotation::cur());
+            vec![(a, table)]
+        });
+        meta.shuffle("shuffle", |meta| {
+            let b = meta.query_advice(b, Rotation::cur());
+            let table = meta.query_fixed(table, Rotation::cur());
+            let s = meta.query_selector(s);
+            vec![(s.clone() * b, s * table)]
+        });
+
+        CacheConfig {
+            a,
+            b,
+            table,
+            instance,
+            s,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: CacheConfig,
+        mut layouter: impl Layouter<Fr>,
+    ) -> Result<(), Error> {
+        layouter.assign_region(
+            || "main",
+            |mut region| {
+                for i in 0..8 {
+                    region.assign_fixed(
+                        || "table",
+                        config.table,
+                        i,
+                        || Value::known(Fr::from(i as u64)),
+                    )?;
+                }
+                for (i, b) in self.b.iter().enumerate() {
+                    config.s.enable(&mut region, i)?;
+                    region.assign_advice(|| "b", config.b, i, || *b)?;
+                }
+                region.assign_advice_from_instance(|| "a", config.instance, 0, config.a, 0)?;
+                Ok(())
+            },
+        )
+    }
+}
+
+fn circuit() -> CacheCircuit {
+    CacheCircuit {
+        b: [3, 1, 0, 2].map(|b| Value::known(Fr::from(b))),
+    }
+}
+
+fn prove(params: &ParamsKZG<Bn256>, pk: &ProvingKey<G1Affine>) -> Vec<u8> {
+    let mut transcript = Blake2bWrite::<_, _, Challenge255<_>>::init(vec![]);
+    create_proof::<KZGCommitmentScheme<Bn256>, ProverSHPLONK<_>, _, _, _, _>(
+        params,
+        pk,
+        &[circuit()],
+        &[&[&[Fr::from(5)]]],
+        ChaCha20Rng::seed_from_u64(0),
+        &mut transcript,
+    )
+    .expect("prover should not fail");
+    transcript.finalize()
+}
+
+#[test]
+fn warm_cache_keeps_proofs_unchanged() {
+    let params = ParamsKZG::<Bn256>::new(K);
+    let vk = keygen_vk(&params, &CacheCircuit::default()).expect("keygen_vk should not fail");
+    let mut pk =
+        keygen_pk(&params, vk, &CacheCircuit::default()).expect("keygen_pk should not fail");
+    assert!(pk.cache().is_none());
+    let cold = prove(&params, &pk);
+
+    pk.warm_cache();
+    let cache = pk.cache().expect("cache should be warm");
+    // The 2 permutation columns, and the table expressions of the lookup and the shuffle.
+    assert_eq!(cache.len(), 4 << K);
+
+    // The proofs are the same, whether the cache is warm or not.
+    for _ in 0..2 {
+        assert_eq!(prove(&params, &pk), cold);
+    }
+
+    let mut transcript = Blake2bRead::<_, _, Challenge255<_>>::init(&cold[..]);
+    let strategy = SingleStrategy::new(&params);
+    verify_proof::<_, VerifierSHPLONK<_>, _, _, _>(
+        &params,
+        pk.get_vk(),
+        strategy,
+        &[&[&[Fr::from(5)]]],
+        &mut transcript,
+    )
+    .expect("proof should verify");
+
+    pk.clear_cache();
+    assert!(pk.cache().is_none());
+    assert_eq!(prove(&params, &pk), cold);
+}
@@ -0,0 +1,126 @@
+mod common;
+
+use common::{setup, MulCircuit};
+use halo2_proofs::circuit::Value;
+use halo2_proofs::plonk::{
+    create_proof, create_proof_with_inputs, generate_witness, verify_proof, Circuit, Error,
+    ProverInput, ProvingKey,
+};
+use halo2_proofs::poly::kzg::commitment::{KZGCommitmentScheme, ParamsKZG};
+use halo2_proofs::poly::kzg::multiopen::{ProverSHPLONK, VerifierSHPLONK};
+use halo2_proofs::poly::kzg::strategy::SingleStrategy;
+use halo2_proofs::transcript::{
+    Blake2bRead, Blake2bWrite, Challenge255, TranscriptReadBuffer, TranscriptWriterBuffer,
+};
+use halo2curves::bn256::{Bn256, Fr, G1Affine};
+use rand_chacha::ChaCha20Rng;
+use rand_core::SeedableRng;
+
+const K: u32 = 4;
+
+/// The circuits proving `i * (i + 1)`, for `i` in `1..=4`, and their public inputs.
+fn circuits() -> (Vec<MulCircuit>, Vec<[Fr; 1]>) {
+    (1..=4u64)
+        .map(|i| {
+            let circuit = MulCircuit {
+                a: Value::known(Fr::from(i)),
+                b: Value::known(Fr::from(i + 1)),
+            };
+            (circuit, [Fr::from(i * (i + 1))])
+        })
+        .unzip()
+}
+
+fn prove_with_inputs(
+    params: &ParamsKZG<Bn256>,
+    pk: &ProvingKey<G1Affine>,
+    inputs: Vec<ProverInput<'_, Fr, MulCircuit>>,
+    instances: &[&[&[Fr]]],
+) -> Result<Vec<u8>, Error> {
+    let mut transcript = Blake2bWrite::<_, _, Challenge255<_>>::init(vec![]);
+    create_proof_with_inputs::<KZGCommitmentScheme<Bn256>, ProverSHPLONK<_>, _, _, _, _>(
+        params,
+        pk,
+        inputs,
+        instances,
+        ChaCha20Rng::seed_from_u64(0),
+        &mut transcript,
+    )?;
+    Ok(transcript.finalize())
+}
+
+#[test]
+fn mixed_inputs_give_the_same_proof() {
+    let (params, pk) = setup(K);
+    let (circuits, public_inputs) = circuits();
+    let instances: Vec<Vec<&[Fr]>> = public_inputs.iter().map(|c| vec![&c[..]]).collect();
+    let instances: Vec<&[&[Fr]]> = instances.iter().map(|i| &i[..]).collect();
+
+    let mut transcript = Blake2bWrite::<_, _, Challenge255<_>>::init(vec![]);
+    create_proof::<KZGCommitmentScheme<Bn256>, ProverSHPLONK<_>, _, _, _, _>(
+        &params,
+        &pk,
+        &circuits,
+        &instances,
+        ChaCha20Rng::seed_from_u64(0),
+        &mut transcript,
+    )
+    .expect("prover should not fail");
+    let expected = transcript.finalize();
+
+    let witness = |i: usize| {
+        generate_witness(K, &circuits[i], instances[i]).expect("witness generation should not fail")
+    };
+    let inputs = vec![
+        ProverInput::Witness(witness(0)),
+        ProverInput::Circuit(&circuits[1]),
+        ProverInput::Witness(witness(2)),
+        ProverInput::Circuit(&circuits[3]),
+    ];
+    assert_eq!(
+        prove_with_inputs(&params, &pk, inputs, &instances).unwrap(),
+        expected
+    );
+
+    let inputs = (0..4).map(|i| ProverInput::Witness(witness(i))).collect();
+    assert_eq!(
+        prove_with_inputs(&params, &pk, inputs, &instances).unwrap(),
+        expected
+    );
+
+    let mut transcript = Blake2bRead::<_, _, Challenge255<_>>::init(&expected[..]);
+    let strategy = SingleStrategy::new(&params);
+    verify_proof::<_, VerifierSHPLONK<_>, _, _, _>(
+        &params,
+        pk.get_vk(),
+        strategy,
+        &instances,
+        &mut transcript,
+    )
+    .expect("proof should verify");
+}
+
+#[test]
+fn mismatched_inputs_are_rejected() {
+    let (params, pk) = setup(K);
+    let (circuits, public_inputs) = circuits();
+    let instances: &[&[&[Fr]]] = &[&[&public_inputs[0]]];
+
+    // One instance for two inputs.
+    let inputs = vec![
+        ProverInput::Circuit(&circuits[0]),
+        ProverInput::Circuit(&circuits[1]),
+    ];
+    assert!(matches!(
+        prove_with_inputs(&params, &pk, inputs, instances),
+        Err(Error::InvalidInstances)
+    ));
+
+    // A witness for a different k.
+    let witness = generate_witness(K + 1, &circuits[0], instances[0]).unwrap();
+    let inputs = vec![ProverInput::Witness(witness)];
+    assert!(matches!(
+        prove_with_inputs(&params, &pk, inputs, instances),
+        Err(Error::Other(_))
+    ));
+}
@@ -0,0 +1,132 @@
+mod common;
+
+use common::{setup, MulCircuit, MulConfig};
+use halo2_proofs::circuit::{Layouter, SimpleFloorPlanner, Value};
+use halo2_proofs::plonk::{
+    create_proof, create_proof_with_options, verify_proof, Circuit, ConstraintSystem, Error,
+    ProverOptions, ProvingKey,
+};
+use halo2_proofs::poly::kzg::commitment::{KZGCommitmentScheme, ParamsKZG};
+use halo2_proofs::poly::kzg::multiopen::{ProverSHPLONK, VerifierSHPLONK};
+use halo2_proofs::poly::kzg::strategy::SingleStrategy;
+use halo2_proofs::transcript::{
+    Blake2bRead, Blake2bWrite, Challenge255, TranscriptReadBuffer, TranscriptWriterBuffer,
+};
+use halo2curves::bn256::{Bn256, Fr, G1Affine};
+use rand_chacha::ChaCha20Rng;
+use rand_core::SeedableRng;
+use std::rc::Rc;
+
+fn circuit() -> MulCircuit {
+    MulCircuit {
+        a: Value::known(Fr::from(2)),
+        b: Value::known(Fr::from(3)),
+    }
+}
+
+/// A [`MulCircuit`] behind an `Rc`, which is not `Sync`.
+#[derive(Default)]
+struct RcCircuit(Rc<MulCircuit>);
+
+impl Circuit<Fr> for RcCircuit {
+    type Config = MulConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+    #[cfg(feature = "circuit-params")]
+    type Params = ();
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fr>) -> MulConfig {
+        MulCircuit::configure(meta)
+    }
+
+    fn synthesize(&self, config: MulConfig, layouter: impl Layouter<Fr>) -> Result<(), Error> {
+        self.0.synthesize(config, layouter)
+    }
+}
+
+/// Proves that `2 * 3 = 6` with the given options, or in the global thread pool.
+fn prove(
+    params: &ParamsKZG<Bn256>,
+    pk: &ProvingKey<G1Affine>,
+    options: Option<ProverOptions>,
+) -> Vec<u8> {
+    let mut transcript = Blake2bWrite::<_, _, Challenge255<_>>::init(vec![]);
+    let rng = ChaCha20Rng::seed_from_u64(0);
+    let instances: &[&[&[Fr]]] = &[&[&[Fr::from(6)]]];
+    match options {
+        Some(options) => {
+            create_proof_with_options::<KZGCommitmentScheme<Bn256>, ProverSHPLONK<_>, _, _, _, _>(
+                params,
+                pk,
+                &[circuit()],
+                instances,
+                rng,
+                &mut transcript,
+                &options,
+            )
+        }
+        None => create_proof::<KZGCommitmentScheme<Bn256>, ProverSHPLONK<_>, _, _, _, _>(
+            params,
+            pk,
+            &[circuit()],
+            instances,
+            rng,
+            &mut transcript,
+        ),
+    }
+    .expect("prover should not fail");
+    transcript.finalize()
+}
+
+#[test]
+fn proofs_do_not_depend_on_num_threads() {
+    let (params, pk) = setup(6);
+    let proof = prove(&params, &pk, None);
+
+    for num_threads in [None, Some(1), Some(2)] {
+        let options = ProverOptions {
+            num_threads,
+            ..Default::default()
+        };
+        assert_eq!(prove(&params, &pk, Some(options)), proof);
+    }
+
+    let mut transcript = Blake2bRead::<_, _, Challenge255<_>>::init(&proof[..]);
+    let strategy = SingleStrategy::new(&params);
+    verify_proof::<_, VerifierSHPLONK<_>, _, _, _>(
+        &params,
+        pk.get_vk(),
+        strategy,
+        &[&[&[Fr::from(6)]]],
+        &mut transcript,
+    )
+    .expect("proof should verify");
+}
+
+#[test]
+fn circuits_need_not_be_sync() {
+    let (params, pk) = setup(6);
+    let proof = prove(&params, &pk, None);
+
+    let circuits = [RcCircuit(Rc::new(circuit()))];
+    for num_threads in [None, Some(2)] {
+        let mut transcript = Blake2bWrite::<_, _, Challenge255<_>>::init(vec![]);
+        create_proof_with_options::<KZGCommitmentScheme<Bn256>, ProverSHPLONK<_>, _, _, _, _>(
+            &params,
+            &pk,
+            &circuits,
+            &[&[&[Fr::from(6)]]],
+            ChaCha20Rng::seed_from_u64(0),
+            &mut transcript,
+            &ProverOptions {
+                num_threads,
+                ..Default::default()
+            },
+        )
+        .expect("prover should not fail");
+        assert_eq!(transcript.finalize(), proof);
+    }
+}
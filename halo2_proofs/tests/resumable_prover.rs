@@ -0,0 +1,239 @@
+use halo2_frontend::circuit::{compile_circuit, WitnessCalculator};
+use halo2_proofs::circuit::{Layouter, SimpleFloorPlanner, Value};
+use halo2_proofs::plonk::{
+    create_proof, keygen_pk, keygen_vk, verify_proof, Advice, Challenge, ChallengeValues, Circuit,
+    Column, ConstraintSystem, Error, FirstPhase, ProverState, ProvingKey, SecondPhase, Selector,
+};
+use halo2_proofs::poly::commitment::ParamsProver;
+use halo2_proofs::poly::kzg::commitment::{KZGCommitmentScheme, ParamsKZG};
+use halo2_proofs::poly::kzg::multiopen::{ProverSHPLONK, VerifierSHPLONK};
+use halo2_proofs::poly::kzg::strategy::SingleStrategy;
+use halo2_proofs::poly::Rotation;
+use halo2_proofs::transcript::{
+    Blake2bRead, Blake2bWrite, Challenge255, TranscriptReadBuffer, TranscriptWriterBuffer,
+};
+use halo2curves::bn256::{Bn256, Fr, G1Affine};
+use rand_chacha::ChaCha20Rng;
+use rand_core::SeedableRng;
+
+const K: u32 = 4;
+
+type State<'a, 'params> = ProverState<
+    'a,
+    'params,
+    KZGCommitmentScheme<Bn256>,
+    ProverSHPLONK<'params, Bn256>,
+    Challenge255<G1Affine>,
+    ChaCha20Rng,
+    Blake2bWrite<Vec<u8>, G1Affine, Challenge255<G1Affine>>,
+>;
+
+#[derive(Clone)]
+struct ScaleConfig {
+    a: Column<Advice>,
+    b: Column<Advice>,
+    s: Selector,
+    challenge: Challenge,
+}
+
+/// Scales `a`, committed in the first phase, by a challenge squeezed after it, into `b`.
+#[derive(Clone, Default)]
+struct ScaleCircuit {
+    a: Value<Fr>,
+}
+
+impl Circuit<Fr> for ScaleCircuit {
+    type Config = ScaleConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+    #[cfg(feature = "circuit-params")]
+    type Params = ();
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fr>) -> ScaleConfig {
+        let a = meta.advice_column_in(FirstPhase);
+        let b = meta.advice_column_in(SecondPhase);
+        let s = meta.selector();
+        let challenge = meta.challenge_usable_after(FirstPhase);
+
+        meta.create_gate("scale", |meta| {
+            let a = meta.query_advice(a, Rotation::cur());
+            let b = meta.query_advice(b, Rotation::cur());
+            let challenge = meta.query_challenge(challenge);
+            let s = meta.query_selector(s);
+            vec![s * (b - a * challenge)]
+        });
+
+        ScaleConfig { a, b, s, challenge }
+    }
+
+    fn synthesize(
+        &self,
+        config: ScaleConfig,
+        mut layouter: impl Layouter<Fr>,
+    ) -> Result<(), Error> {
+        let challenge = layouter.get_challenge(config.challenge);
+        layouter.assign_region(
+            || "scale",
+            |mut region| {
+                config.s.enable(&mut region, 0)?;
+                region.assign_advice(|| "a", config.a, 0, || self.a)?;
+                region.assign_advice(|| "b", config.b, 0, || self.a * challenge)?;
+                Ok(())
+            },
+        )
+    }
+}
+
+fn circuits() -> Vec<ScaleCircuit> {
+    [3, 5]
+        .map(|a| ScaleCircuit {
+            a: Value::known(Fr::from(a)),
+        })
+        .to_vec()
+}
+
+fn setup() -> (ParamsKZG<Bn256>, ProvingKey<G1Affine>) {
+    let params = ParamsKZG::<Bn256>::new(K);
+    let vk = keygen_vk(&params, &ScaleCircuit::default()).expect("keygen_vk should not fail");
+    let pk = keygen_pk(&params, vk, &ScaleCircuit::default()).expect("keygen_pk should not fail");
+    (params, pk)
+}
+
+fn one_shot_proof(params: &ParamsKZG<Bn256>, pk: &ProvingKey<G1Affine>) -> Vec<u8> {
+    let mut transcript = Blake2bWrite::<_, _, Challenge255<_>>::init(vec![]);
+    create_proof::<KZGCommitmentScheme<Bn256>, ProverSHPLONK<_>, _, _, _, _>(
+        params,
+        pk,
+        &circuits(),
+        &[&[], &[]],
+        ChaCha20Rng::seed_from_u64(0),
+        &mut transcript,
+    )
+    .expect("prover should not fail");
+    transcript.finalize()
+}
+
+fn verify(params: &ParamsKZG<Bn256>, pk: &ProvingKey<G1Affine>, proof: &[u8]) {
+    let mut transcript = Blake2bRead::<_, _, Challenge255<_>>::init(proof);
+    verify_proof::<_, VerifierSHPLONK<_>, _, _, _>(
+        params,
+        pk.get_vk(),
+        SingleStrategy::new(params),
+        &[&[], &[]],
+        &mut transcript,
+    )
+    .expect("proof should verify");
+}
+
+#[test]
+fn resumed_proof_matches_one_shot_proof() {
+    let (params, pk) = setup();
+    let circuits = circuits();
+    let (_, config, cs) = compile_circuit(K, &circuits[0], true).expect("circuit should compile");
+    let mut calculators: Vec<_> = circuits
+        .iter()
+        .map(|circuit| WitnessCalculator::new(K, circuit, &config, &cs, &[]))
+        .collect();
+
+    let mut transcript = Blake2bWrite::<_, _, Challenge255<_>>::init(vec![]);
+    let mut state = State::new(
+        &params,
+        &pk,
+        &[&[], &[]],
+        ChaCha20Rng::seed_from_u64(0),
+        &mut transcript,
+    )
+    .expect("prover should not fail");
+    assert_eq!(state.phases(), vec![0, 1]);
+
+    let mut challenges = ChallengeValues::default();
+    for phase in state.phases() {
+        let advice = calculators
+            .iter_mut()
+            .map(|calculator| calculator.next_phase(&challenges))
+            .collect::<Result<Vec<_>, _>>()
+            .expect("witness should be computed");
+        challenges = state
+            .commit_phase(phase, advice)
+            .expect("phase should be committed");
+    }
+    state.finalize().expect("prover should not fail");
+
+    let proof = transcript.finalize();
+    assert_eq!(proof, one_shot_proof(&params, &pk));
+    verify(&params, &pk, &proof);
+}
+
+#[test]
+fn second_phase_can_be_computed_outside_the_frontend() {
+    let (params, pk) = setup();
+    let (_, config, cs) =
+        compile_circuit(K, &ScaleCircuit::default(), true).expect("circuit should compile");
+    let n = 1 << K;
+
+    let mut transcript = Blake2bWrite::<_, _, Challenge255<_>>::init(vec![]);
+    let mut state = State::new(
+        &params,
+        &pk,
+        &[&[], &[]],
+        ChaCha20Rng::seed_from_u64(0),
+        &mut transcript,
+    )
+    .expect("prover should not fail");
+
+    let column = |value: u64| {
+        let mut column = vec![Fr::from(0); n];
+        column[0] = Fr::from(value);
+        column
+    };
+    let challenges = state
+        .commit_phase(
+            0,
+            vec![vec![Some(column(3)), None], vec![Some(column(5)), None]],
+        )
+        .expect("phase should be committed");
+    let challenge = challenges
+        .get(config.challenge)
+        .expect("challenge should be squeezed after the first phase");
+    assert_eq!(challenges.as_map().len(), cs.num_challenges());
+
+    let scaled = |value: u64| {
+        let mut column = vec![Fr::from(0); n];
+        column[0] = Fr::from(value) * challenge;
+        column
+    };
+    state
+        .commit_phase(
+            1,
+            vec![vec![None, Some(scaled(3))], vec![None, Some(scaled(5))]],
+        )
+        .expect("phase should be committed");
+    state.finalize().expect("prover should not fail");
+
+    let proof = transcript.finalize();
+    assert_eq!(proof, one_shot_proof(&params, &pk));
+    verify(&params, &pk, &proof);
+}
+
+#[test]
+fn phases_must_be_committed_in_order() {
+    let (params, pk) = setup();
+    let mut transcript = Blake2bWrite::<_, _, Challenge255<_>>::init(vec![]);
+    let mut state = State::new(
+        &params,
+        &pk,
+        &[&[]],
+        ChaCha20Rng::seed_from_u64(0),
+        &mut transcript,
+    )
+    .expect("prover should not fail");
+
+    assert!(matches!(
+        state.commit_phase(1, vec![vec![None, Some(vec![Fr::from(0); 1 << K])]]),
+        Err(Error::Other(_))
+    ));
+    assert!(matches!(state.finalize(), Err(Error::Other(_))));
+}
@@ -0,0 +1,121 @@
+//! The assertion helpers of `MockProver` panic with a message that lists a bounded number of
+//! failures, with their regions and column annotations.
+
+use std::ops::Range;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+use halo2_proofs::circuit::{Layouter, SimpleFloorPlanner, Value};
+use halo2_proofs::dev::MockProver;
+use halo2_proofs::plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Selector};
+use halo2_proofs::poly::Rotation;
+use halo2curves::bn256::Fr;
+use halo2curves::ff::Field;
+
+const K: u32 = 5;
+
+/// Enforces `q * a = 0` on the rows `0..10` of the `values` region, where `a` is one on the
+/// rows `0..8` and zero on the rows `8..10`.
+#[derive(Clone, Default)]
+struct ZeroCircuit;
+
+impl Circuit<Fr> for ZeroCircuit {
+    type Config = (Column<Advice>, Selector);
+    type FloorPlanner = SimpleFloorPlanner;
+    #[cfg(feature = "circuit-params")]
+    type Params = ();
+
+    fn without_witnesses(&self) -> Self {
+        Self
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+        let a = meta.advice_column();
+        let q = meta.selector();
+        meta.create_gate("zero", |meta| {
+            let a = meta.query_advice(a, Rotation::cur());
+            let q = meta.query_selector(q);
+            vec![q * a]
+        });
+        (a, q)
+    }
+
+    fn synthesize(
+        &self,
+        (a, q): Self::Config,
+        mut layouter: impl Layouter<Fr>,
+    ) -> Result<(), Error> {
+        layouter.assign_region(
+            || "values",
+            |mut region| {
+                region.name_column(|| "input", a);
+                for row in 0..10 {
+                    q.enable(&mut region, row)?;
+                    let value = if row < 8 { Fr::ONE } else { Fr::ZERO };
+                    region.assign_advice(|| "a", a, row, || Value::known(value))?;
+                }
+                Ok(())
+            },
+        )
+    }
+}
+
+/// Returns the panic message of `assertion`, or `None` if it doesn't panic.
+fn panic_message(assertion: impl FnOnce()) -> Option<String> {
+    let payload = catch_unwind(AssertUnwindSafe(assertion)).err()?;
+    Some(
+        payload
+            .downcast_ref::<String>()
+            .cloned()
+            .expect("the panic message should be formatted"),
+    )
+}
+
+/// Returns the lines of `message` that start a failure.
+fn markers(message: &str) -> Vec<&str> {
+    message
+        .lines()
+        .filter(|line| line.starts_with('['))
+        .collect()
+}
+
+#[test]
+fn failures_are_capped() {
+    let prover = MockProver::run(K, &ZeroCircuit, vec![]).unwrap();
+    let message = panic_message(|| prover.assert_satisfied_with(3)).unwrap();
+
+    let mut lines = message.lines();
+    assert_eq!(lines.next(), Some("circuit was not satisfied: 8 failures"));
+    let markers = markers(&message);
+    assert_eq!(markers.len(), 3);
+    for (index, marker) in markers.iter().enumerate() {
+        assert!(marker.starts_with(&format!(
+            "[{}/8] Constraint 0 in gate 0 ('zero')",
+            index + 1
+        )));
+        assert!(marker.ends_with(&format!("in Region 0 ('values') at offset {index}")));
+    }
+    // The values of the failing constraints are named by the column annotations.
+    assert!(message.contains("input"));
+    assert_eq!(message.lines().last(), Some("... 5 more suppressed"));
+    // Colors are only used when requested.
+    assert!(!message.contains('\x1b'));
+}
+
+#[test]
+fn failures_are_restricted_to_rows() {
+    let prover = MockProver::run(K, &ZeroCircuit, vec![]).unwrap();
+    let check = |rows: Range<usize>| {
+        panic_message(|| prover.assert_satisfied_at_rows_with_lookups(rows, 5))
+    };
+
+    let message = check(6..10).unwrap();
+    assert!(message.starts_with("circuit was not satisfied at rows 6..10: 2 failures\n"));
+    let markers = markers(&message);
+    assert_eq!(markers.len(), 2);
+    assert!(markers[0].starts_with("[1/2]"));
+    assert!(markers[0].ends_with("at offset 6"));
+    assert!(markers[1].ends_with("at offset 7"));
+    assert!(!message.contains("suppressed"));
+
+    assert_eq!(check(8..10), None);
+}
@@ -0,0 +1,149 @@
+//! Compiles a circuit with each selector strategy, and checks the selector assignments and the
+//! verifying keys that result from them.
+
+use assert_matches::assert_matches;
+use halo2_frontend::circuit::{
+    compile_circuit_with_options, CompileOptions, CompileOutput, SelectorStrategy,
+};
+use halo2_proofs::circuit::{Layouter, SimpleFloorPlanner, Value};
+use halo2_proofs::plonk::{
+    keygen_vk_custom, keygen_vk_v2, Advice, Circuit, Column, ConstraintSystem, Error, Fixed,
+    Selector, VerifyingKey,
+};
+use halo2_proofs::poly::commitment::ParamsProver;
+use halo2_proofs::poly::kzg::commitment::ParamsKZG;
+use halo2_proofs::poly::Rotation;
+use halo2curves::bn256::{Bn256, Fr, G1Affine};
+
+const K: u32 = 4;
+
+#[derive(Clone)]
+struct ArithConfig {
+    a: Column<Advice>,
+    b: Column<Advice>,
+    c: Column<Advice>,
+    q_mul: Selector,
+    q_add: Selector,
+}
+
+/// Enforces `a * b = c` on row 0 and `a + b = c` on row 1, each with its own selector.
+#[derive(Clone, Default)]
+struct ArithCircuit;
+
+impl Circuit<Fr> for ArithCircuit {
+    type Config = ArithConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+    #[cfg(feature = "circuit-params")]
+    type Params = ();
+
+    fn without_witnesses(&self) -> Self {
+        Self
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fr>) -> ArithConfig {
+        let [a, b, c] = [(); 3].map(|_| meta.advice_column());
+        let q_mul = meta.selector();
+        let q_add = meta.selector();
+        meta.create_gate("mul", |meta| {
+            let q = meta.query_selector(q_mul);
+            let [a, b, c] = [a, b, c].map(|column| meta.query_advice(column, Rotation::cur()));
+            vec![q * (a * b - c)]
+        });
+        meta.create_gate("add", |meta| {
+            let q = meta.query_selector(q_add);
+            let [a, b, c] = [a, b, c].map(|column| meta.query_advice(column, Rotation::cur()));
+            vec![q * (a + b - c)]
+        });
+        ArithConfig {
+            a,
+            b,
+            c,
+            q_mul,
+            q_add,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: ArithConfig,
+        mut layouter: impl Layouter<Fr>,
+    ) -> Result<(), Error> {
+        layouter.assign_region(
+            || "arith",
+            |mut region| {
+                config.q_mul.enable(&mut region, 0)?;
+                config.q_add.enable(&mut region, 1)?;
+                for (row, [a, b, c]) in [[2, 3, 6], [2, 3, 5]].into_iter().enumerate() {
+                    for (column, value) in [(config.a, a), (config.b, b), (config.c, c)] {
+                        region.assign_advice(
+                            || "value",
+                            column,
+                            row,
+                            || Value::known(Fr::from(value)),
+                        )?;
+                    }
+                }
+                Ok(())
+            },
+        )
+    }
+}
+
+fn compile(selector_strategy: SelectorStrategy) -> CompileOutput<Fr, ArithConfig> {
+    compile_circuit_with_options(K, &ArithCircuit, &CompileOptions { selector_strategy }).unwrap()
+}
+
+fn vk(output: CompileOutput<Fr, ArithConfig>) -> (VerifyingKey<G1Affine>, Vec<Column<Fixed>>) {
+    let CompileOutput::Fixed {
+        circuit,
+        selector_map,
+        ..
+    } = output
+    else {
+        panic!("the selectors should be turned into fixed columns");
+    };
+    let params = ParamsKZG::<Bn256>::new(K);
+    (keygen_vk_v2(&params, &circuit).unwrap(), selector_map)
+}
+
+#[test]
+fn raw_selectors_are_kept() {
+    let CompileOutput::Raw {
+        preprocessing,
+        cs,
+        selectors,
+        ..
+    } = compile(SelectorStrategy::KeepRaw)
+    else {
+        panic!("the selectors should be kept");
+    };
+    assert_eq!(cs.num_selectors(), 2);
+    assert_eq!(preprocessing.fixed.len(), cs.num_fixed_columns());
+    assert_eq!(selectors.len(), 2);
+    let active = |selector: &Vec<bool>| {
+        (0..selector.len())
+            .filter(|row| selector[*row])
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(active(&selectors[0]), [0]);
+    assert_eq!(active(&selectors[1]), [1]);
+}
+
+#[test]
+fn selector_strategies_produce_different_vks() {
+    let (direct, direct_map) = vk(compile(SelectorStrategy::DirectToFixed));
+    assert_eq!(direct_map, [Column::new(0, Fixed), Column::new(1, Fixed)]);
+    assert_eq!(direct.fixed_commitments().len(), 2);
+
+    let (compressed, compressed_map) = vk(compile(SelectorStrategy::Compress));
+    assert_eq!(compressed_map.len(), 2);
+    assert!(compressed.fixed_commitments().len() <= direct.fixed_commitments().len());
+    assert_ne!(compressed.transcript_repr(), direct.transcript_repr());
+
+    // The strategies match the selector compression flag of the keygen.
+    let params = ParamsKZG::<Bn256>::new(K);
+    for (compress_selectors, expected) in [(true, &compressed), (false, &direct)] {
+        let vk = keygen_vk_custom::<G1Affine, _, _>(&params, &ArithCircuit, compress_selectors);
+        assert_matches!(vk, Ok(vk) if vk.transcript_repr() == expected.transcript_repr());
+    }
+}
@@ -0,0 +1,162 @@
+mod common;
+
+use assert_matches::assert_matches;
+use common::{setup, MulCircuit};
+use halo2_proofs::circuit::Value;
+use halo2_proofs::plonk::{create_proof, create_proof_streamed, verify_proof, Error, ProvingKey};
+use halo2_proofs::poly::kzg::commitment::{KZGCommitmentScheme, ParamsKZG};
+use halo2_proofs::poly::kzg::multiopen::{ProverSHPLONK, VerifierSHPLONK};
+use halo2_proofs::poly::kzg::strategy::SingleStrategy;
+use halo2_proofs::transcript::{
+    Blake2bRead, Blake2bWrite, Challenge255, StreamWriter, TranscriptReadBuffer,
+    TranscriptWriterBuffer,
+};
+use halo2curves::bn256::{Bn256, Fr, G1Affine};
+use rand_chacha::ChaCha20Rng;
+use rand_core::SeedableRng;
+use std::io::{self, Read, Write};
+
+fn circuit() -> MulCircuit {
+    MulCircuit {
+        a: Value::known(Fr::from(2)),
+        b: Value::known(Fr::from(3)),
+    }
+}
+
+/// Streams a proof that `2 * 3 = 6` to `writer`.
+fn prove_streamed<W: Write>(
+    params: &ParamsKZG<Bn256>,
+    pk: &ProvingKey<G1Affine>,
+    writer: W,
+) -> Result<usize, Error> {
+    create_proof_streamed::<
+        KZGCommitmentScheme<Bn256>,
+        ProverSHPLONK<_>,
+        _,
+        _,
+        _,
+        Blake2bWrite<_, _, Challenge255<_>>,
+        _,
+    >(
+        params,
+        pk,
+        &[circuit()],
+        &[&[&[Fr::from(6)]]],
+        ChaCha20Rng::seed_from_u64(0),
+        writer,
+    )
+}
+
+/// A reader which returns at most one byte per read.
+struct ByteReader<'a>(&'a [u8]);
+
+impl Read for ByteReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = buf.len().min(self.0.len()).min(1);
+        self.0.read(&mut buf[..len])
+    }
+}
+
+/// A writer which fails once `limit` bytes have been written, and records the flushes.
+struct FailingWriter {
+    written: Vec<u8>,
+    limit: usize,
+    flushes: usize,
+}
+
+impl Write for FailingWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.written.len() + buf.len() > self.limit {
+            return Err(io::Error::new(io::ErrorKind::Other, "disk full"));
+        }
+        self.written.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.flushes += 1;
+        Ok(())
+    }
+}
+
+#[test]
+fn streamed_proof_matches_buffered_proof() {
+    let (params, pk) = setup(4);
+
+    let mut transcript = Blake2bWrite::<_, _, Challenge255<_>>::init(vec![]);
+    create_proof::<KZGCommitmentScheme<Bn256>, ProverSHPLONK<_>, _, _, _, _>(
+        &params,
+        &pk,
+        &[circuit()],
+        &[&[&[Fr::from(6)]]],
+        ChaCha20Rng::seed_from_u64(0),
+        &mut transcript,
+    )
+    .expect("prover should not fail");
+    let proof = transcript.finalize();
+
+    let mut streamed = vec![];
+    let written = prove_streamed(&params, &pk, &mut streamed).expect("prover should not fail");
+    assert_eq!(written, proof.len());
+    assert_eq!(streamed, proof);
+
+    // The same bytes are written through an explicitly streamed transcript.
+    let mut transcript = Blake2bWrite::<_, _, Challenge255<_>>::init_streamed(vec![]);
+    create_proof::<KZGCommitmentScheme<Bn256>, ProverSHPLONK<_>, _, _, _, _>(
+        &params,
+        &pk,
+        &[circuit()],
+        &[&[&[Fr::from(6)]]],
+        ChaCha20Rng::seed_from_u64(0),
+        &mut transcript,
+    )
+    .expect("prover should not fail");
+    let writer: StreamWriter<Vec<u8>> = transcript.finalize();
+    assert_eq!(writer.bytes_written(), proof.len());
+    assert_eq!(writer.into_inner(), proof);
+
+    // The verifier reads the proof sequentially.
+    let mut transcript = Blake2bRead::<_, _, Challenge255<_>>::init(ByteReader(&streamed));
+    let strategy = SingleStrategy::new(&params);
+    verify_proof::<_, VerifierSHPLONK<_>, _, _, _>(
+        &params,
+        pk.get_vk(),
+        strategy,
+        &[&[&[Fr::from(6)]]],
+        &mut transcript,
+    )
+    .expect("proof should verify");
+}
+
+#[test]
+fn streamed_proof_flushes_eagerly() {
+    let (params, pk) = setup(4);
+    let mut writer = FailingWriter {
+        written: vec![],
+        limit: usize::MAX,
+        flushes: 0,
+    };
+    let written = prove_streamed(&params, &pk, &mut writer).expect("prover should not fail");
+    assert_eq!(writer.written.len(), written);
+    // Every point and scalar is flushed.
+    assert!(writer.flushes >= written / 32);
+}
+
+#[test]
+fn streamed_proof_write_error() {
+    let (params, pk) = setup(4);
+    let len = prove_streamed(&params, &pk, io::sink()).expect("prover should not fail");
+
+    for limit in [0, 100, len - 1] {
+        let mut writer = FailingWriter {
+            written: vec![],
+            limit,
+            flushes: 0,
+        };
+        assert_matches!(
+            prove_streamed(&params, &pk, &mut writer),
+            Err(Error::Transcript(e)) if e.to_string() == "disk full"
+        );
+        assert!(writer.written.len() <= limit);
+    }
+}
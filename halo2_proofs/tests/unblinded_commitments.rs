@@ -0,0 +1,145 @@
+//! Proofs of different circuits that share the values of an unblinded advice column share its
+//! commitment.
+
+use halo2_proofs::circuit::{Layouter, SimpleFloorPlanner, Value};
+use halo2_proofs::dev::extract_advice_commitments;
+use halo2_proofs::plonk::{
+    create_proof, keygen_pk, keygen_vk, Advice, Circuit, Column, ConstraintSystem, Error,
+    ProvingKey, Selector,
+};
+use halo2_proofs::poly::commitment::ParamsProver;
+use halo2_proofs::poly::kzg::commitment::{KZGCommitmentScheme, ParamsKZG};
+use halo2_proofs::poly::kzg::multiopen::ProverSHPLONK;
+use halo2_proofs::poly::Rotation;
+use halo2_proofs::transcript::{Blake2bWrite, Challenge255, TranscriptWriterBuffer};
+use halo2curves::bn256::{Bn256, Fr, G1Affine};
+use rand_core::OsRng;
+
+const K: u32 = 4;
+
+#[derive(Clone)]
+struct SharedConfig {
+    shared: Column<Advice>,
+    derived: Column<Advice>,
+    s: Selector,
+}
+
+/// Assigns `values` to the unblinded column `shared`, and enforces that the blinded column
+/// `derived` is `shared + OFFSET`. Circuits with different `OFFSET`s have different gates.
+#[derive(Clone, Default)]
+struct SharedCircuit<const OFFSET: u64> {
+    values: [u64; 3],
+}
+
+impl<const OFFSET: u64> Circuit<Fr> for SharedCircuit<OFFSET> {
+    type Config = SharedConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+    #[cfg(feature = "circuit-params")]
+    type Params = ();
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fr>) -> SharedConfig {
+        let shared = meta.unblinded_advice_column();
+        let derived = meta.advice_column();
+        let s = meta.selector();
+        meta.create_gate("derive", |meta| {
+            let s = meta.query_selector(s);
+            let shared = meta.query_advice(shared, Rotation::cur());
+            let derived = meta.query_advice(derived, Rotation::cur());
+            vec![s * (derived - shared - Fr::from(OFFSET))]
+        });
+        SharedConfig { shared, derived, s }
+    }
+
+    fn synthesize(
+        &self,
+        config: SharedConfig,
+        mut layouter: impl Layouter<Fr>,
+    ) -> Result<(), Error> {
+        layouter.assign_region(
+            || "shared",
+            |mut region| {
+                for (row, value) in self.values.iter().enumerate() {
+                    config.s.enable(&mut region, row)?;
+                    let value = Fr::from(*value);
+                    region.assign_advice(
+                        || "shared",
+                        config.shared,
+                        row,
+                        || Value::known(value),
+                    )?;
+                    region.assign_advice(
+                        || "derived",
+                        config.derived,
+                        row,
+                        || Value::known(value + Fr::from(OFFSET)),
+                    )?;
+                }
+                Ok(())
+            },
+        )
+    }
+}
+
+fn setup<const OFFSET: u64>(params: &ParamsKZG<Bn256>) -> ProvingKey<G1Affine> {
+    let vk = keygen_vk(params, &SharedCircuit::<OFFSET>::default()).unwrap();
+    assert_eq!(vk.unblinded_advice_columns(), [0]);
+    keygen_pk(params, vk, &SharedCircuit::<OFFSET>::default()).unwrap()
+}
+
+/// Returns the commitments of the `shared` and `derived` columns in a proof of the circuit.
+fn commitments<const OFFSET: u64>(
+    params: &ParamsKZG<Bn256>,
+    pk: &ProvingKey<G1Affine>,
+    values: [u64; 3],
+) -> (G1Affine, G1Affine) {
+    let mut transcript = Blake2bWrite::<_, G1Affine, Challenge255<_>>::init(vec![]);
+    create_proof::<KZGCommitmentScheme<Bn256>, ProverSHPLONK<_>, _, _, _, _>(
+        params,
+        pk,
+        &[SharedCircuit::<OFFSET> { values }],
+        &[&[]],
+        OsRng,
+        &mut transcript,
+    )
+    .expect("prover should not fail");
+    let proof = transcript.finalize();
+
+    let commitments = extract_advice_commitments(pk.get_vk(), &proof, 1).unwrap();
+    assert_eq!(commitments.len(), 1);
+    (commitments[0][0], commitments[0][1])
+}
+
+#[test]
+fn unblinded_columns_share_commitments() {
+    let params = ParamsKZG::<Bn256>::new(K);
+    let one = setup::<1>(&params);
+    let two = setup::<2>(&params);
+
+    let (shared_one, derived_one) = commitments::<1>(&params, &one, [1, 2, 3]);
+    let (shared_two, derived_two) = commitments::<2>(&params, &two, [1, 2, 3]);
+    assert_eq!(shared_one, shared_two);
+    assert_ne!(derived_one, derived_two);
+
+    // The blinded column is committed differently in every proof, even with the same values.
+    let (shared_again, derived_again) = commitments::<1>(&params, &one, [1, 2, 3]);
+    assert_eq!(shared_again, shared_one);
+    assert_ne!(derived_again, derived_one);
+
+    let (shared_other, _) = commitments::<2>(&params, &two, [1, 2, 4]);
+    assert_ne!(shared_other, shared_one);
+}
+
+#[test]
+fn truncated_proofs_are_reported() {
+    let params = ParamsKZG::<Bn256>::new(K);
+    let pk = setup::<1>(&params);
+    let error = extract_advice_commitments(pk.get_vk(), &[0; 20], 1).unwrap_err();
+    assert_eq!(
+        error,
+        "the proof ends at byte 20, before the commitment of advice column 0 of circuit 0"
+    );
+}
@@ -0,0 +1,84 @@
+//! The usable rows of a circuit are the same whether they are computed from its constraint
+//! system, its `MockProver` or its proving key.
+
+use halo2_proofs::circuit::{Layouter, SimpleFloorPlanner, Value};
+use halo2_proofs::dev::MockProver;
+use halo2_proofs::plonk::{
+    keygen_pk, keygen_vk, Advice, Circuit, Column, ConstraintSystem, Error, Selector,
+};
+use halo2_proofs::poly::commitment::ParamsProver;
+use halo2_proofs::poly::kzg::commitment::ParamsKZG;
+use halo2_proofs::poly::Rotation;
+use halo2curves::bn256::{Bn256, Fr};
+
+const K: u32 = 5;
+
+/// Fills every usable row of an advice column, with a gate that queries the next row.
+#[derive(Clone, Default)]
+struct FillCircuit;
+
+impl Circuit<Fr> for FillCircuit {
+    type Config = (Column<Advice>, Selector);
+    type FloorPlanner = SimpleFloorPlanner;
+    #[cfg(feature = "circuit-params")]
+    type Params = ();
+
+    fn without_witnesses(&self) -> Self {
+        Self
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+        let a = meta.advice_column();
+        let s = meta.selector();
+        meta.create_gate("increment", |meta| {
+            let s = meta.query_selector(s);
+            let cur = meta.query_advice(a, Rotation::cur());
+            let next = meta.query_advice(a, Rotation::next());
+            vec![s * (next - cur - Fr::from(1))]
+        });
+        (a, s)
+    }
+
+    fn synthesize(
+        &self,
+        (a, s): Self::Config,
+        mut layouter: impl Layouter<Fr>,
+    ) -> Result<(), Error> {
+        let mut cs = ConstraintSystem::default();
+        Self::configure(&mut cs);
+        let rows = cs.usable_rows(K);
+        layouter.assign_region(
+            || "fill",
+            |mut region| {
+                for row in rows.clone() {
+                    if row + 1 < rows.end {
+                        s.enable(&mut region, row)?;
+                    }
+                    region.assign_advice(|| "a", a, row, || Value::known(Fr::from(row as u64)))?;
+                }
+                Ok(())
+            },
+        )
+    }
+}
+
+#[test]
+fn usable_rows_agree() {
+    let mut cs = ConstraintSystem::<Fr>::default();
+    FillCircuit::configure(&mut cs);
+    let rows = cs.usable_rows(K);
+    assert_eq!(rows, 0..(1 << K) - cs.blinding_factors() - 1);
+
+    let prover = MockProver::run(K, &FillCircuit, vec![]).unwrap();
+    prover.assert_satisfied();
+    assert_eq!(prover.usable_rows(), rows);
+
+    let params = ParamsKZG::<Bn256>::new(K);
+    let vk = keygen_vk(&params, &FillCircuit).unwrap();
+    assert_eq!(vk.usable_rows(), rows);
+    let pk = keygen_pk(&params, vk, &FillCircuit).unwrap();
+    assert_eq!(pk.usable_rows(), rows);
+
+    // The usable rows shrink with the size of the circuit, down to none.
+    assert_eq!(cs.usable_rows(0), 0..0);
+}
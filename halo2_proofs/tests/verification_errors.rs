@@ -0,0 +1,208 @@
+mod common;
+
+use assert_matches::assert_matches;
+use common::MulCircuit;
+use halo2_proofs::circuit::Value;
+use halo2_proofs::plonk::{
+    create_proof, keygen_pk, keygen_vk, verify_proof, Error, ProvingKey, VerificationError,
+    VerificationStage,
+};
+use halo2_proofs::poly::commitment::ParamsProver;
+use halo2_proofs::poly::kzg::commitment::{KZGCommitmentScheme, ParamsKZG};
+use halo2_proofs::poly::kzg::multiopen::{ProverSHPLONK, VerifierSHPLONK};
+use halo2_proofs::poly::kzg::strategy::SingleStrategy;
+use halo2_proofs::transcript::{
+    Blake2bRead, Blake2bWrite, Challenge255, TranscriptReadBuffer, TranscriptWriterBuffer,
+};
+use halo2curves::bn256::{Bn256, Fr, G1Affine};
+use rand_chacha::ChaCha20Rng;
+use rand_core::SeedableRng;
+use std::io;
+
+/// The size of a point and of a scalar in a proof.
+const ELEMENT: usize = 32;
+
+fn setup() -> (ParamsKZG<Bn256>, ProvingKey<G1Affine>, Vec<u8>) {
+    let params = ParamsKZG::<Bn256>::new(4);
+    let vk = keygen_vk(&params, &MulCircuit::default()).expect("keygen_vk should not fail");
+    let pk = keygen_pk(&params, vk, &MulCircuit::default()).expect("keygen_pk should not fail");
+    let circuit = MulCircuit {
+        a: Value::known(Fr::from(2)),
+        b: Value::known(Fr::from(3)),
+    };
+    let mut transcript = Blake2bWrite::<_, _, Challenge255<_>>::init(vec![]);
+    create_proof::<KZGCommitmentScheme<Bn256>, ProverSHPLONK<_>, _, _, _, _>(
+        &params,
+        &pk,
+        &[circuit],
+        &[&[&[Fr::from(6)]]],
+        ChaCha20Rng::seed_from_u64(0),
+        &mut transcript,
+    )
+    .expect("prover should not fail");
+    (params, pk, transcript.finalize())
+}
+
+fn verify(
+    params: &ParamsKZG<Bn256>,
+    pk: &ProvingKey<G1Affine>,
+    proof: &[u8],
+    instances: &[&[&[Fr]]],
+) -> Result<(), VerificationError> {
+    let mut transcript = Blake2bRead::<_, _, Challenge255<_>>::init(proof);
+    let strategy = SingleStrategy::new(params);
+    verify_proof::<_, VerifierSHPLONK<_>, _, _, _>(
+        params,
+        pk.get_vk(),
+        strategy,
+        instances,
+        &mut transcript,
+    )
+}
+
+/// Returns `proof` with the element at `offset` replaced by bytes which encode neither a
+/// point nor a scalar.
+fn invalidate(proof: &[u8], offset: usize) -> Vec<u8> {
+    let mut proof = proof.to_vec();
+    proof[offset..offset + ELEMENT].fill(0xff);
+    proof
+}
+
+/// The offsets of the parts of a proof of [`MulCircuit`]: the 2 advice commitments, the
+/// commitments of the vanishing argument, the 2 advice evaluations, the evaluation of the
+/// compressed selector, the evaluation of the vanishing argument, and the 2 points of
+/// the SHPLONK multi-open argument. The instance is not committed.
+struct Offsets {
+    advice_commitments: usize,
+    vanishing_commitments: usize,
+    advice_evals: usize,
+    fixed_evals: usize,
+    vanishing_evals: usize,
+    multiopen: usize,
+}
+
+fn offsets(pk: &ProvingKey<G1Affine>, proof: &[u8]) -> Offsets {
+    let h_pieces = pk.get_vk().get_domain().get_quotient_poly_degree();
+    let vanishing_commitments = 2 * ELEMENT;
+    let advice_evals = vanishing_commitments + (1 + h_pieces) * ELEMENT;
+    let offsets = Offsets {
+        advice_commitments: 0,
+        vanishing_commitments,
+        advice_evals,
+        fixed_evals: advice_evals + 2 * ELEMENT,
+        vanishing_evals: advice_evals + 3 * ELEMENT,
+        multiopen: advice_evals + 4 * ELEMENT,
+    };
+    assert_eq!(offsets.multiopen + 2 * ELEMENT, proof.len());
+    offsets
+}
+
+#[test]
+fn valid_proof_verifies() {
+    let (params, pk, proof) = setup();
+    verify(&params, &pk, &proof, &[&[&[Fr::from(6)]]]).expect("proof should verify");
+}
+
+#[test]
+fn invalid_encodings_report_their_stage() {
+    let (params, pk, proof) = setup();
+    let offsets = offsets(&pk, &proof);
+    let instances: &[&[&[Fr]]] = &[&[&[Fr::from(6)]]];
+
+    for (offset, expected) in [
+        (
+            offsets.advice_commitments + ELEMENT,
+            VerificationStage::AdviceCommitments,
+        ),
+        (
+            offsets.vanishing_commitments,
+            VerificationStage::VanishingCommitments,
+        ),
+        (offsets.advice_evals, VerificationStage::AdviceEvaluations),
+        (offsets.fixed_evals, VerificationStage::FixedEvaluations),
+        (
+            offsets.vanishing_evals,
+            VerificationStage::VanishingEvaluations,
+        ),
+    ] {
+        assert_matches!(
+            verify(&params, &pk, &invalidate(&proof, offset), instances),
+            Err(VerificationError::Transcript { stage, .. }) if stage == expected,
+            "at offset {}",
+            offset
+        );
+    }
+
+    // The multi-open argument reads its points itself.
+    assert_matches!(
+        verify(
+            &params,
+            &pk,
+            &invalidate(&proof, offsets.multiopen),
+            instances
+        ),
+        Err(VerificationError::Opening)
+    );
+}
+
+#[test]
+fn truncated_proof_reports_its_stage() {
+    let (params, pk, proof) = setup();
+    let offsets = offsets(&pk, &proof);
+    let err = verify(
+        &params,
+        &pk,
+        &proof[..offsets.advice_evals + ELEMENT / 2],
+        &[&[&[Fr::from(6)]]],
+    )
+    .unwrap_err();
+    assert_matches!(
+        &err,
+        VerificationError::Transcript {
+            stage: VerificationStage::AdviceEvaluations,
+            error,
+        } if error.kind() == io::ErrorKind::UnexpectedEof
+    );
+    assert!(err
+        .to_string()
+        .starts_with("Cannot read the advice evaluations of the proof: "));
+}
+
+#[test]
+fn wrong_values_fail_the_final_check() {
+    let (params, pk, proof) = setup();
+    let offsets = offsets(&pk, &proof);
+
+    // A different, but well-formed, advice evaluation.
+    let mut tampered = proof.clone();
+    tampered[offsets.advice_evals] ^= 1;
+    assert_matches!(
+        verify(&params, &pk, &tampered, &[&[&[Fr::from(6)]]]),
+        Err(VerificationError::FinalCheck)
+    );
+
+    // The proof of other instances.
+    assert_matches!(
+        verify(&params, &pk, &proof, &[&[&[Fr::from(7)]]]),
+        Err(VerificationError::FinalCheck)
+    );
+
+    assert_matches!(
+        verify(&params, &pk, &proof, &[&[]]),
+        Err(VerificationError::InvalidInstances)
+    );
+}
+
+#[test]
+fn errors_convert_to_the_previous_errors() {
+    let (params, pk, proof) = setup();
+    assert_matches!(
+        verify(&params, &pk, &proof, &[&[&[Fr::from(7)]]]).map_err(Error::from),
+        Err(Error::ConstraintSystemFailure)
+    );
+    assert_matches!(
+        verify(&params, &pk, &proof[..10], &[&[&[Fr::from(6)]]]).map_err(Error::from),
+        Err(Error::Transcript(_))
+    );
+    assert_matches!(Error::from(VerificationError::Opening), Error::Opening);
+}
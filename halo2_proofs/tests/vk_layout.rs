@@ -0,0 +1,163 @@
+use ff::{Field, PrimeField};
+use halo2_proofs::circuit::{Layouter, SimpleFloorPlanner, Value};
+use halo2_proofs::plonk::{
+    keygen_vk, Advice, Circuit, Column, ConstraintSystem, Error, Fixed, Instance, Selector,
+    VerifyingKey, VkLayout, VK_LAYOUT_VERSION,
+};
+use halo2_proofs::poly::kzg::commitment::ParamsKZG;
+use halo2_proofs::poly::Rotation;
+use halo2curves::bn256::{Bn256, Fr, G1Affine};
+use halo2curves::{Coordinates, CurveAffine};
+use rand_chacha::ChaCha20Rng;
+use rand_core::SeedableRng;
+
+const K: u32 = 4;
+
+#[derive(Clone)]
+struct ScaleConfig {
+    a: Column<Advice>,
+    b: Column<Advice>,
+    f: Column<Fixed>,
+    c: Column<Instance>,
+    s: Selector,
+}
+
+/// Proves the knowledge of `a` such that `a * 5` is the public input.
+#[derive(Clone, Default)]
+struct ScaleCircuit {
+    a: Value<Fr>,
+}
+
+impl Circuit<Fr> for ScaleCircuit {
+    type Config = ScaleConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+    #[cfg(feature = "circuit-params")]
+    type Params = ();
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fr>) -> ScaleConfig {
+        let a = meta.advice_column();
+        let b = meta.advice_column();
+        let f = meta.fixed_column();
+        let c = meta.instance_column();
+        let s = meta.selector();
+        meta.enable_equality(b);
+        meta.enable_equality(c);
+
+        meta.create_gate("scale", |meta| {
+            let a = meta.query_advice(a, Rotation::cur());
+            let b = meta.query_advice(b, Rotation::cur());
+            let f = meta.query_fixed(f, Rotation::cur());
+            let s = meta.query_selector(s);
+            vec![s * (a * f - b)]
+        });
+
+        ScaleConfig { a, b, f, c, s }
+    }
+
+    fn synthesize(
+        &self,
+        config: ScaleConfig,
+        mut layouter: impl Layouter<Fr>,
+    ) -> Result<(), Error> {
+        let b = layouter.assign_region(
+            || "scale",
+            |mut region| {
+                config.s.enable(&mut region, 0)?;
+                region.assign_advice(|| "a", config.a, 0, || self.a)?;
+                region.assign_fixed(|| "f", config.f, 0, || Value::known(Fr::from(5)))?;
+                region.assign_advice(|| "b", config.b, 0, || self.a * Value::known(Fr::from(5)))
+            },
+        )?;
+        layouter.constrain_instance(b.cell(), config.c, 0)
+    }
+}
+
+fn vk() -> VerifyingKey<G1Affine> {
+    let params = ParamsKZG::<Bn256>::setup(K, ChaCha20Rng::seed_from_u64(0));
+    keygen_vk(&params, &ScaleCircuit::default()).expect("keygen_vk should not fail")
+}
+
+/// Returns the `x` and `y` encoded in `limbs`, as little-endian bytes.
+fn decode(limbs: &[Fr]) -> (Vec<u8>, Vec<u8>) {
+    let bytes = |limbs: &[Fr]| -> Vec<u8> {
+        limbs
+            .iter()
+            .flat_map(|limb| {
+                let repr = limb.to_repr();
+                // Each limb is below `2^128`.
+                assert!(repr[16..].iter().all(|byte| *byte == 0));
+                repr[..16].to_vec()
+            })
+            .collect()
+    };
+    let (x, y) = limbs.split_at(limbs.len() / 2);
+    (bytes(x), bytes(y))
+}
+
+#[test]
+fn layout_is_pinned() {
+    let vk = vk();
+    assert_eq!(
+        vk.io_layout(),
+        VkLayout {
+            version: 1,
+            k: K,
+            limbs_per_coordinate: 2,
+            // `f`, and the fixed column of the compressed selector.
+            num_fixed_commitments: 2,
+            // `b` and `c`.
+            num_permutation_commitments: 2,
+            num_advice_columns: 2,
+            num_instance_columns: 1,
+            commit_instances: false,
+            num_challenges: 0,
+            num_lookups: 0,
+            num_shuffles: 0,
+            num_quotient_pieces: 2,
+            blinding_factors: 5,
+        }
+    );
+    assert_eq!(VK_LAYOUT_VERSION, 1);
+}
+
+#[test]
+fn field_elements_follow_layout() {
+    let vk = vk();
+    let layout = vk.io_layout();
+    let elements = vk.to_field_elements();
+    assert_eq!(elements.len(), layout.num_elements());
+    assert_eq!(elements.len(), 4 + (2 + 2) * 4);
+
+    assert_eq!(elements[0], Fr::from(VK_LAYOUT_VERSION as u64));
+    assert_eq!(elements[1], vk.transcript_repr());
+    assert_eq!(elements[2], Fr::from(K as u64));
+    assert_eq!(elements[3], vk.get_domain().get_omega());
+
+    let commitments = vk
+        .fixed_commitments()
+        .iter()
+        .chain(vk.permutation().commitments());
+    let encoded = elements[layout.fixed_commitments_offset()..].chunks(layout.commitment_len());
+    assert_eq!(
+        layout.permutation_commitments_offset(),
+        layout.fixed_commitments_offset() + 2 * layout.commitment_len()
+    );
+    for (commitment, limbs) in commitments.zip(encoded) {
+        let coordinates: Option<Coordinates<G1Affine>> = commitment.coordinates().into();
+        let (x, y) = coordinates.map_or(
+            (
+                <G1Affine as CurveAffine>::Base::ZERO,
+                <G1Affine as CurveAffine>::Base::ZERO,
+            ),
+            |coordinates| (*coordinates.x(), *coordinates.y()),
+        );
+        assert_eq!(decode(limbs), (x.to_repr().to_vec(), y.to_repr().to_vec()));
+    }
+
+    // The encoding is deterministic.
+    assert_eq!(self::vk().to_field_elements(), elements);
+}